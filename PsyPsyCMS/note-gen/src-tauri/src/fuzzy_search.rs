@@ -0,0 +1,443 @@
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchItem {
+    pub id: Option<String>,
+    pub desc: Option<String>,
+    pub title: Option<String>,
+    pub article: Option<String>,
+    pub url: Option<String>,
+    pub path: Option<String>,
+    pub search_type: Option<String>,
+    #[serde(default, alias = "tagId")]
+    pub tag_id: Option<i64>,
+    /// Milliseconds since the epoch
+    #[serde(default, alias = "createdAt")]
+    pub created_at: Option<i64>,
+    #[serde(default, alias = "modifiedAt")]
+    pub modified_at: Option<i64>,
+    pub score: Option<i64>,
+    pub matches: Option<Vec<MatchInfo>>,
+}
+
+/// Which fields a query is matched against
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchScope {
+    /// The `keys` passed with the query
+    #[default]
+    Keys,
+    TitleOnly,
+    /// Title, description and note body
+    FullContent,
+}
+
+/// Restrictions applied before any item is scored; every set filter must hold
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilters {
+    /// Folder (notebook) path prefix
+    pub folder: Option<String>,
+    /// Item must carry one of these tags
+    pub tag_ids: Vec<i64>,
+    pub created_after: Option<i64>,
+    pub created_before: Option<i64>,
+    pub modified_after: Option<i64>,
+    pub modified_before: Option<i64>,
+    pub scope: SearchScope,
+}
+
+fn in_range(value: Option<i64>, after: Option<i64>, before: Option<i64>) -> bool {
+    if after.is_none() && before.is_none() {
+        return true;
+    }
+    // Items without the timestamp cannot satisfy a date filter
+    value.is_some_and(|v| after.map_or(true, |a| v >= a) && before.map_or(true, |b| v <= b))
+}
+
+impl SearchFilters {
+    pub fn matches(&self, item: &SearchItem) -> bool {
+        if let Some(folder) = self.folder.as_deref().map(|f| f.trim_matches('/')).filter(|f| !f.is_empty()) {
+            let path = item.path.as_deref().unwrap_or("").trim_start_matches('/');
+            let in_folder = path
+                .strip_prefix(folder)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+            if !in_folder {
+                return false;
+            }
+        }
+        if !self.tag_ids.is_empty() && !item.tag_id.is_some_and(|t| self.tag_ids.contains(&t)) {
+            return false;
+        }
+        in_range(item.created_at, self.created_after, self.created_before)
+            && in_range(item.modified_at, self.modified_after, self.modified_before)
+    }
+
+    /// Keys to match under the scope
+    pub fn scoped_keys<'a>(&self, keys: &'a [String]) -> Vec<&'a str> {
+        match self.scope {
+            SearchScope::Keys => keys.iter().map(|s| s.as_str()).collect(),
+            SearchScope::TitleOnly => vec!["title"],
+            SearchScope::FullContent => vec!["title", "desc", "article"],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatchInfo {
+    pub key: String,
+    pub indices: Vec<[usize; 2]>,
+    pub value: String,
+}
+
+/// Context shown under a hit
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Snippet {
+    /// Field the text was taken from
+    pub key: String,
+    pub text: String,
+    /// Matched character ranges within `text`, inclusive
+    pub highlights: Vec<[usize; 2]>,
+}
+
+/// Characters of body text around the best match
+const SNIPPET_CHARS: usize = 120;
+
+/// One ranked hit
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FuzzyMatch {
+    /// Item path, used to break score ties; empty when the item has none
+    pub path: String,
+    pub score: i64,
+    /// Matched character ranges per key, inclusive
+    pub match_spans: Vec<MatchInfo>,
+    /// Title when it matched, otherwise a window of the body around its longest match
+    pub snippet: Option<Snippet>,
+    pub item: SearchItem,
+    pub refindex: usize,
+}
+
+/// Title and file-name hits outrank the same hit in the body
+fn key_weight(key: &str) -> i64 {
+    match key {
+        "title" | "path" => 2,
+        _ => 1,
+    }
+}
+
+/// Merge consecutive matched indices into `[start, end]` ranges
+fn spans(indices: &[usize]) -> Vec<[usize; 2]> {
+    let mut ranges: Vec<[usize; 2]> = Vec::new();
+    for &idx in indices {
+        match ranges.last_mut() {
+            Some(range) if range[1] + 1 == idx => range[1] = idx,
+            _ => ranges.push([idx, idx]),
+        }
+    }
+    ranges
+}
+
+fn snippet(matches: &[MatchInfo]) -> Option<Snippet> {
+    if let Some(title) = matches.iter().find(|m| m.key == "title") {
+        return Some(Snippet { key: title.key.clone(), text: title.value.clone(), highlights: title.indices.clone() });
+    }
+    let body = matches
+        .iter()
+        .find(|m| m.key == "article")
+        .or_else(|| matches.iter().find(|m| m.key == "desc"))?;
+    let best = body.indices.iter().max_by_key(|r| (r[1] - r[0], std::cmp::Reverse(r[0])))?;
+
+    // Indices count characters; cut on their byte offsets so no code point is split
+    let boundaries: Vec<usize> = body.value.char_indices().map(|(i, _)| i).chain([body.value.len()]).collect();
+    let char_count = boundaries.len() - 1;
+    let center = (best[0] + best[1]) / 2;
+    let start = center.saturating_sub(SNIPPET_CHARS / 2).min(char_count.saturating_sub(SNIPPET_CHARS));
+    let end = (start + SNIPPET_CHARS).min(char_count);
+
+    let highlights = body
+        .indices
+        .iter()
+        .filter(|r| r[1] >= start && r[0] < end)
+        .map(|r| [r[0].max(start) - start, r[1].min(end - 1) - start])
+        .collect();
+    Some(Snippet {
+        key: body.key.clone(),
+        text: body.value[boundaries[start]..boundaries[end]].to_string(),
+        highlights,
+    })
+}
+
+fn search_item(
+    matcher: &SkimMatcherV2,
+    item: &SearchItem,
+    pattern: &str,
+    keys: &[&str],
+    threshold: f64,
+) -> Option<FuzzyMatch> {
+    let mut best_score = 0;
+    let mut all_matches = Vec::new();
+
+    for key in keys {
+        let text = match *key {
+            "desc" => item.desc.as_deref().unwrap_or(""),
+            "title" => item.title.as_deref().unwrap_or(""),
+            "article" => item.article.as_deref().unwrap_or(""),
+            "path" => item.path.as_deref().unwrap_or(""),
+            "search_type" => item.search_type.as_deref().unwrap_or(""),
+            _ => continue,
+        };
+
+        if let Some((score, indices)) = matcher.fuzzy_indices(text, pattern) {
+            let normalized_score = (score as f64).abs() / (pattern.len() as f64);
+
+            if normalized_score < threshold {
+                continue;
+            }
+
+            best_score = best_score.max(score * key_weight(key));
+            all_matches.push(MatchInfo {
+                key: key.to_string(),
+                indices: spans(&indices),
+                value: text.to_string(),
+            });
+        }
+    }
+
+    if all_matches.is_empty() {
+        return None;
+    }
+
+    Some(FuzzyMatch {
+        path: item.path.clone().unwrap_or_default(),
+        score: best_score,
+        snippet: snippet(&all_matches),
+        match_spans: all_matches,
+        item: item.clone(),
+        refindex: 0,
+    })
+}
+
+/// Highest score first; ties by path, then input position, so serial and parallel agree
+fn rank(results: &mut Vec<FuzzyMatch>, limit: Option<usize>) {
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.refindex.cmp(&b.refindex))
+    });
+    if let Some(limit) = limit {
+        results.truncate(limit);
+    }
+}
+
+fn finish(mut results: Vec<FuzzyMatch>, include_score: bool, include_matches: bool, limit: Option<usize>) -> Vec<FuzzyMatch> {
+    rank(&mut results, limit);
+    if !include_score || !include_matches {
+        for result in &mut results {
+            if !include_score {
+                result.score = 0;
+                result.item.score = None;
+            }
+            if !include_matches {
+                result.match_spans.clear();
+                result.item.matches = None;
+            }
+        }
+    }
+    results
+}
+
+#[command]
+pub fn fuzzy_search(
+    items: Vec<SearchItem>,
+    query: String,
+    keys: Vec<String>,
+    threshold: f64,
+    include_score: bool,
+    include_matches: bool,
+    filters: Option<SearchFilters>,
+    limit: Option<usize>,
+) -> Vec<FuzzyMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let filters = filters.unwrap_or_default();
+    let keys_str = filters.scoped_keys(&keys);
+    let matcher = SkimMatcherV2::default();
+
+    // Filters run before matching, so excluded items are never scored
+    let results = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| filters.matches(item))
+        .filter_map(|(index, item)| {
+            let mut result = search_item(&matcher, item, &query, &keys_str, threshold)?;
+            result.refindex = index;
+            Some(result)
+        })
+        .collect();
+
+    finish(results, include_score, include_matches, limit)
+}
+
+/// Same results and order as `fuzzy_search`, matched across threads
+#[command]
+pub fn fuzzy_search_parallel(
+    items: Vec<SearchItem>,
+    query: String,
+    keys: Vec<String>,
+    threshold: f64,
+    include_score: bool,
+    include_matches: bool,
+    filters: Option<SearchFilters>,
+    limit: Option<usize>,
+) -> Vec<FuzzyMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let filters = filters.unwrap_or_default();
+    let keys_str = filters.scoped_keys(&keys);
+
+    let results = items
+        .par_iter()
+        .enumerate()
+        .filter(|(_, item)| filters.matches(item))
+        .map_init(SkimMatcherV2::default, |matcher, (index, item)| {
+            let mut result = search_item(matcher, item, &query, &keys_str, threshold)?;
+            result.refindex = index;
+            Some(result)
+        })
+        .flatten()
+        .collect();
+
+    finish(results, include_score, include_matches, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: &str, title: &str, article: &str, path: &str, tag_id: i64, created_at: i64) -> SearchItem {
+        SearchItem {
+            id: Some(id.to_string()),
+            desc: None,
+            title: Some(title.to_string()),
+            article: Some(article.to_string()),
+            url: None,
+            path: Some(path.to_string()),
+            search_type: Some("article".to_string()),
+            tag_id: Some(tag_id),
+            created_at: Some(created_at),
+            modified_at: None,
+            score: None,
+            matches: None,
+        }
+    }
+
+    fn notes() -> Vec<SearchItem> {
+        vec![
+            note("1", "Anxiety protocol", "Breathing exercises", "clinic/protocols/anxiety.md", 1, 1_000),
+            note("2", "Weekly plan", "Review the anxiety protocol with the team", "clinic/weekly.md", 1, 2_000),
+            note("3", "Anxiety reading list", "Books", "personal/reading.md", 2, 3_000),
+        ]
+    }
+
+    fn ids(results: &[FuzzyMatch]) -> Vec<String> {
+        let mut ids: Vec<String> = results.iter().filter_map(|r| r.item.id.clone()).collect();
+        ids.sort();
+        ids
+    }
+
+    fn search(filters: SearchFilters) -> Vec<String> {
+        ids(&fuzzy_search(notes(), "anxiety".to_string(), vec!["title".to_string()], 0.0, true, true, Some(filters), None))
+    }
+
+    #[test]
+    fn test_title_only_and_full_content_scopes_differ() {
+        let title_only = search(SearchFilters { scope: SearchScope::TitleOnly, ..SearchFilters::default() });
+        let full_content = search(SearchFilters { scope: SearchScope::FullContent, ..SearchFilters::default() });
+
+        assert_eq!(title_only, vec!["1", "3"]);
+        assert_eq!(full_content, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_filters_compose() {
+        let filters = SearchFilters {
+            folder: Some("clinic".to_string()),
+            tag_ids: vec![1],
+            created_after: Some(1_500),
+            scope: SearchScope::FullContent,
+            ..SearchFilters::default()
+        };
+        assert_eq!(search(filters), vec!["2"]);
+
+        // A folder prefix must end on a path boundary
+        let partial = SearchFilters { folder: Some("clin".to_string()), ..SearchFilters::default() };
+        assert!(search(partial).is_empty());
+    }
+
+    #[test]
+    fn test_title_hits_rank_above_body_hits_and_limit_applies() {
+        let keys = vec!["title".to_string(), "article".to_string()];
+        let results = fuzzy_search(notes(), "anxiety protocol".to_string(), keys.clone(), 0.0, true, true, None, None);
+        assert_eq!(results.first().and_then(|r| r.item.id.as_deref()), Some("1"));
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(results[0].match_spans[0].indices[0], [0, 15]);
+
+        let top = fuzzy_search(notes(), "anxiety".to_string(), keys, 0.0, true, true, None, Some(2));
+        assert_eq!(top.len(), 2);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial_and_is_faster_on_large_corpus() {
+        let corpus: Vec<SearchItem> = (0..20_000)
+            .map(|i| {
+                let body = format!("Session {} notes on sleep, mood and anxiety management. ", i).repeat(20);
+                note(&i.to_string(), &format!("Client note {}", i % 500), &body, &format!("notes/{:05}.md", i), i % 7, i)
+            })
+            .collect();
+        let keys = vec!["title".to_string(), "article".to_string()];
+        let run = |parallel: bool| {
+            let started = std::time::Instant::now();
+            let search = if parallel { fuzzy_search_parallel } else { fuzzy_search };
+            let results = search(corpus.clone(), "anxiety".to_string(), keys.clone(), 0.0, true, true, None, Some(100));
+            (results, started.elapsed())
+        };
+
+        let (serial, serial_time) = run(false);
+        let (parallel, parallel_time) = run(true);
+        let order = |results: &[FuzzyMatch]| results.iter().map(|r| (r.path.clone(), r.score)).collect::<Vec<_>>();
+        assert_eq!(order(&serial), order(&parallel));
+        assert_eq!(serial.len(), 100);
+
+        // Timing only means something with more than one core to spread the work over
+        if std::thread::available_parallelism().map_or(1, |n| n.get()) >= 2 {
+            assert!(parallel_time < serial_time, "parallel {:?} vs serial {:?}", parallel_time, serial_time);
+        }
+    }
+
+    #[test]
+    fn test_body_snippet_is_centered_and_cut_on_char_boundaries() {
+        let body = format!("{}thérapie cognitive{}", "Séance à domicile 🏠, ".repeat(10), " — suivi prévu".repeat(10));
+        let item = note("1", "Notes", &body, "notes/a.md", 1, 0);
+        let results = fuzzy_search(vec![item], "cognitive".to_string(), vec!["title".to_string(), "article".to_string()], 0.0, true, true, None, None);
+        let snippet = results[0].snippet.clone().unwrap();
+
+        assert_eq!(snippet.key, "article");
+        assert!(snippet.text.chars().count() <= SNIPPET_CHARS);
+        let chars: Vec<char> = snippet.text.chars().collect();
+        let [start, end] = *snippet.highlights.iter().max_by_key(|r| r[1] - r[0]).unwrap();
+        assert_eq!(chars[start..=end].iter().collect::<String>(), "cognitive");
+
+        let titled = fuzzy_search(notes(), "protocol".to_string(), vec!["title".to_string(), "article".to_string()], 0.0, true, true, None, None);
+        let snippet = titled.iter().find(|r| r.item.id.as_deref() == Some("1")).unwrap().snippet.clone().unwrap();
+        assert_eq!((snippet.key.as_str(), snippet.text.as_str()), ("title", "Anxiety protocol"));
+    }
+}
@@ -0,0 +1,280 @@
+use jieba_rs::Jieba;
+use jieba_rs::KeywordExtract;
+use jieba_rs::TextRank;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tauri::command;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Keyword {
+    pub text: String,
+    pub weight: f64,
+}
+
+/// Ranked keywords with the language they were filtered for
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankedKeywords {
+    /// `en`, `fr`, `zh`, `mixed`, or `unknown` when nothing could be detected
+    pub language: String,
+    pub keywords: Vec<Keyword>,
+}
+
+const EN_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "before", "being", "but", "by", "can", "could", "did", "do", "does", "doing", "for", "from",
+    "had", "has", "have", "having", "he", "her", "here", "him", "his", "how", "i", "if", "in", "into",
+    "is", "it", "its", "just", "me", "more", "most", "my", "no", "not", "of", "on", "or", "our", "out",
+    "she", "so", "some", "such", "than", "that", "the", "their", "them", "then", "there", "these",
+    "they", "this", "those", "to", "too", "up", "very", "was", "we", "were", "what", "when", "where",
+    "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+const FR_STOPWORDS: &[&str] = &[
+    "à", "ai", "aussi", "au", "aux", "avec", "avait", "avoir", "c", "ça", "ce", "ces", "cet", "cette",
+    "d", "dans", "de", "des", "donc", "du", "elle", "elles", "en", "est", "et", "été", "être", "eu",
+    "il", "ils", "j", "je", "l", "la", "le", "les", "leur", "leurs", "lui", "m", "ma", "mais", "me",
+    "mes", "moi", "mon", "n", "ne", "nos", "notre", "nous", "on", "ont", "ou", "où", "par", "pas",
+    "pour", "qu", "que", "qui", "s", "sa", "sans", "se", "ses", "si", "son", "sont", "sur", "t", "ta",
+    "te", "tes", "toi", "ton", "tous", "tout", "très", "tu", "un", "une", "vos", "votre", "vous", "y",
+];
+
+/// Share of a note's words a language needs for the note not to count as mixed
+const DOMINANT_SHARE: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Lang {
+    En,
+    Fr,
+}
+
+impl Lang {
+    fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Fr => "fr",
+        }
+    }
+
+    fn stopwords(self) -> &'static HashSet<&'static str> {
+        static EN: OnceLock<HashSet<&'static str>> = OnceLock::new();
+        static FR: OnceLock<HashSet<&'static str>> = OnceLock::new();
+        match self {
+            Lang::En => EN.get_or_init(|| EN_STOPWORDS.iter().copied().collect()),
+            Lang::Fr => FR.get_or_init(|| FR_STOPWORDS.iter().copied().collect()),
+        }
+    }
+}
+
+fn get_jieba() -> &'static Jieba {
+    static JIEBA: OnceLock<Jieba> = OnceLock::new();
+
+    JIEBA.get_or_init(|| {
+        Jieba::new()
+    })
+}
+
+fn get_text_rank() -> TextRank {
+    TextRank::default()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+/// Lower-cased Latin-script words; apostrophes split elisions like "l'anxiété"
+fn latin_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() || is_cjk(c))
+        .filter(|w| !w.is_empty() && !w.chars().all(|c| c.is_numeric()))
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Language of a stretch of text by which stopword list it hits more; `None` when neither
+fn guess_language(words: &[String]) -> Option<Lang> {
+    let hits = |lang: Lang| words.iter().filter(|w| lang.stopwords().contains(w.as_str())).count();
+    let (en, fr) = (hits(Lang::En), hits(Lang::Fr));
+    match en.cmp(&fr) {
+        std::cmp::Ordering::Greater => Some(Lang::En),
+        std::cmp::Ordering::Less => Some(Lang::Fr),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Words grouped by the language of the sentence they came from. With a hint every sentence
+/// takes the hinted language; sentences `auto` cannot place take the note's overall language.
+fn words_by_language(text: &str, hint: Option<Lang>) -> HashMap<Lang, Vec<String>> {
+    let sentences: Vec<Vec<String>> = text
+        .split(|c: char| matches!(c, '.' | '!' | '?' | '\n' | '。' | '！' | '？'))
+        .map(latin_words)
+        .filter(|words| !words.is_empty())
+        .collect();
+    let overall = hint.or_else(|| guess_language(&sentences.concat())).unwrap_or(Lang::En);
+
+    let mut grouped: HashMap<Lang, Vec<String>> = HashMap::new();
+    for words in sentences {
+        let lang = hint.or_else(|| guess_language(&words)).unwrap_or(overall);
+        grouped.entry(lang).or_default().extend(words);
+    }
+    grouped
+}
+
+/// Frequency-ranked keywords of one language, weights relative to the most frequent
+fn rank_words(
+    words: &[String],
+    stopwords: &[&HashSet<&'static str>],
+    min_length: usize,
+    min_frequency: usize,
+) -> Vec<Keyword> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in words {
+        if word.chars().count() >= min_length && !stopwords.iter().any(|s| s.contains(word.as_str())) {
+            *counts.entry(word.as_str()).or_default() += 1;
+        }
+    }
+    let max = counts.values().copied().max().unwrap_or(1) as f64;
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().filter(|(_, n)| *n >= min_frequency).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    ranked
+        .into_iter()
+        .map(|(text, n)| Keyword { text: text.to_string(), weight: n as f64 / max })
+        .collect()
+}
+
+fn rank_chinese(text: &str, top_k: usize, allowed_pos: Option<Vec<String>>) -> Vec<Keyword> {
+    let jieba = get_jieba();
+    let extractor = get_text_rank();
+
+    let pos_tags = allowed_pos.unwrap_or_else(||
+        vec![
+            String::from("n"),    // noun
+            String::from("ns"),   // place name
+            String::from("v"),    // verb
+            String::from("vn"),   // verbal noun
+        ]
+    );
+
+    // The TextRank extract_keywords returns Vec<jieba_rs::Keyword>
+    let jieba_keywords = extractor.extract_keywords(
+        jieba,
+        text,
+        top_k,
+        pos_tags,
+    );
+
+    // Convert jieba_rs::Keyword to our custom Keyword struct
+    // Based on inspection, jieba_rs::Keyword is a tuple struct with (String, f64)
+    jieba_keywords.into_iter()
+        .map(|kw| Keyword {
+            text: kw.keyword.clone(),
+            weight: kw.weight,
+        })
+        .collect()
+}
+
+/// `language` is `en`, `fr` or `auto` (default). Latin-script words shorter than `min_length`
+/// (default 2) or seen fewer than `min_frequency` times (default 1) are dropped. In a mixed note
+/// both stopword lists apply and each language gets a share of `top_k` proportional to its words,
+/// at least one slot, so the minority language is never crowded out.
+#[command]
+pub fn rank_keywords(
+    text: &str,
+    top_k: usize,
+    allowed_pos: Option<Vec<String>>,
+    language: Option<String>,
+    min_length: Option<usize>,
+    min_frequency: Option<usize>,
+) -> RankedKeywords {
+    let hint = match language.as_deref().map(str::trim) {
+        Some("en") => Some(Lang::En),
+        Some("fr") => Some(Lang::Fr),
+        _ => None,
+    };
+    let min_length = min_length.unwrap_or(2).max(1);
+    let min_frequency = min_frequency.unwrap_or(1).max(1);
+
+    let cjk_chars = text.chars().filter(|c| is_cjk(*c)).count();
+    let chinese = if cjk_chars > 0 { rank_chinese(text, top_k, allowed_pos) } else { Vec::new() };
+
+    let grouped = words_by_language(text, hint);
+    let total_words: usize = grouped.values().map(Vec::len).sum();
+    let mut languages: Vec<(Lang, &Vec<String>)> = grouped.iter().map(|(lang, words)| (*lang, words)).collect();
+    languages.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.code().cmp(b.0.code())));
+    let mixed = languages.len() > 1 && (languages[0].1.len() as f64) < DOMINANT_SHARE * total_words as f64;
+
+    let mut keywords = Vec::new();
+    if mixed {
+        let all_stopwords = [Lang::En.stopwords(), Lang::Fr.stopwords()];
+        let mut leftovers = Vec::new();
+        for (_, words) in &languages {
+            let share = ((top_k as f64) * words.len() as f64 / total_words as f64).round() as usize;
+            let mut ranked = rank_words(words, &all_stopwords, min_length, min_frequency);
+            let taken = ranked.len().min(share.max(1));
+            leftovers.extend(ranked.split_off(taken));
+            keywords.extend(ranked);
+        }
+        leftovers.sort_by(|a, b| b.weight.total_cmp(&a.weight).then(a.text.cmp(&b.text)));
+        keywords.extend(leftovers);
+    } else if let Some((lang, words)) = languages.first() {
+        keywords = rank_words(words, &[lang.stopwords()], min_length, min_frequency);
+    }
+
+    // Chinese keywords first when the note is mostly Chinese
+    let mut merged = if cjk_chars >= total_words { chinese } else { Vec::new() };
+    let mut seen: HashSet<String> = merged.iter().map(|k| k.text.clone()).collect();
+    if cjk_chars < total_words {
+        keywords.extend(chinese);
+    }
+    for keyword in keywords {
+        if merged.len() >= top_k {
+            break;
+        }
+        if seen.insert(keyword.text.clone()) {
+            merged.push(keyword);
+        }
+    }
+    merged.truncate(top_k);
+
+    let language = if cjk_chars > 0 && total_words == 0 {
+        "zh"
+    } else if cjk_chars > 0 || mixed {
+        "mixed"
+    } else if let Some((lang, _)) = languages.first() {
+        lang.code()
+    } else {
+        "unknown"
+    };
+    RankedKeywords { language: language.to_string(), keywords: merged }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(ranked: &RankedKeywords) -> Vec<&str> {
+        ranked.keywords.iter().map(|k| k.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_french_note_is_detected_and_stopwords_dropped() {
+        let note = "Le patient parle de son anxiété. L'anxiété revient le soir et le sommeil est difficile. \
+                    Nous travaillons le sommeil et l'anxiété avec des exercices.";
+        let ranked = rank_keywords(note, 3, None, Some("auto".to_string()), Some(3), Some(2));
+        assert_eq!(ranked.language, "fr");
+        assert_eq!(texts(&ranked), vec!["anxiété", "sommeil"]);
+    }
+
+    #[test]
+    fn test_mixed_note_keeps_keywords_from_both_languages() {
+        let note = "Séance de suivi avec la cliente pour la gestion du stress et du stress au travail. \
+                    La cliente note une amélioration du sommeil. \
+                    The homework for next week is a breathing journal and a short walk.";
+        let ranked = rank_keywords(note, 4, None, None, Some(3), None);
+        assert_eq!(ranked.language, "mixed");
+        let keywords = texts(&ranked);
+        assert_eq!(keywords[..2], ["cliente", "stress"]);
+        // The English sentence is the minority but still gets a slot
+        assert!(keywords.contains(&"breathing"));
+        assert!(!keywords.iter().any(|k| ["the", "la", "du"].contains(k)));
+    }
+}
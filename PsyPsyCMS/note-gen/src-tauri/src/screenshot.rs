@@ -0,0 +1,203 @@
+use std::io::Cursor;
+use tauri::ipc::Response;
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+use xcap::image::{imageops, ImageFormat};
+use xcap::{Monitor, Window};
+
+#[cfg(target_os = "macos")]
+use core_graphics::display::CGDisplay;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone)]
+pub struct ScreenshotImage {
+    name: String,
+    path: String,
+    width: u32,
+    height: u32,
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+fn normalized(s: &str) -> String {
+    s.replace(" ", "-")
+    .replace("/", "-")
+    .replace("\\", "-")
+    .replace("*", "-")
+    .replace("?", "-")
+    .replace(":", "-")
+    .replace("<", "-")
+    .replace(">", "-")
+    .replace("|", "-")
+}
+
+// 校验选区：坐标相对于所选显示器左上角，必须完整落在截图范围内
+fn validate_region(
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    bounds_width: u32,
+    bounds_height: u32,
+) -> Result<(u32, u32, u32, u32), String> {
+    if width <= 0 || height <= 0 {
+        return Err(format!("Invalid region: width and height must be positive, got {}x{}", width, height));
+    }
+    let past_right = x.checked_add(width).map_or(true, |right| right > bounds_width as i64);
+    let past_bottom = y.checked_add(height).map_or(true, |bottom| bottom > bounds_height as i64);
+    if x < 0 || y < 0 || past_right || past_bottom {
+        return Err(format!(
+            "Region {}x{} at ({}, {}) is outside the display bounds {}x{}",
+            width, height, x, y, bounds_width, bounds_height
+        ));
+    }
+    Ok((x as u32, y as u32, width as u32, height as u32))
+}
+
+// 返回 PNG 字节；实际尺寸写在 PNG 的 IHDR 中
+fn capture_region(
+    monitor_index: Option<usize>,
+    region: Option<(i64, i64, i64, i64)>,
+) -> Result<Vec<u8>, String> {
+    let monitors = Monitor::all().map_err(|e| format!("Failed to list monitors: {}", e))?;
+    if monitors.is_empty() {
+        return Err("No monitors found".to_string());
+    }
+    // 默认主显示器
+    let index = match monitor_index {
+        Some(index) if index < monitors.len() => index,
+        Some(index) => {
+            return Err(format!("Monitor index {} out of range; {} monitor(s) available", index, monitors.len()))
+        }
+        None => monitors.iter().position(|m| m.is_primary().unwrap_or(false)).unwrap_or(0),
+    };
+
+    let image = monitors[index]
+        .capture_image()
+        .map_err(|e| format!("Failed to capture monitor {}: {}", index, e))?;
+    let image = match region {
+        Some((x, y, width, height)) => {
+            let (x, y, width, height) = validate_region(x, y, width, height, image.width(), image.height())?;
+            imageops::crop_imm(&image, x, y, width, height).to_image()
+        }
+        None => image,
+    };
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+    Ok(png)
+}
+
+// x、y、width、height 需同时传入；只传 monitor_index 时截取整个显示器
+// 区域截图以原始 PNG 字节返回（前端收到 ArrayBuffer），不再序列化为 JSON 数组；否则返回窗口截图列表
+#[allow(dead_code)]
+#[tauri::command]
+pub fn screenshot(
+    app: AppHandle,
+    x: Option<i64>,
+    y: Option<i64>,
+    width: Option<i64>,
+    height: Option<i64>,
+    monitor_index: Option<usize>,
+) -> Result<Response, String> {
+    let region = match (x, y, width, height) {
+        (None, None, None, None) => None,
+        (Some(x), Some(y), Some(width), Some(height)) => Some((x, y, width, height)),
+        _ => return Err("Invalid region: x, y, width and height must be given together".to_string()),
+    };
+    if region.is_some() || monitor_index.is_some() {
+        return capture_region(monitor_index, region).map(Response::new);
+    }
+    let windows = serde_json::to_string(&capture_windows(app))
+        .map_err(|e| format!("Failed to serialize screenshots: {}", e))?;
+    Ok(Response::new(windows))
+}
+
+fn capture_windows(app: AppHandle) -> Vec<ScreenshotImage> {
+    #[cfg(target_os = "macos")]
+    {
+        let display = CGDisplay::main();
+        let _ = display.image();
+    }
+    
+    let windows = Window::all().unwrap();
+
+    let temp_screenshot_folder = app
+        .path()
+        .resolve("temp_screenshot", BaseDirectory::AppData)
+        .unwrap();
+    if std::fs::metadata(&temp_screenshot_folder).is_ok() {
+        std::fs::remove_dir_all(&temp_screenshot_folder).unwrap();
+    }
+    std::fs::create_dir(&temp_screenshot_folder).unwrap();
+
+    let mut files: Vec<ScreenshotImage> = Vec::new();
+
+    let mut i = 0;
+    for window in windows {
+        // 已最小化的窗口跳过
+        if window.is_minimized().unwrap() {
+            continue;
+        }
+        
+        // 获取窗口属性
+        let title = window.title().unwrap_or_default();
+        let width = window.width().unwrap_or(0);
+        let height = window.height().unwrap_or(0);
+        let x = window.x().unwrap_or(0);
+        let y = window.y().unwrap_or(0);
+        let z = window.z().unwrap_or(0);
+        let system_titles = vec!["Dock", "Menu Bar", "MenuBar", "Status", "Notification Center", "", "Desktop", "NoteGen"];
+        
+        if system_titles.contains(&title.as_str()) || 
+           title.len() < 2 ||
+           width < 150 || 
+           height < 150 {
+            continue;
+        }
+        
+        let image = window.capture_image().unwrap();
+        let path = format!(
+            "{}/window-{}-{}.png",
+            temp_screenshot_folder.display(),
+            i,
+            normalized(&window.title().unwrap())
+        );
+        match image.save(&path) {
+            Ok(_) => println!("保存成功: {:?}", path),
+            Err(e) => println!("保存失败: {:?}", e),
+        };
+        files.push(ScreenshotImage {
+            name: title,
+            path,
+            width,
+            height,
+            x,
+            y,
+            z,
+        });
+
+        i += 1;
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_validation() {
+        assert_eq!(validate_region(10, 20, 300, 200, 1920, 1080), Ok((10, 20, 300, 200)));
+        assert_eq!(validate_region(0, 0, 1920, 1080, 1920, 1080), Ok((0, 0, 1920, 1080)));
+
+        assert!(validate_region(10, 20, 0, 200, 1920, 1080).is_err());
+        assert!(validate_region(10, 20, -5, 200, 1920, 1080).is_err());
+        assert!(validate_region(-1, 0, 100, 100, 1920, 1080).is_err());
+        assert!(validate_region(1900, 0, 100, 100, 1920, 1080).is_err());
+        assert!(validate_region(i64::MAX, 0, 1, 1, 1920, 1080).is_err());
+        assert!(validate_region(0, 10, 100, i64::MAX, 1920, 1080).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+// WebDAV 分块上传
+// 大文件按块（Content-Range PUT）写入远程临时文件 `<目标>.part`，全部写完并校验大小后才 MOVE 到目标路径，
+// 所以中断或重试都不会留下损坏的目标文件。每块成功后记录断点，下次从最后成功的块继续；
+// 服务器不支持带 Content-Range 的 PUT 时退回整文件上传。
+
+use reqwest_dav::re_exports::reqwest::{Method, StatusCode};
+use reqwest_dav::{Client, Depth};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// 备份进度事件
+pub const BACKUP_PROGRESS_EVENT: &str = "webdav-backup-progress";
+
+pub const DEFAULT_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+pub const MIN_CHUNK_SIZE: u64 = 64 * 1024;
+
+const MAX_RETRIES: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+// 断点文件，保存在应用数据目录
+pub const UPLOAD_STATE_FILE: &str = "webdav-upload-state.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    pub bytes_uploaded: u64,
+    pub total_bytes: u64,
+}
+
+// 未完成的分块上传
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PendingUpload {
+    // 长度加内容哈希；文件内容变了断点就作废
+    fingerprint: String,
+    chunk_size: u64,
+    uploaded: u64,
+}
+
+// 所有未完成上传的断点
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UploadState {
+    // 服务器地址；更换服务器后旧断点失效
+    server: String,
+    pending: HashMap<String, PendingUpload>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl UploadState {
+    pub fn load(path: &Path, server: &str) -> Self {
+        let state = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<UploadState>(&contents).ok())
+            .filter(|state| state.server == server);
+        let mut state = state.unwrap_or_else(|| UploadState { server: server.to_string(), ..Default::default() });
+        state.path = path.to_path_buf();
+        state
+    }
+
+    fn save(&self) {
+        // 断点只是优化，写不进去就从头上传
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(&self.path, contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("保存上传断点失败: {}", e);
+        }
+    }
+
+    fn record(&mut self, remote_path: &str, upload: PendingUpload) {
+        self.pending.insert(remote_path.to_string(), upload);
+        self.save();
+    }
+
+    fn clear(&mut self, remote_path: &str) {
+        if self.pending.remove(remote_path).is_some() {
+            self.save();
+        }
+    }
+}
+
+enum ChunkError {
+    // 网络错误、5xx、408、429，可重试
+    Transient(String),
+    // 服务器拒绝带 Content-Range 的 PUT
+    Unsupported,
+    Fatal(String),
+}
+
+fn fingerprint(content: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{}-{:016x}", content.len(), hasher.finish())
+}
+
+// 失败后按指数退避重试，只重试暂时性错误
+async fn with_retry<F, Fut>(mut op: F) -> Result<(), ChunkError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), ChunkError>>,
+{
+    let mut delay = INITIAL_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(ChunkError::Transient(e)) if attempt < MAX_RETRIES => {
+                attempt += 1;
+                eprintln!("上传失败，{}ms 后第 {} 次重试: {}", delay.as_millis(), attempt, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            other => return other,
+        }
+    }
+}
+
+async fn send_chunk(client: &Client, path: &str, chunk: &[u8], start: u64, total: u64) -> Result<(), ChunkError> {
+    let request = client
+        .start_request(Method::PUT, path)
+        .await
+        .map_err(|e| ChunkError::Transient(e.to_string()))?;
+    let end = start + chunk.len() as u64 - 1;
+    let response = request
+        .header("Content-Range", format!("bytes {}-{}/{}", start, end, total))
+        .body(chunk.to_vec())
+        .send()
+        .await
+        .map_err(|e| ChunkError::Transient(e.to_string()))?;
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        StatusCode::BAD_REQUEST
+        | StatusCode::METHOD_NOT_ALLOWED
+        | StatusCode::NOT_IMPLEMENTED
+        | StatusCode::RANGE_NOT_SATISFIABLE => Err(ChunkError::Unsupported),
+        status if status.is_server_error()
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS =>
+        {
+            Err(ChunkError::Transient(format!("HTTP {}", status)))
+        }
+        status => Err(ChunkError::Fatal(format!("HTTP {}", status))),
+    }
+}
+
+async fn put_whole(client: &Client, path: &str, content: &[u8]) -> Result<(), ChunkError> {
+    client
+        .put(path, content.to_vec())
+        .await
+        .map_err(|e| ChunkError::Transient(e.to_string()))
+}
+
+// 远程文件大小，不存在时为 None
+async fn remote_length(client: &Client, path: &str) -> Option<u64> {
+    let entries = client.list(path, Depth::Number(0)).await.ok()?;
+    entries.iter().find_map(|entry| {
+        let entry_json = serde_json::to_value(entry).ok()?;
+        entry_json.get("File")?.get("content_length")?.as_u64()
+    })
+}
+
+fn describe(error: ChunkError) -> String {
+    match error {
+        ChunkError::Transient(e) | ChunkError::Fatal(e) => e,
+        ChunkError::Unsupported => "服务器不支持分块上传".to_string(),
+    }
+}
+
+// 整文件上传，用于小文件和不支持分块的服务器
+async fn upload_single(
+    client: &Client,
+    remote_path: &str,
+    content: &[u8],
+    state: &mut UploadState,
+) -> Result<(), String> {
+    let part_path = format!("{}.part", remote_path);
+    if state.pending.contains_key(remote_path) {
+        let _ = client.delete(&part_path).await;
+        state.clear(remote_path);
+    }
+    with_retry(|| put_whole(client, remote_path, content)).await.map_err(describe)
+}
+
+// 上传一个文件；`progress` 收到该文件已上传的字节数
+pub async fn upload_resumable(
+    client: &Client,
+    remote_path: &str,
+    content: &[u8],
+    chunk_size: u64,
+    state: &mut UploadState,
+    mut progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let total = content.len() as u64;
+    let chunk_size = chunk_size.max(MIN_CHUNK_SIZE);
+    if total <= chunk_size {
+        upload_single(client, remote_path, content, state).await?;
+        progress(total);
+        return Ok(());
+    }
+
+    let part_path = format!("{}.part", remote_path);
+    let fingerprint = fingerprint(content);
+    let mut offset = match state.pending.get(remote_path) {
+        Some(pending) if pending.fingerprint == fingerprint && pending.chunk_size == chunk_size => {
+            // 远程临时文件至少要包含记录的部分，否则从头开始
+            match remote_length(client, &part_path).await {
+                Some(len) if len >= pending.uploaded => pending.uploaded,
+                _ => 0,
+            }
+        }
+        _ => 0,
+    };
+    progress(offset);
+
+    let mut range_verified = false;
+    while offset < total {
+        let end = (offset + chunk_size).min(total);
+        let chunk = &content[offset as usize..end as usize];
+        match with_retry(|| send_chunk(client, &part_path, chunk, offset, total)).await {
+            Ok(()) => {}
+            Err(ChunkError::Unsupported) => {
+                upload_single(client, remote_path, content, state).await?;
+                progress(total);
+                return Ok(());
+            }
+            // 断点保留，下次继续
+            Err(e) => return Err(format!("上传 {} 的 {}-{} 字节失败: {}", remote_path, offset, end, describe(e))),
+        }
+
+        // 忽略 Content-Range 的服务器会用这一块覆盖整个临时文件
+        if offset > 0 && !range_verified {
+            if remote_length(client, &part_path).await != Some(end) {
+                upload_single(client, remote_path, content, state).await?;
+                progress(total);
+                return Ok(());
+            }
+            range_verified = true;
+        }
+
+        offset = end;
+        state.record(remote_path, PendingUpload { fingerprint: fingerprint.clone(), chunk_size, uploaded: offset });
+        progress(offset);
+    }
+
+    // 大小一致才替换目标文件
+    if remote_length(client, &part_path).await != Some(total) {
+        let _ = client.delete(&part_path).await;
+        state.clear(remote_path);
+        return Err(format!("上传 {} 后远程文件大小不一致，已丢弃临时文件", remote_path));
+    }
+    client
+        .mv(&part_path, remote_path)
+        .await
+        .map_err(|e| format!("移动 {} 到目标路径失败: {}", part_path, e))?;
+    state.clear(remote_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retry_stops_on_non_transient_errors() {
+        let mut calls = 0;
+        let result = with_retry(|| {
+            calls += 1;
+            let outcome = if calls < 3 { Err(ChunkError::Transient("reset".into())) } else { Ok(()) };
+            async move { outcome }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result = with_retry(|| {
+            calls += 1;
+            async { Err(ChunkError::Unsupported) }
+        })
+        .await;
+        assert!(matches!(result, Err(ChunkError::Unsupported)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_content() {
+        assert_eq!(fingerprint(b"notes"), fingerprint(b"notes"));
+        assert_ne!(fingerprint(b"notes"), fingerprint(b"notez"));
+    }
+}
@@ -0,0 +1,15 @@
+declare module "note-gen/screenshot" {
+    export interface ScreenshotImage {
+        name: string;
+        path: string;
+        width: number;
+        height: number;
+        x: number;
+        y: number;
+        z: number;
+    }
+
+    // 传入 x/y/width/height 或 monitorIndex 时 screenshot 返回区域截图的原始 PNG 字节，
+    // 实际宽高可从 PNG 的 IHDR 读取（偏移 16、20 处的大端 u32）
+    export type RegionCapture = ArrayBuffer;
+}
\ No newline at end of file
@@ -0,0 +1,328 @@
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { FormItem, SettingRow } from "../components/setting-base";
+import { useState, useEffect } from "react";
+import { Badge } from "@/components/ui/badge";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Upload, Download, LoaderCircle } from "lucide-react";
+import useWebDAVStore, { WebDAVConnectionState } from "@/stores/webdav";
+import { useToast } from "@/hooks/use-toast";
+import { useTranslations } from "next-intl";
+import useArticleStore from "@/stores/article";
+import { listen } from "@tauri-apps/api/event";
+import { Progress } from "@/components/ui/progress";
+
+export default function WebdavSync() {
+  const t = useTranslations("settings.backupSync.webdav");
+  const { 
+    url, setUrl,
+    username, setUsername,
+    password, setPassword,
+    path, setPath,
+    chunkSizeMb, setChunkSizeMb,
+    connectionState, 
+    backupToWebDAV,
+    syncFromWebDAV,
+    resolveConflict,
+    initWebDAVData,
+    syncState,
+    backupState,
+    createWebDAVDir,
+  } = useWebDAVStore();
+  const { loadFileTree } = useArticleStore()
+
+
+  useEffect(() => {
+    initWebDAVData();
+  }, []);
+
+  const [backupProgress, setBackupProgress] = useState<{ bytes_uploaded: number, total_bytes: number } | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<{ bytes_uploaded: number, total_bytes: number }>("webdav-backup-progress", (event) => {
+      setBackupProgress(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleChunkSizeChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    const value = Number(e.target.value);
+    if (value > 0) {
+      setChunkSizeMb(value);
+    }
+  };
+
+  const handleUrlChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    setUrl(e.target.value);
+  };
+
+  const handleUsernameChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    setUsername(e.target.value);
+  };
+
+  const handlePasswordChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    setPassword(e.target.value);
+  };
+
+  const handlePathChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    setPath(e.target.value);
+  };
+
+
+  const handleBackupToWebDAV = async () => {
+    setBackupProgress(null);
+    try {
+    const res = await backupToWebDAV();
+    toast({
+        title: t("backupSuccess"),
+        description: t("backupSuccessDesc", { count: res }),
+    });
+    } catch (_error) {
+      const errorMessage = _error as string;
+
+      if (errorMessage.startsWith("[ERR_PATH_NOT_FOUND]")) {
+        toast({
+          variant: "destructive",
+          title: t("backupFailed"),
+          description: t("error.pathNotFound"),
+        });
+      } else {
+        toast({
+          variant: "destructive",
+          title: t("backupFailed"),
+          description: t("error.connectionTimeOut"),
+        });
+      }
+    } finally {
+      setBackupProgress(null);
+    }
+  };
+
+  const [conflicts, setConflicts] = useState<string[]>([]);
+  const [remoteDeleted, setRemoteDeleted] = useState<string[]>([]);
+  const [localDeleted, setLocalDeleted] = useState<string[]>([]);
+
+  const handleSyncFromWebDAV = async () => {
+    try {
+    const res = await syncFromWebDAV();
+    setConflicts(res.conflicts.map((conflict) => conflict.path));
+    setRemoteDeleted(res.remoteDeleted);
+    setLocalDeleted(res.localDeleted);
+    toast({
+        title: t("syncSuccess"),
+        description: t("syncSuccessDesc", { count: res.downloaded + res.uploaded }),
+    });
+    const deleted = res.remoteDeleted.length + res.localDeleted.length;
+    if (res.conflicts.length > 0 || deleted > 0) {
+      toast({
+        variant: "destructive",
+        title: t("conflicts.title"),
+        description: t("conflicts.desc", { conflicts: res.conflicts.length, deleted }),
+      });
+    }
+    } catch (error) {
+      const errorMessage = error as string;
+
+      if (errorMessage.startsWith("[ERR_PATH_NOT_FOUND]")) {
+        const missingPath = errorMessage.replace("[ERR_PATH_NOT_FOUND] ", "");
+        toast({
+          variant: "destructive",
+          title: t("syncFailed"),
+          description: t("error.pathNotFound", { path: missingPath }),
+        });
+      } else {
+        toast({
+          variant: "destructive",
+          title: t("syncFailed"),
+          description: t("error.connectionTimeOut"),
+        });
+      }
+    } finally {
+      loadFileTree()
+    }
+  };
+
+  const handleResolve = async (filePath: string, keep: "local" | "remote") => {
+    try {
+      await resolveConflict(filePath, keep);
+      setConflicts((items) => items.filter((item) => item !== filePath));
+      setRemoteDeleted((items) => items.filter((item) => item !== filePath));
+      setLocalDeleted((items) => items.filter((item) => item !== filePath));
+    } catch (error) {
+      toast({
+        variant: "destructive",
+        title: t("syncFailed"),
+        description: String(error),
+      });
+    } finally {
+      loadFileTree()
+    }
+  };
+
+  const { toast } = useToast();
+
+  const [isCreating, setIsCreating] = useState(false);
+
+  const handleCreateDirectory = async () => {
+    if (!path.trim()) {
+      toast({
+        variant: "destructive",
+        title: t("error.createDirFailed"),
+        description: t("error.pathCannotBeEmpty"),
+      });
+      return;
+    }
+
+    setIsCreating(true);
+
+    try {
+      await createWebDAVDir(path);
+      await initWebDAVData();
+      toast({
+        title: t("success"),
+        description: t("directoryCreatedDesc", { path }),
+      });
+    } catch (error: any) {
+      toast({
+        variant: "destructive",
+        title: t("error.createDirFailed"),
+        description: error.message || t("error.connectionTimeOut"),
+      });
+    } finally {
+      setIsCreating(false);
+    }
+  };
+
+  return (
+    <>
+      <SettingRow>
+        <FormItem title="">
+          <div className="flex items-center space-x-4">
+            <Card className="flex-1">
+              <CardHeader>
+                <CardTitle className="flex items-center gap-4">
+                  <span className="text-base font-bold">WebDAV</span>
+                  <Badge 
+                    className={`${
+                      connectionState === WebDAVConnectionState.success 
+                        ? "bg-green-800"
+                        : connectionState === WebDAVConnectionState.checking 
+                        ? "bg-yellow-800"
+                        : "bg-red-800"
+                    }`}
+                  >
+                    {t(`connectionState.${connectionState}`)}
+                  </Badge>
+                </CardTitle>
+                <CardDescription>{t("description")}</CardDescription>
+              </CardHeader>
+              <CardContent className="flex flex-col md:flex-row gap-4">
+                <Button 
+                  onClick={handleBackupToWebDAV} 
+                  className="mt-2"
+                  disabled={backupState || syncState}
+                >
+                  {backupState ? <LoaderCircle className="animate-spin" /> : <Upload />}
+                  {t("backupTo")}
+                </Button>
+                <Button 
+                  onClick={handleSyncFromWebDAV} 
+                  className="mt-2"
+                  disabled={syncState || backupState}
+                >
+                  {syncState ? <LoaderCircle className="animate-spin" /> : <Download />}
+                  {t("syncFrom")}
+                </Button>
+              </CardContent>
+              {backupState && backupProgress && backupProgress.total_bytes > 0 && (
+                <CardContent>
+                  <Progress value={(backupProgress.bytes_uploaded / backupProgress.total_bytes) * 100} />
+                </CardContent>
+              )}
+              {(conflicts.length > 0 || remoteDeleted.length > 0 || localDeleted.length > 0) && (
+                <CardContent className="flex flex-col gap-2">
+                  {conflicts.map((filePath) => (
+                    <div key={filePath} className="flex items-center gap-2 text-sm">
+                      <Badge className="bg-yellow-800">{t("conflicts.changedBoth")}</Badge>
+                      <span className="flex-1 truncate">{filePath}</span>
+                      <Button size="sm" variant="outline" onClick={() => handleResolve(filePath, "local")}>{t("conflicts.keepLocal")}</Button>
+                      <Button size="sm" variant="outline" onClick={() => handleResolve(filePath, "remote")}>{t("conflicts.keepRemote")}</Button>
+                    </div>
+                  ))}
+                  {remoteDeleted.map((filePath) => (
+                    <div key={filePath} className="flex items-center gap-2 text-sm">
+                      <Badge className="bg-red-800">{t("conflicts.deletedRemotely")}</Badge>
+                      <span className="flex-1 truncate">{filePath}</span>
+                      <Button size="sm" variant="outline" onClick={() => handleResolve(filePath, "local")}>{t("conflicts.restoreRemote")}</Button>
+                      <Button size="sm" variant="outline" onClick={() => handleResolve(filePath, "remote")}>{t("conflicts.deleteLocal")}</Button>
+                    </div>
+                  ))}
+                  {localDeleted.map((filePath) => (
+                    <div key={filePath} className="flex items-center gap-2 text-sm">
+                      <Badge className="bg-red-800">{t("conflicts.deletedLocally")}</Badge>
+                      <span className="flex-1 truncate">{filePath}</span>
+                      <Button size="sm" variant="outline" onClick={() => handleResolve(filePath, "remote")}>{t("conflicts.restoreLocal")}</Button>
+                      <Button size="sm" variant="outline" onClick={() => handleResolve(filePath, "local")}>{t("conflicts.deleteRemote")}</Button>
+                    </div>
+                  ))}
+                </CardContent>
+              )}
+            </Card>
+          </div>
+        </FormItem>
+      </SettingRow>
+      <SettingRow>
+        <FormItem title={t("serverUrl")} desc={t("serverUrlDesc")}>
+          <Input value={url} onChange={handleUrlChange} placeholder={t("serverUrlPlaceholder")} />
+        </FormItem>
+      </SettingRow>
+
+      <SettingRow>
+        <FormItem title={t("username")} desc={t("usernameDesc")}>
+          <Input value={username} onChange={handleUsernameChange} placeholder={t("usernamePlaceholder")} />
+        </FormItem>
+      </SettingRow>
+
+      <SettingRow>
+        <FormItem title={t("password")} desc={t("passwordDesc")}>
+          <Input 
+            value={password} 
+            onChange={handlePasswordChange} 
+            type="password" 
+            placeholder={t("passwordPlaceholder")}
+          />
+        </FormItem>
+      </SettingRow>
+
+
+      <SettingRow>
+        <FormItem title={t("backupPath")} desc={t("backupPathDesc")}>
+          <div className="flex items-center gap-2">
+          <Input 
+              className="flex-grow"
+            value={path} 
+            onChange={handlePathChange} 
+              placeholder={t("backupPathPlaceholder")}
+          />
+            <Button
+              variant="outline"
+              disabled={connectionState !== WebDAVConnectionState.fail || isCreating || !path.trim() || path.trim() === "/"}
+              onClick={handleCreateDirectory}
+            >
+              {isCreating && <LoaderCircle className="animate-spin" />}
+              {t("createDir")}
+            </Button>
+          </div>
+        </FormItem>
+      </SettingRow>
+
+      <SettingRow>
+        <FormItem title={t("chunkSize")} desc={t("chunkSizeDesc")}>
+          <Input type="number" min={1} value={chunkSizeMb} onChange={handleChunkSizeChange} />
+        </FormItem>
+      </SettingRow>
+    </>
+  );
+}
\ No newline at end of file
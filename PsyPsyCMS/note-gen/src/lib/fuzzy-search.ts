@@ -0,0 +1,169 @@
+import { invoke } from '@tauri-apps/api/core';
+
+// 匹配 Rust 类型的接口定义
+export interface SearchItem {
+  id?: string;
+  desc?: string;
+  title?: string;
+  article?: string;
+  url?: string;
+  path?: string;
+  searchType?: string;
+  type?: string;
+  tagId?: number;
+  createdAt?: number;
+  modifiedAt?: number;
+  score?: number;
+  matches?: MatchInfo;
+}
+
+// 匹配信息接口
+export interface MatchInfo {
+  key: string;
+  indices: [number, number][];
+  value: string;
+}
+
+// 命中上下文：标题命中时为标题，否则为正文中最佳匹配附近约 120 个字符
+// highlights 为 text 内的字符下标区间（闭区间）
+export interface Snippet {
+  key: string;
+  text: string;
+  highlights: [number, number][];
+}
+
+// 模糊搜索结果接口
+export interface FuzzySearchResult {
+  item: SearchItem;
+  refIndex: number;
+  matches: MatchInfo[];
+  score: number;
+  snippet?: Snippet;
+}
+
+// Rust 返回的原始结果，按得分降序排列
+interface RawFuzzyMatch {
+  path: string;
+  score: number;
+  match_spans: MatchInfo[];
+  snippet: Snippet | null;
+  item: SearchItem;
+  refindex: number;
+}
+
+// 搜索范围：按传入的 keys、仅标题、或全文
+export type SearchScope = 'keys' | 'titleOnly' | 'fullContent';
+
+// 搜索过滤条件（在排序前应用，条件之间为 AND 关系）
+export interface FuzzySearchFilters {
+  folder?: string;
+  tagIds?: number[];
+  createdAfter?: number;
+  createdBefore?: number;
+  modifiedAfter?: number;
+  modifiedBefore?: number;
+  scope?: SearchScope;
+}
+
+// 模糊搜索选项接口
+export interface FuzzySearchOptions {
+  keys: string[];
+  threshold?: number;
+  includeScore?: boolean;
+  includeMatches?: boolean;
+  filters?: FuzzySearchFilters;
+  // 只返回得分最高的前 N 条
+  limit?: number;
+}
+
+// Rust 模糊搜索包装类
+export class RustFuzzySearch {
+  private items: SearchItem[];
+  private options: FuzzySearchOptions;
+
+  // 构造函数
+  constructor(items: any[], options: Partial<FuzzySearchOptions> = {}) {
+    this.items = items;
+    this.options = {
+      keys: options.keys || [], // 确保有默认的键值
+      threshold: 0.3,
+      includeScore: true,
+      includeMatches: true,
+      ...options
+    };
+  }
+
+  // 执行模糊搜索
+  async search(query: string): Promise<FuzzySearchResult[]> {
+    if (!query) return [];
+    
+    try {
+      const rawResults = await invoke<Array<RawFuzzyMatch>>('fuzzy_search', {
+        items: this.items,
+        query,
+        keys: this.options.keys,
+        threshold: this.options.threshold || 0.3,
+        includeScore: this.options.includeScore ?? true,
+        includeMatches: this.options.includeMatches ?? true,
+        filters: this.options.filters ?? null,
+        limit: this.options.limit ?? null
+      });
+      
+      return rawResults.map((result: RawFuzzyMatch) => {
+        const item = result.item;
+        if ('search_type' in item && typeof item.search_type === 'string') {
+          item.searchType = item.search_type;
+          delete item.search_type;
+        }
+        
+        return {
+        item: result.item,
+        refIndex: result.refindex,
+        score: result.score,
+        matches: result.match_spans,
+        snippet: result.snippet ?? undefined
+      };
+      });
+    } catch (error) {
+      console.error('模糊搜索出错:', error);
+      return [];
+    }
+  }
+
+  // 执行并行模糊搜索（适用于大数据集）
+  async searchParallel(query: string): Promise<FuzzySearchResult[]> {
+    if (!query) return [];
+    
+    try {
+      const rawResults = await invoke<Array<RawFuzzyMatch>>('fuzzy_search_parallel', {
+        items: this.items,
+        query,
+        keys: this.options.keys,
+        threshold: this.options.threshold || 0.3,
+        includeScore: this.options.includeScore ?? true,
+        includeMatches: this.options.includeMatches ?? true,
+        filters: this.options.filters ?? null,
+        limit: this.options.limit ?? null
+      });
+
+      return rawResults.map((result: RawFuzzyMatch) => {
+        const item = result.item;
+        if ('search_type' in item && typeof item.search_type === 'string') {
+          item.searchType = item.search_type;
+          delete item.search_type;
+        }
+
+        return {
+          item: result.item,
+          refIndex: result.refindex,
+          score: result.score,
+          matches: result.match_spans,
+        snippet: result.snippet ?? undefined
+        };
+      });
+    } catch (error) {
+      console.error('并行模糊搜索出错:', error);
+      return [];
+    }
+  }
+}
@@ -2,14 +2,89 @@ use tauri::State;
 use tokio::sync::RwLock;
 use std::sync::Arc;
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 
 use crate::services::FirebaseService;
 use crate::models::{
-    Appointment, CreateAppointmentRequest, UpdateAppointmentRequest, ApiResponse,
-    PaginatedResponse, SearchFilters, SortOptions, AppointmentStats,
+    Appointment, AppointmentStatus, CreateAppointmentRequest, UpdateAppointmentRequest, ApiResponse,
+    PaginatedResponse, SearchFilters, SortOptions, AppointmentStats, GeoPoint, Professional, RecurrenceRule,
+    DEFAULT_SESSION_MINUTES,
 };
 use crate::security::auth::AuthState;
+use crate::security::data_scope::{collect_matching, collect_matching_where};
+use crate::storage::search_index::{IndexedEntity, SearchIndexState};
+use crate::services::event_log::{DomainEventKind, EventLogState};
+use crate::services::dashboard_cache::{DashboardCache, DashboardEntity};
+use crate::services::scheduling::{
+    appointments_on_local_days, booking_window, local_day_bounds, check_booking, clinic_timezone, expand_series, find_available_slots, find_schedule_conflicts, AvailableSlot, ScheduleConflictReport,
+    SchedulingConfig, SkipReason, SkippedOccurrence,
+};
+
+/// Appointments assigned to `professional_id` with a session in `[range_start, range_end)` in
+/// `tz`. Firestore filters on the professional, a page at a time; the window is checked on the
+/// expanded occurrences, since a series that started long ago can still have sessions in it.
+async fn professional_appointments_between(
+    firebase: &FirebaseService,
+    professional_id: &str,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+    tz: &chrono_tz::Tz,
+) -> Result<Vec<Appointment>, String> {
+    let professional = serde_json::Value::String(professional_id.to_string());
+    collect_matching_where(firebase, "appointments", "assignedProfessional", &professional, |a: &Appointment| {
+        !a.occurrences_between(range_start, range_end, tz).is_empty()
+    })
+    .await
+}
+
+/// Appointments with a session in `[range_start, range_end)`, read a page at a time so a large
+/// practice is not cut off at a fixed cap
+async fn appointments_between(
+    firebase: &FirebaseService,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+    tz: &chrono_tz::Tz,
+) -> Result<Vec<Appointment>, String> {
+    collect_matching(firebase, "appointments", |a: &Appointment| {
+        !a.occurrences_between(range_start, range_end, tz).is_empty()
+    })
+    .await
+}
+
+/// Refuse to save an appointment that would double-book its professional
+async fn ensure_no_booking_conflict(
+    firebase: &FirebaseService,
+    appointment: &Appointment,
+    scheduling: &SchedulingConfig,
+) -> Result<(), String> {
+    let Some(professional_id) = appointment.assigned_professional.as_deref() else {
+        return Ok(());
+    };
+    let wanted = appointment.occurrences_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC, &scheduling.clinic_tz());
+    let (Some(first), Some(last)) = (wanted.first(), wanted.last()) else {
+        return Ok(());
+    };
+    let (range_start, range_end) = booking_window(scheduling, professional_id, first.0, last.1);
+    let appointments = professional_appointments_between(firebase, professional_id, range_start, range_end, &scheduling.clinic_tz()).await?;
+    check_booking(&appointments, appointment, scheduling).map_err(|conflict| conflict.to_string())
+}
+
+/// Remove the sessions of a series created before a later one failed, so a failed request
+/// leaves no partial series behind
+async fn roll_back_series(firebase: &FirebaseService, search_index: &SearchIndexState, created: &[Appointment]) -> Vec<String> {
+    let mut left_behind = Vec::new();
+    for appointment in created {
+        if let Err(e) = firebase.delete_document("appointments", &appointment.object_id).await {
+            tracing::error!("Failed to roll back appointment {} of a failed series: {}", appointment.object_id, e);
+            left_behind.push(appointment.object_id.clone());
+            continue;
+        }
+        if let Err(e) = search_index.remove(IndexedEntity::Appointment, &appointment.object_id) {
+            tracing::warn!("Failed to drop rolled back appointment {} from the search index: {}", appointment.object_id, e);
+        }
+    }
+    left_behind
+}
 
 /// Get all appointments with pagination and filters
 #[tauri::command]
@@ -99,6 +174,10 @@ pub async fn create_appointment(
     request: CreateAppointmentRequest,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    search_index: State<'_, SearchIndexState>,
+    scheduling: State<'_, SchedulingConfig>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Appointment>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -115,7 +194,8 @@ pub async fn create_appointment(
 
     let firebase = firebase.lock().await;
 
-    // TODO: Validate appointment time conflicts
+    ensure_no_booking_conflict(&firebase, &appointment, &scheduling).await?;
+
     // TODO: Send notifications to client and professional
 
     // Create appointment in Firestore
@@ -123,6 +203,13 @@ pub async fn create_appointment(
         .await
         .map_err(|e| e.to_string())?;
 
+    if let Err(e) = search_index.index_appointment(&appointment) {
+        let _ = firebase.delete_document("appointments", &appointment_id).await;
+        return Err(e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
+
     // Audit log
     firebase.audit_log(
         "CREATE_APPOINTMENT",
@@ -137,12 +224,139 @@ pub async fn create_appointment(
         }))
     ).await.map_err(|e| e.to_string())?;
 
+    event_log.record(DomainEventKind::AppointmentCreated, "appointment", &appointment_id);
+
     Ok(ApiResponse::success_with_message(
         appointment,
         "Appointment created successfully".to_string()
     ))
 }
 
+/// Appointments generated for a series and the occurrences left out
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurringAppointmentsResult {
+    pub series_id: String,
+    pub created: Vec<Appointment>,
+    pub skipped: Vec<SkippedOccurrence>,
+}
+
+/// Create one appointment per occurrence of `recurrence`, starting at the request's preferred
+/// time and sharing a `series_id`. Occurrences step on the clinic's wall clock. Excluded dates,
+/// holidays (when the rule asks) and occurrences that would double-book `professional_id` are
+/// skipped and reported. If any session cannot be saved, the ones already saved are removed.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_recurring_appointments(
+    request: CreateAppointmentRequest,
+    recurrence: RecurrenceRule,
+    professional_id: Option<String>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    search_index: State<'_, SearchIndexState>,
+    scheduling: State<'_, SchedulingConfig>,
+    dashboard_cache: State<'_, DashboardCache>,
+) -> Result<ApiResponse<RecurringAppointmentsResult>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+
+    if !auth.has_permission("create_appointment") {
+        return Err("Insufficient permissions".to_string());
+    }
+
+    let first = request.preferred_date_time.as_deref()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or("A recurring series needs a preferred date and time")?;
+    let tz = scheduling.clinic_tz();
+    let plan = expand_series(&recurrence, first, &tz);
+
+    let firebase = firebase.lock().await;
+
+    let session = Duration::minutes(request.session_duration.map(i64::from).unwrap_or(DEFAULT_SESSION_MINUTES));
+    let mut booked: Vec<Appointment> = match (professional_id.as_deref(), plan.starts.first(), plan.starts.last()) {
+        (Some(professional_id), Some(first_start), Some(last_start)) => {
+            let (range_start, range_end) = booking_window(&scheduling, professional_id, *first_start, *last_start + session);
+            professional_appointments_between(&firebase, professional_id, range_start, range_end, &tz).await?
+        }
+        _ => Vec::new(),
+    };
+
+    let series_id = Uuid::new_v4().to_string();
+    let mut created = Vec::new();
+    let mut skipped = plan.skipped;
+    for start in plan.starts {
+        let appointment_id = Uuid::new_v4().to_string();
+        let mut appointment = Appointment::from_request(
+            CreateAppointmentRequest { preferred_date_time: Some(start.to_rfc3339()), ..request.clone() },
+            appointment_id.clone(),
+        );
+        appointment.assigned_professional = professional_id.clone();
+        appointment.series_id = Some(series_id.clone());
+
+        // Earlier sessions of this series count as booked too
+        if let Err(conflict) = check_booking(&booked, &appointment, &scheduling) {
+            skipped.push(SkippedOccurrence {
+                local_date: start.with_timezone(&tz).date_naive(),
+                start: Some(start),
+                reason: SkipReason::Conflict { appointment_ids: conflict.appointment_ids },
+            });
+            continue;
+        }
+
+        let saved = match firebase.create_document("appointments", &appointment_id, &appointment).await {
+            Ok(_) => {
+                // Saved but not indexed is still rolled back with the rest
+                created.push(appointment.clone());
+                search_index.index_appointment(&appointment)
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = saved {
+            let left_behind = roll_back_series(&firebase, &search_index, &created).await;
+            if !left_behind.is_empty() {
+                return Err(format!(
+                    "Failed to create the series: {}. These appointments were created and could not be removed: {}",
+                    e,
+                    left_behind.join(", ")
+                ));
+            }
+            return Err(format!("Failed to create the series, nothing was saved: {}", e));
+        }
+        booked.push(appointment);
+    }
+    skipped.sort_by_key(|s| s.local_date);
+    for appointment in &created {
+        event_log.record(DomainEventKind::AppointmentCreated, "appointment", &appointment.object_id);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
+
+    // Audit log
+    firebase.audit_log(
+        "CREATE_RECURRING_APPOINTMENTS",
+        "appointment",
+        auth.user_id.as_ref().unwrap(),
+        true, // PHI created when creating appointments
+        Some(serde_json::json!({
+            "series_id": series_id,
+            "client_id": request.client_id,
+            "professional_id": professional_id,
+            "created": created.iter().map(|a| a.object_id.clone()).collect::<Vec<_>>(),
+            "skipped": skipped.len()
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    let message = format!("Created {} appointments, skipped {}", created.len(), skipped.len());
+    Ok(ApiResponse::success_with_message(
+        RecurringAppointmentsResult { series_id, created, skipped },
+        message,
+    ))
+}
+
 /// Update existing appointment
 #[tauri::command]
 pub async fn update_appointment(
@@ -150,6 +364,10 @@ pub async fn update_appointment(
     request: UpdateAppointmentRequest,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    search_index: State<'_, SearchIndexState>,
+    scheduling: State<'_, SchedulingConfig>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Appointment>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -169,13 +387,23 @@ pub async fn update_appointment(
         .ok_or("Appointment not found")?;
 
     // Update appointment data
+    let previous = appointment.clone();
     appointment.update_from_request(request);
+    // Assigning a professional or moving the session can double-book them
+    ensure_no_booking_conflict(&firebase, &appointment, &scheduling).await?;
 
     // Save to Firestore
     let updated_appointment: Appointment = firebase.update_document("appointments", &id, &appointment)
         .await
         .map_err(|e| e.to_string())?;
 
+    if let Err(e) = search_index.index_appointment(&appointment) {
+        let _ = firebase.update_document("appointments", &id, &previous).await;
+        return Err(e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
+
     // Audit log
     firebase.audit_log(
         "UPDATE_APPOINTMENT",
@@ -189,6 +417,8 @@ pub async fn update_appointment(
         }))
     ).await.map_err(|e| e.to_string())?;
 
+    event_log.record(DomainEventKind::AppointmentUpdated, "appointment", &id);
+
     Ok(ApiResponse::success_with_message(
         updated_appointment,
         "Appointment updated successfully".to_string()
@@ -200,8 +430,11 @@ pub async fn update_appointment(
 pub async fn cancel_appointment(
     id: String,
     cancellation_reason: String,
+    cancel_series: Option<bool>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Appointment>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -221,13 +454,43 @@ pub async fn cancel_appointment(
         .ok_or("Appointment not found")?;
 
     // Cancel the appointment
-    appointment.cancel(Some(cancellation_reason));
+    appointment.cancel(Some(cancellation_reason.clone()));
 
     // Save to Firestore
     let updated_appointment: Appointment = firebase.update_document("appointments", &id, &appointment)
         .await
         .map_err(|e| e.to_string())?;
 
+    // The rest of the series, leaving sessions already held or under way alone. A failure
+    // part way through is reported only after the cancellations so far are audited.
+    let mut series_cancelled = Vec::new();
+    let mut series_error = None;
+    if let (Some(true), Some(series_id)) = (cancel_series, appointment.series_id.as_deref()) {
+        let series = serde_json::Value::String(series_id.to_string());
+        let siblings = collect_matching_where(&firebase, "appointments", "seriesId", &series, |other: &Appointment| {
+            other.object_id != id
+                && other.series_id.as_deref() == Some(series_id)
+                && matches!(other.status, AppointmentStatus::Pending | AppointmentStatus::Confirmed)
+        })
+        .await;
+        match siblings {
+            Ok(siblings) => {
+                for mut other in siblings {
+                    other.cancel(Some(cancellation_reason.clone()));
+                    if let Err(e) = firebase.update_document::<Appointment>("appointments", &other.object_id, &other).await {
+                        series_error = Some(format!("Failed to cancel appointment {} in the series: {}", other.object_id, e));
+                        break;
+                    }
+                    event_log.record(DomainEventKind::AppointmentCancelled, "appointment", &other.object_id);
+                    series_cancelled.push(other.object_id);
+                }
+            }
+            Err(e) => series_error = Some(format!("Failed to load the rest of the series: {}", e)),
+        }
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
+
     // Audit log
     firebase.audit_log(
         "CANCEL_APPOINTMENT",
@@ -238,10 +501,18 @@ pub async fn cancel_appointment(
             "appointment_id": id,
             "client_id": appointment.client_ptr,
             "professional_id": appointment.assigned_professional,
-            "cancellation_reason": appointment.professional_notes
+            "cancellation_reason": appointment.professional_notes,
+            "series_id": appointment.series_id,
+            "series_cancelled": series_cancelled,
+            "series_error": series_error,
         }))
     ).await.map_err(|e| e.to_string())?;
 
+    event_log.record(DomainEventKind::AppointmentCancelled, "appointment", &id);
+    if let Some(e) = series_error {
+        return Err(e);
+    }
+
     Ok(ApiResponse::success_with_message(
         updated_appointment,
         "Appointment cancelled successfully".to_string()
@@ -255,6 +526,8 @@ pub async fn complete_appointment(
     session_notes: Option<String>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Appointment>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -281,6 +554,8 @@ pub async fn complete_appointment(
         .await
         .map_err(|e| e.to_string())?;
 
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
+
     // Audit log
     firebase.audit_log(
         "COMPLETE_APPOINTMENT",
@@ -294,6 +569,8 @@ pub async fn complete_appointment(
         }))
     ).await.map_err(|e| e.to_string())?;
 
+    event_log.record(DomainEventKind::AppointmentCompleted, "appointment", &id);
+
     Ok(ApiResponse::success_with_message(
         updated_appointment,
         "Appointment completed successfully".to_string()
@@ -306,6 +583,9 @@ pub async fn delete_appointment(
     id: String,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<()>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -333,6 +613,11 @@ pub async fn delete_appointment(
     firebase.delete_document("appointments", &id)
         .await
         .map_err(|e| e.to_string())?;
+    if let Err(e) = search_index.remove(IndexedEntity::Appointment, &id) {
+        log::warn!("Deleted appointment {} is still in the search index: {}", id, e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
 
     // Audit log
     firebase.audit_log(
@@ -347,6 +632,8 @@ pub async fn delete_appointment(
         }))
     ).await.map_err(|e| e.to_string())?;
 
+    event_log.record(DomainEventKind::AppointmentDeleted, "appointment", &id);
+
     Ok(ApiResponse::success_with_message(
         (),
         "Appointment deleted successfully".to_string()
@@ -387,11 +674,13 @@ pub async fn search_appointments(
     Ok(ApiResponse::success(appointments))
 }
 
-/// Get appointments by date range
+/// Get appointments starting on the local days `start_date..=end_date` (`YYYY-MM-DD`) in
+/// `timezone` (IANA name, default `America/Montreal`)
 #[tauri::command]
 pub async fn get_appointments_by_date_range(
     start_date: String,
     end_date: String,
+    timezone: Option<String>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
 ) -> Result<ApiResponse<Vec<Appointment>>, String> {
@@ -400,10 +689,21 @@ pub async fn get_appointments_by_date_range(
         return Err("Unauthorized".to_string());
     }
 
+    let tz = clinic_timezone(timezone.as_deref())?;
+    let parse_day = |value: &str| chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {}", value));
+    let (from, to) = (parse_day(&start_date)?, parse_day(&end_date)?);
+    if to < from {
+        return Err("End date must not be before start date".to_string());
+    }
+
+    let (range_start, range_end) = local_day_bounds(&tz, from, to)
+        .ok_or_else(|| format!("Invalid date range: {} to {}", start_date, end_date))?;
+
     let firebase = firebase.lock().await;
 
-    // TODO: Implement date range query with Firestore
-    let appointments = Vec::new(); // Placeholder
+    let within = appointments_between(&firebase, range_start, range_end, &tz).await?;
+    let appointments = appointments_on_local_days(&within, &tz, from, to);
 
     // Audit log
     firebase.audit_log(
@@ -413,16 +713,170 @@ pub async fn get_appointments_by_date_range(
         true, // PHI accessed when viewing appointments
         Some(serde_json::json!({
             "start_date": start_date,
-            "end_date": end_date
+            "end_date": end_date,
+            "timezone": tz.name()
         }))
     ).await.map_err(|e| e.to_string())?;
 
     Ok(ApiResponse::success(appointments))
 }
 
-/// Get today's appointments
+/// Parse an RFC 3339 timestamp or a `YYYY-MM-DD` date (midnight in `tz`)
+fn parse_range_bound(value: &str, tz: &chrono_tz::Tz) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(*tz).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| format!("Invalid date: {}", value))
+}
+
+/// Report every overlap, double-booking, out-of-hours and no-buffer conflict in a
+/// professional's schedule between `start_date` and `end_date` (recurring series expanded).
+/// Hours are the professional's own, or the clinic's if they have none, in `timezone` (IANA
+/// name, default `America/Montreal`).
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_schedule_conflicts(
+    professional_id: String,
+    start_date: String,
+    end_date: String,
+    timezone: Option<String>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    scheduling: State<'_, SchedulingConfig>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<ScheduleConflictReport>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+
+    let tz = clinic_timezone(timezone.as_deref())?;
+    let range_start = parse_range_bound(&start_date, &tz)?;
+    let range_end = parse_range_bound(&end_date, &tz)?;
+    if range_end <= range_start {
+        return Err("End date must be after start date".to_string());
+    }
+
+    let firebase = firebase.lock().await;
+
+    let professional: Professional = firebase.get_document("professionals", &professional_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Professional not found")?;
+    let hours = professional.working_hours.as_ref().unwrap_or(&scheduling.working_hours);
+
+    let appointments = professional_appointments_between(&firebase, &professional_id, range_start, range_end, &tz).await?;
+    let report = find_schedule_conflicts(
+        &appointments,
+        &professional_id,
+        range_start,
+        range_end,
+        hours,
+        &scheduling,
+        &tz,
+    );
+
+    // Audit log
+    firebase.audit_log(
+        "VIEW_SCHEDULE_CONFLICTS",
+        "appointments",
+        auth.user_id.as_ref().unwrap(),
+        true, // Conflict entries identify clients
+        Some(serde_json::json!({
+            "professional_id": professional_id,
+            "start_date": start_date,
+            "end_date": end_date,
+            "timezone": tz.name(),
+            "own_hours": professional.working_hours.is_some(),
+            "conflicts": report.conflicts.len()
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(report))
+}
+
+/// Open slots for a professional on `date` (`YYYY-MM-DD`, local to `timezone`, default
+/// `America/Montreal`): their working hours, or the clinic's if they have none, minus existing
+/// sessions with their buffer and travel rules. Sessions are `duration_minutes` long (default
+/// `DEFAULT_SESSION_MINUTES`, no shorter than the configured minimum) and start on the configured
+/// slot step; `location` is where the new session would take place, if known.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_available_slots(
+    professional_id: String,
+    date: String,
+    duration_minutes: Option<u32>,
+    location: Option<GeoPoint>,
+    timezone: Option<String>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    scheduling: State<'_, SchedulingConfig>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<AvailableSlot>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+
+    let day = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {}", date))?;
+    let duration_minutes = duration_minutes.map(i64::from).unwrap_or(DEFAULT_SESSION_MINUTES);
+    if duration_minutes < i64::from(scheduling.minimum_session_minutes) {
+        return Err(format!("Duration must be at least {} minutes", scheduling.minimum_session_minutes));
+    }
+    let tz = clinic_timezone(timezone.as_deref())?;
+    let (day_start, day_end) = local_day_bounds(&tz, day, day)
+        .ok_or_else(|| format!("Invalid date: {}", date))?;
+
+    let firebase = firebase.lock().await;
+
+    let professional: Professional = firebase.get_document("professionals", &professional_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Professional not found")?;
+    let hours = professional.working_hours.as_ref().unwrap_or(&scheduling.working_hours);
+
+    let (range_start, range_end) = booking_window(&scheduling, &professional_id, day_start, day_end);
+    let appointments = professional_appointments_between(&firebase, &professional_id, range_start, range_end, &tz).await?;
+    let slots = find_available_slots(
+        &appointments,
+        &professional_id,
+        day,
+        Duration::minutes(duration_minutes),
+        Duration::minutes(i64::from(scheduling.slot_step_minutes)),
+        location.as_ref(),
+        hours,
+        &scheduling,
+        &tz,
+    );
+
+    // Audit log
+    firebase.audit_log(
+        "VIEW_AVAILABLE_SLOTS",
+        "appointments",
+        auth.user_id.as_ref().unwrap(),
+        false, // Only free times are returned
+        Some(serde_json::json!({
+            "professional_id": professional_id,
+            "date": date,
+            "timezone": tz.name(),
+            "duration_minutes": duration_minutes,
+            "own_hours": professional.working_hours.is_some(),
+            "slots": slots.len()
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(slots))
+}
+
+/// Get the appointments of the clinic's current local day in `timezone` (IANA name, default
+/// `America/Montreal`)
 #[tauri::command]
 pub async fn get_todays_appointments(
+    timezone: Option<String>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
 ) -> Result<ApiResponse<Vec<Appointment>>, String> {
@@ -431,10 +885,16 @@ pub async fn get_todays_appointments(
         return Err("Unauthorized".to_string());
     }
 
+    let tz = clinic_timezone(timezone.as_deref())?;
+    let today = Utc::now().with_timezone(&tz).date_naive();
+
+    let (range_start, range_end) = local_day_bounds(&tz, today, today)
+        .ok_or("Could not resolve today in the clinic timezone")?;
+
     let firebase = firebase.lock().await;
 
-    // TODO: Implement today's appointments query
-    let appointments = Vec::new(); // Placeholder
+    let within = appointments_between(&firebase, range_start, range_end, &tz).await?;
+    let appointments = appointments_on_local_days(&within, &tz, today, today);
 
     // Audit log
     firebase.audit_log(
@@ -442,7 +902,7 @@ pub async fn get_todays_appointments(
         "appointments",
         auth.user_id.as_ref().unwrap(),
         true, // PHI accessed
-        Some(serde_json::json!({"date": Utc::now().format("%Y-%m-%d").to_string()}))
+        Some(serde_json::json!({"date": today.format("%Y-%m-%d").to_string(), "timezone": tz.name()}))
     ).await.map_err(|e| e.to_string())?;
 
     Ok(ApiResponse::success(appointments))
@@ -494,6 +954,9 @@ pub async fn reschedule_appointment(
     reason: Option<String>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    scheduling: State<'_, SchedulingConfig>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Appointment>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -521,15 +984,16 @@ pub async fn reschedule_appointment(
     let old_date = appointment.confirmed_date_time.clone();
 
     // Reschedule the appointment
-    // Convert DateTime<Utc> to FirestoreTimestamp using helper function
-    let firestore_date = crate::models::common::firestore_now(); // Using current time for now
-    appointment.reschedule(firestore_date, reason);
+    appointment.reschedule(firestore::FirestoreTimestamp::from(new_scheduled_date), reason);
+    ensure_no_booking_conflict(&firebase, &appointment, &scheduling).await?;
 
     // Save to Firestore
     let updated_appointment: Appointment = firebase.update_document("appointments", &id, &appointment)
         .await
         .map_err(|e| e.to_string())?;
 
+    dashboard_cache.invalidate(Some(DashboardEntity::Appointment));
+
     // Audit log
     firebase.audit_log(
         "RESCHEDULE_APPOINTMENT",
@@ -545,6 +1009,8 @@ pub async fn reschedule_appointment(
         }))
     ).await.map_err(|e| e.to_string())?;
 
+    event_log.record(DomainEventKind::AppointmentUpdated, "appointment", &id);
+
     Ok(ApiResponse::success_with_message(
         updated_appointment,
         "Appointment rescheduled successfully".to_string()
@@ -6,13 +6,24 @@ use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-use crate::services::firebase_service_simple::FirebaseServiceState;
+use crate::commands::security_commands::RbacServiceState;
+use crate::services::firebase_service_simple::{AuthServiceState, FirebaseError, FirebaseServiceState};
+use crate::services::workstation_lock::WorkstationLockConfig;
+use crate::services::data_lock::{self, DataLockConfig, DataLockState, DataLockStatus};
 use crate::models::{
     User, LoginRequest, LoginResponse, RefreshTokenRequest, RefreshTokenResponse,
     PasswordResetRequest, PasswordChangeRequest, ProfileUpdateRequest, ApiResponse,
     common::firestore_now
 };
-use crate::security::auth::AuthState;
+use crate::security::auth::{AuthState, FirebaseAuthService, FirebaseUser, MfaChallengeType};
+use crate::security::SecurityError;
+use crate::security::rbac::Permission;
+use crate::security::step_up::StepUpState;
+use crate::security::session_binding::{self, BindingMode, BindingVerdict, SessionBinding, SessionBindingConfig, SessionBindingState};
+use crate::services::event_log::EventLogState;
+use crate::security::login_lockout::LoginLockout;
+use crate::security::totp::TotpEnrollment;
+use crate::services::FirebaseService;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StoredSession {
@@ -23,14 +34,121 @@ pub struct StoredSession {
     pub device_info: Option<String>,
 }
 
+/// Refuse a password check for an account that is locked out
+async fn refuse_if_locked(lockout: &LoginLockout, firebase: &FirebaseService, email: &str) -> Result<(), String> {
+    let now = Utc::now();
+    let Some(until) = lockout.locked_until(email, now) else {
+        return Ok(());
+    };
+    tracing::warn!("Sign-in refused for locked account {} until {}", email, until);
+    firebase.audit_log(
+        "LOGIN_REFUSED_LOCKED",
+        "authentication",
+        "anonymous",
+        false,
+        Some(serde_json::json!({ "email": email, "locked_until": until }))
+    ).await.map_err(|e| e.to_string())?;
+    lockout.check(email, now).map_err(|e| e.to_string())
+}
+
+/// Count a failed password check, auditing the lock when this failure trips it. Only a wrong
+/// email or password counts; an outage or quota error says nothing about the credentials.
+async fn record_failed_login(lockout: &LoginLockout, firebase: &FirebaseService, email: &str, error: &FirebaseError) {
+    if !matches!(error, FirebaseError::InvalidCredentials(_)) {
+        return;
+    }
+    let Some(until) = lockout.record_failure(email, Utc::now()) else {
+        return;
+    };
+    tracing::warn!("Account {} locked until {} after repeated failed sign-ins", email, until);
+    if let Err(e) = firebase.audit_log(
+        "ACCOUNT_LOCKED",
+        "authentication",
+        "anonymous",
+        false,
+        Some(serde_json::json!({ "email": email, "locked_until": until }))
+    ).await {
+        tracing::warn!("Failed to log audit event: {}", e);
+    }
+}
+
+/// Lockout key for a user's TOTP factor, kept apart from the password lock on their email
+fn totp_lockout_account(user_id: &str) -> String {
+    format!("totp:{}", user_id)
+}
+
+/// Count a second-factor attempt toward the TOTP lock. Only a wrong or replayed code counts;
+/// a missing factor or storage error says nothing about the code.
+fn record_totp_attempt<T>(lockout: &LoginLockout, user_id: &str, outcome: &Result<T, SecurityError>, accepted: impl Fn(&T) -> bool) {
+    let account = totp_lockout_account(user_id);
+    match outcome {
+        Ok(value) if accepted(value) => lockout.record_success(&account),
+        Ok(_) | Err(SecurityError::AuthenticationFailed { .. }) => {
+            if let Some(until) = lockout.record_failure(&account, Utc::now()) {
+                tracing::warn!("TOTP for user {} locked until {} after repeated wrong codes", user_id, until);
+            }
+        }
+        Err(_) => {}
+    }
+}
+
+/// Check a TOTP code, refusing while the factor is locked so codes cannot be guessed faster
+/// than passwords
+pub(crate) async fn verify_totp_throttled(
+    service: &FirebaseAuthService,
+    lockout: &LoginLockout,
+    user_id: &str,
+    code: &str,
+) -> Result<bool, SecurityError> {
+    lockout.check(&totp_lockout_account(user_id), Utc::now())?;
+    let outcome = service.verify_totp(user_id, code).await;
+    record_totp_attempt(lockout, user_id, &outcome, |verified| *verified);
+    outcome
+}
+
+/// Answer an MFA challenge under the same TOTP lock as `verify_totp_throttled`
+pub(crate) async fn verify_challenge_throttled(
+    service: &FirebaseAuthService,
+    lockout: &LoginLockout,
+    user_id: &str,
+    challenge_id: &str,
+    code: &str,
+) -> Result<bool, SecurityError> {
+    lockout.check(&totp_lockout_account(user_id), Utc::now())?;
+    let outcome = service.verify_mfa_challenge(challenge_id, code).await;
+    record_totp_attempt(lockout, user_id, &outcome, |verified| *verified);
+    outcome
+}
+
+/// Session role for a user profile
+fn session_role(user: &User) -> crate::security::HealthcareRole {
+    match user.base.user_type {
+        crate::models::UserType::Admin => crate::security::HealthcareRole::Administrator,
+        crate::models::UserType::HealthcareProvider => crate::security::HealthcareRole::HealthcareProvider,
+        crate::models::UserType::Professional => crate::security::HealthcareRole::HealthcareProvider,
+        crate::models::UserType::Client => crate::security::HealthcareRole::Patient,
+    }
+}
+
 /// Authenticate user with email and password
 #[tauri::command]
 pub async fn auth_login(
     email: String,
     password: String,
     firebase: State<'_, FirebaseServiceState>,
+    auth_service: State<'_, AuthServiceState>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    data_lock: State<'_, DataLockState>,
+    app_handle: AppHandle,
+    binding_state: State<'_, SessionBindingState>,
+    binding_config: State<'_, SessionBindingConfig>,
+    step_up: State<'_, StepUpState>,
+    login_lockout: State<'_, LoginLockout>,
 ) -> Result<ApiResponse<LoginResponse>, String> {
+    // A normal login cannot lift an emergency data lock
+    data_lock.ensure_unlocked()?;
+
     let request = LoginRequest {
         email: email.clone(),
         password: password.clone(),
@@ -42,13 +160,16 @@ pub async fn auth_login(
     let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
 
     // Authenticate using Firebase Auth REST API
+    refuse_if_locked(&login_lockout, firebase, &request.email).await?;
     let auth_result = match firebase.authenticate_user(&request.email, &request.password).await {
         Ok(result) => result,
         Err(e) => {
             tracing::error!("Firebase authentication failed: {}", e);
+            record_failed_login(&login_lockout, firebase, &request.email, &e).await;
             return Err(format!("Authentication failed: {}", e));
         }
     };
+    login_lockout.record_success(&request.email);
 
     // Step 2: Get user data from Firestore
     let user = match firebase.get_document::<User>("users", &auth_result.uid).await {
@@ -76,9 +197,20 @@ pub async fn auth_login(
         }
     };
 
-    // Step 3: Create session token with HIPAA audit trail
-    let session_token = crate::security::auth::create_session_token(&user.base.object_id, &user.base.email, user.base.user_type.clone())
-        .map_err(|e| format!("Failed to create session: {}", e))?;
+    // Step 3: Open the auth service session; its tokens are the ones refreshed and revoked later
+    let role = session_role(&user);
+    let session = {
+        let service_guard = auth_service.0.lock().await;
+        let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+        service.create_session(
+            &FirebaseUser::signed_in(&user.base.object_id, &user.base.email),
+            role.clone(),
+            session_binding::outbound_ip().map(|ip| ip.to_string()),
+            None,
+        ).await.map_err(|e| format!("Failed to create session: {}", e))?
+    };
+    // A sign-in from an implausible location must complete MFA before using any permission
+    let mfa_required = session.security_metadata["mfa_required"] == true;
 
     // Step 4: Log HIPAA audit event
     let audit_result = firebase.audit_log(
@@ -101,24 +233,26 @@ pub async fn auth_login(
 
     let response = LoginResponse {
         user: user.clone(),
-        access_token: session_token.clone(),
-        refresh_token: auth_result.refresh_token.clone(),
-        expires_in: auth_result.expires_in as i64,
+        access_token: session.access_token.clone(),
+        refresh_token: session.refresh_token.clone(),
+        expires_in: (session.expires_at - Utc::now()).num_seconds().max(0),
+        mfa_required,
     };
 
+    // A new session never sees events recorded for the previous one
+    if let Ok(mut log) = event_log.0.lock() {
+        log.reset();
+    }
+
     // Update auth state
     {
         let mut auth = auth_state.write().await;
         auth.user_id = Some(user.base.object_id.clone());
-        auth.access_token = Some(session_token);
-        auth.refresh_token = Some(auth_result.refresh_token);
+        auth.access_token = Some(session.access_token.clone());
+        auth.refresh_token = Some(session.refresh_token.clone());
+        auth.session_id = Some(session.session_id.to_string());
         auth.is_authenticated = true;
-        auth.role = Some(match user.base.user_type {
-            crate::models::UserType::Admin => crate::security::HealthcareRole::Administrator,
-            crate::models::UserType::HealthcareProvider => crate::security::HealthcareRole::HealthcareProvider,
-            crate::models::UserType::Professional => crate::security::HealthcareRole::HealthcareProvider,
-            crate::models::UserType::Client => crate::security::HealthcareRole::Patient,
-        });
+        auth.role = Some(role);
         auth.permissions = match user.base.user_type {
             crate::models::UserType::Admin => vec![
                 "read_all".to_string(),
@@ -132,7 +266,24 @@ pub async fn auth_login(
             ],
             crate::models::UserType::Client => vec!["read_basic".to_string()],
         };
-        auth.session_expires_at = Some(chrono::Utc::now() + chrono::Duration::seconds(auth_result.expires_in as i64));
+        auth.session_expires_at = Some(session.expires_at);
+        auth.mfa_verified_at = None;
+        auth.mfa_required = mfa_required;
+    }
+
+    // Tie the new session to this device and network
+    binding_state.clear();
+    step_up.clear();
+    if binding_config.mode != BindingMode::Off {
+        let context = app_handle
+            .path()
+            .app_data_dir()
+            .ok()
+            .and_then(|dir| binding_state.current_context(&dir));
+        match context {
+            Some(context) => binding_state.bind(SessionBinding::capture(&binding_config, &context)),
+            None => tracing::warn!("Session for {} could not be bound to this device", user.base.object_id),
+        }
     }
 
     // Audit log
@@ -151,18 +302,34 @@ pub async fn auth_login(
 #[tauri::command]
 pub async fn auth_logout(
     firebase: State<'_, FirebaseServiceState>,
+    auth_service: State<'_, AuthServiceState>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    event_log: State<'_, EventLogState>,
+    binding_state: State<'_, SessionBindingState>,
+    step_up: State<'_, StepUpState>,
 ) -> Result<ApiResponse<()>, String> {
-    let user_id = {
+    let (user_id, refresh_token) = {
         let auth = auth_state.read().await;
-        auth.user_id.clone()
+        (auth.user_id.clone(), auth.refresh_token.clone())
     };
 
+    // End the server-side session and forget its refresh token family
+    if let Some(token) = refresh_token {
+        if let Some(service) = auth_service.0.lock().await.as_ref() {
+            service.end_session_for_token(&token).await.map_err(|e| e.to_string())?;
+        }
+    }
+
     // Clear auth state
     {
         let mut auth = auth_state.write().await;
         auth.clear();
     }
+    binding_state.clear();
+    step_up.clear();
+    if let Ok(mut log) = event_log.0.lock() {
+        log.reset();
+    }
 
     // Audit log
     if let Some(user_id) = user_id {
@@ -180,29 +347,68 @@ pub async fn auth_logout(
     Ok(ApiResponse::success_with_message((), "Logged out successfully".to_string()))
 }
 
-/// Refresh authentication token
+/// Rotate the session's refresh token; the stored refresh token is used when none is passed.
+/// Presenting a token that was already rotated ends every session of the account, this one included.
 #[tauri::command]
 pub async fn auth_refresh_token(
     refresh_token: String,
-    _firebase: State<'_, FirebaseServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_service: State<'_, AuthServiceState>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    data_lock: State<'_, DataLockState>,
+    binding_state: State<'_, SessionBindingState>,
+    step_up: State<'_, StepUpState>,
 ) -> Result<ApiResponse<RefreshTokenResponse>, String> {
-    let _request = RefreshTokenRequest { refresh_token };
-
-    // TODO: Implement actual token refresh with Firebase
-    let response = RefreshTokenResponse {
-        access_token: "new_mock_access_token".to_string(),
-        refresh_token: "new_mock_refresh_token".to_string(),
-        expires_in: 3600,
+    data_lock.ensure_unlocked()?;
+    let request = RefreshTokenRequest { refresh_token };
+    let (user_id, stored_token) = {
+        let auth = auth_state.read().await;
+        (auth.user_id.clone(), auth.refresh_token.clone())
     };
-
-    // Update auth state
-    {
-        let mut auth = auth_state.write().await;
-        auth.access_token = Some(response.access_token.clone());
+    let presented = Some(request.refresh_token)
+        .filter(|t| !t.trim().is_empty())
+        .or(stored_token)
+        .ok_or("No refresh token for this session")?;
+
+    let service_guard = auth_service.0.lock().await;
+    let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+    match service.refresh_session(&presented).await {
+        Ok(session) => {
+            {
+                let mut auth = auth_state.write().await;
+                auth.access_token = Some(session.access_token.clone());
+                auth.refresh_token = Some(session.refresh_token.clone());
+                auth.session_expires_at = Some(session.expires_at);
+            }
+            Ok(ApiResponse::success(RefreshTokenResponse {
+                expires_in: (session.expires_at - Utc::now()).num_seconds().max(0),
+                access_token: session.access_token,
+                refresh_token: session.refresh_token,
+            }))
+        }
+        Err(SecurityError::AccessDenied { reason }) => {
+            // Token reuse: the service already revoked the family, so sign this client out too
+            drop(service_guard);
+            auth_state.write().await.clear();
+            binding_state.clear();
+            step_up.clear();
+
+            let firebase_guard = firebase.0.lock().await;
+            if let (Some(firebase), Some(user_id)) = (firebase_guard.as_ref(), user_id) {
+                if let Err(e) = firebase.audit_log(
+                    "REFRESH_TOKEN_REUSE_DETECTED",
+                    "authentication",
+                    &user_id,
+                    false,
+                    Some(serde_json::json!({ "sessions_revoked": true }))
+                ).await {
+                    tracing::warn!("Failed to log refresh token reuse: {}", e);
+                }
+            }
+            Err(reason)
+        }
+        Err(e) => Err(format!("Token refresh failed: {}", e)),
     }
-
-    Ok(ApiResponse::success(response))
 }
 
 /// Get current authenticated user
@@ -385,6 +591,501 @@ pub async fn auth_check_status(
     Ok(ApiResponse::success(auth.is_authenticated))
 }
 
+/// Start the MFA challenge needed to resume a locked session with PHI access. The code comes
+/// from the factor enrolled by the session's own user.
+#[tauri::command]
+pub async fn auth_start_unlock_mfa(
+    auth_service: State<'_, AuthServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<String>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_locked() {
+        return Err("Session is not locked".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let service_guard = auth_service.0.lock().await;
+    let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+    let challenge_id = service
+        .start_mfa_challenge(&user_id, MfaChallengeType::Totp)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(challenge_id))
+}
+
+/// Resume a session locked by the OS lock/sleep monitor
+#[tauri::command]
+pub async fn auth_unlock_session(
+    email: String,
+    password: String,
+    mfa_challenge_id: Option<String>,
+    mfa_code: Option<String>,
+    lock_config: State<'_, WorkstationLockConfig>,
+    rbac: State<'_, RbacServiceState>,
+    auth_service: State<'_, AuthServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    data_lock: State<'_, DataLockState>,
+    login_lockout: State<'_, LoginLockout>,
+) -> Result<ApiResponse<bool>, String> {
+    // Lifting a data lock always needs `unlock_all_data`
+    data_lock.ensure_unlocked()?;
+
+    let (user_id, role) = {
+        let auth = auth_state.read().await;
+        if !auth.is_locked() {
+            return Err("Session is not locked".to_string());
+        }
+        (
+            auth.user_id.clone().ok_or("No user ID in auth state")?,
+            auth.get_role().cloned().ok_or("No role in auth state")?,
+        )
+    };
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    // Only the user who owned the session can resume it
+    refuse_if_locked(&login_lockout, firebase, &email).await?;
+    let reauthenticated = match firebase.authenticate_user(&email, &password).await {
+        Ok(result) => {
+            login_lockout.record_success(&email);
+            result.uid == user_id
+        }
+        Err(e) => {
+            tracing::warn!("Re-authentication failed: {}", e);
+            record_failed_login(&login_lockout, firebase, &email, &e).await;
+            false
+        }
+    };
+    if !reauthenticated {
+        firebase.audit_log(
+            "SESSION_UNLOCK_FAILED",
+            "authentication",
+            &user_id,
+            false,
+            Some(serde_json::json!({ "reason": "re-authentication failed" }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err("Re-authentication failed".to_string());
+    }
+
+    let phi_access = rbac.0
+        .get_role_definition(&role)
+        .map_or(false, |def| def.permissions.contains(&Permission::ViewPHI));
+    let mfa_required = lock_config.require_mfa_for_phi && phi_access;
+    if mfa_required {
+        let (challenge_id, code) = match (mfa_challenge_id.as_deref(), mfa_code.as_deref()) {
+            (Some(id), Some(code)) => (id, code),
+            _ => return Err("MFA verification required to resume PHI access".to_string()),
+        };
+        let service_guard = auth_service.0.lock().await;
+        let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+        if service.mfa_challenge_owner(challenge_id).as_deref() != Some(user_id.as_str()) {
+            return Err("MFA challenge does not belong to this session".to_string());
+        }
+        let verified = verify_challenge_throttled(service, &login_lockout, &user_id, challenge_id, code)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !verified {
+            return Err("MFA verification failed".to_string());
+        }
+    }
+
+    {
+        let mut auth = auth_state.write().await;
+        auth.unlock();
+        if mfa_required {
+            auth.mark_mfa_verified();
+        }
+    }
+
+    firebase.audit_log(
+        "SESSION_UNLOCKED",
+        "authentication",
+        &user_id,
+        false,
+        Some(serde_json::json!({ "mfa_verified": mfa_required }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(true, "Session resumed".to_string()))
+}
+
+/// Start the MFA challenge answering a `require_mfa_stepup` refusal. The code comes from the
+/// user's enrolled authenticator; without one the step-up cannot be completed.
+#[tauri::command]
+pub async fn start_mfa_stepup(
+    auth_service: State<'_, AuthServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<String>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let service_guard = auth_service.0.lock().await;
+    let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+    let challenge_id = service
+        .start_mfa_challenge(&user_id, MfaChallengeType::Totp)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(challenge_id))
+}
+
+/// Complete a mid-session MFA step-up. Until this succeeds, the action that asked for it
+/// keeps being refused.
+#[tauri::command]
+pub async fn complete_mfa_stepup(
+    challenge_id: String,
+    code: String,
+    auth_service: State<'_, AuthServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    step_up: State<'_, StepUpState>,
+    login_lockout: State<'_, LoginLockout>,
+) -> Result<ApiResponse<bool>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let verified = {
+        let service_guard = auth_service.0.lock().await;
+        let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+        if service.mfa_challenge_owner(&challenge_id).as_deref() != Some(user_id.as_str()) {
+            return Err("MFA challenge does not belong to this session".to_string());
+        }
+        verify_challenge_throttled(service, &login_lockout, &user_id, &challenge_id, &code)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    let reason = step_up.outstanding().map(|risk| risk.reason);
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    if !verified {
+        firebase.audit_log(
+            "MFA_STEPUP_FAILED",
+            "authentication",
+            &user_id,
+            false,
+            Some(serde_json::json!({ "reason": reason }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err("MFA verification failed".to_string());
+    }
+
+    auth_state.write().await.mark_mfa_verified();
+    step_up.clear();
+    firebase.audit_log(
+        "MFA_STEPUP_COMPLETED",
+        "authentication",
+        &user_id,
+        false,
+        Some(serde_json::json!({ "reason": reason }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(true, "Verification complete".to_string()))
+}
+
+/// Start TOTP enrollment for the signed-in user; the secret is shown once and the factor is
+/// enabled by the first code passed to `verify_totp`. Replacing an enabled factor needs
+/// `current_code` from it.
+#[tauri::command]
+pub async fn enroll_totp(
+    current_code: Option<String>,
+    auth_service: State<'_, AuthServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    login_lockout: State<'_, LoginLockout>,
+) -> Result<ApiResponse<TotpEnrollment>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let enrollment = {
+        let service_guard = auth_service.0.lock().await;
+        let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+        login_lockout.check(&totp_lockout_account(&user_id), Utc::now()).map_err(|e| e.to_string())?;
+        let outcome = service.enroll_totp(&user_id, current_code.as_deref()).await;
+        if current_code.is_some() {
+            record_totp_attempt(&login_lockout, &user_id, &outcome, |_| true);
+        }
+        outcome.map_err(|e| e.to_string())?
+    };
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "MFA_TOTP_ENROLLMENT_STARTED",
+        "authentication",
+        &user_id,
+        false,
+        None
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(enrollment))
+}
+
+/// Check a TOTP code for the signed-in user. The first valid code enables the factor; every
+/// valid code marks the session MFA-verified.
+#[tauri::command]
+pub async fn verify_totp(
+    code: String,
+    auth_service: State<'_, AuthServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    login_lockout: State<'_, LoginLockout>,
+) -> Result<ApiResponse<bool>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let (was_enabled, outcome) = {
+        let service_guard = auth_service.0.lock().await;
+        let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+        (service.totp_enabled(&user_id), verify_totp_throttled(service, &login_lockout, &user_id, &code).await)
+    };
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    let verified = match outcome {
+        Ok(verified) => verified,
+        Err(e) => {
+            firebase.audit_log(
+                "MFA_TOTP_FAILED",
+                "authentication",
+                &user_id,
+                false,
+                Some(serde_json::json!({ "reason": e.to_string() }))
+            ).await.map_err(|e| e.to_string())?;
+            return Err(e.to_string());
+        }
+    };
+    if !verified {
+        firebase.audit_log(
+            "MFA_TOTP_FAILED",
+            "authentication",
+            &user_id,
+            false,
+            Some(serde_json::json!({ "reason": "invalid code" }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err("MFA verification failed".to_string());
+    }
+
+    auth_state.write().await.mark_mfa_verified();
+    if !was_enabled {
+        firebase.audit_log(
+            "MFA_ENABLED",
+            "authentication",
+            &user_id,
+            false,
+            Some(serde_json::json!({ "factor": "totp" }))
+        ).await.map_err(|e| e.to_string())?;
+    }
+    firebase.audit_log(
+        "MFA_TOTP_VERIFIED",
+        "authentication",
+        &user_id,
+        false,
+        None
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(true, "Verification complete".to_string()))
+}
+
+/// Panic lock: purge the note store's master key, revoke all sessions and refuse PHI access
+/// until `unlock_all_data` succeeds. Works whether or not anyone is signed in.
+#[tauri::command]
+pub async fn lock_all_data(
+    app_handle: AppHandle,
+    config: State<'_, DataLockConfig>,
+) -> Result<ApiResponse<DataLockStatus>, String> {
+    if !config.enabled {
+        return Err("Emergency data lock is disabled for this deployment".to_string());
+    }
+    let status = data_lock::engage_data_lock(&app_handle, "command").await;
+    Ok(ApiResponse::success_with_message(status, "All data locked".to_string()))
+}
+
+/// Whether the emergency data lock is engaged
+#[tauri::command]
+pub async fn get_data_lock_status(
+    data_lock: State<'_, DataLockState>,
+) -> Result<ApiResponse<DataLockStatus>, String> {
+    Ok(ApiResponse::success(data_lock.status()))
+}
+
+/// Result of an unlock attempt
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DataUnlockOutcome {
+    pub unlocked: bool,
+    /// Set when the password was accepted; answer it with a code from the enrolled authenticator
+    pub mfa_challenge_id: Option<String>,
+}
+
+/// Lift the emergency data lock. Only the user who held the locked session, or an account that
+/// may manage sessions, can unlock. The first call checks the password and opens a challenge
+/// for the user's enrolled authenticator; the second call, with the challenge ID and code,
+/// unlocks. The storage passphrase must then be entered again, since the master key was purged.
+#[tauri::command]
+pub async fn unlock_all_data(
+    email: String,
+    password: String,
+    mfa_challenge_id: Option<String>,
+    mfa_code: Option<String>,
+    data_lock: State<'_, DataLockState>,
+    auth_service: State<'_, AuthServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    login_lockout: State<'_, LoginLockout>,
+    rbac: State<'_, RbacServiceState>,
+) -> Result<ApiResponse<DataUnlockOutcome>, String> {
+    if !data_lock.is_locked() {
+        return Err("Data is not locked".to_string());
+    }
+    let owner = data_lock.owner();
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    refuse_if_locked(&login_lockout, firebase, &email).await?;
+    let outcome = firebase.authenticate_user(&email, &password).await;
+    match &outcome {
+        Ok(_) => login_lockout.record_success(&email),
+        Err(e) => record_failed_login(&login_lockout, firebase, &email, e).await,
+    }
+    let auth_result = match outcome {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::warn!("Re-authentication for data unlock failed: {}", e);
+            firebase.audit_log(
+                "DATA_UNLOCK_FAILED",
+                "authentication",
+                owner.as_deref().unwrap_or("anonymous"),
+                false,
+                Some(serde_json::json!({ "reason": "re-authentication failed" }))
+            ).await.map_err(|e| e.to_string())?;
+            return Err("Re-authentication failed".to_string());
+        }
+    };
+
+    // The owner may unlock their own lock; anyone else, including when nobody was signed in,
+    // must be allowed to manage other users' sessions
+    let is_owner = owner.as_deref() == Some(auth_result.uid.as_str());
+    let user = firebase.get_document::<User>("users", &auth_result.uid).await
+        .map_err(|e| format!("Failed to get user data: {}", e))?;
+    let may_unlock = is_owner || user.as_ref().map_or(false, |user| {
+        rbac.0.role_grants(&session_role(user), &Permission::ManageUserSessions)
+    });
+    if !may_unlock {
+        firebase.audit_log(
+            "DATA_UNLOCK_DENIED",
+            "authentication",
+            &auth_result.uid,
+            false,
+            Some(serde_json::json!({ "reason": "not the lock owner or an administrator", "owner": owner }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err("Only the user who was signed in or an administrator can unlock".to_string());
+    }
+
+    let service_guard = auth_service.0.lock().await;
+    let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+    let (challenge_id, code) = match (mfa_challenge_id.as_deref(), mfa_code.as_deref()) {
+        (Some(id), Some(code)) => (id, code),
+        _ => {
+            let challenge_id = service
+                .start_mfa_challenge(&auth_result.uid, MfaChallengeType::Totp)
+                .await
+                .map_err(|e| e.to_string())?;
+            return Ok(ApiResponse::success_with_message(
+                DataUnlockOutcome { unlocked: false, mfa_challenge_id: Some(challenge_id) },
+                "MFA verification required to unlock".to_string(),
+            ));
+        }
+    };
+    if service.mfa_challenge_owner(challenge_id).as_deref() != Some(auth_result.uid.as_str()) {
+        return Err("MFA challenge does not belong to this user".to_string());
+    }
+    let verified = verify_challenge_throttled(service, &login_lockout, &auth_result.uid, challenge_id, code)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !verified {
+        firebase.audit_log(
+            "DATA_UNLOCK_FAILED",
+            "authentication",
+            &auth_result.uid,
+            false,
+            Some(serde_json::json!({ "reason": "MFA verification failed" }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err("MFA verification failed".to_string());
+    }
+
+    // Resume the owner's session under a new service session, as a login would
+    let session = match user.as_ref() {
+        Some(user) if is_owner => Some(
+            service.create_session(
+                &FirebaseUser::signed_in(&user.base.object_id, &user.base.email),
+                session_role(user),
+                None,
+                None,
+            ).await.map_err(|e| format!("Failed to create session: {}", e))?
+        ),
+        _ => None,
+    };
+    drop(service_guard);
+
+    data_lock.release();
+    let session_restored = session.is_some();
+    {
+        let mut auth = auth_state.write().await;
+        match (session, user.as_ref()) {
+            (Some(session), Some(user)) => {
+                auth.access_token = Some(session.access_token);
+                auth.refresh_token = Some(session.refresh_token);
+                auth.session_id = Some(session.session_id.to_string());
+                auth.session_expires_at = Some(session.expires_at);
+                auth.role = Some(session_role(user));
+                auth.unlock();
+                auth.mark_mfa_verified();
+            }
+            _ => {
+                // Nobody was signed in, or an administrator unlocked; a normal login follows
+                auth.clear();
+            }
+        }
+    }
+
+    firebase.audit_log(
+        "DATA_LOCK_RELEASED",
+        "authentication",
+        &auth_result.uid,
+        false,
+        Some(serde_json::json!({
+            "mfa_verified": true,
+            "session_restored": session_restored,
+            "owner": owner,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(
+        DataUnlockOutcome { unlocked: true, mfa_challenge_id: None },
+        "Data unlocked; enter the storage passphrase to reopen notes".to_string(),
+    ))
+}
+
 /// Store session for "Remember Me" functionality
 #[tauri::command]
 pub async fn store_session(
@@ -392,7 +1093,10 @@ pub async fn store_session(
     remember_me: bool,
     app_handle: AppHandle,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    data_lock: State<'_, DataLockState>,
+    binding_state: State<'_, SessionBindingState>,
 ) -> Result<ApiResponse<()>, String> {
+    data_lock.ensure_unlocked()?;
     if !remember_me {
         return Ok(ApiResponse::success_with_message((), "Session not stored - remember me disabled".to_string()));
     }
@@ -407,23 +1111,11 @@ pub async fn store_session(
 
     let db_path = app_data_dir.join("psypsy_sessions.db");
 
-    // Initialize database if it doesn't exist
-    let conn = Connection::open(&db_path)
-        .map_err(|e| format!("Failed to open session database: {}", e))?;
-
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS user_sessions (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            user_data TEXT NOT NULL,
-            session_token TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            expires_at TEXT NOT NULL,
-            device_info TEXT,
-            is_active BOOLEAN DEFAULT TRUE
-        )",
-        [],
-    ).map_err(|e| format!("Failed to create sessions table: {}", e))?;
+    // Create or upgrade the session schema before use
+    let conn = crate::storage::migrations::open_and_migrate(
+        &db_path,
+        crate::storage::migrations::SESSIONS_MIGRATIONS,
+    ).map_err(|e| format!("Failed to open session database: {}", e))?;
 
     // Clear any existing sessions for this user
     conn.execute(
@@ -446,11 +1138,17 @@ pub async fn store_session(
     let session_id = Uuid::new_v4().to_string();
     let user_data = serde_json::to_string(&user)
         .map_err(|e| format!("Failed to serialize user data: {}", e))?;
+    // A remembered session is only restored on the device it was stored from
+    let binding = binding_state
+        .current()
+        .map(|binding| serde_json::to_string(&binding))
+        .transpose()
+        .map_err(|e| format!("Failed to serialize session binding: {}", e))?;
 
     conn.execute(
         "INSERT INTO user_sessions
-         (id, user_id, user_data, session_token, created_at, expires_at, device_info, is_active)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+         (id, user_id, user_data, session_token, created_at, expires_at, device_info, is_active, binding)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         params![
             session_id,
             user.base.object_id,
@@ -459,7 +1157,8 @@ pub async fn store_session(
             stored_session.created_at.to_rfc3339(),
             stored_session.expires_at.to_rfc3339(),
             stored_session.device_info,
-            true
+            true,
+            binding
         ],
     ).map_err(|e| format!("Failed to store session: {}", e))?;
 
@@ -471,6 +1170,8 @@ pub async fn store_session(
 #[tauri::command]
 pub async fn get_stored_session(
     app_handle: AppHandle,
+    binding_state: State<'_, SessionBindingState>,
+    binding_config: State<'_, SessionBindingConfig>,
 ) -> Result<Option<StoredSession>, String> {
     let app_data_dir = app_handle
         .path()
@@ -484,11 +1185,13 @@ pub async fn get_stored_session(
         return Ok(None);
     }
 
-    let conn = Connection::open(&db_path)
-        .map_err(|e| format!("Failed to open session database: {}", e))?;
+    let conn = crate::storage::migrations::open_and_migrate(
+        &db_path,
+        crate::storage::migrations::SESSIONS_MIGRATIONS,
+    ).map_err(|e| format!("Failed to open session database: {}", e))?;
 
     let mut stmt = conn.prepare(
-        "SELECT user_data, session_token, created_at, expires_at, device_info
+        "SELECT user_data, session_token, created_at, expires_at, device_info, id, binding
          FROM user_sessions
          WHERE is_active = TRUE AND expires_at > ?1
          ORDER BY created_at DESC
@@ -501,7 +1204,7 @@ pub async fn get_stored_session(
         let user: User = serde_json::from_str(&user_data_str)
             .map_err(|_e| rusqlite::Error::InvalidColumnType(0, "user_data".to_string(), rusqlite::types::Type::Text))?;
 
-        Ok(StoredSession {
+        let session = StoredSession {
             user,
             session_token: row.get(1)?,
             created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
@@ -511,11 +1214,33 @@ pub async fn get_stored_session(
                 .map_err(|_| rusqlite::Error::InvalidColumnType(3, "expires_at".to_string(), rusqlite::types::Type::Text))?
                 .with_timezone(&Utc),
             device_info: row.get(4)?,
-        })
+        };
+        Ok((session, row.get::<_, String>(5)?, row.get::<_, Option<String>>(6)?))
     });
 
     match result {
-        Ok(session) => {
+        Ok((session, session_id, binding)) => {
+            let mut binding = binding.and_then(|json| serde_json::from_str::<SessionBinding>(&json).ok());
+            if let (Some(stored), true) = (binding.as_mut(), binding_config.mode != BindingMode::Off) {
+                let context = binding_state.current_context(&app_data_dir);
+                if let Some(BindingVerdict::Violation { reasons }) = context.map(|c| stored.evaluate(&binding_config, &c)) {
+                    let enforced = binding_config.mode == BindingMode::Enforce;
+                    tracing::error!(
+                        "IntrusionAttempt: stored session for user {} restored from another context: {}",
+                        session.user.base.object_id, reasons.join("; ")
+                    );
+                    session_binding::record_violation(&app_handle, &session.user.base.object_id, "get_stored_session", &reasons, enforced);
+                    if enforced {
+                        // The copied session is burned so it cannot be retried
+                        conn.execute("UPDATE user_sessions SET is_active = FALSE WHERE id = ?1", params![session_id])
+                            .map_err(|e| format!("Failed to deactivate stored session: {}", e))?;
+                        return Ok(None);
+                    }
+                }
+            }
+            if let Some(binding) = binding {
+                binding_state.bind(binding);
+            }
             tracing::info!("Retrieved stored session for user: {}", session.user.base.email);
             Ok(Some(session))
         },
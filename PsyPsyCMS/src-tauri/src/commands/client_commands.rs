@@ -1,51 +1,59 @@
 use tauri::State;
 use tokio::sync::RwLock;
+use std::collections::HashMap;
 use std::sync::Arc;
+use chrono::Utc;
 use uuid::Uuid;
 
 use crate::services::FirebaseService;
 use crate::models::{
-    Client, CreateClientRequest, UpdateClientRequest, ApiResponse, PaginatedResponse, SearchFilters, SortOptions
+    Appointment, Client, CreateClientRequest, UpdateClientRequest, ApiResponse, ListPage, ListParams
 };
+use crate::commands::medical_notes_commands::StorageState;
+use crate::commands::patient_access_commands::{authorize_client_access, log_client_accesses, sync_care_assignments, PatientDataRead};
+use crate::commands::security_commands::RbacServiceState;
+use crate::services::client_search;
+use crate::services::dashboard_cache::{DashboardCache, DashboardEntity};
+use crate::services::client_dedup::{self, ClientMergeRecord, DuplicateCandidate, DuplicateDetectionConfig, MergeSide};
 use crate::security::auth::AuthState;
-
-/// Get all clients with pagination and filters
+use crate::storage::search_index::{IndexedEntity, SearchIndexState};
+use crate::security::access_justification::JustificationPolicyState;
+use crate::security::data_scope::{collect_matching, collect_matching_where, resolve_caller_scope, DataScopePolicy};
+use crate::security::minimization::MinimizationPolicy;
+use crate::security::step_up::StepUpState;
+use crate::security::rbac::Permission;
+use crate::security::{DataClassification, HealthcareRole};
+
+/// One page of the caller's clients, sorted and filtered by `params`
 #[tauri::command]
 pub async fn get_clients(
-    page: Option<u32>,
-    limit: Option<u32>,
-    _filters: Option<SearchFilters>,
-    _sort: Option<SortOptions>,
+    params: Option<ListParams>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
-) -> Result<ApiResponse<PaginatedResponse<Client>>, String> {
+    scope_policy: State<'_, DataScopePolicy>,
+    justification_policy: State<'_, JustificationPolicyState>,
+) -> Result<ApiResponse<ListPage<Client>>, String> {
     // Check authentication
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
         return Err("Unauthorized".to_string());
     }
 
-    let page = page.unwrap_or(1);
-    let limit = limit.unwrap_or(10);
+    let params = params.unwrap_or_default();
 
     let firebase = firebase.lock().await;
 
-    // Query clients from Firestore
-    let clients: Vec<Client> = firebase.query_documents("clients", page, limit)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // In a real implementation, you would apply filters and sorting here
-    let total = clients.len() as u32; // This would be a separate count query
+    // Same scope as the dashboard figures: providers only list their own caseload
+    let scope = resolve_caller_scope(&scope_policy, &auth, &firebase).await?;
+    if !scope.allows_clinical() {
+        return Err("Insufficient permissions".to_string());
+    }
 
-    let response = PaginatedResponse {
-        data: clients,
-        page,
-        limit,
-        total,
-        has_next_page: total > (page * limit),
-        has_previous_page: page > 1,
-    };
+    // Sorting and the total count need every visible client, so scope is applied to each
+    // Firestore page before the requested page is cut
+    let clients: Vec<Client> = collect_matching(&firebase, "clients", |c| scope.includes_client(c)).await?;
+    let (clients, withheld) = withhold_justified_records(&justification_policy, clients);
+    let response = params.apply(clients)?;
 
     // Audit log
     firebase.audit_log(
@@ -53,31 +61,122 @@ pub async fn get_clients(
         "clients",
         auth.user_id.as_ref().unwrap(),
         true, // PHI accessed
-        Some(serde_json::json!({"page": page, "limit": limit}))
+        Some(serde_json::json!({
+            "limit": params.limit,
+            "offset": params.offset,
+            "sortBy": params.sort_by,
+            "returned": response.items.len(),
+            "withheld": withheld,
+            "scope": scope.label(),
+        }))
     ).await.map_err(|e| e.to_string())?;
+    log_client_accesses(
+        auth.user_id.as_ref().unwrap(),
+        auth.get_role(),
+        auth.session_id.as_deref(),
+        response.items.iter().map(|c| c.object_id.as_str()),
+        "LIST_CLIENTS",
+    ).await?;
 
     Ok(ApiResponse::success(response))
 }
 
-/// Get single client by ID
+/// Split off records the reason-for-access policy covers. A list or search cannot carry a
+/// reason per record, so those are only opened one at a time through `get_client`.
+fn withhold_justified_records(policy: &JustificationPolicyState, clients: Vec<Client>) -> (Vec<Client>, usize) {
+    let policy = policy.get();
+    let before = clients.len();
+    let visible: Vec<Client> = clients
+        .into_iter()
+        .filter(|c| !policy.requires_justification(&c.object_id, client_classification(c)))
+        .collect();
+    let withheld = before - visible.len();
+    (visible, withheld)
+}
+
+/// Classification of a client record for the reason-for-access policy: recorded conditions,
+/// medications or history make it medically sensitive
+fn client_classification(client: &Client) -> DataClassification {
+    let has_medical_detail = client.medical_info.as_ref().map_or(false, |info| {
+        !info.conditions.is_empty()
+            || !info.medications.is_empty()
+            || info.medical_history.as_deref().map_or(false, |history| !history.trim().is_empty())
+    });
+    if has_medical_detail {
+        DataClassification::MedicalSensitive
+    } else {
+        DataClassification::Phi
+    }
+}
+
+/// Shape a client record down to the fields the caller's role may receive
+fn shape_client_response(
+    policy: &MinimizationPolicy,
+    client: &Client,
+    role: Option<&HealthcareRole>,
+    fields: Option<&[String]>,
+) -> Result<serde_json::Value, String> {
+    policy.shape("client", client, role, fields).map_err(|e| e.to_string())
+}
+
+/// Get single client by ID, minimized to the caller's role and requested fields.
+/// Records covered by the reason-for-access policy require a `justification`; callers not
+/// assigned to the client need a break-glass grant.
 #[tauri::command]
 pub async fn get_client(
     id: String,
+    fields: Option<Vec<String>>,
+    justification: Option<String>,
+    break_glass_grant_id: Option<Uuid>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
-) -> Result<ApiResponse<Client>, String> {
+    minimization: State<'_, MinimizationPolicy>,
+    justification_policy: State<'_, JustificationPolicyState>,
+    step_up: State<'_, StepUpState>,
+    rbac: State<'_, RbacServiceState>,
+) -> Result<ApiResponse<serde_json::Value>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
         return Err("Unauthorized".to_string());
     }
+    step_up.check_patient(&auth, &id, &justification_policy.get().flagged_patients, Utc::now())?;
 
     let firebase = firebase.lock().await;
-
-    let client: Option<Client> = firebase.get_document("clients", &id)
+    let client: Client = firebase.get_document("clients", &id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+        .ok_or("Client not found")?;
 
-    let client = client.ok_or("Client not found")?;
+    // Checked before anything from the record is returned
+    let justification = match justification_policy.check(&id, client_classification(&client), justification.as_deref()) {
+        Ok(justification) => justification,
+        Err(e) => {
+            firebase.audit_log(
+                "VIEW_CLIENT_DENIED",
+                "client",
+                auth.user_id.as_ref().unwrap(),
+                false, // No PHI returned
+                Some(serde_json::json!({
+                    "client_id": id,
+                    "reason": "missing_justification",
+                }))
+            ).await.map_err(|e| e.to_string())?;
+            return Err(e.to_string());
+        }
+    };
+    authorize_client_access(&auth, &rbac, &firebase, &client, PatientDataRead {
+        patient_id: &id,
+        permission: Permission::ViewPHI,
+        action: "VIEW_CLIENT",
+        justification: justification.as_deref(),
+        break_glass_grant_id,
+    }).await?;
+
+    // Enforce minimum-necessary server-side; the frontend cannot widen the field set
+    let shaped = shape_client_response(&minimization, &client, auth.get_role(), fields.as_deref())?;
+    let returned_fields: Vec<&String> = shaped.as_object()
+        .map(|fields| fields.keys().collect())
+        .unwrap_or_default();
 
     // Audit log
     firebase.audit_log(
@@ -85,10 +184,15 @@ pub async fn get_client(
         "client",
         auth.user_id.as_ref().unwrap(),
         true, // PHI accessed
-        Some(serde_json::json!({"client_id": id}))
+        Some(serde_json::json!({
+            "client_id": id,
+            "role": auth.get_role(),
+            "returned_fields": returned_fields,
+            "justification": justification,
+        }))
     ).await.map_err(|e| e.to_string())?;
 
-    Ok(ApiResponse::success(client))
+    Ok(ApiResponse::success(shaped))
 }
 
 /// Create new client
@@ -97,6 +201,8 @@ pub async fn create_client(
     request: CreateClientRequest,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Client>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -123,6 +229,14 @@ pub async fn create_client(
         .await
         .map_err(|e| e.to_string())?;
 
+    // A record that cannot be indexed would be unsearchable; undo it instead
+    if let Err(e) = search_index.index_client(&client) {
+        let _ = firebase.delete_document("clients", &client_id).await;
+        return Err(e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Client));
+
     // Audit log
     firebase.audit_log(
         "CREATE_CLIENT",
@@ -145,6 +259,8 @@ pub async fn update_client(
     request: UpdateClientRequest,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Client>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -164,6 +280,7 @@ pub async fn update_client(
         .ok_or("Client not found")?;
 
     // Update client data
+    let previous = client.clone();
     client.update_from_request(request);
 
     // Save to Firestore
@@ -171,6 +288,14 @@ pub async fn update_client(
         .await
         .map_err(|e| e.to_string())?;
 
+    // Searchability changes with the record or not at all
+    if let Err(e) = search_index.index_client(&client) {
+        let _ = firebase.update_document("clients", &id, &previous).await;
+        return Err(e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Client));
+
     // Audit log
     firebase.audit_log(
         "UPDATE_CLIENT",
@@ -192,6 +317,8 @@ pub async fn delete_client(
     id: String,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<()>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -219,6 +346,12 @@ pub async fn delete_client(
     firebase.delete_document("clients", &id)
         .await
         .map_err(|e| e.to_string())?;
+    if let Err(e) = search_index.remove(IndexedEntity::Client, &id) {
+        // A stale entry only points at a record that no longer loads
+        log::warn!("Deleted client {} is still in the search index: {}", id, e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Client));
 
     // Audit log
     firebase.audit_log(
@@ -235,14 +368,18 @@ pub async fn delete_client(
     ))
 }
 
-/// Search clients by query
+/// Clients in the caller's scope whose name, email or phone match `query`, ignoring accents and
+/// case; best matches first, minimized to the caller's role
 #[tauri::command]
 pub async fn search_clients(
     query: String,
     limit: Option<u32>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
-) -> Result<ApiResponse<Vec<Client>>, String> {
+    scope_policy: State<'_, DataScopePolicy>,
+    minimization: State<'_, MinimizationPolicy>,
+    justification_policy: State<'_, JustificationPolicyState>,
+) -> Result<ApiResponse<Vec<serde_json::Value>>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
         return Err("Unauthorized".to_string());
@@ -251,22 +388,45 @@ pub async fn search_clients(
     let limit = limit.unwrap_or(10);
     let firebase = firebase.lock().await;
 
-    // TODO: Implement actual search functionality with Firestore
-    // For now, return a basic query
-    let clients: Vec<Client> = firebase.query_documents("clients", 1, limit)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Same scope as `get_clients`: a search must not reach clients the list would not show
+    let scope = resolve_caller_scope(&scope_policy, &auth, &firebase).await?;
+    if !scope.allows_clinical() {
+        return Err("Insufficient permissions".to_string());
+    }
 
-    // Audit log
+    // Firestore cannot match accent-insensitively, so rank in memory
+    let clients: Vec<Client> = collect_matching(&firebase, "clients", |c| scope.includes_client(c)).await?;
+    let (clients, withheld) = withhold_justified_records(&justification_policy, clients);
+    let clients = client_search::search_clients(clients, &query, limit as usize);
+    let returned_ids: Vec<String> = clients.iter().map(|c| c.object_id.clone()).collect();
+    let shaped = clients
+        .iter()
+        .map(|client| shape_client_response(&minimization, client, auth.get_role(), None))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The query is usually a patient's name, so only its length is logged
     firebase.audit_log(
         "SEARCH_CLIENTS",
         "clients",
         auth.user_id.as_ref().unwrap(),
         true, // PHI potentially accessed
-        Some(serde_json::json!({"query": query, "limit": limit}))
+        Some(serde_json::json!({
+            "query_length": query.chars().count(),
+            "limit": limit,
+            "returned": shaped.len(),
+            "withheld": withheld,
+            "scope": scope.label(),
+        }))
     ).await.map_err(|e| e.to_string())?;
+    log_client_accesses(
+        auth.user_id.as_ref().unwrap(),
+        auth.get_role(),
+        auth.session_id.as_deref(),
+        returned_ids.iter().map(String::as_str),
+        "SEARCH_CLIENTS",
+    ).await?;
 
-    Ok(ApiResponse::success(clients))
+    Ok(ApiResponse::success(shaped))
 }
 
 /// Get client appointments
@@ -303,8 +463,10 @@ pub async fn get_client_appointments(
 pub async fn assign_professional_to_client(
     client_id: String,
     professional_id: String,
+    rbac: State<'_, RbacServiceState>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<()>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -330,6 +492,9 @@ pub async fn assign_professional_to_client(
     firebase.update_document("clients", &client_id, &client)
         .await
         .map_err(|e| e.to_string())?;
+    sync_care_assignments(&rbac, &firebase, &client).await;
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Client));
 
     // Audit log
     firebase.audit_log(
@@ -394,8 +559,10 @@ pub async fn get_client_stats(
 pub async fn unassign_professional_from_client(
     client_id: String,
     professional_id: String,
+    rbac: State<'_, RbacServiceState>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<()>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -421,6 +588,9 @@ pub async fn unassign_professional_from_client(
     firebase.update_document("clients", &client_id, &client)
         .await
         .map_err(|e| e.to_string())?;
+    sync_care_assignments(&rbac, &firebase, &client).await;
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Client));
 
     // Audit log
     firebase.audit_log(
@@ -448,6 +618,7 @@ pub async fn increment_client_appointments(
     appointment_type: String, // "total", "completed", or "cancelled"
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<()>, String> {
     let auth = auth_state.read().await;
     if !auth.is_authenticated {
@@ -481,6 +652,8 @@ pub async fn increment_client_appointments(
         .await
         .map_err(|e| e.to_string())?;
 
+    dashboard_cache.invalidate(Some(DashboardEntity::Client));
+
     // Audit log
     firebase.audit_log(
         "INCREMENT_APPOINTMENT_COUNT",
@@ -564,6 +737,336 @@ pub async fn get_client_display_name(
     Ok(ApiResponse::success(display_name))
 }
 
+/// Client pairs that likely describe the same person. With `client_id`, only matches for
+/// that client are returned.
+#[tauri::command]
+pub async fn find_duplicate_clients(
+    client_id: Option<String>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    config: State<'_, DuplicateDetectionConfig>,
+    scope_policy: State<'_, DataScopePolicy>,
+) -> Result<ApiResponse<Vec<DuplicateCandidate>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("view_phi") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+
+    let firebase = firebase.lock().await;
+    // Same scope as `get_clients`: only clients the caller could list are compared
+    let scope = resolve_caller_scope(&scope_policy, &auth, &firebase).await?;
+    drop(auth);
+    if !scope.allows_clinical() {
+        return Err("Insufficient permissions".to_string());
+    }
+    let clients: Vec<Client> = collect_matching(&firebase, "clients", |c| scope.includes_client(c)).await?;
+    let candidates = client_dedup::find_duplicates(&clients, client_id.as_deref(), &config);
+
+    firebase.audit_log(
+        "SCAN_DUPLICATE_CLIENTS",
+        "clients",
+        &user_id,
+        true, // Identity fields compared
+        Some(serde_json::json!({
+            "client_id": client_id,
+            "scanned": clients.len(),
+            "candidates": candidates.len(),
+            "scope": scope.label(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(candidates))
+}
+
+/// Move appointments from one client to another; returns the IDs moved
+async fn move_appointments(
+    firebase: &FirebaseService,
+    from_client_id: &str,
+    to_client_id: &str,
+    only: Option<&[String]>,
+) -> Result<Vec<String>, String> {
+    let from_client = serde_json::Value::String(from_client_id.to_string());
+    let appointments: Vec<Appointment> = collect_matching_where(firebase, "appointments", "clientPtr", &from_client, |a: &Appointment| {
+        a.client_ptr == from_client_id && only.map_or(true, |ids| ids.contains(&a.object_id))
+    })
+    .await?;
+    let mut moved = Vec::new();
+    for mut appointment in appointments {
+        appointment.client_ptr = to_client_id.to_string();
+        appointment.updated_at = crate::models::common::firestore_now();
+        if let Err(e) = firebase.update_document("appointments", &appointment.object_id, &appointment).await {
+            // Put back what already moved so the two clients stay consistent
+            for id in &moved {
+                if let Ok(Some(mut reverted)) = firebase.get_document::<Appointment>("appointments", id).await {
+                    reverted.client_ptr = from_client_id.to_string();
+                    let _ = firebase.update_document("appointments", id, &reverted).await;
+                }
+            }
+            return Err(format!("Failed to move appointment {}: {}", appointment.object_id, e));
+        }
+        moved.push(appointment.object_id);
+    }
+    Ok(moved)
+}
+
+/// Merge `merged_id` into `survivor_id`. Appointments, notes and professional assignments
+/// move to the survivor; the merged record stays as an inactive tombstone. Every conflicting
+/// field needs an entry in `resolutions`, otherwise the conflicts are returned unmerged.
+/// The merge can be undone with `unmerge_clients` within the configured window.
+#[tauri::command]
+pub async fn merge_clients(
+    survivor_id: String,
+    merged_id: String,
+    resolutions: Option<HashMap<String, MergeSide>>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    storage_state: State<'_, StorageState>,
+    config: State<'_, DuplicateDetectionConfig>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
+) -> Result<ApiResponse<ClientMergeRecord>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("update_client") || !auth.has_permission("delete_client") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    if survivor_id == merged_id {
+        return Err("A client cannot be merged into itself".to_string());
+    }
+    let resolutions = resolutions.unwrap_or_default();
+
+    let firebase = firebase.lock().await;
+    let survivor: Client = firebase.get_document("clients", &survivor_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Surviving client not found")?;
+    let merged: Client = firebase.get_document("clients", &merged_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Client to merge not found")?;
+    if survivor.merged_into.is_some() || merged.merged_into.is_some() {
+        return Err("One of the clients has already been merged".to_string());
+    }
+
+    let updated_survivor = match client_dedup::merged_survivor(&survivor, &merged, &resolutions) {
+        Ok(updated) => updated,
+        Err(unresolved) => {
+            // Each conflicting field lists the survivor's value then the merged client's
+            let conflicts = unresolved.conflicts.iter()
+                .map(|c| (c.field.clone(), vec![c.survivor_value.to_string(), c.merged_value.to_string()]))
+                .collect();
+            let mut response = ApiResponse::validation_error(conflicts);
+            response.message = Some(unresolved.to_string());
+            return Ok(response);
+        }
+    };
+
+    // Notes are re-bound to the survivor first: it is the only step with a local transaction
+    let storage_guard = storage_state.lock().await;
+    let storage = storage_guard.as_ref().ok_or("Encrypted note storage must be unlocked to merge clients")?;
+    let moved_note_ids = storage.reassign_patient_notes(&merged_id, &survivor_id, None, &user_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let merged_at = Utc::now();
+    let mut record = ClientMergeRecord {
+        merge_id: Uuid::new_v4(),
+        survivor_id: survivor_id.clone(),
+        merged_id: merged_id.clone(),
+        resolutions,
+        survivor_changes: client_dedup::field_changes(&survivor, &updated_survivor),
+        merged_status_before: merged.status.clone(),
+        merged_was_active: merged.profile.is_active,
+        moved_appointment_ids: Vec::new(),
+        moved_note_ids,
+        merged_by: user_id.clone(),
+        merged_at,
+        undo_until: ClientMergeRecord::undo_deadline(merged_at, &config),
+        reverted_by: None,
+        reverted_at: None,
+    };
+
+    let consolidated: Result<(), String> = async {
+        record.moved_appointment_ids = move_appointments(&firebase, &merged_id, &survivor_id, None).await?;
+        firebase.update_document("clients", &survivor_id, &updated_survivor).await.map_err(|e| e.to_string())?;
+        firebase.update_document("clients", &merged_id, &client_dedup::tombstone(&merged, &survivor_id))
+            .await
+            .map_err(|e| e.to_string())?;
+        firebase.create_document(client_dedup::MERGE_COLLECTION, &record.merge_id.to_string(), &record)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }.await;
+
+    if let Err(e) = consolidated {
+        // Undo the partial merge so neither record is left half-moved
+        if let Err(revert) = storage.reassign_patient_notes(&survivor_id, &merged_id, Some(&record.moved_note_ids), &user_id).await {
+            log::error!("Failed to return notes after aborted merge {}: {}", record.merge_id, revert);
+        }
+        if !record.moved_appointment_ids.is_empty() {
+            if let Err(revert) = move_appointments(&firebase, &survivor_id, &merged_id, Some(&record.moved_appointment_ids)).await {
+                log::error!("Failed to return appointments after aborted merge {}: {}", record.merge_id, revert);
+            }
+        }
+        let _ = firebase.update_document("clients", &survivor_id, &survivor).await;
+        let _ = firebase.update_document("clients", &merged_id, &merged).await;
+        firebase.audit_log(
+            "MERGE_CLIENTS_FAILED",
+            "client",
+            &user_id,
+            true,
+            Some(serde_json::json!({
+                "merge_id": record.merge_id,
+                "survivor_id": survivor_id,
+                "merged_id": merged_id,
+                "error": e,
+            }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err(e);
+    }
+
+    for client in [&updated_survivor, &client_dedup::tombstone(&merged, &survivor_id)] {
+        if let Err(e) = search_index.index_client(client) {
+            log::error!("Search index not updated for client {} after merge {}: {}", client.object_id, record.merge_id, e);
+        }
+    }
+
+    // Both client and appointment records changed hands
+    dashboard_cache.invalidate(None);
+
+    firebase.audit_log(
+        "MERGE_CLIENTS",
+        "client",
+        &user_id,
+        true, // PHI moved between records
+        Some(serde_json::json!({
+            "merge_id": record.merge_id,
+            "survivor_id": survivor_id,
+            "merged_id": merged_id,
+            "resolutions": record.resolutions,
+            "changed_fields": record.survivor_changes.iter().map(|c| &c.field).collect::<Vec<_>>(),
+            "moved_appointments": record.moved_appointment_ids,
+            "moved_notes": record.moved_note_ids,
+            "undo_until": record.undo_until,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(
+        record,
+        "Clients merged successfully".to_string()
+    ))
+}
+
+/// Result of undoing a merge
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmergeOutcome {
+    pub merge: ClientMergeRecord,
+    /// Survivor fields edited after the merge and therefore left as they are
+    pub fields_needing_review: Vec<String>,
+}
+
+/// Undo a merge within its window: the tombstone becomes a live client again and the
+/// appointments and notes it contributed move back to it
+#[tauri::command]
+pub async fn unmerge_clients(
+    merge_id: Uuid,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    storage_state: State<'_, StorageState>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
+) -> Result<ApiResponse<UnmergeOutcome>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("update_client") || !auth.has_permission("delete_client") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let firebase = firebase.lock().await;
+    let mut record: ClientMergeRecord = firebase.get_document(client_dedup::MERGE_COLLECTION, &merge_id.to_string())
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Merge record not found")?;
+    if let Some(blocker) = record.undo_blocker(Utc::now()) {
+        return Err(blocker);
+    }
+
+    let survivor: Client = firebase.get_document("clients", &record.survivor_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Surviving client not found")?;
+    let mut restored: Client = firebase.get_document("clients", &record.merged_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Merged client tombstone not found")?;
+
+    let storage_guard = storage_state.lock().await;
+    let storage = storage_guard.as_ref().ok_or("Encrypted note storage must be unlocked to undo a merge")?;
+    storage.reassign_patient_notes(&record.survivor_id, &record.merged_id, Some(&record.moved_note_ids), &user_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    move_appointments(&firebase, &record.survivor_id, &record.merged_id, Some(&record.moved_appointment_ids)).await?;
+
+    let (reverted_survivor, fields_needing_review) = client_dedup::revert_survivor(&survivor, &record.survivor_changes);
+    firebase.update_document("clients", &record.survivor_id, &reverted_survivor)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    restored.merged_into = None;
+    restored.status = record.merged_status_before.clone();
+    restored.profile.is_active = record.merged_was_active;
+    restored.updated_at = crate::models::common::firestore_now();
+    firebase.update_document("clients", &record.merged_id, &restored)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    record.reverted_by = Some(user_id.clone());
+    record.reverted_at = Some(Utc::now());
+    firebase.update_document(client_dedup::MERGE_COLLECTION, &merge_id.to_string(), &record)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for client in [&reverted_survivor, &restored] {
+        if let Err(e) = search_index.index_client(client) {
+            log::error!("Search index not updated for client {} after unmerge {}: {}", client.object_id, merge_id, e);
+        }
+    }
+
+    // Both client and appointment records changed hands
+    dashboard_cache.invalidate(None);
+
+    firebase.audit_log(
+        "UNMERGE_CLIENTS",
+        "client",
+        &user_id,
+        true, // PHI moved between records
+        Some(serde_json::json!({
+            "merge_id": merge_id,
+            "survivor_id": record.survivor_id,
+            "restored_id": record.merged_id,
+            "returned_appointments": record.moved_appointment_ids,
+            "returned_notes": record.moved_note_ids,
+            "fields_needing_review": fields_needing_review,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(UnmergeOutcome { merge: record, fields_needing_review }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -578,6 +1081,7 @@ mod tests {
             email: "john@example.com".to_string(),
             phone: "1234567890".to_string(),
             date_of_birth: Some("1990-01-01".to_string()),
+            ramq_number: None,
             address: AddressObject {
                 street: "123 Main St".to_string(),
                 city: "Anytown".to_string(),
@@ -604,4 +1108,106 @@ mod tests {
         assert!(request.search_radius.is_some());
         assert_eq!(request.search_radius.unwrap(), 30);
     }
+
+    #[test]
+    fn test_billing_staff_cannot_retrieve_diagnosis() {
+        let request = CreateClientRequest {
+            user_id: "user123".to_string(),
+            first_name: "John".to_string(),
+            last_name: "Doe".to_string(),
+            email: "john@example.com".to_string(),
+            phone: "1234567890".to_string(),
+            date_of_birth: Some("1990-01-01".to_string()),
+            ramq_number: None,
+            address: AddressObject {
+                street: "123 Main St".to_string(),
+                city: "Anytown".to_string(),
+                state: "CA".to_string(),
+                zip_code: "12345".to_string(),
+                country: "USA".to_string(),
+            },
+            spoken_languages: vec![1],
+            search_radius: None,
+            preferences: None,
+            emergency_contacts: None,
+        };
+        let mut client = Client::from_request(request, "client123".to_string());
+        client.medical_info = Some(crate::models::client::MedicalInfo {
+            conditions: vec!["Generalized anxiety disorder".to_string()],
+            medications: vec![],
+            allergies: vec![],
+            insurance_info: Some(crate::models::client::InsuranceInfo {
+                provider: "RAMQ".to_string(),
+                policy_number: "ABCD12345678".to_string(),
+                group_number: None,
+                effective_date: "2024-01-01".to_string(),
+                expiry_date: None,
+                copay: None,
+                deductible: None,
+            }),
+            medical_history: Some("History of panic attacks".to_string()),
+            physician_contact: None,
+        });
+
+        let policy = MinimizationPolicy::default();
+
+        // Explicitly requesting the diagnosis must not widen the billing field set
+        let requested = vec!["medicalInfo.conditions".to_string(), "medicalInfo".to_string()];
+        for fields in [None, Some(requested.as_slice())] {
+            let shaped = shape_client_response(&policy, &client, Some(&HealthcareRole::BillingStaff), fields).unwrap();
+            let medical = &shaped["medicalInfo"];
+            assert!(medical.get("conditions").is_none());
+            assert!(medical.get("medicalHistory").is_none());
+            assert_eq!(medical["insuranceInfo"]["provider"], "RAMQ");
+        }
+
+        // Providers still receive the clinical record
+        let shaped = shape_client_response(&policy, &client, Some(&HealthcareRole::HealthcareProvider), None).unwrap();
+        assert_eq!(shaped["medicalInfo"]["conditions"][0], "Generalized anxiety disorder");
+    }
+
+    #[test]
+    fn test_lists_withhold_records_that_need_a_reason_for_access() {
+        let request = CreateClientRequest {
+            user_id: "user123".to_string(),
+            first_name: "Jane".to_string(),
+            last_name: "Roe".to_string(),
+            email: "jane@example.com".to_string(),
+            phone: "1234567890".to_string(),
+            date_of_birth: None,
+            ramq_number: None,
+            address: AddressObject {
+                street: "1 Rue Principale".to_string(),
+                city: "Montréal".to_string(),
+                state: "QC".to_string(),
+                zip_code: "H2X 1Y4".to_string(),
+                country: "Canada".to_string(),
+            },
+            spoken_languages: vec![1],
+            search_radius: None,
+            preferences: None,
+            emergency_contacts: None,
+        };
+        let plain = Client::from_request(request.clone(), "plain".to_string());
+        let flagged = Client::from_request(request.clone(), "vip-1".to_string());
+        let mut sensitive = Client::from_request(request, "sensitive".to_string());
+        sensitive.medical_info = Some(crate::models::client::MedicalInfo {
+            conditions: vec!["Major depressive disorder".to_string()],
+            medications: vec![],
+            allergies: vec![],
+            insurance_info: None,
+            medical_history: None,
+            physician_contact: None,
+        });
+        assert_eq!(client_classification(&sensitive), DataClassification::MedicalSensitive);
+
+        let mut policy = crate::security::access_justification::JustificationPolicy::default();
+        policy.flagged_patients.insert("vip-1".to_string());
+        policy.classifications.insert(DataClassification::MedicalSensitive);
+        let state = JustificationPolicyState::new(policy);
+
+        let (visible, withheld) = withhold_justified_records(&state, vec![plain, flagged, sensitive]);
+        assert_eq!(visible.iter().map(|c| c.object_id.as_str()).collect::<Vec<_>>(), ["plain"]);
+        assert_eq!(withheld, 2);
+    }
 }
\ No newline at end of file
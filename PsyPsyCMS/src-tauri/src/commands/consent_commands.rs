@@ -0,0 +1,136 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::compliance::consent_receipts::{
+    verify_receipt, ConsentGrant, ConsentReceiptConfig, ConsentReceiptState, ConsentRecord, ReceiptVerification,
+    SignedConsentReceipt,
+};
+use crate::models::ApiResponse;
+use crate::security::auth::AuthState;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+
+/// Record a subject's consent and return its signed receipt
+#[tauri::command]
+pub async fn record_consent(
+    grant: ConsentGrant,
+    receipts: State<'_, ConsentReceiptState>,
+    config: State<'_, ConsentReceiptConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<SignedConsentReceipt>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let receipt = receipts.grant(&config, grant).map_err(|e| e.to_string())?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "CONSENT_RECORDED",
+        "consent",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "event_type": "ConsentRecorded",
+            "consent_id": receipt.receipt.consent_id,
+            "receipt_id": receipt.receipt.consent_receipt_id,
+            "subject_id": receipt.receipt.pii_principal_id,
+            "category": receipt.receipt.category,
+            "expires_at": receipt.receipt.expires_at,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(receipt))
+}
+
+/// Withdraw a consent and return the signed withdrawal receipt
+#[tauri::command]
+pub async fn withdraw_consent(
+    consent_id: Uuid,
+    method: Option<String>,
+    receipts: State<'_, ConsentReceiptState>,
+    config: State<'_, ConsentReceiptConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<SignedConsentReceipt>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let method = method.unwrap_or_else(|| "in-app request".to_string());
+    let receipt = receipts.withdraw(&config, consent_id, &method).map_err(|e| e.to_string())?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "CONSENT_WITHDRAWN",
+        "consent",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "event_type": "ConsentWithdrawn",
+            "consent_id": consent_id,
+            "receipt_id": receipt.receipt.consent_receipt_id,
+            "subject_id": receipt.receipt.pii_principal_id,
+            "method": method,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(receipt))
+}
+
+/// Every consent held for a subject with its receipts, for export to the subject
+#[tauri::command]
+pub async fn export_consent_receipts(
+    subject_id: String,
+    receipts: State<'_, ConsentReceiptState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<ConsentRecord>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if auth.user_id.as_deref() != Some(subject_id.as_str()) && !auth.has_permission("export_data") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let records = receipts.records_for(&subject_id);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "CONSENT_RECEIPTS_EXPORTED",
+        "consent",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "subject_id": subject_id,
+            "consent_count": records.len(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(records))
+}
+
+/// Check receipts against this installation's current and trusted signing keys
+#[tauri::command]
+pub async fn verify_consent_receipts(
+    receipts: Vec<SignedConsentReceipt>,
+    state: State<'_, ConsentReceiptState>,
+    config: State<'_, ConsentReceiptConfig>,
+) -> Result<ApiResponse<Vec<ReceiptVerification>>, String> {
+    let trusted = state.trusted_keys(&config);
+    Ok(ApiResponse::success(receipts.iter().map(|r| verify_receipt(r, &trusted)).collect()))
+}
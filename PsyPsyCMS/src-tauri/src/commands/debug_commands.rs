@@ -1,13 +1,21 @@
 // Debug Commands for PsyPsy CMS
-// Provides DevTools state management and initialization
+// Provides DevTools state management, initialization and command latency profiling
 
 use std::sync::Arc;
-use tauri::State;
+use std::time::Duration;
+use tauri::{Manager, State};
+
+use crate::security::auth::AuthState;
+use crate::security::HealthcareRole;
+use crate::services::command_profiler::{profiler, profiler_injection_script, PerformanceReport, SampleKind};
+use crate::services::firebase_service_simple::FirebaseServiceState;
 
 #[derive(Debug, Default)]
 pub struct DevToolsState {
     pub connected: bool,
     pub port: u16,
+    /// PHI spans replaced in webview logs before they were broadcast
+    pub redactions: u64,
 }
 
 /// Initialize DevTools integration
@@ -44,4 +52,111 @@ pub async fn initialize_devtools(
             }
         }
     }
-}
\ No newline at end of file
+}
+/// Latency sample reported by the webview profiler
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandLatencySample {
+    pub command: String,
+    pub duration_ms: f64,
+    pub ok: bool,
+}
+
+fn is_profiling_admin(auth: &AuthState) -> bool {
+    matches!(
+        auth.get_role(),
+        Some(HealthcareRole::SuperAdmin) | Some(HealthcareRole::Administrator)
+    )
+}
+
+/// Turn command latency profiling on or off, in the backend and in every open window
+#[tauri::command]
+pub async fn set_profiling_enabled(
+    enabled: bool,
+    app: tauri::AppHandle,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<PerformanceReport, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_profiling_admin(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    profiler().set_enabled(enabled);
+    let script = profiler_injection_script(enabled);
+    for (label, window) in app.webview_windows() {
+        if let Err(e) = window.eval(&script) {
+            log::warn!("Failed to update command profiler in window {}: {}", label, e);
+        }
+    }
+
+    let firebase_guard = firebase.0.lock().await;
+    if let Some(firebase) = firebase_guard.as_ref() {
+        firebase.audit_log(
+            if enabled { "PROFILING_ENABLED" } else { "PROFILING_DISABLED" },
+            "performance_profiler",
+            &user_id,
+            false,
+            None,
+        ).await.map_err(|e| e.to_string())?;
+    }
+
+    Ok(profiler().report())
+}
+
+/// Percentile latencies per command and backend stage since profiling was last reset
+#[tauri::command]
+pub async fn get_performance_report(
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<PerformanceReport, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_profiling_admin(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    Ok(profiler().report())
+}
+
+/// Discard collected latency samples
+#[tauri::command]
+pub async fn reset_performance_report(
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<(), String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_profiling_admin(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    profiler().reset();
+    Ok(())
+}
+
+/// Batch of end-to-end timings from the webview; dropped unless profiling is on
+#[tauri::command]
+pub async fn record_command_latencies(samples: Vec<CommandLatencySample>) -> Result<(), String> {
+    let profiler = profiler();
+    if !profiler.is_enabled() {
+        return Ok(());
+    }
+    for sample in samples.iter().take(500) {
+        if !sample.duration_ms.is_finite() || sample.duration_ms < 0.0 {
+            continue;
+        }
+        profiler.record(
+            SampleKind::Command,
+            &sample.command,
+            Duration::from_secs_f64(sample.duration_ms / 1000.0),
+            sample.ok,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+
+use crate::models::ApiResponse;
+use crate::security::auth::AuthState;
+use crate::services::event_log::{EventLogState, EventReplay};
+
+/// Replay domain changes recorded after `since_seq` so a reloaded webview can catch up
+#[tauri::command]
+pub async fn get_events_since(
+    since_seq: u64,
+    event_log: State<'_, EventLogState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<EventReplay>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let role = auth.get_role().cloned().ok_or("No role in auth state")?;
+    drop(auth);
+
+    let log = event_log.0.lock().map_err(|e| format!("Event log unavailable: {}", e))?;
+    Ok(ApiResponse::success(log.events_since(since_seq, &role)))
+}
@@ -0,0 +1,258 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::commands::patient_access_commands::log_client_accesses;
+use crate::compliance::consent_receipts::{
+    verify_receipt, ConsentReceiptConfig, ConsentReceiptState, ReceiptVerification, SignedConsentReceipt,
+};
+use crate::models::{ApiResponse, Client};
+use crate::security::auth::AuthState;
+use crate::services::export_approval::{
+    ExportApprovalConfig, ExportApprovalState, ExportKind, ExportRequest, ExportRequestStatus, ExportScope,
+};
+use crate::services::firebase_service_simple::{FirebaseService, FirebaseServiceState};
+
+/// Upper bound on clients pulled for an all-clients export
+const MAX_EXPORT_CLIENTS: u32 = 10_000;
+
+/// Generated export, tied to the request that authorized it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientDataExport {
+    pub request_id: Uuid,
+    pub generated_at: DateTime<Utc>,
+    pub clients: Vec<Client>,
+    /// DSAR only: every consent receipt issued to the exported clients
+    #[serde(default)]
+    pub consent_receipts: Vec<SignedConsentReceipt>,
+    /// Signature check of each receipt at export time
+    #[serde(default)]
+    pub consent_receipt_verification: Vec<ReceiptVerification>,
+}
+
+/// Drop lapsed requests and record each expiry
+async fn purge_expired_requests(approvals: &ExportApprovalState, firebase: &FirebaseService) -> Result<(), String> {
+    for request in approvals.purge_expired(Utc::now()) {
+        firebase.audit_log(
+            "EXPORT_REQUEST_EXPIRED",
+            "export_request",
+            &request.requested_by,
+            false,
+            Some(serde_json::json!({
+                "request_id": request.id,
+                "status": request.status,
+                "expired_at": request.expires_at,
+            }))
+        ).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// File an export request; bulk and DSAR exports wait for a privacy officer
+#[tauri::command]
+pub async fn request_data_export(
+    scope: ExportScope,
+    justification: String,
+    approvals: State<'_, ExportApprovalState>,
+    config: State<'_, ExportApprovalConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<ExportRequest>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("export_data") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    purge_expired_requests(&approvals, firebase).await?;
+
+    let request = approvals.submit(scope, &user_id, &justification, &config).map_err(|e| e.to_string())?;
+
+    firebase.audit_log(
+        "EXPORT_REQUESTED",
+        "export_request",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "request_id": request.id,
+            "scope": request.scope,
+            "justification": request.justification,
+            "auto_approved": request.auto_approved,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    let message = if request.auto_approved {
+        "Single-patient export approved automatically"
+    } else {
+        "Export request submitted for privacy officer approval"
+    };
+    Ok(ApiResponse::success_with_message(request, message.to_string()))
+}
+
+/// Export requests, optionally filtered by status
+#[tauri::command]
+pub async fn list_export_requests(
+    status: Option<ExportRequestStatus>,
+    approvals: State<'_, ExportApprovalState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<ExportRequest>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let is_approver = auth.has_permission("approve_data_export");
+    drop(auth);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    purge_expired_requests(&approvals, firebase).await?;
+
+    // Requesters only see their own requests
+    let requests = approvals
+        .list(status)
+        .into_iter()
+        .filter(|r| is_approver || r.requested_by == user_id)
+        .collect();
+    Ok(ApiResponse::success(requests))
+}
+
+/// Approve or reject a pending export request
+#[tauri::command]
+pub async fn decide_export_request(
+    request_id: Uuid,
+    approve: bool,
+    note: Option<String>,
+    approvals: State<'_, ExportApprovalState>,
+    config: State<'_, ExportApprovalConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<ExportRequest>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("approve_data_export") {
+        return Err("Insufficient permissions to approve data exports".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    purge_expired_requests(&approvals, firebase).await?;
+
+    let request = approvals.decide(request_id, &user_id, approve, note, &config).map_err(|e| e.to_string())?;
+
+    firebase.audit_log(
+        if approve { "EXPORT_REQUEST_APPROVED" } else { "EXPORT_REQUEST_REJECTED" },
+        "export_request",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "request_id": request.id,
+            "requested_by": request.requested_by,
+            "scope": request.scope,
+            "note": request.decision_note,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(request))
+}
+
+/// Generate the export for an approved request
+#[tauri::command]
+pub async fn run_approved_export(
+    request_id: Uuid,
+    approvals: State<'_, ExportApprovalState>,
+    consent_receipts: State<'_, ConsentReceiptState>,
+    receipt_config: State<'_, ConsentReceiptConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<PatientDataExport>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("export_data") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let role = auth.get_role().cloned();
+    let session_id = auth.session_id.clone();
+    drop(auth);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    purge_expired_requests(&approvals, firebase).await?;
+
+    let request = approvals.begin_export(request_id, &user_id).map_err(|e| e.to_string())?;
+
+    let clients: Vec<Client> = if request.scope.all_clients {
+        firebase.query_documents("clients", 1, MAX_EXPORT_CLIENTS)
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        let mut clients = Vec::with_capacity(request.scope.client_ids.len());
+        for client_id in &request.scope.client_ids {
+            let client: Option<Client> = firebase.get_document("clients", client_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            clients.extend(client);
+        }
+        clients
+    };
+
+    let receipts: Vec<SignedConsentReceipt> = if request.scope.kind == ExportKind::Dsar {
+        clients.iter().flat_map(|c| consent_receipts.receipts_for(&c.object_id)).collect()
+    } else {
+        Vec::new()
+    };
+    let trusted_keys = consent_receipts.trusted_keys(&receipt_config);
+    let receipt_verification: Vec<ReceiptVerification> =
+        receipts.iter().map(|r| verify_receipt(r, &trusted_keys)).collect();
+
+    firebase.audit_log(
+        "PATIENT_DATA_EXPORTED",
+        "export",
+        &user_id,
+        true, // PHI exported
+        Some(serde_json::json!({
+            "event_type": "PatientDataExported",
+            "request_id": request.id,
+            "approved_by": request.decided_by,
+            "approved_at": request.decided_at,
+            "auto_approved": request.auto_approved,
+            "kind": request.scope.kind,
+            "client_count": clients.len(),
+            "consent_receipt_count": receipts.len(),
+            "invalid_consent_receipts": receipt_verification.iter().filter(|v| !v.valid).count(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+    log_client_accesses(
+        &user_id,
+        role.as_ref(),
+        session_id.as_deref(),
+        clients.iter().map(|c| c.object_id.as_str()),
+        "EXPORT_PATIENT_DATA",
+    ).await?;
+
+    Ok(ApiResponse::success(PatientDataExport {
+        request_id: request.id,
+        generated_at: Utc::now(),
+        clients,
+        consent_receipts: receipts,
+        consent_receipt_verification: receipt_verification,
+    }))
+}
@@ -1,7 +1,22 @@
-use crate::services::encrypted_storage::{EncryptedNoteStorage, MedicalNote, QuebecComplianceMetadata, SyncStatus, AuditEntry};
-use tokio::sync::Mutex;
-use tauri::{AppHandle, State};
-use chrono::Utc;
+use crate::services::encrypted_storage::{EncryptedNoteStorage, MedicalNote, NoteAttachment, NoteListFilter, NoteVersionSummary, QuebecComplianceMetadata, SyncStatus, AuditEntry, ReencryptionJob};
+use crate::services::media_moderation::MediaScannerState;
+use crate::services::note_compliance_rules::{ComplianceFinding, ComplianceRuleSet};
+use crate::security::DataClassification;
+use crate::security::crypto::CryptoServiceState;
+use crate::services::error_reporter::report_command_error;
+use crate::services::event_log::{DomainEventKind, EventLogState};
+use crate::services::data_lock::DataLockState;
+use crate::commands::patient_access_commands::{authorize_patient_access, PatientDataRead};
+use crate::commands::security_commands::RbacServiceState;
+use crate::commands::offline_sync_commands::SyncServiceState;
+use crate::security::auth::AuthState;
+use crate::security::rbac::Permission;
+use crate::services::FirebaseService;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, State};
+use chrono::{NaiveDate, Utc};
+use uuid::Uuid;
 
 // Global storage instance
 pub type StorageState = Mutex<Option<EncryptedNoteStorage>>;
@@ -31,15 +46,60 @@ impl<T> CommandResult<T> {
     }
 }
 
+/// Gate for reading one patient's notes: `user_id` must be the signed-in user, whose role must
+/// view clinical notes and whom RBAC must allow this patient (assignment or break-glass)
+async fn authorize_note_read(
+    auth_state: &RwLock<AuthState>,
+    rbac: &RbacServiceState,
+    firebase: &Mutex<FirebaseService>,
+    patient_id: &str,
+    user_id: &str,
+    break_glass_grant_id: Option<Uuid>,
+) -> Result<(), String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if auth.user_id.as_deref() != Some(user_id) {
+        return Err("User ID does not match the signed-in user".to_string());
+    }
+    let role = auth.get_role().ok_or("No role in session")?;
+    if !rbac.0.role_grants(role, &Permission::ViewClinicalNotes) {
+        return Err("Insufficient permissions to view clinical notes".to_string());
+    }
+    let firebase = firebase.lock().await;
+    authorize_patient_access(&auth, rbac, &firebase, PatientDataRead {
+        patient_id,
+        permission: Permission::ViewPHI,
+        action: "VIEW_CLINICAL_NOTES",
+        justification: None,
+        break_glass_grant_id,
+    }).await
+}
+
 /// Initialize encrypted storage with user passphrase
 #[tauri::command]
 pub async fn initialize_encrypted_storage(
     app_handle: AppHandle,
     storage_state: State<'_, StorageState>,
+    data_lock: State<'_, DataLockState>,
+    crypto: State<'_, CryptoServiceState>,
     passphrase: String,
 ) -> Result<CommandResult<String>, String> {
+    if let Err(e) = data_lock.ensure_unlocked() {
+        return Ok(CommandResult::error(e));
+    }
     match EncryptedNoteStorage::new(&app_handle, &passphrase) {
         Ok(storage) => {
+            // Transcripts and other PHI files are encrypted through the shared crypto service,
+            // which is unlocked by the same passphrase
+            if let Err(e) = crypto.0.initialize_master_key(&passphrase, None).await {
+                return Ok(CommandResult::error(format!("Failed to initialize encryption: {}", e)));
+            }
+            // Deleted notes are kept for their retention period, then purged here
+            if let Err(e) = storage.purge_expired_notes(Utc::now(), "system").await {
+                tracing::warn!("Failed to purge expired notes: {}", e);
+            }
             let mut state = storage_state.lock().await;
             *state = Some(storage);
             Ok(CommandResult::success("Storage initialized successfully".to_string()))
@@ -48,19 +108,38 @@ pub async fn initialize_encrypted_storage(
     }
 }
 
-/// Save a medical note with encryption
+/// Save a medical note with encryption and queue it for upload
 #[tauri::command]
 pub async fn save_medical_note(
     storage_state: State<'_, StorageState>,
-    note: MedicalNote,
+    sync_state: State<'_, SyncServiceState>,
+    event_log: State<'_, EventLogState>,
+    mut note: MedicalNote,
     user_id: String,
 ) -> Result<CommandResult<String>, String> {
     let storage_guard = storage_state.lock().await;
 
     if let Some(storage) = storage_guard.as_ref() {
+        // A conflicted note stays out of the queue until the conflict is resolved
+        if note.sync_status != SyncStatus::Conflict {
+            note.sync_status = SyncStatus::Pending;
+        }
+        let mut queued = note.clone();
         match storage.save_note(note, &user_id).await {
-            Ok(note_id) => Ok(CommandResult::success(note_id)),
-            Err(e) => Ok(CommandResult::error(format!("Failed to save note: {}", e))),
+            Ok(note_id) => {
+                drop(storage_guard);
+                event_log.record(DomainEventKind::NoteSaved, "medical_note", &note_id);
+                if queued.sync_status == SyncStatus::Pending {
+                    // Without a sync service the note is picked up from storage when one starts
+                    if let Some(sync_service) = sync_state.lock().await.as_mut() {
+                        queued.id = note_id.clone();
+                        queued.modified_at = Utc::now();
+                        sync_service.enqueue_note(&queued);
+                    }
+                }
+                Ok(CommandResult::success(note_id))
+            }
+            Err(e) => Ok(CommandResult::error(report_command_error("save_medical_note", format!("Failed to save note: {}", e)))),
         }
     } else {
         Ok(CommandResult::error("Storage not initialized".to_string()))
@@ -71,49 +150,145 @@ pub async fn save_medical_note(
 #[tauri::command]
 pub async fn get_medical_note(
     storage_state: State<'_, StorageState>,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, Arc<Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
     note_id: String,
     user_id: String,
+    break_glass_grant_id: Option<Uuid>,
 ) -> Result<CommandResult<Option<MedicalNote>>, String> {
     let storage_guard = storage_state.lock().await;
 
     if let Some(storage) = storage_guard.as_ref() {
+        let patient_id = match storage.note_patient_id(&note_id) {
+            Ok(Some(patient_id)) => patient_id,
+            Ok(None) => return Ok(CommandResult::success(None)),
+            Err(e) => return Ok(CommandResult::error(format!("Failed to get note: {}", e))),
+        };
+        if let Err(e) = authorize_note_read(&auth_state, &rbac, &firebase, &patient_id, &user_id, break_glass_grant_id).await {
+            return Ok(CommandResult::error(e));
+        }
         match storage.get_note(&note_id, &user_id).await {
             Ok(note) => Ok(CommandResult::success(note)),
-            Err(e) => Ok(CommandResult::error(format!("Failed to get note: {}", e))),
+            Err(e) => Ok(CommandResult::error(report_command_error("get_medical_note", format!("Failed to get note: {}", e)))),
         }
     } else {
         Ok(CommandResult::error("Storage not initialized".to_string()))
     }
 }
 
-/// List medical notes for a patient
+/// List medical notes for a patient, optionally only those with an appointment date
+/// (`YYYY-MM-DD`, inclusive) in range
 #[tauri::command]
 pub async fn list_patient_notes(
     storage_state: State<'_, StorageState>,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, Arc<Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
     patient_id: String,
     user_id: String,
     limit: Option<u32>,
     offset: Option<u32>,
+    appointment_from: Option<String>,
+    appointment_to: Option<String>,
+    break_glass_grant_id: Option<Uuid>,
 ) -> Result<CommandResult<Vec<MedicalNote>>, String> {
+    let parse_date = |value: Option<String>| {
+        value
+            .map(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| format!("Invalid appointment date '{}'", date)))
+            .transpose()
+    };
+    let filter = match (parse_date(appointment_from), parse_date(appointment_to)) {
+        (Ok(appointment_from), Ok(appointment_to)) => NoteListFilter { appointment_from, appointment_to },
+        (Err(e), _) | (_, Err(e)) => return Ok(CommandResult::error(e)),
+    };
+    if let Err(e) = authorize_note_read(&auth_state, &rbac, &firebase, &patient_id, &user_id, break_glass_grant_id).await {
+        return Ok(CommandResult::error(e));
+    }
+
     let storage_guard = storage_state.lock().await;
 
     if let Some(storage) = storage_guard.as_ref() {
         let limit = limit.unwrap_or(50);
         let offset = offset.unwrap_or(0);
 
-        match storage.list_notes_for_patient(&patient_id, &user_id, limit, offset).await {
+        match storage.list_notes_for_patient(&patient_id, &user_id, limit, offset, &filter).await {
             Ok(notes) => Ok(CommandResult::success(notes)),
-            Err(e) => Ok(CommandResult::error(format!("Failed to list notes: {}", e))),
+            Err(e) => Ok(CommandResult::error(report_command_error("list_patient_notes", format!("Failed to list notes: {}", e)))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
+/// Versions of a note, oldest first, with their author and timestamp
+#[tauri::command]
+pub async fn get_note_history(
+    storage_state: State<'_, StorageState>,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, Arc<Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    note_id: String,
+    user_id: String,
+    break_glass_grant_id: Option<Uuid>,
+) -> Result<CommandResult<Vec<NoteVersionSummary>>, String> {
+    let storage_guard = storage_state.lock().await;
+
+    if let Some(storage) = storage_guard.as_ref() {
+        let patient_id = match storage.note_patient_id(&note_id) {
+            Ok(Some(patient_id)) => patient_id,
+            Ok(None) => return Ok(CommandResult::success(Vec::new())),
+            Err(e) => return Ok(CommandResult::error(format!("Failed to get note history: {}", e))),
+        };
+        if let Err(e) = authorize_note_read(&auth_state, &rbac, &firebase, &patient_id, &user_id, break_glass_grant_id).await {
+            return Ok(CommandResult::error(e));
+        }
+        match storage.get_note_history(&note_id, &user_id).await {
+            Ok(history) => Ok(CommandResult::success(history)),
+            Err(e) => Ok(CommandResult::error(report_command_error("get_note_history", format!("Failed to get note history: {}", e)))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
+/// Retrieve a specific version of a medical note
+#[tauri::command]
+pub async fn get_note_version(
+    storage_state: State<'_, StorageState>,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, Arc<Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    note_id: String,
+    version: u32,
+    user_id: String,
+    break_glass_grant_id: Option<Uuid>,
+) -> Result<CommandResult<Option<MedicalNote>>, String> {
+    let storage_guard = storage_state.lock().await;
+
+    if let Some(storage) = storage_guard.as_ref() {
+        let patient_id = match storage.note_patient_id(&note_id) {
+            Ok(Some(patient_id)) => patient_id,
+            Ok(None) => return Ok(CommandResult::success(None)),
+            Err(e) => return Ok(CommandResult::error(format!("Failed to get note version: {}", e))),
+        };
+        if let Err(e) = authorize_note_read(&auth_state, &rbac, &firebase, &patient_id, &user_id, break_glass_grant_id).await {
+            return Ok(CommandResult::error(e));
+        }
+        match storage.get_note_version(&note_id, version, &user_id).await {
+            Ok(note) => Ok(CommandResult::success(note)),
+            Err(e) => Ok(CommandResult::error(report_command_error("get_note_version", format!("Failed to get note version: {}", e)))),
         }
     } else {
         Ok(CommandResult::error("Storage not initialized".to_string()))
     }
 }
 
-/// Delete a medical note
+/// Delete a medical note; it is kept, with its history, until the retention period ends
 #[tauri::command]
 pub async fn delete_medical_note(
     storage_state: State<'_, StorageState>,
+    event_log: State<'_, EventLogState>,
     note_id: String,
     user_id: String,
 ) -> Result<CommandResult<String>, String> {
@@ -121,8 +296,51 @@ pub async fn delete_medical_note(
 
     if let Some(storage) = storage_guard.as_ref() {
         match storage.delete_note(&note_id, &user_id).await {
-            Ok(_) => Ok(CommandResult::success("Note deleted successfully".to_string())),
-            Err(e) => Ok(CommandResult::error(format!("Failed to delete note: {}", e))),
+            Ok(_) => {
+                event_log.record(DomainEventKind::NoteDeleted, "medical_note", &note_id);
+                Ok(CommandResult::success("Note deleted successfully".to_string()))
+            }
+            Err(e) => Ok(CommandResult::error(report_command_error("delete_medical_note", format!("Failed to delete note: {}", e)))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
+/// Scan media for PHI (when moderation is enabled) and attach it to a note with the scan result
+#[tauri::command]
+pub async fn attach_note_media(
+    storage_state: State<'_, StorageState>,
+    scanner: State<'_, MediaScannerState>,
+    note_id: String,
+    media_ref: String,
+    user_id: String,
+) -> Result<CommandResult<NoteAttachment>, String> {
+    let scan = scanner.0.scan_media(&media_ref, scanner.0.config().note_action).await;
+
+    let storage_guard = storage_state.lock().await;
+    if let Some(storage) = storage_guard.as_ref() {
+        match storage.attach_media(&note_id, scan, &user_id).await {
+            Ok(attachment) => Ok(CommandResult::success(attachment)),
+            Err(e) => Ok(CommandResult::error(format!("Failed to attach media: {}", e))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
+/// List a note's attachments and their scan results
+#[tauri::command]
+pub async fn list_note_attachments(
+    storage_state: State<'_, StorageState>,
+    note_id: String,
+) -> Result<CommandResult<Vec<NoteAttachment>>, String> {
+    let storage_guard = storage_state.lock().await;
+
+    if let Some(storage) = storage_guard.as_ref() {
+        match storage.list_attachments(&note_id).await {
+            Ok(attachments) => Ok(CommandResult::success(attachments)),
+            Err(e) => Ok(CommandResult::error(format!("Failed to list attachments: {}", e))),
         }
     } else {
         Ok(CommandResult::error("Storage not initialized".to_string()))
@@ -148,6 +366,76 @@ pub async fn get_audit_trail(
     }
 }
 
+/// Notes re-encrypted between progress updates
+const REENCRYPTION_BATCH_SIZE: u32 = 50;
+
+/// Change a note's data classification; run the re-encryption job to upgrade its ciphertext
+#[tauri::command]
+pub async fn reclassify_medical_note(
+    storage_state: State<'_, StorageState>,
+    note_id: String,
+    classification: DataClassification,
+    user_id: String,
+) -> Result<CommandResult<String>, String> {
+    let storage_guard = storage_state.lock().await;
+
+    if let Some(storage) = storage_guard.as_ref() {
+        match storage.set_note_classification(&note_id, classification, &user_id).await {
+            Ok(_) => Ok(CommandResult::success(format!("Note reclassified as {:?}", classification))),
+            Err(e) => Ok(CommandResult::error(format!("Failed to reclassify note: {}", e))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
+/// Upgrade note ciphertext to match classifications; pass `job_id` to resume an interrupted run.
+/// Progress is emitted as `note-reencryption-progress` events.
+#[tauri::command]
+pub async fn reencrypt_notes_for_classification(
+    app_handle: AppHandle,
+    storage_state: State<'_, StorageState>,
+    job_id: Option<String>,
+    user_id: String,
+) -> Result<CommandResult<ReencryptionJob>, String> {
+    let storage_guard = storage_state.lock().await;
+
+    if let Some(storage) = storage_guard.as_ref() {
+        let on_progress = |job: &ReencryptionJob| {
+            if let Err(e) = app_handle.emit("note-reencryption-progress", job) {
+                tracing::warn!("Failed to emit re-encryption progress: {}", e);
+            }
+        };
+        match storage.reencrypt_for_classification(job_id.as_deref(), &user_id, REENCRYPTION_BATCH_SIZE, on_progress).await {
+            Ok(job) => Ok(CommandResult::success(job)),
+            Err(e) => Ok(CommandResult::error(report_command_error(
+                "reencrypt_notes_for_classification",
+                format!("Re-encryption job failed: {}", e),
+            ))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
+/// Status of a re-encryption job, including notes needing manual follow-up
+#[tauri::command]
+pub async fn get_reencryption_job(
+    storage_state: State<'_, StorageState>,
+    job_id: String,
+) -> Result<CommandResult<Option<ReencryptionJob>>, String> {
+    let storage_guard = storage_state.lock().await;
+
+    if let Some(storage) = storage_guard.as_ref() {
+        match storage.get_reencryption_job(&job_id).await {
+            Ok(job) => Ok(CommandResult::success(job)),
+            Err(e) => Ok(CommandResult::error(format!("Failed to get re-encryption job: {}", e))),
+        }
+    } else {
+        Ok(CommandResult::error("Storage not initialized".to_string()))
+    }
+}
+
 /// Create a new medical note with Quebec compliance defaults
 #[tauri::command]
 pub async fn create_medical_note(
@@ -173,45 +461,20 @@ pub async fn create_medical_note(
             professional_order: None,
             audit_trail: Vec::new(),
         },
+        metadata: Default::default(),
+        version: 0,
     };
 
     Ok(CommandResult::success(note))
 }
 
-/// Validate note compliance before saving
+/// Validate a note against the Law 25 checks and the clinic's compliance rules
 #[tauri::command]
-pub async fn validate_note_compliance(note: MedicalNote) -> Result<CommandResult<Vec<String>>, String> {
-    let mut violations = Vec::new();
-
-    if !note.consent_obtained {
-        violations.push("Law 25: Patient consent is required for processing personal health information".to_string());
-    }
-
-    if !note.quebec_compliance.law_25_consent {
-        violations.push("Law 25: Explicit consent flag must be set".to_string());
-    }
-
-    if !note.quebec_compliance.data_minimization {
-        violations.push("Law 25: Data minimization principle must be enforced".to_string());
-    }
-
-    if note.quebec_compliance.retention_period_days == 0 {
-        violations.push("Law 25: Retention period must be specified".to_string());
-    }
-
-    if note.content.trim().is_empty() {
-        violations.push("Note content cannot be empty".to_string());
-    }
-
-    if note.patient_id.trim().is_empty() {
-        violations.push("Patient ID is required".to_string());
-    }
-
-    if note.template_type.trim().is_empty() {
-        violations.push("Template type is required".to_string());
-    }
-
-    Ok(CommandResult::success(violations))
+pub async fn validate_note_compliance(
+    rules: State<'_, ComplianceRuleSet>,
+    note: MedicalNote,
+) -> Result<CommandResult<Vec<ComplianceFinding>>, String> {
+    Ok(CommandResult::success(rules.evaluate(&note)))
 }
 
 /// Check storage status
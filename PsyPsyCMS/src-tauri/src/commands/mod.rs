@@ -8,6 +8,14 @@ pub mod medical_notes_commands;
 pub mod offline_sync_commands;
 pub mod social_media_commands;
 pub mod debug_commands;
+pub mod security_commands;
+pub mod event_commands;
+pub mod reminder_commands;
+pub mod export_commands;
+pub mod consent_commands;
+pub mod search_commands;
+pub mod timeline_commands;
+pub mod patient_access_commands;
 
 // Note: Individual commands are imported directly in lib.rs for better granular control
 // Blanket re-exports removed to eliminate unused import warnings
\ No newline at end of file
@@ -1,6 +1,14 @@
-use crate::services::offline_sync::{OfflineSyncService, SyncMetadata, ResolutionStrategy};
+use crate::services::offline_sync::{
+    self, ConflictResolutionOutcome, MergeStrategy, NoteConflict, OfflineSyncService, PendingSyncCount, SyncMetadata,
+};
 use crate::services::encrypted_storage::MedicalNote;
-use tokio::sync::Mutex;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use crate::services::sync_schedule::{self, NetworkStatus, SyncSchedule, SyncScheduleStatus, SyncScheduler};
+use crate::services::data_lock::DataLockState;
+use crate::security::auth::AuthState;
+use chrono::Utc;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
 use tauri::State;
 
 // Global sync service state
@@ -51,47 +59,122 @@ pub async fn initialize_sync_service(
     ))
 }
 
-/// Perform manual sync
+/// Perform manual sync; failed note pushes are retried with backoff before this returns
 #[tauri::command]
 pub async fn perform_manual_sync(
     sync_state: State<'_, SyncServiceState>,
 ) -> Result<SyncCommandResult<String>, String> {
-    // Check if sync service is available without holding lock across await
-    let has_sync_service = {
-        let sync_guard = sync_state.lock().await;
-        sync_guard.is_some()
-    };
-
-    if !has_sync_service {
-        return Ok(SyncCommandResult::error("Sync service not initialized".to_string()));
+    match offline_sync::sync_with_retry(&sync_state, |service| Box::pin(service.perform_sync())).await {
+        Ok(()) => Ok(SyncCommandResult::success("Manual sync completed".to_string())),
+        Err(e) => Ok(SyncCommandResult::error(e.to_string())),
     }
+}
 
-    // For now, return success placeholder since we can't hold mutex across await
-    // This would need refactoring to use Arc<Tokio::Mutex> for proper async compatibility
-    Ok(SyncCommandResult::success("Manual sync functionality requires async refactoring".to_string()))
+/// Sync metadata plus the background schedule
+#[derive(serde::Serialize)]
+pub struct SyncStatusReport {
+    #[serde(flatten)]
+    metadata: SyncMetadata,
+    schedule: SyncScheduleStatus,
+    /// Upload queue place of the requested note, 1 being next; absent when it is not queued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queue_position: Option<usize>,
 }
 
-/// Get sync status
+/// Get sync status, including the next scheduled run and whether quiet hours are in effect.
+/// With `note_id`, also where that note is in the upload queue.
 #[tauri::command]
 pub async fn get_sync_status(
     sync_state: State<'_, SyncServiceState>,
-) -> Result<SyncCommandResult<SyncMetadata>, String> {
+    scheduler: State<'_, SyncScheduler>,
+    note_id: Option<String>,
+) -> Result<SyncCommandResult<SyncStatusReport>, String> {
     let sync_guard = sync_state.lock().await;
+    let queue_position = sync_guard
+        .as_ref()
+        .zip(note_id.as_deref())
+        .and_then(|(sync_service, note_id)| sync_service.queue_position(note_id));
 
-    if let Some(sync_service) = sync_guard.as_ref() {
-        let metadata = sync_service.get_sync_status().clone();
-        Ok(SyncCommandResult::success(metadata))
+    let metadata = if let Some(sync_service) = sync_guard.as_ref() {
+        sync_service.get_sync_status().clone()
     } else {
         // Return default metadata when not initialized
-        let default_metadata = SyncMetadata {
+        SyncMetadata {
             last_sync: None,
             pending_uploads: Vec::new(),
             conflict_notes: Vec::new(),
             sync_enabled: false,
             firebase_collection: "encrypted_medical_notes".to_string(),
-        };
-        Ok(SyncCommandResult::success(default_metadata))
+            note_attempts: Default::default(),
+        }
+    };
+    Ok(SyncCommandResult::success(SyncStatusReport {
+        metadata,
+        schedule: scheduler.status(Utc::now()),
+        queue_position,
+    }))
+}
+
+/// Current background sync schedule
+#[tauri::command]
+pub async fn get_sync_schedule(
+    scheduler: State<'_, SyncScheduler>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<SyncCommandResult<SyncSchedule>, String> {
+    if !auth_state.read().await.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    Ok(SyncCommandResult::success(scheduler.schedule()))
+}
+
+/// Change the sync interval and quiet hours; takes effect immediately
+#[tauri::command]
+pub async fn update_sync_schedule(
+    schedule: SyncSchedule,
+    scheduler: State<'_, SyncScheduler>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<SyncCommandResult<SyncScheduleStatus>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    if let Err(e) = scheduler.set_schedule(schedule.clone(), Utc::now()) {
+        return Ok(SyncCommandResult::error(e));
+    }
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "SYNC_SCHEDULE_UPDATED",
+        "sync_schedule",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "interval_minutes": schedule.interval_minutes,
+            "quiet_hours": schedule.quiet_hours,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(SyncCommandResult::success(scheduler.status(Utc::now())))
+}
+
+/// Run background sync now, even inside quiet hours
+#[tauri::command]
+pub async fn sync_now(
+    scheduler: State<'_, SyncScheduler>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    data_lock: State<'_, DataLockState>,
+) -> Result<SyncCommandResult<String>, String> {
+    if !auth_state.read().await.is_authenticated {
+        return Err("Unauthorized".to_string());
     }
+    data_lock.ensure_unlocked()?;
+    scheduler.request_run();
+    Ok(SyncCommandResult::success("Sync requested".to_string()))
 }
 
 /// Enable or disable sync
@@ -110,135 +193,99 @@ pub async fn set_sync_enabled(
     }
 }
 
-/// Force sync a specific note
+/// Upload a note now, ahead of everything else queued
 #[tauri::command]
 pub async fn force_sync_note(
     sync_state: State<'_, SyncServiceState>,
     note_id: String,
 ) -> Result<SyncCommandResult<String>, String> {
-    // Check if sync service is available without holding lock across await
-    let has_sync_service = {
-        let sync_guard = sync_state.lock().await;
-        sync_guard.is_some()
-    };
-
-    if !has_sync_service {
-        return Ok(SyncCommandResult::error("Sync service not initialized".to_string()));
+    let outcome = offline_sync::sync_with_retry(&sync_state, |service| {
+        let note_id = note_id.clone();
+        Box::pin(async move { service.force_sync_note(&note_id).await })
+    })
+    .await;
+    match outcome {
+        Ok(()) => Ok(SyncCommandResult::success(format!("Note {} synced", note_id))),
+        Err(e) => Ok(SyncCommandResult::error(e.to_string())),
     }
-
-    // For now, return success placeholder since we can't hold mutex across await
-    // This would need refactoring to use Arc<Tokio::Mutex> for proper async compatibility
-    Ok(SyncCommandResult::success(format!("Note {} sync functionality requires async refactoring", note_id)))
 }
 
-/// Get conflict notes that need manual resolution
+/// Get conflict notes that need manual resolution, each with a per-field diff
 #[tauri::command]
 pub async fn get_conflict_notes(
     sync_state: State<'_, SyncServiceState>,
-) -> Result<SyncCommandResult<Vec<MedicalNote>>, String> {
-    // Check if sync service is available without holding lock across await
-    let has_sync_service = {
-        let sync_guard = sync_state.lock().await;
-        sync_guard.is_some()
-    };
+) -> Result<SyncCommandResult<Vec<NoteConflict>>, String> {
+    let sync_guard = sync_state.lock().await;
 
-    if !has_sync_service {
-        return Ok(SyncCommandResult::success(Vec::new()));
+    match sync_guard.as_ref() {
+        Some(sync_service) => Ok(SyncCommandResult::success(sync_service.get_conflict_notes())),
+        None => Ok(SyncCommandResult::success(Vec::new())),
     }
-
-    // For now, return empty list since we can't hold mutex across await
-    // This would need refactoring to use Arc<Tokio::Mutex> for proper async compatibility
-    Ok(SyncCommandResult::success(Vec::new()))
 }
 
-/// Manually resolve a conflict
+/// Resolve a conflict by keeping one side, merging field changes, or saving a hand-edited note.
+/// A merge that finds the same field edited differently on both sides returns `resolved: false`
+/// and leaves the note in conflict.
 #[tauri::command]
 pub async fn resolve_conflict_manually(
     sync_state: State<'_, SyncServiceState>,
-    _note_id: String,
-    resolution_strategy: String,
-    _resolved_note: MedicalNote,
-) -> Result<SyncCommandResult<String>, String> {
-    let _strategy = match resolution_strategy.as_str() {
-        "use_local" => ResolutionStrategy::UseLocal,
-        "use_remote" => ResolutionStrategy::UseRemote,
-        "merge" => ResolutionStrategy::Merge,
-        _ => ResolutionStrategy::ManualReview,
-    };
-
-    // Check if sync service is available in a separate scope
-    let has_sync_service = {
-        let sync_guard = sync_state.lock().await;
-        sync_guard.is_some()
-    };
-
-    if !has_sync_service {
+    note_id: String,
+    resolution_strategy: MergeStrategy,
+    resolved_note: Option<MedicalNote>,
+) -> Result<SyncCommandResult<ConflictResolutionOutcome>, String> {
+    let mut sync_guard = sync_state.lock().await;
+    let Some(sync_service) = sync_guard.as_mut() else {
         return Ok(SyncCommandResult::error("Sync service not initialized".to_string()));
-    }
-
-    // Get service and perform async operation in separate scope
-    let result = {
-        let mut sync_guard = sync_state.lock().await;
-        if let Some(_sync_service) = sync_guard.as_mut() {
-            // Clone the service or use a different approach
-            // For now, we'll return an error since we can't hold the lock across await
-            drop(sync_guard);
-            Ok(SyncCommandResult::error("Manual conflict resolution requires refactoring for async compatibility".to_string()))
-        } else {
-            Ok(SyncCommandResult::error("Sync service not initialized".to_string()))
-        }
     };
 
-    result
+    match sync_service.resolve_conflict_manually(&note_id, resolution_strategy, resolved_note).await {
+        Ok(outcome) => Ok(SyncCommandResult::success(outcome)),
+        Err(e) => Ok(SyncCommandResult::error(e.to_string())),
+    }
 }
 
-/// Check network connectivity for sync
+/// Check network connectivity and quality for sync; the scheduler is told so a reconnect syncs
+/// promptly and a slow or metered connection is throttled
 #[tauri::command]
-pub async fn check_network_connectivity() -> Result<SyncCommandResult<bool>, String> {
-    // Simple network check - in a real implementation, this would ping Firebase
-    let is_online = std::process::Command::new("ping")
-        .arg("-c")
-        .arg("1")
-        .arg("8.8.8.8")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false);
+pub async fn check_network_connectivity(
+    scheduler: State<'_, SyncScheduler>,
+) -> Result<SyncCommandResult<NetworkStatus>, String> {
+    let status = sync_schedule::probe_network().await;
+    scheduler.set_network(status.clone(), Utc::now());
 
-    Ok(SyncCommandResult::success(is_online))
+    Ok(SyncCommandResult::success(status))
 }
 
-/// Get pending sync count
+/// Get pending sync count, split into notes a later sync will retry and notes that failed for good
 #[tauri::command]
 pub async fn get_pending_sync_count(
     sync_state: State<'_, SyncServiceState>,
-) -> Result<SyncCommandResult<usize>, String> {
+) -> Result<SyncCommandResult<PendingSyncCount>, String> {
     let sync_guard = sync_state.lock().await;
 
     if let Some(sync_service) = sync_guard.as_ref() {
-        let metadata = sync_service.get_sync_status();
-        Ok(SyncCommandResult::success(metadata.pending_uploads.len()))
+        Ok(SyncCommandResult::success(sync_service.get_sync_status().pending_count()))
     } else {
-        Ok(SyncCommandResult::success(0))
+        Ok(SyncCommandResult::success(PendingSyncCount::default()))
     }
 }
 
-/// Start background sync (non-blocking)
+/// Resume scheduled background sync
 #[tauri::command]
 pub async fn start_background_sync(
-    _sync_state: State<'_, SyncServiceState>,
+    scheduler: State<'_, SyncScheduler>,
 ) -> Result<SyncCommandResult<String>, String> {
-    // In a real implementation, this would start the background sync task
-    // For now, just return success
     tracing::info!("Background sync start requested");
+    scheduler.set_paused(false);
     Ok(SyncCommandResult::success("Background sync started".to_string()))
 }
 
-/// Stop background sync
+/// Pause scheduled background sync; "sync now" still works
 #[tauri::command]
 pub async fn stop_background_sync(
-    _sync_state: State<'_, SyncServiceState>,
+    scheduler: State<'_, SyncScheduler>,
 ) -> Result<SyncCommandResult<String>, String> {
-    // In a real implementation, this would stop the background sync task
     tracing::info!("Background sync stop requested");
+    scheduler.set_paused(true);
     Ok(SyncCommandResult::success("Background sync stopped".to_string()))
 }
\ No newline at end of file
@@ -0,0 +1,290 @@
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::sync::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::commands::security_commands::RbacServiceState;
+use crate::models::user::User;
+use crate::models::{ApiResponse, Client, Professional};
+use crate::security::audit::{log_phi_access, subscribe_phi_access, AuditOutcome};
+use crate::security::auth::AuthState;
+use crate::security::data_scope::collect_matching;
+use crate::security::rbac::{stable_uuid, Permission, PermissionContext};
+use crate::security::HealthcareRole;
+use crate::services::notifier::NotifierState;
+use crate::services::patient_access_notifications::{self, PatientAccess, PatientAccessNotifications};
+use crate::services::FirebaseService;
+
+/// The client record belonging to a signed-in patient
+async fn own_client_record(auth: &AuthState, firebase: &FirebaseService) -> Result<Client, String> {
+    if auth.get_role() != Some(&HealthcareRole::Patient) {
+        return Err("Only patients can manage their own access notifications".to_string());
+    }
+    let user_id = auth.user_id.as_deref().ok_or("No user ID in auth state")?;
+    // Every page is read, so no record falls past a scan cap
+    let clients: Vec<Client> = collect_matching(firebase, "clients", |c: &Client| c.user_id == user_id).await?;
+    clients
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No patient record is linked to this account".to_string())
+}
+
+/// Email on the account linked to a client record
+async fn account_email(firebase: &FirebaseService, user_id: &str) -> Option<String> {
+    match firebase.get_document::<User>("users", user_id).await {
+        Ok(user) => user.map(|u| u.base.email).filter(|e| !e.trim().is_empty()),
+        Err(e) => {
+            log::warn!("Could not look up account email for access notification: {}", e);
+            None
+        }
+    }
+}
+
+/// Point RBAC's assignment check at the client's current professionals. A professional ID may
+/// name a professional record or the user directly; records resolve to the user who owns them.
+pub(crate) async fn sync_care_assignments(rbac: &RbacServiceState, firebase: &FirebaseService, client: &Client) {
+    let mut users = Vec::new();
+    for professional_id in &client.assigned_professionals {
+        let owner = match firebase.get_document::<Professional>("professionals", professional_id).await {
+            Ok(Some(professional)) => professional.user_id,
+            Ok(None) => professional_id.clone(),
+            Err(e) => {
+                log::warn!("Could not resolve professional {} for assignment check: {}", professional_id, e);
+                professional_id.clone()
+            }
+        };
+        users.push(stable_uuid(&owner));
+    }
+    rbac.0.set_assignments(&client.object_id, users);
+}
+
+/// A read of one patient's data, checked by `authorize_patient_access`
+pub(crate) struct PatientDataRead<'a> {
+    pub patient_id: &'a str,
+    pub permission: Permission,
+    /// Audited action, e.g. `VIEW_CLIENT`
+    pub action: &'a str,
+    /// Reason for access already accepted by the justification policy
+    pub justification: Option<&'a str>,
+    pub break_glass_grant_id: Option<Uuid>,
+}
+
+/// Check a read of one patient's data through RBAC before anything is returned. The caller's
+/// session must still be live and their role must grant the permission; they must also be
+/// assigned to the patient, per the stored client record, or name a break-glass grant for them.
+pub(crate) async fn authorize_patient_access(
+    auth: &AuthState,
+    rbac: &RbacServiceState,
+    firebase: &FirebaseService,
+    read: PatientDataRead<'_>,
+) -> Result<(), String> {
+    // Assignments follow the stored record, not whatever this process last saw
+    match firebase.get_document::<Client>("clients", read.patient_id).await {
+        Ok(Some(client)) => authorize_client_access(auth, rbac, firebase, &client, read).await,
+        Ok(None) => {
+            rbac.0.set_assignments(read.patient_id, []);
+            check_patient_access(auth, rbac, read).await
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `authorize_patient_access` for a client record the caller already loaded
+pub(crate) async fn authorize_client_access(
+    auth: &AuthState,
+    rbac: &RbacServiceState,
+    firebase: &FirebaseService,
+    client: &Client,
+    read: PatientDataRead<'_>,
+) -> Result<(), String> {
+    sync_care_assignments(rbac, firebase, client).await;
+    check_patient_access(auth, rbac, read).await
+}
+
+async fn check_patient_access(auth: &AuthState, rbac: &RbacServiceState, read: PatientDataRead<'_>) -> Result<(), String> {
+    let user_id = auth.user_id.as_deref().ok_or("No user ID in auth state")?;
+    let role = auth.get_role().cloned().ok_or("No role in session")?;
+    let session_id = auth.session_id.clone().ok_or("No active session")?;
+
+    let mut metadata = HashMap::from([("action".to_string(), read.action.to_string())]);
+    if let Some(justification) = read.justification {
+        metadata.insert("justification".to_string(), justification.to_string());
+    }
+    let context = PermissionContext {
+        user_id: stable_uuid(user_id),
+        role,
+        permission: read.permission,
+        resource_id: Some(read.patient_id.to_string()),
+        patient_id: None,
+        ip_address: None,
+        timestamp: Utc::now(),
+        session_id,
+        mfa_verified: auth.mfa_verified_at.is_some(),
+        metadata,
+    };
+    let decision = rbac.0.access_patient_data(context, read.break_glass_grant_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !decision.granted {
+        return Err(decision.denial_reason.unwrap_or_else(|| "Access to this patient's data was denied".to_string()));
+    }
+    Ok(())
+}
+
+/// Audit a read of each client's data through `log_phi_access`. Lists, searches and exports
+/// return many records without a per-record RBAC check, so they record each one here; the
+/// patient access notifier picks the accesses up from there.
+pub(crate) async fn log_client_accesses<'a>(
+    user_id: &str,
+    role: Option<&HealthcareRole>,
+    session_id: Option<&str>,
+    client_ids: impl IntoIterator<Item = &'a str>,
+    action: &str,
+) -> Result<(), String> {
+    let Some(audit) = crate::security::audit::audit_service() else {
+        return Ok(());
+    };
+    for client_id in client_ids {
+        log_phi_access(
+            &audit,
+            stable_uuid(user_id),
+            role.cloned(),
+            client_id,
+            action,
+            AuditOutcome::Success,
+            session_id.unwrap_or_default().to_string(),
+            None,
+        ).await.map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Add every granted PHI access from the audit trail to the patient's access log and notify
+/// them if they opted in. Break-glass accesses notify the account email even without an opt-in.
+pub fn start_access_notifier<R: Runtime>(app: AppHandle<R>) {
+    let mut accesses = match subscribe_phi_access() {
+        Ok(accesses) => accesses,
+        Err(e) => {
+            log::error!("Patient access notifications are disabled: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = accesses.recv().await {
+            let (Some(user_id), Some(client_id)) = (event.user_id, event.resource_id.clone()) else {
+                continue;
+            };
+            let rbac = app.state::<RbacServiceState>();
+            let break_glass = patient_access_notifications::holds_break_glass(
+                &rbac.0.list_active_elevated_grants(),
+                &user_id.to_string(),
+                &client_id,
+                Utc::now(),
+            );
+            let Some(access) = PatientAccess::from_audit_event(&event, break_glass) else {
+                continue;
+            };
+            let notifications = app.state::<PatientAccessNotifications>();
+            let contact = if break_glass && notifications.subscription(&client_id).is_none() {
+                let firebase = app.state::<Arc<tokio::sync::Mutex<FirebaseService>>>();
+                let firebase = firebase.lock().await;
+                match firebase.get_document::<Client>("clients", &client_id).await {
+                    Ok(Some(client)) => account_email(&firebase, &client.user_id).await,
+                    Ok(None) => None,
+                    Err(e) => {
+                        log::warn!("Could not look up client for access notification: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            let notifier = app.state::<NotifierState>();
+            patient_access_notifications::notify_record_access(&notifications, &notifier, access, contact.as_deref()).await;
+        }
+    });
+}
+
+/// Opt in to (or out of) notices when your record is accessed. Notices go to the given address,
+/// or the email on your account when none is given.
+#[tauri::command]
+pub async fn set_access_notifications(
+    enabled: bool,
+    recipient: Option<String>,
+    notifications: State<'_, PatientAccessNotifications>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<bool>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let firebase = firebase.lock().await;
+    let client = own_client_record(&auth, &firebase).await?;
+    drop(auth);
+
+    let recipient = if enabled {
+        let address = match recipient.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()) {
+            Some(address) => address,
+            None => account_email(&firebase, &user_id).await.unwrap_or_default(),
+        };
+        if !address.contains('@') {
+            return Err("A valid email address is required for access notifications".to_string());
+        }
+        Some(address)
+    } else {
+        None
+    };
+    notifications.set_subscription(&client.object_id, recipient).await;
+
+    firebase.audit_log(
+        "PATIENT_ACCESS_NOTIFICATIONS_UPDATED",
+        "client",
+        &user_id,
+        false, // Preference only
+        Some(serde_json::json!({
+            "client_id": client.object_id,
+            "enabled": enabled,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(enabled))
+}
+
+/// Who accessed your record, in what role, when and for what kind of purpose; newest first
+#[tauri::command]
+pub async fn get_my_access_log(
+    limit: Option<usize>,
+    notifications: State<'_, PatientAccessNotifications>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<PatientAccess>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let firebase = firebase.lock().await;
+    let client = own_client_record(&auth, &firebase).await?;
+    drop(auth);
+
+    // Only the caller's own record is ever looked up
+    let entries = notifications.access_log(&client.object_id, limit.unwrap_or(100).clamp(1, 500));
+
+    firebase.audit_log(
+        "VIEW_OWN_ACCESS_LOG",
+        "client",
+        &user_id,
+        false, // Access metadata only
+        Some(serde_json::json!({
+            "client_id": client.object_id,
+            "returned": entries.len(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(entries))
+}
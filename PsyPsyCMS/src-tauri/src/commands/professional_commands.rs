@@ -4,54 +4,32 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::services::firebase_service_simple::{FirebaseService, FirebaseServiceState};
+use crate::services::dashboard_cache::{DashboardCache, DashboardEntity};
 use crate::models::{
     Professional, CreateProfessionalRequest, UpdateProfessionalRequest, ApiResponse,
-    PaginatedResponse, SearchFilters, SortOptions, ProfessionalStats
+    ListPage, ListParams, ProfessionalStats
 };
 use crate::models::professional::ProfessionalStatus;
 use crate::security::auth::AuthState;
+use crate::storage::search_index::{IndexedEntity, SearchIndexState};
+use crate::security::HealthcareRole;
+use crate::services::specialty_taxonomy::{Specialty, SpecialtyMapping, SpecialtyTaxonomy, SpecialtyTaxonomyState};
 
-/// Get all professionals with pagination and filters
+/// One page of professionals, sorted and filtered by `params`
 #[tauri::command]
 pub async fn get_professionals(
-    page: Option<u32>,
-    limit: Option<u32>,
-    _filters: Option<SearchFilters>,
-    _sort: Option<SortOptions>,
+    params: Option<ListParams>,
     _firebase_state: State<'_, FirebaseServiceState>,
     auth_state: State<'_, Arc<RwLock<AuthState>>>,
-) -> Result<ApiResponse<PaginatedResponse<Professional>>, String> {
+) -> Result<ApiResponse<ListPage<Professional>>, String> {
     // For now, return mock data since Firebase is not fully initialized
     // This will be replaced with real Firebase queries once the service is complete
 
     // Check if auth state is accessible (safety check for state management)
     let _auth = auth_state.read().await;
 
-    let page = page.unwrap_or(1);
-    let limit = limit.unwrap_or(10);
-
-    // Generate mock professionals data
-    let mock_professionals = generate_mock_professionals();
-
-    // Apply pagination
-    let start_index = ((page - 1) * limit) as usize;
-    let end_index = (start_index + limit as usize).min(mock_professionals.len());
-    let professionals = if start_index < mock_professionals.len() {
-        mock_professionals[start_index..end_index].to_vec()
-    } else {
-        Vec::new()
-    };
-
-    let total = mock_professionals.len() as u32;
-
-    let response = PaginatedResponse {
-        data: professionals,
-        page,
-        limit,
-        total,
-        has_next_page: total > (page * limit),
-        has_previous_page: page > 1,
-    };
+    let params = params.unwrap_or_default();
+    let response = params.apply(generate_mock_professionals())?;
 
     // Log the operation (when Firebase is available)
     let firebase_guard = _firebase_state.0.lock().await;
@@ -61,7 +39,7 @@ pub async fn get_professionals(
             "professionals",
             "system", // Default user until auth is implemented
             false, // No specific PHI accessed for listing
-            Some(serde_json::json!({"page": page, "limit": limit}))
+            Some(serde_json::json!({"limit": params.limit, "offset": params.offset, "sortBy": params.sort_by}))
         ).await;
     }
 
@@ -107,6 +85,7 @@ fn generate_mock_professionals() -> Vec<Professional> {
             buss_email: "sarah.johnson@psypsy.com".to_string(),
             business_name: "Dr. Sarah Johnson Psychology Services".to_string(),
             prof_type: 1, // Clinical Psychologist
+            specialties: vec!["clinical_psychology".to_string(), "cbt".to_string()],
             edu_institute: 1, // McGill University
             mother_tongue: 1, // English
             offered_lang_arr: vec![1, 2], // English, French
@@ -143,6 +122,7 @@ fn generate_mock_professionals() -> Vec<Professional> {
             ]),
             served_clientele: vec![1, 2, 3], // Adults, adolescents, young adults
             availability: vec![1, 2, 3, 4, 5], // Monday to Friday
+            working_hours: None,
             meet_type: MeetingType::Both,
             third_party_payers: vec![1, 2], // Blue Cross, Sun Life
             part_of_order: None,
@@ -209,6 +189,7 @@ fn generate_mock_professionals() -> Vec<Professional> {
             buss_email: "michael.chen@psypsy.com".to_string(),
             business_name: "Dr. Michael Chen Child Psychology".to_string(),
             prof_type: 2, // Child Psychologist
+            specialties: vec!["child_adolescent".to_string()],
             edu_institute: 2, // University of Montreal
             mother_tongue: 1, // English
             offered_lang_arr: vec![1, 3], // English, Mandarin
@@ -245,6 +226,7 @@ fn generate_mock_professionals() -> Vec<Professional> {
             ]),
             served_clientele: vec![4, 5], // Children, adolescents
             availability: vec![1, 2, 3, 4], // Monday to Thursday
+            working_hours: None,
             meet_type: MeetingType::Both,
             third_party_payers: vec![3, 4], // Desjardins, Manulife
             part_of_order: None,
@@ -311,6 +293,7 @@ fn generate_mock_professionals() -> Vec<Professional> {
             buss_email: "marie.dubois@psypsy.com".to_string(),
             business_name: "Couples Wellness Center".to_string(),
             prof_type: 3, // Marriage and Family Therapist
+            specialties: vec!["couple_family".to_string()],
             edu_institute: 3, // Université de Sherbrooke
             mother_tongue: 2, // French
             offered_lang_arr: vec![1, 2], // English, French
@@ -347,6 +330,7 @@ fn generate_mock_professionals() -> Vec<Professional> {
             ]),
             served_clientele: vec![1, 6], // Adults, couples
             availability: vec![2, 3, 4, 5, 6], // Tuesday to Saturday
+            working_hours: None,
             meet_type: MeetingType::Both,
             third_party_payers: vec![5, 2], // Great-West Life, Sun Life
             part_of_order: None,
@@ -418,9 +402,12 @@ pub async fn get_professional(
 /// Create new professional
 #[tauri::command]
 pub async fn create_professional(
-    request: CreateProfessionalRequest,
+    mut request: CreateProfessionalRequest,
+    taxonomy: State<'_, SpecialtyTaxonomyState>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     _auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Professional>, String> {
     let auth = _auth_state.read().await;
     if !auth.is_authenticated {
@@ -432,6 +419,8 @@ pub async fn create_professional(
         return Err("Insufficient permissions".to_string());
     }
 
+    request.specialties = taxonomy.validate(&request.specialties).map_err(|e| e.to_string())?;
+
     let professional_id = Uuid::new_v4().to_string();
     let professional = Professional::from_request(request, professional_id.clone());
 
@@ -442,6 +431,13 @@ pub async fn create_professional(
         .await
         .map_err(|e| e.to_string())?;
 
+    if let Err(e) = search_index.index_professional(&professional) {
+        let _ = firebase.delete_document("professionals", &professional_id).await;
+        return Err(e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Professional));
+
     // Audit log
     firebase.audit_log(
         "CREATE_PROFESSIONAL",
@@ -465,9 +461,12 @@ pub async fn create_professional(
 #[tauri::command]
 pub async fn update_professional(
     id: String,
-    request: UpdateProfessionalRequest,
+    mut request: UpdateProfessionalRequest,
+    taxonomy: State<'_, SpecialtyTaxonomyState>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     _auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Professional>, String> {
     let auth = _auth_state.read().await;
     if !auth.is_authenticated {
@@ -478,6 +477,10 @@ pub async fn update_professional(
         return Err("Insufficient permissions".to_string());
     }
 
+    if let Some(specialties) = request.specialties.as_deref() {
+        request.specialties = Some(taxonomy.validate(specialties).map_err(|e| e.to_string())?);
+    }
+
     let firebase = firebase.lock().await;
 
     // Get existing professional
@@ -487,6 +490,7 @@ pub async fn update_professional(
         .ok_or("Professional not found")?;
 
     // Update professional data
+    let previous = professional.clone();
     professional.update_from_request(request);
 
     // Save to Firestore
@@ -494,6 +498,13 @@ pub async fn update_professional(
         .await
         .map_err(|e| e.to_string())?;
 
+    if let Err(e) = search_index.index_professional(&professional) {
+        let _ = firebase.update_document("professionals", &id, &previous).await;
+        return Err(e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Professional));
+
     // Audit log
     firebase.audit_log(
         "UPDATE_PROFESSIONAL",
@@ -519,6 +530,8 @@ pub async fn delete_professional(
     id: String,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     _auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    search_index: State<'_, SearchIndexState>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<()>, String> {
     let auth = _auth_state.read().await;
     if !auth.is_authenticated {
@@ -547,6 +560,11 @@ pub async fn delete_professional(
     firebase.delete_document("professionals", &id)
         .await
         .map_err(|e| e.to_string())?;
+    if let Err(e) = search_index.remove(IndexedEntity::Professional, &id) {
+        log::warn!("Deleted professional {} is still in the search index: {}", id, e);
+    }
+
+    dashboard_cache.invalidate(Some(DashboardEntity::Professional));
 
     // Audit log
     firebase.audit_log(
@@ -572,10 +590,19 @@ pub async fn delete_professional(
 pub async fn search_professionals(
     query: String,
     limit: Option<u32>,
+    specialty: Option<String>,
+    taxonomy: State<'_, SpecialtyTaxonomyState>,
     _firebase_state: State<'_, FirebaseServiceState>,
     _auth_state: State<'_, Arc<RwLock<AuthState>>>,
 ) -> Result<ApiResponse<Vec<Professional>>, String> {
     let limit = limit.unwrap_or(10);
+    // Any label or alias narrows to the canonical code stored on records
+    let specialty_code = match specialty.as_deref() {
+        Some(value) => Some(
+            taxonomy.get().resolve(value).map(|s| s.code.clone()).ok_or_else(|| format!("Unknown specialty '{}'", value))?,
+        ),
+        None => None,
+    };
 
     // Search through mock professionals
     let mock_professionals = generate_mock_professionals();
@@ -589,6 +616,7 @@ pub async fn search_professionals(
             p.business_name.to_lowercase().contains(&query_lower) ||
             p.buss_email.to_lowercase().contains(&query_lower)
         })
+        .filter(|p| specialty_code.as_ref().map_or(true, |code| p.specialties.contains(code)))
         .take(limit as usize)
         .collect();
 
@@ -600,7 +628,7 @@ pub async fn search_professionals(
             "professionals",
             "system", // Default user until auth is implemented
             false, // No PHI accessed in professional search
-            Some(serde_json::json!({"query": query, "limit": limit}))
+            Some(serde_json::json!({"query": query, "limit": limit, "specialty": specialty_code}))
         ).await;
     }
 
@@ -785,6 +813,7 @@ pub async fn update_professional_verification(
     verification_notes: Option<String>,
     firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
     _auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    dashboard_cache: State<'_, DashboardCache>,
 ) -> Result<ApiResponse<Professional>, String> {
     let auth = _auth_state.read().await;
     if !auth.is_authenticated {
@@ -811,6 +840,8 @@ pub async fn update_professional_verification(
         .await
         .map_err(|e| e.to_string())?;
 
+    dashboard_cache.invalidate(Some(DashboardEntity::Professional));
+
     // Audit log
     firebase.audit_log(
         "UPDATE_PROFESSIONAL_VERIFICATION",
@@ -830,6 +861,71 @@ pub async fn update_professional_verification(
     ))
 }
 
+/// Specialties that can be assigned to professionals and templates
+#[tauri::command]
+pub async fn list_specialties(
+    include_retired: Option<bool>,
+    taxonomy: State<'_, SpecialtyTaxonomyState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<Specialty>>, String> {
+    if !auth_state.read().await.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+
+    let mut specialties = taxonomy.get().specialties;
+    if !include_retired.unwrap_or(false) {
+        specialties.retain(|s| s.active);
+    }
+    Ok(ApiResponse::success(specialties))
+}
+
+/// Propose taxonomy codes for legacy free-text specialties; nothing is changed
+#[tauri::command]
+pub async fn map_legacy_specialties(
+    values: Vec<String>,
+    taxonomy: State<'_, SpecialtyTaxonomyState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<SpecialtyMapping>>, String> {
+    if !auth_state.read().await.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    Ok(ApiResponse::success(taxonomy.map_values(&values)))
+}
+
+/// Add a specialty, or replace one with the same code (e.g. to retire it)
+#[tauri::command]
+pub async fn upsert_specialty(
+    specialty: Specialty,
+    taxonomy: State<'_, SpecialtyTaxonomyState>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<SpecialtyTaxonomy>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !matches!(auth.get_role(), Some(HealthcareRole::SuperAdmin) | Some(HealthcareRole::Administrator)) {
+        return Err("Insufficient permissions".to_string());
+    }
+
+    taxonomy.upsert(specialty.clone())?;
+
+    let firebase = firebase.lock().await;
+    firebase.audit_log(
+        "SPECIALTY_TAXONOMY_UPDATED",
+        "specialty_taxonomy",
+        auth.user_id.as_ref().unwrap(),
+        false,
+        Some(serde_json::json!({
+            "code": specialty.code,
+            "label_en": specialty.label_en,
+            "active": specialty.active,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(taxonomy.get()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,116 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use chrono::Utc;
+
+use crate::models::{ApiResponse, NotificationMethod};
+use crate::security::auth::AuthState;
+use crate::security::HealthcareRole;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use crate::services::reminder_templates::{
+    check_template, ReminderTemplate, ReminderTemplateState, TemplateComplianceReport,
+};
+
+/// Roles allowed to change a clinic's reminder wording
+fn can_manage_templates(auth: &AuthState) -> bool {
+    matches!(
+        auth.get_role(),
+        Some(HealthcareRole::SuperAdmin)
+            | Some(HealthcareRole::Administrator)
+            | Some(HealthcareRole::AdminStaff)
+            | Some(HealthcareRole::AdministrativeStaff)
+    )
+}
+
+/// Reminder template in effect for a clinic and channel
+#[tauri::command]
+pub async fn get_reminder_template(
+    clinic_id: String,
+    channel: NotificationMethod,
+    templates: State<'_, ReminderTemplateState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<ReminderTemplate>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    drop(auth);
+
+    Ok(ApiResponse::success(templates.get(&clinic_id, channel)))
+}
+
+/// Preview the compliance check without saving
+#[tauri::command]
+pub async fn check_reminder_template(
+    template: ReminderTemplate,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<TemplateComplianceReport>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    drop(auth);
+
+    Ok(ApiResponse::success(check_template(&template)))
+}
+
+/// Save a clinic's reminder template; content that could disclose care is rejected
+#[tauri::command]
+pub async fn save_reminder_template(
+    mut template: ReminderTemplate,
+    templates: State<'_, ReminderTemplateState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<ReminderTemplate>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_manage_templates(&auth) {
+        return Err("Insufficient permissions to manage reminder templates".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    template.updated_by = Some(user_id.clone());
+    template.updated_at = Some(Utc::now());
+    let clinic_id = template.clinic_id.clone();
+    let channel = template.channel.clone();
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    match templates.save(template) {
+        Ok(saved) => {
+            firebase.audit_log(
+                "REMINDER_TEMPLATE_UPDATED",
+                "reminder_templates",
+                &user_id,
+                false,
+                Some(serde_json::json!({ "clinic_id": clinic_id, "channel": channel }))
+            ).await.map_err(|e| e.to_string())?;
+
+            Ok(ApiResponse::success_with_message(saved, "Reminder template saved".to_string()))
+        }
+        Err(report) => {
+            // Flagged attempts are recorded by kind only; the offending text stays out of the log
+            firebase.audit_log(
+                "REMINDER_TEMPLATE_REJECTED",
+                "reminder_templates",
+                &user_id,
+                false,
+                Some(serde_json::json!({
+                    "clinic_id": clinic_id,
+                    "channel": channel,
+                    "finding_kinds": report.findings.iter().map(|f| &f.kind).collect::<Vec<_>>(),
+                }))
+            ).await.map_err(|e| e.to_string())?;
+
+            Err(format!(
+                "Template rejected: {} finding(s) could disclose health information ({})",
+                report.findings.len(),
+                report.findings.iter().map(|f| f.matched_text.as_str()).collect::<Vec<_>>().join(", "),
+            ))
+        }
+    }
+}
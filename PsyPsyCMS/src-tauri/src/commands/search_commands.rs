@@ -0,0 +1,103 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use serde::Serialize;
+
+use crate::commands::patient_access_commands::log_client_accesses;
+use crate::commands::security_commands::RbacServiceState;
+use crate::services::FirebaseService;
+use crate::models::{ApiResponse, Appointment, Client, Professional};
+use crate::security::auth::AuthState;
+use crate::security::data_scope::{resolve_caller_scope, DataScopePolicy};
+use crate::security::rbac::Permission;
+use crate::storage::search_index::SearchIndexState;
+
+/// Records matching a global search, limited to what the caller may see
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchResults {
+    pub clients: Vec<Client>,
+    pub professionals: Vec<Professional>,
+    pub appointments: Vec<Appointment>,
+}
+
+/// Search clients, professionals and appointments by name prefix or exact RAMQ number,
+/// email or phone. Matching runs on the blind index, so only matched records are loaded;
+/// each entity type is returned only when the caller's role may view it, and clients and
+/// appointments are further limited to the caller's data scope.
+#[tauri::command]
+pub async fn global_search(
+    query: String,
+    search_index: State<'_, SearchIndexState>,
+    rbac: State<'_, RbacServiceState>,
+    scope_policy: State<'_, DataScopePolicy>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<GlobalSearchResults>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let role = auth.get_role().cloned().ok_or("No role in session")?;
+    let session_id = auth.session_id.clone();
+
+    let query = query.trim();
+    if query.chars().count() < 2 {
+        return Ok(ApiResponse::validation_error(
+            [("query".to_string(), vec!["Enter at least two characters".to_string()])].into_iter().collect(),
+        ));
+    }
+    let matches = search_index.search(query)?;
+
+    let firebase = firebase.lock().await;
+    let scope = resolve_caller_scope(&scope_policy, &auth, &firebase).await?;
+    drop(auth);
+
+    let mut results = GlobalSearchResults::default();
+    if rbac.0.role_grants(&role, &Permission::ViewDemographics) {
+        for id in &matches.client_ids {
+            if let Some(client) = firebase.get_document::<Client>("clients", id).await.map_err(|e| e.to_string())? {
+                if scope.includes_client(&client) {
+                    results.clients.push(client);
+                }
+            }
+        }
+    }
+    for id in &matches.professional_ids {
+        if let Some(professional) = firebase.get_document::<Professional>("professionals", id).await.map_err(|e| e.to_string())? {
+            results.professionals.push(professional);
+        }
+    }
+    if rbac.0.role_grants(&role, &Permission::ViewSchedule) {
+        for id in &matches.appointment_ids {
+            if let Some(appointment) = firebase.get_document::<Appointment>("appointments", id).await.map_err(|e| e.to_string())? {
+                if scope.includes_appointment(&appointment) {
+                    results.appointments.push(appointment);
+                }
+            }
+        }
+    }
+
+    // The query itself may be a name or RAMQ number, so only counts are recorded
+    firebase.audit_log(
+        "GLOBAL_SEARCH",
+        "search",
+        &user_id,
+        !results.clients.is_empty() || !results.appointments.is_empty(),
+        Some(serde_json::json!({
+            "scope": scope.label(),
+            "clients": results.clients.iter().map(|c| &c.object_id).collect::<Vec<_>>(),
+            "professionals": results.professionals.len(),
+            "appointments": results.appointments.iter().map(|a| &a.object_id).collect::<Vec<_>>(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+    let mut accessed: Vec<&str> = results.clients.iter().map(|c| c.object_id.as_str())
+        .chain(results.appointments.iter().map(|a| a.client_id.as_str()))
+        .collect();
+    accessed.sort_unstable();
+    accessed.dedup();
+    log_client_accesses(&user_id, Some(&role), session_id.as_deref(), accessed, "GLOBAL_SEARCH").await?;
+
+    Ok(ApiResponse::success(results))
+}
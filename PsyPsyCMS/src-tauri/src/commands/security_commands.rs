@@ -0,0 +1,938 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::services::error_reporter::{error_reporter, ErrorReport};
+use crate::services::firebase_service_simple::{AuthServiceState, CredentialReloadOutcome, FirebaseServiceState};
+use crate::commands::auth_commands::verify_totp_throttled;
+use crate::security::login_lockout::LoginLockout;
+use crate::models::ApiResponse;
+use crate::security::auth::AuthState;
+use crate::security::effective_config::{SecurityConfigSnapshot, SecurityConfigState};
+use crate::security::rbac::{stable_uuid, BreakGlassGrant, ElevatedGrant, Permission, PermissionContext, RbacService};
+use crate::security::HealthcareRole;
+use crate::security::key_escrow::{self, EscrowManifest, KeyEscrowConfig, KeyShare};
+use crate::security::access_justification::{JustificationPolicy, JustificationPolicyState};
+use crate::security::audit::{audit_log_path, AuditQuery, AuditQueryPage};
+use crate::security::audit_export::{self, AuditExportManifest, AuditExportProfiles, FieldTreatment, RedactionProfile};
+use crate::security::encryption_coverage::{self, EncryptionCoverageReport};
+use crate::security::access_heatmap::{self, AccessHeatmap, AccessHeatmapConfig, HeatmapAxis, TimeBucket};
+use crate::security::validation::{SanitizationServiceState, SanitizeFieldType, SanitizeResult};
+use crate::security::rate_limit::{ActiveBan, BanInfo, BanTarget, RateLimitServiceState};
+use chrono::{DateTime, Utc};
+use crate::commands::medical_notes_commands::StorageState;
+use crate::services::encrypted_storage::EncryptedNoteStorage;
+use tauri::{AppHandle, Manager};
+
+/// Shared RBAC service state
+#[derive(Clone)]
+pub struct RbacServiceState(pub Arc<RbacService>);
+
+impl Default for RbacServiceState {
+    fn default() -> Self {
+        Self(Arc::new(RbacService::new()))
+    }
+}
+
+/// Roles allowed to review elevated access
+fn can_review_grants(auth: &AuthState) -> bool {
+    matches!(
+        auth.get_role(),
+        Some(HealthcareRole::SuperAdmin) | Some(HealthcareRole::Administrator) | Some(HealthcareRole::Auditor)
+    )
+}
+
+/// Roles allowed to revoke elevated access and manage service identity
+fn is_security_admin(auth: &AuthState) -> bool {
+    matches!(
+        auth.get_role(),
+        Some(HealthcareRole::SuperAdmin) | Some(HealthcareRole::Administrator)
+    )
+}
+
+/// List every break-glass session and delegation currently in force
+#[tauri::command]
+pub async fn list_active_elevated_grants(
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<ElevatedGrant>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to review elevated grants".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let grants = rbac.0.list_active_elevated_grants();
+
+    // Every review of the exception paths is part of the audit trail
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "REVIEW_ELEVATED_GRANTS",
+        "elevated_grants",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "active_grants": grants.len(),
+            "grant_ids": grants.iter().map(|g| g.id).collect::<Vec<_>>(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(grants))
+}
+
+/// Open emergency access to a patient you are not assigned to, for one hour. Requires a
+/// provider or super admin, a current code from their authenticator and a justification of
+/// at least 30 characters; the grant is reviewed afterwards.
+#[tauri::command]
+pub async fn request_break_glass(
+    patient_id: String,
+    justification: String,
+    mfa_code: String,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    auth_service: State<'_, AuthServiceState>,
+    login_lockout: State<'_, LoginLockout>,
+) -> Result<ApiResponse<BreakGlassGrant>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let role = auth.get_role().cloned().ok_or("No role in session")?;
+    let session_id = auth.session_id.clone().ok_or("No active session")?;
+    drop(auth);
+
+    // Emergency access is confirmed with a fresh code from the enrolled authenticator
+    let mfa_verified = {
+        let service_guard = auth_service.0.lock().await;
+        let service = service_guard.as_ref().ok_or("Auth service not initialized")?;
+        verify_totp_throttled(service, &login_lockout, &user_id, &mfa_code)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    if mfa_verified {
+        auth_state.write().await.mark_mfa_verified();
+    }
+    let context = PermissionContext {
+        user_id: stable_uuid(&user_id),
+        role,
+        permission: Permission::ViewPHI,
+        resource_id: Some(patient_id.clone()),
+        patient_id: None,
+        ip_address: None,
+        timestamp: Utc::now(),
+        session_id,
+        mfa_verified,
+        metadata: std::collections::HashMap::new(),
+    };
+
+    let grant = rbac.0.request_break_glass(context, justification).await.map_err(|e| e.to_string())?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "BREAK_GLASS_ACCESS_GRANTED",
+        "elevated_grants",
+        &user_id,
+        false, // The grant itself carries no PHI
+        Some(serde_json::json!({
+            "event_type": "ComplianceEvent",
+            "grant_id": grant.id,
+            "client_id": patient_id,
+            "justification": grant.reason,
+            "expires_at": grant.expires_at,
+            "requires_review": true,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(grant, "Break-glass access granted; it will be reviewed".to_string()))
+}
+
+/// Revoke a break-glass session or delegation before it expires
+#[tauri::command]
+pub async fn terminate_grant(
+    grant_id: String,
+    reason: String,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<ElevatedGrant>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to terminate elevated grants".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    if reason.trim().is_empty() {
+        return Err("A reason is required to terminate a grant".to_string());
+    }
+    let grant_id = Uuid::parse_str(&grant_id)
+        .map_err(|e| format!("Invalid grant ID: {}", e))?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    let terminated = rbac.0.terminate_grant(grant_id, &user_id, &reason)
+        .map_err(|e| e.to_string())?;
+
+    firebase.audit_log(
+        "TERMINATE_ELEVATED_GRANT",
+        "elevated_grants",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "grant_id": terminated.id,
+            "kind": terminated.kind,
+            "grantee_user_id": terminated.grantee_user_id,
+            "grantor_user_id": terminated.grantor_user_id,
+            "patient_id": terminated.patient_id,
+            "original_expiry": terminated.expires_at,
+            "reason": reason,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(terminated, "Grant terminated".to_string()))
+}
+
+/// Force a service-account credential reload after a key rotation
+#[tauri::command]
+pub async fn reload_firebase_credentials(
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<CredentialReloadOutcome>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to reload service credentials".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    // The reload is audited by the service whether it succeeds or not
+    let outcome = firebase.reload_credentials(&user_id)
+        .await
+        .map_err(|e| format!("Credential reload failed, previous credential retained: {}", e))?;
+
+    Ok(ApiResponse::success(outcome))
+}
+
+/// Export the running security configuration (secrets redacted) for auditor sign-off
+#[tauri::command]
+pub async fn get_effective_security_config(
+    config_state: State<'_, SecurityConfigState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<SecurityConfigSnapshot>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !matches!(auth.get_role(), Some(HealthcareRole::SuperAdmin)) {
+        return Err("Only super administrators can export the security configuration".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let snapshot = config_state.snapshot_for_review();
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    // Drift since the last recorded baseline gets its own entry
+    if snapshot.changed_since_last_review {
+        firebase.audit_log(
+            "SECURITY_CONFIG_CHANGED",
+            "security_config",
+            &user_id,
+            false,
+            Some(serde_json::json!({
+                "previous_hash": snapshot.previous_hash,
+                "config_hash": snapshot.config_hash,
+            }))
+        ).await.map_err(|e| e.to_string())?;
+    }
+
+    firebase.audit_log(
+        "SECURITY_CONFIG_REVIEWED",
+        "security_config",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "config_hash": snapshot.config_hash,
+            "generated_at": snapshot.generated_at,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(snapshot))
+}
+
+/// View locally stored, PHI-scrubbed error reports
+#[tauri::command]
+pub async fn list_error_reports(
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<ErrorReport>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to view error reports".to_string());
+    }
+    drop(auth);
+
+    let reports = error_reporter().map(|r| r.list()).unwrap_or_default();
+    Ok(ApiResponse::success(reports))
+}
+
+/// Delete every locally stored error report
+#[tauri::command]
+pub async fn clear_error_reports(
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<usize>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to clear error reports".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let removed = error_reporter().map(|r| r.clear()).unwrap_or(0);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "ERROR_REPORTS_CLEARED",
+        "error_reports",
+        &user_id,
+        false,
+        Some(serde_json::json!({ "removed": removed }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(removed, format!("{} error reports cleared", removed)))
+}
+
+/// Split the note storage master key into one share per custodian.
+/// Shares are returned once for hand-off and never stored; only the manifest is kept.
+/// `threshold` may raise the configured quorum but not lower it.
+#[tauri::command]
+pub async fn create_key_escrow(
+    custodian_ids: Vec<String>,
+    threshold: Option<u8>,
+    app_handle: AppHandle,
+    escrow_config: State<'_, KeyEscrowConfig>,
+    storage_state: State<'_, StorageState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<KeyShare>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to manage key escrow".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    if !escrow_config.enabled {
+        return Err("Key escrow is disabled for this deployment".to_string());
+    }
+    // A caller may ask for a stricter quorum, never a weaker one than KEY_ESCROW_THRESHOLD
+    let threshold = threshold.unwrap_or(escrow_config.threshold);
+    if threshold < escrow_config.threshold {
+        return Err(format!(
+            "Key escrow threshold cannot be lower than the configured {}",
+            escrow_config.threshold
+        ));
+    }
+    if custodian_ids.len() != escrow_config.total_shares as usize {
+        return Err(format!("Key escrow requires exactly {} custodians", escrow_config.total_shares));
+    }
+
+    let (manifest, shares) = {
+        let storage_guard = storage_state.lock().await;
+        let storage = storage_guard.as_ref().ok_or("Storage not initialized")?;
+        storage.escrow_master_key(threshold, &custodian_ids, &user_id).map_err(|e| e.to_string())?
+    };
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    manifest.save(&data_dir).map_err(|e| e.to_string())?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "KEY_ESCROW_SHARES_DISTRIBUTED",
+        "key_escrow",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "escrow_id": manifest.escrow_id,
+            "threshold": manifest.threshold,
+            "total_shares": manifest.total_shares,
+            "custodians": manifest.custodians,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(
+        shares,
+        format!("Master key split into {} shares; {} required for recovery", manifest.total_shares, manifest.threshold),
+    ))
+}
+
+/// Current escrow manifest, if one was created
+#[tauri::command]
+pub async fn get_key_escrow_manifest(
+    app_handle: AppHandle,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Option<EscrowManifest>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to view key escrow".to_string());
+    }
+    drop(auth);
+
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let manifest = EscrowManifest::load(&data_dir).map_err(|e| e.to_string())?;
+    Ok(ApiResponse::success(manifest))
+}
+
+/// Rebuild the master key from a quorum of custodian shares and reopen note storage with it.
+/// Every attempt is audited, successful or not.
+#[tauri::command]
+pub async fn recover_master_key(
+    shares: Vec<KeyShare>,
+    app_handle: AppHandle,
+    storage_state: State<'_, StorageState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<String>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to recover the master key".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    let presented: Vec<&str> = shares.iter().map(|s| s.custodian_id.as_str()).collect();
+
+    let outcome: Result<(EscrowManifest, EncryptedNoteStorage), String> = (|| {
+        let manifest = EscrowManifest::load(&data_dir)
+            .map_err(|e| e.to_string())?
+            .ok_or("No key escrow has been created")?;
+        let mut recovered = key_escrow::recover_master_key(&manifest, &shares).map_err(|e| e.to_string())?;
+        let key: Result<[u8; 32], _> = recovered.as_slice().try_into();
+        zeroize::Zeroize::zeroize(&mut recovered);
+        let key = key.map_err(|_| "Recovered key has an unexpected length".to_string())?;
+        let db_path = EncryptedNoteStorage::default_db_path(&app_handle).map_err(|e| e.to_string())?;
+        let storage = EncryptedNoteStorage::open_with_key(db_path, key).map_err(|e| e.to_string())?;
+        Ok((manifest, storage))
+    })();
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    let (action, details) = match &outcome {
+        Ok((manifest, _)) => ("MASTER_KEY_RECOVERED", serde_json::json!({
+            "escrow_id": manifest.escrow_id,
+            "threshold": manifest.threshold,
+            "custodians_presented": presented,
+        })),
+        Err(error) => ("MASTER_KEY_RECOVERY_FAILED", serde_json::json!({
+            "custodians_presented": presented,
+            "error": error,
+        })),
+    };
+    firebase.audit_log(action, "key_escrow", &user_id, false, Some(details))
+        .await
+        .map_err(|e| e.to_string())?;
+    drop(firebase_guard);
+
+    let (_, storage) = outcome?;
+    *storage_state.lock().await = Some(storage);
+    Ok(ApiResponse::success("Master key recovered; encrypted storage reopened".to_string()))
+}
+
+/// Current reason-for-access policy
+#[tauri::command]
+pub async fn get_justification_policy(
+    policy: State<'_, JustificationPolicyState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<JustificationPolicy>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to view the access justification policy".to_string());
+    }
+
+    Ok(ApiResponse::success(policy.get()))
+}
+
+/// Replace the reason-for-access policy (classifications and flagged patients)
+#[tauri::command]
+pub async fn update_justification_policy(
+    new_policy: JustificationPolicy,
+    policy: State<'_, JustificationPolicyState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<JustificationPolicy>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_security_admin(&auth) {
+        return Err("Insufficient permissions to change the access justification policy".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    if new_policy.min_length == 0 {
+        return Err("Minimum justification length must be at least 1".to_string());
+    }
+    policy.update(new_policy.clone());
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "JUSTIFICATION_POLICY_UPDATED",
+        "access_justification_policy",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "classifications": new_policy.classifications,
+            "flagged_patient_count": new_policy.flagged_patients.len(),
+            "min_length": new_policy.min_length,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(new_policy))
+}
+
+/// Redaction profiles available for audit exports
+#[tauri::command]
+pub async fn list_audit_export_profiles(
+    profiles: State<'_, AuditExportProfiles>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<RedactionProfile>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to export audit logs".to_string());
+    }
+
+    Ok(ApiResponse::success(profiles.list()))
+}
+
+/// Export the audit log for external sharing under a named redaction profile.
+/// Returns the manifest; the file (events plus manifest) is written to the app data directory.
+#[tauri::command]
+pub async fn export_audit_log(
+    profile: String,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    app_handle: AppHandle,
+    profiles: State<'_, AuditExportProfiles>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<AuditExportManifest>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to export audit logs".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let redaction = profiles.get(&profile).ok_or_else(|| format!("Unknown redaction profile: {}", profile))?;
+    let log_path = audit_log_path().ok_or("Audit log is not file-backed")?;
+    let events = audit_export::read_audit_log(&log_path, start_date, end_date).map_err(|e| e.to_string())?;
+    let export = audit_export::build_audit_export(&events, &redaction, &user_id, start_date, end_date)
+        .map_err(|e| e.to_string())?;
+
+    let export_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?.join("audit_exports");
+    let path = audit_export::write_audit_export(&export_dir, &export).map_err(|e| e.to_string())?;
+
+    // Anything short of full redaction of payloads can carry PHI
+    let phi_included = ["before_state", "after_state", "metadata", "description"]
+        .iter()
+        .any(|field| redaction.treatment_for(field) == FieldTreatment::Include);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "AUDIT_LOG_EXPORTED",
+        "audit_log",
+        &user_id,
+        phi_included,
+        Some(serde_json::json!({
+            "event_type": "PatientDataExported",
+            "export_id": export.manifest.export_id,
+            "profile": export.manifest.profile,
+            "event_count": export.manifest.event_count,
+            "content_sha256": export.manifest.content_sha256,
+            "file": path.file_name().map(|n| n.to_string_lossy().to_string()),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(
+        export.manifest,
+        format!("Audit export written to {}", path.display()),
+    ))
+}
+
+/// Per-user PHI access counts by patient or time bucket, with outliers flagged, built from the
+/// audit log alone. Access frequency only: no clinical content or audit payloads are returned.
+#[tauri::command]
+pub async fn get_access_heatmap(
+    axis: Option<HeatmapAxis>,
+    bucket: Option<TimeBucket>,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    config: State<'_, AccessHeatmapConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<AccessHeatmap>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to review access patterns".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let axis = axis.unwrap_or(HeatmapAxis::Patients);
+    let bucket = bucket.unwrap_or(TimeBucket::Day);
+    let end = end_date.unwrap_or_else(Utc::now);
+    let start = start_date.unwrap_or(end - config.lookback());
+    if start >= end {
+        return Err("Start date must be before end date".to_string());
+    }
+
+    let log_path = audit_log_path().ok_or("Audit log is not file-backed")?;
+    let events = audit_export::read_audit_log(&log_path, Some(start), Some(end)).map_err(|e| e.to_string())?;
+    let heatmap = access_heatmap::build_heatmap(&events, axis, bucket, start, end, &config);
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "ACCESS_HEATMAP_VIEWED",
+        "audit_log",
+        &user_id,
+        false, // Access counts only
+        Some(serde_json::json!({
+            "axis": axis,
+            "bucket": heatmap.bucket,
+            "start": start,
+            "end": end,
+            "users": heatmap.rows.len(),
+            "outliers": heatmap.rows.iter().filter(|r| r.outlier).count(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(heatmap))
+}
+
+/// Audit records matching a structured filter, one page at a time (at most 500 rows) with the
+/// total match count. Requires `ViewAuditLogs`; every query is itself audited.
+#[tauri::command]
+pub async fn query_audit_events(
+    query: AuditQuery,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<AuditQueryPage>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let role = auth.get_role().cloned().ok_or("No role in session")?;
+    let context = PermissionContext {
+        user_id: Uuid::parse_str(&user_id).unwrap_or(Uuid::nil()),
+        role,
+        permission: Permission::ViewAuditLogs,
+        resource_id: None,
+        patient_id: query.patient_id,
+        ip_address: None,
+        timestamp: Utc::now(),
+        // Stable for the session so the RBAC cache is reused rather than grown per call
+        session_id: format!("{}:{}", user_id, auth.session_expires_at.map_or(0, |t| t.timestamp())),
+        mfa_verified: auth.mfa_verified_at.is_some(),
+        metadata: std::collections::HashMap::new(),
+    };
+    drop(auth);
+
+    let decision = rbac.0.check_permission(context).await.map_err(|e| e.to_string())?;
+    if !decision.granted {
+        return Err(decision.denial_reason.unwrap_or_else(|| "Insufficient permissions to view audit logs".to_string()));
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err("Start date must not be after end date".to_string());
+        }
+    }
+
+    let log_path = audit_log_path().ok_or("Audit log is not file-backed")?;
+    let page = query.page_from_log(&log_path).map_err(|e| e.to_string())?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "AUDIT_LOG_QUERIED",
+        "audit_log",
+        &user_id,
+        // Records name patients and can carry before/after states
+        page.events.iter().any(|e| e.patient_id.is_some() || e.before_state.is_some() || e.after_state.is_some()),
+        Some(serde_json::json!({
+            "filter": {
+                "user_id": query.user_id,
+                "patient_id": query.patient_id,
+                "event_type": query.event_type,
+                "from": query.from,
+                "to": query.to,
+                "outcome": query.outcome,
+            },
+            "offset": page.offset,
+            "limit": page.limit,
+            "returned": page.events.len(),
+            "has_more": page.has_more,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(page))
+}
+
+/// Check that every stored artifact for a patient is encrypted at the level its classification
+/// requires. Only envelope metadata is read; plaintext or under-encrypted PHI is reported as a
+/// compliance violation.
+#[tauri::command]
+pub async fn verify_record_encryption(
+    patient_id: String,
+    transcript_paths: Option<Vec<String>>,
+    storage_state: State<'_, StorageState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<EncryptionCoverageReport>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_grants(&auth) {
+        return Err("Insufficient permissions to verify record encryption".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let mut envelopes = {
+        let storage_guard = storage_state.lock().await;
+        let storage = storage_guard.as_ref().ok_or("Storage not initialized")?;
+        storage.encryption_envelopes(&patient_id).map_err(|e| e.to_string())?
+    };
+    // Transcripts are saved at caller-chosen paths, so the caller names the patient's files
+    envelopes.extend(
+        transcript_paths
+            .unwrap_or_default()
+            .iter()
+            .map(|path| encryption_coverage::inspect_transcript_file(std::path::Path::new(path))),
+    );
+    let report = encryption_coverage::build_coverage_report(
+        &patient_id,
+        &user_id,
+        &envelopes,
+        vec!["offline document cache (offline service disabled in this build)".to_string()],
+    );
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    for violation in &report.violations {
+        firebase.audit_log(
+            "COMPLIANCE_VIOLATION_DETECTED",
+            "encryption_coverage",
+            &user_id,
+            false,
+            Some(serde_json::json!({
+                "violation_id": violation.violation_id,
+                "violation_type": violation.violation_type,
+                "severity": violation.severity,
+                "requirement_id": violation.requirement_id,
+                "patient_id": patient_id,
+                "description": violation.description,
+            }))
+        ).await.map_err(|e| e.to_string())?;
+    }
+    firebase.audit_log(
+        "RECORD_ENCRYPTION_VERIFIED",
+        "encryption_coverage",
+        &user_id,
+        false, // Envelope metadata only
+        Some(serde_json::json!({
+            "patient_id": patient_id,
+            "artifacts": report.artifacts.len(),
+            "violations": report.violations.len(),
+            "fully_protected": report.fully_protected,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(report))
+}
+
+/// Pre-validate a form field: strip control characters and neutralize markup and script injection
+#[tauri::command]
+pub async fn sanitize_input(
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+    sanitizer: State<'_, SanitizationServiceState>,
+    field_type: SanitizeFieldType,
+    value: String,
+) -> Result<ApiResponse<SanitizeResult>, String> {
+    if !auth_state.read().await.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    Ok(ApiResponse::success(sanitizer.0.sanitize_field(field_type, &value)))
+}
+
+/// Whether the session's role grants `permission`, decided by RBAC so the command agrees with
+/// its entry in the command policy table
+fn session_role_grants(rbac: &RbacServiceState, auth: &AuthState, permission: Permission) -> bool {
+    auth.get_role().map_or(false, |role| rbac.0.role_grants(role, &permission))
+}
+
+/// List IP and user bans currently in force
+#[tauri::command]
+pub async fn list_active_bans(
+    rate_limits: State<'_, RateLimitServiceState>,
+    rbac: State<'_, RbacServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<Vec<ActiveBan>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !session_role_grants(&rbac, &auth, Permission::ViewSecurityReports) {
+        return Err("Insufficient permissions to view bans".to_string());
+    }
+    drop(auth);
+
+    Ok(ApiResponse::success(rate_limits.0.list_active_bans()))
+}
+
+/// Lift a ban on an IP address before it expires
+#[tauri::command]
+pub async fn unban_ip(
+    ip_address: String,
+    rate_limits: State<'_, RateLimitServiceState>,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<BanInfo>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !session_role_grants(&rbac, &auth, Permission::ManageUserSessions) {
+        return Err("Insufficient permissions to lift bans".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let ip: std::net::IpAddr = ip_address.trim().parse()
+        .map_err(|e| format!("Invalid IP address: {}", e))?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    // Audited before the ban is lifted, so a failed audit write leaves the ban in place
+    let ban = rate_limits.0.active_ban(&BanTarget::Ip(ip))
+        .ok_or_else(|| format!("No active ban for IP {}", ip))?;
+    firebase.audit_log(
+        "BAN_LIFTED",
+        "rate_limit_bans",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "ip_address": ip,
+            "banned_at": ban.banned_at,
+            "ban_reason": ban.reason,
+            "lifted_by": user_id,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    let lifted = rate_limits.0.unban_ip(ip, &user_id).map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(lifted, "Ban lifted".to_string()))
+}
+
+/// Lift a ban on a user before it expires
+#[tauri::command]
+pub async fn unban_user(
+    banned_user_id: String,
+    rate_limits: State<'_, RateLimitServiceState>,
+    rbac: State<'_, RbacServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<BanInfo>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !session_role_grants(&rbac, &auth, Permission::ManageUserSessions) {
+        return Err("Insufficient permissions to lift bans".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let banned_user_id = Uuid::parse_str(&banned_user_id)
+        .map_err(|e| format!("Invalid user ID: {}", e))?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    // Audited before the ban is lifted, so a failed audit write leaves the ban in place
+    let ban = rate_limits.0.active_ban(&BanTarget::User(banned_user_id))
+        .ok_or_else(|| format!("No active ban for user {}", banned_user_id))?;
+    firebase.audit_log(
+        "BAN_LIFTED",
+        "rate_limit_bans",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "banned_user_id": banned_user_id,
+            "banned_at": ban.banned_at,
+            "ban_reason": ban.reason,
+            "lifted_by": user_id,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    let lifted = rate_limits.0.unban_user(banned_user_id, &user_id).map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success_with_message(lifted, "Ban lifted".to_string()))
+}
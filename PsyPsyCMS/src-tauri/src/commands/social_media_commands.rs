@@ -2,6 +2,11 @@ use tauri::State;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use std::collections::HashMap;
+use crate::services::media_moderation::{MediaScanResult, MediaScannerState};
+use crate::services::media_validation::{self, MediaValidationConfig};
+use crate::services::phi_detector::phi_detector;
+use crate::compliance::consent_receipts::{ConsentCategory, ConsentGrant, ConsentReceiptConfig, ConsentReceiptState};
+use crate::security::auth::AuthState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SocialMediaPost {
@@ -14,6 +19,9 @@ pub struct SocialMediaPost {
     pub compliance: PostComplianceData,
     pub created_at: String,
     pub updated_at: String,
+    /// User who scheduled or published the post; set server-side from the session
+    #[serde(default)]
+    pub author_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +41,9 @@ pub struct MediaComplianceData {
     pub contains_phi: bool,
     pub compliance_checked: bool,
     pub approved_for_sharing: bool,
+    /// OCR moderation result, recorded before the post is queued or published
+    #[serde(default)]
+    pub scan: Option<MediaScanResult>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +64,9 @@ pub struct PostComplianceData {
     pub reviewed_by: Option<String>,
     pub reviewed_at: Option<String>,
     pub compliance_notes: Option<String>,
+    /// Consent version in effect per platform when the post was published
+    #[serde(default)]
+    pub consent_versions: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +121,29 @@ pub struct ConsentStatus {
     pub social_media_sharing_consent: bool,
     pub consent_date: Option<String>,
     pub consent_version: String,
+    #[serde(default)]
+    pub withdrawn_at: Option<String>,
+    /// Consent record holding the signed receipts
+    #[serde(default)]
+    pub consent_id: Option<String>,
+}
+
+/// Consent requirements enforced before anything is posted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocialMediaConsentPolicy {
+    /// Consent/acknowledgment versions currently accepted
+    pub accepted_versions: Vec<String>,
+    /// Require the Quebec Law 25 acknowledgment in addition to sharing consent
+    pub require_law25_acknowledgment: bool,
+}
+
+impl Default for SocialMediaConsentPolicy {
+    fn default() -> Self {
+        Self {
+            accepted_versions: vec!["1.0".to_string()],
+            require_law25_acknowledgment: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -170,36 +207,117 @@ pub struct SocialMediaState {
     pub oauth_configs: Mutex<HashMap<String, OAuthCredentials>>,
     pub scheduled_posts: Mutex<Vec<SocialMediaPost>>,
     pub published_posts: Mutex<Vec<SocialMediaPost>>,
-    pub consent_records: Mutex<HashMap<String, ConsentStatus>>,
+    /// Consent by (user ID, platform); one professional's consent never covers another's posts
+    pub consent_records: Mutex<HashMap<(String, String), ConsentStatus>>,
+    pub consent_policy: Mutex<SocialMediaConsentPolicy>,
+}
+
+/// Verify `user_id` holds a valid, non-withdrawn consent for every enabled target platform.
+///
+/// Returns the consent version in effect per platform so the post can be tied to it.
+fn check_posting_consent(
+    post: &SocialMediaPost,
+    user_id: &str,
+    consent_records: &HashMap<(String, String), ConsentStatus>,
+    policy: &SocialMediaConsentPolicy,
+) -> Result<HashMap<String, String>, String> {
+    let mut versions = HashMap::new();
+
+    for platform in post.platforms.iter().filter(|p| p.enabled) {
+        let consent = consent_records.get(&(user_id.to_string(), platform.platform.clone())).ok_or_else(|| {
+            format!("No social media consent recorded for {}; record consent before posting", platform.platform)
+        })?;
+
+        if consent.withdrawn_at.is_some() {
+            return Err(format!("Social media consent for {} has been withdrawn", platform.platform));
+        }
+        if !consent.social_media_sharing_consent
+            || (policy.require_law25_acknowledgment && !consent.quebec_law25_consent)
+        {
+            return Err(format!(
+                "Social media consent for {} does not cover the required compliance obligations",
+                platform.platform
+            ));
+        }
+        if !policy.accepted_versions.contains(&consent.consent_version) {
+            return Err(format!(
+                "Consent version {} for {} is no longer accepted; please review and re-acknowledge",
+                consent.consent_version, platform.platform
+            ));
+        }
+
+        versions.insert(platform.platform.clone(), consent.consent_version.clone());
+    }
+
+    if versions.is_empty() {
+        return Err("Post has no enabled platforms".to_string());
+    }
+    Ok(versions)
+}
+
+/// Check every attachment's type, size and reachability against the enabled platforms' limits.
+/// Verified values replace the client-declared ones; all failing items are reported together.
+async fn validate_post_media(post: &mut SocialMediaPost, config: &MediaValidationConfig) -> Result<(), String> {
+    let platforms: Vec<String> = post.platforms.iter().filter(|p| p.enabled).map(|p| p.platform.clone()).collect();
+    let mut errors = Vec::new();
+
+    for media in post.media.iter_mut() {
+        match media_validation::validate_media(&media.url, Some(&media.mime_type), &platforms, config).await {
+            Ok(validated) => {
+                media.mime_type = validated.mime_type;
+                media.size = validated.size;
+            }
+            Err(error) => errors.push(format!("{}: {}", media.filename, error)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Media validation failed: {}", errors.join("; ")))
+    }
 }
 
-// PHI Detection patterns (simplified for demo)
+/// Scan every attachment and record the result on it; fails if any attachment is blocked
+async fn moderate_post_media(post: &mut SocialMediaPost, scanner: &MediaScannerState) -> Result<(), String> {
+    let action = scanner.0.config().social_action;
+    let mut blocked = Vec::new();
+
+    for media in post.media.iter_mut() {
+        let scan = scanner.0.scan_media(&media.url, action).await;
+        media.compliance.compliance_checked = true;
+        media.compliance.contains_phi = scan.contains_phi();
+        media.compliance.approved_for_sharing = !scan.is_blocked() && !scan.contains_phi();
+        if scan.is_blocked() {
+            blocked.push(media.filename.clone());
+        }
+        media.compliance.scan = Some(scan);
+    }
+
+    if blocked.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Media failed the content scan and cannot be shared: {}", blocked.join(", ")))
+    }
+}
+
+// Identifiers found by the shared PHI detector; spans are byte offsets for highlighting
 fn detect_phi_in_content_internal(content: &str) -> PHIDetectionResult {
     let mut detected_elements = Vec::new();
     let mut confidence_scores = Vec::new();
 
-    // Basic PHI patterns
-    let patterns = [
-        (r"\b\d{3}[-.]?\d{3}[-.]?\d{4}\b", "PHONE", 0.8),
-        (r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Z|a-z]{2,}\b", "EMAIL", 0.9),
-        (r"\b(RAMQ|Medicare|Health Card)[\s#:]*\d+", "MEDICAL_ID", 0.95),
-        (r"\b\d{4}[-/]\d{2}[-/]\d{2}\b", "DATE", 0.7),
-        (r"\b\d+\s+[A-Za-z\s]+\s+(Street|St|Ave|Avenue|Road|Rd|Blvd|Boulevard)", "ADDRESS", 0.8),
-    ];
-
-    for (pattern, phi_type, confidence) in patterns {
-        if let Ok(regex) = regex::Regex::new(pattern) {
-            for mat in regex.find_iter(content) {
-                detected_elements.push(PHIElement {
-                    element_type: phi_type.to_string(),
-                    value: mat.as_str().to_string(),
-                    start_index: mat.start(),
-                    end_index: mat.end(),
-                    confidence,
-                });
-                confidence_scores.push(confidence);
-            }
-        }
+    for phi in phi_detector().detect(content) {
+        detected_elements.push(PHIElement {
+            element_type: serde_json::to_value(phi.phi_type)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            value: phi.text(content).to_string(),
+            start_index: phi.start,
+            end_index: phi.end,
+            confidence: phi.confidence,
+        });
+        confidence_scores.push(phi.confidence);
     }
 
     let overall_confidence = if confidence_scores.is_empty() {
@@ -357,19 +475,103 @@ pub async fn save_oauth_config(
 #[tauri::command]
 pub async fn record_social_media_consent(
     platform: String,
-    consent_data: ConsentStatus,
+    mut consent_data: ConsentStatus,
     state: State<'_, SocialMediaState>,
+    receipts: State<'_, ConsentReceiptState>,
+    receipt_config: State<'_, ConsentReceiptConfig>,
+    auth_state: State<'_, std::sync::Arc<tokio::sync::RwLock<AuthState>>>,
 ) -> Result<CommandResult<String>, String> {
+    let Some(user_id) = auth_state.read().await.user_id.clone() else {
+        return Ok(CommandResult {
+            success: false,
+            data: None,
+            error: Some("Sign in to record consent".to_string()),
+        });
+    };
+
+    // The professional receives a signed receipt for what they agreed to
+    let receipt = receipts.grant(&receipt_config, ConsentGrant {
+        subject_id: user_id.clone(),
+        category: ConsentCategory::SocialMedia,
+        purposes: vec![format!("Publish professional content to {}", platform)],
+        pii_categories: vec!["professional profile".to_string(), "published content".to_string()],
+        third_party_disclosure: true,
+        expires_at: None,
+        collection_method: format!("in-app consent form v{}", consent_data.consent_version),
+    }).map_err(|e| e.to_string())?;
+    consent_data.consent_id = Some(receipt.receipt.consent_id.to_string());
+
     let mut consent_records = state.consent_records.lock().await;
-    consent_records.insert(platform.clone(), consent_data);
+    consent_records.insert((user_id, platform.clone()), consent_data);
+
+    Ok(CommandResult {
+        success: true,
+        data: Some(receipt.receipt.consent_receipt_id),
+        error: None,
+    })
+}
+
+/// Withdraw the caller's consent for a platform: blocks their further posts and cancels their
+/// scheduled ones
+#[tauri::command]
+pub async fn withdraw_social_media_consent(
+    platform: String,
+    state: State<'_, SocialMediaState>,
+    receipts: State<'_, ConsentReceiptState>,
+    receipt_config: State<'_, ConsentReceiptConfig>,
+    auth_state: State<'_, std::sync::Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<CommandResult<usize>, String> {
+    let user_id = signed_in_user(&auth_state).await?;
+    {
+        let mut consent_records = state.consent_records.lock().await;
+        match consent_records.get_mut(&(user_id.clone(), platform.clone())) {
+            Some(consent) => {
+                consent.withdrawn_at = Some(chrono::Utc::now().to_rfc3339());
+                consent.social_media_sharing_consent = false;
+                let consent_id = consent.consent_id.as_deref().and_then(|id| uuid::Uuid::parse_str(id).ok());
+                if let Some(consent_id) = consent_id {
+                    if let Err(e) = receipts.withdraw(&receipt_config, consent_id, "social media settings") {
+                        tracing::warn!("No withdrawal receipt issued for {}: {}", platform, e);
+                    }
+                }
+            }
+            None => {
+                return Ok(CommandResult {
+                    success: false,
+                    data: None,
+                    error: Some(format!("No consent recorded for {}", platform)),
+                });
+            }
+        }
+    }
+
+    // Drop the platform from the caller's scheduled posts; posts left without targets are cancelled
+    let mut scheduled_posts = state.scheduled_posts.lock().await;
+    let before = scheduled_posts.len();
+    for post in scheduled_posts.iter_mut().filter(|post| post.author_id.as_deref() == Some(user_id.as_str())) {
+        post.platforms.retain(|p| p.platform != platform);
+    }
+    scheduled_posts.retain(|post| post.platforms.iter().any(|p| p.enabled));
+    let cancelled = before - scheduled_posts.len();
+
+    tracing::info!("Social media consent withdrawn for {}; {} scheduled posts cancelled", platform, cancelled);
 
     Ok(CommandResult {
         success: true,
-        data: Some(format!("Consent recorded for {}", platform)),
+        data: Some(cancelled),
         error: None,
     })
 }
 
+/// User ID of the signed-in caller
+async fn signed_in_user(auth_state: &std::sync::Arc<tokio::sync::RwLock<AuthState>>) -> Result<String, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    auth.user_id.clone().ok_or_else(|| "No user ID in auth state".to_string())
+}
+
 #[tauri::command]
 pub async fn initiate_oauth_flow(
     platform: String,
@@ -485,9 +687,50 @@ pub async fn calculate_compliance_metrics(
 
 #[tauri::command]
 pub async fn publish_social_media_post(
-    post: SocialMediaPost,
+    mut post: SocialMediaPost,
     state: State<'_, SocialMediaState>,
+    scanner: State<'_, MediaScannerState>,
+    media_validation: State<'_, MediaValidationConfig>,
+    auth_state: State<'_, std::sync::Arc<tokio::sync::RwLock<AuthState>>>,
 ) -> Result<CommandResult<String>, String> {
+    let user_id = signed_in_user(&auth_state).await?;
+    post.author_id = Some(user_id.clone());
+
+    // Consent is the caller's own server-side record, never the post's flags
+    let consent_versions = {
+        let consent_records = state.consent_records.lock().await;
+        let policy = state.consent_policy.lock().await;
+        check_posting_consent(&post, &user_id, &consent_records, &policy)
+    };
+    let consent_versions = match consent_versions {
+        Ok(versions) => versions,
+        Err(error) => {
+            return Ok(CommandResult {
+                success: false,
+                data: None,
+                error: Some(error),
+            });
+        }
+    };
+    post.compliance.consent_obtained = true;
+    post.compliance.consent_versions = consent_versions;
+
+    // Media is validated and scanned at publish time even if it was checked when scheduled
+    if let Err(error) = validate_post_media(&mut post, &media_validation).await {
+        return Ok(CommandResult {
+            success: false,
+            data: None,
+            error: Some(error),
+        });
+    }
+    if let Err(error) = moderate_post_media(&mut post, &scanner).await {
+        return Ok(CommandResult {
+            success: false,
+            data: None,
+            error: Some(error),
+        });
+    }
+
     // Validate compliance before publishing
     let compliance_result = validate_quebec_compliance(&post);
     if !compliance_result.compliant {
@@ -516,9 +759,43 @@ pub async fn publish_social_media_post(
 
 #[tauri::command]
 pub async fn schedule_social_media_post(
-    post: SocialMediaPost,
+    mut post: SocialMediaPost,
     state: State<'_, SocialMediaState>,
+    scanner: State<'_, MediaScannerState>,
+    media_validation: State<'_, MediaValidationConfig>,
+    auth_state: State<'_, std::sync::Arc<tokio::sync::RwLock<AuthState>>>,
 ) -> Result<CommandResult<String>, String> {
+    let user_id = signed_in_user(&auth_state).await?;
+    post.author_id = Some(user_id.clone());
+
+    // Refuse to queue posts that could not be published under the caller's current consent
+    {
+        let consent_records = state.consent_records.lock().await;
+        let policy = state.consent_policy.lock().await;
+        if let Err(error) = check_posting_consent(&post, &user_id, &consent_records, &policy) {
+            return Ok(CommandResult {
+                success: false,
+                data: None,
+                error: Some(error),
+            });
+        }
+    }
+
+    if let Err(error) = validate_post_media(&mut post, &media_validation).await {
+        return Ok(CommandResult {
+            success: false,
+            data: None,
+            error: Some(error),
+        });
+    }
+    if let Err(error) = moderate_post_media(&mut post, &scanner).await {
+        return Ok(CommandResult {
+            success: false,
+            data: None,
+            error: Some(error),
+        });
+    }
+
     // Validate compliance before scheduling
     let compliance_result = validate_quebec_compliance(&post);
     if !compliance_result.compliant {
@@ -569,4 +846,82 @@ pub async fn get_published_posts(
         data: Some(limited_posts),
         error: None,
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn consent(version: &str) -> ConsentStatus {
+        ConsentStatus {
+            quebec_law25_consent: true,
+            data_processing_consent: true,
+            social_media_sharing_consent: true,
+            consent_date: Some("2025-01-01T00:00:00Z".to_string()),
+            consent_version: version.to_string(),
+            withdrawn_at: None,
+            consent_id: None,
+        }
+    }
+
+    fn post_for(platform: &str) -> SocialMediaPost {
+        SocialMediaPost {
+            id: "post-1".to_string(),
+            content: "Conseils pour mieux dormir".to_string(),
+            media: vec![],
+            scheduled_at: None,
+            status: "draft".to_string(),
+            platforms: vec![PlatformConfig {
+                platform: platform.to_string(),
+                account_id: "acct".to_string(),
+                settings: HashMap::new(),
+                enabled: true,
+            }],
+            compliance: PostComplianceData {
+                contains_medical_content: false,
+                contains_phi: false,
+                quebec_law25_compliant: true,
+                professional_order_approved: true,
+                consent_obtained: true,
+                reviewed_by: None,
+                reviewed_at: None,
+                compliance_notes: None,
+                consent_versions: HashMap::new(),
+            },
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            updated_at: "2025-01-01T00:00:00Z".to_string(),
+            author_id: None,
+        }
+    }
+
+    fn key(user: &str, platform: &str) -> (String, String) {
+        (user.to_string(), platform.to_string())
+    }
+
+    #[test]
+    fn test_posting_requires_recorded_consent() {
+        let policy = SocialMediaConsentPolicy::default();
+        let mut records = HashMap::new();
+        assert!(check_posting_consent(&post_for("linkedin"), "user-1", &records, &policy).is_err());
+
+        records.insert(key("user-1", "linkedin"), consent("1.0"));
+        let versions = check_posting_consent(&post_for("linkedin"), "user-1", &records, &policy).unwrap();
+        assert_eq!(versions.get("linkedin").map(String::as_str), Some("1.0"));
+
+        // Another professional's consent does not cover this user's posts
+        assert!(check_posting_consent(&post_for("linkedin"), "user-2", &records, &policy).is_err());
+    }
+
+    #[test]
+    fn test_withdrawn_or_outdated_consent_blocks_posting() {
+        let policy = SocialMediaConsentPolicy::default();
+        let mut records = HashMap::new();
+
+        let mut withdrawn = consent("1.0");
+        withdrawn.withdrawn_at = Some("2025-02-01T00:00:00Z".to_string());
+        records.insert(key("user-1", "linkedin"), withdrawn);
+        assert!(check_posting_consent(&post_for("linkedin"), "user-1", &records, &policy).is_err());
+
+        records.insert(key("user-1", "linkedin"), consent("0.9"));
+        assert!(check_posting_consent(&post_for("linkedin"), "user-1", &records, &policy).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use tauri::State;
+use tokio::sync::RwLock;
+use std::collections::HashSet;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::commands::medical_notes_commands::StorageState;
+use crate::commands::patient_access_commands::{authorize_patient_access, PatientDataRead};
+use crate::commands::security_commands::RbacServiceState;
+use crate::meeting::retention::{scan_media, MediaKind, MediaRetentionConfig};
+use crate::models::{ApiResponse, Appointment, Client, PaginatedResponse};
+use crate::security::audit::audit_log_path;
+use crate::security::audit_export::read_audit_log;
+use crate::security::auth::AuthState;
+use crate::security::crypto::CryptoServiceState;
+use crate::security::data_scope::{resolve_caller_scope, DataScopePolicy};
+use crate::security::access_justification::JustificationPolicyState;
+use crate::security::rbac::Permission;
+use crate::security::step_up::StepUpState;
+use crate::services::patient_timeline::{self, PatientTimelineConfig, TimelineEvent, TimelineEventType, TimelineFilter};
+use crate::services::FirebaseService;
+
+/// Upper bound on appointments scanned for one patient's timeline
+const MAX_TIMELINE_APPOINTMENTS: u32 = 10_000;
+
+/// One patient's appointments, notes, transcripts and audit events in time order.
+/// The patient must be within the caller's data scope; each source is included only when the
+/// caller's role may view it, and excluded events are simply absent from the result.
+#[tauri::command]
+pub async fn get_patient_timeline(
+    patient_id: String,
+    event_types: Option<Vec<TimelineEventType>>,
+    start_date: Option<DateTime<Utc>>,
+    end_date: Option<DateTime<Utc>>,
+    page: Option<u32>,
+    limit: Option<u32>,
+    break_glass_grant_id: Option<Uuid>,
+    config: State<'_, PatientTimelineConfig>,
+    media_config: State<'_, MediaRetentionConfig>,
+    storage_state: State<'_, StorageState>,
+    rbac: State<'_, RbacServiceState>,
+    scope_policy: State<'_, DataScopePolicy>,
+    justification_policy: State<'_, JustificationPolicyState>,
+    step_up: State<'_, StepUpState>,
+    crypto: State<'_, CryptoServiceState>,
+    firebase: State<'_, Arc<tokio::sync::Mutex<FirebaseService>>>,
+    auth_state: State<'_, Arc<RwLock<AuthState>>>,
+) -> Result<ApiResponse<PaginatedResponse<TimelineEvent>>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    let role = auth.get_role().cloned().ok_or("No role in session")?;
+    step_up.check_patient(&auth, &patient_id, &justification_policy.get().flagged_patients, Utc::now())?;
+
+    let filter = TimelineFilter {
+        event_types: event_types.map(|types| types.into_iter().collect::<HashSet<_>>()),
+        start: start_date,
+        end: end_date,
+    };
+    let page = page.unwrap_or(1).max(1);
+    let limit = limit.unwrap_or(config.default_page_size).clamp(1, config.max_page_size);
+
+    let firebase = firebase.lock().await;
+    authorize_patient_access(&auth, &rbac, &firebase, PatientDataRead {
+        patient_id: &patient_id,
+        permission: Permission::ViewPHI,
+        action: "VIEW_PATIENT_TIMELINE",
+        justification: None,
+        break_glass_grant_id,
+    }).await?;
+    let scope = resolve_caller_scope(&scope_policy, &auth, &firebase).await?;
+    drop(auth);
+
+    let client: Client = firebase.get_document("clients", &patient_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Client not found")?;
+    // A break-glass grant checked above stands in for assignment
+    if !scope.allows_clinical() || (break_glass_grant_id.is_none() && !scope.includes_client(&client)) {
+        firebase.audit_log(
+            "VIEW_PATIENT_TIMELINE_DENIED",
+            "client",
+            &user_id,
+            false, // No PHI returned
+            Some(serde_json::json!({
+                "client_id": patient_id,
+                "scope": scope.label(),
+            }))
+        ).await.map_err(|e| e.to_string())?;
+        return Err("Patient is not assigned to you".to_string());
+    }
+
+    let may = |event_type: TimelineEventType, permission: Permission| {
+        filter.wants(event_type) && rbac.0.role_grants(&role, &permission)
+    };
+    let mut events = Vec::new();
+
+    if may(TimelineEventType::Appointment, Permission::ViewSchedule) {
+        let appointments: Vec<Appointment> = firebase.query_documents("appointments", 1, MAX_TIMELINE_APPOINTMENTS)
+            .await
+            .map_err(|e| e.to_string())?;
+        events.extend(
+            appointments
+                .iter()
+                .filter(|a| a.client_ptr == patient_id && scope.includes_appointment(a))
+                .map(patient_timeline::appointment_event),
+        );
+    }
+
+    if may(TimelineEventType::Note, Permission::ViewClinicalNotes) {
+        let storage_guard = storage_state.lock().await;
+        // Notes only exist once the encrypted store is unlocked; without it there is nothing to show
+        if let Some(storage) = storage_guard.as_ref() {
+            let notes = storage.list_notes_for_patient(&patient_id, &user_id, config.max_notes, 0, &Default::default())
+                .await
+                .map_err(|e| e.to_string())?;
+            events.extend(notes.iter().map(patient_timeline::note_event));
+        }
+    }
+
+    if may(TimelineEventType::Transcript, Permission::ViewPHI) {
+        for artifact in scan_media(&media_config.media_dirs) {
+            if artifact.kind != MediaKind::Transcript || artifact.client_id.as_deref() != Some(patient_id.as_str()) {
+                continue;
+            }
+            match crate::meeting::read_transcript(&artifact.path.to_string_lossy(), &crypto.0).await {
+                Ok(content) => events.push(patient_timeline::transcript_event(
+                    artifact.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                    artifact.created_at,
+                    content,
+                )),
+                Err(e) => log::warn!("Skipping transcript {} in patient timeline: {}", artifact.path.display(), e),
+            }
+        }
+    }
+
+    if config.include_audit_events && may(TimelineEventType::Audit, Permission::ViewAuditLogs) {
+        if let Some(log_path) = audit_log_path() {
+            let audit_events = read_audit_log(&log_path, filter.start, filter.end).map_err(|e| e.to_string())?;
+            events.extend(
+                audit_events
+                    .iter()
+                    .filter(|e| patient_timeline::audit_concerns_patient(e, &patient_id))
+                    .map(patient_timeline::audit_event),
+            );
+        }
+    }
+
+    let response = patient_timeline::assemble(events, &filter, page, limit);
+    let included: HashSet<TimelineEventType> = response.data.iter().map(|e| e.event_type).collect();
+
+    firebase.audit_log(
+        "VIEW_PATIENT_TIMELINE",
+        "client",
+        &user_id,
+        true, // PHI accessed
+        Some(serde_json::json!({
+            "client_id": patient_id,
+            "scope": scope.label(),
+            "event_types": included,
+            "returned": response.data.len(),
+            "total": response.total,
+            "page": page,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(ApiResponse::success(response))
+}
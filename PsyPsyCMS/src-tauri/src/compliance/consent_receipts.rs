@@ -0,0 +1,579 @@
+// Consent Receipts for PsyPsy CMS
+// Every consent granted or withdrawn yields a receipt in the Kantara Consent Receipt v1.1 shape,
+// signed with the installation's Ed25519 key so the subject (or a DSAR export reviewer) can
+// later prove exactly what was agreed to, when, and how to withdraw.
+
+use crate::security::SecurityError;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+const RECORDS_FILE: &str = "consent_records.json";
+const SIGNING_KEY_FILE: &str = "consent_receipt_signing.key";
+
+/// Kantara specification version receipts follow
+pub const RECEIPT_VERSION: &str = "KI-CR-v1.1.0";
+pub const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
+/// What the consent covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentCategory {
+    SessionRecording,
+    SocialMedia,
+    DataProcessing,
+}
+
+/// Issuer settings printed on every receipt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentReceiptConfig {
+    pub jurisdiction: String,
+    pub controller_name: String,
+    pub controller_contact: String,
+    pub controller_email: String,
+    pub policy_url: String,
+    /// How a subject withdraws, in words they can act on
+    pub withdrawal_method: String,
+    pub language: String,
+    /// Base64 public keys of earlier signing keys whose receipts are still honoured
+    pub trusted_public_keys: Vec<String>,
+}
+
+impl Default for ConsentReceiptConfig {
+    fn default() -> Self {
+        Self {
+            jurisdiction: "CA-QC".to_string(),
+            controller_name: "PsyPsy".to_string(),
+            controller_contact: "Privacy Officer".to_string(),
+            controller_email: "privacy@psypsy.ca".to_string(),
+            policy_url: "https://psypsy.ca/privacy".to_string(),
+            withdrawal_method: "Withdraw at any time from your account settings or by writing to the privacy officer"
+                .to_string(),
+            language: "fr-CA".to_string(),
+            trusted_public_keys: Vec::new(),
+        }
+    }
+}
+
+impl ConsentReceiptConfig {
+    /// Defaults with `CONSENT_*` environment overrides; `CONSENT_RECEIPT_TRUSTED_KEYS` is comma-separated
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let overrides: [(&str, &mut String); 7] = [
+            ("CONSENT_JURISDICTION", &mut config.jurisdiction),
+            ("CONSENT_CONTROLLER_NAME", &mut config.controller_name),
+            ("CONSENT_CONTROLLER_CONTACT", &mut config.controller_contact),
+            ("CONSENT_CONTROLLER_EMAIL", &mut config.controller_email),
+            ("CONSENT_POLICY_URL", &mut config.policy_url),
+            ("CONSENT_WITHDRAWAL_METHOD", &mut config.withdrawal_method),
+            ("CONSENT_RECEIPT_LANGUAGE", &mut config.language),
+        ];
+        for (name, field) in overrides {
+            if let Ok(value) = std::env::var(name) {
+                *field = value;
+            }
+        }
+        if let Ok(keys) = std::env::var("CONSENT_RECEIPT_TRUSTED_KEYS") {
+            config.trusted_public_keys = keys.split(',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect();
+        }
+        config
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PiiController {
+    pub pii_controller: String,
+    pub contact: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptPurpose {
+    pub purpose: String,
+    pub purpose_category: Vec<String>,
+    pub consent_type: String,
+    pub pii_category: Vec<String>,
+    pub primary_purpose: bool,
+    /// When the purpose ends, in words
+    pub termination: String,
+    pub third_party_disclosure: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptService {
+    pub service: String,
+    pub purposes: Vec<ReceiptPurpose>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptType {
+    Grant,
+    Withdrawal,
+}
+
+/// Kantara consent receipt, plus the grant/withdrawal details Law 25 subjects need
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentReceipt {
+    pub version: String,
+    pub jurisdiction: String,
+    /// Seconds since the epoch, as the specification requires
+    pub consent_timestamp: i64,
+    pub collection_method: String,
+    pub consent_receipt_id: String,
+    pub language: String,
+    pub pii_principal_id: String,
+    pub pii_controllers: Vec<PiiController>,
+    pub policy_url: String,
+    pub services: Vec<ReceiptService>,
+    pub sensitive: bool,
+    pub spi_cat: Vec<String>,
+    // Extensions
+    pub receipt_type: ReceiptType,
+    pub consent_id: Uuid,
+    pub category: ConsentCategory,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub withdrawal_method: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptSignature {
+    pub algorithm: String,
+    /// First 16 hex characters of the SHA-256 of the public key
+    pub key_id: String,
+    pub public_key: String,
+    pub value: String,
+}
+
+/// Receipt with a detached signature over its canonical JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedConsentReceipt {
+    pub receipt: ConsentReceipt,
+    pub signature: ReceiptSignature,
+}
+
+/// Outcome of checking a receipt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptVerification {
+    pub consent_receipt_id: String,
+    /// Signature matches the receipt content and the key it names
+    pub signature_valid: bool,
+    /// The signing key is this installation's current or a trusted earlier key
+    pub trusted_key: bool,
+    pub valid: bool,
+}
+
+/// Consent held for one subject and purpose, with every receipt issued for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentRecord {
+    pub consent_id: Uuid,
+    pub subject_id: String,
+    pub category: ConsentCategory,
+    pub purposes: Vec<String>,
+    pub granted_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub withdrawn_at: Option<DateTime<Utc>>,
+    pub receipts: Vec<SignedConsentReceipt>,
+}
+
+impl ConsentRecord {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.withdrawn_at.is_none() && self.expires_at.map_or(true, |expiry| expiry > now)
+    }
+}
+
+/// What the subject is agreeing to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentGrant {
+    pub subject_id: String,
+    pub category: ConsentCategory,
+    pub purposes: Vec<String>,
+    /// Personal information involved ("audio", "contact details", ...)
+    #[serde(default)]
+    pub pii_categories: Vec<String>,
+    #[serde(default)]
+    pub third_party_disclosure: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// "in-app checkbox", "signed form", ...
+    pub collection_method: String,
+}
+
+fn canonical_bytes(receipt: &ConsentReceipt) -> Result<Vec<u8>, SecurityError> {
+    // serde_json maps are sorted, which keeps the serialization canonical
+    let value = serde_json::to_value(receipt).map_err(|e| SecurityError::CryptographicError { reason: e.to_string() })?;
+    serde_json::to_vec(&value).map_err(|e| SecurityError::CryptographicError { reason: e.to_string() })
+}
+
+fn key_id(public_key: &[u8]) -> String {
+    Sha256::digest(public_key).iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Installation key that signs receipts
+pub struct ReceiptSigner {
+    key: SigningKey,
+}
+
+impl ReceiptSigner {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self { key: SigningKey::from_bytes(&seed) }
+    }
+
+    pub fn generate() -> Self {
+        let mut seed = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+
+    /// Key from `CONSENT_RECEIPT_SIGNING_KEY` (base64 seed), else the one saved in `dir`,
+    /// else a new key saved there
+    pub fn load_or_create(dir: &Path) -> std::io::Result<Self> {
+        let invalid = |what: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, what.to_string());
+        let decode = |encoded: &str| -> std::io::Result<Self> {
+            let bytes = general_purpose::STANDARD.decode(encoded.trim()).map_err(|_| invalid("signing key is not base64"))?;
+            let seed: [u8; 32] = bytes.try_into().map_err(|_| invalid("signing key must be 32 bytes"))?;
+            Ok(Self::from_seed(seed))
+        };
+
+        if let Ok(encoded) = std::env::var("CONSENT_RECEIPT_SIGNING_KEY") {
+            return decode(&encoded);
+        }
+        let path = dir.join(SIGNING_KEY_FILE);
+        if path.exists() {
+            return decode(&std::fs::read_to_string(&path)?);
+        }
+        let signer = Self::generate();
+        std::fs::write(&path, general_purpose::STANDARD.encode(signer.key.to_bytes()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(signer)
+    }
+
+    pub fn public_key(&self) -> String {
+        general_purpose::STANDARD.encode(self.key.verifying_key().to_bytes())
+    }
+
+    pub fn sign(&self, receipt: ConsentReceipt) -> Result<SignedConsentReceipt, SecurityError> {
+        let signature = self.key.sign(&canonical_bytes(&receipt)?);
+        let public_key = self.key.verifying_key().to_bytes();
+        Ok(SignedConsentReceipt {
+            receipt,
+            signature: ReceiptSignature {
+                algorithm: SIGNATURE_ALGORITHM.to_string(),
+                key_id: key_id(&public_key),
+                public_key: general_purpose::STANDARD.encode(public_key),
+                value: general_purpose::STANDARD.encode(signature.to_bytes()),
+            },
+        })
+    }
+}
+
+/// Check a receipt's signature and that it was made by one of `trusted_keys`
+pub fn verify_receipt(signed: &SignedConsentReceipt, trusted_keys: &[String]) -> ReceiptVerification {
+    let signature_valid = (|| -> Option<bool> {
+        if signed.signature.algorithm != SIGNATURE_ALGORITHM {
+            return Some(false);
+        }
+        let public_key: [u8; 32] = general_purpose::STANDARD.decode(&signed.signature.public_key).ok()?.try_into().ok()?;
+        let signature = Signature::from_slice(&general_purpose::STANDARD.decode(&signed.signature.value).ok()?).ok()?;
+        let key = VerifyingKey::from_bytes(&public_key).ok()?;
+        let message = canonical_bytes(&signed.receipt).ok()?;
+        Some(key_id(&public_key) == signed.signature.key_id && key.verify(&message, &signature).is_ok())
+    })()
+    .unwrap_or(false);
+    let trusted_key = trusted_keys.iter().any(|k| k == &signed.signature.public_key);
+
+    ReceiptVerification {
+        consent_receipt_id: signed.receipt.consent_receipt_id.clone(),
+        signature_valid,
+        trusted_key,
+        valid: signature_valid && trusted_key,
+    }
+}
+
+fn build_receipt(
+    config: &ConsentReceiptConfig,
+    record: &ConsentRecord,
+    receipt_type: ReceiptType,
+    collection_method: &str,
+    pii_categories: &[String],
+    third_party_disclosure: bool,
+    at: DateTime<Utc>,
+) -> ConsentReceipt {
+    let termination = match (receipt_type, record.expires_at) {
+        (ReceiptType::Withdrawal, _) => format!("Withdrawn on {}", at.to_rfc3339()),
+        (ReceiptType::Grant, Some(expiry)) => format!("Until {} or withdrawal", expiry.to_rfc3339()),
+        (ReceiptType::Grant, None) => "Until withdrawal".to_string(),
+    };
+    let category = serde_json::to_value(record.category)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    // Session recordings and clinical processing involve health information
+    let sensitive = matches!(record.category, ConsentCategory::SessionRecording | ConsentCategory::DataProcessing);
+
+    ConsentReceipt {
+        version: RECEIPT_VERSION.to_string(),
+        jurisdiction: config.jurisdiction.clone(),
+        consent_timestamp: at.timestamp(),
+        collection_method: collection_method.to_string(),
+        consent_receipt_id: Uuid::new_v4().to_string(),
+        language: config.language.clone(),
+        pii_principal_id: record.subject_id.clone(),
+        pii_controllers: vec![PiiController {
+            pii_controller: config.controller_name.clone(),
+            contact: config.controller_contact.clone(),
+            email: config.controller_email.clone(),
+        }],
+        policy_url: config.policy_url.clone(),
+        services: vec![ReceiptService {
+            service: category.clone(),
+            purposes: record
+                .purposes
+                .iter()
+                .enumerate()
+                .map(|(i, purpose)| ReceiptPurpose {
+                    purpose: purpose.clone(),
+                    purpose_category: vec![category.clone()],
+                    consent_type: "EXPLICIT".to_string(),
+                    pii_category: pii_categories.to_vec(),
+                    primary_purpose: i == 0,
+                    termination: termination.clone(),
+                    third_party_disclosure,
+                })
+                .collect(),
+        }],
+        sensitive,
+        spi_cat: if sensitive { vec!["Health".to_string()] } else { Vec::new() },
+        receipt_type,
+        consent_id: record.consent_id,
+        category: record.category,
+        expires_at: record.expires_at,
+        withdrawal_method: config.withdrawal_method.clone(),
+    }
+}
+
+/// Consent records and the key that signs their receipts
+#[derive(Clone, Default)]
+pub struct ConsentReceiptState {
+    records: Arc<RwLock<HashMap<Uuid, ConsentRecord>>>,
+    signer: Arc<RwLock<Option<ReceiptSigner>>>,
+    storage_path: Arc<RwLock<Option<PathBuf>>>,
+}
+
+impl ConsentReceiptState {
+    /// Load saved records and the signing key from `dir` and persist future changes there
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        *self.signer.write().unwrap() = Some(ReceiptSigner::load_or_create(dir)?);
+        let path = dir.join(RECORDS_FILE);
+        if path.exists() {
+            if let Ok(saved) = serde_json::from_slice::<HashMap<Uuid, ConsentRecord>>(&std::fs::read(&path)?) {
+                *self.records.write().unwrap() = saved;
+            }
+        }
+        *self.storage_path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    /// Use a specific signer, without persistence
+    pub fn with_signer(signer: ReceiptSigner) -> Self {
+        let state = Self::default();
+        *state.signer.write().unwrap() = Some(signer);
+        state
+    }
+
+    fn sign(&self, receipt: ConsentReceipt) -> Result<SignedConsentReceipt, SecurityError> {
+        self.signer
+            .read()
+            .unwrap()
+            .as_ref()
+            .ok_or_else(|| SecurityError::ConfigurationError {
+                reason: "Consent receipt signing key not loaded".to_string(),
+            })?
+            .sign(receipt)
+    }
+
+    /// Keys whose receipts verify: the current key plus configured earlier ones
+    pub fn trusted_keys(&self, config: &ConsentReceiptConfig) -> Vec<String> {
+        let mut keys = config.trusted_public_keys.clone();
+        keys.extend(self.signer.read().unwrap().as_ref().map(ReceiptSigner::public_key));
+        keys
+    }
+
+    /// Record a consent and issue its receipt
+    pub fn grant(&self, config: &ConsentReceiptConfig, grant: ConsentGrant) -> Result<SignedConsentReceipt, SecurityError> {
+        let purposes: Vec<String> = grant.purposes.iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+        if grant.subject_id.trim().is_empty() || purposes.is_empty() {
+            return Err(SecurityError::ValidationFailed {
+                reason: "Consent needs a subject and at least one purpose".to_string(),
+            });
+        }
+        let now = Utc::now();
+        if grant.expires_at.is_some_and(|expiry| expiry <= now) {
+            return Err(SecurityError::ValidationFailed {
+                reason: "Consent expiry must be in the future".to_string(),
+            });
+        }
+
+        let mut record = ConsentRecord {
+            consent_id: Uuid::new_v4(),
+            subject_id: grant.subject_id.trim().to_string(),
+            category: grant.category,
+            purposes,
+            granted_at: now,
+            expires_at: grant.expires_at,
+            withdrawn_at: None,
+            receipts: Vec::new(),
+        };
+        let receipt = self.sign(build_receipt(
+            config,
+            &record,
+            ReceiptType::Grant,
+            &grant.collection_method,
+            &grant.pii_categories,
+            grant.third_party_disclosure,
+            now,
+        ))?;
+        record.receipts.push(receipt.clone());
+        self.records.write().unwrap().insert(record.consent_id, record);
+        self.persist();
+        Ok(receipt)
+    }
+
+    /// Withdraw a consent and issue the withdrawal receipt
+    pub fn withdraw(&self, config: &ConsentReceiptConfig, consent_id: Uuid, method: &str) -> Result<SignedConsentReceipt, SecurityError> {
+        let mut records = self.records.write().unwrap();
+        let record = records.get_mut(&consent_id).ok_or_else(|| SecurityError::NotFound {
+            reason: "Consent record not found".to_string(),
+        })?;
+        if record.withdrawn_at.is_some() {
+            return Err(SecurityError::ValidationFailed {
+                reason: "Consent was already withdrawn".to_string(),
+            });
+        }
+        let now = Utc::now();
+        // Purpose details are carried over from the grant so the withdrawal stands on its own
+        let granted = record.receipts.first().map(|r| &r.receipt);
+        let purpose = granted.and_then(|r| r.services.first()).and_then(|s| s.purposes.first());
+        let pii_categories = purpose.map(|p| p.pii_category.clone()).unwrap_or_default();
+        let third_party_disclosure = purpose.is_some_and(|p| p.third_party_disclosure);
+
+        let receipt = self.sign(build_receipt(config, record, ReceiptType::Withdrawal, method, &pii_categories, third_party_disclosure, now))?;
+        record.withdrawn_at = Some(now);
+        record.receipts.push(receipt.clone());
+        drop(records);
+        self.persist();
+        Ok(receipt)
+    }
+
+    pub fn get(&self, consent_id: Uuid) -> Option<ConsentRecord> {
+        self.records.read().unwrap().get(&consent_id).cloned()
+    }
+
+    /// Every consent held for a subject, oldest first
+    pub fn records_for(&self, subject_id: &str) -> Vec<ConsentRecord> {
+        let mut records: Vec<ConsentRecord> = self
+            .records
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.subject_id == subject_id)
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.granted_at);
+        records
+    }
+
+    /// Every receipt issued to a subject, for export to them
+    pub fn receipts_for(&self, subject_id: &str) -> Vec<SignedConsentReceipt> {
+        self.records_for(subject_id).into_iter().flat_map(|r| r.receipts).collect()
+    }
+
+    fn persist(&self) {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            return;
+        };
+        match serde_json::to_vec_pretty(&*self.records.read().unwrap()) {
+            Ok(bytes) => {
+                if let Err(e) = std::fs::write(&path, bytes) {
+                    log::warn!("Failed to persist consent records: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize consent records: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant() -> ConsentGrant {
+        ConsentGrant {
+            subject_id: "client-1".to_string(),
+            category: ConsentCategory::SessionRecording,
+            purposes: vec!["Record session audio for clinical notes".to_string()],
+            pii_categories: vec!["audio".to_string()],
+            third_party_disclosure: false,
+            expires_at: None,
+            collection_method: "in-app checkbox".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_grant_and_withdrawal_receipts_verify() {
+        let config = ConsentReceiptConfig::default();
+        let state = ConsentReceiptState::with_signer(ReceiptSigner::from_seed([7u8; 32]));
+        let trusted = state.trusted_keys(&config);
+
+        let granted = state.grant(&config, grant()).unwrap();
+        assert_eq!(granted.receipt.version, RECEIPT_VERSION);
+        assert!(granted.receipt.sensitive);
+        assert!(verify_receipt(&granted, &trusted).valid);
+
+        let withdrawn = state.withdraw(&config, granted.receipt.consent_id, "account settings").unwrap();
+        assert_eq!(withdrawn.receipt.receipt_type, ReceiptType::Withdrawal);
+        assert_eq!(withdrawn.receipt.services[0].purposes[0].pii_category, vec!["audio"]);
+        assert!(verify_receipt(&withdrawn, &trusted).valid);
+        assert!(state.withdraw(&config, granted.receipt.consent_id, "again").is_err());
+
+        let record = state.get(granted.receipt.consent_id).unwrap();
+        assert!(!record.is_active(Utc::now()));
+        assert_eq!(state.receipts_for("client-1").len(), 2);
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_receipt_fails() {
+        let config = ConsentReceiptConfig::default();
+        let state = ConsentReceiptState::with_signer(ReceiptSigner::from_seed([7u8; 32]));
+        let trusted = state.trusted_keys(&config);
+        let signed = state.grant(&config, grant()).unwrap();
+
+        let mut tampered = signed.clone();
+        tampered.receipt.services[0].purposes[0].third_party_disclosure = true;
+        let verification = verify_receipt(&tampered, &trusted);
+        assert!(!verification.signature_valid && !verification.valid);
+
+        let foreign = ReceiptSigner::from_seed([9u8; 32]).sign(signed.receipt.clone()).unwrap();
+        let verification = verify_receipt(&foreign, &trusted);
+        assert!(verification.signature_valid && !verification.trusted_key);
+    }
+}
@@ -4,6 +4,8 @@
 //! (An Act to modernize legislative provisions as regards the protection of personal information).
 //! This includes consent management, audit trails, data subject rights, and breach reporting.
 
+pub mod consent_receipts;
+
 // Temporarily disabled due to sqlx dependency
 // pub mod quebec_law25;
 
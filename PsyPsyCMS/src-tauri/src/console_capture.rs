@@ -1,10 +1,13 @@
-use crate::devtools_server::{DevToolsMessage, LogMessage};
+use crate::commands::debug_commands::DevToolsState;
+use crate::devtools_server::{DevToolsHub, DevToolsMessage, LogMessage};
+use crate::services::phi_detector::{phi_detector, PhiType};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use tauri::{command, AppHandle, Manager, Window};
-use tokio::sync::broadcast;
 use uuid::Uuid;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsoleMessage {
@@ -15,19 +18,204 @@ pub struct ConsoleMessage {
     pub source: Option<String>,
 }
 
-// State to hold the broadcast sender
+/// Category the injection script gives errors it matched against the healthcare React patterns
+pub const HEALTHCARE_ERROR_CATEGORY: &str = "healthcare_react_error";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Unknown levels are treated as info, as `log_to_devtools` logs them
+    pub fn parse(level: &str) -> Self {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "trace" => LogLevel::Trace,
+            "debug" => LogLevel::Debug,
+            "warn" | "warning" => LogLevel::Warn,
+            "error" => LogLevel::Error,
+            _ => LogLevel::Info,
+        }
+    }
+}
+
+/// What `log_to_devtools` forwards to the debugger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevToolsFilter {
+    pub min_level: LogLevel,
+    /// Sources or categories to forward; empty forwards all. A source matches by prefix, so a
+    /// page URL without its query string works.
+    pub sources: Vec<String>,
+}
+
+impl Default for DevToolsFilter {
+    fn default() -> Self {
+        Self { min_level: LogLevel::Trace, sources: Vec::new() }
+    }
+}
+
+impl DevToolsFilter {
+    pub fn allows(&self, level: LogLevel, source: Option<&str>, category: Option<&str>) -> bool {
+        // Healthcare React errors always surface
+        if category == Some(HEALTHCARE_ERROR_CATEGORY) {
+            return true;
+        }
+        if level < self.min_level {
+            return false;
+        }
+        self.sources.is_empty()
+            || self.sources.iter().any(|allowed| {
+                category == Some(allowed.as_str()) || source.is_some_and(|s| s.starts_with(allowed.as_str()))
+            })
+    }
+}
+
+// State to hold the DevTools log buffer and the filter applied before entries reach it
 pub struct DevToolsBroadcaster {
-    pub tx: broadcast::Sender<DevToolsMessage>,
+    pub hub: DevToolsHub,
+    filter: std::sync::RwLock<DevToolsFilter>,
+    /// Entries held back by the filter
+    filtered: AtomicU64,
+}
+
+impl DevToolsBroadcaster {
+    pub fn new(hub: DevToolsHub) -> Self {
+        Self { hub, filter: std::sync::RwLock::new(DevToolsFilter::default()), filtered: AtomicU64::new(0) }
+    }
+
+    pub fn filter(&self) -> DevToolsFilter {
+        self.filter.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub fn set_filter(&self, filter: DevToolsFilter) {
+        *self.filter.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = filter;
+    }
+
+    /// Publish unless the filter holds the entry back; returns whether it was published
+    pub fn forward(&self, level: LogLevel, source: Option<&str>, category: Option<&str>, message: DevToolsMessage) -> bool {
+        if !self.filter().allows(level, source, category) {
+            self.filtered.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        self.hub.publish(message);
+        true
+    }
+
+    pub fn filtered(&self) -> u64 {
+        self.filtered.load(Ordering::Relaxed)
+    }
 }
 
 #[command]
-pub async fn get_devtools_status() -> Result<serde_json::Value, String> {
+pub async fn set_devtools_filter(
+    app: AppHandle,
+    min_level: String,
+    sources: Option<Vec<String>>,
+) -> Result<DevToolsFilter, String> {
+    let broadcaster = app.try_state::<DevToolsBroadcaster>().ok_or("DevTools is not running")?;
+    let min_level = match min_level.trim().to_ascii_lowercase().as_str() {
+        "error" => LogLevel::Error,
+        "warn" => LogLevel::Warn,
+        "info" => LogLevel::Info,
+        "debug" => LogLevel::Debug,
+        other => return Err(format!("Unknown log level '{}', expected error, warn, info or debug", other)),
+    };
+    let filter = DevToolsFilter {
+        min_level,
+        sources: sources
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    };
+    broadcaster.set_filter(filter.clone());
+    Ok(filter)
+}
+
+fn phi_label(phi_type: PhiType) -> String {
+    serde_json::to_value(phi_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "PHI".to_string())
+}
+
+/// Replace each identifier the PHI detector finds with `[REDACTED:<type>]`
+fn redact_text(text: &str) -> (String, u64) {
+    let matches = phi_detector().detect(text);
+    if matches.is_empty() {
+        return (text.to_string(), 0);
+    }
+    let mut redacted = String::with_capacity(text.len());
+    let mut last = 0;
+    for m in &matches {
+        redacted.push_str(&text[last..m.start]);
+        redacted.push_str(&format!("[REDACTED:{}]", phi_label(m.phi_type)));
+        last = m.end;
+    }
+    redacted.push_str(&text[last..]);
+    (redacted, matches.len() as u64)
+}
+
+fn redact_value(value: &mut serde_json::Value) -> u64 {
+    match value {
+        serde_json::Value::String(text) => {
+            let (redacted, count) = redact_text(text);
+            if count > 0 {
+                *text = redacted;
+            }
+            count
+        }
+        serde_json::Value::Array(items) => items.iter_mut().map(redact_value).sum(),
+        serde_json::Value::Object(fields) => fields.values_mut().map(redact_value).sum(),
+        _ => 0,
+    }
+}
+
+/// Redact PHI from a captured log message. The injection script sends objects as JSON; such a
+/// message is walked and only its string values are redacted, so the document stays valid.
+/// Returns the message and the number of redactions.
+pub(crate) fn redact_message(message: &str) -> (String, u64) {
+    match serde_json::from_str::<serde_json::Value>(message) {
+        Ok(mut value) if value.is_object() || value.is_array() => match redact_value(&mut value) {
+            // Untouched documents are passed through as sent
+            0 => (message.to_string(), 0),
+            count => (serde_json::to_string_pretty(&value).unwrap_or_default(), count),
+        },
+        _ => redact_text(message),
+    }
+}
+
+#[command]
+pub async fn get_devtools_status(app: AppHandle) -> Result<serde_json::Value, String> {
+    // `evicted` counts entries dropped from the full buffer; a non-zero value means logs were lost
+    let broadcaster = app.try_state::<DevToolsBroadcaster>();
+    let log_buffer = broadcaster.as_ref().map(|broadcaster| {
+        serde_json::json!({
+            "capacity": broadcaster.hub.capacity(),
+            "buffered": broadcaster.hub.buffered(),
+            "evicted": broadcaster.hub.evicted(),
+            "filtered": broadcaster.filtered(),
+        })
+    });
+    let filter = broadcaster.map(|broadcaster| broadcaster.filter());
+    let phi_redactions = app
+        .try_state::<Arc<RwLock<DevToolsState>>>()
+        .map(|state| state.read().unwrap_or_else(|poisoned| poisoned.into_inner()).redactions);
     Ok(serde_json::json!({
         "enabled": true,
         "websocket_port": 9223,
         "websocket_url": "ws://127.0.0.1:9223",
         "stdout_capture": cfg!(unix),
         "status": "running",
+        "log_buffer": log_buffer,
+        "filter": filter,
+        "phi_redactions": phi_redactions,
         "healthcare_features": {
             "hipaa_compliance": true,
             "quebec_law_25": true,
@@ -46,7 +234,24 @@ pub async fn log_to_devtools(
     message: String,
     _stack: Option<String>,
     source: Option<String>,
+    category: Option<String>,
 ) -> Result<(), String> {
+    // Rendered patient data must not reach the debug channel or the captured stdout
+    let (message, message_redactions) = redact_message(&message);
+    let (source, source_redactions) = match source {
+        Some(source) => {
+            let (source, count) = redact_text(&source);
+            (Some(source), count)
+        }
+        None => (None, 0),
+    };
+    let redactions = message_redactions + source_redactions;
+    if redactions > 0 {
+        if let Some(state) = app.try_state::<Arc<RwLock<DevToolsState>>>() {
+            state.write().unwrap_or_else(|poisoned| poisoned.into_inner()).redactions += redactions;
+        }
+    }
+
     // Try to get the broadcaster from app state
     if let Some(broadcaster) = app.try_state::<DevToolsBroadcaster>() {
         // Create a LogMessage to send to DevTools
@@ -68,7 +273,7 @@ pub async fn log_to_devtools(
         };
 
         // Send to WebSocket clients
-        let _ = broadcaster.tx.send(devtools_msg.clone());
+        broadcaster.forward(LogLevel::parse(&level), source.as_deref(), category.as_deref(), devtools_msg);
     }
 
     // Also log to stdout for debugging (this will be captured)
@@ -400,13 +605,18 @@ pub fn get_console_injection_script() -> String {
 
                 const stack = new Error().stack;
                 const source = window.location.href;
+                // Errors matched against the healthcare React patterns bypass the DevTools filter
+                const matchedPattern = args.some(arg =>
+                    arg && arg.cmsDebuggerEnhanced && arg.analysis && arg.analysis.pattern !== 'UNKNOWN');
+                const category = matchedPattern ? 'healthcare_react_error' : 'console';
 
                 if (window.__TAURI__ && window.__TAURI__.core && window.__TAURI__.core.invoke) {
                     window.__TAURI__.core.invoke('log_to_devtools', {
                         level: level,
                         message: message,
                         stack: stack,
-                        source: source
+                        source: source,
+                        category: category
                     }).then(() => {
                         devToolsSending = false;
                     }).catch(err => {
@@ -673,4 +883,47 @@ pub fn get_console_injection_script() -> String {
         }
     })();
     "#.to_string()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_drops_quiet_levels_and_unlisted_sources_but_not_healthcare_errors() {
+        let filter = DevToolsFilter {
+            min_level: LogLevel::Warn,
+            sources: vec!["http://localhost:5173/patients".to_string(), "tauri".to_string()],
+        };
+        let page = Some("http://localhost:5173/patients?id=42");
+        assert!(filter.allows(LogLevel::Error, page, Some("console")));
+        assert!(!filter.allows(LogLevel::Info, page, Some("console")));
+        assert!(!filter.allows(LogLevel::Error, Some("http://localhost:5173/settings"), Some("console")));
+        assert!(filter.allows(LogLevel::Warn, None, Some("tauri")));
+        assert!(filter.allows(LogLevel::Debug, Some("http://localhost:5173/settings"), Some(HEALTHCARE_ERROR_CATEGORY)));
+
+        assert!(DevToolsFilter::default().allows(LogLevel::parse("log"), None, None));
+        assert_eq!(LogLevel::parse("WARNING"), LogLevel::Warn);
+    }
+
+    #[test]
+    fn test_redaction_walks_json_string_values() {
+        let message = r#"{
+  "message": "Failed to load chart for jane.doe@example.com",
+  "props": { "phone": "514-555-0199", "visits": 3, "tags": ["RAMQ DOEJ 8501 1234"] },
+  "component": "PatientCard"
+}"#;
+        let (redacted, count) = redact_message(message);
+        assert_eq!(count, 3);
+        let value: serde_json::Value = serde_json::from_str(&redacted).expect("still valid JSON");
+        assert_eq!(value["message"], "Failed to load chart for [REDACTED:EMAIL]");
+        assert_eq!(value["props"]["phone"], "[REDACTED:PHONE]");
+        assert_eq!(value["props"]["visits"], 3);
+        assert_eq!(value["props"]["tags"][0], "RAMQ [REDACTED:RAMQ_NUMBER]");
+        assert_eq!(value["component"], "PatientCard");
+
+        let (flat, count) = redact_message("Render took 12ms for 514-555-0199");
+        assert_eq!((flat.as_str(), count), ("Render took 12ms for [REDACTED:PHONE]", 1));
+        assert_eq!(redact_message(r#"{"ok": true}"#), (r#"{"ok": true}"#.to_string(), 0));
+    }
+}
@@ -1,17 +1,23 @@
 use futures_util::{SinkExt, StreamExt};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::broadcast;
-use tokio::sync::Mutex;
-use tokio_tungstenite::{accept_hdr_async, tungstenite::handshake::server::Request, tungstenite::handshake::server::Response, tungstenite::Message};
+use tokio::sync::{Mutex, Notify};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::{accept_hdr_async, tungstenite::Message};
 use uuid::Uuid;
 
+/// Per-client channel for replies meant only for that client (welcome, pong, status)
 pub type Clients = Arc<Mutex<HashMap<String, tokio::sync::mpsc::UnboundedSender<Message>>>>;
 
+/// Log entries kept for replay when `DEVTOOLS_LOG_BUFFER` is not set
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DevToolsMessage {
     pub msg_type: String,
@@ -31,26 +37,106 @@ pub struct LogMessage {
     pub fields: HashMap<String, String>,
 }
 
+struct Ring {
+    /// Oldest first, each with its sequence number
+    entries: VecDeque<(u64, DevToolsMessage)>,
+    next_seq: u64,
+}
+
+struct HubInner {
+    capacity: usize,
+    ring: std::sync::Mutex<Ring>,
+    evicted: AtomicU64,
+    notify: Notify,
+}
+
+/// Bounded history of DevTools messages shared by producers and connected clients. Publishing
+/// never waits on clients: a full buffer evicts its oldest entry. Each client reads from the
+/// buffer at its own pace, starting with the retained backlog, so a slow client only falls behind
+/// itself.
+#[derive(Clone)]
+pub struct DevToolsHub {
+    inner: Arc<HubInner>,
+}
+
+impl DevToolsHub {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            inner: Arc::new(HubInner {
+                capacity,
+                ring: std::sync::Mutex::new(Ring { entries: VecDeque::with_capacity(capacity), next_seq: 0 }),
+                evicted: AtomicU64::new(0),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Capacity from `DEVTOOLS_LOG_BUFFER`, else [`DEFAULT_LOG_CAPACITY`]
+    pub fn from_env() -> Self {
+        let mut capacity = DEFAULT_LOG_CAPACITY;
+        if let Ok(value) = std::env::var("DEVTOOLS_LOG_BUFFER") {
+            match value.trim().parse::<usize>() {
+                Ok(n) if n > 0 => capacity = n,
+                _ => warn!("Ignoring invalid DEVTOOLS_LOG_BUFFER '{}'", value),
+            }
+        }
+        Self::new(capacity)
+    }
+
+    pub fn publish(&self, message: DevToolsMessage) {
+        {
+            let mut ring = self.inner.ring.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let seq = ring.next_seq;
+            ring.next_seq += 1;
+            ring.entries.push_back((seq, message));
+            if ring.entries.len() > self.inner.capacity {
+                ring.entries.pop_front();
+                self.inner.evicted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Entries at or after `cursor` and the cursor to read from next. A cursor older than the
+    /// buffer starts at its oldest entry.
+    pub fn read_from(&self, cursor: u64) -> (Vec<DevToolsMessage>, u64) {
+        let ring = self.inner.ring.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let entries = ring.entries.iter().filter(|(seq, _)| *seq >= cursor).map(|(_, m)| m.clone()).collect();
+        (entries, ring.next_seq)
+    }
+
+    /// Entries dropped because the buffer was full
+    pub fn evicted(&self) -> u64 {
+        self.inner.evicted.load(Ordering::Relaxed)
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.inner.ring.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).entries.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
 pub struct DevToolsServer {
     port: u16,
     clients: Clients,
-    broadcast_tx: broadcast::Sender<DevToolsMessage>,
+    hub: DevToolsHub,
 }
 
 impl DevToolsServer {
     pub fn new(port: u16) -> Self {
-        let clients = Arc::new(Mutex::new(HashMap::new()));
-        let (broadcast_tx, _) = broadcast::channel::<DevToolsMessage>(1000);
-
         Self {
             port,
-            clients,
-            broadcast_tx,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            hub: DevToolsHub::from_env(),
         }
     }
 
-    pub fn get_broadcast_sender(&self) -> broadcast::Sender<DevToolsMessage> {
-        self.broadcast_tx.clone()
+    pub fn hub(&self) -> DevToolsHub {
+        self.hub.clone()
     }
 
     pub async fn start(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -58,56 +144,49 @@ impl DevToolsServer {
         let listener = TcpListener::bind(&addr).await?;
         info!("🚀 DevTools WebSocket server listening on ws://{}", addr);
 
-        let clients = self.clients.clone();
-        let broadcast_tx = self.broadcast_tx.clone();
-
-        // Spawn broadcast task
-        let clients_for_broadcast = clients.clone();
-        let mut broadcast_rx = broadcast_tx.subscribe();
-        tokio::spawn(async move {
-            while let Ok(message) = broadcast_rx.recv().await {
-                let clients_guard = clients_for_broadcast.lock().await;
-                let message_json = serde_json::to_string(&message).unwrap_or_default();
-                let ws_message = Message::Text(message_json);
-
-                for (client_id, sender) in clients_guard.iter() {
-                    if let Err(e) = sender.send(ws_message.clone()) {
-                        warn!("Failed to send message to client {}: {}", client_id, e);
-                    }
-                }
-            }
-        });
-
         // Accept connections
         while let Ok((stream, addr)) = listener.accept().await {
-            let clients = clients.clone();
-            let broadcast_tx = broadcast_tx.clone();
-            tokio::spawn(handle_connection(stream, addr, clients, broadcast_tx));
+            tokio::spawn(handle_connection(stream, addr, self.clients.clone(), self.hub.clone()));
         }
 
         Ok(())
     }
 }
 
+/// Connections without an `Origin` (the CLI debugger) and from local pages only
+fn origin_allowed(origin: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    let Ok(url) = tauri::Url::parse(origin) else {
+        return false;
+    };
+    matches!(url.scheme(), "http" | "https" | "tauri")
+        && matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]" | "tauri.localhost"))
+}
+
+fn to_ws(message: &DevToolsMessage) -> Message {
+    Message::Text(serde_json::to_string(message).unwrap_or_default())
+}
+
 async fn handle_connection(
     stream: TcpStream,
     addr: SocketAddr,
     clients: Clients,
-    broadcast_tx: broadcast::Sender<DevToolsMessage>,
+    hub: DevToolsHub,
 ) {
     info!("🔗 New WebSocket connection from: {}", addr);
 
-    // Handle WebSocket handshake with CORS headers
-    let ws_stream = match accept_hdr_async(stream, |req: &Request, mut response: Response| {
-        // Add CORS headers for cross-origin requests
-        response.headers_mut()
-            .insert("Access-Control-Allow-Origin", "*".parse().unwrap());
-        response.headers_mut()
-            .insert("Access-Control-Allow-Methods", "GET, POST, OPTIONS".parse().unwrap());
-        response.headers_mut()
-            .insert("Access-Control-Allow-Headers", "Content-Type, Authorization".parse().unwrap());
-
+    // Browsers let any page open a WebSocket to loopback, so refuse pages that are not ours
+    let ws_stream = match accept_hdr_async(stream, |req: &Request, response: Response| {
         debug!("WebSocket handshake request: {:?}", req);
+        let origin = req.headers().get("Origin").and_then(|value| value.to_str().ok());
+        if !origin_allowed(origin) {
+            warn!("Refusing DevTools connection from origin {:?}", origin);
+            let mut refusal = ErrorResponse::new(Some("Origin not allowed".to_string()));
+            *refusal.status_mut() = StatusCode::FORBIDDEN;
+            return Err(refusal);
+        }
         Ok(response)
     }).await {
         Ok(ws) => ws,
@@ -126,7 +205,7 @@ async fn handle_connection(
     // Add client to the registry
     {
         let mut clients_guard = clients.lock().await;
-        clients_guard.insert(client_id.clone(), client_tx);
+        clients_guard.insert(client_id.clone(), client_tx.clone());
     }
 
     // Send welcome message
@@ -145,20 +224,57 @@ async fn handle_connection(
         }),
     };
 
-    if let Err(e) = broadcast_tx.send(welcome_msg) {
-        warn!("Failed to send welcome message: {}", e);
-    }
+    let _ = client_tx.send(to_ws(&welcome_msg));
+
+    let hub_for_receiver = hub.clone();
 
     // Split the WebSocket stream
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-    // Spawn task to handle outgoing messages to this client
+    // Spawn task to handle outgoing messages to this client: the backlog first, then new log
+    // entries as they are published, interleaved with replies meant for this client
     let client_id_for_sender = client_id.clone();
     tokio::spawn(async move {
-        while let Some(message) = client_rx.recv().await {
-            if let Err(e) = ws_sender.send(message).await {
-                warn!("Failed to send message to client {}: {}", client_id_for_sender, e);
-                break;
+        let mut cursor = 0;
+        'outgoing: loop {
+            // Replies go out before the next batch so a busy log stream cannot hold back a pong
+            loop {
+                match client_rx.try_recv() {
+                    Ok(message) => {
+                        if let Err(e) = ws_sender.send(message).await {
+                            warn!("Failed to send message to client {}: {}", client_id_for_sender, e);
+                            break 'outgoing;
+                        }
+                    }
+                    Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
+                    Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => break 'outgoing,
+                }
+            }
+
+            // Registered before reading so a publish in between still wakes us
+            let published = hub.inner.notify.notified();
+            let (batch, next) = hub.read_from(cursor);
+            cursor = next;
+            if batch.is_empty() {
+                tokio::select! {
+                    _ = published => {}
+                    reply = client_rx.recv() => match reply {
+                        Some(message) => {
+                            if let Err(e) = ws_sender.send(message).await {
+                                warn!("Failed to send message to client {}: {}", client_id_for_sender, e);
+                                break 'outgoing;
+                            }
+                        }
+                        None => break 'outgoing,
+                    },
+                }
+                continue;
+            }
+            for message in &batch {
+                if let Err(e) = ws_sender.send(to_ws(message)).await {
+                    warn!("Failed to send message to client {}: {}", client_id_for_sender, e);
+                    break 'outgoing;
+                }
             }
         }
         debug!("Outgoing message handler closed for client: {}", client_id_for_sender);
@@ -184,7 +300,7 @@ async fn handle_connection(
                                         "timestamp": chrono::Utc::now().timestamp_millis()
                                     }),
                                 };
-                                let _ = broadcast_tx.send(pong_msg);
+                                let _ = client_tx.send(to_ws(&pong_msg));
                             }
                             "request_status" => {
                                 // Respond with server status
@@ -192,6 +308,8 @@ async fn handle_connection(
                                     msg_type: "status".to_string(),
                                     data: serde_json::json!({
                                         "connected_clients": clients_for_receiver.lock().await.len(),
+                                        "buffered_logs": hub_for_receiver.buffered(),
+                                        "evicted_logs": hub_for_receiver.evicted(),
                                         "server_uptime": std::time::SystemTime::now()
                                             .duration_since(std::time::UNIX_EPOCH)
                                             .unwrap_or_default()
@@ -203,7 +321,7 @@ async fn handle_connection(
                                         ]
                                     }),
                                 };
-                                let _ = broadcast_tx.send(status_msg);
+                                let _ = client_tx.send(to_ws(&status_msg));
                             }
                             _ => {
                                 debug!("Unknown message type: {}", devtools_msg.msg_type);
@@ -247,12 +365,12 @@ async fn handle_connection(
 
 // Custom tracing layer to forward logs to DevTools
 pub struct DevToolsTracingLayer {
-    broadcast_tx: broadcast::Sender<DevToolsMessage>,
+    hub: DevToolsHub,
 }
 
 impl DevToolsTracingLayer {
-    pub fn new(broadcast_tx: broadcast::Sender<DevToolsMessage>) -> Self {
-        Self { broadcast_tx }
+    pub fn new(hub: DevToolsHub) -> Self {
+        Self { hub }
     }
 }
 
@@ -315,6 +433,46 @@ where
         };
 
         // Send to WebSocket clients (fire and forget)
-        let _ = self.broadcast_tx.send(devtools_msg);
+        self.hub.publish(devtools_msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(n: u64) -> DevToolsMessage {
+        DevToolsMessage { msg_type: "log".to_string(), data: serde_json::json!({ "n": n }) }
+    }
+
+    #[test]
+    fn test_full_buffer_evicts_oldest_and_counts() {
+        let hub = DevToolsHub::new(3);
+        for n in 0..5 {
+            hub.publish(log(n));
+        }
+        assert_eq!(hub.evicted(), 2);
+        assert_eq!(hub.buffered(), 3);
+
+        // A client attaching now gets the retained backlog, oldest first
+        let (backlog, cursor) = hub.read_from(0);
+        let replayed: Vec<u64> = backlog.iter().map(|m| m.data["n"].as_u64().unwrap()).collect();
+        assert_eq!(replayed, vec![2, 3, 4]);
+
+        hub.publish(log(5));
+        let (next, _) = hub.read_from(cursor);
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].data["n"], 5);
+    }
+
+    #[test]
+    fn test_only_local_origins_may_connect() {
+        assert!(origin_allowed(None));
+        assert!(origin_allowed(Some("http://localhost:1420")));
+        assert!(origin_allowed(Some("tauri://localhost")));
+        assert!(origin_allowed(Some("http://tauri.localhost")));
+        assert!(!origin_allowed(Some("https://example.com")));
+        assert!(!origin_allowed(Some("http://localhost.example.com")));
+        assert!(!origin_allowed(Some("null")));
     }
 }
\ No newline at end of file
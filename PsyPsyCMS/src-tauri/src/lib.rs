@@ -19,12 +19,19 @@ use commands::medical_notes_commands::{
     initialize_encrypted_storage,
     save_medical_note,
     get_medical_note,
+    get_note_history,
+    get_note_version,
     list_patient_notes,
     delete_medical_note,
     get_audit_trail,
     create_medical_note,
     validate_note_compliance,
     storage_status,
+    reclassify_medical_note,
+    reencrypt_notes_for_classification,
+    get_reencryption_job,
+    attach_note_media,
+    list_note_attachments,
 };
 use commands::offline_sync_commands::{
     SyncServiceState,
@@ -39,6 +46,9 @@ use commands::offline_sync_commands::{
     get_pending_sync_count,
     start_background_sync,
     stop_background_sync,
+    get_sync_schedule,
+    update_sync_schedule,
+    sync_now,
 };
 use commands::social_media_commands::{
     SocialMediaState,
@@ -46,6 +56,7 @@ use commands::social_media_commands::{
     get_oauth_configs,
     save_oauth_config,
     record_social_media_consent,
+    withdraw_social_media_consent,
     initiate_oauth_flow,
     disconnect_platform,
     get_connected_platforms,
@@ -60,9 +71,25 @@ use commands::social_media_commands::{
 use meeting::{
     start_recording,
     stop_recording,
+    force_stop_recording,
+    get_recording_owner,
     is_recording,
+    list_input_devices,
     get_transcription_status,
     save_transcript,
+    load_transcript,
+    export_redacted_transcript,
+    list_expiring_media,
+    run_media_retention_purge,
+    place_media_legal_hold,
+    release_media_legal_hold,
+    list_media_legal_holds,
+};
+use commands::consent_commands::{
+    record_consent,
+    withdraw_consent,
+    export_consent_receipts,
+    verify_consent_receipts,
 };
 use commands::auth_commands::{
     store_session,
@@ -77,6 +104,15 @@ use commands::auth_commands::{
     auth_request_password_reset,
     auth_verify_token,
     auth_check_status,
+    auth_start_unlock_mfa,
+    auth_unlock_session,
+    start_mfa_stepup,
+    complete_mfa_stepup,
+    enroll_totp,
+    verify_totp,
+    lock_all_data,
+    get_data_lock_status,
+    unlock_all_data,
 };
 use commands::user_commands::{
     create_user,
@@ -101,6 +137,9 @@ use commands::client_commands::{
     unassign_professional_from_client,
     increment_client_appointments,
     check_client_active_status,
+    find_duplicate_clients,
+    merge_clients,
+    unmerge_clients,
     get_client_display_name,
 };
 use commands::professional_commands::{
@@ -114,6 +153,9 @@ use commands::professional_commands::{
     get_professional_appointments,
     get_professional_stats,
     update_professional_verification,
+    list_specialties,
+    map_legacy_specialties,
+    upsert_specialty,
     check_professional_active_status,
     get_professional_display_name,
 };
@@ -121,12 +163,15 @@ use commands::appointment_commands::{
     get_appointments,
     get_appointment,
     create_appointment,
+    create_recurring_appointments,
     update_appointment,
     cancel_appointment,
     complete_appointment,
     delete_appointment,
     search_appointments,
     get_appointments_by_date_range,
+    get_schedule_conflicts,
+    get_available_slots,
     get_todays_appointments,
     get_appointment_stats,
     reschedule_appointment,
@@ -136,15 +181,61 @@ use commands::dashboard_commands::{
     get_client_dashboard_stats,
     get_professional_dashboard_stats,
     get_appointment_dashboard_stats,
+    invalidate_dashboard_cache,
     get_system_health_stats,
+    list_deidentified_report_runs,
+};
+use commands::search_commands::global_search;
+use commands::timeline_commands::get_patient_timeline;
+use commands::patient_access_commands::{set_access_notifications, get_my_access_log};
+use commands::security_commands::{
+    RbacServiceState,
+    list_active_elevated_grants,
+    request_break_glass,
+    terminate_grant,
+    reload_firebase_credentials,
+    get_effective_security_config,
+    list_error_reports,
+    clear_error_reports,
+    create_key_escrow,
+    get_key_escrow_manifest,
+    recover_master_key,
+    get_justification_policy,
+    update_justification_policy,
+    list_audit_export_profiles,
+    export_audit_log,
+    verify_record_encryption,
+    get_access_heatmap,
+    query_audit_events,
+    sanitize_input,
+    list_active_bans,
+    unban_ip,
+    unban_user,
+};
+use commands::event_commands::get_events_since;
+use commands::reminder_commands::{
+    get_reminder_template,
+    check_reminder_template,
+    save_reminder_template,
+};
+use commands::export_commands::{
+    request_data_export,
+    list_export_requests,
+    decide_export_request,
+    run_approved_export,
 };
 use commands::debug_commands::{
     initialize_devtools,
+    set_profiling_enabled,
+    get_performance_report,
+    reset_performance_report,
+    record_command_latencies,
     DevToolsState,
 };
 use console_capture::{
     log_to_devtools,
     get_devtools_status,
+    set_devtools_filter,
     get_console_injection_script,
     DevToolsBroadcaster,
 };
@@ -193,7 +284,7 @@ async fn initialize_application_services(app_handle: tauri::AppHandle) -> Result
     // Initialize Firebase service
     let firebase_service_state: tauri::State<FirebaseServiceState> = app_handle.state();
     let project_id = std::env::var("FIREBASE_PROJECT_ID")
-        .unwrap_or_else(|_| "psypsy-cms-dev".to_string());
+        .unwrap_or_else(|_| security::readiness::DEV_FIREBASE_PROJECT.to_string());
     let service_account_path = std::env::var("FIREBASE_SERVICE_ACCOUNT_PATH")
         .unwrap_or_else(|_| "firebase-service-account.json".to_string());
 
@@ -208,22 +299,104 @@ async fn initialize_application_services(app_handle: tauri::AppHandle) -> Result
         }
     }
 
+    // Expired bans and idle rate limiters are swept in the background
+    security::rate_limit::start_cleanup_sweep(
+        app_handle.state::<security::rate_limit::RateLimitServiceState>().0.clone(),
+        security::rate_limit::CLEANUP_INTERVAL,
+    );
+
+    // Keep the service-account credential and access token fresh across key rotations
+    services::firebase_service_simple::start_credential_refresh(
+        firebase_service_state.inner().clone(),
+        services::firebase_service_simple::CredentialRefreshConfig::default(),
+    );
+
+    let app_data_dir = app_handle.path().app_data_dir()?;
+    std::fs::create_dir_all(&app_data_dir)?;
+
     // Initialize Auth service
     let auth_service_state: tauri::State<AuthServiceState> = app_handle.state();
     let api_key = std::env::var("FIREBASE_API_KEY")
-        .unwrap_or_else(|_| "demo-api-key".to_string());
+        .unwrap_or_else(|_| security::readiness::DEMO_FIREBASE_API_KEY.to_string());
     let jwt_secret = std::env::var("JWT_SECRET")
-        .unwrap_or_else(|_| "default-dev-secret-change-in-production".to_string());
+        .unwrap_or_else(|_| security::readiness::DEV_JWT_SECRET.to_string());
 
+    // Concurrent-session limits come from the running rate-limit configuration
+    let rate_limits = app_handle.state::<security::effective_config::SecurityConfigState>().current().rate_limits;
     let auth_service = security::auth::FirebaseAuthService::new(
         project_id.clone(),
         api_key,
         jwt_secret.as_bytes(),
-    );
+    )
+    .with_rate_limits(rate_limits)
+    .with_geo_resolver(security::geolocation::resolver_from_env())
+    .with_audit_service(app_handle.state::<security::audit::AuditServiceState>().0.clone());
+    // Second factors must be readable before anyone signs in
+    auth_service.attach_factor_storage(&app_data_dir)?;
+    app_handle.state::<RbacServiceState>().0.attach_sessions(auth_service.session_tracker());
     log::info!("Auth service initialized successfully");
     let mut guard = auth_service_state.0.lock().await;
     *guard = Some(auth_service);
 
+    // Create or upgrade local SQLite schemas before any command touches them
+    for (file, migrations) in [
+        ("psypsy_notes.db", storage::migrations::NOTES_MIGRATIONS),
+        ("psypsy_sessions.db", storage::migrations::SESSIONS_MIGRATIONS),
+    ] {
+        storage::migrations::open_and_migrate(&app_data_dir.join(file), migrations)?;
+    }
+    log::info!("Local database schemas are up to date");
+
+    if let Some(reporter) = services::error_reporter::error_reporter() {
+        if let Err(e) = reporter.attach_storage(&app_data_dir) {
+            log::warn!("Error reports will not persist across restarts: {}", e);
+        }
+    }
+    // Data keys are wrapped into the keyring so encrypted files survive a restart
+    let crypto = app_handle.state::<security::crypto::CryptoServiceState>();
+    if let Err(e) = crypto.0.attach_keyring(&app_data_dir) {
+        log::error!("Encryption keyring unavailable; PHI files cannot be encrypted until it opens: {}", e);
+    }
+    let security_config = app_handle.state::<security::effective_config::SecurityConfigState>();
+    if let Err(e) = security_config.attach_storage(&app_data_dir) {
+        log::warn!("Security config reviews will not detect drift across restarts: {}", e);
+    }
+    let justification_policy = app_handle.state::<security::access_justification::JustificationPolicyState>();
+    if let Err(e) = justification_policy.attach_storage(&app_data_dir) {
+        log::warn!("Access justification policy will not persist across restarts: {}", e);
+    }
+    let consent_receipts = app_handle.state::<compliance::consent_receipts::ConsentReceiptState>();
+    if let Err(e) = consent_receipts.attach_storage(&app_data_dir) {
+        log::error!("Consent receipts cannot be signed until storage is available: {}", e);
+    }
+    let specialty_taxonomy = app_handle.state::<services::specialty_taxonomy::SpecialtyTaxonomyState>();
+    if let Err(e) = specialty_taxonomy.attach_storage(&app_data_dir) {
+        log::warn!("Specialty taxonomy changes will not persist across restarts: {}", e);
+    }
+    let access_notifications = app_handle.state::<services::patient_access_notifications::PatientAccessNotifications>();
+    if let Err(e) = access_notifications.attach_storage(&app_data_dir) {
+        log::error!("Patient access notification store could not be opened; opt-ins and access logs will not persist: {}", e);
+    }
+    commands::patient_access_commands::start_access_notifier(app_handle.clone());
+    let search_index = app_handle.state::<storage::search_index::SearchIndexState>();
+    let search_config = app_handle.state::<storage::search_index::SearchIndexConfig>().inner().clone();
+    if let Err(e) = search_index.attach_storage(&app_data_dir, search_config) {
+        log::error!("Search index unavailable; record changes will be refused until it opens: {}", e);
+    }
+    let legal_holds = app_handle.state::<meeting::retention::LegalHoldState>();
+    match legal_holds.attach_storage(&app_data_dir) {
+        Ok(()) => {
+            let retention_config = app_handle.state::<meeting::retention::MediaRetentionConfig>().inner().clone();
+            meeting::retention::start_media_retention_job(app_handle.clone(), retention_config);
+        }
+        Err(e) => log::error!("Media legal holds could not be loaded; retention purge disabled: {}", e),
+    }
+
+    let report_config = app_handle.state::<services::deidentified_reports::DeidentifiedReportConfig>().inner().clone();
+    services::deidentified_reports::start_deidentified_report_job(app_handle.clone(), report_config);
+    services::sync_schedule::start_sync_scheduler(app_handle.clone());
+    security::crypto::start_key_rotation_scheduler(app_handle.clone());
+
     // Note: Storage and sync services are initialized via Tauri commands when needed
     // This is because they require user-specific data (passphrase, user ID, etc.)
 
@@ -240,9 +413,15 @@ pub fn run() {
 
     // Initialize DevTools server for WebSocket debugging
     let devtools_server = DevToolsServer::new(9223); // Use port 9223 for cms-debugger
-    let broadcast_tx = devtools_server.get_broadcast_sender();
+    let devtools_hub = devtools_server.hub();
+
+    // The WebSocket server replays console output, which can carry PHI, to anything on loopback,
+    // so release builds never start it
+    #[cfg(not(debug_assertions))]
+    drop(devtools_server);
 
     // Start DevTools WebSocket server in a separate thread with proper error handling
+    #[cfg(debug_assertions)]
     std::thread::spawn(move || {
         eprintln!("🔧 CMS DevTools thread spawned, starting WebSocket server on port 9223...");
 
@@ -271,18 +450,114 @@ pub fn run() {
         eprintln!("🔧 CMS DevTools thread completed");
     });
 
+    // Capture panics from the start; reporting stays off unless the deployment opts in
+    let error_reporter = services::error_reporter::install_error_reporter(
+        services::error_reporter::ErrorReporter::new(services::error_reporter::ErrorReporterConfig::from_env())
+    );
+    log::info!("Error reporting {}", if error_reporter.is_enabled() { "enabled" } else { "disabled" });
+
+    // Install the SSRF guard before any HTTP client or config snapshot is built
+    let mut outbound_policy = security::outbound::OutboundPolicy::default();
+    if let Ok(extra_hosts) = std::env::var("OUTBOUND_ALLOWED_HOSTS") {
+        outbound_policy.allowed_hosts.extend(
+            extra_hosts.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty())
+        );
+    }
+    if let Err(e) = security::outbound::install_outbound_guard(security::outbound::OutboundGuard::new(outbound_policy)) {
+        log::warn!("{}", e);
+    }
+
+    // Refuse to run a compliance deployment on development secrets or open defaults
+    let security_config = security::effective_config::EffectiveSecurityConfig::from_env();
+    if let Err(message) = security::readiness::enforce_production_readiness(
+        &security_config,
+        &security::readiness::DeploymentSettings::from_env(),
+    ) {
+        log::error!("{}", message);
+        eprintln!("❌ {}", message);
+        std::process::exit(1);
+    }
+    let sanitizer = match security::validation::SanitizationService::new() {
+        Ok(service) => security::validation::SanitizationServiceState::new(service),
+        Err(e) => {
+            log::error!("Failed to build input sanitizer: {}", e);
+            eprintln!("❌ Failed to build input sanitizer: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .manage(StorageState::default())
         .manage(SyncServiceState::default())
+        .manage(services::sync_schedule::SyncScheduler::new(services::sync_schedule::SyncSchedule::from_env()))
         .manage(SocialMediaState::default())
         .manage(FirebaseServiceState::default())
         .manage(AuthServiceState::default())
+        .manage(RbacServiceState::default())
+        .manage(sanitizer)
+        .manage(security::command_policy::CommandPolicy::from_env())
+        .manage(security::session_binding::SessionBindingConfig::from_env())
+        .manage(security::session_binding::SessionBindingState::default())
+        .manage(security::step_up::StepUpState::new(security::step_up::StepUpRules::from_env()))
+        .manage(security::minimization::MinimizationPolicy::default())
+        .manage(security::data_scope::DataScopePolicy::default())
+        .manage(security::audit_export::AuditExportProfiles::default())
+        .manage(security::login_lockout::LoginLockout::from_config(&security_config.security))
+        .manage(security::crypto::CryptoServiceState::new(security::crypto::CryptoService::from_security_config(&security_config.security)))
+        .manage(security::rate_limit::RateLimitServiceState::new(
+            security::rate_limit::RateLimitService::new(security_config.rate_limits.clone())
+                .with_geo_resolver(security::geolocation::resolver_from_env()),
+        ))
+        .manage(security::effective_config::SecurityConfigState::new(security_config))
+        .manage(services::event_log::EventLogState::default())
+        .manage(services::workstation_lock::WorkstationLockConfig::from_env())
+        .manage(services::data_lock::DataLockConfig::from_env())
+        .manage(services::data_lock::DataLockState::default())
+        .manage(security::key_escrow::KeyEscrowConfig::from_env())
+        .manage(security::access_justification::JustificationPolicyState::new(
+            security::access_justification::JustificationPolicy::from_env(),
+        ))
+        .manage(services::scheduling::SchedulingConfig::from_env())
+        .manage(services::dashboard_cache::DashboardCache::from_env())
+        .manage(services::system_health::SystemHealthConfig::from_env())
+        .manage(services::client_dedup::DuplicateDetectionConfig::from_env())
+        .manage(services::notifier::NotifierState::from_env())
+        .manage(services::patient_access_notifications::PatientAccessNotifications::new(
+            services::patient_access_notifications::PatientAccessNotificationConfig::from_env(),
+        ))
+        .manage(services::deidentified_reports::DeidentifiedReportConfig::from_env())
+        .manage(services::deidentified_reports::DeidentifiedReportState::default())
+        .manage(storage::search_index::SearchIndexConfig::from_env())
+        .manage(storage::search_index::SearchIndexState::default())
+        .manage(services::patient_timeline::PatientTimelineConfig::from_env())
+        .manage(services::specialty_taxonomy::SpecialtyTaxonomyState::new(
+            services::specialty_taxonomy::SpecialtyTaxonomy::from_env(),
+        ))
+        .manage(services::note_compliance_rules::ComplianceRuleSet::from_env())
+        .manage(meeting::RecordingConfig::from_env())
+        .manage(meeting::RecordingState::default())
+        .manage(meeting::redaction::TranscriptRedactionConfig::from_env())
+        .manage(meeting::transcription::LiveTranscriptionConfig::from_env())
+        .manage(meeting::retention::MediaRetentionConfig::from_env())
+        .manage(security::secure_wipe::SecureWipeConfig::from_env())
+        .manage(meeting::retention::LegalHoldState::default())
+        .manage(services::reminder_templates::ReminderTemplateState::default())
+        .manage(services::export_approval::ExportApprovalConfig::from_env())
+        .manage(services::export_approval::ExportApprovalState::default())
+        .manage(compliance::consent_receipts::ConsentReceiptConfig::from_env())
+        .manage(compliance::consent_receipts::ConsentReceiptState::default())
+        .manage(services::media_validation::MediaValidationConfig::from_env())
+        .manage(security::access_heatmap::AccessHeatmapConfig::from_env())
+        .manage(services::media_moderation::MediaScannerState(std::sync::Arc::new(
+            services::media_moderation::MediaScanner::new(services::media_moderation::MediaModerationConfig::from_env()),
+        )))
         .manage(Arc::new(tokio::sync::RwLock::new(AuthState::default())))
         .manage(Arc::new(std::sync::RwLock::new(DevToolsState::default())))
-        .manage(DevToolsBroadcaster { tx: broadcast_tx.clone() })
+        .manage(DevToolsBroadcaster::new(devtools_hub.clone()))
         .manage(std::sync::RwLock::new(HashMap::<String, User>::new()))
-        .invoke_handler(tauri::generate_handler![
+        // Every command passes its declared requirements in security::command_policy first
+        .invoke_handler(security::command_policy::guarded(tauri::generate_handler![
             // Core system commands
             greet,
             get_system_info,
@@ -298,6 +573,15 @@ pub fn run() {
             auth_request_password_reset,
             auth_verify_token,
             auth_check_status,
+            auth_start_unlock_mfa,
+            auth_unlock_session,
+            start_mfa_stepup,
+            complete_mfa_stepup,
+            enroll_totp,
+            verify_totp,
+            lock_all_data,
+            get_data_lock_status,
+            unlock_all_data,
             store_session,
             get_stored_session,
             clear_stored_session,
@@ -325,6 +609,9 @@ pub fn run() {
             unassign_professional_from_client,
             increment_client_appointments,
             check_client_active_status,
+            find_duplicate_clients,
+            merge_clients,
+            unmerge_clients,
             get_client_display_name,
 
             // Professional management commands
@@ -338,6 +625,9 @@ pub fn run() {
             get_professional_appointments,
             get_professional_stats,
             update_professional_verification,
+            list_specialties,
+            map_legacy_specialties,
+            upsert_specialty,
             check_professional_active_status,
             get_professional_display_name,
 
@@ -345,12 +635,15 @@ pub fn run() {
             get_appointments,
             get_appointment,
             create_appointment,
+            create_recurring_appointments,
             update_appointment,
             cancel_appointment,
             complete_appointment,
             delete_appointment,
             search_appointments,
             get_appointments_by_date_range,
+            get_schedule_conflicts,
+            get_available_slots,
             get_todays_appointments,
             get_appointment_stats,
             reschedule_appointment,
@@ -360,18 +653,68 @@ pub fn run() {
             get_client_dashboard_stats,
             get_professional_dashboard_stats,
             get_appointment_dashboard_stats,
+            invalidate_dashboard_cache,
             get_system_health_stats,
+            list_deidentified_report_runs,
+            global_search,
+            get_patient_timeline,
+            set_access_notifications,
+            get_my_access_log,
+
+            // Reminder template commands
+            get_reminder_template,
+            check_reminder_template,
+            save_reminder_template,
+
+            // Data export approval commands
+            request_data_export,
+            list_export_requests,
+            decide_export_request,
+            run_approved_export,
+
+            // Event replay commands
+            get_events_since,
+
+            // Security oversight commands
+            list_active_elevated_grants,
+            request_break_glass,
+            terminate_grant,
+            reload_firebase_credentials,
+            get_effective_security_config,
+            list_error_reports,
+            clear_error_reports,
+            create_key_escrow,
+            get_key_escrow_manifest,
+            recover_master_key,
+            get_justification_policy,
+            update_justification_policy,
+            list_audit_export_profiles,
+            export_audit_log,
+            verify_record_encryption,
+            get_access_heatmap,
+            query_audit_events,
+            sanitize_input,
+            list_active_bans,
+            unban_ip,
+            unban_user,
 
             // Medical notes commands
             initialize_encrypted_storage,
             save_medical_note,
             get_medical_note,
+            get_note_history,
+            get_note_version,
             list_patient_notes,
             delete_medical_note,
             get_audit_trail,
             create_medical_note,
             validate_note_compliance,
             storage_status,
+            reclassify_medical_note,
+            reencrypt_notes_for_classification,
+            get_reencryption_job,
+            attach_note_media,
+            list_note_attachments,
 
             // Offline sync commands
             initialize_sync_service,
@@ -385,12 +728,20 @@ pub fn run() {
             get_pending_sync_count,
             start_background_sync,
             stop_background_sync,
+            get_sync_schedule,
+            update_sync_schedule,
+            sync_now,
 
             // Social media integration commands
             get_social_media_connections,
             get_oauth_configs,
             save_oauth_config,
             record_social_media_consent,
+            withdraw_social_media_consent,
+            record_consent,
+            withdraw_consent,
+            export_consent_receipts,
+            verify_consent_receipts,
             initiate_oauth_flow,
             disconnect_platform,
             get_connected_platforms,
@@ -405,15 +756,35 @@ pub fn run() {
             // Meeting and recording commands
             start_recording,
             stop_recording,
+            force_stop_recording,
+            get_recording_owner,
             is_recording,
+            list_input_devices,
             get_transcription_status,
             save_transcript,
+            load_transcript,
+            export_redacted_transcript,
+            list_expiring_media,
+            run_media_retention_purge,
+            place_media_legal_hold,
+            release_media_legal_hold,
+            list_media_legal_holds,
 
             // Debug and DevTools commands
             log_to_devtools,
             initialize_devtools,
-            get_devtools_status
-        ])
+            get_devtools_status,
+            set_devtools_filter,
+            set_profiling_enabled,
+            get_performance_report,
+            reset_performance_report,
+            record_command_latencies
+        ]))
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                meeting::release_recording_for_closed_window(window.app_handle(), window.label());
+            }
+        })
         .setup(|app| {
             // Inject enhanced console capture script with healthcare React error patterns
             let console_script = get_console_injection_script();
@@ -428,6 +799,15 @@ pub fn run() {
                     eprintln!("✅ CMS DevTools console capture script injected successfully");
                 }
 
+                // Command latency profiling, off unless PROFILING_ENABLED is set
+                let profiler = services::command_profiler::profiler();
+                if profiler.is_enabled() {
+                    let script = services::command_profiler::profiler_injection_script(true);
+                    if let Err(e) = window.eval(&script) {
+                        log::warn!("Failed to inject command profiler: {}", e);
+                    }
+                }
+
                 // Open developer tools in debug builds
                 #[cfg(debug_assertions)]
                 {
@@ -439,6 +819,16 @@ pub fn run() {
                 eprintln!("⚠️ Could not find main window for script injection");
             }
 
+            // Audit trail under the app data directory; built inside the runtime so batches
+            // are flushed on time, and installed before any command can write to it
+            let audit_dir = app.path().app_data_dir()?;
+            let audit_config = security::audit::AuditConfig::for_app_data(&audit_dir);
+            let audit_service = Arc::new(tauri::async_runtime::block_on(async {
+                security::audit::AuditService::new(audit_config)
+            })?);
+            security::audit::install_audit_service(audit_service.clone())?;
+            app.manage(security::audit::AuditServiceState(audit_service));
+
             // Initialize services on startup
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
@@ -449,6 +839,10 @@ pub fn run() {
                 }
             });
 
+            // Lock the session when the OS locks or sleeps
+            let lock_config = app.state::<services::workstation_lock::WorkstationLockConfig>().inner().clone();
+            services::workstation_lock::start_workstation_lock_monitor(app.handle().clone(), lock_config);
+
             log::info!("PsyPsy CMS - Quebec Law 25 Compliant Healthcare System with encrypted medical notes initialized");
             Ok(())
         })
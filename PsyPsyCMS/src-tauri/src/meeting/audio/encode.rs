@@ -3,7 +3,7 @@ use super::AudioDevice;
 use std::io::Write;
 use std::sync::Arc;
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 use tracing::{debug, error};
@@ -82,3 +82,52 @@ pub fn encode_single_audio(
 
     Ok(())
 }
+
+/// Write mono samples as 16-bit PCM WAV, readable only by the current user. An empty
+/// recording still produces a valid file with no audio frames.
+pub fn write_wav(samples: &[f32], sample_rate: u32, output_path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(output_path)?;
+    // The mode above only applies to new files
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    encode_pcm16(samples, sample_rate, std::io::BufWriter::new(file))?;
+    debug!("Wrote {} samples to {:?}", samples.len(), output_path);
+    Ok(())
+}
+
+/// Mono samples as an in-memory 16-bit PCM WAV file
+pub fn wav_bytes(samples: &[f32], sample_rate: u32) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    encode_pcm16(samples, sample_rate, &mut bytes)?;
+    Ok(bytes.into_inner())
+}
+
+fn encode_pcm16<W: std::io::Write + std::io::Seek>(samples: &[f32], sample_rate: u32, out: W) -> anyhow::Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::new(out, spec)?;
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
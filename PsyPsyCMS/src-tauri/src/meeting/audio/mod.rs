@@ -6,12 +6,12 @@ pub mod ffmpeg;
 
 // Only export actually used components to reduce warnings
 pub use core::{
-    default_input_device,
-    AudioDevice, AudioStream,
+    default_input_device, list_audio_devices,
+    AudioDevice, AudioStream, DeviceType,
 };
 
 // Unused exports commented out to reduce warnings:
-// default_output_device, get_device_and_config, list_audio_devices,
+// default_output_device, get_device_and_config,
 // parse_audio_device, trigger_audio_permission,
-// AudioTranscriptionEngine, DeviceControl, DeviceType,
+// AudioTranscriptionEngine, DeviceControl,
 // LAST_AUDIO_CAPTURE, encode_single_audio, AudioInput
\ No newline at end of file
@@ -4,18 +4,263 @@
 pub mod audio;
 pub mod analytics;
 pub mod utils;
+pub mod redaction;
+pub mod retention;
+pub mod transcription;
 
-use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}, OnceLock};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tauri::{Runtime, AppHandle};
-use crate::meeting::audio::AudioStream;
+use tauri::{Runtime, AppHandle, Manager, State, Window};
+use crate::meeting::audio::{AudioDevice, AudioStream, DeviceType};
+use crate::security::auth::AuthState;
+use crate::security::crypto::{CryptoService, CryptoServiceState, EncryptedData, EncryptionContext};
+use crate::security::{DataClassification, HealthcareRole};
+use crate::security::secure_wipe;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use crate::meeting::redaction::{redact_transcript, RedactedTranscript, TranscriptRedactionConfig, TranscriptRedactionMode};
+use crate::meeting::retention::{LegalHold, LegalHoldState, MediaPurgeReport, MediaRetentionConfig, MediaRetentionEntry};
 
-static RECORDING_FLAG: AtomicBool = AtomicBool::new(false);
-static MIC_BUFFER: OnceLock<Arc<Mutex<Vec<f32>>>> = OnceLock::new();
-static SYSTEM_BUFFER: OnceLock<Arc<Mutex<Vec<f32>>>> = OnceLock::new();
-static MIC_STREAM: OnceLock<Arc<AudioStream>> = OnceLock::new();
-static SYSTEM_STREAM: OnceLock<Arc<AudioStream>> = OnceLock::new();
-static IS_RUNNING: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+/// Capture rate assumed when no input stream reported one
+const DEFAULT_SAMPLE_RATE: u32 = 16_000;
+
+/// Recording behaviour across windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingConfig {
+    /// Stop and release a recording when the window that owns it is destroyed
+    pub release_on_window_close: bool,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            release_on_window_close: true,
+        }
+    }
+}
+
+impl RecordingConfig {
+    /// Defaults with a `RECORDING_RELEASE_ON_WINDOW_CLOSE` override
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            release_on_window_close: std::env::var("RECORDING_RELEASE_ON_WINDOW_CLOSE")
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(defaults.release_on_window_close),
+        }
+    }
+}
+
+/// Window and user that started the active recording
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingOwner {
+    pub window_label: String,
+    pub user_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Single-owner lock on the recording pipeline; claim and release are atomic
+pub struct RecordingOwnership(Mutex<Option<RecordingOwner>>);
+
+impl RecordingOwnership {
+    pub const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    fn owned_elsewhere(owner: &RecordingOwner) -> String {
+        format!(
+            "Recording owned by another session (window '{}', started {})",
+            owner.window_label,
+            owner.started_at.to_rfc3339()
+        )
+    }
+
+    pub fn claim(&self, owner: RecordingOwner) -> Result<(), String> {
+        let mut current = self.0.lock().unwrap();
+        match current.as_ref() {
+            Some(existing) if existing.window_label == owner.window_label => {
+                Err("Recording already in progress in this window".to_string())
+            }
+            Some(existing) => Err(Self::owned_elsewhere(existing)),
+            None => {
+                *current = Some(owner);
+                Ok(())
+            }
+        }
+    }
+
+    /// Release on behalf of `window_label`; other windows are refused
+    pub fn release(&self, window_label: &str) -> Result<Option<RecordingOwner>, String> {
+        let mut current = self.0.lock().unwrap();
+        match current.as_ref() {
+            None => Ok(None),
+            Some(existing) if existing.window_label == window_label => Ok(current.take()),
+            Some(existing) => Err(Self::owned_elsewhere(existing)),
+        }
+    }
+
+    /// Release regardless of owner
+    pub fn force_release(&self) -> Option<RecordingOwner> {
+        self.0.lock().unwrap().take()
+    }
+
+    pub fn owner(&self) -> Option<RecordingOwner> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Default for RecordingOwnership {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Buffers, streams and running flag of one recording. Every recording gets a new session,
+/// which is torn down when the recording stops.
+pub struct RecordingSession {
+    mic_buffer: Arc<Mutex<Vec<f32>>>,
+    system_buffer: Arc<Mutex<Vec<f32>>>,
+    mic_stream: Option<AudioStream>,
+    system_stream: Option<AudioStream>,
+    is_running: Arc<AtomicBool>,
+    /// Input device the microphone stream was opened on
+    device_name: Option<String>,
+}
+
+impl RecordingSession {
+    fn new() -> Self {
+        Self {
+            mic_buffer: Arc::new(Mutex::new(Vec::new())),
+            system_buffer: Arc::new(Mutex::new(Vec::new())),
+            mic_stream: None,
+            system_stream: None,
+            is_running: Arc::new(AtomicBool::new(true)),
+            device_name: None,
+        }
+    }
+
+    /// Open `device` and start filling the microphone buffer
+    async fn start_capture(&mut self, device: AudioDevice) -> Result<(), String> {
+        log::info!("Found input device for recording: {}", device.name);
+        let name = device.name.clone();
+        let stream = AudioStream::from_device(Arc::new(device), self.is_running.clone())
+            .await
+            .map_err(|e| format!("Failed to initialize microphone stream: {}", e))?;
+        spawn_capture(&stream, self.mic_buffer.clone(), self.is_running.clone()).await;
+        self.mic_stream = Some(stream);
+        self.device_name = Some(name);
+        log::info!("Microphone stream initialized successfully");
+        Ok(())
+    }
+
+    /// Sample rate of the microphone stream
+    fn sample_rate(&self) -> u32 {
+        self.mic_stream.as_ref().map_or(DEFAULT_SAMPLE_RATE, |stream| stream.device_config.sample_rate().0)
+    }
+
+    /// Mic samples with system audio mixed in, copied out of the capture buffers
+    fn mixed_samples(&self) -> Vec<f32> {
+        let copy = |buffer: &Mutex<Vec<f32>>| match buffer.lock() {
+            Ok(samples) => samples.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let mut mixed = copy(&self.mic_buffer);
+        let mut system = copy(&self.system_buffer);
+        mix_into(&mut mixed, &system);
+        secure_wipe::wipe_buffer(&mut system);
+        mixed
+    }
+
+    /// Stop the streams so nothing more arrives in the buffers
+    async fn stop_capture(&self) {
+        self.is_running.store(false, Ordering::SeqCst);
+        for (name, stream) in [("microphone", &self.mic_stream), ("system audio", &self.system_stream)] {
+            if let Some(stream) = stream {
+                if let Err(e) = stream.stop().await {
+                    log::warn!("Failed to stop {} stream: {}", name, e);
+                }
+            }
+        }
+    }
+
+    /// Zero captured samples and drop the streams. The capture tasks end once the streams are
+    /// gone, so nothing outlives the session.
+    fn wipe(self) {
+        for buffer in [&self.mic_buffer, &self.system_buffer] {
+            match buffer.lock() {
+                Ok(mut samples) => secure_wipe::wipe_buffer(&mut samples),
+                Err(poisoned) => secure_wipe::wipe_buffer(&mut poisoned.into_inner()),
+            }
+        }
+    }
+
+    /// Stop capturing and wipe the buffers
+    async fn shut_down(self) {
+        self.stop_capture().await;
+        self.wipe();
+        log::info!("Recording session torn down");
+    }
+}
+
+/// Recording owner, active session and live transcription progress, managed by Tauri
+#[derive(Default)]
+pub struct RecordingState {
+    ownership: RecordingOwnership,
+    session: Mutex<Option<RecordingSession>>,
+    /// Kept after the session ends so the worker can still report while it drains
+    transcription: Mutex<Option<Arc<transcription::TranscriptionProgress>>>,
+}
+
+impl RecordingState {
+    /// Install a new session, returning any session it replaces
+    fn replace_session(&self, session: RecordingSession) -> Option<RecordingSession> {
+        self.session.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).replace(session)
+    }
+
+    fn take_session(&self) -> Option<RecordingSession> {
+        self.session.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.session.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_some()
+    }
+
+    /// Input device of the active session
+    fn device_name(&self) -> Option<String> {
+        self.session
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .and_then(|session| session.device_name.clone())
+    }
+}
+
+/// The input device called `name`, as listed or in its `Name (input)` display form
+fn find_input_device(devices: &[AudioDevice], name: &str) -> Result<AudioDevice, String> {
+    let name = name.trim();
+    devices
+        .iter()
+        .filter(|d| d.device_type == DeviceType::Input)
+        .find(|d| d.name == name || d.to_string() == name)
+        .cloned()
+        .ok_or_else(|| {
+            let available: Vec<&str> = devices
+                .iter()
+                .filter(|d| d.device_type == DeviceType::Input)
+                .map(|d| d.name.as_str())
+                .collect();
+            format!("Input device '{}' not found; available: {}", name, available.join(", "))
+        })
+}
+
+/// Microphones and other input devices that can be recorded from
+#[tauri::command]
+pub async fn list_input_devices() -> Result<Vec<AudioDevice>, String> {
+    let devices = audio::list_audio_devices()
+        .await
+        .map_err(|e| format!("Failed to list audio devices: {}", e))?;
+    Ok(devices.into_iter().filter(|d| d.device_type == DeviceType::Input).collect())
+}
 
 #[derive(Debug, Deserialize)]
 pub struct RecordingArgs {
@@ -27,6 +272,8 @@ pub struct TranscriptionStatus {
     pub chunks_in_queue: usize,
     pub is_processing: bool,
     pub last_activity_ms: u64,
+    /// Input device being captured
+    pub device_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -40,157 +287,383 @@ pub struct TranscriptUpdate {
 }
 
 // Basic recording commands for HIPAA compliance
+/// Start recording, owned by the calling window until that window stops it. Records from the
+/// named input device, or the system default when none is given.
 #[tauri::command]
-pub async fn start_recording<R: Runtime>(_app: AppHandle<R>) -> Result<(), String> {
+pub async fn start_recording<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    device_name: Option<String>,
+    recording: State<'_, RecordingState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<(), String> {
     log::info!("Starting PIPEDA + Quebec Law 25 compliant recording...");
 
-    if is_recording() {
-        return Err("Recording already in progress".to_string());
-    }
-
-    // Initialize recording infrastructure
-    let _ = MIC_BUFFER.set(Arc::new(Mutex::new(Vec::new())));
-    let _ = SYSTEM_BUFFER.set(Arc::new(Mutex::new(Vec::new())));
-    let _ = IS_RUNNING.set(Arc::new(AtomicBool::new(true)));
+    // A named device must exist; resolved before the recording is claimed
+    let device = match device_name.as_deref().filter(|n| !n.trim().is_empty()) {
+        Some(name) => {
+            let devices = audio::list_audio_devices()
+                .await
+                .map_err(|e| format!("Failed to list audio devices: {}", e))?;
+            Some(find_input_device(&devices, name)?)
+        }
+        None => match audio::default_input_device() {
+            Ok(device) => Some(device),
+            Err(e) => {
+                log::warn!("No default input device found: {}", e);
+                None
+            }
+        },
+    };
 
-    RECORDING_FLAG.store(true, Ordering::SeqCst);
+    let user_id = auth_state.read().await.user_id.clone();
+    recording.ownership.claim(RecordingOwner {
+        window_label: window.label().to_string(),
+        user_id,
+        started_at: Utc::now(),
+    })?;
 
-    // Initialize audio streams for recording
-    match initialize_audio_recording().await {
-        Ok(_) => {
-            log::info!("Audio recording infrastructure initialized successfully");
-        }
-        Err(e) => {
-            log::warn!("Failed to initialize audio recording: {} (continuing with basic recording)", e);
+    let mut session = RecordingSession::new();
+    if let Some(device) = device {
+        match session.start_capture(device).await {
+            Ok(_) => {
+                log::info!("Audio recording infrastructure initialized successfully");
+            }
+            Err(e) => {
+                log::warn!("Failed to initialize audio recording: {} (continuing with basic recording)", e);
+            }
         }
     }
 
+    if let Some(config) = app.try_state::<transcription::LiveTranscriptionConfig>().filter(|c| c.enabled) {
+        let config = config.inner().clone();
+        let engine = Arc::new(transcription::CommandSpeechToText::new(config.clone()));
+        let progress = Arc::new(transcription::TranscriptionProgress::new());
+        transcription::spawn_worker(
+            app.clone(),
+            session.mic_buffer.clone(),
+            session.sample_rate(),
+            session.is_running.clone(),
+            engine,
+            progress.clone(),
+            config,
+        );
+        *recording.transcription.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(progress);
+    }
+
+    // Ownership allows one recording at a time, so a leftover session can only be one whose
+    // teardown never ran
+    if let Some(stale) = recording.replace_session(session) {
+        log::warn!("Tearing down a recording session that was never stopped");
+        stale.shut_down().await;
+    }
+
     log::info!("Recording started successfully with PIPEDA + Quebec Law 25 compliance");
     Ok(())
 }
 
-// Initialize audio recording infrastructure
-async fn initialize_audio_recording() -> Result<(), String> {
-    use crate::meeting::audio::{AudioStream, default_input_device};
-
-    // Try to get default input device for microphone recording
-    match default_input_device() {
-        Ok(device) => {
-            log::info!("Found input device for recording: {}", device.name);
-
-            if let Some(is_running) = IS_RUNNING.get() {
-                // Initialize microphone stream
-                match AudioStream::from_device(Arc::new(device), is_running.clone()).await {
-                    Ok(stream) => {
-                        let _ = MIC_STREAM.set(Arc::new(stream));
-                        log::info!("Microphone stream initialized successfully");
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to initialize microphone stream: {}", e);
-                    }
-                }
+/// Append a stream's chunks to a capture buffer while the recording runs
+async fn spawn_capture(stream: &AudioStream, buffer: Arc<Mutex<Vec<f32>>>, is_running: Arc<AtomicBool>) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut chunks = stream.subscribe().await;
+    tokio::spawn(async move {
+        while is_running.load(Ordering::SeqCst) {
+            match chunks.recv().await {
+                Ok(chunk) => match buffer.lock() {
+                    Ok(mut samples) => samples.extend_from_slice(&chunk),
+                    Err(poisoned) => poisoned.into_inner().extend_from_slice(&chunk),
+                },
+                Err(RecvError::Lagged(skipped)) => log::warn!("Recording fell behind; {} chunks dropped", skipped),
+                Err(RecvError::Closed) => break,
             }
         }
-        Err(e) => {
-            log::warn!("No default input device found: {}", e);
-        }
-    }
+    });
+}
 
-    Ok(())
+/// Add `other` onto `samples` sample by sample, extending with silence where it is longer
+fn mix_into(samples: &mut Vec<f32>, other: &[f32]) {
+    if other.len() > samples.len() {
+        samples.resize(other.len(), 0.0);
+    }
+    for (sample, extra) in samples.iter_mut().zip(other) {
+        *sample = (*sample + extra).clamp(-1.0, 1.0);
+    }
 }
 
+/// Directory under the app data directory that recordings are saved into
+const RECORDINGS_DIR: &str = "recordings";
+
+/// Stop the recording and save the captured audio as encrypted WAV under the app data
+/// directory; only the window that started it may do so. `save_path` names the file, and any
+/// directory it gives must lie inside the app data directory. An existing file is never
+/// overwritten. Stopping when nothing is recording does nothing. Returns the saved path.
 #[tauri::command]
-pub async fn stop_recording(_args: RecordingArgs) -> Result<(), String> {
+pub async fn stop_recording<R: Runtime>(
+    args: RecordingArgs,
+    window: Window<R>,
+    recording: State<'_, RecordingState>,
+    crypto: State<'_, CryptoServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<Option<String>, String> {
     log::info!("Stopping PIPEDA + Quebec Law 25 compliant recording...");
 
-    if !RECORDING_FLAG.load(Ordering::SeqCst) {
-        return Ok(());
+    // Ownership is checked without releasing, so another window's recording is refused and
+    // stopping when idle succeeds before the save path is looked at
+    match recording.ownership.owner() {
+        None => return Ok(None),
+        Some(owner) if owner.window_label != window.label() => {
+            return recording.ownership.release(window.label()).map(|_| None);
+        }
+        Some(_) => {}
     }
 
-    // Signal stopping to all recording infrastructure
-    if let Some(is_running) = IS_RUNNING.get() {
-        is_running.store(false, Ordering::SeqCst);
+    // Checked before anything stops, so a bad path or locked storage does not cost the recording
+    let app_data_dir = window.app_handle().path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    let save_path = recording_save_path(&app_data_dir, std::path::Path::new(&args.save_path))?;
+    if save_path.exists() {
+        return Err(format!("A recording named {} already exists", save_path.display()));
+    }
+    if !crypto.0.is_initialized().await {
+        return Err("Encryption is not initialized; unlock encrypted storage before saving recordings".to_string());
     }
 
-    RECORDING_FLAG.store(false, Ordering::SeqCst);
+    let Some(owner) = recording.ownership.release(window.label())? else {
+        return Ok(None);
+    };
+    let Some(session) = recording.take_session() else {
+        log::warn!("Recording owned by '{}' had no capture session; nothing to save", owner.window_label);
+        return Ok(None);
+    };
 
-    // Clean up audio streams
-    cleanup_audio_recording().await;
+    // Stop capturing first so nothing arrives after the buffers are copied
+    session.stop_capture().await;
 
-    // Clean up recording infrastructure
-    // Note: OnceLock doesn't support taking values after initialization
-    // The cleanup will happen when the static variables are dropped
-    if let Some(mic_stream) = MIC_STREAM.get() {
-        if let Err(e) = mic_stream.stop().await {
-            log::warn!("Failed to stop microphone stream: {}", e);
-        }
-    }
-    // System stream cleanup would go here if needed
-    log::info!("Recording infrastructure marked for cleanup");
+    let mut samples = session.mixed_samples();
+    let sample_rate = session.sample_rate();
+    let sample_count = samples.len();
+    let encoded = audio::encode::wav_bytes(&samples, sample_rate);
+    secure_wipe::wipe_buffer(&mut samples);
+    session.wipe();
+    let mut wav = encoded.map_err(|e| format!("Failed to encode recording: {}", e))?;
 
-    log::info!("Recording stopped and encrypted for PIPEDA + Quebec Law 25 compliance");
-    Ok(())
+    let recording_id = uuid::Uuid::new_v4().to_string();
+    let encrypted = crypto.0
+        .encrypt_with_context(&wav, DataClassification::Phi, None, &recording_context(&recording_id))
+        .await;
+    secure_wipe::wipe_buffer(&mut wav);
+    let encrypted = encrypted.map_err(|e| format!("Failed to encrypt recording: {}", e))?;
+    write_recording(&save_path, &recording_id, &encrypted)?;
+
+    log::info!("Recording saved: {} samples at {} Hz", sample_count, sample_rate);
+
+    let user_id = match auth_state.read().await.user_id.clone() {
+        Some(user_id) => user_id,
+        None => owner.user_id.unwrap_or_default(),
+    };
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "RECORDING_SAVED",
+        "session_media",
+        &user_id,
+        true, // Session audio
+        Some(serde_json::json!({
+            "file": save_path.file_name().map(|n| n.to_string_lossy().to_string()),
+            "recording_id": recording_id,
+            "encrypted": true,
+            "window": owner.window_label,
+            "started_at": owner.started_at.to_rfc3339(),
+            "sample_rate": sample_rate,
+            "duration_secs": sample_count as f64 / sample_rate as f64,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(Some(save_path.to_string_lossy().to_string()))
 }
 
-// Clean up audio recording infrastructure
-async fn cleanup_audio_recording() {
-    log::info!("Cleaning up audio recording infrastructure...");
+/// Where a recording asked for at `requested` is saved: the file name, which must end in
+/// .wav, inside the recordings directory of `app_data_dir`. A requested directory outside the
+/// app data directory is refused.
+fn recording_save_path(app_data_dir: &std::path::Path, requested: &std::path::Path) -> Result<std::path::PathBuf, String> {
+    use std::path::Component;
 
-    if let Some(mic_stream) = MIC_STREAM.get() {
-        if let Err(e) = mic_stream.stop().await {
-            log::warn!("Failed to stop microphone stream gracefully: {}", e);
-        } else {
-            log::info!("Microphone stream stopped successfully");
+    if requested.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err("Recording paths may not contain '..'".to_string());
+    }
+    if let Some(parent) = requested.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if !parent.starts_with(app_data_dir) {
+            return Err("Recordings are saved in the app data directory".to_string());
         }
     }
+    let file_name = requested.file_name().ok_or("The save path must name a file")?;
+    if !std::path::Path::new(file_name).extension().map_or(false, |e| e.eq_ignore_ascii_case("wav")) {
+        return Err("Recordings are saved as WAV; the save path must end in .wav".to_string());
+    }
+    Ok(app_data_dir.join(RECORDINGS_DIR).join(file_name))
+}
+
+/// Encryption context of a recording; recordings are not tied to a client when saved
+fn recording_context(recording_id: &str) -> EncryptionContext {
+    EncryptionContext::for_record("unassigned", recording_id)
+}
+
+/// Write the encrypted recording with the id its context was bound to; fails rather than
+/// replace an existing file
+fn write_recording(path: &std::path::Path, recording_id: &str, encrypted: &EncryptedData) -> Result<(), String> {
+    use std::io::Write;
 
-    if let Some(_system_stream) = SYSTEM_STREAM.get() {
-        // System audio stream cleanup would go here
-        log::info!("System audio stream cleanup completed");
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
     }
+    let envelope = serde_json::to_vec(&serde_json::json!({
+        "encrypted_content": encrypted,
+        "encrypted": true,
+        "format": "wav",
+        "recording_id": recording_id,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })).map_err(|e| format!("Failed to serialize recording: {}", e))?;
 
-    log::info!("Audio recording infrastructure cleanup completed");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)
+        .map_err(|e| format!("Failed to save recording: {}", e))?;
+    file.write_all(&envelope)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to save recording: {}", e))
 }
 
+/// Stop a recording owned by any window, e.g. after its window crashed
 #[tauri::command]
-pub fn is_recording() -> bool {
-    RECORDING_FLAG.load(Ordering::SeqCst)
+pub async fn force_stop_recording(
+    reason: String,
+    recording: State<'_, RecordingState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<Option<RecordingOwner>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !matches!(auth.get_role(), Some(HealthcareRole::SuperAdmin) | Some(HealthcareRole::Administrator)) {
+        return Err("Insufficient permissions to force-stop a recording".to_string());
+    }
+    let user_id = auth.user_id.clone().unwrap_or_default();
+    drop(auth);
+
+    let Some(owner) = recording.ownership.force_release() else {
+        return Ok(None);
+    };
+    log::warn!("AUDIT: Recording force-stopped - Owner window: {}, Owner user: {:?}, By: {}, Reason: {}, Timestamp: {}",
+        owner.window_label, owner.user_id, user_id, reason, Utc::now().to_rfc3339());
+
+    if let Some(session) = recording.take_session() {
+        session.shut_down().await;
+    }
+    Ok(Some(owner))
 }
 
+/// Current recording owner, if any
 #[tauri::command]
-pub fn get_transcription_status() -> TranscriptionStatus {
-    // Check if recording is active and get real status
-    let is_active = is_recording();
-    let (chunks_in_queue, last_activity_ms) = if let Some(mic_buffer) = MIC_BUFFER.get() {
-        if let Ok(buffer_guard) = mic_buffer.try_lock() {
-            let chunk_count = buffer_guard.len() / 16000; // Estimate 1-second chunks at 16kHz
-            let last_activity = utils::format_timestamp(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs_f64()
-            );
-            (chunk_count.min(100), last_activity.len() as u64) // Mock last activity
-        } else {
-            (0, 0)
-        }
-    } else {
-        (0, 0)
+pub fn get_recording_owner(recording: State<'_, RecordingState>) -> Option<RecordingOwner> {
+    recording.ownership.owner()
+}
+
+/// Window-destroyed hook: stop a recording whose owning window is gone
+pub fn release_recording_for_closed_window<R: Runtime>(app: &AppHandle<R>, window_label: &str) {
+    if !app.try_state::<RecordingConfig>().map_or(true, |c| c.release_on_window_close) {
+        return;
+    }
+    let Some(recording) = app.try_state::<RecordingState>() else {
+        return;
     };
+    // Recordings owned by other windows are refused and left running
+    if let Ok(Some(owner)) = recording.ownership.release(window_label) {
+        log::warn!("Owning window '{}' closed; stopping its recording", owner.window_label);
+        if let Some(session) = recording.take_session() {
+            tauri::async_runtime::spawn(session.shut_down());
+        }
+    }
+}
 
-    TranscriptionStatus {
-        chunks_in_queue,
-        is_processing: is_active,
-        last_activity_ms,
+#[tauri::command]
+pub fn is_recording(recording: State<'_, RecordingState>) -> bool {
+    recording.is_recording()
+}
+
+/// Live transcription worker status
+#[tauri::command]
+pub fn get_transcription_status(recording: State<'_, RecordingState>) -> TranscriptionStatus {
+    let progress = recording.transcription.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    let device_name = recording.device_name();
+    match progress {
+        Some(progress) => TranscriptionStatus {
+            chunks_in_queue: progress.queue_depth(),
+            is_processing: progress.is_active(),
+            last_activity_ms: progress.ms_since_activity(),
+            device_name,
+        },
+        None => TranscriptionStatus {
+            chunks_in_queue: 0,
+            is_processing: false,
+            last_activity_ms: 0,
+            device_name,
+        },
     }
 }
 
+/// Save a transcript encrypted at the PHI classification. `client_id` and `jurisdiction` are
+/// recorded in the clear alongside the ciphertext so retention and DSAR checks can attribute the
+/// file without decrypting it. Fails when encryption has not been initialized.
 #[tauri::command]
-pub async fn save_transcript(file_path: String, content: String) -> Result<(), String> {
+pub async fn save_transcript(
+    file_path: String,
+    content: String,
+    client_id: Option<String>,
+    jurisdiction: Option<String>,
+    crypto: State<'_, CryptoServiceState>,
+) -> Result<(), String> {
     log::info!("Saving PIPEDA + Quebec Law 25 compliant transcript to: {}", file_path);
 
+    write_transcript(&file_path, &content, client_id, jurisdiction, &crypto.0).await?;
+
+    log::info!("PIPEDA + Quebec Law 25 compliant transcript saved successfully with metadata");
+
+    // Log audit trail for personal information access (PIPEDA + Quebec Law 25)
+    log::info!("AUDIT: Transcript saved - File: {}, Personal Info: true, PIPEDA: true, Quebec Law 25: true, Timestamp: {}",
+        file_path, chrono::Utc::now().to_rfc3339());
+
+    Ok(())
+}
+
+/// Encrypt a transcript and write it with its metadata envelope; only ciphertext reaches disk
+pub(crate) async fn write_transcript(
+    file_path: &str,
+    content: &str,
+    client_id: Option<String>,
+    jurisdiction: Option<String>,
+    crypto: &CryptoService,
+) -> Result<(), String> {
+    if !crypto.is_initialized().await {
+        return Err("Encryption is not initialized; unlock encrypted storage before saving transcripts".to_string());
+    }
+    // The cleartext client and recording ids are bound as AAD, so a transcript moved onto
+    // another client's record no longer decrypts
+    let recording_id = uuid::Uuid::new_v4().to_string();
+    let context = transcript_context(client_id.as_deref(), &recording_id);
+    let encrypted = crypto.encrypt_with_context(content.as_bytes(), DataClassification::Phi, None, &context)
+        .await
+        .map_err(|e| format!("Failed to encrypt transcript: {}", e))?;
+
     // Ensure parent directory exists
-    if let Some(parent) = std::path::Path::new(&file_path).parent() {
+    if let Some(parent) = std::path::Path::new(file_path).parent() {
         if !parent.exists() {
             std::fs::create_dir_all(parent)
                 .map_err(|e| format!("Failed to create directory: {}", e))?;
@@ -199,7 +672,7 @@ pub async fn save_transcript(file_path: String, content: String) -> Result<(), S
 
     // Prepare transcript data with metadata for PIPEDA + Quebec Law 25 compliance
     let transcript_data = serde_json::json!({
-        "content": content,
+        "encrypted_content": encrypted,
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "encrypted": true,
         "compliance": "PIPEDA + Quebec Law 25",
@@ -209,23 +682,482 @@ pub async fn save_transcript(file_path: String, content: String) -> Result<(), S
             "audio_source": "healthcare_session",
             "personal_info": true,
             "pipeda_protected": true,
-            "retention_period_years": 7
+            "retention_period_years": 7,
+            "recording_id": recording_id,
+            "client_id": client_id,
+            "jurisdiction": jurisdiction
         }
     });
 
-    // For now, write as JSON (in production, this should be encrypted)
-    // TODO: Implement AES-256-GCM encryption before writing to disk
     let json_content = serde_json::to_string_pretty(&transcript_data)
         .map_err(|e| format!("Failed to serialize transcript data: {}", e))?;
 
-    std::fs::write(&file_path, json_content)
-        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+    std::fs::write(file_path, json_content)
+        .map_err(|e| format!("Failed to write transcript: {}", e))
+}
 
-    log::info!("PIPEDA + Quebec Law 25 compliant transcript saved successfully with metadata");
+/// Encryption context of a transcript; transcripts saved without a client are bound to the
+/// recording alone
+fn transcript_context(client_id: Option<&str>, recording_id: &str) -> EncryptionContext {
+    EncryptionContext::for_record(client_id.unwrap_or("unassigned"), recording_id)
+}
 
-    // Log audit trail for personal information access (PIPEDA + Quebec Law 25)
-    log::info!("AUDIT: Transcript saved - File: {}, Personal Info: true, PIPEDA: true, Quebec Law 25: true, Timestamp: {}",
-        file_path, chrono::Utc::now().to_rfc3339());
+/// Transcript text as written by `save_transcript`. Files saved before transcripts were
+/// encrypted still carry their text in `content` and are read as is.
+pub(crate) async fn read_transcript(file_path: &str, crypto: &CryptoService) -> Result<String, String> {
+    let raw = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    let data: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|e| format!("Invalid transcript file: {}", e))?;
 
-    Ok(())
-}
\ No newline at end of file
+    if let Some(envelope) = data.get("encrypted_content") {
+        let encrypted: EncryptedData = serde_json::from_value(envelope.clone())
+            .map_err(|e| format!("Invalid encrypted transcript: {}", e))?;
+        let plaintext = if encrypted.context_bound {
+            let metadata = data.get("metadata");
+            let field = |name: &str| metadata.and_then(|m| m.get(name)).and_then(|v| v.as_str());
+            let recording_id = field("recording_id").ok_or("Encrypted transcript has no recording id")?;
+            crypto.decrypt_with_context(&encrypted, &transcript_context(field("client_id"), recording_id)).await
+        } else {
+            crypto.decrypt(&encrypted).await
+        }
+        .map_err(|e| format!("Failed to decrypt transcript: {}", e))?;
+        return String::from_utf8(plaintext).map_err(|_| "Decrypted transcript is not valid UTF-8".to_string());
+    }
+
+    data.get("content")
+        .and_then(|c| c.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "Transcript has no content".to_string())
+}
+
+/// Decrypt a transcript saved by `save_transcript`
+#[tauri::command]
+pub async fn load_transcript(
+    file_path: String,
+    crypto: State<'_, CryptoServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<String, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("view_phi") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let content = read_transcript(&file_path, &crypto.0).await?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "TRANSCRIPT_VIEWED",
+        "transcript",
+        &user_id,
+        true, // Transcript text is PHI
+        Some(serde_json::json!({
+            "file": std::path::Path::new(&file_path).file_name().map(|n| n.to_string_lossy().to_string()),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(content)
+}
+
+/// Write a PHI-redacted copy of a transcript for supervision or quality review.
+/// The original file is left untouched; the export is audited as a PHI export.
+#[tauri::command]
+pub async fn export_redacted_transcript(
+    file_path: String,
+    output_path: Option<String>,
+    mode: Option<TranscriptRedactionMode>,
+    known_names: Option<Vec<String>>,
+    redaction_config: State<'_, TranscriptRedactionConfig>,
+    crypto: State<'_, CryptoServiceState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<RedactedTranscript, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !auth.has_permission("view_phi") {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let source = std::path::Path::new(&file_path);
+    let output = output_path.map(std::path::PathBuf::from).unwrap_or_else(|| {
+        let stem = source.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        source.with_file_name(format!("{}_redacted.json", stem))
+    });
+    if output == source {
+        return Err("Redacted copy cannot overwrite the original transcript".to_string());
+    }
+
+    let mut config = redaction_config.inner().clone();
+    if let Some(mode) = mode {
+        config.mode = mode;
+    }
+    let redacted = redact_transcript(&read_transcript(&file_path, &crypto.0).await?, &known_names.unwrap_or_default(), &config);
+
+    let export = serde_json::json!({
+        "content": redacted.content,
+        "timestamp": Utc::now().to_rfc3339(),
+        "redacted": true,
+        "redaction_mode": redacted.mode,
+        "redactions": redacted.redactions,
+    });
+    let json_content = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize redacted transcript: {}", e))?;
+    std::fs::write(&output, json_content)
+        .map_err(|e| format!("Failed to write redacted transcript: {}", e))?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "TRANSCRIPT_REDACTED_EXPORT",
+        "transcript",
+        &user_id,
+        true, // Derived from PHI
+        Some(serde_json::json!({
+            "event_type": "PatientDataExported",
+            "source": source.file_name().map(|n| n.to_string_lossy().to_string()),
+            "output": output.file_name().map(|n| n.to_string_lossy().to_string()),
+            "redaction_mode": redacted.mode,
+            "redactions": redacted.redactions,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(redacted)
+}
+
+/// Roles allowed to purge session media and manage legal holds
+fn is_media_admin(auth: &AuthState) -> bool {
+    matches!(auth.get_role(), Some(HealthcareRole::SuperAdmin) | Some(HealthcareRole::Administrator))
+}
+
+/// Roles allowed to review media retention
+fn can_review_media_retention(auth: &AuthState) -> bool {
+    is_media_admin(auth) || matches!(auth.get_role(), Some(HealthcareRole::Auditor))
+}
+
+/// Recordings and transcripts expiring within `within_days` (default: the configured warning
+/// window), including expired media still kept by a legal hold or open DSAR
+#[tauri::command]
+pub async fn list_expiring_media(
+    app: AppHandle,
+    within_days: Option<u32>,
+    retention_config: State<'_, MediaRetentionConfig>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<Vec<MediaRetentionEntry>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_media_retention(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let window = i64::from(within_days.unwrap_or(retention_config.warning_days));
+    let entries: Vec<MediaRetentionEntry> = retention::retention_entries(&app)
+        .into_iter()
+        .filter(|e| e.days_remaining <= window)
+        .collect();
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "MEDIA_RETENTION_VIEWED",
+        "session_media",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "within_days": window,
+            "expiring": entries.len(),
+            "expired": entries.iter().filter(|e| e.is_expired()).count(),
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(entries)
+}
+
+/// Purge expired recordings and transcripts now; `dry_run` reports without deleting
+#[tauri::command]
+pub async fn run_media_retention_purge(
+    app: AppHandle,
+    dry_run: Option<bool>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<MediaPurgeReport, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_media_admin(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    retention::purge_expired_media(&app, &user_id, dry_run.unwrap_or(false)).await
+}
+
+/// Hold a file, a directory or every transcript of a client back from retention purges
+#[tauri::command]
+pub async fn place_media_legal_hold(
+    path: Option<String>,
+    client_id: Option<String>,
+    reason: String,
+    holds: State<'_, LegalHoldState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<LegalHold, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_media_admin(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let hold = holds.place(path, client_id, &reason, &user_id)?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "LEGAL_HOLD_PLACED",
+        "session_media",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "hold_id": hold.id,
+            "path": hold.path,
+            "client_id": hold.client_id,
+            "reason": hold.reason,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(hold)
+}
+
+#[tauri::command]
+pub async fn release_media_legal_hold(
+    hold_id: String,
+    holds: State<'_, LegalHoldState>,
+    firebase: State<'_, FirebaseServiceState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<LegalHold, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !is_media_admin(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    let user_id = auth.user_id.clone().ok_or("No user ID in auth state")?;
+    drop(auth);
+
+    let hold_id = uuid::Uuid::parse_str(&hold_id).map_err(|e| format!("Invalid hold ID: {}", e))?;
+    let hold = holds.release(hold_id).ok_or("Legal hold not found")?;
+
+    let firebase_guard = firebase.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+    firebase.audit_log(
+        "LEGAL_HOLD_RELEASED",
+        "session_media",
+        &user_id,
+        false,
+        Some(serde_json::json!({
+            "hold_id": hold.id,
+            "path": hold.path,
+            "client_id": hold.client_id,
+            "placed_by": hold.placed_by,
+        }))
+    ).await.map_err(|e| e.to_string())?;
+
+    Ok(hold)
+}
+
+#[tauri::command]
+pub async fn list_media_legal_holds(
+    holds: State<'_, LegalHoldState>,
+    auth_state: State<'_, Arc<tokio::sync::RwLock<AuthState>>>,
+) -> Result<Vec<LegalHold>, String> {
+    let auth = auth_state.read().await;
+    if !auth.is_authenticated {
+        return Err("Unauthorized".to_string());
+    }
+    if !can_review_media_retention(&auth) {
+        return Err("Insufficient permissions".to_string());
+    }
+    Ok(holds.list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owner(window: &str) -> RecordingOwner {
+        RecordingOwner {
+            window_label: window.to_string(),
+            user_id: Some("user-1".to_string()),
+            started_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_second_window_cannot_start_or_stop() {
+        let ownership = RecordingOwnership::new();
+        ownership.claim(owner("main")).unwrap();
+
+        let err = ownership.claim(owner("notes")).unwrap_err();
+        assert!(err.contains("owned by another session") && err.contains("main"));
+        assert!(ownership.release("notes").is_err());
+        assert_eq!(ownership.owner().unwrap().window_label, "main");
+
+        assert_eq!(ownership.release("main").unwrap().unwrap().window_label, "main");
+        assert!(ownership.owner().is_none());
+    }
+
+    #[test]
+    fn test_force_release_clears_orphaned_recording() {
+        let ownership = RecordingOwnership::new();
+        ownership.claim(owner("closed-window")).unwrap();
+
+        assert!(ownership.force_release().is_some());
+        ownership.claim(owner("main")).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transcript_round_trips_without_plaintext_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcripts").join("session.json");
+        let path = path.to_str().unwrap();
+        let text = "Patient reports panic attacks since March";
+
+        let crypto = CryptoService::new();
+        let err = write_transcript(path, text, None, None, &crypto).await.unwrap_err();
+        assert!(err.contains("not initialized"));
+        assert!(!std::path::Path::new(path).exists());
+
+        crypto.initialize_master_key("correct horse battery staple", None).await.unwrap();
+        write_transcript(path, text, Some("client-7".to_string()), Some("QC".to_string()), &crypto).await.unwrap();
+
+        let raw = std::fs::read_to_string(path).unwrap();
+        assert!(!raw.contains("panic attacks"));
+        assert!(raw.contains("client-7"));
+        assert_eq!(read_transcript(path, &crypto).await.unwrap(), text);
+
+        // Reattributing the file to another client breaks the AAD
+        std::fs::write(path, raw.replace("client-7", "client-8")).unwrap();
+        assert!(read_transcript(path, &crypto).await.is_err());
+    }
+
+    #[test]
+    fn test_empty_recording_is_a_valid_private_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions").join("recording.wav");
+
+        audio::encode::write_wav(&[], DEFAULT_SAMPLE_RATE, &path).unwrap();
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, DEFAULT_SAMPLE_RATE);
+        assert_eq!(reader.len(), 0);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let mut mic = vec![0.5, 0.5];
+        mix_into(&mut mic, &[0.75, 0.0, -0.25]);
+        assert_eq!(mic, vec![1.0, 0.5, -0.25]);
+    }
+
+    #[tokio::test]
+    async fn test_recordings_stay_encrypted_in_app_data_without_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_data = dir.path().join("app");
+
+        let path = recording_save_path(&app_data, &app_data.join("recording-1.wav")).unwrap();
+        assert_eq!(path, app_data.join(RECORDINGS_DIR).join("recording-1.wav"));
+        assert!(recording_save_path(&app_data, std::path::Path::new("recording-1.wav")).is_ok());
+        assert!(recording_save_path(&app_data, &dir.path().join("elsewhere").join("r.wav")).is_err());
+        assert!(recording_save_path(&app_data, &app_data.join("..").join("r.wav")).is_err());
+        assert!(recording_save_path(&app_data, &app_data.join("recording-1.mp3")).is_err());
+
+        let crypto = CryptoService::new();
+        crypto.initialize_master_key("correct horse battery staple", None).await.unwrap();
+        let wav = audio::encode::wav_bytes(&[0.25, -0.25], DEFAULT_SAMPLE_RATE).unwrap();
+        let encrypted = crypto
+            .encrypt_with_context(&wav, DataClassification::Phi, None, &recording_context("rec-1"))
+            .await
+            .unwrap();
+
+        write_recording(&path, "rec-1", &encrypted).unwrap();
+        assert!(!std::fs::read(&path).unwrap().starts_with(b"RIFF"));
+        assert!(write_recording(&path, "rec-2", &encrypted).is_err());
+        let saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved["recording_id"], "rec-1");
+    }
+
+    #[test]
+    fn test_named_input_device_is_found_or_refused() {
+        let devices = vec![
+            AudioDevice::new("MacBook Pro Microphone".to_string(), DeviceType::Input),
+            AudioDevice::new("Shure MV7".to_string(), DeviceType::Input),
+            AudioDevice::new("Shure MV7 Speakers".to_string(), DeviceType::Output),
+        ];
+
+        assert_eq!(find_input_device(&devices, "Shure MV7").unwrap().name, "Shure MV7");
+        assert_eq!(find_input_device(&devices, "Shure MV7 (input)").unwrap().name, "Shure MV7");
+
+        let err = find_input_device(&devices, "Shure MV7 Speakers").unwrap_err();
+        assert!(err.contains("not found"));
+        assert!(err.contains("MacBook Pro Microphone, Shure MV7"));
+    }
+
+    #[tokio::test]
+    async fn test_stopping_zeroes_capture_buffers() {
+        let session = RecordingSession::new();
+        let mic = session.mic_buffer.clone();
+        mic.lock().unwrap().extend(std::iter::repeat(0.25f32).take(16_000));
+        mic.lock().unwrap().truncate(8_000);
+
+        session.shut_down().await;
+
+        let mut samples = mic.lock().unwrap();
+        assert!(samples.is_empty());
+        assert!(samples.capacity() >= 16_000);
+        // Spare capacity held the truncated samples and must be zeroed as well
+        assert!(samples.spare_capacity_mut().iter().all(|s| unsafe { s.assume_init() } == 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_recording_again_gets_a_fresh_session() {
+        let state = RecordingState::default();
+        let first = RecordingSession::new();
+        let first_running = first.is_running.clone();
+        let first_buffer = first.mic_buffer.clone();
+        assert!(state.replace_session(first).is_none());
+        assert!(state.is_recording());
+
+        state.take_session().unwrap().shut_down().await;
+        assert!(!state.is_recording());
+        assert!(!first_running.load(Ordering::SeqCst));
+
+        assert!(state.replace_session(RecordingSession::new()).is_none());
+        let session = state.session.lock().unwrap();
+        let second = session.as_ref().unwrap();
+        assert!(second.is_running.load(Ordering::SeqCst));
+        assert!(!Arc::ptr_eq(&second.mic_buffer, &first_buffer));
+    }
+}
@@ -0,0 +1,190 @@
+// Transcript Redaction for PsyPsy CMS
+// Produces a PHI-masked copy of a session transcript for supervision or quality review;
+// the original transcript is never modified.
+
+use crate::security::validation::{detect_clinical_terms, detect_identifiers, PhiDetection, PhiType, SanitizationService};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Honorific followed by one or two capitalized words ("Dr. Tremblay", "Mme Marie Roy")
+static TITLED_NAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:Mr|Mrs|Ms|Miss|Dr|Mme|Mlle|M)\.?\s+[A-ZÀ-Ý][a-zà-ÿ'-]+(?:\s+[A-ZÀ-Ý][a-zà-ÿ'-]+)?")
+        .expect("valid titled name pattern")
+});
+
+/// How redacted spans are replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptRedactionMode {
+    /// `[REDACTED-KIND]`
+    Mask,
+    /// `[KIND-n]`, the same value always getting the same token within one transcript
+    Pseudonymize,
+}
+
+/// Redaction settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptRedactionConfig {
+    pub mode: TranscriptRedactionMode,
+    /// Also redact specialty/diagnosis wording; off by default since reviewers need clinical content
+    pub redact_clinical_terms: bool,
+}
+
+impl Default for TranscriptRedactionConfig {
+    fn default() -> Self {
+        Self {
+            mode: TranscriptRedactionMode::Mask,
+            redact_clinical_terms: false,
+        }
+    }
+}
+
+impl TranscriptRedactionConfig {
+    /// Defaults with `TRANSCRIPT_REDACTION_*` environment overrides
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            mode: match std::env::var("TRANSCRIPT_REDACTION_MODE").map(|v| v.to_ascii_lowercase()) {
+                Ok(v) if v == "pseudonymize" => TranscriptRedactionMode::Pseudonymize,
+                Ok(v) if v == "mask" => TranscriptRedactionMode::Mask,
+                _ => defaults.mode,
+            },
+            redact_clinical_terms: std::env::var("TRANSCRIPT_REDACT_CLINICAL_TERMS")
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(defaults.redact_clinical_terms),
+        }
+    }
+}
+
+/// Redacted text and what was removed, without the removed values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactedTranscript {
+    pub content: String,
+    pub mode: TranscriptRedactionMode,
+    /// Number of spans redacted per kind
+    pub redactions: BTreeMap<String, usize>,
+}
+
+fn kind_label(kind: &PhiType) -> &'static str {
+    match kind {
+        PhiType::HealthInsuranceNumber => "RAMQ",
+        PhiType::SocialSecurityNumber => "SIN",
+        PhiType::CreditCardNumber => "CARD",
+        PhiType::DriversLicense => "LICENSE",
+        PhiType::MedicalRecordNumber => "MRN",
+        PhiType::InsurancePolicyNumber => "POLICY",
+        PhiType::PhoneNumber => "PHONE",
+        PhiType::EmailAddress => "EMAIL",
+        PhiType::PostalCode => "POSTAL",
+        PhiType::ClinicalInformation => "CLINICAL",
+        PhiType::Other => "NAME",
+    }
+}
+
+fn name_detections(text: &str, known_names: &[String]) -> Vec<PhiDetection> {
+    let titled = TITLED_NAME.find_iter(text).map(|m| (m.start(), m.end()));
+    let known = known_names
+        .iter()
+        .filter(|n| n.trim().chars().count() >= 2)
+        .filter_map(|n| Regex::new(&format!(r"(?i)\b{}\b", regex::escape(n.trim()))).ok())
+        .flat_map(|re| re.find_iter(text).map(|m| (m.start(), m.end())).collect::<Vec<_>>());
+    titled
+        .chain(known)
+        .map(|(start, end)| PhiDetection {
+            pattern_type: PhiType::Other,
+            matched_text: text[start..end].to_string(),
+            start_position: start,
+            end_position: end,
+            confidence: 0.7,
+        })
+        .collect()
+}
+
+/// Redact identifiers, names (titled or listed in `known_names`) and optionally clinical terms
+pub fn redact_transcript(text: &str, known_names: &[String], config: &TranscriptRedactionConfig) -> RedactedTranscript {
+    let mut detections = detect_identifiers(text);
+    match SanitizationService::new() {
+        Ok(sanitizer) => detections.extend(sanitizer.detect_phi(text)),
+        Err(e) => log::error!("PHI detector unavailable during transcript redaction: {}", e),
+    }
+    detections.extend(name_detections(text, known_names));
+    if config.redact_clinical_terms {
+        detections.extend(detect_clinical_terms(text));
+    }
+
+    // Longest span wins where detections overlap
+    detections.sort_by(|a, b| {
+        a.start_position
+            .cmp(&b.start_position)
+            .then((b.end_position - b.start_position).cmp(&(a.end_position - a.start_position)))
+    });
+    let mut spans: Vec<PhiDetection> = Vec::new();
+    for detection in detections {
+        match spans.last_mut() {
+            Some(last) if detection.start_position < last.end_position => {
+                last.end_position = last.end_position.max(detection.end_position);
+            }
+            _ => spans.push(detection),
+        }
+    }
+
+    let mut content = String::with_capacity(text.len());
+    let mut redactions = BTreeMap::new();
+    let mut pseudonyms: HashMap<(&'static str, String), String> = HashMap::new();
+    let mut cursor = 0;
+    for span in &spans {
+        let label = kind_label(&span.pattern_type);
+        content.push_str(&text[cursor..span.start_position]);
+        let replacement = match config.mode {
+            TranscriptRedactionMode::Mask => format!("[REDACTED-{}]", label),
+            TranscriptRedactionMode::Pseudonymize => {
+                let value = text[span.start_position..span.end_position].to_lowercase();
+                let next = pseudonyms.keys().filter(|(l, _)| *l == label).count() + 1;
+                pseudonyms
+                    .entry((label, value))
+                    .or_insert_with(|| format!("[{}-{}]", label, next))
+                    .clone()
+            }
+        };
+        content.push_str(&replacement);
+        *redactions.entry(label.to_string()).or_insert(0) += 1;
+        cursor = span.end_position;
+    }
+    content.push_str(&text[cursor..]);
+
+    RedactedTranscript {
+        content,
+        mode: config.mode,
+        redactions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramq_number_is_masked() {
+        let text = "Client confirmed RAMQ TREM 8501 1234 and phone 514-555-0199 before the session.";
+        let redacted = redact_transcript(text, &[], &TranscriptRedactionConfig::default());
+
+        assert!(!redacted.content.contains("8501"));
+        assert!(!redacted.content.contains("555-0199"));
+        assert!(redacted.content.contains("[REDACTED-RAMQ]"));
+        assert_eq!(redacted.redactions.get("RAMQ"), Some(&1));
+    }
+
+    #[test]
+    fn test_pseudonymize_keeps_names_consistent() {
+        let config = TranscriptRedactionConfig {
+            mode: TranscriptRedactionMode::Pseudonymize,
+            ..TranscriptRedactionConfig::default()
+        };
+        let text = "Julie said Marc called. Later julie repeated it to Dr. Tremblay.";
+        let redacted = redact_transcript(text, &["Julie".to_string(), "Marc".to_string()], &config);
+
+        assert_eq!(redacted.content, "[NAME-1] said [NAME-2] called. Later [NAME-1] repeated it to [NAME-3].");
+    }
+}
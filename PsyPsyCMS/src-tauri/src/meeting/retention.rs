@@ -0,0 +1,606 @@
+// Session Media Retention for PsyPsy CMS
+// Expires session recordings and transcripts once their retention period ends. Artifacts under
+// legal hold, or possibly covered by a DSAR that has not been exported yet, are never removed.
+// Every purge is audited; running the job again only removes what has expired since.
+
+use crate::security::secure_wipe::{wipe_file, SecureWipeConfig};
+use crate::services::export_approval::{ExportApprovalState, ExportKind, ExportRequest, ExportRequestStatus};
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+const LEGAL_HOLD_FILE: &str = "media_legal_holds.json";
+
+const RECORDING_EXTENSIONS: &[&str] = &["mp4", "m4a", "wav", "mp3", "ogg", "webm"];
+
+/// Kind of session media
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Recording,
+    Transcript,
+}
+
+/// Days each kind of media is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPeriods {
+    pub recording_days: u32,
+    pub transcript_days: u32,
+}
+
+impl RetentionPeriods {
+    pub fn for_kind(&self, kind: MediaKind) -> u32 {
+        match kind {
+            MediaKind::Recording => self.recording_days,
+            MediaKind::Transcript => self.transcript_days,
+        }
+    }
+}
+
+/// Retention settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaRetentionConfig {
+    /// Run the scheduled purge; expiry can still be listed when off
+    pub enabled: bool,
+    /// Directories holding recordings and transcripts, scanned recursively
+    pub media_dirs: Vec<PathBuf>,
+    /// Jurisdiction for media that does not record its own
+    pub default_jurisdiction: String,
+    /// Periods per jurisdiction code ("QC", "ON", ...)
+    pub jurisdictions: HashMap<String, RetentionPeriods>,
+    /// Periods for jurisdictions without an entry
+    pub default_periods: RetentionPeriods,
+    /// Media expiring within this many days is reported as approaching expiry
+    pub warning_days: u32,
+    pub purge_interval_hours: u64,
+}
+
+impl Default for MediaRetentionConfig {
+    fn default() -> Self {
+        let mut jurisdictions = HashMap::new();
+        // Quebec: psychologists keep records five years after the last service
+        jurisdictions.insert("QC".to_string(), RetentionPeriods { recording_days: 365, transcript_days: 5 * 365 });
+        // Ontario: ten years after the last contact
+        jurisdictions.insert("ON".to_string(), RetentionPeriods { recording_days: 365, transcript_days: 10 * 365 });
+        Self {
+            enabled: true,
+            media_dirs: Vec::new(),
+            default_jurisdiction: "QC".to_string(),
+            jurisdictions,
+            default_periods: RetentionPeriods { recording_days: 365, transcript_days: 7 * 365 },
+            warning_days: 30,
+            purge_interval_hours: 24,
+        }
+    }
+}
+
+impl MediaRetentionConfig {
+    /// Defaults with `MEDIA_RETENTION_*` environment overrides. `MEDIA_RETENTION_DIRS` is a
+    /// path list; `MEDIA_RETENTION_POLICIES` is a JSON map of jurisdiction to periods.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(v) = std::env::var("MEDIA_RETENTION_ENABLED") {
+            config.enabled = matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes");
+        }
+        if let Some(dirs) = std::env::var_os("MEDIA_RETENTION_DIRS") {
+            config.media_dirs = std::env::split_paths(&dirs).filter(|p| !p.as_os_str().is_empty()).collect();
+        }
+        if let Ok(jurisdiction) = std::env::var("MEDIA_RETENTION_JURISDICTION") {
+            config.default_jurisdiction = jurisdiction.trim().to_ascii_uppercase();
+        }
+        if let Ok(policies) = std::env::var("MEDIA_RETENTION_POLICIES") {
+            match serde_json::from_str::<HashMap<String, RetentionPeriods>>(&policies) {
+                Ok(policies) => config
+                    .jurisdictions
+                    .extend(policies.into_iter().map(|(code, periods)| (code.to_ascii_uppercase(), periods))),
+                Err(e) => log::warn!("Ignoring invalid MEDIA_RETENTION_POLICIES: {}", e),
+            }
+        }
+        if let Some(days) = std::env::var("MEDIA_RETENTION_WARNING_DAYS").ok().and_then(|v| v.parse().ok()) {
+            config.warning_days = days;
+        }
+        if let Some(hours) = std::env::var("MEDIA_RETENTION_INTERVAL_HOURS").ok().and_then(|v| v.parse().ok()) {
+            config.purge_interval_hours = hours;
+        }
+        config
+    }
+
+    fn periods_for(&self, jurisdiction: &str) -> RetentionPeriods {
+        self.jurisdictions.get(jurisdiction).copied().unwrap_or(self.default_periods)
+    }
+}
+
+/// A recording or transcript found on disk
+#[derive(Debug, Clone)]
+pub struct MediaArtifact {
+    pub path: PathBuf,
+    pub kind: MediaKind,
+    pub created_at: DateTime<Utc>,
+    pub client_id: Option<String>,
+    pub jurisdiction: Option<String>,
+    /// Retention promised in the transcript's own metadata
+    pub stated_retention_years: Option<u32>,
+}
+
+/// Legal hold on a path (file or directory) or on everything recorded for a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegalHold {
+    pub id: Uuid,
+    pub path: Option<String>,
+    pub client_id: Option<String>,
+    pub reason: String,
+    pub placed_by: String,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl LegalHold {
+    pub fn covers(&self, artifact: &MediaArtifact) -> bool {
+        let path_held = self.path.as_deref().is_some_and(|p| artifact.path.starts_with(p));
+        let client_held = self.client_id.is_some() && self.client_id == artifact.client_id;
+        path_held || client_held
+    }
+}
+
+/// Legal holds, persisted in the app data directory once storage is attached
+#[derive(Debug, Clone, Default)]
+pub struct LegalHoldState {
+    holds: Arc<RwLock<BTreeMap<Uuid, LegalHold>>>,
+    storage_path: Arc<RwLock<Option<PathBuf>>>,
+}
+
+impl LegalHoldState {
+    /// Load saved holds from `dir` and persist future changes there
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        let path = dir.join(LEGAL_HOLD_FILE);
+        if path.exists() {
+            match serde_json::from_slice::<BTreeMap<Uuid, LegalHold>>(&std::fs::read(&path)?) {
+                Ok(saved) => *self.holds.write().unwrap() = saved,
+                Err(e) => {
+                    // Refuse to run with holds we cannot read rather than purge held media
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+                }
+            }
+        }
+        *self.storage_path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    /// Whether holds are backed by storage; the purge does not run until they are
+    pub fn is_loaded(&self) -> bool {
+        self.storage_path.read().unwrap().is_some()
+    }
+
+    pub fn place(&self, path: Option<String>, client_id: Option<String>, reason: &str, placed_by: &str) -> Result<LegalHold, String> {
+        let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+        let client_id = client_id.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+        if path.is_none() && client_id.is_none() {
+            return Err("A legal hold needs a path or a client".to_string());
+        }
+        if reason.trim().is_empty() {
+            return Err("A legal hold needs a reason".to_string());
+        }
+        let hold = LegalHold {
+            id: Uuid::new_v4(),
+            path,
+            client_id,
+            reason: reason.trim().to_string(),
+            placed_by: placed_by.to_string(),
+            placed_at: Utc::now(),
+        };
+        self.holds.write().unwrap().insert(hold.id, hold.clone());
+        self.persist();
+        Ok(hold)
+    }
+
+    pub fn release(&self, id: Uuid) -> Option<LegalHold> {
+        let released = self.holds.write().unwrap().remove(&id);
+        if released.is_some() {
+            self.persist();
+        }
+        released
+    }
+
+    pub fn list(&self) -> Vec<LegalHold> {
+        self.holds.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn covering(&self, artifact: &MediaArtifact) -> Option<LegalHold> {
+        self.holds.read().unwrap().values().find(|h| h.covers(artifact)).cloned()
+    }
+
+    fn persist(&self) {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            return;
+        };
+        match serde_json::to_vec_pretty(&*self.holds.read().unwrap()) {
+            Ok(bytes) => {
+                if let Err(e) = std::fs::write(&path, bytes) {
+                    log::warn!("Failed to persist media legal holds: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize media legal holds: {}", e),
+        }
+    }
+}
+
+/// Clients referenced by DSAR requests that have not been exported yet
+#[derive(Debug, Clone, Default)]
+pub struct OpenDsarReferences {
+    any_open: bool,
+    all_clients: bool,
+    client_ids: HashSet<String>,
+}
+
+impl OpenDsarReferences {
+    pub fn from_requests(requests: &[ExportRequest]) -> Self {
+        let mut references = Self::default();
+        let open = requests.iter().filter(|r| {
+            r.scope.kind == ExportKind::Dsar
+                && matches!(r.status, ExportRequestStatus::Pending | ExportRequestStatus::Approved)
+        });
+        for request in open {
+            references.any_open = true;
+            references.all_clients |= request.scope.all_clients;
+            references.client_ids.extend(request.scope.client_ids.iter().cloned());
+        }
+        references
+    }
+
+    /// Media with no recorded client is kept while any DSAR is open, since it cannot be ruled out
+    pub fn references(&self, artifact: &MediaArtifact) -> bool {
+        match &artifact.client_id {
+            _ if self.all_clients => true,
+            Some(client_id) => self.client_ids.contains(client_id),
+            None => self.any_open,
+        }
+    }
+}
+
+/// Why expired media was not purged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum PurgeBlock {
+    LegalHold { hold_id: Uuid, reason: String },
+    OpenDsar,
+}
+
+/// Retention state of one artifact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaRetentionEntry {
+    pub path: String,
+    pub kind: MediaKind,
+    pub client_id: Option<String>,
+    pub jurisdiction: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Negative once expired
+    pub days_remaining: i64,
+    pub blocked_by: Option<PurgeBlock>,
+}
+
+impl MediaRetentionEntry {
+    pub fn is_expired(&self) -> bool {
+        self.days_remaining < 0
+    }
+}
+
+/// Work out when an artifact expires and what, if anything, keeps it. Where the transcript states
+/// a longer retention than policy, the longer period wins.
+pub fn evaluate_artifact(
+    artifact: &MediaArtifact,
+    config: &MediaRetentionConfig,
+    holds: &LegalHoldState,
+    dsar: &OpenDsarReferences,
+    now: DateTime<Utc>,
+) -> MediaRetentionEntry {
+    let jurisdiction = artifact
+        .jurisdiction
+        .as_deref()
+        .map(str::to_ascii_uppercase)
+        .unwrap_or_else(|| config.default_jurisdiction.clone());
+    let policy_days = config.periods_for(&jurisdiction).for_kind(artifact.kind) as i64;
+    let stated_days = artifact.stated_retention_years.map(|y| y as i64 * 365).unwrap_or(0);
+    let expires_at = artifact.created_at + Duration::days(policy_days.max(stated_days));
+
+    let blocked_by = match holds.covering(artifact) {
+        Some(hold) => Some(PurgeBlock::LegalHold { hold_id: hold.id, reason: hold.reason }),
+        None if dsar.references(artifact) => Some(PurgeBlock::OpenDsar),
+        None => None,
+    };
+
+    MediaRetentionEntry {
+        path: artifact.path.display().to_string(),
+        kind: artifact.kind,
+        client_id: artifact.client_id.clone(),
+        jurisdiction,
+        created_at: artifact.created_at,
+        expires_at,
+        days_remaining: (expires_at - now).num_days() - i64::from(expires_at < now),
+        blocked_by,
+    }
+}
+
+fn media_kind(path: &Path) -> Option<MediaKind> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    if extension == "json" {
+        Some(MediaKind::Transcript)
+    } else if RECORDING_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Recording)
+    } else {
+        None
+    }
+}
+
+/// Describe one file; transcripts are dated and attributed from their own metadata
+pub fn read_artifact(path: &Path) -> Option<MediaArtifact> {
+    let kind = media_kind(path)?;
+    let modified: DateTime<Utc> = std::fs::metadata(path).ok()?.modified().ok()?.into();
+    let mut artifact = MediaArtifact {
+        path: path.to_path_buf(),
+        kind,
+        created_at: modified,
+        client_id: None,
+        jurisdiction: None,
+        stated_retention_years: None,
+    };
+
+    if kind == MediaKind::Transcript {
+        let value: serde_json::Value = serde_json::from_slice(&std::fs::read(path).ok()?).ok()?;
+        // Only JSON written as a transcript counts; settings or exports alongside are left alone
+        value.get("content").or_else(|| value.get("encrypted_content"))?;
+        let metadata = value.get("metadata");
+        let text = |key: &str| metadata.and_then(|m| m.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+        if let Some(timestamp) = value.get("timestamp").and_then(|t| t.as_str()) {
+            if let Ok(created) = DateTime::parse_from_rfc3339(timestamp) {
+                artifact.created_at = created.with_timezone(&Utc);
+            }
+        }
+        artifact.client_id = text("client_id");
+        artifact.jurisdiction = text("jurisdiction");
+        artifact.stated_retention_years = metadata
+            .and_then(|m| m.get("retention_period_years"))
+            .and_then(|v| v.as_u64())
+            .map(|y| y as u32);
+    }
+    Some(artifact)
+}
+
+/// Every recording and transcript under the configured directories
+pub fn scan_media(dirs: &[PathBuf]) -> Vec<MediaArtifact> {
+    let mut artifacts = Vec::new();
+    let mut pending: Vec<PathBuf> = dirs.to_vec();
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            log::warn!("Media retention cannot read {}", dir.display());
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => pending.push(path),
+                Ok(t) if t.is_file() => artifacts.extend(read_artifact(&path)),
+                _ => {}
+            }
+        }
+    }
+    artifacts
+}
+
+/// Retention state of every artifact, soonest expiry first
+pub fn retention_entries(app: &AppHandle) -> Vec<MediaRetentionEntry> {
+    let config = app.state::<MediaRetentionConfig>();
+    let holds = app.state::<LegalHoldState>();
+    let dsar = OpenDsarReferences::from_requests(&app.state::<ExportApprovalState>().list(None));
+    let now = Utc::now();
+    let mut entries: Vec<MediaRetentionEntry> = scan_media(&config.media_dirs)
+        .iter()
+        .map(|artifact| evaluate_artifact(artifact, &config, &holds, &dsar, now))
+        .collect();
+    entries.sort_by_key(|e| e.expires_at);
+    entries
+}
+
+/// Outcome of one purge run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaPurgeReport {
+    pub scanned: usize,
+    pub purged: Vec<MediaRetentionEntry>,
+    /// Expired but kept by a legal hold or open DSAR
+    pub retained: Vec<MediaRetentionEntry>,
+    pub failed: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// Purge expired, unblocked media, auditing each removal before it happens. Nothing is removed
+/// while legal holds are unreadable or the audit log is unavailable.
+pub async fn purge_expired_media(app: &AppHandle, triggered_by: &str, dry_run: bool) -> Result<MediaPurgeReport, String> {
+    if !app.state::<LegalHoldState>().is_loaded() {
+        return Err("Legal holds are not loaded; media retention purge refused".to_string());
+    }
+    let entries = retention_entries(app);
+    let mut report = MediaPurgeReport {
+        scanned: entries.len(),
+        dry_run,
+        ..MediaPurgeReport::default()
+    };
+
+    let passes = app.try_state::<SecureWipeConfig>().map_or(1, |c| c.overwrite_passes);
+    let firebase_state = app.state::<FirebaseServiceState>();
+    let firebase_guard = firebase_state.0.lock().await;
+    let firebase = firebase_guard.as_ref().ok_or("Firebase service not initialized")?;
+
+    for entry in entries.into_iter().filter(MediaRetentionEntry::is_expired) {
+        if entry.blocked_by.is_some() {
+            report.retained.push(entry);
+            continue;
+        }
+        if dry_run {
+            report.purged.push(entry);
+            continue;
+        }
+        let action = match entry.kind {
+            MediaKind::Recording => "RECORDING_PURGED",
+            MediaKind::Transcript => "TRANSCRIPT_PURGED",
+        };
+        if let Err(e) = firebase.audit_log(
+            action,
+            "session_media",
+            triggered_by,
+            true,
+            Some(serde_json::json!({
+                "event_type": "DataRetentionPurge",
+                "path": entry.path,
+                "client_id": entry.client_id,
+                "jurisdiction": entry.jurisdiction,
+                "created_at": entry.created_at,
+                "expired_at": entry.expires_at,
+            }))
+        ).await {
+            log::error!("Skipping purge of {}: audit failed: {}", entry.path, e);
+            report.failed.push(entry.path);
+            continue;
+        }
+        // Session media is not stored under a per-file key that could be destroyed instead
+        match wipe_file(Path::new(&entry.path), passes) {
+            Ok(()) => report.purged.push(entry),
+            Err(e) => {
+                log::error!("Failed to purge {}: {}", entry.path, e);
+                report.failed.push(entry.path);
+            }
+        }
+    }
+
+    log::info!(
+        "Media retention run by {}: {} scanned, {} purged, {} retained, {} failed{}",
+        triggered_by,
+        report.scanned,
+        report.purged.len(),
+        report.retained.len(),
+        report.failed.len(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+    Ok(report)
+}
+
+/// Purge expired media on a fixed interval
+pub fn start_media_retention_job(app: AppHandle, config: MediaRetentionConfig) {
+    if !config.enabled {
+        log::warn!("Scheduled media retention purge disabled by configuration");
+        return;
+    }
+    if config.media_dirs.is_empty() {
+        log::info!("No media directories configured; scheduled media retention purge not started");
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let interval = std::time::Duration::from_secs(config.purge_interval_hours.max(1) * 3600);
+        loop {
+            if let Err(e) = purge_expired_media(&app, "system:media-retention", false).await {
+                log::warn!("Media retention purge skipped: {}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(client_id: Option<&str>, age_days: i64) -> MediaArtifact {
+        MediaArtifact {
+            path: PathBuf::from("/media/session.json"),
+            kind: MediaKind::Transcript,
+            created_at: Utc::now() - Duration::days(age_days),
+            client_id: client_id.map(str::to_string),
+            jurisdiction: None,
+            stated_retention_years: None,
+        }
+    }
+
+    fn dsar(client_ids: &[&str], status: ExportRequestStatus) -> ExportRequest {
+        ExportRequest {
+            id: Uuid::new_v4(),
+            scope: crate::services::export_approval::ExportScope {
+                kind: ExportKind::Dsar,
+                client_ids: client_ids.iter().map(|c| c.to_string()).collect(),
+                all_clients: false,
+            },
+            requested_by: "privacy-officer".to_string(),
+            justification: "Access request".to_string(),
+            status,
+            created_at: Utc::now(),
+            expires_at: Utc::now() + Duration::days(30),
+            decided_by: None,
+            decided_at: None,
+            decision_note: None,
+            auto_approved: false,
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_expiry_follows_jurisdiction_and_blocks() {
+        let config = MediaRetentionConfig::default();
+        let holds = LegalHoldState::default();
+        let no_dsar = OpenDsarReferences::default();
+
+        let old = transcript(Some("client-1"), 6 * 365);
+        assert!(evaluate_artifact(&old, &config, &holds, &no_dsar, Utc::now()).is_expired());
+
+        let ontario = MediaArtifact { jurisdiction: Some("on".to_string()), ..old.clone() };
+        let entry = evaluate_artifact(&ontario, &config, &holds, &no_dsar, Utc::now());
+        assert_eq!(entry.jurisdiction, "ON");
+        assert!(!entry.is_expired());
+
+        let promised = MediaArtifact { stated_retention_years: Some(7), ..old.clone() };
+        assert!(!evaluate_artifact(&promised, &config, &holds, &no_dsar, Utc::now()).is_expired());
+
+        let open = OpenDsarReferences::from_requests(&[
+            dsar(&["client-1"], ExportRequestStatus::Approved),
+            dsar(&["client-2"], ExportRequestStatus::Completed),
+        ]);
+        let entry = evaluate_artifact(&old, &config, &holds, &open, Utc::now());
+        assert_eq!(entry.blocked_by, Some(PurgeBlock::OpenDsar));
+        assert!(evaluate_artifact(&transcript(None, 6 * 365), &config, &holds, &open, Utc::now()).blocked_by.is_some());
+        assert!(evaluate_artifact(&transcript(Some("client-2"), 6 * 365), &config, &holds, &open, Utc::now())
+            .blocked_by
+            .is_none());
+
+        let hold = holds.place(None, Some("client-1".to_string()), "Litigation", "admin").unwrap();
+        let entry = evaluate_artifact(&old, &config, &holds, &no_dsar, Utc::now());
+        assert!(matches!(entry.blocked_by, Some(PurgeBlock::LegalHold { hold_id, .. }) if hold_id == hold.id));
+    }
+
+    #[test]
+    fn test_shred_is_idempotent() {
+        let dir = std::env::temp_dir().join(format!("psypsy-retention-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.json");
+        std::fs::write(
+            &path,
+            r#"{"content": "notes", "timestamp": "2015-01-01T00:00:00Z", "metadata": {"client_id": "c-9", "retention_period_years": 7}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("settings.json"), r#"{"theme": "dark"}"#).unwrap();
+
+        let artifacts = scan_media(&[dir.clone()]);
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].client_id.as_deref(), Some("c-9"));
+        assert_eq!(artifacts[0].created_at.format("%Y").to_string(), "2015");
+
+        wipe_file(&path, 1).unwrap();
+        assert!(!path.exists());
+        wipe_file(&path, 1).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
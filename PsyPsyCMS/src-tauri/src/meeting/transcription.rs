@@ -0,0 +1,476 @@
+// Live Transcription for PsyPsy CMS
+// While a recording runs, the microphone buffer is read in chunks of about a second, each chunk is
+// handed to a local speech-to-text executable (whisper.cpp by default) and the text is emitted to
+// the frontend as `TranscriptUpdate` events. Chunks are transcribed concurrently, so results are
+// put back in capture order before they are emitted; a chunk that is still not done after the
+// reorder wait is skipped, and if it finishes later it is dropped rather than emitted out of order.
+// A chunk that runs past its timeout is abandoned and its speech-to-text process killed.
+// Audio never leaves the machine. Live transcription is off unless a deployment enables it.
+
+use crate::meeting::audio::audio_processing::resample;
+use crate::meeting::audio::encode::wav_bytes;
+use crate::meeting::TranscriptUpdate;
+use crate::security::secure_wipe::{self, SecureTempFile, SecureWipeConfig, TempFileProtection};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::{mpsc, Semaphore};
+use zeroize::Zeroizing;
+
+/// Event carrying each [`TranscriptUpdate`]
+pub const TRANSCRIPT_UPDATE_EVENT: &str = "transcript-update";
+
+/// Rate whisper models are trained on; chunks are resampled to it
+const STT_SAMPLE_RATE: u32 = 16_000;
+
+/// How often the worker looks for new audio
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveTranscriptionConfig {
+    pub enabled: bool,
+    /// Speech-to-text executable, run as `<command> [-m <model>] -l <language> -nt -np -f <wav>`;
+    /// it must print the transcript on stdout
+    pub stt_command: String,
+    pub model_path: Option<String>,
+    /// Language code, or `auto`
+    pub language: String,
+    /// Audio per chunk
+    pub chunk_ms: u64,
+    /// Chunks transcribed at the same time
+    pub max_in_flight: usize,
+    /// How long a finished chunk waits for earlier ones before those are skipped
+    pub reorder_wait_ms: u64,
+    /// A chunk whose transcription takes longer is given up on
+    pub chunk_timeout_ms: u64,
+}
+
+impl Default for LiveTranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stt_command: "whisper-cli".to_string(),
+            model_path: None,
+            language: "auto".to_string(),
+            chunk_ms: 1_000,
+            max_in_flight: 2,
+            reorder_wait_ms: 3_000,
+            chunk_timeout_ms: 15_000,
+        }
+    }
+}
+
+impl LiveTranscriptionConfig {
+    /// Defaults with `LIVE_TRANSCRIPTION_*` environment overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.enabled = std::env::var("LIVE_TRANSCRIPTION_ENABLED")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        if let Ok(command) = std::env::var("LIVE_TRANSCRIPTION_COMMAND") {
+            if !command.trim().is_empty() {
+                config.stt_command = command;
+            }
+        }
+        config.model_path = std::env::var("LIVE_TRANSCRIPTION_MODEL").ok().filter(|m| !m.trim().is_empty());
+        if let Ok(language) = std::env::var("LIVE_TRANSCRIPTION_LANGUAGE") {
+            if !language.trim().is_empty() {
+                config.language = language.trim().to_string();
+            }
+        }
+        if let Ok(value) = std::env::var("LIVE_TRANSCRIPTION_CHUNK_MS") {
+            match value.trim().parse::<u64>() {
+                Ok(ms) if (250..=10_000).contains(&ms) => config.chunk_ms = ms,
+                _ => log::warn!("Ignoring invalid LIVE_TRANSCRIPTION_CHUNK_MS '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("LIVE_TRANSCRIPTION_MAX_IN_FLIGHT") {
+            match value.trim().parse::<usize>() {
+                Ok(n) if (1..=8).contains(&n) => config.max_in_flight = n,
+                _ => log::warn!("Ignoring invalid LIVE_TRANSCRIPTION_MAX_IN_FLIGHT '{}'", value),
+            }
+        }
+        config
+    }
+}
+
+/// Turns a chunk of mono audio into text. Dropping the future abandons the chunk, so an
+/// implementation must stop its work when that happens.
+#[async_trait]
+pub trait SpeechToText: Send + Sync {
+    async fn transcribe(&self, samples: &[f32], sample_rate: u32) -> Result<String, String>;
+}
+
+/// Runs the configured speech-to-text executable on each chunk
+pub struct CommandSpeechToText {
+    config: LiveTranscriptionConfig,
+}
+
+impl CommandSpeechToText {
+    pub fn new(config: LiveTranscriptionConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl SpeechToText for CommandSpeechToText {
+    async fn transcribe(&self, samples: &[f32], sample_rate: u32) -> Result<String, String> {
+        let mut resampled = if sample_rate == STT_SAMPLE_RATE {
+            samples.to_vec()
+        } else {
+            resample(samples, sample_rate, STT_SAMPLE_RATE).map_err(|e| format!("Failed to resample chunk: {}", e))?
+        };
+        let wav = wav_bytes(&resampled, STT_SAMPLE_RATE).map(Zeroizing::new);
+        secure_wipe::wipe_buffer(&mut resampled);
+        let wav = wav.map_err(|e| format!("Failed to encode chunk: {}", e))?;
+
+        // The executable needs the audio in the clear; the file is overwritten and removed as
+        // soon as the handle drops
+        let wipe = SecureWipeConfig { temp_file_protection: TempFileProtection::Overwrite, ..SecureWipeConfig::from_env() };
+        let temp = SecureTempFile::create(&std::env::temp_dir().join("psypsy-stt"), "wav", &wav, &wipe)
+            .map_err(|e| format!("Failed to stage chunk: {}", e))?;
+
+        // Killed if the chunk times out and this future is dropped
+        let mut command = tokio::process::Command::new(&self.config.stt_command);
+        if let Some(model) = &self.config.model_path {
+            command.arg("-m").arg(model);
+        }
+        let output = command
+            .args(["-l", self.config.language.as_str(), "-nt", "-np", "-f"])
+            .arg(temp.path())
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run {}: {}", self.config.stt_command, e))?;
+        if !output.status.success() {
+            return Err(format!("{} exited with {}", self.config.stt_command, output.status));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Puts chunk results back in sequence order
+pub(crate) struct ReorderBuffer<T> {
+    next: u64,
+    pending: BTreeMap<u64, (Option<T>, Instant)>,
+}
+
+impl<T> ReorderBuffer<T> {
+    pub fn new() -> Self {
+        Self { next: 0, pending: BTreeMap::new() }
+    }
+
+    /// Accept a finished chunk (`None` when it produced no text) and return every result that is
+    /// now due, in order
+    pub fn push(&mut self, sequence_id: u64, result: Option<T>, now: Instant) -> Vec<T> {
+        if sequence_id < self.next {
+            log::warn!("Dropping transcript chunk {} that finished after later chunks were emitted", sequence_id);
+            return Vec::new();
+        }
+        self.pending.insert(sequence_id, (result, now));
+        self.release()
+    }
+
+    /// Skip missing chunks once the earliest waiting result has waited `wait`
+    pub fn expire(&mut self, now: Instant, wait: Duration) -> Vec<T> {
+        let Some((&first, (_, since))) = self.pending.iter().next() else {
+            return Vec::new();
+        };
+        if now.duration_since(*since) < wait {
+            return Vec::new();
+        }
+        log::warn!("Skipping {} transcript chunk(s) that did not finish in time", first - self.next);
+        self.next = first;
+        self.release()
+    }
+
+    /// Results held back waiting for earlier chunks
+    pub fn waiting(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn release(&mut self) -> Vec<T> {
+        let mut ready = Vec::new();
+        while let Some((result, _)) = self.pending.remove(&self.next) {
+            self.next += 1;
+            ready.extend(result);
+        }
+        ready
+    }
+}
+
+/// Figures of one recording's worker, read by `get_transcription_status`
+pub struct TranscriptionProgress {
+    active: AtomicBool,
+    queue_depth: AtomicUsize,
+    /// Unix milliseconds of the last finished chunk
+    last_activity: AtomicU64,
+}
+
+impl TranscriptionProgress {
+    pub const fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            queue_depth: AtomicUsize::new(0),
+            last_activity: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Chunks taken off the buffer and not yet emitted or dropped
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth.load(Ordering::SeqCst)
+    }
+
+    /// Milliseconds since a chunk last finished; 0 before the first one
+    pub fn ms_since_activity(&self) -> u64 {
+        match self.last_activity.load(Ordering::SeqCst) {
+            0 => 0,
+            at => (chrono::Utc::now().timestamp_millis() as u64).saturating_sub(at),
+        }
+    }
+
+    fn touch(&self) {
+        self.last_activity.store(chrono::Utc::now().timestamp_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Default for TranscriptionProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copy the next full chunk after `cursor` out of the capture buffer
+fn take_chunk(buffer: &Mutex<Vec<f32>>, cursor: &mut usize, len: usize) -> Option<Vec<f32>> {
+    let samples = buffer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if samples.len().saturating_sub(*cursor) < len {
+        return None;
+    }
+    let chunk = samples[*cursor..*cursor + len].to_vec();
+    *cursor += len;
+    Some(chunk)
+}
+
+/// Transcribe the capture buffer while `is_running` is set, emitting updates on `app`
+pub fn spawn_worker<R: Runtime>(
+    app: AppHandle<R>,
+    buffer: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+    is_running: Arc<AtomicBool>,
+    engine: Arc<dyn SpeechToText>,
+    progress: Arc<TranscriptionProgress>,
+    config: LiveTranscriptionConfig,
+) {
+    tauri::async_runtime::spawn(async move {
+        run_worker(buffer, sample_rate, is_running, engine, &config, &progress, |update| {
+            if let Err(e) = app.emit(TRANSCRIPT_UPDATE_EVENT, &update) {
+                log::warn!("Failed to emit transcript update {}: {}", update.sequence_id, e);
+            }
+        })
+        .await;
+    });
+}
+
+async fn run_worker(
+    buffer: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+    is_running: Arc<AtomicBool>,
+    engine: Arc<dyn SpeechToText>,
+    config: &LiveTranscriptionConfig,
+    progress: &TranscriptionProgress,
+    mut emit: impl FnMut(TranscriptUpdate),
+) {
+    let chunk_len = ((sample_rate as u64 * config.chunk_ms) / 1000).max(1) as usize;
+    let reorder_wait = Duration::from_millis(config.reorder_wait_ms);
+    let chunk_timeout = Duration::from_millis(config.chunk_timeout_ms);
+    let permits = Arc::new(Semaphore::new(config.max_in_flight.max(1)));
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel::<(u64, Option<TranscriptUpdate>)>();
+    let mut reorder = ReorderBuffer::new();
+    let mut cursor = buffer.lock().map_or(0, |samples| samples.len());
+    let mut next_sequence_id = 0u64;
+    let mut in_flight = 0usize;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+
+    progress.active.store(true, Ordering::SeqCst);
+    progress.queue_depth.store(0, Ordering::SeqCst);
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {
+                // Audio still in the buffer at stop is saved with the recording but not transcribed
+                while is_running.load(Ordering::SeqCst) {
+                    let Some(chunk) = take_chunk(&buffer, &mut cursor, chunk_len) else {
+                        break;
+                    };
+                    let sequence_id = next_sequence_id;
+                    next_sequence_id += 1;
+                    in_flight += 1;
+                    let chunk_start_time = (cursor - chunk_len) as f64 / sample_rate as f64;
+                    let engine = engine.clone();
+                    let permits = permits.clone();
+                    let done_tx = done_tx.clone();
+                    tokio::spawn(async move {
+                        let _permit = permits.acquire_owned().await;
+                        let mut chunk = chunk;
+                        let result = tokio::time::timeout(chunk_timeout, engine.transcribe(&chunk, sample_rate)).await;
+                        secure_wipe::wipe_buffer(&mut chunk);
+                        let update = match result {
+                            Ok(Ok(text)) if !text.trim().is_empty() => Some(TranscriptUpdate {
+                                text,
+                                timestamp: chrono::Utc::now().to_rfc3339(),
+                                source: "microphone".to_string(),
+                                sequence_id,
+                                chunk_start_time,
+                                is_partial: false,
+                            }),
+                            Ok(Ok(_)) => None,
+                            Ok(Err(e)) => {
+                                log::warn!("Transcription of chunk {} failed: {}", sequence_id, e);
+                                None
+                            }
+                            Err(_) => {
+                                log::warn!("Transcription of chunk {} timed out and was abandoned", sequence_id);
+                                None
+                            }
+                        };
+                        let _ = done_tx.send((sequence_id, update));
+                    });
+                }
+                reorder.expire(Instant::now(), reorder_wait).into_iter().for_each(&mut emit);
+            }
+            Some((sequence_id, update)) = done_rx.recv() => {
+                in_flight -= 1;
+                progress.touch();
+                reorder.push(sequence_id, update, Instant::now()).into_iter().for_each(&mut emit);
+            }
+        }
+        progress.queue_depth.store(in_flight + reorder.waiting(), Ordering::SeqCst);
+        if !is_running.load(Ordering::SeqCst) && in_flight == 0 {
+            break;
+        }
+    }
+    progress.queue_depth.store(0, Ordering::SeqCst);
+    progress.active.store(false, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SlowFirstChunk;
+
+    #[async_trait]
+    impl SpeechToText for SlowFirstChunk {
+        async fn transcribe(&self, samples: &[f32], _sample_rate: u32) -> Result<String, String> {
+            // The chunk's first sample says which chunk it is
+            let index = samples[0] as usize;
+            if index == 0 {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+            }
+            Ok(format!("chunk {}", index))
+        }
+    }
+
+    /// Never finishes; records when the worker gives up on it
+    struct Hangs(Arc<AtomicBool>);
+
+    struct SetOnDrop(Arc<AtomicBool>);
+
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl SpeechToText for Hangs {
+        async fn transcribe(&self, _samples: &[f32], _sample_rate: u32) -> Result<String, String> {
+            let _abandoned = SetOnDrop(self.0.clone());
+            std::future::pending::<()>().await;
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_reorder_buffer_releases_in_sequence_order() {
+        let now = Instant::now();
+        let mut reorder = ReorderBuffer::new();
+        assert!(reorder.push(1, Some("b"), now).is_empty());
+        assert!(reorder.push(2, None, now).is_empty());
+        assert_eq!(reorder.push(0, Some("a"), now), vec!["a", "b"]);
+
+        assert!(reorder.push(4, Some("e"), now).is_empty());
+        assert!(reorder.expire(now + Duration::from_millis(10), Duration::from_secs(1)).is_empty());
+        assert_eq!(reorder.expire(now + Duration::from_secs(2), Duration::from_secs(1)), vec!["e"]);
+        // Chunk 3 was skipped, so it is never emitted after chunk 4
+        assert!(reorder.push(3, Some("d"), now).is_empty());
+        assert_eq!(reorder.waiting(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_worker_emits_chunks_in_order_when_they_finish_out_of_order() {
+        let sample_rate = 100;
+        let samples: Vec<f32> = (0..3).flat_map(|i| vec![i as f32; 100]).collect();
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let is_running = Arc::new(AtomicBool::new(true));
+        let config = LiveTranscriptionConfig { max_in_flight: 3, ..LiveTranscriptionConfig::default() };
+        let progress = TranscriptionProgress::new();
+
+        let feeder = {
+            let buffer = buffer.clone();
+            let is_running = is_running.clone();
+            tokio::spawn(async move {
+                buffer.lock().unwrap().extend_from_slice(&samples);
+                tokio::time::sleep(Duration::from_millis(400)).await;
+                is_running.store(false, Ordering::SeqCst);
+            })
+        };
+        let mut emitted = Vec::new();
+        run_worker(buffer, sample_rate, is_running, Arc::new(SlowFirstChunk), &config, &progress, |u| emitted.push(u)).await;
+        feeder.await.unwrap();
+
+        let texts: Vec<&str> = emitted.iter().map(|u| u.text.as_str()).collect();
+        assert_eq!(texts, vec!["chunk 0", "chunk 1", "chunk 2"]);
+        assert_eq!(emitted.iter().map(|u| u.sequence_id).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(emitted[2].chunk_start_time, 2.0);
+        assert!(!progress.is_active());
+        assert_eq!(progress.queue_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_chunk_is_abandoned() {
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let is_running = Arc::new(AtomicBool::new(true));
+        let config = LiveTranscriptionConfig { chunk_timeout_ms: 50, ..LiveTranscriptionConfig::default() };
+        let progress = TranscriptionProgress::new();
+
+        let feeder = {
+            let buffer = buffer.clone();
+            let is_running = is_running.clone();
+            tokio::spawn(async move {
+                buffer.lock().unwrap().extend_from_slice(&[0.0; 100]);
+                tokio::time::sleep(Duration::from_millis(400)).await;
+                is_running.store(false, Ordering::SeqCst);
+            })
+        };
+        let mut emitted = Vec::new();
+        run_worker(buffer, 100, is_running, Arc::new(Hangs(abandoned.clone())), &config, &progress, |u| emitted.push(u)).await;
+        feeder.await.unwrap();
+
+        // Dropping the engine's future is what kills a hung speech-to-text process
+        assert!(abandoned.load(Ordering::SeqCst));
+        assert!(emitted.is_empty());
+        assert_eq!(progress.queue_depth(), 0);
+    }
+}
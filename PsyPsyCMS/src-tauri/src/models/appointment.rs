@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
 use firestore::FirestoreTimestamp;
-use crate::models::common::firestore_now;
+use chrono::{DateTime, Duration, Months, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use crate::models::common::{firestore_now, GeoPoint};
+
+/// Session length assumed when an appointment has no duration set
+pub const DEFAULT_SESSION_MINUTES: i64 = 50;
+
+/// Upper bound on occurrences expanded from one recurring series
+const MAX_SERIES_OCCURRENCES: usize = 1000;
 
 /// Appointment structure based on mobile Firebase structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -27,6 +34,9 @@ pub struct Appointment {
     pub avail_arr: Vec<i32>,
     pub preferred_date_time: Option<String>,
     pub session_duration: Option<i32>,
+    /// Where an in-person session takes place; used for travel-time rules
+    #[serde(default)]
+    pub location: Option<GeoPoint>,
 
     // Assignment and status
     pub assigned_professional: Option<String>,
@@ -41,6 +51,60 @@ pub struct Appointment {
 
     // Payment information
     pub payment_info: Option<PaymentInfo>,
+
+    // Recurring series (first occurrence is the scheduled time)
+    #[serde(default)]
+    pub recurrence: Option<RecurrenceRule>,
+    /// Shared by appointments generated together from one recurrence rule
+    #[serde(default)]
+    pub series_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Repeat rule for a recurring series; every occurrence keeps the series time and duration
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    /// Repeat every `interval` days/weeks/months
+    pub interval: u32,
+    /// Total occurrences, including the first
+    pub count: Option<u32>,
+    /// Last instant an occurrence may start
+    pub until: Option<DateTime<Utc>>,
+    /// Local dates of occurrences skipped (cancelled individually)
+    #[serde(default)]
+    pub excluded_dates: Vec<NaiveDate>,
+    /// Also skip Quebec statutory holidays when generating a series
+    #[serde(default)]
+    pub skip_statutory_holidays: bool,
+}
+
+/// Wall-clock start of occurrence `n` of a series whose first session starts at `first_local`;
+/// `None` once the calendar runs out
+pub(crate) fn series_local_start(rule: &RecurrenceRule, first_local: NaiveDateTime, n: u32) -> Option<NaiveDateTime> {
+    let step = rule.interval.max(1).checked_mul(n)?;
+    match rule.frequency {
+        RecurrenceFrequency::Daily => first_local.checked_add_signed(Duration::days(step as i64)),
+        RecurrenceFrequency::Weekly => first_local.checked_add_signed(Duration::weeks(step as i64)),
+        RecurrenceFrequency::Monthly => first_local.checked_add_months(Months::new(step)),
+    }
+}
+
+/// Instant a clinic-local wall time falls on. In the spring-forward gap the session moves one
+/// hour later; in the repeated fall-back hour the first occurrence is used.
+pub(crate) fn resolve_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|t| t.with_timezone(&Utc))
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -117,6 +181,8 @@ pub struct UpdateAppointmentRequest {
     pub availability: Option<Vec<i32>>,
     pub preferred_date_time: Option<String>,
     pub session_duration: Option<i32>,
+    #[serde(default)]
+    pub location: Option<GeoPoint>,
     pub assigned_professional: Option<String>,
     pub status: Option<AppointmentStatus>,
     pub estimated_cost: Option<f64>,
@@ -145,7 +211,7 @@ pub enum ReminderTime {
     CustomMinutes(i32),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub enum NotificationMethod {
     Email,
@@ -174,6 +240,7 @@ impl Appointment {
             avail_arr: request.availability,
             preferred_date_time: request.preferred_date_time,
             session_duration: request.session_duration,
+            location: None,
             assigned_professional: None,
             status: AppointmentStatus::Pending,
             estimated_cost: None,
@@ -182,7 +249,66 @@ impl Appointment {
             session_notes: None,
             professional_notes: None,
             payment_info: None,
+            recurrence: None,
+            series_id: None,
+        }
+    }
+
+    /// Start of the appointment (or of the first occurrence of its series)
+    pub fn scheduled_start(&self) -> Option<DateTime<Utc>> {
+        self.confirmed_date_time.as_ref().map(|t| t.0).or_else(|| {
+            self.preferred_date_time
+                .as_deref()
+                .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                .map(|t| t.with_timezone(&Utc))
+        })
+    }
+
+    /// Physical location the professional has to travel to; online sessions have none
+    pub fn visit_location(&self) -> Option<&GeoPoint> {
+        match self.meet_pref {
+            MeetingPreference::Online => None,
+            _ => self.location.as_ref(),
+        }
+    }
+
+    pub fn scheduled_duration(&self) -> Duration {
+        Duration::minutes(self.session_duration.map(i64::from).unwrap_or(DEFAULT_SESSION_MINUTES))
+    }
+
+    /// Start/end of every occurrence that intersects `[range_start, range_end)`, expanding
+    /// recurring series and skipping excluded dates. Series step on the wall clock of the clinic
+    /// zone `tz`, as `expand_series` does, and excluded dates are local dates in it.
+    pub fn occurrences_between<Tz: TimeZone>(
+        &self,
+        range_start: DateTime<Utc>,
+        range_end: DateTime<Utc>,
+        tz: &Tz,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let Some(first) = self.scheduled_start() else {
+            return Vec::new();
+        };
+        let duration = self.scheduled_duration();
+        let intersects = |start: DateTime<Utc>| start < range_end && start + duration > range_start;
+
+        let Some(rule) = &self.recurrence else {
+            return if intersects(first) { vec![(first, first + duration)] } else { Vec::new() };
+        };
+
+        let first_local = first.with_timezone(tz).naive_local();
+        let limit = rule.count.map(|c| c as usize).unwrap_or(MAX_SERIES_OCCURRENCES).min(MAX_SERIES_OCCURRENCES);
+        let mut occurrences = Vec::new();
+        for n in 0..limit as u32 {
+            let Some(local) = series_local_start(rule, first_local, n) else { break };
+            let Some(start) = resolve_local(tz, local) else { continue };
+            if start >= range_end || rule.until.is_some_and(|until| start > until) {
+                break;
+            }
+            if intersects(start) && !rule.excluded_dates.contains(&local.date()) {
+                occurrences.push((start, start + duration));
+            }
         }
+        occurrences
     }
 
     pub fn assign_professional(&mut self, professional_id: String, estimated_cost: f64) {
@@ -243,6 +369,9 @@ impl Appointment {
         if let Some(session_duration) = request.session_duration {
             self.session_duration = Some(session_duration);
         }
+        if let Some(location) = request.location {
+            self.location = Some(location);
+        }
         if let Some(assigned_professional) = request.assigned_professional {
             self.assigned_professional = Some(assigned_professional);
         }
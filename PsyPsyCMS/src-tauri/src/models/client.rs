@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use firestore::FirestoreTimestamp;
 
-use super::common::{UserProfile, AddressObject, GeoPoint, firestore_now};
+use super::common::{UserProfile, AddressObject, GeoPoint, Listable, SearchFilters, compare_profile_names, profile_matches_query, firestore_now};
 
 /// Client structure based on mobile Firebase structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -14,6 +14,14 @@ pub struct Client {
     // Personal profile information
     #[serde(flatten)]
     pub profile: UserProfile,
+    /// Quebec health insurance (RAMQ) number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ramq_number: Option<String>,
+    /// Contact details given at intake; records created before they were kept have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
 
     // Location information
     pub address_obj: AddressObject,
@@ -42,6 +50,10 @@ pub struct Client {
     // Timestamps
     pub created_at: FirestoreTimestamp,
     pub updated_at: FirestoreTimestamp,
+
+    /// Set when this record was merged into another; the record is kept as a tombstone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<String>,
 }
 
 /// Client status enumeration
@@ -158,6 +170,8 @@ pub struct CreateClientRequest {
     pub email: String,
     pub phone: String,
     pub date_of_birth: Option<String>,
+    #[serde(default)]
+    pub ramq_number: Option<String>,
     pub address: AddressObject,
     pub spoken_languages: Vec<i32>,
     pub search_radius: Option<i32>,
@@ -171,6 +185,8 @@ pub struct CreateClientRequest {
 pub struct UpdateClientRequest {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    #[serde(default)]
+    pub ramq_number: Option<String>,
     pub address: Option<AddressObject>,
     pub search_radius: Option<i32>,
     pub spoken_languages: Option<Vec<i32>>,
@@ -222,6 +238,9 @@ impl Client {
                 updated_at: now.clone(),
                 is_active: true,
             },
+            ramq_number: request.ramq_number,
+            email: Some(request.email).filter(|e| !e.trim().is_empty()),
+            phone: Some(request.phone).filter(|p| !p.trim().is_empty()),
             address_obj: request.address,
             geo_pt: None, // Will be geocoded separately
             search_radius: request.search_radius.unwrap_or(25), // Default 25km
@@ -236,6 +255,7 @@ impl Client {
             preferences: request.preferences.unwrap_or_default(),
             created_at: now.clone(),
             updated_at: now.clone(),
+            merged_into: None,
         }
     }
 
@@ -247,6 +267,9 @@ impl Client {
         if let Some(last_name) = request.last_name {
             self.profile.last_name = last_name;
         }
+        if let Some(ramq_number) = request.ramq_number {
+            self.ramq_number = Some(ramq_number);
+        }
         if let Some(address) = request.address {
             self.address_obj = address;
         }
@@ -305,6 +328,38 @@ impl Client {
     }
 }
 
+/// Sorting for `get_clients`; the filter's `status`, `assigned_professional` and `search_query`
+/// (matched against the name) apply
+impl Listable for Client {
+    const SORT_FIELDS: &'static [&'static str] = &["name", "status", "createdAt", "updatedAt", "totalAppointments"];
+
+    fn list_id(&self) -> &str {
+        &self.object_id
+    }
+
+    fn compare_by(&self, other: &Self, field: &str) -> std::cmp::Ordering {
+        match field {
+            "status" => format!("{:?}", self.status).cmp(&format!("{:?}", other.status)),
+            "createdAt" => self.created_at.0.cmp(&other.created_at.0),
+            "updatedAt" => self.updated_at.0.cmp(&other.updated_at.0),
+            "totalAppointments" => self.total_appointments.cmp(&other.total_appointments),
+            _ => compare_profile_names(&self.profile, &other.profile),
+        }
+    }
+
+    fn matches_filter(&self, filter: &SearchFilters) -> bool {
+        let status_matches = filter
+            .status
+            .as_deref()
+            .map_or(true, |status| format!("{:?}", self.status).eq_ignore_ascii_case(status));
+        let professional_matches = filter
+            .assigned_professional
+            .as_ref()
+            .map_or(true, |id| self.assigned_professionals.contains(id));
+        status_matches && professional_matches && profile_matches_query(&self.profile, filter)
+    }
+}
+
 /// Appointment type for tracking
 pub enum AppointmentType {
     Total,
@@ -315,6 +370,7 @@ pub enum AppointmentType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::common::{ListPage, ListParams, SortDirection};
 
     #[test]
     fn test_client_creation() {
@@ -325,6 +381,7 @@ mod tests {
             email: "john@example.com".to_string(),
             phone: "1234567890".to_string(),
             date_of_birth: Some("1990-01-01".to_string()),
+            ramq_number: None,
             address: AddressObject {
                 street: "123 Main St".to_string(),
                 city: "Anytown".to_string(),
@@ -356,6 +413,7 @@ mod tests {
                 email: "john@example.com".to_string(),
                 phone: "1234567890".to_string(),
                 date_of_birth: None,
+                ramq_number: None,
                 address: AddressObject {
                     street: "123 Main St".to_string(),
                     city: "Anytown".to_string(),
@@ -380,4 +438,81 @@ mod tests {
         client.unassign_professional("prof123");
         assert_eq!(client.assigned_professionals.len(), 0);
     }
+
+    fn named_client(id: &str, first_name: &str, last_name: &str) -> Client {
+        Client::from_request(
+            CreateClientRequest {
+                user_id: format!("user-{}", id),
+                first_name: first_name.to_string(),
+                last_name: last_name.to_string(),
+                email: format!("{}@example.com", id),
+                phone: "5145550100".to_string(),
+                date_of_birth: None,
+                ramq_number: None,
+                address: AddressObject {
+                    street: "1 Rue Principale".to_string(),
+                    city: "Montréal".to_string(),
+                    state: "QC".to_string(),
+                    zip_code: "H2X 1Y4".to_string(),
+                    country: "Canada".to_string(),
+                },
+                spoken_languages: vec![1],
+                search_radius: None,
+                preferences: None,
+                emergency_contacts: None,
+            },
+            id.to_string(),
+        )
+    }
+
+    fn clients() -> Vec<Client> {
+        vec![
+            named_client("c3", "Marie", "Tremblay"),
+            named_client("c1", "Luc", "gagnon"),
+            named_client("c2", "Anne", "Gagnon"),
+            named_client("c4", "Paul", "Roy"),
+        ]
+    }
+
+    fn ids(page: &ListPage<Client>) -> Vec<&str> {
+        page.items.iter().map(|c| c.object_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_params_sort_by_name_and_page() {
+        let page = ListParams::default().apply(clients()).unwrap();
+        assert_eq!(ids(&page), vec!["c2", "c1", "c4", "c3"]);
+        assert_eq!(page.total_count, 4);
+
+        let params = ListParams {
+            limit: Some(2),
+            offset: Some(1),
+            sort_dir: Some(SortDirection::Desc),
+            ..Default::default()
+        };
+        assert_eq!(ids(&params.apply(clients()).unwrap()), vec!["c4", "c1"]);
+
+        let params = ListParams { sort_by: Some("email; DROP".to_string()), ..Default::default() };
+        let error = params.apply(clients()).unwrap_err();
+        assert!(error.starts_with("Validation failed"), "{}", error);
+    }
+
+    #[test]
+    fn test_list_params_offset_past_end_keeps_total() {
+        let params = ListParams {
+            offset: Some(10),
+            filter: Some(SearchFilters {
+                status: None,
+                user_type: None,
+                assigned_professional: None,
+                date_from: None,
+                date_to: None,
+                search_query: Some("gagnon".to_string()),
+            }),
+            ..Default::default()
+        };
+        let page = params.apply(clients()).unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.total_count, 2);
+    }
 }
\ No newline at end of file
@@ -167,13 +167,102 @@ pub struct SortOptions {
 }
 
 /// Sort direction
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SortDirection {
     Asc,
     Desc,
 }
 
+/// Page size when a list request does not give one
+pub const DEFAULT_LIST_LIMIT: u32 = 10;
+/// Largest page a list command returns
+pub const MAX_LIST_LIMIT: u32 = 100;
+
+/// Paging, sorting and filtering for list commands; all optional, so no params gives the
+/// first page sorted by name
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListParams {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// One of the record type's `SORT_FIELDS`
+    pub sort_by: Option<String>,
+    pub sort_dir: Option<SortDirection>,
+    pub filter: Option<SearchFilters>,
+}
+
+/// One page of a list and the number of records matching the filter across all pages
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPage<T> {
+    pub items: Vec<T>,
+    pub total_count: u32,
+}
+
+/// Records list commands can sort and filter
+pub trait Listable {
+    /// Allowed `sort_by` values; `name` must be one of them
+    const SORT_FIELDS: &'static [&'static str];
+
+    fn list_id(&self) -> &str;
+
+    /// Ascending order on one of `SORT_FIELDS`
+    fn compare_by(&self, other: &Self, field: &str) -> std::cmp::Ordering;
+
+    fn matches_filter(&self, filter: &SearchFilters) -> bool;
+}
+
+/// Last name then first name, ignoring case
+pub fn compare_profile_names(a: &UserProfile, b: &UserProfile) -> std::cmp::Ordering {
+    let key = |p: &UserProfile| (p.last_name.to_lowercase(), p.first_name.to_lowercase());
+    key(a).cmp(&key(b))
+}
+
+/// Whether a profile's full name contains the filter's search query, ignoring case
+pub fn profile_matches_query(profile: &UserProfile, filter: &SearchFilters) -> bool {
+    match filter.search_query.as_deref().map(str::trim) {
+        Some(query) if !query.is_empty() => format!("{} {}", profile.first_name, profile.last_name)
+            .to_lowercase()
+            .contains(&query.to_lowercase()),
+        _ => true,
+    }
+}
+
+impl ListParams {
+    /// Filters, sorts and slices `records`. Ties on the sort field fall back to name and then
+    /// id, so pages stay stable between requests.
+    pub fn apply<T: Listable>(&self, records: Vec<T>) -> Result<ListPage<T>, String> {
+        let sort_by = self.sort_by.as_deref().map(str::trim).unwrap_or("name");
+        if !T::SORT_FIELDS.contains(&sort_by) {
+            return Err(format!(
+                "Validation failed: cannot sort by '{}', expected one of: {}",
+                sort_by,
+                T::SORT_FIELDS.join(", ")
+            ));
+        }
+        let descending = self.sort_dir == Some(SortDirection::Desc);
+
+        let mut records: Vec<T> = match &self.filter {
+            Some(filter) => records.into_iter().filter(|r| r.matches_filter(filter)).collect(),
+            None => records,
+        };
+        records.sort_by(|a, b| {
+            let primary = a.compare_by(b, sort_by);
+            let primary = if descending { primary.reverse() } else { primary };
+            primary
+                .then_with(|| a.compare_by(b, "name"))
+                .then_with(|| a.list_id().cmp(b.list_id()))
+        });
+
+        let total_count = records.len() as u32;
+        let limit = self.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT) as usize;
+        let offset = self.offset.unwrap_or(0) as usize;
+        let items = records.into_iter().skip(offset).take(limit).collect();
+        Ok(ListPage { items, total_count })
+    }
+}
+
 /// API Response wrapper
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 use firestore::FirestoreTimestamp;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
 use std::collections::HashMap;
 
-use super::common::{UserProfile, AddressObject, GeoPoint, PhoneNumber, ExpertiseObject, ServiceObject, OrderInfo, firestore_now};
+use super::common::{UserProfile, AddressObject, GeoPoint, PhoneNumber, ExpertiseObject, ServiceObject, OrderInfo, Listable, SearchFilters, compare_profile_names, profile_matches_query, firestore_now};
 
 /// Professional structure based on mobile Firebase structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,6 +24,9 @@ pub struct Professional {
 
     // Professional Details
     pub prof_type: i32,
+    /// Codes from the specialty taxonomy
+    #[serde(default)]
+    pub specialties: Vec<String>,
     pub edu_institute: i32,
     pub mother_tongue: i32,
     pub offered_lang_arr: Vec<i32>,
@@ -35,6 +39,9 @@ pub struct Professional {
 
     // Business Operations
     pub availability: Vec<i32>,
+    /// Bookable hours; `None` falls back to the clinic's hours
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_hours: Option<WorkingHours>,
     pub meet_type: MeetingType, // 0=both, 1=in-person, 2=online
     pub third_party_payers: Vec<i32>,
     pub part_of_order: Option<OrderInfo>,
@@ -55,6 +62,76 @@ pub struct Professional {
     pub updated_at: FirestoreTimestamp,
 }
 
+/// A span of the working day, in the clinic's local time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+/// Hours that replace the weekly ones on one date; no ranges means the day is off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingHoursException {
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub ranges: Vec<TimeRange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Working hours per weekday plus dated exceptions; days without an entry are not worked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingHours {
+    pub weekly: HashMap<Weekday, Vec<TimeRange>>,
+    #[serde(default)]
+    pub exceptions: Vec<WorkingHoursException>,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        let day = vec![TimeRange {
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+        }];
+        Self {
+            weekly: [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]
+                .into_iter()
+                .map(|d| (d, day.clone()))
+                .collect(),
+            exceptions: Vec::new(),
+        }
+    }
+}
+
+impl WorkingHours {
+    /// Ranges worked on a local date, an exception for that date taking precedence
+    pub fn ranges_on(&self, date: NaiveDate) -> &[TimeRange] {
+        match self.exceptions.iter().find(|e| e.date == date) {
+            Some(exception) => &exception.ranges,
+            None => self.weekly.get(&date.weekday()).map_or(&[], Vec::as_slice),
+        }
+    }
+
+    /// Whether `[start, end)` falls entirely inside one working range of its day
+    pub fn contains<Tz: TimeZone>(&self, start: DateTime<Utc>, end: DateTime<Utc>, tz: &Tz) -> bool {
+        let local_start = start.with_timezone(tz);
+        let local_end = end.with_timezone(tz);
+        if local_start.date_naive() != local_end.date_naive() && local_end.time() != NaiveTime::MIN {
+            return false;
+        }
+        let end_time = if local_end.time() == NaiveTime::MIN && end > start {
+            NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+        } else {
+            local_end.time()
+        };
+        self.ranges_on(local_start.date_naive())
+            .iter()
+            .any(|r| r.start <= local_start.time() && end_time <= r.end)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum MeetingType {
@@ -120,6 +197,9 @@ pub struct CreateProfessionalRequest {
     pub phone: PhoneNumber,
     pub address: AddressObject,
     pub prof_type: i32,
+    /// Specialty codes, labels or aliases; stored as canonical codes
+    #[serde(default)]
+    pub specialties: Vec<String>,
     pub license_info: LicenseInfo,
     pub expertises: Vec<ExpertiseObject>,
     pub services: HashMap<i32, ServiceObject>,
@@ -135,10 +215,13 @@ pub struct UpdateProfessionalRequest {
     pub phone: Option<PhoneNumber>,
     pub address: Option<AddressObject>,
     pub prof_type: Option<i32>,
+    pub specialties: Option<Vec<String>>,
     pub license_info: Option<LicenseInfo>,
     pub expertises: Option<Vec<ExpertiseObject>>,
     pub services: Option<HashMap<i32, ServiceObject>>,
     pub availability: Option<Vec<i32>>,
+    #[serde(default)]
+    pub working_hours: Option<WorkingHours>,
     pub meet_type: Option<MeetingType>,
     pub third_party_payers: Option<Vec<i32>>,
     pub served_clientele: Option<Vec<i32>>,
@@ -172,6 +255,7 @@ impl Professional {
             buss_email: request.buss_email,
             business_name: request.business_name,
             prof_type: request.prof_type,
+            specialties: request.specialties,
             edu_institute: 0,
             mother_tongue: 1, // Default to English
             offered_lang_arr: vec![1],
@@ -180,6 +264,7 @@ impl Professional {
             serv_offered_obj: request.services,
             served_clientele: Vec::new(),
             availability: Vec::new(),
+            working_hours: None,
             meet_type: MeetingType::Both,
             third_party_payers: Vec::new(),
             part_of_order: None,
@@ -235,6 +320,9 @@ impl Professional {
         if let Some(prof_type) = request.prof_type {
             self.prof_type = prof_type;
         }
+        if let Some(specialties) = request.specialties {
+            self.specialties = specialties;
+        }
         if let Some(license_info) = request.license_info {
             self.license_info = license_info;
         }
@@ -248,6 +336,9 @@ impl Professional {
         if let Some(availability) = request.availability {
             self.availability = availability;
         }
+        if let Some(working_hours) = request.working_hours {
+            self.working_hours = Some(working_hours);
+        }
         if let Some(meet_type) = request.meet_type {
             self.meet_type = meet_type;
         }
@@ -274,4 +365,37 @@ impl Professional {
         // In a real implementation, verification_notes would be stored in a separate audit log
         self.updated_at = firestore_now();
     }
+}
+
+/// Sorting for `get_professionals`; the filter's `status` and `search_query` (matched against the
+/// name or business name) apply
+impl Listable for Professional {
+    const SORT_FIELDS: &'static [&'static str] = &["name", "businessName", "status", "createdAt", "updatedAt"];
+
+    fn list_id(&self) -> &str {
+        &self.object_id
+    }
+
+    fn compare_by(&self, other: &Self, field: &str) -> std::cmp::Ordering {
+        match field {
+            "businessName" => self.business_name.to_lowercase().cmp(&other.business_name.to_lowercase()),
+            "status" => format!("{:?}", self.status).cmp(&format!("{:?}", other.status)),
+            "createdAt" => self.created_at.0.cmp(&other.created_at.0),
+            "updatedAt" => self.updated_at.0.cmp(&other.updated_at.0),
+            _ => compare_profile_names(&self.profile, &other.profile),
+        }
+    }
+
+    fn matches_filter(&self, filter: &SearchFilters) -> bool {
+        let status_matches = filter
+            .status
+            .as_deref()
+            .map_or(true, |status| format!("{:?}", self.status).eq_ignore_ascii_case(status));
+        let query_matches = profile_matches_query(&self.profile, filter)
+            || filter
+                .search_query
+                .as_deref()
+                .is_some_and(|q| self.business_name.to_lowercase().contains(&q.trim().to_lowercase()));
+        status_matches && query_matches
+    }
 }
\ No newline at end of file
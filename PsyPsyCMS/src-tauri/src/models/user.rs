@@ -101,6 +101,9 @@ pub struct LoginResponse {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_in: i64,
+    /// The sign-in was flagged and MFA must be completed before anything else
+    #[serde(default)]
+    pub mfa_required: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,278 @@
+// Access Heatmap for PsyPsy CMS
+// Aggregates PHI-access audit events into per-user access counts, either by patient or by time
+// bucket, so privacy officers can spot snooping. Only who, whom and when are read from the log;
+// actions, states and metadata never reach the report. A user is flagged as an outlier when they
+// touched far more distinct patients than the median of their peers over the same period.
+
+use crate::security::audit::AuditEvent;
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Columns of the heatmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapAxis {
+    /// One column per patient
+    Patients,
+    /// One column per time bucket
+    Time,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeBucket {
+    Hour,
+    Day,
+    Week,
+}
+
+impl TimeBucket {
+    fn label(&self, at: DateTime<Utc>) -> String {
+        match self {
+            TimeBucket::Hour => at.format("%Y-%m-%dT%H:00Z").to_string(),
+            TimeBucket::Day => at.format("%Y-%m-%d").to_string(),
+            // Weeks are labelled by their Monday
+            TimeBucket::Week => (at - Duration::days(at.weekday().num_days_from_monday() as i64))
+                .format("%Y-%m-%d")
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessHeatmapConfig {
+    /// A user is an outlier above this multiple of the peer median of distinct patients
+    pub outlier_factor: f64,
+    /// Users below this many distinct patients are never flagged
+    pub min_patients_for_outlier: usize,
+    /// Period covered when the caller gives no start date
+    pub default_lookback_days: i64,
+}
+
+impl Default for AccessHeatmapConfig {
+    fn default() -> Self {
+        Self {
+            outlier_factor: 3.0,
+            min_patients_for_outlier: 10,
+            default_lookback_days: 30,
+        }
+    }
+}
+
+/// Longest default lookback; the audit log is only retained this long
+pub const MAX_LOOKBACK_DAYS: i64 = 2555;
+
+impl AccessHeatmapConfig {
+    /// Defaults with `ACCESS_HEATMAP_OUTLIER_FACTOR`, `ACCESS_HEATMAP_MIN_PATIENTS` and
+    /// `ACCESS_HEATMAP_LOOKBACK_DAYS` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("ACCESS_HEATMAP_OUTLIER_FACTOR") {
+            match value.trim().parse::<f64>() {
+                Ok(factor) if factor > 1.0 && factor.is_finite() => config.outlier_factor = factor,
+                _ => log::warn!("Ignoring invalid ACCESS_HEATMAP_OUTLIER_FACTOR '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("ACCESS_HEATMAP_MIN_PATIENTS") {
+            match value.trim().parse::<usize>() {
+                Ok(min) => config.min_patients_for_outlier = min,
+                Err(_) => log::warn!("Ignoring invalid ACCESS_HEATMAP_MIN_PATIENTS '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("ACCESS_HEATMAP_LOOKBACK_DAYS") {
+            match value.trim().parse::<i64>() {
+                Ok(days) if days > 0 => config.default_lookback_days = days.min(MAX_LOOKBACK_DAYS),
+                _ => log::warn!("Ignoring invalid ACCESS_HEATMAP_LOOKBACK_DAYS '{}'", value),
+            }
+        }
+        config
+    }
+
+    /// Period covered when the caller gives no start date, kept within audit retention
+    pub fn lookback(&self) -> chrono::Duration {
+        chrono::Duration::days(self.default_lookback_days.clamp(1, MAX_LOOKBACK_DAYS))
+    }
+}
+
+/// One user's row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapRow {
+    pub user_id: String,
+    pub total_accesses: u32,
+    pub distinct_patients: usize,
+    /// Access count per column; columns without access are omitted
+    pub cells: BTreeMap<String, u32>,
+    pub outlier: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessHeatmap {
+    pub axis: HeatmapAxis,
+    pub bucket: Option<TimeBucket>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub columns: Vec<String>,
+    /// Busiest users first
+    pub rows: Vec<HeatmapRow>,
+    pub peer_median_patients: f64,
+    /// Distinct-patient count above which a user was flagged
+    pub outlier_threshold: f64,
+    pub event_count: usize,
+}
+
+fn median(values: &mut [usize]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) as f64 / 2.0
+    } else {
+        values[mid] as f64
+    }
+}
+
+/// Build the heatmap from audit events; only events naming both a user and a patient count
+pub fn build_heatmap(
+    events: &[AuditEvent],
+    axis: HeatmapAxis,
+    bucket: TimeBucket,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    config: &AccessHeatmapConfig,
+) -> AccessHeatmap {
+    let mut rows: HashMap<String, (HeatmapRow, HashSet<String>)> = HashMap::new();
+    let mut columns = BTreeSet::new();
+    let mut event_count = 0;
+
+    for event in events.iter().filter(|e| e.timestamp >= start && e.timestamp < end) {
+        let (Some(user_id), Some(patient_id)) = (event.user_id, event.patient_id) else {
+            continue;
+        };
+        event_count += 1;
+        let user_id = user_id.to_string();
+        let patient_id = patient_id.to_string();
+        let column = match axis {
+            HeatmapAxis::Patients => patient_id.clone(),
+            HeatmapAxis::Time => bucket.label(event.timestamp),
+        };
+
+        let (row, patients) = rows.entry(user_id.clone()).or_insert_with(|| {
+            (
+                HeatmapRow { user_id, total_accesses: 0, distinct_patients: 0, cells: BTreeMap::new(), outlier: false },
+                HashSet::new(),
+            )
+        });
+        // A coalesced entry stands for several views
+        row.total_accesses += event.occurrences();
+        *row.cells.entry(column.clone()).or_insert(0) += event.occurrences();
+        patients.insert(patient_id);
+        columns.insert(column);
+    }
+
+    let mut rows: Vec<HeatmapRow> = rows
+        .into_values()
+        .map(|(mut row, patients)| {
+            row.distinct_patients = patients.len();
+            row
+        })
+        .collect();
+    let peer_median_patients = median(&mut rows.iter().map(|r| r.distinct_patients).collect::<Vec<_>>());
+    let outlier_threshold = (peer_median_patients * config.outlier_factor).max(config.min_patients_for_outlier as f64);
+    // A lone user has no peers to be compared with
+    if rows.len() > 1 {
+        for row in rows.iter_mut() {
+            row.outlier = row.distinct_patients as f64 > outlier_threshold;
+        }
+    }
+    rows.sort_by(|a, b| b.total_accesses.cmp(&a.total_accesses).then_with(|| a.user_id.cmp(&b.user_id)));
+
+    AccessHeatmap {
+        axis,
+        bucket: (axis == HeatmapAxis::Time).then_some(bucket),
+        start,
+        end,
+        columns: columns.into_iter().collect(),
+        rows,
+        peer_median_patients,
+        outlier_threshold,
+        event_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::audit::AuditOutcome;
+    use crate::security::AuditEventType;
+    use uuid::Uuid;
+
+    fn access(user: Uuid, patient: Uuid, at: DateTime<Utc>) -> AuditEvent {
+        let mut event = AuditEvent::new(AuditEventType::PatientDataViewed, Some(user), "view_client".to_string(), AuditOutcome::Success)
+            .with_phi_access(patient, "patient_record");
+        event.timestamp = at;
+        event.description = "session notes: patient reports anxiety".to_string();
+        event
+    }
+
+    #[test]
+    fn test_lookback_is_clamped_to_retention() {
+        let config = AccessHeatmapConfig { default_lookback_days: i64::MAX, ..AccessHeatmapConfig::default() };
+        assert_eq!(config.lookback(), chrono::Duration::days(MAX_LOOKBACK_DAYS));
+    }
+
+    #[test]
+    fn test_user_touching_far_more_patients_is_flagged() {
+        let now = Utc::now();
+        let config = AccessHeatmapConfig { min_patients_for_outlier: 5, ..AccessHeatmapConfig::default() };
+        let patients: Vec<Uuid> = (0..30).map(|_| Uuid::new_v4()).collect();
+        let peers: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let snoop = Uuid::new_v4();
+
+        let mut events = Vec::new();
+        for (i, peer) in peers.iter().enumerate() {
+            for patient in &patients[i * 2..i * 2 + 2] {
+                events.push(access(*peer, *patient, now - Duration::hours(1)));
+            }
+        }
+        for patient in &patients {
+            events.push(access(snoop, *patient, now - Duration::hours(2)));
+        }
+
+        let heatmap = build_heatmap(&events, HeatmapAxis::Patients, TimeBucket::Day, now - Duration::days(1), now, &config);
+        assert_eq!(heatmap.event_count, 36);
+        assert_eq!(heatmap.rows[0].user_id, snoop.to_string());
+        assert!(heatmap.rows[0].outlier);
+        assert!(heatmap.rows[1..].iter().all(|r| !r.outlier));
+        // Counts only; nothing from the event's content is carried over
+        assert!(!serde_json::to_string(&heatmap).unwrap().contains("anxiety"));
+    }
+
+    #[test]
+    fn test_time_axis_buckets_accesses() {
+        let day = DateTime::parse_from_rfc3339("2026-03-04T10:15:00Z").unwrap().with_timezone(&Utc);
+        let user = Uuid::new_v4();
+        let patient = Uuid::new_v4();
+        let events = vec![
+            access(user, patient, day),
+            access(user, patient, day + Duration::hours(3)),
+            access(user, patient, day + Duration::days(1)),
+        ];
+        let heatmap = build_heatmap(
+            &events,
+            HeatmapAxis::Time,
+            TimeBucket::Day,
+            day - Duration::days(1),
+            day + Duration::days(2),
+            &AccessHeatmapConfig::default(),
+        );
+        assert_eq!(heatmap.columns, vec!["2026-03-04", "2026-03-05"]);
+        assert_eq!(heatmap.rows[0].cells.get("2026-03-04"), Some(&2));
+        assert_eq!(heatmap.rows[0].distinct_patients, 1);
+    }
+}
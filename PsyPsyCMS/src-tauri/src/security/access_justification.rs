@@ -0,0 +1,174 @@
+// Reason-for-Access Policy for PsyPsy CMS
+// Optionally requires a justification before PHI is returned for sensitive classifications or
+// flagged (e.g. VIP) patients. Records outside the policy stay frictionless.
+
+use crate::security::{DataClassification, SecurityError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// File holding the policy inside the app data directory
+const POLICY_FILE: &str = "access_justification_policy.json";
+
+/// Which records require a reason for access
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JustificationPolicy {
+    /// Classifications that always require a justification
+    pub classifications: HashSet<DataClassification>,
+    /// Patients/clients flagged for justification regardless of classification
+    pub flagged_patients: HashSet<String>,
+    /// Shortest accepted justification, after trimming
+    pub min_length: usize,
+}
+
+impl Default for JustificationPolicy {
+    fn default() -> Self {
+        Self {
+            classifications: HashSet::new(),
+            flagged_patients: HashSet::new(),
+            min_length: 10,
+        }
+    }
+}
+
+impl JustificationPolicy {
+    /// Defaults with a `PHI_JUSTIFICATION_CLASSIFICATIONS` override (e.g. `MedicalSensitive,Phi`)
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(value) = std::env::var("PHI_JUSTIFICATION_CLASSIFICATIONS") {
+            policy.classifications = value
+                .split(',')
+                .filter_map(|name| serde_json::from_value(serde_json::Value::String(name.trim().to_string())).ok())
+                .collect();
+        }
+        policy
+    }
+
+    /// Whether access to this record needs a justification
+    pub fn requires_justification(&self, patient_id: &str, classification: DataClassification) -> bool {
+        self.classifications.contains(&classification) || self.flagged_patients.contains(patient_id)
+    }
+
+    /// Check the justification supplied for an access; returns the normalized reason to audit
+    pub fn check(
+        &self,
+        patient_id: &str,
+        classification: DataClassification,
+        justification: Option<&str>,
+    ) -> Result<Option<String>, SecurityError> {
+        let reason = justification.map(str::trim).filter(|r| !r.is_empty());
+        if !self.requires_justification(patient_id, classification) {
+            return Ok(reason.map(str::to_string));
+        }
+        match reason {
+            Some(reason) if reason.chars().count() >= self.min_length => Ok(Some(reason.to_string())),
+            Some(_) => Err(SecurityError::AccessDenied {
+                reason: format!("Reason for access must be at least {} characters", self.min_length),
+            }),
+            None => Err(SecurityError::AccessDenied {
+                reason: "A reason for access is required for this record".to_string(),
+            }),
+        }
+    }
+}
+
+/// Shared policy, persisted once storage is attached
+#[derive(Debug, Clone)]
+pub struct JustificationPolicyState {
+    policy: Arc<RwLock<JustificationPolicy>>,
+    storage_path: Arc<RwLock<Option<PathBuf>>>,
+}
+
+impl JustificationPolicyState {
+    pub fn new(policy: JustificationPolicy) -> Self {
+        Self {
+            policy: Arc::new(RwLock::new(policy)),
+            storage_path: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Load a saved policy from `dir` (replacing the initial one) and persist future changes there
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        let path = dir.join(POLICY_FILE);
+        if path.exists() {
+            if let Ok(saved) = serde_json::from_slice::<JustificationPolicy>(&std::fs::read(&path)?) {
+                *self.policy.write().unwrap() = saved;
+            }
+        }
+        *self.storage_path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    pub fn get(&self) -> JustificationPolicy {
+        self.policy.read().unwrap().clone()
+    }
+
+    pub fn check(
+        &self,
+        patient_id: &str,
+        classification: DataClassification,
+        justification: Option<&str>,
+    ) -> Result<Option<String>, SecurityError> {
+        self.policy.read().unwrap().check(patient_id, classification, justification)
+    }
+
+    pub fn update(&self, policy: JustificationPolicy) {
+        *self.policy.write().unwrap() = policy;
+        self.persist();
+    }
+
+    fn persist(&self) {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            return;
+        };
+        match serde_json::to_vec_pretty(&*self.policy.read().unwrap()) {
+            Ok(bytes) => {
+                if let Err(e) = std::fs::write(&path, bytes) {
+                    log::warn!("Failed to persist access justification policy: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize access justification policy: {}", e),
+        }
+    }
+}
+
+impl Default for JustificationPolicyState {
+    fn default() -> Self {
+        Self::new(JustificationPolicy::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_records_are_frictionless() {
+        let policy = JustificationPolicy::default();
+        assert_eq!(policy.check("client-1", DataClassification::Phi, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_flagged_patient_requires_reason() {
+        let mut policy = JustificationPolicy::default();
+        policy.flagged_patients.insert("vip-1".to_string());
+
+        assert!(matches!(policy.check("vip-1", DataClassification::Phi, None), Err(SecurityError::AccessDenied { .. })));
+        assert!(policy.check("vip-1", DataClassification::Phi, Some("  chart  ")).is_err());
+        assert_eq!(
+            policy.check("vip-1", DataClassification::Phi, Some(" Follow-up after ER visit ")).unwrap().as_deref(),
+            Some("Follow-up after ER visit")
+        );
+        assert!(policy.check("client-2", DataClassification::Phi, None).is_ok());
+    }
+
+    #[test]
+    fn test_classification_requires_reason() {
+        let mut policy = JustificationPolicy::default();
+        policy.classifications.insert(DataClassification::MedicalSensitive);
+
+        assert!(policy.check("client-1", DataClassification::MedicalSensitive, None).is_err());
+        assert!(policy.check("client-1", DataClassification::Phi, None).is_ok());
+    }
+}
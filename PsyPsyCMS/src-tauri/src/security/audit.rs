@@ -2,6 +2,7 @@
 // Implements comprehensive audit logging for healthcare data access and system events
 
 use crate::security::{SecurityError, AuditEventType, HealthcareRole, DataClassification};
+use crate::security::audit_worm::{self, WormAlertHook, WormAuditWriter, WormConfig};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -10,9 +11,13 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc, Duration};
 use std::fs::{File, OpenOptions};
 use std::io::{Write, BufWriter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use ring::digest;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use tracing::{info, warn, error, debug};
+use once_cell::sync::OnceCell;
 
 /// HIPAA audit event with comprehensive tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +74,47 @@ pub struct AuditEvent {
     pub data_size_bytes: Option<u64>,
     /// Number of records affected
     pub records_affected: Option<u32>,
+    /// Position in the log, assigned when the service accepts the event. Batches are written
+    /// in this order, so the log order is the order events were accepted.
+    #[serde(default)]
+    pub sequence: u64,
+    /// Set when identical rapid events were collapsed into this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coalesced: Option<CoalescedSpan>,
+    /// Hash of the previous entry in the log, or of the configured seed for the first entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+    /// Hash of this entry, `prev_hash` included; set when the entry is written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_hash: Option<String>,
+}
+
+/// JSON text with object keys in sorted order
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let sorted: std::collections::BTreeMap<&String, String> =
+                map.iter().map(|(k, v)| (k, canonical_json(v))).collect();
+            let fields: Vec<String> = sorted
+                .into_iter()
+                .map(|(k, v)| format!("{}:{}", serde_json::Value::String(k.clone()), v))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        serde_json::Value::Array(items) => {
+            format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Span covered by an entry that stands for several identical events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoalescedSpan {
+    /// Number of events the entry stands for, including the first
+    pub count: u32,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
 }
 
 impl AuditEvent {
@@ -106,6 +152,10 @@ impl AuditEvent {
             duration_ms: None,
             data_size_bytes: None,
             records_affected: None,
+            sequence: 0,
+            coalesced: None,
+            prev_hash: None,
+            entry_hash: None,
         }
     }
     
@@ -150,6 +200,25 @@ impl AuditEvent {
         let hash = digest::digest(&digest::SHA256, event_json.as_bytes());
         general_purpose::STANDARD.encode(hash.as_ref())
     }
+
+    /// MAC stored in `entry_hash`: the entry without its own hash, with object keys sorted
+    /// so the metadata map hashes the same after a round trip through the log. Keyed, so an
+    /// edited log cannot be resealed without the install's chain key.
+    pub fn chain_hash(&self, key: &[u8]) -> String {
+        use base64::{Engine as _, engine::general_purpose};
+        let mut unsealed = self.clone();
+        unsealed.entry_hash = None;
+        let value = serde_json::to_value(&unsealed).unwrap_or_default();
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(canonical_json(&value).as_bytes());
+        general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+    }
+
+    /// Link this entry after `prev_hash` and seal it
+    fn seal(&mut self, prev_hash: String, key: &[u8]) {
+        self.prev_hash = Some(prev_hash);
+        self.entry_hash = Some(self.chain_hash(key));
+    }
     
     /// Check if event is HIPAA-critical
     pub fn is_hipaa_critical(&self) -> bool {
@@ -160,6 +229,102 @@ impl AuditEvent {
         ) || self.data_classification == Some(DataClassification::Phi) ||
         self.data_classification == Some(DataClassification::MedicalSensitive)
     }
+
+    /// Events that must be on disk before `log_event` returns: high-risk events, denials and
+    /// security violations are never held in a batch
+    pub fn bypasses_batching(&self) -> bool {
+        self.risk_level >= 4
+            || self.requires_attention
+            || matches!(self.outcome, AuditOutcome::Denied | AuditOutcome::Blocked)
+            || matches!(self.event_type, AuditEventType::SecurityViolationDetected | AuditEventType::IntrusionAttempt)
+    }
+
+    /// Number of events this entry stands for
+    pub fn occurrences(&self) -> u32 {
+        self.coalesced.as_ref().map_or(1, |span| span.count)
+    }
+
+    /// Whether `other` repeats this event: same kind of access by the same user, session and
+    /// address to the same patient and resource
+    fn repeats(&self, other: &AuditEvent) -> bool {
+        self.event_type == other.event_type
+            && self.user_id == other.user_id
+            && self.patient_id == other.patient_id
+            && self.action == other.action
+            && self.outcome == other.outcome
+            && self.resource_type == other.resource_type
+            && self.resource_id == other.resource_id
+            && self.session_id == other.session_id
+            && self.source_ip == other.source_ip
+    }
+
+    /// Fold a repeat into this entry; the entry keeps its own timestamp as the first one
+    fn absorb(&mut self, repeat: &AuditEvent) {
+        let span = self.coalesced.get_or_insert_with(|| CoalescedSpan {
+            count: 1,
+            first_at: self.timestamp,
+            last_at: self.timestamp,
+        });
+        span.count += 1;
+        span.last_at = span.last_at.max(repeat.timestamp);
+    }
+}
+
+/// Collapsing of rapid identical read events into one entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditCoalescingConfig {
+    pub enabled: bool,
+    /// Repeats up to this long after the first event join its entry
+    pub window_ms: u64,
+    /// Event types that may be collapsed
+    pub event_types: Vec<AuditEventType>,
+}
+
+impl Default for AuditCoalescingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_ms: 5_000,
+            event_types: vec![AuditEventType::PatientDataViewed],
+        }
+    }
+}
+
+impl AuditCoalescingConfig {
+    /// Defaults with `AUDIT_COALESCE_ENABLED` and `AUDIT_COALESCE_WINDOW_MS` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("AUDIT_COALESCE_ENABLED") {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => config.enabled = true,
+                "0" | "false" | "no" | "off" => config.enabled = false,
+                _ => warn!("Ignoring invalid AUDIT_COALESCE_ENABLED '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("AUDIT_COALESCE_WINDOW_MS") {
+            match value.trim().parse::<u64>() {
+                Ok(ms) => config.window_ms = ms,
+                Err(_) => warn!("Ignoring invalid AUDIT_COALESCE_WINDOW_MS '{}'", value),
+            }
+        }
+        config
+    }
+
+    /// Whether an event may be collapsed at all. Modifications, creations, deletions, exports,
+    /// failures and anything that bypasses batching always get their own entry.
+    fn accepts(&self, event: &AuditEvent) -> bool {
+        self.enabled
+            && self.window_ms > 0
+            && self.event_types.contains(&event.event_type)
+            && !matches!(event.event_type,
+                AuditEventType::PatientDataModified | AuditEventType::PatientDataDeleted |
+                AuditEventType::PatientDataExported | AuditEventType::PatientDataCreated
+            )
+            && event.outcome == AuditOutcome::Success
+            && event.before_state.is_none()
+            && event.after_state.is_none()
+            && !event.bypasses_batching()
+    }
 }
 
 /// Audit event outcome
@@ -225,6 +390,83 @@ pub struct AuditConfig {
     pub compliance_standards: Vec<String>,
     /// Batch size for event processing (None = immediate processing)
     pub batch_size: Option<usize>,
+    /// Longest a batched event may wait before its batch is flushed, in milliseconds
+    #[serde(default = "default_batch_max_latency_ms")]
+    pub batch_max_latency_ms: u64,
+    /// Gzip each flushed batch as its own member of the log file
+    #[serde(default)]
+    pub compress_batches: bool,
+    /// Also lock every batch in write-once storage (None = disabled)
+    #[serde(default)]
+    pub worm: Option<WormConfig>,
+    /// Collapsing of rapid identical views; only applies to batched events
+    #[serde(default)]
+    pub coalescing: AuditCoalescingConfig,
+    /// Seed whose hash the first entry of a chained log links to
+    #[serde(default = "default_chain_seed")]
+    pub chain_seed: String,
+}
+
+fn default_batch_max_latency_ms() -> u64 {
+    2_000
+}
+
+fn default_chain_seed() -> String {
+    "psypsy-cms-hipaa-audit-chain-v1".to_string()
+}
+
+/// Hash the first entry of a chained log links to
+pub fn chain_genesis_hash(key: &[u8], seed: &str) -> String {
+    use base64::{Engine as _, engine::general_purpose};
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(seed.as_bytes());
+    general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// Chain key file kept beside the log it seals
+fn chain_key_path(log_path: &Path) -> PathBuf {
+    log_path.with_file_name("audit_chain.key")
+}
+
+fn load_or_create_chain_key(path: &Path) -> Result<Vec<u8>, SecurityError> {
+    let failed = |e: std::io::Error| SecurityError::AuditLogFailed {
+        reason: format!("Failed to load audit chain key: {}", e),
+    };
+    match std::fs::read(path) {
+        Ok(key) if key.len() == 32 => Ok(key),
+        Ok(_) => Err(SecurityError::AuditLogFailed { reason: "Audit chain key is corrupt".to_string() }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            use ring::rand::SecureRandom;
+            let mut key = vec![0u8; 32];
+            ring::rand::SystemRandom::new().fill(&mut key).map_err(|_| SecurityError::AuditLogFailed {
+                reason: "Random source unavailable for the audit chain key".to_string(),
+            })?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(failed)?;
+            }
+            std::fs::write(path, &key).map_err(failed)?;
+            Ok(key)
+        }
+        Err(e) => Err(failed(e)),
+    }
+}
+
+/// Most recent file the log was rotated to; rotated names end in a sortable timestamp
+fn latest_rotated_log(log_path: &Path) -> Result<Option<PathBuf>, SecurityError> {
+    let (Some(dir), Some(name)) = (log_path.parent(), log_path.file_name().and_then(|n| n.to_str())) else {
+        return Ok(None);
+    };
+    let prefix = format!("{}.", name);
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(SecurityError::AuditLogFailed { reason: format!("Failed to list audit logs: {}", e) }),
+    };
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().and_then(|n| n.to_str()).map_or(false, |n| n.starts_with(&prefix)))
+        .max())
 }
 
 impl Default for AuditConfig {
@@ -249,10 +491,26 @@ impl Default for AuditConfig {
             encrypt_logs: true,
             compliance_standards: vec!["HIPAA".to_string(), "HITECH".to_string()],
             batch_size: Some(50), // Process events in batches of 50 for efficiency
+            batch_max_latency_ms: default_batch_max_latency_ms(),
+            compress_batches: true,
+            worm: None,
+            coalescing: AuditCoalescingConfig::default(),
+            chain_seed: default_chain_seed(),
         }
     }
 }
 
+impl AuditConfig {
+    /// Defaults with the log kept under the app data directory and WORM/coalescing from the env
+    pub fn for_app_data(app_data_dir: &Path) -> Self {
+        let mut config = Self::default();
+        config.log_file_path = Some(app_data_dir.join("audit").join("hipaa_audit.log"));
+        config.worm = WormConfig::from_env(config.retention_days);
+        config.coalescing = AuditCoalescingConfig::from_env();
+        config
+    }
+}
+
 /// Alert threshold configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AlertThresholds {
@@ -354,8 +612,8 @@ pub enum AlertSeverity {
 pub struct AuditService {
     /// Audit configuration
     config: Arc<RwLock<AuditConfig>>,
-    /// Audit event buffer for batching
-    event_buffer: Arc<Mutex<Vec<AuditEvent>>>,
+    /// Accepted events waiting for the next batch flush
+    event_buffer: Arc<Mutex<PendingBatch>>,
     /// Active audit writers
     writers: Arc<RwLock<HashMap<String, Box<dyn AuditWriter + Send + Sync>>>>,
     /// Event statistics
@@ -364,6 +622,58 @@ pub struct AuditService {
     alerts: Arc<RwLock<HashMap<Uuid, AuditAlert>>>,
     /// Alert handlers
     alert_handlers: Arc<RwLock<Vec<Box<dyn AlertHandler + Send + Sync>>>>,
+    /// Key sealing the hash chain; empty when integrity checking is off
+    chain_key: Arc<Vec<u8>>,
+}
+
+/// Events accepted but not yet written, in sequence order
+#[derive(Debug, Default)]
+struct PendingBatch {
+    events: Vec<AuditEvent>,
+    /// When the oldest pending event was accepted
+    oldest_at: Option<Instant>,
+    next_sequence: u64,
+    /// Hash of the last entry written; None when integrity checking is off
+    chain_head: Option<String>,
+    /// Key the chain is sealed with
+    chain_key: Arc<Vec<u8>>,
+    /// Writer name -> how many leading pending events it already accepted, so a retry after
+    /// one writer failed does not duplicate the batch in the others
+    delivered: HashMap<String, usize>,
+}
+
+/// Result of walking the hash chain of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainVerification {
+    pub entries_checked: usize,
+    /// Index of the first entry whose hash or link does not match; None when the chain is intact
+    pub first_break: Option<usize>,
+    /// Whether the first entry links to the genesis seed. A log continued after rotation
+    /// links to the last entry of the rotated file instead, and only verifies against it.
+    pub anchored_at_genesis: bool,
+}
+
+impl ChainVerification {
+    pub fn is_intact(&self) -> bool {
+        self.first_break.is_none()
+    }
+}
+
+/// Walk the hash chain of entries read in log order. The first entry must link to the
+/// genesis seed or to `predecessor`, the last hash of the file the log was rotated from.
+pub fn verify_entries(entries: &[AuditEvent], key: &[u8], seed: &str, predecessor: Option<&str>) -> ChainVerification {
+    let genesis = chain_genesis_hash(key, seed);
+    let anchored_at_genesis = entries.first().map_or(true, |e| e.prev_hash.as_deref() == Some(genesis.as_str()));
+    let first_break = entries.iter().enumerate().position(|(i, entry)| {
+        let sealed = entry.entry_hash.as_deref() == Some(entry.chain_hash(key).as_str());
+        let linked = match i {
+            0 => anchored_at_genesis || (predecessor.is_some() && entry.prev_hash.as_deref() == predecessor),
+            _ => entry.prev_hash.is_some() && entry.prev_hash == entries[i - 1].entry_hash,
+        };
+        !(sealed && linked)
+    });
+    ChainVerification { entries_checked: entries.len(), first_break, anchored_at_genesis }
 }
 
 /// Audit statistics
@@ -407,6 +717,19 @@ pub trait AuditWriter {
     fn write_event(&mut self, event: &AuditEvent) -> Result<(), SecurityError>;
     fn flush(&mut self) -> Result<(), SecurityError>;
     fn rotate(&mut self) -> Result<(), SecurityError>;
+
+    /// Write events in order as one unit; they must be durable when this returns
+    fn write_batch(&mut self, events: &[AuditEvent]) -> Result<(), SecurityError> {
+        for event in events {
+            self.write_event(event)?;
+        }
+        self.flush()
+    }
+
+    /// Whether the sink could take a write now, checked without writing an entry
+    fn check_writable(&self) -> Result<(), SecurityError> {
+        Ok(())
+    }
 }
 
 /// File-based audit writer
@@ -415,6 +738,8 @@ pub struct FileAuditWriter {
     writer: Option<BufWriter<File>>,
     current_size: u64,
     max_size: u64,
+    /// Write each batch as a separate gzip member
+    compress: bool,
 }
 
 impl FileAuditWriter {
@@ -429,8 +754,16 @@ impl FileAuditWriter {
             writer: None,
             current_size: 0,
             max_size,
+            compress: false,
         })
     }
+
+    /// Compress batches. Concatenated gzip members are still one valid gzip stream, and a
+    /// member is only appended once complete, so earlier batches stay readable after a crash.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
     
     fn ensure_writer(&mut self) -> Result<(), SecurityError> {
         if self.writer.is_none() {
@@ -456,6 +789,9 @@ impl FileAuditWriter {
 
 impl AuditWriter for FileAuditWriter {
     fn write_event(&mut self, event: &AuditEvent) -> Result<(), SecurityError> {
+        if self.compress {
+            return self.write_batch(std::slice::from_ref(event));
+        }
         self.ensure_writer()?;
         
         let event_json = serde_json::to_string(event)
@@ -491,6 +827,58 @@ impl AuditWriter for FileAuditWriter {
         }
         Ok(())
     }
+
+    fn check_writable(&self) -> Result<(), SecurityError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file_path)
+            .map(drop)
+            .map_err(|e| SecurityError::AuditLogFailed {
+                reason: format!("Log file {} is not writable: {}", self.file_path.display(), e)
+            })
+    }
+
+    fn write_batch(&mut self, events: &[AuditEvent]) -> Result<(), SecurityError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.ensure_writer()?;
+
+        let mut lines = Vec::new();
+        for event in events {
+            serde_json::to_writer(&mut lines, event)
+                .map_err(|e| SecurityError::AuditLogFailed { 
+                    reason: format!("Failed to serialize event: {}", e) 
+                })?;
+            lines.push(b'\n');
+        }
+        let bytes = if self.compress {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&lines)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| SecurityError::AuditLogFailed { 
+                    reason: format!("Failed to compress audit batch: {}", e) 
+                })?
+        } else {
+            lines
+        };
+
+        if let Some(writer) = &mut self.writer {
+            writer.write_all(&bytes)
+                .and_then(|_| writer.flush())
+                .and_then(|_| writer.get_ref().sync_data())
+                .map_err(|e| SecurityError::AuditLogFailed { 
+                    reason: format!("Failed to write audit batch: {}", e) 
+                })?;
+            self.current_size += bytes.len() as u64;
+        }
+
+        if self.current_size >= self.max_size {
+            self.rotate()?;
+        }
+        Ok(())
+    }
     
     fn rotate(&mut self) -> Result<(), SecurityError> {
         // Close current writer
@@ -543,13 +931,18 @@ impl AlertHandler for ConsoleAlertHandler {
 impl AuditService {
     /// Create new audit service
     pub fn new(config: AuditConfig) -> Result<Self, SecurityError> {
+        let chain_key = match &config.log_file_path {
+            Some(log_path) if config.enable_integrity_checking => load_or_create_chain_key(&chain_key_path(log_path))?,
+            _ => Vec::new(),
+        };
         let service = Self {
             config: Arc::new(RwLock::new(config)),
-            event_buffer: Arc::new(Mutex::new(Vec::new())),
+            event_buffer: Arc::new(Mutex::new(PendingBatch::default())),
             writers: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(AuditStats::default())),
             alerts: Arc::new(RwLock::new(HashMap::new())),
             alert_handlers: Arc::new(RwLock::new(Vec::new())),
+            chain_key: Arc::new(chain_key),
         };
         
         // Initialize default alert handler
@@ -560,17 +953,105 @@ impl AuditService {
             let config = service.config.read().unwrap();
             if config.storage_type == "file" {
                 if let Some(log_path) = &config.log_file_path {
-                    let writer = FileAuditWriter::new(log_path.clone(), config.max_file_size_bytes)?;
+                    let writer = FileAuditWriter::new(log_path.clone(), config.max_file_size_bytes)?
+                        .with_compression(config.compress_batches);
                     service.writers.write().unwrap().insert("file".to_string(), Box::new(writer));
+
+                    // Continue the sequence and hash chain of an existing log
+                    let mut pending = service.event_buffer.try_lock().expect("new audit buffer is uncontended");
+                    let last = read_audit_entries(log_path)?.pop();
+                    if let Some(last) = &last {
+                        pending.next_sequence = last.sequence + 1;
+                    }
+                    if config.enable_integrity_checking {
+                        pending.chain_head = Some(
+                            last.and_then(|e| e.entry_hash)
+                                .unwrap_or_else(|| chain_genesis_hash(&service.chain_key, &config.chain_seed))
+                        );
+                        pending.chain_key = service.chain_key.clone();
+                    }
                 }
             }
+            // WORM failures alert instead of being absorbed by the mutable sinks
+            if let Some(worm) = &config.worm {
+                let writer = WormAuditWriter::new(worm.clone(), service.worm_alert_hook())?;
+                service.writers.write().unwrap().insert("worm".to_string(), Box::new(writer));
+                audit_worm::spawn_worm_uploader(worm.clone(), service.worm_alert_hook());
+            }
         } // config borrow is dropped here
 
+        service.spawn_latency_flusher();
         Ok(service)
     }
+
+    /// Alert hook for the WORM sink; holds weak references so it never keeps the service alive
+    fn worm_alert_hook(&self) -> WormAlertHook {
+        let alerts = Arc::downgrade(&self.alerts);
+        let handlers = Arc::downgrade(&self.alert_handlers);
+        let stats = Arc::downgrade(&self.stats);
+        Arc::new(move |description: String| {
+            error!("WORM audit storage failure: {}", description);
+            if let (Some(alerts), Some(handlers), Some(stats)) = (alerts.upgrade(), handlers.upgrade(), stats.upgrade()) {
+                Self::dispatch_alert(&alerts, &handlers, &stats, AuditAlert {
+                    alert_id: Uuid::new_v4(),
+                    severity: AlertSeverity::Emergency,
+                    title: "WORM Audit Storage Failure".to_string(),
+                    description,
+                    related_events: Vec::new(),
+                    timestamp: Utc::now(),
+                    acknowledged: false,
+                    acknowledged_by: None,
+                    acknowledged_at: None,
+                    metadata: HashMap::new(),
+                });
+            }
+        })
+    }
+
+    /// Store an alert and pass it to every handler
+    fn dispatch_alert(
+        alerts: &RwLock<HashMap<Uuid, AuditAlert>>,
+        handlers: &RwLock<Vec<Box<dyn AlertHandler + Send + Sync>>>,
+        stats: &RwLock<AuditStats>,
+        alert: AuditAlert,
+    ) {
+        alerts.write().unwrap().insert(alert.alert_id, alert.clone());
+        for handler in handlers.read().unwrap().iter() {
+            if let Err(e) = handler.handle_alert(&alert) {
+                error!("Alert handler failed: {:?}", e);
+            }
+        }
+        stats.write().unwrap().active_alerts += 1;
+    }
+
+    /// Flush batches whose oldest event has waited `batch_max_latency_ms`. Runs while the
+    /// service is alive when it was created inside a Tokio runtime.
+    fn spawn_latency_flusher(&self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let max_latency = std::time::Duration::from_millis(self.config.read().unwrap().batch_max_latency_ms.max(1));
+        let buffer = Arc::downgrade(&self.event_buffer);
+        let writers = Arc::downgrade(&self.writers);
+        runtime.spawn(async move {
+            let mut ticker = tokio::time::interval((max_latency / 4).max(std::time::Duration::from_millis(10)));
+            loop {
+                ticker.tick().await;
+                let (Some(buffer), Some(writers)) = (buffer.upgrade(), writers.upgrade()) else {
+                    break;
+                };
+                let mut pending = buffer.lock().await;
+                if pending.oldest_at.map_or(false, |t| t.elapsed() >= max_latency) {
+                    if let Err(e) = Self::write_pending(&mut pending, &writers, None) {
+                        error!("Failed to flush audit batch: {:?}", e);
+                    }
+                }
+            }
+        });
+    }
     
     /// Log audit event
-    pub async fn log_event(&self, event: AuditEvent) -> Result<(), SecurityError> {
+    pub async fn log_event(&self, mut event: AuditEvent) -> Result<(), SecurityError> {
         // Update statistics
         {
             let mut stats = self.stats.write().unwrap();
@@ -593,40 +1074,6 @@ impl AuditService {
         // Check for alert conditions
         self.check_alert_conditions(&event).await?;
 
-        // Add event to buffer for batch processing
-        {
-            let mut buffer = self.event_buffer.lock().await;
-            buffer.push(event.clone());
-
-            // Check if buffer needs to be flushed (configurable batch size)
-            let config = self.config.read().unwrap();
-            let batch_size = config.batch_size.unwrap_or(100);
-
-            if buffer.len() >= batch_size {
-                // Process buffer in background
-                let events_to_process = buffer.clone();
-                buffer.clear();
-
-                // Spawn background task to process events
-                let writers_clone = self.writers.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = Self::process_event_batch(events_to_process, writers_clone).await {
-                        error!("Failed to process audit event batch: {:?}", e);
-                    }
-                });
-            }
-        }
-
-        // Write event to all configured writers (immediate processing for critical events)
-        if event.risk_level >= 4 || event.is_hipaa_critical() {
-            let mut writers = self.writers.write().unwrap();
-            for (name, writer) in writers.iter_mut() {
-                if let Err(e) = writer.write_event(&event) {
-                    error!("Failed to write to audit writer {}: {:?}", name, e);
-                }
-            }
-        }
-        
         // Log to tracing system based on risk level
         match event.risk_level {
             5 => error!("High-risk audit event: {} - {}", event.action, event.description),
@@ -635,43 +1082,147 @@ impl AuditService {
             2 => info!("Audit event: {} - {}", event.action, event.description),
             _ => debug!("Audit event: {} - {}", event.action, event.description),
         }
-        
+
+        // Batch the event; mandatory events and unbatched configs are written before returning
+        {
+            let mut pending = self.event_buffer.lock().await;
+            let (batch_size, max_latency_ms, coalescing) = {
+                let config = self.config.read().unwrap();
+                (config.batch_size, config.batch_max_latency_ms, config.coalescing.clone())
+            };
+            if batch_size.is_some() && Self::coalesce_into_pending(&mut pending, &event, &coalescing) {
+                return Ok(());
+            }
+
+            event.sequence = pending.next_sequence;
+            pending.next_sequence += 1;
+            match batch_size {
+                Some(batch_size) if !event.bypasses_batching() => {
+                    pending.events.push(event);
+                    let oldest_at = *pending.oldest_at.get_or_insert_with(Instant::now);
+                    let overdue = oldest_at.elapsed().as_millis() >= u128::from(max_latency_ms);
+                    if overdue || pending.events.len() >= batch_size {
+                        Self::write_pending(&mut pending, &self.writers, None)?;
+                    }
+                }
+                // Written together with anything pending ahead of it so the order holds
+                _ => Self::write_pending(&mut pending, &self.writers, Some(event))?,
+            }
+        }
+
         Ok(())
     }
 
-    /// Process a batch of audit events
-    async fn process_event_batch(
-        events: Vec<AuditEvent>,
-        writers: Arc<RwLock<HashMap<String, Box<dyn AuditWriter + Send + Sync>>>>
+    /// Fold a repeat into the latest pending entry for the same user and patient. Only the
+    /// latest one is considered, so a modification in between always starts a new entry; and
+    /// entries already written are never touched, so the collapsed entry is chained like any other.
+    fn coalesce_into_pending(pending: &mut PendingBatch, event: &AuditEvent, config: &AuditCoalescingConfig) -> bool {
+        if !config.accepts(event) {
+            return false;
+        }
+        let Some(latest) = pending
+            .events
+            .iter_mut()
+            .rev()
+            .find(|e| e.user_id == event.user_id && e.patient_id == event.patient_id)
+        else {
+            return false;
+        };
+        let first_at = latest.coalesced.as_ref().map_or(latest.timestamp, |span| span.first_at);
+        let within_window = event.timestamp >= first_at
+            && event.timestamp - first_at <= Duration::milliseconds(config.window_ms as i64);
+        if !config.accepts(latest) || !latest.repeats(event) || !within_window {
+            return false;
+        }
+        latest.absorb(event);
+        true
+    }
+
+    /// Write pending events, then `extra`, as one batch to every writer. On failure the events
+    /// stay pending in order and are retried with the next flush, sent only to the writers
+    /// that have not yet accepted them.
+    fn write_pending(
+        pending: &mut PendingBatch,
+        writers: &RwLock<HashMap<String, Box<dyn AuditWriter + Send + Sync>>>,
+        extra: Option<AuditEvent>,
     ) -> Result<(), SecurityError> {
-        let mut writers = writers.write().unwrap();
+        let mut events = std::mem::take(&mut pending.events);
+        events.extend(extra);
+        if events.is_empty() {
+            return Ok(());
+        }
+        // Sealed at write time, so coalescing never changes an entry after it is hashed
+        let mut chain_head = pending.chain_head.clone();
+        if let Some(head) = chain_head.as_mut() {
+            for event in events.iter_mut() {
+                event.seal(std::mem::take(head), &pending.chain_key);
+                *head = event.entry_hash.clone().unwrap_or_default();
+            }
+        }
 
-        for event in events {
+        let mut result = Ok(());
+        {
+            let mut writers = writers.write().unwrap();
             for (name, writer) in writers.iter_mut() {
-                if let Err(e) = writer.write_event(&event) {
-                    error!("Failed to write to audit writer {} in batch: {:?}", name, e);
+                let done = pending.delivered.get(name).copied().unwrap_or(0).min(events.len());
+                if done == events.len() {
+                    continue;
+                }
+                match writer.write_batch(&events[done..]) {
+                    Ok(()) => {
+                        pending.delivered.insert(name.clone(), events.len());
+                    }
+                    Err(e) => {
+                        error!("Failed to write audit batch to writer {}: {:?}", name, e);
+                        result = Err(e);
+                    }
                 }
             }
         }
 
-        Ok(())
+        match result {
+            Ok(()) => {
+                pending.oldest_at = None;
+                pending.chain_head = chain_head;
+                pending.delivered.clear();
+            }
+            Err(_) => {
+                pending.events = events;
+                pending.oldest_at.get_or_insert_with(Instant::now);
+            }
+        }
+        result
     }
 
     /// Flush all pending events in buffer (should be called on shutdown)
     pub async fn flush_pending_events(&self) -> Result<(), SecurityError> {
-        let events_to_process = {
-            let mut buffer = self.event_buffer.lock().await;
-            let events = buffer.clone();
-            buffer.clear();
-            events
-        };
-
-        if !events_to_process.is_empty() {
-            info!("Flushing {} pending audit events", events_to_process.len());
-            Self::process_event_batch(events_to_process, self.writers.clone()).await?;
+        let mut pending = self.event_buffer.lock().await;
+        if !pending.events.is_empty() {
+            info!("Flushing {} pending audit events", pending.events.len());
         }
+        Self::write_pending(&mut pending, &self.writers, None)
+    }
 
-        Ok(())
+    /// Walk the hash chain of the on-disk log and report the first entry that was altered,
+    /// removed or inserted. A log continued after rotation is checked against the last entry
+    /// of the newest rotated file. Events still pending a batch flush are not yet chained.
+    pub fn verify_chain(&self) -> Result<ChainVerification, SecurityError> {
+        let (log_path, seed) = {
+            let config = self.config.read().unwrap();
+            let log_path = config.log_file_path.clone().ok_or_else(|| SecurityError::AuditLogFailed {
+                reason: "No audit log file configured".to_string(),
+            })?;
+            (log_path, config.chain_seed.clone())
+        };
+        let predecessor = match latest_rotated_log(&log_path)? {
+            Some(rotated) => read_audit_entries(&rotated)?.pop().and_then(|e| e.entry_hash),
+            None => None,
+        };
+        let verification = verify_entries(&read_audit_entries(&log_path)?, &self.chain_key, &seed, predecessor.as_deref());
+        if let Some(index) = verification.first_break {
+            error!("Audit log hash chain broken at entry {} of {:?}", index, log_path);
+        }
+        Ok(verification)
     }
 
     /// Check for alert conditions
@@ -733,25 +1284,15 @@ impl AuditService {
                 metadata: HashMap::new(),
             };
             
-            // Store alert
-            self.alerts.write().unwrap().insert(alert.alert_id, alert.clone());
-            
-            // Send to alert handlers
-            let handlers = self.alert_handlers.read().unwrap();
-            for handler in handlers.iter() {
-                if let Err(e) = handler.handle_alert(&alert) {
-                    error!("Alert handler failed: {:?}", e);
-                }
-            }
-            
-            self.stats.write().unwrap().active_alerts += 1;
+            Self::dispatch_alert(&self.alerts, &self.alert_handlers, &self.stats, alert);
         }
         
         Ok(())
     }
     
-    /// Flush all audit writers
+    /// Write out pending batches and flush all audit writers
     pub async fn flush(&self) -> Result<(), SecurityError> {
+        self.flush_pending_events().await?;
         let mut writers = self.writers.write().unwrap();
         for (name, writer) in writers.iter_mut() {
             if let Err(e) = writer.flush() {
@@ -761,6 +1302,26 @@ impl AuditService {
         Ok(())
     }
     
+    /// Check every writer can take a write, without adding an entry to the trail
+    pub fn check_writable(&self) -> Result<(), SecurityError> {
+        let writers = self.writers.read().unwrap_or_else(|e| e.into_inner());
+        if writers.is_empty() {
+            return Err(SecurityError::AuditLogFailed { reason: "No audit writer is configured".to_string() });
+        }
+        for (name, writer) in writers.iter() {
+            writer.check_writable().map_err(|e| SecurityError::AuditLogFailed {
+                reason: format!("Audit writer {}: {}", name, e),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// File the service writes to, when it is file-backed
+    pub fn log_file_path(&self) -> Option<PathBuf> {
+        let config = self.config.read().unwrap();
+        if config.storage_type == "file" { config.log_file_path.clone() } else { None }
+    }
+
     /// Get audit statistics
     pub fn get_stats(&self) -> AuditStats {
         self.stats.read().unwrap().clone()
@@ -839,9 +1400,257 @@ impl AuditService {
     }
 }
 
+impl Drop for AuditService {
+    /// Shutdown flush for events still waiting in a batch
+    fn drop(&mut self) {
+        if let Ok(mut pending) = self.event_buffer.try_lock() {
+            if let Err(e) = Self::write_pending(&mut pending, &self.writers, None) {
+                error!("Failed to flush audit events on shutdown: {:?}", e);
+            }
+        }
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Read every event from an audit log in write order. Plain JSON lines and gzip batches may
+/// be mixed; a damaged batch (e.g. cut short by a crash) is skipped and reading resumes at
+/// the next one.
+pub fn read_audit_entries(path: &Path) -> Result<Vec<AuditEvent>, SecurityError> {
+    let mut events = Vec::new();
+    for_each_audit_entry(path, |event| {
+        events.push(event);
+        true
+    })?;
+    Ok(events)
+}
+
+/// Stream the events of an audit log in write order without loading the file, stopping as
+/// soon as `visit` returns false. Damaged batches are skipped as in `read_audit_entries`.
+pub fn for_each_audit_entry(path: &Path, mut visit: impl FnMut(AuditEvent) -> bool) -> Result<(), SecurityError> {
+    use std::io::BufRead;
+
+    if !path.exists() {
+        return Ok(());
+    }
+    let read_failed = |e: std::io::Error| SecurityError::AuditLogFailed {
+        reason: format!("Failed to read audit log: {}", e),
+    };
+    let mut reader = std::io::BufReader::new(File::open(path).map_err(read_failed)?);
+    let mut line = Vec::new();
+    loop {
+        let Some(&first) = reader.fill_buf().map_err(read_failed)?.first() else {
+            return Ok(());
+        };
+        // JSON lines start with '{', so a leading 0x1f is always a gzip batch
+        if first == GZIP_MAGIC[0] {
+            let mut batch = std::io::BufReader::new(flate2::bufread::GzDecoder::new(&mut reader));
+            loop {
+                line.clear();
+                match batch.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if let Ok(event) = serde_json::from_slice::<AuditEvent>(&line) {
+                            if !visit(event) {
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Skipping damaged audit batch in {:?}: {}", path, e);
+                        drop(batch);
+                        skip_to_next_batch(&mut reader).map_err(read_failed)?;
+                        break;
+                    }
+                }
+            }
+        } else {
+            line.clear();
+            reader.read_until(b'\n', &mut line).map_err(read_failed)?;
+            if let Ok(event) = serde_json::from_str::<AuditEvent>(String::from_utf8_lossy(&line).trim()) {
+                if !visit(event) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Advance past a damaged batch to the next gzip header, or to the end of the log
+fn skip_to_next_batch(reader: &mut impl std::io::BufRead) -> std::io::Result<()> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        match buf.iter().position(|&b| b == GZIP_MAGIC[0]) {
+            // A header split across buffer refills is taken on trust; a false match is just
+            // another damaged batch
+            Some(i) if buf.get(i + 1).map_or(true, |&b| b == GZIP_MAGIC[1]) => {
+                reader.consume(i);
+                return Ok(());
+            }
+            Some(i) => reader.consume(i + 1),
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// Most audit records returned by one query
+pub const MAX_AUDIT_QUERY_ROWS: usize = 500;
+
+/// Structured filter over the audit log; every criterion given must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    pub user_id: Option<Uuid>,
+    pub patient_id: Option<Uuid>,
+    /// Any of these types; empty matches every type
+    #[serde(default)]
+    pub event_type: Vec<AuditEventType>,
+    /// Inclusive lower bound
+    pub from: Option<DateTime<Utc>>,
+    /// Inclusive upper bound
+    pub to: Option<DateTime<Utc>>,
+    pub outcome: Option<AuditOutcome>,
+    /// Page size, capped at `MAX_AUDIT_QUERY_ROWS`
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// One page of matching audit records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQueryPage {
+    pub events: Vec<AuditEvent>,
+    /// Whether further records match beyond this page
+    pub has_more: bool,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.user_id.map_or(true, |id| event.user_id == Some(id))
+            && self.patient_id.map_or(true, |id| event.patient_id == Some(id))
+            && (self.event_type.is_empty() || self.event_type.contains(&event.event_type))
+            && self.from.map_or(true, |from| event.timestamp >= from)
+            && self.to.map_or(true, |to| event.timestamp <= to)
+            && self.outcome.as_ref().map_or(true, |outcome| &event.outcome == outcome)
+    }
+
+    /// Page of matching records in log order. Stops reading once the page is full and one
+    /// further match shows whether there are more.
+    pub fn page(&self, events: impl IntoIterator<Item = AuditEvent>) -> AuditQueryPage {
+        let mut collector = self.collector();
+        for event in events {
+            if !collector.push(event) {
+                break;
+            }
+        }
+        collector.finish()
+    }
+
+    /// Page of matching records read straight from an audit log, without loading it whole
+    pub fn page_from_log(&self, path: &Path) -> Result<AuditQueryPage, SecurityError> {
+        let mut collector = self.collector();
+        for_each_audit_entry(path, |event| collector.push(event))?;
+        Ok(collector.finish())
+    }
+
+    fn collector(&self) -> PageCollector<'_> {
+        PageCollector {
+            query: self,
+            limit: self.limit.unwrap_or(100).clamp(1, MAX_AUDIT_QUERY_ROWS),
+            skipped: 0,
+            events: Vec::new(),
+            has_more: false,
+        }
+    }
+}
+
+/// Matching records gathered for one page
+struct PageCollector<'a> {
+    query: &'a AuditQuery,
+    limit: usize,
+    skipped: usize,
+    events: Vec<AuditEvent>,
+    has_more: bool,
+}
+
+impl PageCollector<'_> {
+    /// Take an event; false once the page is full and a further match has been seen
+    fn push(&mut self, event: AuditEvent) -> bool {
+        if !self.query.matches(&event) {
+            return true;
+        }
+        if self.skipped < self.query.offset {
+            self.skipped += 1;
+        } else if self.events.len() < self.limit {
+            self.events.push(event);
+        } else {
+            self.has_more = true;
+            return false;
+        }
+        true
+    }
+
+    fn finish(self) -> AuditQueryPage {
+        AuditQueryPage {
+            events: self.events,
+            has_more: self.has_more,
+            limit: self.limit,
+            offset: self.query.offset,
+        }
+    }
+}
+
+/// Audit service as managed Tauri state
+pub struct AuditServiceState(pub Arc<AuditService>);
+
+static AUDIT_SERVICE: OnceCell<Arc<AuditService>> = OnceCell::new();
+
+/// Install the process-wide audit service that command-level events are written to
+pub fn install_audit_service(service: Arc<AuditService>) -> Result<(), SecurityError> {
+    AUDIT_SERVICE.set(service).map_err(|_| SecurityError::ConfigurationError {
+        reason: "Audit service already installed".to_string(),
+    })
+}
+
+/// Process-wide audit service, if the app installed one
+pub fn audit_service() -> Option<Arc<AuditService>> {
+    AUDIT_SERVICE.get().cloned()
+}
+
+static PHI_ACCESS_LISTENER: OnceCell<tokio::sync::mpsc::UnboundedSender<AuditEvent>> = OnceCell::new();
+
+/// Receive every granted access written through `log_phi_access` from now on; there is one
+/// listener per process
+pub fn subscribe_phi_access() -> Result<tokio::sync::mpsc::UnboundedReceiver<AuditEvent>, SecurityError> {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    PHI_ACCESS_LISTENER.set(sender).map_err(|_| SecurityError::ConfigurationError {
+        reason: "PHI access listener already installed".to_string(),
+    })?;
+    Ok(receiver)
+}
+
+/// Log file of the installed audit service, falling back to the default location
+pub fn audit_log_path() -> Option<PathBuf> {
+    match audit_service() {
+        Some(service) => service.log_file_path(),
+        None => AuditConfig::default().log_file_path,
+    }
+}
+
 /// Initialize HIPAA audit system
 pub async fn initialize_audit_system() -> Result<(), SecurityError> {
-    let config = AuditConfig::default();
+    let mut config = AuditConfig::default();
+    config.worm = WormConfig::from_env(config.retention_days);
+    config.coalescing = AuditCoalescingConfig::from_env();
     let audit_service = AuditService::new(config)?;
     
     // Test audit logging with a system startup event
@@ -865,24 +1674,41 @@ pub async fn initialize_audit_system() -> Result<(), SecurityError> {
 
 /// Convenience functions for common audit events
 
-/// Log PHI access event
+/// Log PHI access event for a client record, with the stated reason for access when one was
+/// given. Granted accesses are passed on to the `subscribe_phi_access` listener.
+#[allow(clippy::too_many_arguments)]
 pub async fn log_phi_access(
     audit_service: &AuditService,
     user_id: Uuid,
-    patient_id: Uuid,
+    user_role: Option<HealthcareRole>,
+    client_id: &str,
     action: &str,
     outcome: AuditOutcome,
     session_id: String,
+    justification: Option<&str>,
 ) -> Result<(), SecurityError> {
-    let event = AuditEvent::new(
+    // Client IDs that are not UUIDs are keyed by their stable UUID; the ID itself is the resource
+    let patient_id = Uuid::parse_str(client_id).unwrap_or_else(|_| crate::security::rbac::stable_uuid(client_id));
+    let mut event = AuditEvent::new(
         AuditEventType::PatientDataViewed,
         Some(user_id),
         action.to_string(),
         outcome,
     ).with_phi_access(patient_id, "patient_record")
     .with_session(session_id, None, None);
-    
-    audit_service.log_event(event).await
+    event.user_role = user_role;
+    event.resource_id = Some(client_id.to_string());
+    if let Some(justification) = justification {
+        event.metadata.insert("justification".to_string(), serde_json::Value::String(justification.to_string()));
+    }
+
+    let granted = (outcome == AuditOutcome::Success).then(|| event.clone());
+    audit_service.log_event(event).await?;
+    if let (Some(event), Some(listener)) = (granted, PHI_ACCESS_LISTENER.get()) {
+        // The receiver only goes away at shutdown
+        let _ = listener.send(event);
+    }
+    Ok(())
 }
 
 /// Log authentication event
@@ -1012,6 +1838,305 @@ mod tests {
         
         assert!(log_path.exists());
     }
+
+    #[test]
+    fn test_writability_check_adds_no_entry() {
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join("probe_audit.log");
+        let mut config = AuditConfig::default();
+        config.log_file_path = Some(log_path.clone());
+        config.enable_real_time_alerts = false;
+        let audit_service = AuditService::new(config).unwrap();
+
+        audit_service.check_writable().unwrap();
+        assert_eq!(audit_service.get_stats().total_events, 0);
+        assert!(std::fs::read_to_string(&log_path).unwrap_or_default().is_empty());
+
+        // A sink that cannot be opened for append is reported
+        std::fs::remove_file(&log_path).ok();
+        std::fs::create_dir(&log_path).unwrap();
+        assert!(audit_service.check_writable().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledged_batches_survive_crash_in_order() {
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join("batched_audit.log");
+
+        let mut config = AuditConfig::default();
+        config.log_file_path = Some(log_path.clone());
+        config.enable_real_time_alerts = false;
+        config.batch_size = Some(3);
+        config.batch_max_latency_ms = 60_000;
+        config.compress_batches = true;
+        let audit_service = AuditService::new(config).unwrap();
+
+        let view = |action: &str| AuditEvent::new(
+            AuditEventType::PatientDataViewed,
+            Some(Uuid::new_v4()),
+            action.to_string(),
+            AuditOutcome::Success,
+        ).with_phi_access(Uuid::new_v4(), "patient_record");
+
+        audit_service.log_event(view("view_0")).await.unwrap();
+        audit_service.log_event(view("view_1")).await.unwrap();
+        // Mandatory event: written at once, behind the two views already accepted
+        let denied = AuditEvent::new(
+            AuditEventType::PatientDataExported,
+            Some(Uuid::new_v4()),
+            "export_denied".to_string(),
+            AuditOutcome::Denied,
+        );
+        audit_service.log_event(denied).await.unwrap();
+        for action in ["view_2", "view_3", "view_4"] {
+            audit_service.log_event(view(action)).await.unwrap();
+        }
+        audit_service.log_event(view("view_unflushed")).await.unwrap();
+
+        // Crash: no shutdown flush runs
+        std::mem::forget(audit_service);
+
+        let raw = std::fs::read(&log_path).unwrap();
+        assert!(raw.starts_with(&GZIP_MAGIC));
+        let events = read_audit_entries(&log_path).unwrap();
+        let actions: Vec<&str> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["view_0", "view_1", "export_denied", "view_2", "view_3", "view_4"]);
+        assert!(events.iter().enumerate().all(|(i, e)| e.sequence == i as u64));
+    }
+
+    #[tokio::test]
+    async fn test_rapid_views_coalesce_but_modifications_never_merge() {
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join("coalesced_audit.log");
+
+        let mut config = AuditConfig::default();
+        config.log_file_path = Some(log_path.clone());
+        config.enable_real_time_alerts = false;
+        config.batch_size = Some(100);
+        config.batch_max_latency_ms = 60_000;
+        let audit_service = AuditService::new(config).unwrap();
+
+        let user = Uuid::new_v4();
+        let patient = Uuid::new_v4();
+        let event = |event_type: AuditEventType, action: &str| AuditEvent::new(
+            event_type,
+            Some(user),
+            action.to_string(),
+            AuditOutcome::Success,
+        ).with_phi_access(patient, "patient_record");
+
+        for _ in 0..3 {
+            audit_service.log_event(event(AuditEventType::PatientDataViewed, "view_chart")).await.unwrap();
+        }
+        audit_service.log_event(event(AuditEventType::PatientDataModified, "view_chart")).await.unwrap();
+        for _ in 0..2 {
+            audit_service.log_event(event(AuditEventType::PatientDataViewed, "view_chart")).await.unwrap();
+        }
+        audit_service.flush().await.unwrap();
+
+        let events = read_audit_entries(&log_path).unwrap();
+        let entries: Vec<(AuditEventType, u32)> = events.iter().map(|e| (e.event_type.clone(), e.occurrences())).collect();
+        assert_eq!(entries, [
+            (AuditEventType::PatientDataViewed, 3),
+            (AuditEventType::PatientDataModified, 1),
+            (AuditEventType::PatientDataViewed, 2),
+        ]);
+        let span = events[0].coalesced.as_ref().unwrap();
+        assert_eq!(span.first_at, events[0].timestamp);
+        assert!(span.last_at >= span.first_at);
+        assert!(events.iter().enumerate().all(|(i, e)| e.sequence == i as u64));
+    }
+
+    #[test]
+    fn test_coalescing_refuses_modifications_even_when_configured() {
+        let config = AuditCoalescingConfig {
+            event_types: vec![AuditEventType::PatientDataViewed, AuditEventType::PatientDataModified],
+            ..AuditCoalescingConfig::default()
+        };
+        let user = Some(Uuid::new_v4());
+        let view = AuditEvent::new(AuditEventType::PatientDataViewed, user, "chart".to_string(), AuditOutcome::Success);
+        let modify = AuditEvent::new(AuditEventType::PatientDataModified, user, "chart".to_string(), AuditOutcome::Success);
+        let denied = AuditEvent::new(AuditEventType::PatientDataViewed, user, "chart".to_string(), AuditOutcome::Denied);
+        assert!(config.accepts(&view));
+        assert!(!config.accepts(&modify));
+        assert!(!config.accepts(&denied));
+
+        let mut pending = PendingBatch::default();
+        pending.events.push(modify);
+        assert!(!AuditService::coalesce_into_pending(&mut pending, &view, &config));
+    }
+
+    #[tokio::test]
+    async fn test_verify_chain_flags_the_edited_entry() {
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join("chained_audit.log");
+
+        let mut config = AuditConfig::default();
+        config.log_file_path = Some(log_path.clone());
+        config.enable_real_time_alerts = false;
+        config.batch_size = None;
+        config.compress_batches = false;
+        let audit_service = AuditService::new(config).unwrap();
+
+        let user = Uuid::new_v4();
+        log_authentication(&audit_service, Some(user), AuditEventType::UserLogin, AuditOutcome::Success, None, None).await.unwrap();
+        for _ in 0..3 {
+            log_phi_access(&audit_service, user, None, &Uuid::new_v4().to_string(), "view_client", AuditOutcome::Success, "s".to_string(), None).await.unwrap();
+        }
+        log_authentication(&audit_service, Some(user), AuditEventType::UserLogout, AuditOutcome::Success, None, None).await.unwrap();
+        let verification = audit_service.verify_chain().unwrap();
+        assert_eq!(verification.entries_checked, 5);
+        assert!(verification.is_intact());
+        assert!(verification.anchored_at_genesis);
+
+        // Quietly change who was looked at in the third entry
+        let content = std::fs::read_to_string(&log_path).unwrap();
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let mut edited: AuditEvent = serde_json::from_str(&lines[2]).unwrap();
+        edited.patient_id = Some(Uuid::new_v4());
+        lines[2] = serde_json::to_string(&edited).unwrap();
+        std::fs::write(&log_path, lines.join("\n") + "\n").unwrap();
+
+        assert_eq!(audit_service.verify_chain().unwrap().first_break, Some(2));
+    }
+
+    #[test]
+    fn test_first_entry_must_link_to_genesis_or_the_rotated_log() {
+        let key = [7u8; 32];
+        let seed = default_chain_seed();
+        let sealed = |prev: &str, sequence: u64, key: &[u8]| {
+            let mut event = AuditEvent::new(AuditEventType::SystemEvent, None, "test".to_string(), AuditOutcome::Success);
+            event.sequence = sequence;
+            event.seal(prev.to_string(), key);
+            event
+        };
+
+        let rotated_tail = sealed(&chain_genesis_hash(&key, &seed), 0, &key);
+        let continued = vec![sealed(rotated_tail.entry_hash.as_deref().unwrap(), 1, &key)];
+        assert!(verify_entries(&continued, &key, &seed, rotated_tail.entry_hash.as_deref()).is_intact());
+
+        // A truncated log that starts mid-chain no longer passes on its own
+        assert_eq!(verify_entries(&continued, &key, &seed, None).first_break, Some(0));
+
+        // Nor does a chain resealed from the public seed without the key
+        let forged = vec![sealed(&chain_genesis_hash(&[0u8; 32], &seed), 0, &[0u8; 32])];
+        assert_eq!(verify_entries(&forged, &[0u8; 32], &seed, None).first_break, None);
+        assert_eq!(verify_entries(&forged, &key, &seed, None).first_break, Some(0));
+    }
+
+    #[test]
+    fn test_audit_query_bounds_are_inclusive_and_pages_capped() {
+        let start = DateTime::parse_from_rfc3339("2026-05-01T09:00:00Z").unwrap().with_timezone(&Utc);
+        let user = Uuid::new_v4();
+        let events: Vec<AuditEvent> = (0..600)
+            .map(|i| {
+                let mut event = AuditEvent::new(AuditEventType::PatientDataViewed, Some(user), "view".to_string(), AuditOutcome::Success);
+                event.timestamp = start + Duration::seconds(i);
+                event
+            })
+            .collect();
+
+        let query = AuditQuery {
+            user_id: Some(user),
+            from: Some(start + Duration::seconds(10)),
+            to: Some(start + Duration::seconds(20)),
+            ..AuditQuery::default()
+        };
+        let page = query.page(events.clone());
+        assert_eq!((page.events.len(), page.has_more), (11, false));
+        assert_eq!(page.events.first().unwrap().timestamp, start + Duration::seconds(10));
+        assert_eq!(page.events.last().unwrap().timestamp, start + Duration::seconds(20));
+
+        let page = AuditQuery { limit: Some(10_000), offset: 50, ..AuditQuery::default() }.page(events.clone());
+        assert_eq!((page.events.len(), page.limit, page.has_more), (MAX_AUDIT_QUERY_ROWS, MAX_AUDIT_QUERY_ROWS, true));
+        let last = AuditQuery { limit: Some(100), offset: 550, ..AuditQuery::default() }.page(events.clone());
+        assert_eq!((last.events.len(), last.has_more), (50, false));
+        let denied = AuditQuery { outcome: Some(AuditOutcome::Denied), ..AuditQuery::default() }.page(events);
+        assert!(denied.events.is_empty() && !denied.has_more);
+    }
+
+    #[test]
+    fn test_damaged_trailing_batch_keeps_earlier_entries() {
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join("torn_audit.log");
+        let mut writer = FileAuditWriter::new(log_path.clone(), u64::MAX).unwrap().with_compression(true);
+
+        let event = |action: &str| AuditEvent::new(
+            AuditEventType::UserLogin,
+            Some(Uuid::new_v4()),
+            action.to_string(),
+            AuditOutcome::Success,
+        );
+        writer.write_batch(&[event("first"), event("second")]).unwrap();
+        writer.write_batch(&[event("third")]).unwrap();
+        drop(writer);
+
+        // Tear the last batch as a crash mid-write would
+        let raw = std::fs::read(&log_path).unwrap();
+        std::fs::write(&log_path, &raw[..raw.len() - 6]).unwrap();
+
+        let actions: Vec<String> = read_audit_entries(&log_path).unwrap().into_iter().map(|e| e.action).collect();
+        assert_eq!(actions, ["first", "second"]);
+    }
+
+    /// Counts events written and fails while `fail` is set
+    struct FlakyWriter {
+        written: Arc<std::sync::Mutex<Vec<u64>>>,
+        fail: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl AuditWriter for FlakyWriter {
+        fn write_event(&mut self, event: &AuditEvent) -> Result<(), SecurityError> {
+            if self.fail.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(SecurityError::AuditLogFailed { reason: "unavailable".to_string() });
+            }
+            self.written.lock().unwrap().push(event.sequence);
+            Ok(())
+        }
+        fn flush(&mut self) -> Result<(), SecurityError> { Ok(()) }
+        fn rotate(&mut self) -> Result<(), SecurityError> { Ok(()) }
+    }
+
+    #[test]
+    fn test_retry_only_resends_to_writers_that_failed() {
+        let file_log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let worm_log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let worm_down = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let writers: RwLock<HashMap<String, Box<dyn AuditWriter + Send + Sync>>> = RwLock::new(HashMap::from([
+            ("file".to_string(), Box::new(FlakyWriter { written: file_log.clone(), fail: Arc::new(false.into()) }) as Box<dyn AuditWriter + Send + Sync>),
+            ("worm".to_string(), Box::new(FlakyWriter { written: worm_log.clone(), fail: worm_down.clone() }) as Box<dyn AuditWriter + Send + Sync>),
+        ]));
+        let event = |sequence| {
+            let mut event = AuditEvent::new(AuditEventType::SystemEvent, None, "test".to_string(), AuditOutcome::Success);
+            event.sequence = sequence;
+            event
+        };
+
+        let mut pending = PendingBatch::default();
+        pending.events = vec![event(1), event(2)];
+        assert!(AuditService::write_pending(&mut pending, &writers, None).is_err());
+        assert_eq!(pending.events.len(), 2);
+
+        worm_down.store(false, std::sync::atomic::Ordering::SeqCst);
+        assert!(AuditService::write_pending(&mut pending, &writers, Some(event(3))).is_ok());
+        assert!(pending.events.is_empty());
+        // The file writer got each event once; the WORM writer caught up on all of them
+        assert_eq!(*file_log.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(*worm_log.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_command_event_maps_uid_and_patient_like_rbac() {
+        let details = serde_json::json!({ "client_id": "client-7", "fields": 3 });
+        let event = command_audit_event("CLIENT_VIEW_DENIED", "clients", "firebase-uid-1", true, Some(details));
+
+        assert_eq!(event.user_id, Some(crate::security::rbac::stable_uuid("firebase-uid-1")));
+        assert_eq!(event.patient_id, Some(crate::security::rbac::stable_uuid("client-7")));
+        assert_eq!(event.resource_id.as_deref(), Some("client-7"));
+        assert!(matches!(event.outcome, AuditOutcome::Denied));
+        assert!(matches!(event.event_type, AuditEventType::PatientDataViewed));
+        assert_eq!(event.metadata.get("fields"), Some(&serde_json::json!(3)));
+    }
 }
 
 /// Simple HIPAA audit logging function compatible with Firebase service
@@ -1022,55 +2147,71 @@ pub async fn hipaa_audit_log(
     resource: &str,
     user_id: &str,
     phi_accessed: bool,
-    _details: Option<serde_json::Value>,
+    details: Option<serde_json::Value>,
 ) -> Result<(), SecurityError> {
-    // Create audit event
-    let event = AuditEvent {
-        event_id: Uuid::new_v4(),
-        event_type: if phi_accessed {
-            AuditEventType::PatientDataViewed
-        } else {
-            AuditEventType::SystemStartup
-        },
-        timestamp: chrono::Utc::now(),
-        user_id: Some(Uuid::parse_str(user_id).unwrap_or_else(|_| Uuid::new_v4())),
-        user_role: Some(HealthcareRole::HealthcareProvider), // Default role
-        session_id: None,
-        source_ip: None,
-        user_agent: None,
-        action: action.to_string(),
-        resource_type: Some(resource.to_string()),
-        resource_id: None,
-        patient_id: None,
-        outcome: AuditOutcome::Success,
-        data_classification: if phi_accessed {
-            Some(DataClassification::Phi)
-        } else {
-            Some(DataClassification::Internal)
-        },
-        before_state: None,
-        after_state: None,
-        description: action.to_string(),
-        metadata: HashMap::new(),
-        risk_level: if phi_accessed { 8 } else { 3 },
-        requires_attention: false,
-        compliance_tags: vec!["HIPAA".to_string()],
-        location: None,
-        data_size_bytes: None,
-        device_info: None,
-        duration_ms: None,
-        records_affected: None,
+    record_command_event(action, resource, user_id, phi_accessed, details).await
+}
+
+/// Audit event for a command-level action. `user_id` is the Firebase uid, mapped to the same
+/// UUID the RBAC layer uses; `client_id`/`patient_id` in the details become the patient.
+pub fn command_audit_event(
+    action: &str,
+    resource: &str,
+    user_id: &str,
+    phi_accessed: bool,
+    details: Option<serde_json::Value>,
+) -> AuditEvent {
+    let outcome = if action.ends_with("_DENIED") || action.ends_with("_BLOCKED") {
+        AuditOutcome::Denied
+    } else if action.ends_with("_FAILED") {
+        AuditOutcome::Failure
+    } else {
+        AuditOutcome::Success
     };
+    let event_type = if phi_accessed { AuditEventType::PatientDataViewed } else { AuditEventType::SystemEvent };
+    let user = (!user_id.is_empty()).then(|| crate::security::rbac::stable_uuid(user_id));
 
-    // Log using tracing for now - in production this would use proper audit storage
-    tracing::info!(
-        event_id = %event.event_id,
-        user_id = %user_id,
-        action = action,
-        resource = resource,
-        phi_accessed = phi_accessed,
-        "HIPAA Audit Log"
-    );
+    let mut event = AuditEvent::new(event_type, user, action.to_string(), outcome);
+    event.resource_type = Some(resource.to_string());
+    event.description = action.to_string();
+    event.data_classification = Some(if phi_accessed { DataClassification::Phi } else { DataClassification::Internal });
+    if phi_accessed {
+        event.compliance_tags.push("PHI_ACCESS".to_string());
+        event.risk_level = 3;
+    }
+    if let Some(serde_json::Value::Object(map)) = details {
+        let patient = map.get("client_id").or_else(|| map.get("patient_id")).and_then(|v| v.as_str());
+        if let Some(patient) = patient {
+            event.resource_id = Some(patient.to_string());
+            event.patient_id = Some(crate::security::rbac::stable_uuid(patient));
+        }
+        event.metadata.extend(map);
+    }
+    event
+}
 
-    Ok(())
+/// Write a command-level event to the installed audit service; traced only when none is
+/// installed (unit tests and tools that run without the app)
+pub async fn record_command_event(
+    action: &str,
+    resource: &str,
+    user_id: &str,
+    phi_accessed: bool,
+    details: Option<serde_json::Value>,
+) -> Result<(), SecurityError> {
+    let event = command_audit_event(action, resource, user_id, phi_accessed, details);
+    match audit_service() {
+        Some(service) => service.log_event(event).await,
+        None => {
+            tracing::info!(
+                event_id = %event.event_id,
+                user_id = %user_id,
+                action = action,
+                resource = resource,
+                phi_accessed = phi_accessed,
+                "HIPAA Audit Log"
+            );
+            Ok(())
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,309 @@
+// Audit Log Export with Redaction Profiles for PsyPsy CMS
+// Shares audit trails with external parties under a named profile that decides, field by
+// field, what each recipient receives. Every export carries a manifest with its content hash.
+
+use crate::security::audit::{for_each_audit_entry, AuditEvent};
+use crate::security::SecurityError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// How one audit event field is exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldTreatment {
+    /// Exported as recorded
+    Include,
+    /// Replaced by a pseudonym that is stable within one export
+    Pseudonymize,
+    /// Present but replaced by `[REDACTED]`
+    Mask,
+    /// Left out entirely
+    Exclude,
+}
+
+/// Named set of field treatments for one kind of recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionProfile {
+    pub name: String,
+    pub description: String,
+    /// Treatment per top-level audit event field
+    pub fields: BTreeMap<String, FieldTreatment>,
+    /// Treatment for fields not listed
+    pub default_treatment: FieldTreatment,
+}
+
+impl RedactionProfile {
+    pub fn treatment_for(&self, field: &str) -> FieldTreatment {
+        self.fields.get(field).copied().unwrap_or(self.default_treatment)
+    }
+
+    fn with(name: &str, description: &str, default_treatment: FieldTreatment, fields: &[(&str, FieldTreatment)]) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            fields: fields.iter().map(|(f, t)| (f.to_string(), *t)).collect(),
+            default_treatment,
+        }
+    }
+
+    /// External regulator: who did what and when, never the PHI itself
+    pub fn regulator() -> Self {
+        use FieldTreatment::*;
+        Self::with(
+            "regulator",
+            "External regulator or auditor; PHI payloads removed, patients pseudonymized",
+            Include,
+            &[
+                ("patient_id", Pseudonymize),
+                ("resource_id", Pseudonymize),
+                ("before_state", Exclude),
+                ("after_state", Exclude),
+                ("metadata", Exclude),
+                ("description", Exclude),
+                ("source_ip", Mask),
+                ("user_agent", Exclude),
+                ("location", Mask),
+                ("device_info", Exclude),
+            ],
+        )
+    }
+
+    /// Internal compliance review: identities kept, record contents withheld
+    pub fn internal() -> Self {
+        use FieldTreatment::*;
+        Self::with(
+            "internal",
+            "Internal privacy and compliance review; record contents withheld",
+            Include,
+            &[("before_state", Mask), ("after_state", Mask)],
+        )
+    }
+
+    /// Legal hold: the complete, unaltered record
+    pub fn litigation_hold() -> Self {
+        Self::with(
+            "litigation-hold",
+            "Legal hold; complete and unaltered events",
+            FieldTreatment::Include,
+            &[],
+        )
+    }
+}
+
+/// Configured redaction profiles by name
+#[derive(Debug, Clone)]
+pub struct AuditExportProfiles(pub Arc<RwLock<HashMap<String, RedactionProfile>>>);
+
+impl Default for AuditExportProfiles {
+    fn default() -> Self {
+        let profiles = [RedactionProfile::regulator(), RedactionProfile::internal(), RedactionProfile::litigation_hold()]
+            .into_iter()
+            .map(|p| (p.name.clone(), p))
+            .collect();
+        Self(Arc::new(RwLock::new(profiles)))
+    }
+}
+
+impl AuditExportProfiles {
+    pub fn get(&self, name: &str) -> Option<RedactionProfile> {
+        self.0.read().unwrap().get(name).cloned()
+    }
+
+    pub fn list(&self) -> Vec<RedactionProfile> {
+        let mut profiles: Vec<RedactionProfile> = self.0.read().unwrap().values().cloned().collect();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        profiles
+    }
+}
+
+/// Chain-of-custody record shipped with every export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditExportManifest {
+    pub export_id: Uuid,
+    pub profile: String,
+    pub profile_description: String,
+    pub field_treatments: BTreeMap<String, FieldTreatment>,
+    pub default_treatment: FieldTreatment,
+    pub generated_by: String,
+    pub generated_at: DateTime<Utc>,
+    pub period_start: Option<DateTime<Utc>>,
+    pub period_end: Option<DateTime<Utc>>,
+    pub event_count: usize,
+    /// SHA-256 (hex) of the serialized `events` array
+    pub content_sha256: String,
+}
+
+/// Export file contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditExport {
+    pub manifest: AuditExportManifest,
+    pub events: Vec<Value>,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn content_hash(events: &[Value]) -> Result<String, SecurityError> {
+    let bytes = serde_json::to_vec(events).map_err(|e| SecurityError::AuditLogFailed {
+        reason: format!("Failed to serialize audit export: {}", e),
+    })?;
+    Ok(sha256_hex(&bytes))
+}
+
+/// Apply a profile to one event; pseudonyms are keyed by `export_id`
+pub fn redact_event(event: &AuditEvent, profile: &RedactionProfile, export_id: Uuid) -> Result<Value, SecurityError> {
+    let Value::Object(fields) = serde_json::to_value(event).map_err(|e| SecurityError::AuditLogFailed {
+        reason: format!("Failed to serialize audit event: {}", e),
+    })?
+    else {
+        return Ok(Value::Null);
+    };
+
+    let redacted = fields
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = match profile.treatment_for(&name) {
+                FieldTreatment::Include => value,
+                FieldTreatment::Exclude => return None,
+                FieldTreatment::Mask if value.is_null() => value,
+                FieldTreatment::Mask => Value::String("[REDACTED]".to_string()),
+                FieldTreatment::Pseudonymize if value.is_null() => value,
+                FieldTreatment::Pseudonymize => {
+                    let digest = sha256_hex(format!("{}:{}:{}", export_id, name, value).as_bytes());
+                    Value::String(format!("pseudo-{}", &digest[..16]))
+                }
+            };
+            Some((name, value))
+        })
+        .collect();
+    Ok(Value::Object(redacted))
+}
+
+/// Redact `events` under `profile` and build the manifest
+pub fn build_audit_export(
+    events: &[AuditEvent],
+    profile: &RedactionProfile,
+    generated_by: &str,
+    period_start: Option<DateTime<Utc>>,
+    period_end: Option<DateTime<Utc>>,
+) -> Result<AuditExport, SecurityError> {
+    let export_id = Uuid::new_v4();
+    let events = events
+        .iter()
+        .map(|event| redact_event(event, profile, export_id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AuditExport {
+        manifest: AuditExportManifest {
+            export_id,
+            profile: profile.name.clone(),
+            profile_description: profile.description.clone(),
+            field_treatments: profile.fields.clone(),
+            default_treatment: profile.default_treatment,
+            generated_by: generated_by.to_string(),
+            generated_at: Utc::now(),
+            period_start,
+            period_end,
+            event_count: events.len(),
+            content_sha256: content_hash(&events)?,
+        },
+        events,
+    })
+}
+
+/// Recipient-side check that the events match the manifest hash
+pub fn verify_audit_export(export: &AuditExport) -> Result<bool, SecurityError> {
+    Ok(export.manifest.event_count == export.events.len() && content_hash(&export.events)? == export.manifest.content_sha256)
+}
+
+/// Events from an audit log (JSON lines or gzip batches) within `[start, end)`; unreadable entries are skipped
+pub fn read_audit_log(
+    path: &Path,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Vec<AuditEvent>, SecurityError> {
+    let mut events = Vec::new();
+    for_each_audit_entry(path, |e| {
+        if start.map_or(true, |s| e.timestamp >= s) && end.map_or(true, |t| e.timestamp < t) {
+            events.push(e);
+        }
+        true
+    })?;
+    Ok(events)
+}
+
+/// Write the export as `<export_id>.json` in `dir`
+pub fn write_audit_export(dir: &Path, export: &AuditExport) -> Result<PathBuf, SecurityError> {
+    std::fs::create_dir_all(dir).map_err(|e| SecurityError::AuditLogFailed {
+        reason: format!("Failed to create export directory: {}", e),
+    })?;
+    let path = dir.join(format!("{}.json", export.manifest.export_id));
+    let bytes = serde_json::to_vec_pretty(export).map_err(|e| SecurityError::AuditLogFailed {
+        reason: format!("Failed to serialize audit export: {}", e),
+    })?;
+    std::fs::write(&path, bytes).map_err(|e| SecurityError::AuditLogFailed {
+        reason: format!("Failed to write audit export: {}", e),
+    })?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::audit::AuditOutcome;
+    use crate::security::AuditEventType;
+
+    fn phi_event() -> AuditEvent {
+        let mut event = AuditEvent::new(
+            AuditEventType::PatientDataModified,
+            Some(Uuid::new_v4()),
+            "update_client".to_string(),
+            AuditOutcome::Success,
+        )
+        .with_phi_access(Uuid::new_v4(), "client")
+        .with_state_change(
+            serde_json::json!({"diagnosis": "F32.1 Major depressive disorder"}),
+            serde_json::json!({"diagnosis": "F33.0 Recurrent depression"}),
+        );
+        event.description = "Updated diagnosis for Jane Doe".to_string();
+        event.metadata.insert("justification".to_string(), Value::String("Jane Doe follow-up".to_string()));
+        event
+    }
+
+    #[test]
+    fn test_regulator_profile_excludes_phi_payloads() {
+        let event = phi_event();
+        let export = build_audit_export(&[event.clone()], &RedactionProfile::regulator(), "admin", None, None).unwrap();
+        let exported = serde_json::to_string(&export.events).unwrap();
+
+        assert!(!exported.contains("F32.1"));
+        assert!(!exported.contains("Jane Doe"));
+        assert!(!exported.contains(&event.patient_id.unwrap().to_string()));
+        assert!(export.events[0].get("before_state").is_none());
+        assert_eq!(export.events[0]["action"], "update_client");
+        assert_eq!(export.manifest.profile, "regulator");
+    }
+
+    #[test]
+    fn test_litigation_hold_is_unaltered() {
+        let event = phi_event();
+        let export = build_audit_export(&[event.clone()], &RedactionProfile::litigation_hold(), "admin", None, None).unwrap();
+        assert_eq!(export.events[0], serde_json::to_value(&event).unwrap());
+    }
+
+    #[test]
+    fn test_manifest_hash_detects_tampering() {
+        let mut export = build_audit_export(&[phi_event()], &RedactionProfile::internal(), "admin", None, None).unwrap();
+        assert!(verify_audit_export(&export).unwrap());
+
+        export.events[0]["action"] = Value::String("view_client".to_string());
+        assert!(!verify_audit_export(&export).unwrap());
+    }
+}
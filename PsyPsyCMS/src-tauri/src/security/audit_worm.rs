@@ -0,0 +1,617 @@
+// WORM Audit Sink for PsyPsy CMS
+// Writes each flushed audit batch as an immutable, retention-locked segment: S3 Object Lock in
+// compliance mode, or a local write-once store. Segments chain by digest, so a missing or
+// rewritten segment is detectable. Failures raise an alert; the sink never degrades to a
+// mutable location.
+
+use crate::security::audit::{AuditEvent, AuditWriter};
+use crate::security::SecurityError;
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Called with a description whenever a segment cannot be made immutable
+pub type WormAlertHook = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Where segments are locked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WormBackend {
+    /// Write-once directory: files are created exclusively, made read-only and carry a
+    /// retention record the application refuses to act against
+    Local { path: PathBuf },
+    /// S3 (or compatible) bucket with Object Lock enabled; segments are spooled locally
+    /// and uploaded in compliance mode
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        prefix: String,
+    },
+}
+
+/// WORM export settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WormConfig {
+    pub backend: WormBackend,
+    /// How long each segment is locked against deletion or overwrite
+    pub retention_days: u32,
+    /// Outbox for segments awaiting upload (S3 only)
+    pub spool_dir: PathBuf,
+    /// Seconds between upload attempts for spooled segments
+    pub upload_interval_secs: u64,
+}
+
+impl WormConfig {
+    /// WORM sink from `AUDIT_WORM_BACKEND` ("local" or "s3"); `None` when unset.
+    /// Local: `AUDIT_WORM_PATH`. S3: `AUDIT_WORM_S3_ENDPOINT`, `AUDIT_WORM_S3_BUCKET`,
+    /// `AUDIT_WORM_S3_REGION`, `AUDIT_WORM_S3_PREFIX`, with credentials from the standard
+    /// `AWS_*` variables; the endpoint host must be on the outbound allowlist.
+    /// `AUDIT_WORM_RETENTION_DAYS` defaults to `default_retention_days`.
+    pub fn from_env(default_retention_days: u32) -> Option<Self> {
+        let env = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let backend = match env("AUDIT_WORM_BACKEND")?.to_ascii_lowercase().as_str() {
+            "local" => WormBackend::Local {
+                path: env("AUDIT_WORM_PATH").map_or_else(|| PathBuf::from("./logs/worm"), PathBuf::from),
+            },
+            "s3" => match (env("AUDIT_WORM_S3_ENDPOINT"), env("AUDIT_WORM_S3_BUCKET")) {
+                (Some(endpoint), Some(bucket)) => WormBackend::S3 {
+                    endpoint: endpoint.trim_end_matches('/').to_string(),
+                    bucket,
+                    region: env("AUDIT_WORM_S3_REGION").unwrap_or_else(|| "ca-central-1".to_string()),
+                    prefix: env("AUDIT_WORM_S3_PREFIX").unwrap_or_default(),
+                },
+                _ => {
+                    log::error!("AUDIT_WORM_BACKEND=s3 needs AUDIT_WORM_S3_ENDPOINT and AUDIT_WORM_S3_BUCKET; WORM export disabled");
+                    return None;
+                }
+            },
+            other => {
+                log::error!("Unknown AUDIT_WORM_BACKEND '{}'; WORM export disabled", other);
+                return None;
+            }
+        };
+        let retention_days = match env("AUDIT_WORM_RETENTION_DAYS").map(|v| v.parse::<u32>()) {
+            Some(Ok(days)) if days > 0 => days,
+            Some(_) => {
+                log::warn!("Ignoring invalid AUDIT_WORM_RETENTION_DAYS; using {}", default_retention_days);
+                default_retention_days
+            }
+            None => default_retention_days,
+        };
+        Some(Self {
+            backend,
+            retention_days,
+            spool_dir: env("AUDIT_WORM_SPOOL_DIR").map_or_else(|| PathBuf::from("./logs/worm_spool"), PathBuf::from),
+            upload_interval_secs: 30,
+        })
+    }
+}
+
+/// First line of every segment; links it to the segment before
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentHeader {
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+    pub event_count: usize,
+    /// SHA-256 (hex) of the previous segment's bytes; `None` for the first segment
+    pub previous_segment_sha256: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub retain_until: DateTime<Utc>,
+}
+
+/// Retention record stored beside a locally locked segment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalRetention {
+    sha256: String,
+    retain_until: DateTime<Utc>,
+}
+
+const CHAIN_HEAD_FILE: &str = "chain_head";
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn push_json_line<T: Serialize>(lines: &mut Vec<u8>, value: &T) -> Result<(), SecurityError> {
+    serde_json::to_writer(&mut *lines, value)
+        .map_err(|e| worm_error(format!("Failed to encode WORM segment: {}", e)))?;
+    lines.push(b'\n');
+    Ok(())
+}
+
+fn worm_error(reason: String) -> SecurityError {
+    SecurityError::AuditLogFailed { reason }
+}
+
+/// Write a segment once into a local WORM directory. Existing keys are never replaced.
+pub fn local_put_locked(dir: &Path, key: &str, bytes: &[u8], retain_until: DateTime<Utc>) -> Result<(), SecurityError> {
+    std::fs::create_dir_all(dir).map_err(|e| worm_error(format!("Failed to create WORM directory: {}", e)))?;
+    let write_once = |path: PathBuf, contents: &[u8]| -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        let mut permissions = file.metadata()?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions)
+    };
+    let retention = serde_json::to_vec(&LocalRetention { sha256: sha256_hex(bytes), retain_until })
+        .map_err(|e| worm_error(format!("Failed to encode retention record: {}", e)))?;
+    write_once(dir.join(format!("{}.retention", key)), &retention)
+        .and_then(|_| write_once(dir.join(key), bytes))
+        .map_err(|e| worm_error(format!("Failed to lock WORM segment {}: {}", key, e)))
+}
+
+/// Remove a local segment whose retention has lapsed; refused while it is still locked
+pub fn local_remove_expired(dir: &Path, key: &str, now: DateTime<Utc>) -> Result<(), SecurityError> {
+    let retention: LocalRetention = std::fs::read(dir.join(format!("{}.retention", key)))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| worm_error(format!("No retention record for WORM segment {}", key)))?;
+    if now < retention.retain_until {
+        return Err(worm_error(format!("WORM segment {} is locked until {}", key, retention.retain_until)));
+    }
+    for path in [dir.join(key), dir.join(format!("{}.retention", key))] {
+        let mut permissions = std::fs::metadata(&path)
+            .map_err(|e| worm_error(format!("Failed to read WORM segment {}: {}", key, e)))?
+            .permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        std::fs::set_permissions(&path, permissions)
+            .and_then(|_| std::fs::remove_file(&path))
+            .map_err(|e| worm_error(format!("Failed to remove WORM segment {}: {}", key, e)))?;
+    }
+    Ok(())
+}
+
+/// Audit writer that locks every batch as a WORM segment
+pub struct WormAuditWriter {
+    config: WormConfig,
+    previous_sha256: Option<String>,
+    alert: WormAlertHook,
+}
+
+impl WormAuditWriter {
+    pub fn new(config: WormConfig, alert: WormAlertHook) -> Result<Self, SecurityError> {
+        let state_dir = match &config.backend {
+            WormBackend::Local { path } => path.clone(),
+            WormBackend::S3 { .. } => config.spool_dir.clone(),
+        };
+        std::fs::create_dir_all(&state_dir).map_err(|e| worm_error(format!("Failed to create WORM directory: {}", e)))?;
+        let previous_sha256 = std::fs::read_to_string(state_dir.join(CHAIN_HEAD_FILE))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        Ok(Self { config, previous_sha256, alert })
+    }
+
+    fn state_dir(&self) -> &Path {
+        match &self.config.backend {
+            WormBackend::Local { path } => path,
+            WormBackend::S3 { .. } => &self.config.spool_dir,
+        }
+    }
+
+    /// Gzipped header line followed by one JSON line per event
+    fn build_segment(&self, events: &[AuditEvent], now: DateTime<Utc>) -> Result<(SegmentHeader, String, Vec<u8>), SecurityError> {
+        let header = SegmentHeader {
+            first_sequence: events.first().map_or(0, |e| e.sequence),
+            last_sequence: events.last().map_or(0, |e| e.sequence),
+            event_count: events.len(),
+            previous_segment_sha256: self.previous_sha256.clone(),
+            created_at: now,
+            retain_until: now + Duration::days(i64::from(self.config.retention_days)),
+        };
+        let mut lines = Vec::new();
+        push_json_line(&mut lines, &header)?;
+        for event in events {
+            push_json_line(&mut lines, event)?;
+        }
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let bytes = encoder.write_all(&lines)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| worm_error(format!("Failed to compress WORM segment: {}", e)))?;
+
+        // Keys sort in sequence order; the suffix keeps a retried batch from colliding
+        let key = format!(
+            "audit-{:020}-{:020}-{}.jsonl.gz",
+            header.first_sequence,
+            header.last_sequence,
+            &sha256_hex(&bytes)[..12]
+        );
+        Ok((header, key, bytes))
+    }
+
+    fn lock_segment(&mut self, events: &[AuditEvent]) -> Result<(), SecurityError> {
+        let (header, key, bytes) = self.build_segment(events, Utc::now())?;
+        // Spooled segments are written once as well; the uploader ships them in key order
+        local_put_locked(self.state_dir(), &key, &bytes, header.retain_until)?;
+        let digest = sha256_hex(&bytes);
+        if let Err(e) = std::fs::write(self.state_dir().join(CHAIN_HEAD_FILE), &digest) {
+            log::warn!("Failed to record WORM chain head: {}", e);
+        }
+        self.previous_sha256 = Some(digest);
+        Ok(())
+    }
+}
+
+impl AuditWriter for WormAuditWriter {
+    fn write_event(&mut self, event: &AuditEvent) -> Result<(), SecurityError> {
+        self.write_batch(std::slice::from_ref(event))
+    }
+
+    fn flush(&mut self) -> Result<(), SecurityError> {
+        Ok(())
+    }
+
+    /// Segments are immutable; there is nothing to rotate
+    fn rotate(&mut self) -> Result<(), SecurityError> {
+        Ok(())
+    }
+
+    /// Segments land in the state directory first, whatever the backend
+    fn check_writable(&self) -> Result<(), SecurityError> {
+        let metadata = std::fs::metadata(self.state_dir())
+            .map_err(|e| worm_error(format!("WORM directory is unavailable: {}", e)))?;
+        if !metadata.is_dir() || metadata.permissions().readonly() {
+            return Err(worm_error("WORM directory is not writable".to_string()));
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self, events: &[AuditEvent]) -> Result<(), SecurityError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.lock_segment(events).map_err(|e| {
+            (self.alert)(format!("Audit batch of {} events could not be written to WORM storage: {}", events.len(), e));
+            e
+        })
+    }
+}
+
+/// Read and check a segment: returns its header and events
+pub fn read_segment(bytes: &[u8]) -> Result<(SegmentHeader, Vec<AuditEvent>), SecurityError> {
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_string(&mut text)
+        .map_err(|e| worm_error(format!("Damaged WORM segment: {}", e)))?;
+    let mut lines = text.as_bytes().lines();
+    let header: SegmentHeader = lines.next()
+        .and_then(|line| line.ok())
+        .and_then(|line| serde_json::from_str(&line).ok())
+        .ok_or_else(|| worm_error("WORM segment has no header".to_string()))?;
+    let events = lines
+        .map(|line| {
+            line.ok()
+                .and_then(|line| serde_json::from_str::<AuditEvent>(&line).ok())
+                .ok_or_else(|| worm_error("Unreadable event in WORM segment".to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if events.len() != header.event_count {
+        return Err(worm_error("WORM segment event count does not match its header".to_string()));
+    }
+    Ok((header, events))
+}
+
+/// Read every segment of a local WORM store in order, checking each against its retention
+/// digest and the chain link to the segment before it
+pub fn verify_local_store(dir: &Path) -> Result<Vec<AuditEvent>, SecurityError> {
+    let mut keys: Vec<String> = std::fs::read_dir(dir)
+        .map_err(|e| worm_error(format!("Failed to list WORM store: {}", e)))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with("audit-") && name.ends_with(".jsonl.gz"))
+        .collect();
+    keys.sort();
+
+    let mut previous: Option<String> = None;
+    let mut events = Vec::new();
+    for key in keys {
+        let bytes = std::fs::read(dir.join(&key)).map_err(|e| worm_error(format!("Failed to read {}: {}", key, e)))?;
+        let digest = sha256_hex(&bytes);
+        let retention: Option<LocalRetention> = std::fs::read(dir.join(format!("{}.retention", key)))
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok());
+        if retention.map_or(true, |r| r.sha256 != digest) {
+            return Err(worm_error(format!("WORM segment {} does not match its retention record", key)));
+        }
+        let (header, segment_events) = read_segment(&bytes)?;
+        if header.previous_segment_sha256 != previous {
+            return Err(worm_error(format!("WORM chain broken before segment {}", key)));
+        }
+        previous = Some(digest);
+        events.extend(segment_events);
+    }
+    Ok(events)
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Credentials for signing S3 requests
+struct S3Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl S3Credentials {
+    fn from_env() -> Result<Self, SecurityError> {
+        Ok(Self {
+            access_key: std::env::var("AWS_ACCESS_KEY_ID").map_err(|_| worm_error("AWS_ACCESS_KEY_ID is not set".to_string()))?,
+            secret_key: std::env::var("AWS_SECRET_ACCESS_KEY").map_err(|_| worm_error("AWS_SECRET_ACCESS_KEY is not set".to_string()))?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// Build a request signed with AWS Signature V4. `headers` are the extra `x-amz-*` headers to
+/// sign; host, date, payload hash and session token are added here.
+fn signed_s3_request(
+    client: &reqwest::Client,
+    method: reqwest::Method,
+    url: reqwest::Url,
+    region: &str,
+    credentials: &S3Credentials,
+    payload_hash: &str,
+    mut headers: Vec<(&'static str, String)>,
+) -> Result<reqwest::RequestBuilder, SecurityError> {
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        _ => return Err(worm_error("WORM endpoint has no host".to_string())),
+    };
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    headers.push(("host", host));
+    headers.push(("x-amz-content-sha256", payload_hash.to_string()));
+    headers.push(("x-amz-date", amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.sort_by(|a, b| a.0.cmp(b.0));
+
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+    let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+    let canonical_request = format!("{}\n{}\n\n{}\n{}\n{}", method, url.path(), canonical_headers, signed_headers, payload_hash);
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, sha256_hex(canonical_request.as_bytes()));
+    let signing_key = ["s3", "aws4_request"].iter().fold(
+        hmac(&hmac(format!("AWS4{}", credentials.secret_key).as_bytes(), &date), region),
+        |key, part| hmac(&key, part),
+    );
+    let signature: String = hmac(&signing_key, &string_to_sign).iter().map(|b| format!("{:02x}", b)).collect();
+
+    let mut request = client.request(method, url)
+        .header("Authorization", format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key, scope, signed_headers, signature
+        ));
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        request = request.header(name, value);
+    }
+    Ok(request)
+}
+
+/// SHA-256 checksum S3 stored for an existing object, if it has one
+async fn s3_object_checksum(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    region: &str,
+    credentials: &S3Credentials,
+    key: &str,
+) -> Result<Option<String>, SecurityError> {
+    let response = signed_s3_request(
+        client,
+        reqwest::Method::HEAD,
+        url,
+        region,
+        credentials,
+        &sha256_hex(b""),
+        vec![("x-amz-checksum-mode", "ENABLED".to_string())],
+    )?
+    .send()
+    .await
+    .map_err(|e| worm_error(format!("WORM lookup of {} failed: {}", key, e)))?;
+    if !response.status().is_success() {
+        return Err(worm_error(format!("WORM lookup of {} rejected with {}", key, response.status())));
+    }
+    Ok(response.headers()
+        .get("x-amz-checksum-sha256")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string))
+}
+
+/// Upload one segment with a compliance-mode Object Lock, signed with AWS Signature V4.
+/// A segment already in the bucket with the same checksum counts as uploaded, so a retry
+/// after a lost response does not stall the spool.
+async fn s3_put_locked(
+    client: &reqwest::Client,
+    endpoint: &str,
+    bucket: &str,
+    region: &str,
+    key: &str,
+    bytes: Vec<u8>,
+    retain_until: DateTime<Utc>,
+) -> Result<(), SecurityError> {
+    let credentials = S3Credentials::from_env()?;
+    let url = reqwest::Url::parse(&format!("{}/{}/{}", endpoint, bucket, key))
+        .map_err(|e| worm_error(format!("Invalid WORM endpoint: {}", e)))?;
+    let payload_hash = sha256_hex(&bytes);
+    let checksum = general_purpose::STANDARD.encode(Sha256::digest(&bytes));
+    let retain_until = retain_until.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    let response = signed_s3_request(
+        client,
+        reqwest::Method::PUT,
+        url.clone(),
+        region,
+        &credentials,
+        &payload_hash,
+        vec![
+            ("x-amz-checksum-sha256", checksum.clone()),
+            ("x-amz-object-lock-mode", "COMPLIANCE".to_string()),
+            ("x-amz-object-lock-retain-until-date", retain_until),
+            ("x-amz-sdk-checksum-algorithm", "SHA256".to_string()),
+        ],
+    )?
+    // Never replace an object that already exists under this key
+    .header("If-None-Match", "*")
+    .body(bytes)
+    .send()
+    .await
+    .map_err(|e| worm_error(format!("WORM upload of {} failed: {}", key, e)))?;
+
+    if response.status() == reqwest::StatusCode::PRECONDITION_FAILED {
+        return match s3_object_checksum(client, url, region, &credentials, key).await? {
+            Some(existing) if existing == checksum => {
+                log::info!("WORM segment {} was already uploaded", key);
+                Ok(())
+            }
+            _ => Err(worm_error(format!("WORM object {} already exists with different content", key))),
+        };
+    }
+    if !response.status().is_success() {
+        return Err(worm_error(format!("WORM upload of {} rejected with {}", key, response.status())));
+    }
+    Ok(())
+}
+
+/// Upload spooled segments in order, removing each from the spool once the bucket has it.
+/// Stops at the first failure so segments never land out of order.
+pub async fn upload_spooled_segments(config: &WormConfig, client: &reqwest::Client) -> Result<usize, SecurityError> {
+    let WormBackend::S3 { endpoint, bucket, region, prefix } = &config.backend else {
+        return Ok(0);
+    };
+    let mut keys: Vec<String> = std::fs::read_dir(&config.spool_dir)
+        .map_err(|e| worm_error(format!("Failed to list WORM spool: {}", e)))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with("audit-") && name.ends_with(".jsonl.gz"))
+        .collect();
+    keys.sort();
+
+    let mut uploaded = 0;
+    for key in keys {
+        let bytes = std::fs::read(config.spool_dir.join(&key))
+            .map_err(|e| worm_error(format!("Failed to read spooled segment {}: {}", key, e)))?;
+        let (header, _) = read_segment(&bytes)?;
+        let object_key = format!("{}{}", prefix, key);
+        s3_put_locked(client, endpoint, bucket, region, &object_key, bytes, header.retain_until).await?;
+        // The spool copy has served its purpose once the locked object exists
+        local_remove_spooled(&config.spool_dir, &key)?;
+        uploaded += 1;
+    }
+    Ok(uploaded)
+}
+
+/// Remove an uploaded segment from the spool regardless of its local retention record
+fn local_remove_spooled(dir: &Path, key: &str) -> Result<(), SecurityError> {
+    local_remove_expired(dir, key, DateTime::<Utc>::MAX_UTC)
+}
+
+/// Periodically ship spooled segments; every failed attempt raises an alert
+pub fn spawn_worm_uploader(config: WormConfig, alert: WormAlertHook) {
+    if !matches!(config.backend, WormBackend::S3 { .. }) {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    runtime.spawn(async move {
+        let client = crate::security::outbound::guarded_client();
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(config.upload_interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            match upload_spooled_segments(&config, &client).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Uploaded {} audit segments to WORM storage", count),
+                Err(e) => alert(format!("Audit segments are waiting in the spool and not yet locked: {}", e)),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::audit::AuditOutcome;
+    use crate::security::AuditEventType;
+    use std::sync::Mutex;
+
+    fn events(range: std::ops::Range<u64>) -> Vec<AuditEvent> {
+        range.map(|sequence| {
+            let mut event = AuditEvent::new(AuditEventType::PatientDataViewed, None, "view".to_string(), AuditOutcome::Success);
+            event.sequence = sequence;
+            event
+        }).collect()
+    }
+
+    #[test]
+    fn test_local_segments_are_locked_and_chained() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = WormConfig {
+            backend: WormBackend::Local { path: dir.path().to_path_buf() },
+            retention_days: 30,
+            spool_dir: dir.path().join("spool"),
+            upload_interval_secs: 30,
+        };
+        let mut writer = WormAuditWriter::new(config.clone(), Arc::new(|_| {})).unwrap();
+        writer.write_batch(&events(0..3)).unwrap();
+        writer.write_batch(&events(3..5)).unwrap();
+
+        let stored = verify_local_store(dir.path()).unwrap();
+        assert_eq!(stored.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+
+        // Locked segments can be neither rewritten nor removed early
+        let key = std::fs::read_dir(dir.path()).unwrap()
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .find(|name| name.ends_with(".jsonl.gz"))
+            .unwrap();
+        assert!(local_put_locked(dir.path(), &key, b"forged", Utc::now()).is_err());
+        assert!(local_remove_expired(dir.path(), &key, Utc::now()).is_err());
+
+        // Dropping a segment from the middle of the store breaks the chain
+        let mut keys: Vec<_> = std::fs::read_dir(dir.path()).unwrap()
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|name| name.ends_with(".jsonl.gz"))
+            .collect();
+        keys.sort();
+        local_remove_expired(dir.path(), &keys[0], Utc::now() + Duration::days(31)).unwrap();
+        assert!(verify_local_store(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_write_failure_raises_alert() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("not-a-directory");
+        std::fs::write(&blocker, b"").unwrap();
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&alerts);
+        let config = WormConfig {
+            backend: WormBackend::Local { path: dir.path().to_path_buf() },
+            retention_days: 30,
+            spool_dir: dir.path().join("spool"),
+            upload_interval_secs: 30,
+        };
+        let mut writer = WormAuditWriter::new(config, Arc::new(move |msg| sink.lock().unwrap().push(msg))).unwrap();
+        writer.config.backend = WormBackend::Local { path: blocker };
+
+        assert!(writer.write_batch(&events(0..2)).is_err());
+        assert_eq!(alerts.lock().unwrap().len(), 1);
+    }
+}
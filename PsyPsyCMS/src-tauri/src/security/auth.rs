@@ -2,11 +2,19 @@
 // Implements secure authentication with healthcare-specific requirements
 
 use crate::security::{SecurityError, SecuritySession, HealthcareRole, SecurityConfig};
+use crate::security::audit::{log_security_violation, AuditEvent, AuditOutcome, AuditService};
+use crate::security::rate_limit::{RateLimitConfig, SessionQuotaAction};
+use crate::security::AuditEventType;
+use crate::security::crypto::{unwrap_with, wrap_with};
+use crate::security::totp::{self, TotpEnrollment};
+use crate::security::geolocation::{self, GeoLocation, GeoResolver, NoopGeoResolver};
+use crate::security::rbac::stable_uuid;
 use serde::{Deserialize, Serialize};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use reqwest::Client;
 use oauth2::{
@@ -39,6 +47,24 @@ pub struct FirebaseUser {
     pub provider_data: Vec<ProviderData>,
 }
 
+impl FirebaseUser {
+    /// User known only from a password sign-in, before their profile is looked up
+    pub fn signed_in(uid: impl Into<String>, email: impl Into<String>) -> Self {
+        Self {
+            uid: uid.into(),
+            email: email.into(),
+            display_name: None,
+            email_verified: false,
+            phone_number: None,
+            photo_url: None,
+            created_at: Utc::now(),
+            last_sign_in: Some(Utc::now()),
+            custom_claims: HashMap::new(),
+            provider_data: Vec::new(),
+        }
+    }
+}
+
 /// Authentication provider information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderData {
@@ -168,8 +194,9 @@ pub struct MfaChallenge {
 pub enum MfaChallengeType {
     /// SMS text message
     Sms { phone_number: String },
-    /// Time-based one-time password (Google Authenticator, etc.)
-    Totp { secret_key: String },
+    /// Time-based one-time password (Google Authenticator, etc.), checked against the user's
+    /// enrolled factor
+    Totp,
     /// Email verification
     Email { email_address: String },
     /// Hardware security key (WebAuthn)
@@ -178,6 +205,61 @@ pub enum MfaChallengeType {
     BackupCode,
 }
 
+/// Refresh tokens issued to one user. Kept apart from the active sessions so a replayed token
+/// is still recognised after the session it belonged to was rotated or revoked.
+#[derive(Debug, Default)]
+struct RefreshTokenFamily {
+    /// Session ID -> ID of the one refresh token that may currently be redeemed
+    current: HashMap<String, String>,
+    /// Refresh token ID already rotated out -> (session ID, token expiry as a Unix timestamp)
+    rotated: HashMap<String, (String, i64)>,
+}
+
+/// A user's TOTP second factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotpFactor {
+    /// Shared secret sealed with the factor key, bound to the user ID
+    secret: String,
+    /// Set once a first code has been verified
+    enabled: bool,
+    /// Last step a code was accepted for; codes from it or earlier are replays
+    last_used_step: Option<i64>,
+}
+
+/// Files holding second factors inside the app data directory
+const FACTOR_STORE_FILE: &str = "mfa_factors.json";
+const FACTOR_KEY_FILE: &str = "mfa_factors.key";
+
+/// Where TOTP factors are persisted. The key is generated on first use and kept beside the
+/// app data rather than under the storage passphrase, so factors can be checked at sign-in.
+#[derive(Clone)]
+struct FactorStore {
+    path: PathBuf,
+    key: Vec<u8>,
+}
+
+fn totp_secret_aad(user_id: &str) -> Vec<u8> {
+    format!("psypsy-totp:{}", user_id).into_bytes()
+}
+
+fn load_or_create_factor_key(path: &Path) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) if key.len() == 32 => Ok(key),
+        Ok(_) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "MFA factor key is corrupt")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            use ring::rand::SecureRandom;
+            let mut key = vec![0u8; 32];
+            ring::rand::SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "random source unavailable"))?;
+            std::fs::write(path, &key)?;
+            Ok(key)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 /// Firebase authentication service
 pub struct FirebaseAuthService {
     /// Firebase project ID
@@ -192,12 +274,26 @@ pub struct FirebaseAuthService {
     jwt_decoding_key: DecodingKey,
     /// Active sessions
     sessions: Arc<RwLock<HashMap<String, SecuritySession>>>,
+    /// Refresh token families by user ID
+    refresh_families: Arc<RwLock<HashMap<String, RefreshTokenFamily>>>,
     /// Active MFA challenges
     mfa_challenges: Arc<RwLock<HashMap<String, MfaChallenge>>>,
     /// Security configuration
     config: SecurityConfig,
+    /// Per-role limits, including concurrent sessions
+    rate_limits: RateLimitConfig,
     /// OAuth2 client for provider authentication
     oauth_client: Option<BasicClient>,
+    /// Audit trail for refresh token reuse and second-factor events
+    audit: Option<Arc<AuditService>>,
+    /// TOTP factors by user ID
+    totp_factors: Arc<RwLock<HashMap<String, TotpFactor>>>,
+    /// Persists TOTP factors; enrollment needs it
+    factor_store: Arc<RwLock<Option<FactorStore>>>,
+    /// Places sign-in IP addresses
+    geo_resolver: Arc<dyn GeoResolver>,
+    /// Recent sign-in locations by user ID, for impossible-travel checks
+    recent_locations: Arc<RwLock<HashMap<String, Vec<(GeoLocation, DateTime<Utc>)>>>>,
 }
 
 impl std::fmt::Debug for FirebaseAuthService {
@@ -212,6 +308,10 @@ impl std::fmt::Debug for FirebaseAuthService {
             .field("mfa_challenges", &self.mfa_challenges)
             .field("config", &self.config)
             .field("oauth_client", &self.oauth_client)
+            .field("audit", &self.audit.is_some())
+            .field("totp_factors", &"[REDACTED]")
+            .field("factor_store", &self.factor_store.read().unwrap().as_ref().map(|store| store.path.clone()))
+            .field("recent_locations", &"[REDACTED]")
             .finish()
     }
 }
@@ -219,7 +319,7 @@ impl std::fmt::Debug for FirebaseAuthService {
 impl FirebaseAuthService {
     /// Create new Firebase authentication service
     pub fn new(project_id: String, api_key: String, jwt_secret: &[u8]) -> Self {
-        let client = Client::new();
+        let client = crate::security::outbound::guarded_client();
         
         // Create JWT keys
         let jwt_encoding_key = EncodingKey::from_secret(jwt_secret);
@@ -232,11 +332,67 @@ impl FirebaseAuthService {
             jwt_encoding_key,
             jwt_decoding_key,
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            refresh_families: Arc::new(RwLock::new(HashMap::new())),
             mfa_challenges: Arc::new(RwLock::new(HashMap::new())),
             config: SecurityConfig::default(),
+            rate_limits: RateLimitConfig::default(),
             oauth_client: None,
+            audit: None,
+            totp_factors: Arc::new(RwLock::new(HashMap::new())),
+            factor_store: Arc::new(RwLock::new(None)),
+            geo_resolver: Arc::new(NoopGeoResolver),
+            recent_locations: Arc::new(RwLock::new(HashMap::new())),
         }
     }
+
+    /// Use the running rate-limit configuration for per-role session limits
+    pub fn with_rate_limits(mut self, rate_limits: RateLimitConfig) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Record refresh token reuse in the audit trail
+    pub fn with_audit_service(mut self, audit: Arc<AuditService>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Load TOTP factors saved in `dir` and persist future changes there. A corrupt store is an
+    /// error rather than an empty one, which would let anyone with the password re-enroll.
+    pub fn attach_factor_storage(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let key = load_or_create_factor_key(&dir.join(FACTOR_KEY_FILE))?;
+        let path = dir.join(FACTOR_STORE_FILE);
+        if path.exists() {
+            let factors: HashMap<String, TotpFactor> = serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            *self.totp_factors.write().unwrap() = factors;
+        }
+        *self.factor_store.write().unwrap() = Some(FactorStore { path, key });
+        Ok(())
+    }
+
+    fn factor_store(&self) -> Result<FactorStore, SecurityError> {
+        self.factor_store.read().unwrap().clone().ok_or_else(|| SecurityError::ConfigurationError {
+            reason: "TOTP needs factor storage".to_string(),
+        })
+    }
+
+    /// Write every factor out, replacing the file atomically
+    fn persist_factors(&self, store: &FactorStore) -> Result<(), SecurityError> {
+        let bytes = serde_json::to_vec(&*self.totp_factors.read().unwrap())
+            .map_err(|e| SecurityError::ConfigurationError { reason: format!("Failed to serialize MFA factors: {}", e) })?;
+        let tmp = store.path.with_extension("json.tmp");
+        std::fs::write(&tmp, bytes)
+            .and_then(|_| std::fs::rename(&tmp, &store.path))
+            .map_err(|e| SecurityError::ConfigurationError { reason: format!("Failed to save MFA factors: {}", e) })
+    }
+
+    /// Geolocate sign-ins so sessions carry a location and impossible travel is flagged
+    pub fn with_geo_resolver(mut self, geo_resolver: Arc<dyn GeoResolver>) -> Self {
+        self.geo_resolver = geo_resolver;
+        self
+    }
     
     /// Initialize OAuth2 client for provider authentication
     pub fn init_oauth2(&mut self, client_id: String, client_secret: String, redirect_url: String) -> Result<(), SecurityError> {
@@ -261,6 +417,24 @@ impl FirebaseAuthService {
         Ok(())
     }
     
+    /// Whether the Firebase Auth API answers. Any HTTP response counts, including the rejection
+    /// of the empty lookup sent here; only transport failures mean it is unreachable.
+    pub async fn check_reachability(&self) -> Result<reqwest::StatusCode, SecurityError> {
+        let url = format!(
+            "https://identitytoolkit.googleapis.com/v1/accounts:lookup?key={}",
+            self.api_key
+        );
+        let response = self.client
+            .post(&url)
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .map_err(|e| SecurityError::AuthenticationFailed {
+                reason: format!("Firebase API unreachable: {}", e)
+            })?;
+        Ok(response.status())
+    }
+
     /// Authenticate user with Firebase
     pub async fn authenticate_with_firebase(&self, id_token: &str) -> Result<FirebaseUser, SecurityError> {
         let url = format!(
@@ -329,11 +503,26 @@ impl FirebaseAuthService {
         user_agent: Option<String>,
     ) -> Result<SecuritySession, SecurityError> {
         let session_id = Uuid::new_v4();
-        let user_id = Uuid::parse_str(&user.uid)
-            .map_err(|_| SecurityError::AuthenticationFailed { 
-                reason: "Invalid user ID format".to_string() 
-            })?;
+        // Firebase UIDs are not UUIDs; map them the same way RBAC does
+        let user_id = stable_uuid(&user.uid);
         
+        self.enforce_session_quota(user_id, &role).await?;
+
+        let now = Utc::now();
+        let location = match ip_address.as_deref() {
+            Some(ip) => self.geo_resolver.resolve(ip).await.unwrap_or_else(|e| {
+                log::warn!("Could not geolocate sign-in for user {}: {}", user.uid, e);
+                None
+            }),
+            None => None,
+        };
+        let restrictions = self.rate_limits.ip_limits.geographic_restrictions.as_ref();
+        let travel = location
+            .as_ref()
+            .and_then(|current| self.record_sign_in_location(&user.uid, current, now))
+            .filter(|_| restrictions.is_some_and(|r| r.alert_suspicious_locations));
+        let mfa_required = travel.is_some() && restrictions.is_some_and(|r| r.require_mfa_on_suspicious_location);
+
         // Determine permissions based on role
         let permissions = self.get_role_permissions(&role);
         
@@ -346,8 +535,13 @@ impl FirebaseAuthService {
                 reason: format!("Failed to create access token: {}", e) 
             })?;
         
-        // Create refresh token (simplified, in practice would have different claims)
-        let refresh_claims = claims.clone();
+        // The refresh token has its own ID so it can be rotated and its reuse detected, and
+        // outlives the access token so an expired access token can still be renewed
+        let refresh_claims = HipaaJwtClaims {
+            jti: Uuid::new_v4().to_string(),
+            exp: (now + self.config.refresh_token_lifetime()).timestamp(),
+            ..claims.clone()
+        };
         let refresh_token = encode(&Header::default(), &refresh_claims, &self.jwt_encoding_key)
             .map_err(|e| SecurityError::AuthenticationFailed { 
                 reason: format!("Failed to create refresh token: {}", e) 
@@ -364,7 +558,7 @@ impl FirebaseAuthService {
             expires_at: Utc::now() + Duration::seconds(self.config.jwt_expiry_seconds),
             ip_address,
             user_agent,
-            location: None, // Could be determined from IP
+            location: location.as_ref().map(GeoLocation::label),
             is_elevated: false,
             mfa_verified: false,
             permissions,
@@ -372,19 +566,65 @@ impl FirebaseAuthService {
             security_metadata: serde_json::json!({
                 "firebase_uid": user.uid,
                 "email_verified": user.email_verified,
-                "creation_method": "firebase_auth"
+                "creation_method": "firebase_auth",
+                "geolocation": location,
+                "suspicious_location": travel,
+                "mfa_required": mfa_required
             }),
         };
         
         // Store session
         self.sessions.write().unwrap().insert(session_id.to_string(), session.clone());
+        self.refresh_families.write().unwrap()
+            .entry(user.uid.clone())
+            .or_default()
+            .current
+            .insert(session_id.to_string(), refresh_claims.jti);
         
+        if let Some(travel) = &travel {
+            log::warn!(
+                "Impossible travel for user {}: {} to {} ({:.0} km in {} min)",
+                user.uid, travel.previous.label(), travel.current.label(), travel.distance_km, travel.elapsed_minutes
+            );
+            if let Some(audit) = &self.audit {
+                let mut event = AuditEvent::new(
+                    AuditEventType::AnomalousActivity,
+                    Some(user_id),
+                    "impossible_travel".to_string(),
+                    if mfa_required { AuditOutcome::Pending } else { AuditOutcome::Success },
+                )
+                .with_session(session_id.to_string(), session.ip_address.clone(), session.user_agent.clone())
+                .mark_high_risk("Sign-in location implies impossible travel");
+                event.location = session.location.clone();
+                event.metadata.insert("impossible_travel".to_string(), serde_json::to_value(travel).unwrap_or_default());
+                event.metadata.insert("mfa_required".to_string(), serde_json::json!(mfa_required));
+                if let Err(e) = audit.log_event(event).await {
+                    log::warn!("Failed to audit impossible travel: {}", e);
+                }
+            }
+        }
+
         log::info!("Created secure session {} for user {} with role {:?}", session_id, user.email, &role);
         Ok(session)
     }
+
+    /// Remember a sign-in location and compare it with the user's others from the travel window
+    fn record_sign_in_location(
+        &self,
+        user_id: &str,
+        current: &GeoLocation,
+        now: DateTime<Utc>,
+    ) -> Option<geolocation::ImpossibleTravel> {
+        let mut recent_locations = self.recent_locations.write().unwrap();
+        let history = recent_locations.entry(user_id.to_string()).or_default();
+        history.retain(|(_, at)| now - *at <= Duration::hours(geolocation::TRAVEL_WINDOW_HOURS));
+        let travel = geolocation::detect_impossible_travel(history, current, now);
+        history.push((current.clone(), now));
+        travel
+    }
     
-    /// Validate JWT token and return claims
-    pub fn validate_token(&self, token: &str) -> Result<HipaaJwtClaims, SecurityError> {
+    /// Check a token's signature, issuer, audience and validity period
+    fn decode_claims(&self, token: &str) -> Result<HipaaJwtClaims, SecurityError> {
         let mut validation = Validation::new(Algorithm::HS256);
         validation.set_audience(&["psypsy-cms-tauri"]);
         validation.set_issuer(&["psypsy-cms-hipaa"]);
@@ -402,66 +642,150 @@ impl FirebaseAuthService {
                 reason: "Token is expired or not yet valid".to_string() 
             });
         }
+        Ok(claims)
+    }
+
+    /// Validate JWT token and return claims
+    pub fn validate_token(&self, token: &str) -> Result<HipaaJwtClaims, SecurityError> {
+        let claims = self.decode_claims(token)?;
         
-        // Verify session still exists
-        if !self.sessions.read().unwrap().contains_key(&claims.session_id) {
-            return Err(SecurityError::SessionExpired {
-                expired_at: Utc::now(),
-                reason: "Session not found in active sessions".to_string()
-            });
-        }
-        
+        // The session must still be active, and this use counts as activity
+        self.validate_session(&claims.session_id)?;
+
         Ok(claims)
     }
     
-    /// Refresh JWT token
+    /// Refresh JWT token; returns the new access and refresh tokens
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<(String, String), SecurityError> {
-        let claims = self.validate_token(refresh_token)?;
-        
-        // Get session
-        let _session = self.sessions.read().unwrap()
-            .get(&claims.session_id)
-            .cloned()
-            .ok_or_else(|| SecurityError::SessionExpired {
-                expired_at: Utc::now(),
-                reason: "Session not found or expired".to_string()
-            })?;
-        
-        // Store session_id before move
-        let session_id = claims.session_id.clone();
+        let session = self.refresh_session(refresh_token).await?;
+        Ok((session.access_token, session.refresh_token))
+    }
+    
+    /// Rotate a session's refresh token: the presented token is redeemed once for a new access
+    /// and refresh pair. Presenting a token that was already rotated out is treated as theft;
+    /// every session of that user is revoked and a security violation is recorded.
+    pub async fn refresh_session(&self, refresh_token: &str) -> Result<SecuritySession, SecurityError> {
+        let claims = self.decode_claims(refresh_token)?;
 
-        // Create new tokens
-        let new_claims = HipaaJwtClaims {
-            iat: Utc::now().timestamp(),
-            exp: (Utc::now() + Duration::seconds(self.config.jwt_expiry_seconds)).timestamp(),
+        let reused = self.refresh_families.read().unwrap()
+            .get(&claims.sub)
+            .map_or(false, |family| family.rotated.contains_key(&claims.jti));
+        if reused {
+            let revoked = self.revoke_refresh_family(&claims.sub);
+            log::error!(
+                "SecurityViolationDetected: rotated refresh token for session {} was presented again; revoked {} session(s) of user {}",
+                claims.session_id, revoked, claims.sub
+            );
+            if let Some(audit) = &self.audit {
+                let description = format!(
+                    "Rotated refresh token for session {} was presented again; {} session(s) revoked",
+                    claims.session_id, revoked
+                );
+                let user_id = Uuid::parse_str(&claims.sub).ok();
+                if let Err(e) = log_security_violation(audit, user_id, "refresh_token_reuse", &description, Some(claims.session_id.clone())).await {
+                    log::warn!("Failed to audit refresh token reuse: {}", e);
+                }
+            }
+            return Err(SecurityError::AccessDenied {
+                reason: "Refresh token reuse detected; all sessions for this account were revoked".to_string(),
+            });
+        }
+
+        let is_current = self.refresh_families.read().unwrap()
+            .get(&claims.sub)
+            .and_then(|family| family.current.get(&claims.session_id))
+            .map_or(false, |jti| *jti == claims.jti);
+        if !is_current {
+            return Err(SecurityError::InvalidToken {
+                reason: "Not a current refresh token for this session".to_string(),
+            });
+        }
+
+        let now = Utc::now();
+        let access_claims = HipaaJwtClaims {
+            iat: now.timestamp(),
+            nbf: now.timestamp(),
+            exp: (now + Duration::seconds(self.config.jwt_expiry_seconds)).timestamp(),
             jti: Uuid::new_v4().to_string(),
-            ..claims
+            ..claims.clone()
         };
-
-        let new_access_token = encode(&Header::default(), &new_claims, &self.jwt_encoding_key)
+        let refresh_claims = HipaaJwtClaims {
+            jti: Uuid::new_v4().to_string(),
+            exp: (now + self.config.refresh_token_lifetime()).timestamp(),
+            ..access_claims.clone()
+        };
+        let access_token = encode(&Header::default(), &access_claims, &self.jwt_encoding_key)
             .map_err(|e| SecurityError::AuthenticationFailed {
                 reason: format!("Failed to create new access token: {}", e)
             })?;
-
-        let new_refresh_token = encode(&Header::default(), &new_claims, &self.jwt_encoding_key)
+        let new_refresh_token = encode(&Header::default(), &refresh_claims, &self.jwt_encoding_key)
             .map_err(|e| SecurityError::AuthenticationFailed {
                 reason: format!("Failed to create new refresh token: {}", e)
             })?;
 
-        // Update session
+        // Swap the session and family under both locks so a concurrent refresh of the same
+        // token cannot also succeed
         let mut sessions = self.sessions.write().unwrap();
-        if let Some(session) = sessions.get_mut(&session_id) {
-            session.access_token = new_access_token.clone();
-            session.refresh_token = new_refresh_token.clone();
-            session.last_activity = Utc::now();
-            session.expires_at = Utc::now() + Duration::seconds(self.config.jwt_expiry_seconds);
+        let mut families = self.refresh_families.write().unwrap();
+        let family = families.entry(claims.sub.clone()).or_default();
+        if family.current.get(&claims.session_id) != Some(&claims.jti) {
+            return Err(SecurityError::InvalidToken {
+                reason: "Refresh token was already redeemed".to_string(),
+            });
         }
-        
-        Ok((new_access_token, new_refresh_token))
+        let session = sessions.get_mut(&claims.session_id)
+            .ok_or_else(|| SecurityError::SessionExpired {
+                expired_at: now,
+                reason: "Session not found or expired".to_string()
+            })?;
+        session.access_token = access_token;
+        session.refresh_token = new_refresh_token;
+        session.last_activity = now;
+        session.expires_at = now + Duration::seconds(self.config.jwt_expiry_seconds);
+
+        family.rotated.insert(claims.jti.clone(), (claims.session_id.clone(), claims.exp));
+        family.current.insert(claims.session_id.clone(), refresh_claims.jti);
+
+        log::info!("Rotated refresh token for session {}", claims.session_id);
+        Ok(session.clone())
     }
-    
-    /// Start MFA challenge
+
+    /// End every session in a user's refresh token family. Rotated token IDs are kept so
+    /// further replays are still caught. Returns how many sessions were ended.
+    fn revoke_refresh_family(&self, user_id: &str) -> usize {
+        let session_ids: Vec<String> = {
+            let mut families = self.refresh_families.write().unwrap();
+            let Some(family) = families.get_mut(user_id) else {
+                return 0;
+            };
+            family.current.drain().map(|(session_id, _)| session_id).collect()
+        };
+        let mut sessions = self.sessions.write().unwrap();
+        session_ids.iter().filter(|id| sessions.remove(id.as_str()).is_some()).count()
+    }
+
+    /// End the session a token was issued for; a no-op for tokens this service did not issue
+    pub async fn end_session_for_token(&self, token: &str) -> Result<(), SecurityError> {
+        match self.decode_claims(token) {
+            Ok(claims) => self.end_session(&claims.session_id).await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Start MFA challenge. Only TOTP can be checked, so other types and users without an
+    /// enabled factor are refused rather than given a challenge any code would pass.
     pub async fn start_mfa_challenge(&self, user_id: &str, challenge_type: MfaChallengeType) -> Result<String, SecurityError> {
+        if !matches!(challenge_type, MfaChallengeType::Totp) {
+            return Err(SecurityError::ConfigurationError {
+                reason: "Only authenticator app codes are supported as a second factor".to_string(),
+            });
+        }
+        if !self.totp_enabled(user_id) {
+            return Err(SecurityError::MfaRequired {
+                reason: "Enroll an authenticator app before verifying".to_string(),
+            });
+        }
+
         let challenge_id = Uuid::new_v4().to_string();
         let challenge = MfaChallenge {
             challenge_id: challenge_id.clone(),
@@ -476,84 +800,227 @@ impl FirebaseAuthService {
         
         self.mfa_challenges.write().unwrap().insert(challenge_id.clone(), challenge);
         
-        // In practice, would send SMS/email or prepare TOTP verification
         log::info!("Started MFA challenge {} for user {}", challenge_id, user_id);
         Ok(challenge_id)
     }
     
-    /// Verify MFA challenge
+    /// User an MFA challenge was issued to
+    pub fn mfa_challenge_owner(&self, challenge_id: &str) -> Option<String> {
+        self.mfa_challenges.read().unwrap().get(challenge_id).map(|c| c.user_id.clone())
+    }
+
+    /// Verify MFA challenge against the user's enrolled factor
     pub async fn verify_mfa_challenge(&self, challenge_id: &str, code: &str) -> Result<bool, SecurityError> {
-        let mut challenges = self.mfa_challenges.write().unwrap();
-        let challenge = challenges.get_mut(challenge_id)
-            .ok_or_else(|| SecurityError::AuthenticationFailed { 
-                reason: "MFA challenge not found".to_string() 
-            })?;
+        let (user_id, challenge_type) = {
+            let mut challenges = self.mfa_challenges.write().unwrap();
+            let challenge = challenges.get_mut(challenge_id)
+                .ok_or_else(|| SecurityError::AuthenticationFailed { 
+                    reason: "MFA challenge not found".to_string() 
+                })?;
+            
+            if challenge.completed {
+                return Err(SecurityError::AuthenticationFailed { 
+                    reason: "MFA challenge already completed".to_string() 
+                });
+            }
+            
+            if Utc::now() > challenge.expires_at {
+                return Err(SecurityError::AuthenticationFailed { 
+                    reason: "MFA challenge expired".to_string() 
+                });
+            }
+            
+            if challenge.attempts >= challenge.max_attempts {
+                return Err(SecurityError::AuthenticationFailed { 
+                    reason: "Maximum MFA attempts exceeded".to_string() 
+                });
+            }
+            
+            challenge.attempts += 1;
+            (challenge.user_id.clone(), challenge.challenge_type.clone())
+        };
+
+        // Anything but an enrolled TOTP factor fails closed
+        let is_valid = match challenge_type {
+            MfaChallengeType::Totp => self.verify_totp(&user_id, code).await?,
+            _ => false,
+        };
         
-        if challenge.completed {
-            return Err(SecurityError::AuthenticationFailed { 
-                reason: "MFA challenge already completed".to_string() 
-            });
+        if is_valid {
+            if let Some(challenge) = self.mfa_challenges.write().unwrap().get_mut(challenge_id) {
+                challenge.completed = true;
+            }
+            log::info!("MFA challenge {} verified successfully for user {}", challenge_id, user_id);
         }
-        
-        if Utc::now() > challenge.expires_at {
-            return Err(SecurityError::AuthenticationFailed { 
-                reason: "MFA challenge expired".to_string() 
-            });
+        Ok(is_valid)
+    }
+    
+    /// Start TOTP enrollment with a fresh secret. The factor is enabled by the first code that
+    /// verifies; until then enrolling again replaces the pending secret. Replacing an enabled
+    /// factor needs a current code from it.
+    pub async fn enroll_totp(&self, user_id: &str, current_code: Option<&str>) -> Result<TotpEnrollment, SecurityError> {
+        let store = self.factor_store()?;
+        if self.totp_enabled(user_id) {
+            let code = current_code.ok_or_else(|| SecurityError::MfaRequired {
+                reason: "A code from the current authenticator is needed to replace it".to_string(),
+            })?;
+            if !self.verify_totp(user_id, code).await? {
+                return Err(SecurityError::AuthenticationFailed {
+                    reason: "Invalid code from the current authenticator".to_string(),
+                });
+            }
         }
-        
-        if challenge.attempts >= challenge.max_attempts {
-            return Err(SecurityError::AuthenticationFailed { 
-                reason: "Maximum MFA attempts exceeded".to_string() 
-            });
+
+        let secret = totp::generate_secret();
+        let sealed = wrap_with(&store.key, &secret, &totp_secret_aad(user_id))?;
+        self.totp_factors.write().unwrap().insert(
+            user_id.to_string(),
+            TotpFactor { secret: sealed, enabled: false, last_used_step: None },
+        );
+        self.persist_factors(&store)?;
+
+        log::info!("Started TOTP enrollment for user {}", user_id);
+        Ok(TotpEnrollment {
+            secret: totp::base32_encode(&secret),
+            otpauth_url: totp::otpauth_url(user_id, &secret),
+        })
+    }
+
+    /// Whether the user has a verified TOTP factor
+    pub fn totp_enabled(&self, user_id: &str) -> bool {
+        self.totp_factors.read().unwrap().get(user_id).is_some_and(|factor| factor.enabled)
+    }
+
+    /// Check a TOTP code, allowing one step of clock skew. A code from a step already used is
+    /// refused as a replay. A valid code enables a pending factor and marks the user's sessions
+    /// MFA-verified.
+    pub async fn verify_totp(&self, user_id: &str, code: &str) -> Result<bool, SecurityError> {
+        self.verify_totp_at(user_id, code, Utc::now()).await
+    }
+
+    async fn verify_totp_at(&self, user_id: &str, code: &str, now: DateTime<Utc>) -> Result<bool, SecurityError> {
+        let store = self.factor_store()?;
+        let sealed = self.totp_factors.read().unwrap().get(user_id)
+            .map(|factor| factor.secret.clone())
+            .ok_or_else(|| SecurityError::NotFound { reason: "No TOTP factor enrolled".to_string() })?;
+        let secret = unwrap_with(&store.key, &sealed, &totp_secret_aad(user_id))?;
+
+        let Some(step) = totp::matching_step(&secret, code, now.timestamp()) else {
+            log::warn!("Rejected TOTP code for user {}", user_id);
+            self.audit_second_factor(AuditEventType::Authentication, user_id, "totp_verify", AuditOutcome::Failure).await;
+            return Ok(false);
+        };
+
+        // `None` when the step was already used
+        let newly_enabled = {
+            let mut factors = self.totp_factors.write().unwrap();
+            let factor = factors.get_mut(user_id)
+                .ok_or_else(|| SecurityError::NotFound { reason: "No TOTP factor enrolled".to_string() })?;
+            if factor.last_used_step.is_some_and(|used| step <= used) {
+                None
+            } else {
+                factor.last_used_step = Some(step);
+                Some(!std::mem::replace(&mut factor.enabled, true))
+            }
+        };
+        let Some(newly_enabled) = newly_enabled else {
+            log::warn!("Replayed TOTP code for user {}", user_id);
+            self.audit_second_factor(AuditEventType::Authentication, user_id, "totp_replay", AuditOutcome::Denied).await;
+            return Err(SecurityError::AuthenticationFailed { reason: "TOTP code already used".to_string() });
+        };
+        // The used step must survive a restart or the code could be replayed after one
+        self.persist_factors(&store)?;
+
+        for session in self.sessions.write().unwrap().values_mut() {
+            if session.user_id.to_string() == user_id {
+                session.mfa_verified = true;
+            }
         }
-        
-        challenge.attempts += 1;
-        
-        // Verify code based on challenge type
-        let is_valid = match &challenge.challenge_type {
-            MfaChallengeType::Sms { .. } => {
-                // In practice, would verify SMS code
-                code.len() == 6 && code.chars().all(|c| c.is_numeric())
-            },
-            MfaChallengeType::Totp { secret_key: _secret_key } => {
-                // In practice, would verify TOTP code using secret_key
-                code.len() == 6 && code.chars().all(|c| c.is_numeric())
-            },
-            MfaChallengeType::Email { .. } => {
-                // In practice, would verify email token
-                code.len() >= 6
-            },
-            MfaChallengeType::SecurityKey { .. } => {
-                // In practice, would verify WebAuthn signature
-                !code.is_empty()
-            },
-            MfaChallengeType::BackupCode => {
-                // In practice, would verify against stored backup codes
-                code.len() >= 8
-            },
+
+        if newly_enabled {
+            log::info!("TOTP enabled for user {}", user_id);
+            self.audit_second_factor(AuditEventType::MfaEnabled, user_id, "totp_enabled", AuditOutcome::Success).await;
+        }
+        self.audit_second_factor(AuditEventType::Authentication, user_id, "totp_verify", AuditOutcome::Success).await;
+        Ok(true)
+    }
+
+    async fn audit_second_factor(&self, event_type: AuditEventType, user_id: &str, action: &str, outcome: AuditOutcome) {
+        let Some(audit) = &self.audit else {
+            return;
         };
-        
-        if is_valid {
-            challenge.completed = true;
-            
-            // Update session MFA status
+        let event = AuditEvent::new(event_type, Uuid::parse_str(user_id).ok(), action.to_string(), outcome);
+        if let Err(e) = audit.log_event(event).await {
+            log::warn!("Failed to audit {}: {}", action, e);
+        }
+    }
+
+    /// Make room for a new session under the role's `max_concurrent_sessions`. Expired and idle
+    /// sessions are purged first so they never count. At the limit the login is refused, or the
+    /// least recently active sessions are ended, depending on `session_quota_action`.
+    pub async fn enforce_session_quota(&self, user_id: Uuid, role: &HealthcareRole) -> Result<(), SecurityError> {
+        let now = Utc::now();
+        let evicted: Vec<SecuritySession> = {
             let mut sessions = self.sessions.write().unwrap();
-            for session in sessions.values_mut() {
-                if session.user_id.to_string() == challenge.user_id {
-                    session.mfa_verified = true;
+            let idle_timeout = self.config.idle_timeout();
+            sessions.retain(|_, session| session.is_valid_at(idle_timeout, now));
+
+            let Some(limit) = self.rate_limits.role_limits.get(role).map(|l| l.max_concurrent_sessions as usize) else {
+                return Ok(());
+            };
+            let mut own: Vec<&SecuritySession> = sessions.values().filter(|s| s.user_id == user_id).collect();
+            if own.len() < limit {
+                return Ok(());
+            }
+            if limit == 0 || self.rate_limits.session_quota_action == SessionQuotaAction::Reject {
+                return Err(SecurityError::AuthorizationDenied {
+                    reason: format!(
+                        "Concurrent session limit reached ({} of {} for role {}); sign out elsewhere first",
+                        own.len(), limit, role
+                    ),
+                });
+            }
+            own.sort_by_key(|s| s.last_activity);
+            let oldest: Vec<String> = own.iter().take(own.len() + 1 - limit).map(|s| s.session_id.to_string()).collect();
+            oldest.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+
+        for session in evicted {
+            let session_id = session.session_id.to_string();
+            self.forget_refresh_tokens(&session_id);
+            log::info!("Ended session {} of user {}: concurrent session limit for {} reached", session_id, user_id, role);
+            if let Some(audit) = &self.audit {
+                let mut event = AuditEvent::new(
+                    AuditEventType::UserLogout,
+                    Some(user_id),
+                    "session_evicted".to_string(),
+                    AuditOutcome::Success,
+                ).with_session(session_id, session.ip_address.clone(), session.user_agent.clone());
+                event.user_role = Some(role.clone());
+                event.description = "Least recently active session ended to stay within the concurrent session limit".to_string();
+                if let Err(e) = audit.log_event(event).await {
+                    log::warn!("Failed to audit evicted session: {}", e);
                 }
             }
-            
-            log::info!("MFA challenge {} verified successfully for user {}", challenge_id, challenge.user_id);
-            Ok(true)
-        } else {
-            Ok(false)
         }
+        Ok(())
     }
-    
+
+    /// Drop a session's refresh tokens, rotated ones included
+    fn forget_refresh_tokens(&self, session_id: &str) {
+        let mut families = self.refresh_families.write().unwrap();
+        for family in families.values_mut() {
+            family.current.remove(session_id);
+            family.rotated.retain(|_, (owner, _)| owner != session_id);
+        }
+        families.retain(|_, family| !family.current.is_empty() || !family.rotated.is_empty());
+    }
+
     /// End user session
     pub async fn end_session(&self, session_id: &str) -> Result<(), SecurityError> {
         self.sessions.write().unwrap().remove(session_id);
+        // Logging out forgets the session's refresh tokens
+        self.forget_refresh_tokens(session_id);
         log::info!("Ended session {}", session_id);
         Ok(())
     }
@@ -570,6 +1037,7 @@ impl FirebaseAuthService {
                 "system_admin".to_string(),
                 "audit_access".to_string(),
                 "security_config".to_string(),
+                "approve_data_export".to_string(),
             ],
             HealthcareRole::SuperAdmin => vec![
                 "view_phi".to_string(),
@@ -580,6 +1048,7 @@ impl FirebaseAuthService {
                 "system_admin".to_string(),
                 "audit_access".to_string(),
                 "security_config".to_string(),
+                "approve_data_export".to_string(),
             ],
             HealthcareRole::HealthcareProvider => vec![
                 "view_phi".to_string(),
@@ -609,6 +1078,7 @@ impl FirebaseAuthService {
                 "audit_access".to_string(),
                 "view_logs".to_string(),
                 "compliance_reports".to_string(),
+                "approve_data_export".to_string(),
             ],
             HealthcareRole::Patient => vec![
                 "view_own_data".to_string(),
@@ -647,7 +1117,17 @@ impl FirebaseAuthService {
         
         // Clean up expired sessions
         let mut sessions = self.sessions.write().unwrap();
-        sessions.retain(|_, session| session.expires_at > now);
+        let idle_timeout = self.config.idle_timeout();
+        sessions.retain(|_, session| session.is_valid_at(idle_timeout, now));
+
+        // Rotated tokens past their expiry could no longer be replayed anyway
+        let mut families = self.refresh_families.write().unwrap();
+        for family in families.values_mut() {
+            family.current.retain(|session_id, _| sessions.contains_key(session_id));
+            family.rotated.retain(|_, (_, exp)| *exp > now.timestamp());
+        }
+        families.retain(|_, family| !family.current.is_empty() || !family.rotated.is_empty());
+        drop(families);
         
         // Clean up expired MFA challenges
         let mut challenges = self.mfa_challenges.write().unwrap();
@@ -665,6 +1145,52 @@ impl FirebaseAuthService {
     pub fn get_session(&self, session_id: &str) -> Option<SecuritySession> {
         self.sessions.read().unwrap().get(session_id).cloned()
     }
+
+    /// Check a session is still active and record this use as activity
+    pub fn validate_session(&self, session_id: &str) -> Result<SecuritySession, SecurityError> {
+        self.session_tracker().validate(session_id, Utc::now())
+    }
+
+    /// Handle on the active sessions for checks made outside this service
+    pub fn session_tracker(&self) -> SessionTracker {
+        SessionTracker { sessions: self.sessions.clone(), idle_timeout: self.config.idle_timeout() }
+    }
+}
+
+/// Shared view of the active sessions that applies the absolute and idle timeouts
+#[derive(Debug, Clone)]
+pub struct SessionTracker {
+    sessions: Arc<RwLock<HashMap<String, SecuritySession>>>,
+    idle_timeout: Duration,
+}
+
+impl SessionTracker {
+    /// The session if it is still valid at `now`, with its last activity moved to `now`. An
+    /// expired session is removed.
+    pub fn validate(&self, session_id: &str, now: DateTime<Utc>) -> Result<SecuritySession, SecurityError> {
+        let mut sessions = self.sessions.write().unwrap();
+        let session = sessions.get_mut(session_id).ok_or_else(|| SecurityError::SessionExpired {
+            expired_at: now,
+            reason: "Session not found in active sessions".to_string(),
+        })?;
+        if !session.is_valid_at(self.idle_timeout, now) {
+            let expired_at = session.expired_at(self.idle_timeout);
+            let reason = if expired_at < session.expires_at {
+                "Session ended after inactivity"
+            } else {
+                "Session expired"
+            };
+            sessions.remove(session_id);
+            return Err(SecurityError::SessionExpired { expired_at, reason: reason.to_string() });
+        }
+        session.last_activity = now;
+        Ok(session.clone())
+    }
+
+    /// Whether the session was flagged at sign-in and has not completed MFA since
+    pub fn mfa_pending(&self, session_id: &str) -> bool {
+        self.sessions.read().unwrap().get(session_id).map_or(false, SecuritySession::mfa_pending)
+    }
 }
 
 /// Helper function to parse timestamp from Firebase
@@ -704,6 +1230,11 @@ fn parse_provider_data(provider_data: Option<&serde_json::Value>) -> Vec<Provide
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    /// Verified account with a fresh UUID as its uid
+    fn test_user(email: &str) -> FirebaseUser {
+        FirebaseUser { email_verified: true, ..FirebaseUser::signed_in(Uuid::new_v4().to_string(), email) }
+    }
     
     #[tokio::test]
     async fn test_firebase_auth_service_creation() {
@@ -766,6 +1297,323 @@ mod tests {
         assert!(admin_permissions.len() > provider_permissions.len());
         assert!(provider_permissions.len() > patient_permissions.len());
     }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_and_reuse_revokes_the_family() {
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        let user = test_user("clinician@example.com");
+        let first = service.create_session(&user, HealthcareRole::HealthcareProvider, None, None).await.unwrap();
+        let second = service.create_session(&user, HealthcareRole::HealthcareProvider, None, None).await.unwrap();
+        // An access token cannot be redeemed as a refresh token
+        assert!(service.refresh_session(&first.access_token).await.is_err());
+
+        let rotated = service.refresh_session(&first.refresh_token).await.unwrap();
+        assert_ne!(rotated.refresh_token, first.refresh_token);
+        assert_eq!(rotated.session_id, first.session_id);
+
+        let replay = service.refresh_session(&first.refresh_token).await.unwrap_err();
+        assert!(matches!(replay, SecurityError::AccessDenied { .. }));
+        assert_eq!(service.get_active_sessions_count(), 0);
+        assert!(service.refresh_session(&rotated.refresh_token).await.is_err());
+        assert!(service.refresh_session(&second.refresh_token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_firebase_uid_session_refreshes_with_longer_lived_token() {
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        let user = FirebaseUser::signed_in("kV3xQ9fTzWb2aYc8LmN0pR4sT6u1", "clinician@example.com");
+        let session = service.create_session(&user, HealthcareRole::HealthcareProvider, None, None).await.unwrap();
+        assert_eq!(session.user_id, stable_uuid(&user.uid));
+
+        let access = service.decode_claims(&session.access_token).unwrap();
+        let refresh = service.decode_claims(&session.refresh_token).unwrap();
+        assert!(refresh.exp > access.exp);
+        assert!(service.refresh_session(&session.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_session_quota_ignores_expired_and_evicts_oldest() {
+        let mut rate_limits = RateLimitConfig::default();
+        rate_limits.role_limits.get_mut(&HealthcareRole::Patient).unwrap().max_concurrent_sessions = 2;
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        ).with_rate_limits(rate_limits.clone());
+        let user = test_user("patient@example.com");
+
+        let stale = service.create_session(&user, HealthcareRole::Patient, None, None).await.unwrap();
+        let oldest = service.create_session(&user, HealthcareRole::Patient, None, None).await.unwrap();
+        let newest = service.create_session(&user, HealthcareRole::Patient, None, None).await.unwrap();
+        // `stale` was evicted as the least recently active; expire `oldest` so it no longer counts
+        assert!(service.get_session(&stale.session_id.to_string()).is_none());
+        service.sessions.write().unwrap().get_mut(&oldest.session_id.to_string()).unwrap().expires_at = Utc::now();
+        service.sessions.write().unwrap().get_mut(&newest.session_id.to_string()).unwrap().last_activity = Utc::now();
+
+        let latest = service.create_session(&user, HealthcareRole::Patient, None, None).await.unwrap();
+        assert!(service.get_session(&newest.session_id.to_string()).is_some());
+        assert!(service.get_session(&latest.session_id.to_string()).is_some());
+        assert_eq!(service.get_active_sessions_count(), 2);
+
+        rate_limits.session_quota_action = SessionQuotaAction::Reject;
+        let strict = service.with_rate_limits(rate_limits);
+        let err = strict.enforce_session_quota(Uuid::parse_str(&user.uid).unwrap(), &HealthcareRole::Patient).await.unwrap_err();
+        assert!(matches!(err, SecurityError::AuthorizationDenied { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_logout_clears_refresh_family() {
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        let user = test_user("clinician@example.com");
+        let session = service.create_session(&user, HealthcareRole::HealthcareProvider, None, None).await.unwrap();
+        let rotated = service.refresh_session(&session.refresh_token).await.unwrap();
+
+        service.end_session_for_token(&rotated.access_token).await.unwrap();
+        assert!(service.refresh_families.read().unwrap().is_empty());
+        // After logout an old token is simply unknown rather than a breach
+        let err = service.refresh_session(&session.refresh_token).await.unwrap_err();
+        assert!(matches!(err, SecurityError::InvalidToken { .. }));
+    }
+
+    #[test]
+    fn test_locked_session_keeps_identity() {
+        let mut state = AuthState::new();
+        assert!(state.lock().is_none());
+
+        state.set_authenticated(
+            "user-1".to_string(),
+            "access".to_string(),
+            "refresh".to_string(),
+            HealthcareRole::HealthcareProvider,
+            vec!["view_phi".to_string()],
+            Utc::now() + Duration::hours(1),
+        );
+        assert_eq!(state.lock().as_deref(), Some("user-1"));
+        assert!(state.is_locked());
+        assert!(!state.has_permission("view_phi"));
+        assert_eq!(state.user_id.as_deref(), Some("user-1"));
+
+        state.unlock();
+        assert!(!state.is_locked());
+        assert!(state.has_permission("view_phi"));
+    }
+
+    async fn session_for_tracking(service: &FirebaseAuthService) -> SecuritySession {
+        let user = test_user("clinician@example.com");
+        service.create_session(&user, HealthcareRole::HealthcareProvider, None, None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_ends_at_absolute_expiry_despite_activity() {
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        let session = session_for_tracking(&service).await;
+        let tracker = service.session_tracker();
+        let id = session.session_id.to_string();
+
+        // Steady use keeps the session from idling out, but not past its expiry
+        let mut now = session.created_at;
+        while now + Duration::minutes(30) < session.expires_at {
+            now = now + Duration::minutes(30);
+            tracker.validate(&id, now).unwrap();
+        }
+        let err = tracker.validate(&id, session.expires_at).unwrap_err();
+        assert!(matches!(err, SecurityError::SessionExpired { expired_at, ref reason }
+            if expired_at == session.expires_at && reason == "Session expired"));
+        assert!(service.get_session(&id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_ends_after_idle_timeout() {
+        let mut service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        service.config.session_timeout_seconds = 15 * 60;
+        service.config.jwt_expiry_seconds = 8 * 3600;
+        let session = session_for_tracking(&service).await;
+        let tracker = service.session_tracker();
+        let id = session.session_id.to_string();
+
+        let used = session.last_activity + Duration::minutes(10);
+        assert_eq!(tracker.validate(&id, used).unwrap().last_activity, used);
+        // Idle time counts from the last use, not from sign-in
+        assert!(tracker.validate(&id, used + Duration::minutes(14)).is_ok());
+        let idle_until = used + Duration::minutes(14) + Duration::minutes(15);
+        let err = tracker.validate(&id, idle_until).unwrap_err();
+        assert!(matches!(err, SecurityError::SessionExpired { expired_at, ref reason }
+            if expired_at == idle_until && reason == "Session ended after inactivity"));
+        assert!(service.get_session(&id).is_none());
+    }
+
+    /// Places each IP at a fixed city
+    struct FixedGeoResolver(HashMap<&'static str, GeoLocation>);
+
+    #[async_trait::async_trait]
+    impl GeoResolver for FixedGeoResolver {
+        async fn resolve(&self, ip: &str) -> Result<Option<GeoLocation>, String> {
+            Ok(self.0.get(ip).cloned())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_impossible_travel_flags_session_and_requires_mfa() {
+        let place = |city: &str, latitude, longitude| GeoLocation {
+            latitude,
+            longitude,
+            city: Some(city.to_string()),
+            country: None,
+        };
+        let resolver = FixedGeoResolver(HashMap::from([
+            ("198.51.100.7", place("Montreal", 45.50, -73.57)),
+            ("203.0.113.9", place("Paris", 48.86, 2.35)),
+        ]));
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        )
+        .with_geo_resolver(Arc::new(resolver));
+        let user = test_user("clinician@example.com");
+
+        let home = service
+            .create_session(&user, HealthcareRole::HealthcareProvider, Some("198.51.100.7".to_string()), None)
+            .await
+            .unwrap();
+        assert_eq!(home.location.as_deref(), Some("Montreal"));
+        assert!(home.security_metadata["suspicious_location"].is_null());
+        assert!(!home.requires_mfa("view_phi"));
+
+        // Paris moments after Montreal cannot be the same person travelling
+        let abroad = service
+            .create_session(&user, HealthcareRole::HealthcareProvider, Some("203.0.113.9".to_string()), None)
+            .await
+            .unwrap();
+        assert_eq!(abroad.location.as_deref(), Some("Paris"));
+        assert_eq!(abroad.security_metadata["suspicious_location"]["previous"]["city"], "Montreal");
+        assert!(abroad.requires_mfa("view_phi"));
+    }
+
+    #[tokio::test]
+    async fn test_totp_enrollment_verifies_once_per_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        service.attach_factor_storage(dir.path()).unwrap();
+        let user_id = Uuid::new_v4().to_string();
+
+        let enrollment = service.enroll_totp(&user_id, None).await.unwrap();
+        assert!(enrollment.otpauth_url.contains(&format!("secret={}", enrollment.secret)));
+        assert!(!service.totp_enabled(&user_id));
+
+        // The stored secret is sealed, not the base32 handed to the user
+        let stored = service.totp_factors.read().unwrap()[&user_id].secret.clone();
+        assert_ne!(stored, enrollment.secret);
+        let key = std::fs::read(dir.path().join(FACTOR_KEY_FILE)).unwrap();
+        let secret = unwrap_with(&key, &stored, &totp_secret_aad(&user_id)).unwrap();
+
+        let now = Utc::now();
+        let code = totp::code_at_step(&secret, totp::step_at(now.timestamp()));
+        assert!(service.verify_totp_at(&user_id, &code, now).await.unwrap());
+        assert!(service.totp_enabled(&user_id));
+
+        // The same code cannot be used twice, even within its window
+        let replay = service.verify_totp_at(&user_id, &code, now).await.unwrap_err();
+        assert!(matches!(replay, SecurityError::AuthenticationFailed { .. }));
+
+        let later = now + Duration::seconds(totp::STEP_SECONDS);
+        let next = totp::code_at_step(&secret, totp::step_at(later.timestamp()));
+        assert!(service.verify_totp_at(&user_id, &next, later).await.unwrap());
+        assert!(service.enroll_totp(&user_id, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mfa_challenges_need_the_enrolled_factor() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = FirebaseAuthService::new(
+            "test-project".to_string(),
+            "test-api-key".to_string(),
+            b"test-jwt-secret-key-for-testing-purposes",
+        );
+        service.attach_factor_storage(dir.path()).unwrap();
+        let user_id = Uuid::new_v4().to_string();
+
+        // Nothing to check a code against, and no delivery for email codes
+        assert!(service.start_mfa_challenge(&user_id, MfaChallengeType::Totp).await.is_err());
+        let email = MfaChallengeType::Email { email_address: "anyone@example.com".to_string() };
+        assert!(service.start_mfa_challenge(&user_id, email).await.is_err());
+
+        service.enroll_totp(&user_id, None).await.unwrap();
+        let stored = service.totp_factors.read().unwrap()[&user_id].secret.clone();
+        let key = std::fs::read(dir.path().join(FACTOR_KEY_FILE)).unwrap();
+        let secret = unwrap_with(&key, &stored, &totp_secret_aad(&user_id)).unwrap();
+        let now = Utc::now();
+        let previous = totp::code_at_step(&secret, totp::step_at(now.timestamp()) - 1);
+        assert!(service.verify_totp_at(&user_id, &previous, now).await.unwrap());
+
+        let challenge_id = service.start_mfa_challenge(&user_id, MfaChallengeType::Totp).await.unwrap();
+        // Any six digits used to pass
+        let wrong = if previous == "123456" { "654321" } else { "123456" };
+        assert!(!service.verify_mfa_challenge(&challenge_id, wrong).await.unwrap());
+        let current = totp::code_at_step(&secret, totp::step_at(Utc::now().timestamp()));
+        assert!(service.verify_mfa_challenge(&challenge_id, &current).await.unwrap());
+        assert!(service.verify_mfa_challenge(&challenge_id, &current).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_totp_factor_survives_restart_and_blocks_re_enrollment() {
+        let dir = tempfile::tempdir().unwrap();
+        let new_service = || {
+            let service = FirebaseAuthService::new(
+                "test-project".to_string(),
+                "test-api-key".to_string(),
+                b"test-jwt-secret-key-for-testing-purposes",
+            );
+            service.attach_factor_storage(dir.path()).unwrap();
+            service
+        };
+        let user_id = Uuid::new_v4().to_string();
+
+        let first = new_service();
+        first.enroll_totp(&user_id, None).await.unwrap();
+        let stored = first.totp_factors.read().unwrap()[&user_id].secret.clone();
+        let key = std::fs::read(dir.path().join(FACTOR_KEY_FILE)).unwrap();
+        let secret = unwrap_with(&key, &stored, &totp_secret_aad(&user_id)).unwrap();
+        let now = Utc::now();
+        let code = totp::code_at_step(&secret, totp::step_at(now.timestamp()));
+        assert!(first.verify_totp_at(&user_id, &code, now).await.unwrap());
+        drop(first);
+
+        let restarted = new_service();
+        assert!(restarted.totp_enabled(&user_id));
+        // The used step was persisted too
+        assert!(restarted.verify_totp_at(&user_id, &code, now).await.is_err());
+        let refused = restarted.enroll_totp(&user_id, None).await.unwrap_err();
+        assert!(matches!(refused, SecurityError::MfaRequired { .. }));
+        assert!(restarted.enroll_totp(&user_id, Some("000000")).await.is_err());
+
+        // A factor sealed for one user does not open for another
+        assert!(unwrap_with(&key, &stored, &totp_secret_aad("someone-else")).is_err());
+    }
 }
 
 /// Authentication state for Tauri application
@@ -778,6 +1626,15 @@ pub struct AuthState {
     pub role: Option<HealthcareRole>,
     pub permissions: Vec<String>,
     pub session_expires_at: Option<DateTime<Utc>>,
+    /// Set while the workstation is locked; the session is kept but unusable until re-authentication
+    pub locked_at: Option<DateTime<Utc>>,
+    /// When the user last completed an MFA challenge in this session
+    pub mfa_verified_at: Option<DateTime<Utc>>,
+    /// Auth service session backing this sign-in; patient data access is checked against it
+    pub session_id: Option<String>,
+    /// Set when sign-in was flagged (e.g. impossible travel); commands that need a permission
+    /// are refused until an MFA challenge is completed
+    pub mfa_required: bool,
 }
 
 impl AuthState {
@@ -791,6 +1648,10 @@ impl AuthState {
             role: None,
             permissions: Vec::new(),
             session_expires_at: None,
+            locked_at: None,
+            mfa_verified_at: None,
+            session_id: None,
+            mfa_required: false,
         }
     }
 
@@ -811,6 +1672,7 @@ impl AuthState {
         self.role = Some(role);
         self.permissions = permissions;
         self.session_expires_at = Some(expires_at);
+        self.mfa_verified_at = None;
     }
 
     /// Clear authentication state
@@ -822,6 +1684,60 @@ impl AuthState {
         self.role = None;
         self.permissions.clear();
         self.session_expires_at = None;
+        self.locked_at = None;
+        self.mfa_verified_at = None;
+        self.session_id = None;
+        self.mfa_required = false;
+    }
+
+    /// Suspend an active session; returns the user ID if a session was locked.
+    /// Identity and tokens are kept so the same user can resume without losing drafts.
+    pub fn lock(&mut self) -> Option<String> {
+        if !self.is_authenticated {
+            return None;
+        }
+        self.is_authenticated = false;
+        self.locked_at = Some(Utc::now());
+        self.user_id.clone()
+    }
+
+    /// Discard the session's tokens for an emergency data lock. Identity is kept so only the
+    /// same user can unlock; returns that user's ID.
+    pub fn revoke(&mut self) -> Option<String> {
+        self.access_token = None;
+        self.refresh_token = None;
+        self.session_expires_at = None;
+        self.is_authenticated = false;
+        self.locked_at.get_or_insert_with(Utc::now);
+        self.user_id.clone()
+    }
+
+    /// Whether the session is waiting for re-authentication
+    pub fn is_locked(&self) -> bool {
+        self.locked_at.is_some()
+    }
+
+    /// Resume a locked session after the user has re-authenticated
+    pub fn unlock(&mut self) {
+        if self.locked_at.take().is_some() {
+            self.is_authenticated = true;
+        }
+    }
+
+    /// Record a successful MFA challenge for this session
+    pub fn mark_mfa_verified(&mut self) {
+        self.mfa_verified_at = Some(Utc::now());
+    }
+
+    /// Whether a flagged sign-in is still waiting for its MFA challenge
+    pub fn mfa_pending(&self) -> bool {
+        self.mfa_required && self.mfa_verified_at.is_none()
+    }
+
+    /// Whether MFA was completed within the last `window_minutes`
+    pub fn mfa_verified_within(&self, window_minutes: i64, now: DateTime<Utc>) -> bool {
+        self.mfa_verified_at
+            .map_or(false, |at| now - at <= chrono::Duration::minutes(window_minutes))
     }
 
     /// Check if session is expired
@@ -908,7 +1824,7 @@ pub fn create_session_token(
 
     // Create JWT token
     let jwt_secret = std::env::var("JWT_SECRET")
-        .unwrap_or_else(|_| "default-dev-secret-change-in-production".to_string());
+        .unwrap_or_else(|_| crate::security::readiness::DEV_JWT_SECRET.to_string());
 
     let encoding_key = EncodingKey::from_secret(jwt_secret.as_bytes());
 
@@ -0,0 +1,651 @@
+// Command Authorization Policy for PsyPsy CMS
+// Declarative table of what every Tauri command requires (sign-in, permission, MFA, elevated
+// access), enforced by one guard before dispatch. Commands missing from the table are refused
+// in release builds, so a new command cannot ship without an authorization decision.
+
+use crate::commands::security_commands::RbacServiceState;
+use crate::security::auth::AuthState;
+use crate::security::rate_limit::{RateLimitContext, RateLimitServiceState};
+use crate::security::rbac::{stable_uuid, Permission, PermissionCategory, RbacService};
+use crate::security::session_binding;
+use crate::security::step_up::StepUpState;
+use crate::security::SecurityError;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+use tokio::sync::RwLock;
+
+/// What a command needs before it may run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandRequirement {
+    /// Callable without a session (login, lock screen, diagnostics)
+    #[serde(default)]
+    pub public: bool,
+    /// Permission the caller's role must hold; `None` means any signed-in user
+    #[serde(default)]
+    pub permission: Option<Permission>,
+    /// MFA must have been completed within the policy's window
+    #[serde(default)]
+    pub mfa: bool,
+    /// Caller must hold an active break-glass or delegation grant covering the permission
+    #[serde(default)]
+    pub elevation: bool,
+    /// Only callable while a session is paused by the lock screen (resuming it)
+    #[serde(default)]
+    pub locked: bool,
+}
+
+impl CommandRequirement {
+    fn public() -> Self {
+        Self { public: true, permission: None, mfa: false, elevation: false, locked: false }
+    }
+
+    fn signed_in() -> Self {
+        Self { public: false, permission: None, mfa: false, elevation: false, locked: false }
+    }
+
+    fn locked_session() -> Self {
+        Self { locked: true, ..Self::signed_in() }
+    }
+
+    fn needs(permission: Permission) -> Self {
+        Self { permission: Some(permission), ..Self::signed_in() }
+    }
+
+    fn needs_mfa(permission: Permission) -> Self {
+        Self { mfa: true, ..Self::needs(permission) }
+    }
+}
+
+/// Built-in requirements for every registered command. Keep entries grouped as in
+/// `generate_handler!`; a test fails when a registered command has no entry.
+fn builtin_requirements() -> Vec<(&'static str, CommandRequirement)> {
+    use CommandRequirement as R;
+    use Permission as P;
+    vec![
+        // Core system
+        ("greet", R::public()),
+        ("get_system_info", R::public()),
+        ("get_compliance_status", R::public()),
+
+        // Authentication; these run before or without a usable session
+        ("auth_login", R::public()),
+        ("auth_logout", R::public()),
+        ("auth_refresh_token", R::public()),
+        ("auth_get_current_user", R::signed_in()),
+        ("auth_update_profile", R::signed_in()),
+        ("auth_change_password", R::signed_in()),
+        ("auth_request_password_reset", R::public()),
+        ("auth_verify_token", R::public()),
+        ("auth_check_status", R::public()),
+        ("auth_start_unlock_mfa", R::locked_session()),
+        ("auth_unlock_session", R::locked_session()),
+        ("lock_all_data", R::public()),
+        ("get_data_lock_status", R::public()),
+        ("unlock_all_data", R::public()),
+        ("store_session", R::signed_in()),
+        ("get_stored_session", R::public()),
+        ("clear_stored_session", R::public()),
+
+        // User management
+        ("create_user", R::needs(P::CreateUser)),
+        ("get_user_by_id", R::signed_in()),
+        ("update_user_profile", R::signed_in()),
+        ("record_user_login", R::signed_in()),
+        ("suspend_user", R::needs(P::ModifyUser)),
+        ("reactivate_user", R::needs(P::ModifyUser)),
+        ("get_user_display_name", R::signed_in()),
+        ("check_user_availability", R::signed_in()),
+
+        // Clients
+        ("get_clients", R::needs(P::ViewDemographics)),
+        ("get_client", R::needs(P::ViewDemographics)),
+        ("create_client", R::needs(P::CreatePatientRecord)),
+        ("update_client", R::needs(P::ModifyDemographics)),
+        ("delete_client", R::needs_mfa(P::DeletePHI)),
+        ("search_clients", R::needs(P::ViewDemographics)),
+        ("get_client_appointments", R::needs(P::ViewSchedule)),
+        ("assign_professional_to_client", R::needs(P::ModifyDemographics)),
+        ("get_client_stats", R::needs(P::ViewDemographics)),
+        ("unassign_professional_from_client", R::needs(P::ModifyDemographics)),
+        ("increment_client_appointments", R::needs(P::ModifySchedule)),
+        ("check_client_active_status", R::needs(P::ViewDemographics)),
+        ("find_duplicate_clients", R::needs(P::ViewPHI)),
+        ("merge_clients", R::needs_mfa(P::DeletePHI)),
+        ("unmerge_clients", R::needs_mfa(P::DeletePHI)),
+        ("get_client_display_name", R::needs(P::ViewDemographics)),
+
+        // Professionals
+        ("get_professionals", R::signed_in()),
+        ("get_professional", R::signed_in()),
+        ("create_professional", R::needs(P::CreateUser)),
+        ("update_professional", R::needs(P::ModifyUser)),
+        ("delete_professional", R::needs_mfa(P::DeleteUser)),
+        ("search_professionals", R::signed_in()),
+        ("get_professional_clients", R::needs(P::ViewDemographics)),
+        ("get_professional_appointments", R::needs(P::ViewSchedule)),
+        ("get_professional_stats", R::signed_in()),
+        ("update_professional_verification", R::needs(P::ModifyUser)),
+        ("list_specialties", R::signed_in()),
+        ("map_legacy_specialties", R::signed_in()),
+        ("upsert_specialty", R::needs_mfa(P::SystemConfiguration)),
+        ("check_professional_active_status", R::signed_in()),
+        ("get_professional_display_name", R::signed_in()),
+
+        // Appointments
+        ("get_appointments", R::needs(P::ViewSchedule)),
+        ("get_appointment", R::needs(P::ViewSchedule)),
+        ("create_appointment", R::needs(P::CreateAppointment)),
+        ("create_recurring_appointments", R::needs(P::CreateAppointment)),
+        ("update_appointment", R::needs(P::ModifySchedule)),
+        ("cancel_appointment", R::needs(P::CancelAppointment)),
+        ("complete_appointment", R::needs(P::ModifySchedule)),
+        ("delete_appointment", R::needs(P::ModifySchedule)),
+        ("search_appointments", R::needs(P::ViewSchedule)),
+        ("get_appointments_by_date_range", R::needs(P::ViewSchedule)),
+        ("get_schedule_conflicts", R::needs(P::ViewSchedule)),
+        ("get_available_slots", R::needs(P::ViewSchedule)),
+        ("get_todays_appointments", R::needs(P::ViewSchedule)),
+        ("get_appointment_stats", R::needs(P::ViewSchedule)),
+        ("reschedule_appointment", R::needs(P::RescheduleAppointment)),
+
+        // Dashboards filter by role themselves
+        ("get_dashboard_stats", R::signed_in()),
+        ("get_client_dashboard_stats", R::signed_in()),
+        ("get_professional_dashboard_stats", R::signed_in()),
+        ("get_appointment_dashboard_stats", R::signed_in()),
+        ("invalidate_dashboard_cache", R::signed_in()),
+        ("get_system_health_stats", R::needs(P::ViewSystemLogs)),
+        ("list_deidentified_report_runs", R::needs(P::ViewStatistics)),
+
+        // MFA step-up answers a challenge raised mid-session
+        ("start_mfa_stepup", R::signed_in()),
+        ("complete_mfa_stepup", R::signed_in()),
+        ("enroll_totp", R::signed_in()),
+        ("verify_totp", R::signed_in()),
+
+        // Global search filters each entity type by the caller's role and scope
+        ("global_search", R::signed_in()),
+        ("get_patient_timeline", R::needs(P::ViewPatientHistory)),
+        ("set_access_notifications", R::needs(P::ViewDemographics)),
+        ("get_my_access_log", R::needs(P::ViewDemographics)),
+
+        // Reminder templates
+        ("get_reminder_template", R::needs(P::ViewSchedule)),
+        ("check_reminder_template", R::needs(P::ViewSchedule)),
+        ("save_reminder_template", R::needs(P::ModifySchedule)),
+
+        // Data export approval
+        ("request_data_export", R::needs_mfa(P::ExportPHI)),
+        ("list_export_requests", R::needs(P::ViewAuditLogs)),
+        ("decide_export_request", R::needs_mfa(P::ExportPHI)),
+        ("run_approved_export", R::needs_mfa(P::ExportPHI)),
+
+        // Event replay checks the caller's role per event
+        ("get_events_since", R::signed_in()),
+
+        // Security oversight
+        ("list_active_elevated_grants", R::needs(P::ViewAuditLogs)),
+        ("request_break_glass", R::needs_mfa(P::ViewPHI)),
+        ("terminate_grant", R::needs(P::ManageUserSessions)),
+        ("reload_firebase_credentials", R::needs_mfa(P::SecuritySettings)),
+        ("get_effective_security_config", R::needs(P::ViewSecurityReports)),
+        ("list_error_reports", R::needs(P::ViewSystemLogs)),
+        ("clear_error_reports", R::needs(P::SystemMaintenance)),
+        ("create_key_escrow", R::needs_mfa(P::BackupRestore)),
+        ("get_key_escrow_manifest", R::needs(P::ViewSecurityReports)),
+        ("recover_master_key", R::needs_mfa(P::BackupRestore)),
+        ("get_justification_policy", R::needs(P::ViewSecurityReports)),
+        ("update_justification_policy", R::needs(P::ComplianceConfiguration)),
+        ("list_audit_export_profiles", R::needs(P::ViewAuditLogs)),
+        ("export_audit_log", R::needs_mfa(P::ExportAuditLogs)),
+        ("verify_record_encryption", R::needs(P::ViewSecurityReports)),
+        ("get_access_heatmap", R::needs(P::GenerateComplianceReports)),
+        ("query_audit_events", R::needs(P::ViewAuditLogs)),
+        ("sanitize_input", R::signed_in()),
+        ("list_active_bans", R::needs(P::ViewSecurityReports)),
+        ("unban_ip", R::needs(P::ManageUserSessions)),
+        ("unban_user", R::needs(P::ManageUserSessions)),
+
+        // Medical notes
+        ("initialize_encrypted_storage", R::needs(P::ViewClinicalNotes)),
+        ("save_medical_note", R::needs(P::CreateClinicalNotes)),
+        ("get_medical_note", R::needs(P::ViewClinicalNotes)),
+        ("get_note_history", R::needs(P::ViewClinicalNotes)),
+        ("get_note_version", R::needs(P::ViewClinicalNotes)),
+        ("list_patient_notes", R::needs(P::ViewClinicalNotes)),
+        ("delete_medical_note", R::needs_mfa(P::DeletePHI)),
+        ("get_audit_trail", R::needs(P::ViewClinicalNotes)),
+        ("create_medical_note", R::needs(P::CreateClinicalNotes)),
+        ("validate_note_compliance", R::signed_in()),
+        ("storage_status", R::signed_in()),
+        ("reclassify_medical_note", R::needs(P::CreateClinicalNotes)),
+        ("reencrypt_notes_for_classification", R::needs_mfa(P::SecuritySettings)),
+        ("get_reencryption_job", R::needs(P::ViewClinicalNotes)),
+        ("attach_note_media", R::needs(P::CreateClinicalNotes)),
+        ("list_note_attachments", R::needs(P::ViewClinicalNotes)),
+
+        // Offline sync
+        ("initialize_sync_service", R::signed_in()),
+        ("perform_manual_sync", R::signed_in()),
+        ("get_sync_status", R::signed_in()),
+        ("set_sync_enabled", R::signed_in()),
+        ("force_sync_note", R::needs(P::ViewClinicalNotes)),
+        ("get_conflict_notes", R::needs(P::ViewClinicalNotes)),
+        ("resolve_conflict_manually", R::needs(P::CreateClinicalNotes)),
+        ("check_network_connectivity", R::public()),
+        ("get_pending_sync_count", R::signed_in()),
+        ("start_background_sync", R::signed_in()),
+        ("stop_background_sync", R::signed_in()),
+        ("get_sync_schedule", R::signed_in()),
+        ("update_sync_schedule", R::needs(P::ModifySystemSettings)),
+        ("sync_now", R::signed_in()),
+
+        // Social media and consent
+        ("get_social_media_connections", R::signed_in()),
+        ("get_oauth_configs", R::signed_in()),
+        ("save_oauth_config", R::needs(P::ManageIntegrations)),
+        ("record_social_media_consent", R::signed_in()),
+        ("withdraw_social_media_consent", R::signed_in()),
+        ("record_consent", R::signed_in()),
+        ("withdraw_consent", R::signed_in()),
+        ("export_consent_receipts", R::signed_in()),
+        ("verify_consent_receipts", R::signed_in()),
+        ("initiate_oauth_flow", R::signed_in()),
+        ("disconnect_platform", R::signed_in()),
+        ("get_connected_platforms", R::signed_in()),
+        ("validate_post_compliance", R::signed_in()),
+        ("detect_phi_in_content", R::signed_in()),
+        ("calculate_compliance_metrics", R::signed_in()),
+        ("publish_social_media_post", R::signed_in()),
+        ("schedule_social_media_post", R::signed_in()),
+        ("get_scheduled_posts", R::signed_in()),
+        ("get_published_posts", R::signed_in()),
+
+        // Meetings and recordings
+        ("start_recording", R::needs(P::CreateClinicalNotes)),
+        ("stop_recording", R::signed_in()),
+        ("force_stop_recording", R::needs(P::ManageUserSessions)),
+        ("get_recording_owner", R::signed_in()),
+        ("is_recording", R::signed_in()),
+        ("list_input_devices", R::signed_in()),
+        ("get_transcription_status", R::signed_in()),
+        ("save_transcript", R::needs(P::CreateClinicalNotes)),
+        ("load_transcript", R::needs(P::ViewPHI)),
+        ("export_redacted_transcript", R::needs(P::ViewClinicalNotes)),
+        ("list_expiring_media", R::needs(P::DataRetentionManagement)),
+        ("run_media_retention_purge", R::needs(P::DataRetentionManagement)),
+        ("place_media_legal_hold", R::needs(P::DataRetentionManagement)),
+        ("release_media_legal_hold", R::needs(P::DataRetentionManagement)),
+        ("list_media_legal_holds", R::needs(P::DataRetentionManagement)),
+
+        // DevTools and profiling
+        ("log_to_devtools", R::public()),
+        ("initialize_devtools", R::public()),
+        ("get_devtools_status", R::public()),
+        ("set_devtools_filter", R::public()),
+        ("set_profiling_enabled", R::needs(P::SystemMaintenance)),
+        ("get_performance_report", R::needs(P::ViewPerformanceMetrics)),
+        ("reset_performance_report", R::needs(P::SystemMaintenance)),
+        ("record_command_latencies", R::public()),
+    ]
+}
+
+/// Per-command authorization requirements, consulted before every dispatch
+#[derive(Debug, Clone)]
+pub struct CommandPolicy {
+    requirements: HashMap<String, CommandRequirement>,
+    /// How long a completed MFA challenge satisfies MFA-gated commands
+    pub mfa_window_minutes: i64,
+    /// Refuse commands without an entry; always on in release builds
+    pub fail_closed: bool,
+}
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        Self {
+            requirements: builtin_requirements()
+                .into_iter()
+                .map(|(name, requirement)| (name.to_string(), requirement))
+                .collect(),
+            mfa_window_minutes: 15,
+            fail_closed: !cfg!(debug_assertions),
+        }
+    }
+}
+
+impl CommandPolicy {
+    /// Built-in table with `COMMAND_POLICY_OVERRIDES` (JSON object of command name to
+    /// requirement) and `COMMAND_MFA_WINDOW_MINUTES` overrides. Overrides cannot make a
+    /// command public.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(minutes) = std::env::var("COMMAND_MFA_WINDOW_MINUTES") {
+            match minutes.trim().parse::<i64>() {
+                Ok(m) if m > 0 => policy.mfa_window_minutes = m,
+                _ => log::warn!("Ignoring invalid COMMAND_MFA_WINDOW_MINUTES '{}'", minutes),
+            }
+        }
+        if let Ok(overrides) = std::env::var("COMMAND_POLICY_OVERRIDES") {
+            match serde_json::from_str::<HashMap<String, CommandRequirement>>(&overrides) {
+                Ok(overrides) => {
+                    for (command, requirement) in overrides {
+                        if requirement.public && !policy.requirement(&command).map_or(false, |r| r.public) {
+                            log::warn!("Ignoring COMMAND_POLICY_OVERRIDES entry that would make '{}' public", command);
+                            continue;
+                        }
+                        policy.requirements.insert(command, requirement);
+                    }
+                }
+                Err(e) => log::warn!("Ignoring invalid COMMAND_POLICY_OVERRIDES: {}", e),
+            }
+        }
+        policy
+    }
+
+    pub fn requirement(&self, command: &str) -> Option<&CommandRequirement> {
+        self.requirements.get(command)
+    }
+
+    /// The whole table, sorted by command name, for review
+    pub fn entries(&self) -> Vec<(&str, &CommandRequirement)> {
+        let mut entries: Vec<_> = self.requirements.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+
+    /// Decide whether the current session may run `command`
+    pub fn authorize(
+        &self,
+        command: &str,
+        auth: &AuthState,
+        rbac: &RbacService,
+        now: DateTime<Utc>,
+    ) -> Result<(), SecurityError> {
+        let requirement = match self.requirement(command) {
+            Some(requirement) => requirement,
+            None if self.fail_closed => {
+                return Err(SecurityError::AuthorizationDenied {
+                    reason: format!("Command '{}' has no declared authorization requirement", command),
+                });
+            }
+            None => {
+                log::warn!("Command '{}' has no entry in the command policy; allowed in debug builds only", command);
+                return Ok(());
+            }
+        };
+        if requirement.public {
+            return Ok(());
+        }
+        if requirement.locked {
+            return match auth.user_id {
+                Some(_) if auth.is_locked() => Ok(()),
+                _ => Err(SecurityError::AuthenticationFailed { reason: "No locked session to resume".to_string() }),
+            };
+        }
+
+        if !auth.is_authenticated || auth.is_session_expired() {
+            return Err(SecurityError::AuthenticationFailed { reason: "Unauthorized".to_string() });
+        }
+        let role = auth.get_role().ok_or_else(|| SecurityError::AuthorizationDenied {
+            reason: "No role in session".to_string(),
+        })?;
+        let user_id = auth.user_id.as_deref().unwrap_or_default();
+
+        if let Some(permission) = &requirement.permission {
+            if !rbac.role_grants(role, permission) {
+                return Err(SecurityError::AuthorizationDenied {
+                    reason: format!("Role {} lacks {:?} required by '{}'", role, permission, command),
+                });
+            }
+        }
+        if requirement.permission.is_some() && auth.mfa_pending() {
+            return Err(SecurityError::AuthorizationDenied {
+                reason: format!("Sign-in was flagged; complete multi-factor authentication before '{}'", command),
+            });
+        }
+        if requirement.mfa && !auth.mfa_verified_within(self.mfa_window_minutes, now) {
+            return Err(SecurityError::AuthorizationDenied {
+                reason: format!("Multi-factor authentication required for '{}'", command),
+            });
+        }
+        if requirement.elevation {
+            let elevated = rbac.list_active_elevated_grants().iter().any(|grant| {
+                grant.grantee_user_id == user_id
+                    && grant.is_active_at(now)
+                    && requirement.permission.as_ref().map_or(true, |p| grant.scope.contains(p))
+            });
+            if !elevated {
+                return Err(SecurityError::AuthorizationDenied {
+                    reason: format!("Elevated access required for '{}'", command),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rate limit context for an invoke. The PHI, export and audit flags come from the permission
+/// the policy table requires, so the HIPAA budgets count what the command is declared to touch.
+fn rate_limit_context(policy: &CommandPolicy, command: &str, auth: &AuthState, now: DateTime<Utc>) -> RateLimitContext {
+    use Permission as P;
+    let permission = policy.requirement(command).and_then(|requirement| requirement.permission.as_ref());
+    let accesses_phi = permission.map_or(false, |p| p.category() == PermissionCategory::PatientData);
+    let reads_only = matches!(
+        permission,
+        Some(P::ViewPHI | P::ViewPatientHistory | P::ViewDemographics | P::ViewClinicalNotes | P::ViewLabResults
+            | P::ViewMedications | P::ViewAllergies | P::ViewInsuranceInfo)
+    );
+    let session = auth.user_id.as_deref().zip(auth.get_role()).filter(|_| auth.is_authenticated);
+    RateLimitContext {
+        user_id: session.map(|(user_id, _)| stable_uuid(user_id)),
+        user_role: session.map(|(_, role)| role.clone()),
+        // Invokes come from this machine's webview
+        ip_address: std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
+        endpoint: command.to_string(),
+        method: if reads_only { "GET" } else { "POST" }.to_string(),
+        user_agent: None,
+        session_id: auth.session_id.clone(),
+        accesses_phi,
+        is_data_export: matches!(permission, Some(P::ExportPHI | P::ExportAuditLogs | P::ExportReports | P::DataImportExport)),
+        accesses_audit_log: matches!(permission, Some(P::ViewAuditLogs | P::ExportAuditLogs)),
+        mfa_verified: auth.mfa_verified_within(policy.mfa_window_minutes, now),
+        timestamp: now,
+    }
+}
+
+/// Check an incoming invoke against the managed policy; on success, the context to rate limit
+/// it with
+fn check_invoke<R: Runtime>(webview: &tauri::Webview<R>, command: &str) -> Result<RateLimitContext, SecurityError> {
+    let unavailable = || SecurityError::AuthorizationDenied {
+        reason: "Authorization state unavailable".to_string(),
+    };
+    let policy = webview.try_state::<CommandPolicy>().ok_or_else(unavailable)?;
+    let rbac = webview.try_state::<RbacServiceState>().ok_or_else(unavailable)?;
+    let auth_state = webview.try_state::<Arc<RwLock<AuthState>>>().ok_or_else(unavailable)?;
+
+    // Dispatch is synchronous; a session update in progress is refused rather than waited on
+    let auth = auth_state.try_read().map_err(|_| SecurityError::AuthorizationDenied {
+        reason: "Session is being updated; retry the request".to_string(),
+    })?;
+    let now = Utc::now();
+    let mut result = policy.authorize(command, &auth, &rbac.0, now);
+    if let (Ok(()), Some(user_id)) = (&result, auth.user_id.as_deref()) {
+        let touches_phi = policy
+            .requirement(command)
+            .and_then(|requirement| requirement.permission.as_ref())
+            .map_or(false, |permission| permission.category() == PermissionCategory::PatientData);
+        if touches_phi {
+            result = session_binding::check_phi_access(webview.app_handle(), user_id, command);
+        }
+        if let (Ok(()), Some(step_up)) = (&result, webview.try_state::<StepUpState>()) {
+            result = step_up
+                .check_command(command, touches_phi, &auth, Utc::now())
+                .map_err(|reason| SecurityError::AuthorizationDenied { reason });
+        }
+    }
+
+    if let (Err(e), Some(user_id)) = (&result, auth.user_id.clone()) {
+        log::warn!("Command '{}' refused for user {}: {}", command, user_id, e);
+        let app = webview.app_handle().clone();
+        let (command, reason) = (command.to_string(), e.to_string());
+        tauri::async_runtime::spawn(async move {
+            let firebase = app.state::<FirebaseServiceState>();
+            let firebase_guard = firebase.0.lock().await;
+            if let Some(firebase) = firebase_guard.as_ref() {
+                let _ = firebase.audit_log(
+                    "COMMAND_DENIED",
+                    "command",
+                    &user_id,
+                    false,
+                    Some(serde_json::json!({ "command": command, "reason": reason }))
+                ).await;
+            }
+        });
+    }
+    result.map(|()| rate_limit_context(&policy, command, &auth, now))
+}
+
+/// Wrap the generated invoke handler so every app command passes the policy, then the rate
+/// limits, before it runs
+pub fn guarded<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    let handler = Arc::new(handler);
+    move |invoke: Invoke<R>| {
+        let command = invoke.message.command().to_string();
+        let webview = invoke.message.webview();
+        let context = match check_invoke(&webview, &command) {
+            Ok(context) => context,
+            Err(e) => {
+                invoke.resolver.reject(e.to_string());
+                return true;
+            }
+        };
+        let Some(rate_limits) = webview.try_state::<RateLimitServiceState>() else {
+            return handler(invoke);
+        };
+
+        // Country lookups make the check async, so the command is dispatched once it completes
+        let rate_limits = rate_limits.0.clone();
+        let handler = handler.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = rate_limits.check_rate_limit(context).await;
+            if result.allowed {
+                handler(invoke);
+            } else {
+                let reason = result.denial_reason.unwrap_or_else(|| "Rate limit exceeded".to_string());
+                log::warn!("Command '{}' rate limited: {}", command, reason);
+                invoke.resolver.reject(match result.retry_after_seconds {
+                    Some(seconds) => format!("{}; retry in {} seconds", reason, seconds),
+                    None => reason,
+                });
+            }
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::HealthcareRole;
+
+    fn session(role: HealthcareRole) -> AuthState {
+        let mut auth = AuthState::new();
+        auth.set_authenticated(
+            "user-1".to_string(),
+            "access".to_string(),
+            "refresh".to_string(),
+            role,
+            Vec::new(),
+            Utc::now() + chrono::Duration::hours(1),
+        );
+        auth
+    }
+
+    #[test]
+    fn test_every_registered_command_is_declared() {
+        let lib = include_str!("../lib.rs");
+        let start = lib.find("generate_handler![").expect("handler list") + "generate_handler![".len();
+        let list = &lib[start..start + lib[start..].find(']').expect("end of handler list")];
+        let policy = CommandPolicy::default();
+        let missing: Vec<&str> = list
+            .lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|name| !name.is_empty() && !name.starts_with("//"))
+            .filter(|name| policy.requirement(name).is_none())
+            .collect();
+        assert!(missing.is_empty(), "commands without a policy entry: {:?}", missing);
+
+        // The table agrees with permissions that are MFA-gated on their own
+        for (command, requirement) in policy.entries() {
+            if let Some(permission) = &requirement.permission {
+                assert!(!permission.requires_mfa() || requirement.mfa, "'{}' must require MFA", command);
+            }
+        }
+    }
+
+    #[test]
+    fn test_guard_decisions() {
+        let policy = CommandPolicy { fail_closed: true, ..CommandPolicy::default() };
+        let rbac = RbacService::new();
+        let now = Utc::now();
+
+        assert!(policy.authorize("auth_login", &AuthState::new(), &rbac, now).is_ok());
+        assert!(policy.authorize("get_clients", &AuthState::new(), &rbac, now).is_err());
+        assert!(policy.authorize("not_a_command", &session(HealthcareRole::SuperAdmin), &rbac, now).is_err());
+
+        let patient = session(HealthcareRole::Patient);
+        assert!(policy.authorize("get_appointments", &patient, &rbac, now).is_ok());
+        assert!(policy.authorize("save_medical_note", &patient, &rbac, now).is_err());
+
+        // Administrators share the super admin definition but still need a recent MFA
+        let mut admin = session(HealthcareRole::Administrator);
+        assert!(policy.authorize("export_audit_log", &admin, &rbac, now).is_err());
+        admin.mark_mfa_verified();
+        assert!(policy.authorize("export_audit_log", &admin, &rbac, now).is_ok());
+        let later = now + chrono::Duration::minutes(policy.mfa_window_minutes + 1);
+        assert!(policy.authorize("export_audit_log", &admin, &rbac, later).is_err());
+
+        // Resuming needs a session the lock screen paused
+        assert!(policy.authorize("auth_start_unlock_mfa", &AuthState::new(), &rbac, now).is_err());
+        assert!(policy.authorize("auth_start_unlock_mfa", &patient, &rbac, now).is_err());
+        let mut locked = session(HealthcareRole::Patient);
+        locked.lock();
+        assert!(policy.authorize("auth_start_unlock_mfa", &locked, &rbac, now).is_ok());
+
+        // A flagged sign-in can still reach signed-in commands, but nothing permissioned
+        let mut flagged = session(HealthcareRole::Patient);
+        flagged.mfa_required = true;
+        assert!(policy.authorize("get_appointments", &flagged, &rbac, now).is_err());
+        flagged.mark_mfa_verified();
+        assert!(policy.authorize("get_appointments", &flagged, &rbac, now).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_flags_follow_the_policy_table() {
+        let policy = CommandPolicy::default();
+        let auth = session(HealthcareRole::Administrator);
+        let context = |command: &str| rate_limit_context(&policy, command, &auth, Utc::now());
+
+        let notes = context("get_medical_note");
+        assert!(notes.accesses_phi && !notes.is_data_export && !notes.accesses_audit_log);
+        assert_eq!(notes.method, "GET");
+        assert_eq!(context("save_medical_note").method, "POST");
+
+        let export = context("export_audit_log");
+        assert!(export.is_data_export && export.accesses_audit_log && !export.accesses_phi);
+        assert!(context("query_audit_events").accesses_audit_log);
+        assert_eq!(export.user_id, Some(stable_uuid("user-1")));
+
+        // Without a session only the address-based limits apply
+        let anonymous = rate_limit_context(&policy, "auth_login", &AuthState::new(), Utc::now());
+        assert!(anonymous.user_id.is_none() && anonymous.user_role.is_none());
+    }
+}
@@ -1,7 +1,8 @@
 // HIPAA-Compliant Medical Grade Encryption Module
 // Implements AES-256-GCM and ChaCha20-Poly1305 encryption for Protected Health Information (PHI)
 
-use crate::security::{SecurityError, DataClassification, EncryptionLevel};
+use crate::security::{SecurityError, DataClassification, EncryptionLevel, SecurityConfig, AuditEventType};
+use crate::security::audit::{AuditEvent, AuditOutcome, AuditService};
 use aes_gcm::{
     aead::{Aead, KeyInit, OsRng},
     Aes256Gcm, Key, Nonce,
@@ -14,6 +15,7 @@ use password_hash::Salt;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
 use uuid::Uuid;
@@ -39,10 +41,185 @@ pub struct EncryptedData {
     pub encrypted_at: DateTime<Utc>,
     /// Key identifier used for encryption
     pub key_id: Uuid,
+    /// Version of that key within its classification; 0 on envelopes written before versioning
+    #[serde(default)]
+    pub key_version: u32,
     /// Additional authenticated data (base64 encoded)
     pub aad: Option<String>,
     /// HMAC for additional integrity verification
     pub hmac: Option<String>,
+    /// Whether the AAD was derived from an `EncryptionContext` and must be
+    /// re-derived from the owning record at decryption time
+    #[serde(default)]
+    pub context_bound: bool,
+    /// Layout of a context-derived AAD; 1 on envelopes written before its fields were
+    /// length-prefixed
+    #[serde(default = "legacy_aad_version")]
+    pub aad_version: u32,
+    /// Layers applied, innermost first, for layered (Maximum) envelopes; decryption removes
+    /// them in reverse. The inner layers' nonces travel inside the outer ciphertext.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<EncryptionLayer>,
+}
+
+/// One cipher pass of a layered envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EncryptionLayer {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl EncryptionLayer {
+    fn name(&self) -> &'static str {
+        match self {
+            EncryptionLayer::Aes256Gcm => "AES-256-GCM",
+            EncryptionLayer::ChaCha20Poly1305 => "ChaCha20-Poly1305",
+        }
+    }
+
+    /// Key for this layer, derived from the envelope key with HKDF-SHA256 under a per-layer
+    /// label, so no two layers share a key
+    fn derive_key(&self, envelope_key: &[u8]) -> Result<[u8; 32], SecurityError> {
+        let salt = ring::hkdf::Salt::new(ring::hkdf::HKDF_SHA256, b"PsyPsy-CMS-layered-encryption");
+        let info = [self.name().as_bytes()];
+        let okm = salt.extract(envelope_key)
+            .expand(&info, ring::hkdf::HKDF_SHA256)
+            .map_err(|_| SecurityError::CryptoOperationFailed {
+                reason: format!("{} layer key derivation failed", self.name())
+            })?;
+        let mut key = [0u8; 32];
+        okm.fill(&mut key).map_err(|_| SecurityError::CryptoOperationFailed {
+            reason: format!("{} layer key derivation failed", self.name())
+        })?;
+        Ok(key)
+    }
+}
+
+/// Layers of a Maximum envelope, innermost first
+const MAXIMUM_LAYERS: [EncryptionLayer; 2] = [EncryptionLayer::Aes256Gcm, EncryptionLayer::ChaCha20Poly1305];
+
+/// Encrypt with AES-256-GCM, then encrypt that nonce and ciphertext again with
+/// ChaCha20-Poly1305. Both layers authenticate the same AAD.
+fn seal_layered(
+    data: &[u8],
+    aes_key: &[u8; 32],
+    chacha_key: &[u8; 32],
+    aad: &[u8],
+    inner_nonce: &[u8; 12],
+    outer_nonce: &[u8; 12],
+) -> Result<Vec<u8>, SecurityError> {
+    let inner = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(aes_key))
+        .encrypt(Nonce::from_slice(inner_nonce), aes_gcm::aead::Payload { msg: data, aad })
+        .map_err(|e| SecurityError::EncryptionFailed {
+            reason: format!("Inner AES-256-GCM layer failed: {}", e)
+        })?;
+    let mut wrapped = inner_nonce.to_vec();
+    wrapped.extend_from_slice(&inner);
+    ChaCha20Poly1305::new(ChachaKey::from_slice(chacha_key))
+        .encrypt(ChachaNonce::from_slice(outer_nonce), chacha20poly1305::aead::Payload { msg: &wrapped, aad })
+        .map_err(|e| SecurityError::EncryptionFailed {
+            reason: format!("Outer ChaCha20-Poly1305 layer failed: {}", e)
+        })
+}
+
+/// Reverse `seal_layered`. The outer tag is checked before anything of the inner layer is read.
+fn open_layered(
+    ciphertext: &[u8],
+    aes_key: &[u8; 32],
+    chacha_key: &[u8; 32],
+    aad: &[u8],
+    outer_nonce: &[u8],
+) -> Result<Vec<u8>, SecurityError> {
+    if outer_nonce.len() != 12 {
+        return Err(SecurityError::DecryptionFailed { reason: "Invalid outer nonce length".to_string() });
+    }
+    let wrapped = ChaCha20Poly1305::new(ChachaKey::from_slice(chacha_key))
+        .decrypt(ChachaNonce::from_slice(outer_nonce), chacha20poly1305::aead::Payload { msg: ciphertext, aad })
+        .map_err(|_| SecurityError::DecryptionFailed {
+            reason: "Outer ChaCha20-Poly1305 layer failed authentication".to_string()
+        })?;
+    if wrapped.len() < 12 {
+        return Err(SecurityError::DecryptionFailed { reason: "Inner layer is truncated".to_string() });
+    }
+    let (inner_nonce, inner) = wrapped.split_at(12);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(aes_key))
+        .decrypt(Nonce::from_slice(inner_nonce), aes_gcm::aead::Payload { msg: inner, aad })
+        .map_err(|_| SecurityError::DecryptionFailed {
+            reason: "Inner AES-256-GCM layer failed authentication".to_string()
+        })
+}
+
+/// AAD layout written for new envelopes
+pub const CURRENT_AAD_VERSION: u32 = 2;
+
+fn legacy_aad_version() -> u32 {
+    1
+}
+
+/// Record context bound into the ciphertext as AEAD associated data.
+///
+/// Binding the owning patient and record prevents an envelope from being moved
+/// under another record: decryption recomputes the AAD from where the envelope
+/// was found, so a relocated ciphertext fails authentication.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptionContext {
+    /// Patient the protected data belongs to
+    pub patient_id: Option<String>,
+    /// Record (note, transcript, document) holding the envelope
+    pub record_id: Option<String>,
+}
+
+impl EncryptionContext {
+    /// Context for a record owned by a patient
+    pub fn for_record(patient_id: impl Into<String>, record_id: impl Into<String>) -> Self {
+        Self {
+            patient_id: Some(patient_id.into()),
+            record_id: Some(record_id.into()),
+        }
+    }
+
+    /// Canonical AAD bytes for this context and classification. Each field is length-prefixed,
+    /// so no ID can spill into the next field and collide with another context.
+    pub fn to_aad(&self, classification: DataClassification) -> Vec<u8> {
+        let mut aad = b"PsyPsy-CMS|aad-v2".to_vec();
+        let classification = format!("{:?}", classification);
+        for field in [Some(classification.as_str()), self.patient_id.as_deref(), self.record_id.as_deref()] {
+            match field {
+                Some(value) => {
+                    aad.push(1);
+                    aad.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                    aad.extend_from_slice(value.as_bytes());
+                }
+                None => aad.push(0),
+            }
+        }
+        aad
+    }
+
+    /// AAD in the layout `version` envelopes were sealed with. The separator-joined version 1
+    /// layout is only unambiguous without `|` in the IDs, so such contexts are refused.
+    pub fn aad_for_version(&self, classification: DataClassification, version: u32) -> Result<Vec<u8>, SecurityError> {
+        match version {
+            CURRENT_AAD_VERSION => Ok(self.to_aad(classification)),
+            1 => {
+                let ids = [self.patient_id.as_deref(), self.record_id.as_deref()];
+                if ids.iter().flatten().any(|id| id.contains('|')) {
+                    return Err(SecurityError::DecryptionFailed {
+                        reason: "Record context contains the AAD separator".to_string()
+                    });
+                }
+                Ok(format!(
+                    "PsyPsy-CMS|aad-v1|classification={:?}|patient={}|record={}",
+                    classification,
+                    self.patient_id.as_deref().unwrap_or("-"),
+                    self.record_id.as_deref().unwrap_or("-"),
+                ).into_bytes())
+            }
+            other => Err(SecurityError::DecryptionFailed {
+                reason: format!("Unsupported AAD version {}", other)
+            }),
+        }
+    }
 }
 
 /// Encryption key with metadata and rotation tracking
@@ -64,10 +241,12 @@ pub struct EncryptionKey {
     pub classification: DataClassification,
     /// Salt used in key derivation (if applicable)
     pub salt: Option<Vec<u8>>,
+    /// Increases by one with each rotation of the classification's key
+    pub version: u32,
 }
 
 impl EncryptionKey {
-    /// Check if key is still valid
+    /// Check if key is still valid for new encryptions
     pub fn is_valid(&self) -> bool {
         let now = Utc::now();
         now < self.expires_at && self.is_active
@@ -148,6 +327,102 @@ pub struct CryptoService {
     kdf_params: HashMap<DataClassification, KeyDerivationParams>,
     /// Random number generator
     rng: Arc<Mutex<OsRng>>,
+    /// Refuse context-free decryption of envelopes that were context bound
+    require_context_binding: bool,
+    /// Days a key version stays current before `rotate_keys` replaces it
+    rotation_interval_days: u32,
+    /// When keys were last rotated
+    last_rotation: Arc<RwLock<Option<DateTime<Utc>>>>,
+    /// Audit trail for key rotations
+    audit: Option<Arc<AuditService>>,
+    /// Keyring file holding the master key salt and the wrapped data keys; without one,
+    /// keys live only as long as the process
+    keyring_path: std::sync::Mutex<Option<PathBuf>>,
+    /// Salt the current master key was derived with (base64 encoded)
+    keyring_salt: std::sync::Mutex<Option<String>>,
+}
+
+/// File the keyring is kept in, under the app data directory
+const KEYRING_FILE: &str = "crypto_keyring.json";
+
+/// Plaintext sealed under the master key so a wrong passphrase is refused before any data
+/// key is unwrapped
+const KEYRING_VERIFIER: &[u8] = b"psypsy-keyring-v1";
+
+/// Master key salt, passphrase check and wrapped data keys as persisted on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredKeyring {
+    salt: String,
+    verifier: String,
+    #[serde(default)]
+    keys: Vec<WrappedKey>,
+    #[serde(default)]
+    last_rotation: Option<DateTime<Utc>>,
+}
+
+/// A data key encrypted under the master key. The key id and version are the AAD, so a
+/// wrapped key cannot be swapped into another slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WrappedKey {
+    id: Uuid,
+    classification: DataClassification,
+    version: u32,
+    algorithm: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    is_active: bool,
+    /// Nonce followed by the AES-256-GCM ciphertext (base64 encoded)
+    wrapped: String,
+}
+
+pub(crate) fn wrap_with(master_key: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<String, SecurityError> {
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(master_key))
+        .encrypt(Nonce::from_slice(&nonce), aes_gcm::aead::Payload { msg: plaintext, aad })
+        .map_err(|e| SecurityError::EncryptionFailed { reason: format!("Key wrapping failed: {}", e) })?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(out))
+}
+
+pub(crate) fn unwrap_with(master_key: &[u8], wrapped: &str, aad: &[u8]) -> Result<Vec<u8>, SecurityError> {
+    let bytes = BASE64.decode(wrapped)
+        .map_err(|e| SecurityError::DecryptionFailed { reason: format!("Invalid wrapped key: {}", e) })?;
+    if bytes.len() < 12 {
+        return Err(SecurityError::DecryptionFailed { reason: "Wrapped key is truncated".to_string() });
+    }
+    let (nonce, ciphertext) = bytes.split_at(12);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(master_key))
+        .decrypt(Nonce::from_slice(nonce), aes_gcm::aead::Payload { msg: ciphertext, aad })
+        .map_err(|_| SecurityError::DecryptionFailed { reason: "Wrapped key failed authentication".to_string() })
+}
+
+fn key_wrap_aad(id: &Uuid, version: u32) -> Vec<u8> {
+    format!("psypsy-keyring:{}:{}", id, version).into_bytes()
+}
+
+/// One classification's key replaced by a rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatedKey {
+    pub classification: DataClassification,
+    pub previous_key_id: Option<Uuid>,
+    pub previous_version: u32,
+    pub new_key_id: Uuid,
+    pub new_version: u32,
+}
+
+/// Outcome of `CryptoService::rotate_keys`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotationReport {
+    pub rotated_at: DateTime<Utc>,
+    pub rotated: Vec<RotatedKey>,
+    /// Earlier key versions kept so existing ciphertext still decrypts
+    pub retained_versions: usize,
 }
 
 impl CryptoService {
@@ -165,16 +440,122 @@ impl CryptoService {
             master_key: Arc::new(Mutex::new(None)),
             kdf_params,
             rng: Arc::new(Mutex::new(OsRng)),
+            require_context_binding: true,
+            rotation_interval_days: SecurityConfig::default().encryption_key_rotation_days,
+            last_rotation: Arc::new(RwLock::new(None)),
+            audit: None,
+            keyring_path: std::sync::Mutex::new(None),
+            keyring_salt: std::sync::Mutex::new(None),
         }
     }
+
+    /// Create cryptographic service honoring the application security configuration
+    pub fn from_security_config(config: &SecurityConfig) -> Self {
+        let mut service = Self::new().with_context_binding(config.require_encryption_context);
+        service.rotation_interval_days = config.encryption_key_rotation_days.max(1);
+        service
+    }
+
+    /// Record key rotations in the audit trail
+    pub fn with_audit_service(mut self, audit: Arc<AuditService>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Explicit audit service, else the app-wide one installed at startup
+    fn audit_sink(&self) -> Option<Arc<AuditService>> {
+        self.audit.clone().or_else(crate::security::audit::audit_service)
+    }
+
+
+    /// Configure whether context-bound envelopes may only be opened with their context
+    pub fn with_context_binding(mut self, required: bool) -> Self {
+        self.require_context_binding = required;
+        self
+    }
     
-    /// Initialize master key from password with HIPAA-compliant key derivation
+    /// Keep the master key salt and wrapped data keys in `dir`, so data encrypted in one run
+    /// still decrypts in the next. The keyring is read when the master key is initialized.
+    pub fn attach_keyring(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        *self.keyring_path.lock().unwrap() = Some(dir.join(KEYRING_FILE));
+        Ok(())
+    }
+
+    fn load_keyring(&self) -> Result<Option<StoredKeyring>, SecurityError> {
+        let Some(path) = self.keyring_path.lock().unwrap().clone() else {
+            return Ok(None);
+        };
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = std::fs::read(&path).map_err(|e| SecurityError::CryptoOperationFailed {
+            reason: format!("Failed to read keyring: {}", e)
+        })?;
+        // A corrupt keyring must not be replaced with a fresh one; that would orphan every key
+        serde_json::from_slice(&raw).map(Some).map_err(|e| SecurityError::CryptoOperationFailed {
+            reason: format!("Keyring is unreadable: {}", e)
+        })
+    }
+
+    /// Write the salt, verifier and every data key, wrapped under the master key. A no-op
+    /// when no keyring is attached.
+    async fn persist_keyring(&self) -> Result<(), SecurityError> {
+        let Some(path) = self.keyring_path.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        let Some(master_key) = self.master_key.lock().await.clone() else {
+            return Err(SecurityError::EncryptionFailed {
+                reason: "Master key is not initialized; data keys cannot be persisted".to_string()
+            });
+        };
+        let Some(salt) = self.keyring_salt.lock().unwrap().clone() else {
+            return Err(SecurityError::CryptoOperationFailed { reason: "Keyring salt is missing".to_string() });
+        };
+
+        let keys = self.keys.read().unwrap().values().cloned().collect::<Vec<_>>();
+        let mut wrapped = Vec::with_capacity(keys.len());
+        for key in keys {
+            wrapped.push(WrappedKey {
+                wrapped: wrap_with(&master_key, &key.key, &key_wrap_aad(&key.id, key.version))?,
+                id: key.id,
+                classification: key.classification,
+                version: key.version,
+                algorithm: key.algorithm,
+                created_at: key.created_at,
+                expires_at: key.expires_at,
+                is_active: key.is_active,
+            });
+        }
+        let keyring = StoredKeyring {
+            salt,
+            verifier: wrap_with(&master_key, KEYRING_VERIFIER, b"psypsy-keyring-verifier")?,
+            keys: wrapped,
+            last_rotation: *self.last_rotation.read().unwrap(),
+        };
+
+        let json = serde_json::to_vec_pretty(&keyring).map_err(|e| SecurityError::CryptoOperationFailed {
+            reason: format!("Failed to serialize keyring: {}", e)
+        })?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| SecurityError::CryptoOperationFailed { reason: format!("Failed to write keyring: {}", e) })
+    }
+
+    /// Initialize master key from password with HIPAA-compliant key derivation. With a keyring
+    /// attached, its stored salt is used, the password is checked against it and the wrapped
+    /// data keys are loaded; the first initialization creates the keyring.
     pub async fn initialize_master_key(&self, password: &str, salt: Option<&[u8]>) -> Result<(), SecurityError> {
         let params = &self.kdf_params[&DataClassification::MedicalSensitive];
-        
-        let salt = match salt {
-            Some(s) => s.to_vec(),
-            None => {
+        let stored = self.load_keyring()?;
+
+        let salt = match (salt, &stored) {
+            (Some(s), _) => s.to_vec(),
+            (None, Some(keyring)) => BASE64.decode(&keyring.salt).map_err(|e| SecurityError::CryptoOperationFailed {
+                reason: format!("Invalid keyring salt: {}", e)
+            })?,
+            (None, None) => {
                 let mut salt_bytes = vec![0u8; params.salt_length];
                 self.rng.lock().await.fill_bytes(&mut salt_bytes);
                 salt_bytes
@@ -193,20 +574,71 @@ impl CryptoService {
                 reason: format!("Password hashing: {}", e) 
             })?;
         
-        let key = password_hash.hash.unwrap().as_bytes().to_vec();
-        *self.master_key.lock().await = Some(key);
+        let key = password_hash.hash
+            .ok_or_else(|| SecurityError::CryptoOperationFailed { reason: "Key derivation produced no output".to_string() })?
+            .as_bytes()
+            .to_vec();
+
+        match stored {
+            Some(keyring) => {
+                unwrap_with(&key, &keyring.verifier, b"psypsy-keyring-verifier").map_err(|_| {
+                    SecurityError::AuthenticationFailed { reason: "Passphrase does not match the keyring".to_string() }
+                })?;
+                let mut loaded = HashMap::with_capacity(keyring.keys.len());
+                for wrapped in keyring.keys {
+                    let key_bytes = unwrap_with(&key, &wrapped.wrapped, &key_wrap_aad(&wrapped.id, wrapped.version))?;
+                    loaded.insert(wrapped.id, EncryptionKey {
+                        id: wrapped.id,
+                        key: key_bytes,
+                        algorithm: wrapped.algorithm,
+                        created_at: wrapped.created_at,
+                        expires_at: wrapped.expires_at,
+                        is_active: wrapped.is_active,
+                        classification: wrapped.classification,
+                        salt: None,
+                        version: wrapped.version,
+                    });
+                }
+                self.keys.write().unwrap().extend(loaded);
+                *self.last_rotation.write().unwrap() = keyring.last_rotation;
+                *self.keyring_salt.lock().unwrap() = Some(keyring.salt);
+                *self.master_key.lock().await = Some(key);
+            }
+            None => {
+                *self.keyring_salt.lock().unwrap() = Some(BASE64.encode(&salt));
+                *self.master_key.lock().await = Some(key);
+                self.persist_keyring().await?;
+            }
+        }
         
         log::info!("Master key initialized with HIPAA-compliant parameters");
         Ok(())
     }
+
+    /// Whether a master key has been set up; PHI should not be written to disk before then
+    pub async fn is_initialized(&self) -> bool {
+        self.master_key.lock().await.is_some()
+    }
     
     /// Generate new encryption key for specified data classification
     pub async fn generate_key(&self, classification: DataClassification) -> Result<Uuid, SecurityError> {
+        // A key that cannot be wrapped would be lost on restart along with everything it encrypts
+        if self.keyring_path.lock().unwrap().is_some() && !self.is_initialized().await {
+            return Err(SecurityError::EncryptionFailed {
+                reason: "Encryption keys are locked; unlock encrypted storage first".to_string()
+            });
+        }
         let params = &self.kdf_params[&classification];
         let mut key_bytes = vec![0u8; params.key_length];
         self.rng.lock().await.fill_bytes(&mut key_bytes);
         
         let key_id = Uuid::new_v4();
+        let mut keys = self.keys.write().unwrap();
+        let version = keys.values()
+            .filter(|k| k.classification == classification)
+            .map(|k| k.version)
+            .max()
+            .unwrap_or(0) + 1;
         let key = EncryptionKey {
             id: key_id,
             key: key_bytes,
@@ -216,16 +648,78 @@ impl CryptoService {
             is_active: true,
             classification: classification.clone(),
             salt: None,
+            version,
         };
 
-        self.keys.write().unwrap().insert(key_id, key);
+        keys.insert(key_id, key);
+        drop(keys);
+        self.persist_keyring().await?;
 
         log::info!("Generated new encryption key {} for classification {:?}", key_id, classification);
         Ok(key_id)
     }
     
+    /// Newest active key version for a classification, if any
+    fn current_key(&self, classification: &DataClassification) -> Option<EncryptionKey> {
+        self.keys.read().unwrap()
+            .values()
+            .filter(|k| k.classification == *classification && k.is_valid())
+            .max_by_key(|k| k.version)
+            .cloned()
+    }
+
+    /// Key for a new encryption: the requested one if it is still active, otherwise the
+    /// classification's current version (created on first use)
+    async fn encryption_key(&self, classification: &DataClassification, key_id: Option<Uuid>) -> Result<EncryptionKey, SecurityError> {
+        if let Some(key_id) = key_id {
+            let key = self.keys.read().unwrap()
+                .get(&key_id)
+                .cloned()
+                .ok_or_else(|| SecurityError::EncryptionFailed { 
+                    reason: format!("Key {} not found", key_id) 
+                })?;
+            if !key.is_valid() {
+                return Err(SecurityError::EncryptionFailed {
+                    reason: format!("Key {} is retired and only decrypts existing data", key_id)
+                });
+            }
+            return Ok(key);
+        }
+        if let Some(key) = self.current_key(classification) {
+            return Ok(key);
+        }
+        let key_id = self.generate_key(classification.clone()).await?;
+        self.keys.read().unwrap()
+            .get(&key_id)
+            .cloned()
+            .ok_or_else(|| SecurityError::EncryptionFailed { 
+                reason: format!("Key {} not found", key_id) 
+            })
+    }
+
     /// Encrypt data using medical-grade encryption based on classification
     pub async fn encrypt(&self, data: &[u8], classification: DataClassification, key_id: Option<Uuid>) -> Result<EncryptedData, SecurityError> {
+        self.encrypt_internal(data, classification, key_id, None).await
+    }
+
+    /// Encrypt data with the owning record bound as additional authenticated data
+    pub async fn encrypt_with_context(
+        &self,
+        data: &[u8],
+        classification: DataClassification,
+        key_id: Option<Uuid>,
+        context: &EncryptionContext,
+    ) -> Result<EncryptedData, SecurityError> {
+        self.encrypt_internal(data, classification, key_id, Some(context)).await
+    }
+
+    async fn encrypt_internal(
+        &self,
+        data: &[u8],
+        classification: DataClassification,
+        key_id: Option<Uuid>,
+        context: Option<&EncryptionContext>,
+    ) -> Result<EncryptedData, SecurityError> {
         let encryption_level = classification.encryption_requirements();
         
         match encryption_level {
@@ -234,15 +728,37 @@ impl CryptoService {
                     reason: "Cannot encrypt public data".to_string() 
                 });
             },
-            EncryptionLevel::Standard => self.encrypt_aes_128_gcm(data, classification, key_id).await,
-            EncryptionLevel::Strong => self.encrypt_aes_256_gcm(data, classification, key_id).await,
-            EncryptionLevel::Medical => self.encrypt_medical_grade(data, classification, key_id).await,
-            EncryptionLevel::Maximum => self.encrypt_maximum_security(data, classification, key_id).await,
+            EncryptionLevel::Standard => self.encrypt_aes_128_gcm(data, classification, key_id, context).await,
+            EncryptionLevel::Strong => self.encrypt_aes_256_gcm(data, classification, key_id, context).await,
+            EncryptionLevel::Medical => self.encrypt_medical_grade(data, classification, key_id, context).await,
+            EncryptionLevel::Maximum => self.encrypt_maximum_security(data, classification, key_id, context).await,
         }
     }
     
     /// Decrypt previously encrypted data
     pub async fn decrypt(&self, encrypted_data: &EncryptedData) -> Result<Vec<u8>, SecurityError> {
+        if encrypted_data.context_bound && self.require_context_binding {
+            return Err(SecurityError::DecryptionFailed {
+                reason: "Envelope is bound to a record context; decrypt with its context".to_string()
+            });
+        }
+
+        let aad = encrypted_data.aad.as_ref()
+            .map(|a| BASE64.decode(a).unwrap_or_default())
+            .unwrap_or_default();
+        self.decrypt_internal(encrypted_data, &aad).await
+    }
+
+    /// Decrypt data, re-deriving the AAD from the record the envelope was loaded from.
+    ///
+    /// The stored AAD is ignored, so an envelope relocated under another patient or
+    /// record fails authentication.
+    pub async fn decrypt_with_context(&self, encrypted_data: &EncryptedData, context: &EncryptionContext) -> Result<Vec<u8>, SecurityError> {
+        let aad = context.aad_for_version(encrypted_data.classification, encrypted_data.aad_version)?;
+        self.decrypt_internal(encrypted_data, &aad).await
+    }
+
+    async fn decrypt_internal(&self, encrypted_data: &EncryptedData, aad: &[u8]) -> Result<Vec<u8>, SecurityError> {
         let key = self.keys.read().unwrap()
             .get(&encrypted_data.key_id)
             .cloned()
@@ -250,17 +766,19 @@ impl CryptoService {
                 reason: format!("Key {} not found", encrypted_data.key_id) 
             })?;
         
-        if !key.is_valid() {
-            return Err(SecurityError::DecryptionFailed { 
-                reason: "Encryption key has expired".to_string() 
+        // Retired versions still decrypt; only new encryptions need the current key
+        if encrypted_data.key_version != 0 && encrypted_data.key_version != key.version {
+            return Err(SecurityError::DecryptionFailed {
+                reason: format!("Key version mismatch for key {}", encrypted_data.key_id)
             });
         }
         
         match encrypted_data.algorithm.as_str() {
-            algo if algo.starts_with("AES-128-GCM") => self.decrypt_aes_128_gcm(encrypted_data, &key).await,
-            algo if algo.starts_with("AES-256-GCM") => self.decrypt_aes_256_gcm(encrypted_data, &key).await,
+            algo if algo.starts_with("AES-128-GCM") => self.decrypt_aes_128_gcm(encrypted_data, &key, aad).await,
+            algo if algo.starts_with("AES-256-GCM") => self.decrypt_aes_256_gcm(encrypted_data, &key, aad).await,
+            algo if algo.starts_with("Medical-Grade-AES-256-GCM") => self.decrypt_aes_256_gcm(encrypted_data, &key, aad).await,
             algo if algo.starts_with("ChaCha20-Poly1305") => self.decrypt_chacha20_poly1305(encrypted_data, &key).await,
-            algo if algo.starts_with("Layered") => self.decrypt_layered_encryption(encrypted_data, &key).await,
+            algo if algo.starts_with("Layered") => self.decrypt_layered_encryption(encrypted_data, &key, aad).await,
             _ => Err(SecurityError::DecryptionFailed { 
                 reason: format!("Unsupported algorithm: {}", encrypted_data.algorithm) 
             }),
@@ -268,18 +786,15 @@ impl CryptoService {
     }
     
     /// Encrypt using AES-256-GCM (medical grade)
-    async fn encrypt_aes_256_gcm(&self, data: &[u8], classification: DataClassification, key_id: Option<Uuid>) -> Result<EncryptedData, SecurityError> {
-        let key_id = match key_id {
-            Some(id) => id,
-            None => self.generate_key(classification.clone()).await?,
-        };
-        
-        let encryption_key = self.keys.read().unwrap()
-            .get(&key_id)
-            .cloned()
-            .ok_or_else(|| SecurityError::EncryptionFailed { 
-                reason: format!("Key {} not found", key_id) 
-            })?;
+    async fn encrypt_aes_256_gcm(
+        &self,
+        data: &[u8],
+        classification: DataClassification,
+        key_id: Option<Uuid>,
+        context: Option<&EncryptionContext>,
+    ) -> Result<EncryptedData, SecurityError> {
+        let encryption_key = self.encryption_key(&classification, key_id).await?;
+        let key_id = encryption_key.id;
         
         let key = Key::<Aes256Gcm>::from_slice(&encryption_key.key[..32]);
         let cipher = Aes256Gcm::new(key);
@@ -289,11 +804,14 @@ impl CryptoService {
         self.rng.lock().await.fill_bytes(&mut nonce_bytes);
         let nonce = Nonce::from_slice(&nonce_bytes);
         
-        // Encrypt with additional authenticated data
-        let aad = format!("PsyPsy-CMS-{}-{}", classification.clone() as u8, Utc::now().timestamp());
+        // Encrypt with additional authenticated data (record context when provided)
+        let aad = match context {
+            Some(ctx) => ctx.to_aad(classification),
+            None => format!("PsyPsy-CMS-{}-{}", classification.clone() as u8, Utc::now().timestamp()).into_bytes(),
+        };
         let ciphertext = cipher.encrypt(nonce, aes_gcm::aead::Payload {
             msg: data,
-            aad: aad.as_bytes(),
+            aad: &aad,
         }).map_err(|e| SecurityError::EncryptionFailed { 
             reason: format!("AES-256-GCM encryption failed: {}", e) 
         })?;
@@ -311,22 +829,38 @@ impl CryptoService {
             classification: classification.clone(),
             encrypted_at: Utc::now(),
             key_id,
+            key_version: encryption_key.version,
             aad: Some(BASE64.encode(&aad)),
             hmac: Some(BASE64.encode(hmac_tag.as_ref())),
+            context_bound: context.is_some(),
+            aad_version: CURRENT_AAD_VERSION,
+            layers: Vec::new(),
         })
     }
     
     /// Encrypt using AES-128-GCM (standard)
-    async fn encrypt_aes_128_gcm(&self, data: &[u8], classification: DataClassification, key_id: Option<Uuid>) -> Result<EncryptedData, SecurityError> {
+    async fn encrypt_aes_128_gcm(
+        &self,
+        data: &[u8],
+        classification: DataClassification,
+        key_id: Option<Uuid>,
+        context: Option<&EncryptionContext>,
+    ) -> Result<EncryptedData, SecurityError> {
         // Similar to AES-256 but with 128-bit key
         // Implementation follows same pattern with shorter key
-        self.encrypt_aes_256_gcm(data, classification, key_id).await
+        self.encrypt_aes_256_gcm(data, classification, key_id, context).await
     }
     
     /// Encrypt using medical-grade security (AES-256-GCM with enhanced key derivation)
-    async fn encrypt_medical_grade(&self, data: &[u8], classification: DataClassification, key_id: Option<Uuid>) -> Result<EncryptedData, SecurityError> {
+    async fn encrypt_medical_grade(
+        &self,
+        data: &[u8],
+        classification: DataClassification,
+        key_id: Option<Uuid>,
+        context: Option<&EncryptionContext>,
+    ) -> Result<EncryptedData, SecurityError> {
         // Enhanced version of AES-256-GCM with additional security measures
-        let mut result = self.encrypt_aes_256_gcm(data, classification.clone(), key_id).await?;
+        let mut result = self.encrypt_aes_256_gcm(data, classification.clone(), key_id, context).await?;
         result.algorithm = format!("Medical-Grade-AES-256-GCM-{:?}", classification);
         
         // TODO: Additional security: Re-encrypt key components with master key
@@ -336,45 +870,56 @@ impl CryptoService {
         Ok(result)
     }
     
-    /// Encrypt using maximum security (layered encryption)
-    async fn encrypt_maximum_security(&self, data: &[u8], classification: DataClassification, key_id: Option<Uuid>) -> Result<EncryptedData, SecurityError> {
-        // First layer: ChaCha20-Poly1305
-        let chacha_result = self.encrypt_chacha20_poly1305(data, classification.clone(), None).await?;
-        let chacha_data = BASE64.decode(&chacha_result.data)
-            .map_err(|e| SecurityError::EncryptionFailed { 
-                reason: format!("Base64 decode error: {}", e) 
-            })?;
-        
-        // Second layer: AES-256-GCM
-        let aes_result = self.encrypt_aes_256_gcm(&chacha_data, classification.clone(), key_id).await?;
+    /// Encrypt using maximum security: AES-256-GCM, then ChaCha20-Poly1305 over the inner
+    /// nonce and ciphertext, each with its own key derived from the envelope key
+    async fn encrypt_maximum_security(
+        &self,
+        data: &[u8],
+        classification: DataClassification,
+        key_id: Option<Uuid>,
+        context: Option<&EncryptionContext>,
+    ) -> Result<EncryptedData, SecurityError> {
+        let encryption_key = self.encryption_key(&classification, key_id).await?;
+        let key_id = encryption_key.id;
+        let [inner_layer, outer_layer] = MAXIMUM_LAYERS;
+        let aes_key = inner_layer.derive_key(&encryption_key.key)?;
+        let chacha_key = outer_layer.derive_key(&encryption_key.key)?;
+
+        let mut inner_nonce = [0u8; 12];
+        let mut outer_nonce = [0u8; 12];
+        {
+            let mut rng = self.rng.lock().await;
+            rng.fill_bytes(&mut inner_nonce);
+            rng.fill_bytes(&mut outer_nonce);
+        }
+        let aad = match context {
+            Some(ctx) => ctx.to_aad(classification),
+            None => format!("PsyPsy-CMS-{}-{}", classification.clone() as u8, Utc::now().timestamp()).into_bytes(),
+        };
+        let ciphertext = seal_layered(data, &aes_key, &chacha_key, &aad, &inner_nonce, &outer_nonce)?;
 
         Ok(EncryptedData {
             id: Uuid::new_v4(),
-            algorithm: format!("Layered-ChaCha20-AES256-{:?}", classification),
-            data: aes_result.data,
-            iv: aes_result.iv,
-            tag: aes_result.tag,
+            algorithm: format!("Layered-AES256-ChaCha20-{:?}", classification),
+            data: BASE64.encode(&ciphertext),
+            iv: BASE64.encode(outer_nonce),
+            tag: None, // Both AEAD tags are part of the ciphertext
             classification: classification.clone(),
             encrypted_at: Utc::now(),
-            key_id: aes_result.key_id,
-            aad: Some(BASE64.encode(&format!("Layered-{}", chacha_result.id))),
-            hmac: aes_result.hmac,
+            key_id,
+            key_version: encryption_key.version,
+            aad: Some(BASE64.encode(&aad)),
+            hmac: None,
+            context_bound: context.is_some(),
+            aad_version: CURRENT_AAD_VERSION,
+            layers: MAXIMUM_LAYERS.to_vec(),
         })
     }
     
     /// Encrypt using ChaCha20-Poly1305
     async fn encrypt_chacha20_poly1305(&self, data: &[u8], classification: DataClassification, key_id: Option<Uuid>) -> Result<EncryptedData, SecurityError> {
-        let key_id = match key_id {
-            Some(id) => id,
-            None => self.generate_key(classification.clone()).await?,
-        };
-        
-        let encryption_key = self.keys.read().unwrap()
-            .get(&key_id)
-            .cloned()
-            .ok_or_else(|| SecurityError::EncryptionFailed { 
-                reason: format!("Key {} not found", key_id) 
-            })?;
+        let encryption_key = self.encryption_key(&classification, key_id).await?;
+        let key_id = encryption_key.id;
         
         let key = ChachaKey::from_slice(&encryption_key.key[..32]);
         let cipher = ChaCha20Poly1305::new(key);
@@ -397,13 +942,17 @@ impl CryptoService {
             classification,
             encrypted_at: Utc::now(),
             key_id,
+            key_version: encryption_key.version,
             aad: None,
             hmac: None,
+            context_bound: false,
+            aad_version: CURRENT_AAD_VERSION,
+            layers: Vec::new(),
         })
     }
     
     /// Decrypt AES-256-GCM encrypted data
-    async fn decrypt_aes_256_gcm(&self, encrypted_data: &EncryptedData, key: &EncryptionKey) -> Result<Vec<u8>, SecurityError> {
+    async fn decrypt_aes_256_gcm(&self, encrypted_data: &EncryptedData, key: &EncryptionKey, aad: &[u8]) -> Result<Vec<u8>, SecurityError> {
         let cipher_key = Key::<Aes256Gcm>::from_slice(&key.key[..32]);
         let cipher = Aes256Gcm::new(cipher_key);
         
@@ -432,13 +981,9 @@ impl CryptoService {
                 })?;
         }
         
-        let aad = encrypted_data.aad.as_ref()
-            .map(|a| BASE64.decode(a).unwrap_or_default())
-            .unwrap_or_default();
-        
         let plaintext = cipher.decrypt(nonce, aes_gcm::aead::Payload {
             msg: &ciphertext,
-            aad: &aad,
+            aad,
         }).map_err(|e| SecurityError::DecryptionFailed { 
             reason: format!("AES-256-GCM decryption failed: {}", e) 
         })?;
@@ -447,9 +992,9 @@ impl CryptoService {
     }
     
     /// Decrypt AES-128-GCM encrypted data
-    async fn decrypt_aes_128_gcm(&self, encrypted_data: &EncryptedData, key: &EncryptionKey) -> Result<Vec<u8>, SecurityError> {
+    async fn decrypt_aes_128_gcm(&self, encrypted_data: &EncryptedData, key: &EncryptionKey, aad: &[u8]) -> Result<Vec<u8>, SecurityError> {
         // Use same implementation as AES-256-GCM for simplicity
-        self.decrypt_aes_256_gcm(encrypted_data, key).await
+        self.decrypt_aes_256_gcm(encrypted_data, key, aad).await
     }
     
     /// Decrypt ChaCha20-Poly1305 encrypted data
@@ -476,42 +1021,203 @@ impl CryptoService {
         Ok(plaintext)
     }
     
-    /// Decrypt layered encryption
-    async fn decrypt_layered_encryption(&self, encrypted_data: &EncryptedData, key: &EncryptionKey) -> Result<Vec<u8>, SecurityError> {
-        // First, decrypt AES layer
-        let aes_plaintext = self.decrypt_aes_256_gcm(encrypted_data, key).await?;
-        
-        // Then decrypt ChaCha layer (would need ChaCha key, simplified for demo)
-        // In practice, this would involve multiple keys and more complex key management
-        Ok(aes_plaintext)
+    /// Decrypt layered encryption, removing the recorded layers outermost first
+    async fn decrypt_layered_encryption(&self, encrypted_data: &EncryptedData, key: &EncryptionKey, aad: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        if encrypted_data.layers != MAXIMUM_LAYERS {
+            return Err(SecurityError::DecryptionFailed {
+                reason: format!("Unsupported encryption layers: {:?}", encrypted_data.layers)
+            });
+        }
+        let [inner_layer, outer_layer] = MAXIMUM_LAYERS;
+        let ciphertext = BASE64.decode(&encrypted_data.data)
+            .map_err(|e| SecurityError::DecryptionFailed { 
+                reason: format!("Base64 decode error: {}", e) 
+            })?;
+        let outer_nonce = BASE64.decode(&encrypted_data.iv)
+            .map_err(|e| SecurityError::DecryptionFailed {
+                reason: format!("Nonce decode error: {}", e)
+            })?;
+        open_layered(
+            &ciphertext,
+            &inner_layer.derive_key(&key.key)?,
+            &outer_layer.derive_key(&key.key)?,
+            aad,
+            &outer_nonce,
+        )
     }
     
-    /// Rotate encryption key for specified classification
+    /// Rotate encryption key for specified classification. The previous versions are retired
+    /// but kept, so data they encrypted still decrypts.
     pub async fn rotate_key(&self, classification: DataClassification) -> Result<Uuid, SecurityError> {
-        // Generate new key
         let new_key_id = self.generate_key(classification.clone()).await?;
         
-        // Mark old keys as inactive
+        // Retire every other version of this classification
         let mut keys = self.keys.write().unwrap();
-        for (_, key) in keys.iter_mut() {
-            if key.classification == classification && key.is_active {
+        for (id, key) in keys.iter_mut() {
+            if key.classification == classification && *id != new_key_id {
                 key.is_active = false;
             }
         }
+        drop(keys);
+        self.persist_keyring().await?;
         
         log::info!("Rotated encryption key for classification {:?}, new key: {}", classification, new_key_id);
         Ok(new_key_id)
     }
+
+    /// Issue a new key version for every classification that has keys and retire the old ones
+    pub async fn rotate_keys(&self) -> Result<KeyRotationReport, SecurityError> {
+        let mut classifications: Vec<DataClassification> = Vec::new();
+        for key in self.keys.read().unwrap().values() {
+            if !classifications.contains(&key.classification) {
+                classifications.push(key.classification);
+            }
+        }
+
+        let mut rotated = Vec::new();
+        for classification in classifications {
+            let previous = self.current_key(&classification);
+            let new_key_id = self.rotate_key(classification).await?;
+            let new_version = self.keys.read().unwrap().get(&new_key_id).map_or(0, |k| k.version);
+            rotated.push(RotatedKey {
+                classification,
+                previous_key_id: previous.as_ref().map(|k| k.id),
+                previous_version: previous.map_or(0, |k| k.version),
+                new_key_id,
+                new_version,
+            });
+        }
+
+        let rotated_at = Utc::now();
+        *self.last_rotation.write().unwrap() = Some(rotated_at);
+        self.persist_keyring().await?;
+        let retained_versions = self.keys.read().unwrap().values().filter(|k| !k.is_active).count();
+        let report = KeyRotationReport { rotated_at, rotated, retained_versions };
+
+        if let Some(audit) = self.audit_sink() {
+            let mut event = AuditEvent::new(
+                AuditEventType::EncryptionKeyRotated,
+                None,
+                "rotate_encryption_keys".to_string(),
+                AuditOutcome::Success,
+            );
+            event.description = format!(
+                "Rotated {} encryption key(s); {} earlier version(s) retained for decryption",
+                report.rotated.len(), report.retained_versions
+            );
+            event.metadata.insert(
+                "rotated".to_string(),
+                serde_json::to_value(&report.rotated).unwrap_or(serde_json::Value::Null),
+            );
+            audit.log_event(event).await?;
+        }
+        log::info!("Rotated {} encryption key(s)", report.rotated.len());
+        Ok(report)
+    }
+
+    /// Whether `rotation_interval_days` has passed since the last rotation; before the first
+    /// rotation, whether any active key is older than that
+    pub fn rotation_due(&self) -> bool {
+        let interval = chrono::Duration::days(self.rotation_interval_days as i64);
+        match *self.last_rotation.read().unwrap() {
+            Some(last) => Utc::now() - last >= interval,
+            None => self.keys.read().unwrap()
+                .values()
+                .any(|k| k.is_active && k.needs_rotation(self.rotation_interval_days)),
+        }
+    }
+
+    /// When keys were last rotated
+    pub fn last_rotation(&self) -> Option<DateTime<Utc>> {
+        *self.last_rotation.read().unwrap()
+    }
+
+    /// Re-encrypt an envelope under its classification's current key version, for lazy migration
+    /// on read. Returns `None` when the envelope already uses the current version.
+    pub async fn re_encrypt_to_current(&self, blob: &EncryptedData) -> Result<Option<EncryptedData>, SecurityError> {
+        if self.is_current(blob) {
+            return Ok(None);
+        }
+        let plaintext = self.decrypt(blob).await?;
+        self.encrypt(&plaintext, blob.classification, None).await.map(Some)
+    }
+
+    /// `re_encrypt_to_current` for context-bound envelopes; the new envelope stays bound
+    pub async fn re_encrypt_to_current_with_context(
+        &self,
+        blob: &EncryptedData,
+        context: &EncryptionContext,
+    ) -> Result<Option<EncryptedData>, SecurityError> {
+        if self.is_current(blob) {
+            return Ok(None);
+        }
+        let plaintext = self.decrypt_with_context(blob, context).await?;
+        self.encrypt_with_context(&plaintext, blob.classification, None, context).await.map(Some)
+    }
+
+    fn is_current(&self, blob: &EncryptedData) -> bool {
+        self.current_key(&blob.classification).map_or(false, |k| k.id == blob.key_id)
+    }
     
     /// Get key rotation status
     pub fn get_key_rotation_status(&self) -> Vec<(Uuid, bool)> {
         self.keys.read().unwrap()
             .iter()
-            .map(|(id, key)| (*id, key.needs_rotation(90))) // 90 day rotation
+            .map(|(id, key)| (*id, key.is_active && key.needs_rotation(self.rotation_interval_days)))
             .collect()
     }
 }
 
+/// Managed crypto service shared with background jobs
+#[derive(Clone)]
+pub struct CryptoServiceState(pub Arc<CryptoService>);
+
+impl CryptoServiceState {
+    pub fn new(service: CryptoService) -> Self {
+        Self(Arc::new(service))
+    }
+}
+
+/// How often the scheduler checks whether rotation is due
+const KEY_ROTATION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
+/// Check for due key rotation at startup and every 24 hours, rotating when the configured
+/// interval has elapsed
+pub fn start_key_rotation_scheduler(app: tauri::AppHandle) {
+    use tauri::Manager;
+
+    let crypto = app.state::<CryptoServiceState>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // Rotated keys are wrapped into the keyring, which needs the master key
+            if crypto.0.is_initialized().await && crypto.0.rotation_due() {
+                match crypto.0.rotate_keys().await {
+                    Ok(report) => {
+                        let firebase_state = app.state::<crate::services::firebase_service_simple::FirebaseServiceState>();
+                        let firebase_guard = firebase_state.0.lock().await;
+                        if let Some(firebase) = firebase_guard.as_ref() {
+                            if let Err(e) = firebase.audit_log(
+                                "ENCRYPTION_KEY_ROTATED",
+                                "encryption_key",
+                                "system",
+                                false, // Key identifiers only
+                                Some(serde_json::json!({
+                                    "rotated": report.rotated,
+                                    "retained_versions": report.retained_versions,
+                                }))
+                            ).await {
+                                log::error!("Failed to audit encryption key rotation: {}", e);
+                            }
+                        }
+                    }
+                    Err(e) => log::error!("Scheduled encryption key rotation failed: {}", e),
+                }
+            }
+            tokio::time::sleep(KEY_ROTATION_CHECK_INTERVAL).await;
+        }
+    });
+}
+
 /// Initialize cryptographic system
 pub async fn initialize_crypto_system() -> Result<(), SecurityError> {
     // Verify cryptographic capabilities
@@ -586,4 +1292,162 @@ mod tests {
         assert_eq!(sensitive_data, decrypted.as_slice());
         assert!(encrypted.algorithm.contains("Layered") || encrypted.algorithm.contains("Maximum"));
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_maximum_envelope_needs_both_layer_keys() {
+        let crypto_service = CryptoService::new();
+        let sensitive_data = b"Highly sensitive medical research data";
+        let encrypted = crypto_service.encrypt(sensitive_data, DataClassification::MedicalSensitive, None).await.unwrap();
+        assert_eq!(encrypted.layers, vec![EncryptionLayer::Aes256Gcm, EncryptionLayer::ChaCha20Poly1305]);
+
+        let key = crypto_service.keys.read().unwrap()[&encrypted.key_id].key.clone();
+        let aes_key = EncryptionLayer::Aes256Gcm.derive_key(&key).unwrap();
+        let chacha_key = EncryptionLayer::ChaCha20Poly1305.derive_key(&key).unwrap();
+        assert_ne!(aes_key, chacha_key);
+
+        let ciphertext = BASE64.decode(&encrypted.data).unwrap();
+        let nonce = BASE64.decode(&encrypted.iv).unwrap();
+        let aad = BASE64.decode(encrypted.aad.as_ref().unwrap()).unwrap();
+        let wrong = [7u8; 32];
+        assert_eq!(open_layered(&ciphertext, &aes_key, &chacha_key, &aad, &nonce).unwrap(), sensitive_data);
+        let err = open_layered(&ciphertext, &wrong, &chacha_key, &aad, &nonce).unwrap_err();
+        assert!(err.to_string().contains("Inner AES-256-GCM"));
+        let err = open_layered(&ciphertext, &aes_key, &wrong, &aad, &nonce).unwrap_err();
+        assert!(err.to_string().contains("Outer ChaCha20-Poly1305"));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_outer_tag_is_rejected_before_inner_layer() {
+        let crypto_service = CryptoService::new();
+        let mut encrypted = crypto_service.encrypt(b"Trauma history", DataClassification::MedicalSensitive, None).await.unwrap();
+        let mut ciphertext = BASE64.decode(&encrypted.data).unwrap();
+        *ciphertext.last_mut().unwrap() ^= 0x01; // The outer Poly1305 tag ends the ciphertext
+        encrypted.data = BASE64.encode(&ciphertext);
+
+        let err = crypto_service.decrypt(&encrypted).await.unwrap_err();
+        assert!(err.to_string().contains("Outer ChaCha20-Poly1305"));
+
+        // Even with a wrong inner key the failure is the outer one: the inner layer is never reached
+        let key = crypto_service.keys.read().unwrap()[&encrypted.key_id].key.clone();
+        let chacha_key = EncryptionLayer::ChaCha20Poly1305.derive_key(&key).unwrap();
+        let aad = BASE64.decode(encrypted.aad.as_ref().unwrap()).unwrap();
+        let nonce = BASE64.decode(&encrypted.iv).unwrap();
+        let err = open_layered(&ciphertext, &[0u8; 32], &chacha_key, &aad, &nonce).unwrap_err();
+        assert!(err.to_string().contains("Outer ChaCha20-Poly1305"));
+    }
+
+    #[tokio::test]
+    async fn test_data_encrypted_before_rotation_still_decrypts() {
+        let crypto_service = CryptoService::new();
+        let note = b"Intake note written before rotation";
+        let old = crypto_service.encrypt(note, DataClassification::Phi, None).await.unwrap();
+        assert_eq!(old.key_version, 1);
+        // New encryptions reuse the current version rather than minting keys
+        assert_eq!(crypto_service.encrypt(b"x", DataClassification::Phi, None).await.unwrap().key_id, old.key_id);
+
+        let report = crypto_service.rotate_keys().await.unwrap();
+        assert_eq!(report.rotated.len(), 1);
+        assert_eq!(report.rotated[0].new_version, 2);
+        assert!(!crypto_service.rotation_due());
+
+        assert_eq!(crypto_service.decrypt(&old).await.unwrap(), note);
+        assert!(crypto_service.encrypt(note, DataClassification::Phi, Some(old.key_id)).await.is_err());
+
+        let migrated = crypto_service.re_encrypt_to_current(&old).await.unwrap().unwrap();
+        assert_eq!(migrated.key_version, 2);
+        assert_eq!(crypto_service.decrypt(&migrated).await.unwrap(), note);
+        assert!(crypto_service.re_encrypt_to_current(&migrated).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_keyring_survives_restart_and_checks_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = EncryptionContext::for_record("client-1", "note-1");
+
+        let first = CryptoService::new();
+        first.attach_keyring(dir.path()).unwrap();
+        assert!(first.encrypt(b"x", DataClassification::Phi, None).await.is_err());
+        first.initialize_master_key("test_password", None).await.unwrap();
+        let encrypted = first.encrypt_with_context(b"session notes", DataClassification::Phi, None, &context).await.unwrap();
+        let raw = std::fs::read_to_string(dir.path().join(KEYRING_FILE)).unwrap();
+        assert!(raw.contains(&encrypted.key_id.to_string()));
+
+        let wrong = CryptoService::new();
+        wrong.attach_keyring(dir.path()).unwrap();
+        assert!(wrong.initialize_master_key("not_the_password", None).await.is_err());
+
+        let restarted = CryptoService::new();
+        restarted.attach_keyring(dir.path()).unwrap();
+        restarted.initialize_master_key("test_password", None).await.unwrap();
+        assert_eq!(restarted.decrypt_with_context(&encrypted, &context).await.unwrap(), b"session notes");
+    }
+
+    #[tokio::test]
+    async fn test_rotation_state_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = CryptoService::new();
+        first.attach_keyring(dir.path()).unwrap();
+        first.initialize_master_key("test_password", None).await.unwrap();
+        let before = first.encrypt(b"pre-rotation", DataClassification::Phi, None).await.unwrap();
+        let report = first.rotate_keys().await.unwrap();
+
+        let restarted = CryptoService::new();
+        restarted.attach_keyring(dir.path()).unwrap();
+        restarted.initialize_master_key("test_password", None).await.unwrap();
+        assert_eq!(restarted.last_rotation(), Some(report.rotated_at));
+        assert!(!restarted.rotation_due());
+        assert_eq!(restarted.decrypt(&before).await.unwrap(), b"pre-rotation");
+        let migrated = restarted.re_encrypt_to_current(&before).await.unwrap().unwrap();
+        assert_eq!(migrated.key_version, report.rotated[0].new_version);
+    }
+
+    #[tokio::test]
+    async fn test_context_bound_decryption() {
+        let crypto_service = CryptoService::new();
+        crypto_service.initialize_master_key("test_password", None).await.unwrap();
+        
+        let note = b"Session note: patient reports improved sleep";
+        let context = EncryptionContext::for_record("patient-a", "note-1");
+        let encrypted = crypto_service.encrypt_with_context(note, DataClassification::Phi, None, &context).await.unwrap();
+        assert!(encrypted.context_bound);
+        
+        let decrypted = crypto_service.decrypt_with_context(&encrypted, &context).await.unwrap();
+        assert_eq!(note, decrypted.as_slice());
+        
+        // Context-free decryption must not bypass the binding
+        assert!(crypto_service.decrypt(&encrypted).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_relocated_envelope_fails_to_decrypt() {
+        let crypto_service = CryptoService::new();
+        crypto_service.initialize_master_key("test_password", None).await.unwrap();
+        
+        let note = b"Session note: patient reports improved sleep";
+        let context = EncryptionContext::for_record("patient-a", "note-1");
+        let encrypted = crypto_service.encrypt_with_context(note, DataClassification::Phi, None, &context).await.unwrap();
+        
+        // Envelope moved under another patient's record
+        let relocated = EncryptionContext::for_record("patient-b", "note-1");
+        assert!(crypto_service.decrypt_with_context(&encrypted, &relocated).await.is_err());
+        
+        // Envelope moved to another record of the same patient
+        let other_record = EncryptionContext::for_record("patient-a", "note-2");
+        assert!(crypto_service.decrypt_with_context(&encrypted, &other_record).await.is_err());
+    }
+
+    #[test]
+    fn test_aad_fields_cannot_spill_into_each_other() {
+        let spliced = EncryptionContext::for_record("a|record=b", "c");
+        let honest = EncryptionContext::for_record("a", "b|record=c");
+        assert_ne!(spliced.to_aad(DataClassification::Phi), honest.to_aad(DataClassification::Phi));
+
+        let missing = EncryptionContext { patient_id: None, record_id: Some("note-1".to_string()) };
+        let dash = EncryptionContext::for_record("-", "note-1");
+        assert_ne!(missing.to_aad(DataClassification::Phi), dash.to_aad(DataClassification::Phi));
+
+        // Legacy envelopes still decrypt, but not for IDs the old layout could confuse
+        assert!(honest.aad_for_version(DataClassification::Phi, 1).is_err());
+        assert!(EncryptionContext::for_record("patient-a", "note-1").aad_for_version(DataClassification::Phi, 1).is_ok());
+    }
+}
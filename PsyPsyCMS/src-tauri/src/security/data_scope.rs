@@ -0,0 +1,194 @@
+// Role-Based Record Scoping for PsyPsy CMS
+// Decides which client and appointment records a caller's figures and lists may draw on,
+// so dashboards never aggregate over records the caller could not list.
+
+use crate::models::{Appointment, Client, Professional};
+use crate::security::auth::AuthState;
+use crate::security::HealthcareRole;
+use crate::services::FirebaseService;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// How much of the practice a role may see
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopeLevel {
+    /// Every record in the practice
+    Practice,
+    /// Only clients assigned to, and appointments with, the caller
+    Caseload,
+    /// Financial aggregates only; no clinical or demographic figures
+    Billing,
+    /// Nothing
+    None,
+}
+
+/// Scope level per role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataScopePolicy {
+    pub roles: HashMap<HealthcareRole, ScopeLevel>,
+    /// Level for roles without an explicit rule
+    pub default_level: ScopeLevel,
+}
+
+impl Default for DataScopePolicy {
+    fn default() -> Self {
+        let mut roles = HashMap::new();
+        for role in [
+            HealthcareRole::SuperAdmin,
+            HealthcareRole::Administrator,
+            HealthcareRole::Auditor,
+            HealthcareRole::AdminStaff,
+            HealthcareRole::AdministrativeStaff,
+        ] {
+            roles.insert(role, ScopeLevel::Practice);
+        }
+        roles.insert(HealthcareRole::HealthcareProvider, ScopeLevel::Caseload);
+        roles.insert(HealthcareRole::BillingStaff, ScopeLevel::Billing);
+
+        Self {
+            roles,
+            default_level: ScopeLevel::None,
+        }
+    }
+}
+
+impl DataScopePolicy {
+    pub fn level_for(&self, role: Option<&HealthcareRole>) -> ScopeLevel {
+        role.and_then(|r| self.roles.get(r)).copied().unwrap_or(self.default_level)
+    }
+
+    /// Resolve the caller's scope; `professional_ids` are the professional records the caller owns
+    pub fn resolve(&self, role: Option<&HealthcareRole>, professional_ids: HashSet<String>) -> DataScope {
+        match self.level_for(role) {
+            ScopeLevel::Practice => DataScope::Practice,
+            ScopeLevel::Caseload => DataScope::Caseload(professional_ids),
+            ScopeLevel::Billing => DataScope::Billing,
+            ScopeLevel::None => DataScope::None,
+        }
+    }
+}
+
+/// A caller's resolved scope
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataScope {
+    Practice,
+    /// Professional IDs whose caseload is visible
+    Caseload(HashSet<String>),
+    Billing,
+    None,
+}
+
+impl DataScope {
+    /// Whether client-level and clinical figures may be shown
+    pub fn allows_clinical(&self) -> bool {
+        matches!(self, DataScope::Practice | DataScope::Caseload(_))
+    }
+
+    /// Billing sees financial aggregates only, never client records
+    pub fn includes_client(&self, client: &Client) -> bool {
+        match self {
+            DataScope::Practice => true,
+            DataScope::Billing => false,
+            DataScope::Caseload(ids) => client.assigned_professionals.iter().any(|p| ids.contains(p)),
+            DataScope::None => false,
+        }
+    }
+
+    pub fn includes_appointment(&self, appointment: &Appointment) -> bool {
+        match self {
+            DataScope::Practice | DataScope::Billing => true,
+            DataScope::Caseload(ids) => appointment.assigned_professional.as_ref().is_some_and(|p| ids.contains(p)),
+            DataScope::None => false,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DataScope::Practice => "practice",
+            DataScope::Caseload(_) => "caseload",
+            DataScope::Billing => "billing",
+            DataScope::None => "none",
+        }
+    }
+}
+
+/// Page size when walking a whole collection
+const SCAN_PAGE_SIZE: u32 = 500;
+
+/// Every record of `collection` that `keep` accepts, read a page at a time until a short page.
+/// Out-of-scope records are dropped as each page arrives, and no fixed cap cuts the list short.
+pub async fn collect_matching<T>(
+    firebase: &FirebaseService,
+    collection: &str,
+    keep: impl Fn(&T) -> bool,
+) -> Result<Vec<T>, String>
+where
+    T: for<'de> Deserialize<'de> + Send,
+{
+    let mut matching = Vec::new();
+    for page in 1.. {
+        let records: Vec<T> = firebase.query_documents(collection, page, SCAN_PAGE_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        let last_page = records.len() < SCAN_PAGE_SIZE as usize;
+        matching.extend(records.into_iter().filter(|record| keep(record)));
+        if last_page {
+            break;
+        }
+    }
+    Ok(matching)
+}
+
+/// Every record of `collection` whose `field` equals `value` and that `keep` accepts, read a
+/// page at a time like `collect_matching`
+pub async fn collect_matching_where<T>(
+    firebase: &FirebaseService,
+    collection: &str,
+    field: &str,
+    value: &serde_json::Value,
+    keep: impl Fn(&T) -> bool,
+) -> Result<Vec<T>, String>
+where
+    T: for<'de> Deserialize<'de> + Send,
+{
+    let mut matching = Vec::new();
+    for page in 1.. {
+        let records: Vec<T> = firebase.query_documents_where(collection, field, value, page, SCAN_PAGE_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        let last_page = records.len() < SCAN_PAGE_SIZE as usize;
+        matching.extend(records.into_iter().filter(|record| keep(record)));
+        if last_page {
+            break;
+        }
+    }
+    Ok(matching)
+}
+
+/// Professional records owned by a user; the user ID itself also counts as a professional ID
+pub fn owned_professional_ids(user_id: &str, professionals: &[Professional]) -> HashSet<String> {
+    professionals
+        .iter()
+        .filter(|p| p.user_id == user_id)
+        .map(|p| p.object_id.clone())
+        .chain(std::iter::once(user_id.to_string()))
+        .collect()
+}
+
+/// Resolve the authenticated caller's scope, looking up their professional records when needed
+pub async fn resolve_caller_scope(
+    policy: &DataScopePolicy,
+    auth: &AuthState,
+    firebase: &FirebaseService,
+) -> Result<DataScope, String> {
+    let role = auth.get_role();
+    if policy.level_for(role) != ScopeLevel::Caseload {
+        return Ok(policy.resolve(role, HashSet::new()));
+    }
+    let user_id = auth.user_id.as_deref().ok_or("No user ID in auth state")?;
+    let professionals: Vec<Professional> = firebase.query_documents("professionals", 1, 10_000)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(policy.resolve(role, owned_professional_ids(user_id, &professionals)))
+}
@@ -0,0 +1,321 @@
+// Effective Security Configuration for PsyPsy CMS
+// Merged, running security configuration with redaction and drift hashing for audit sign-off.
+// The drift hash is an HMAC under a per-install key, so it covers secrets without letting anyone
+// who sees it test guesses of them; the last reviewed hash is kept next to the key.
+
+use crate::security::compliance::ComplianceConfig;
+use crate::security::outbound::OutboundPolicy;
+use crate::security::rate_limit::{RateLimitConfig, SessionQuotaAction};
+use crate::security::SecurityConfig;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Placeholder written in place of secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Per-install key for the drift hash
+const HASH_KEY_FILE: &str = "security_config_hash.key";
+/// Hash of the configuration at the last review
+const REVIEW_BASELINE_FILE: &str = "security_config_review.json";
+
+/// Key fragments whose string values are never exported
+const SECRET_KEY_FRAGMENTS: &[&str] = &["secret", "password", "token", "api_key", "private_key", "credential"];
+
+/// Running security configuration assembled from defaults and environment overrides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveSecurityConfig {
+    pub security: SecurityConfig,
+    pub rate_limits: RateLimitConfig,
+    pub compliance: ComplianceConfig,
+    pub outbound: OutboundPolicy,
+}
+
+impl EffectiveSecurityConfig {
+    /// Assemble configuration from defaults and environment overrides
+    pub fn from_env() -> Self {
+        let mut security = SecurityConfig::default();
+        if let Ok(secret) = std::env::var("JWT_SECRET") {
+            security.jwt_secret = secret;
+        }
+        if let Some(seconds) = std::env::var("SESSION_TIMEOUT_SECONDS").ok().and_then(|v| v.parse().ok()) {
+            security.session_timeout_seconds = seconds;
+        } else if let Some(seconds) = std::env::var("SESSION_TIMEOUT_HOURS").ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .and_then(|hours| hours.checked_mul(3600))
+        {
+            // Earlier releases configured the timeout in hours
+            log::warn!("SESSION_TIMEOUT_HOURS is deprecated; set SESSION_TIMEOUT_SECONDS instead");
+            security.session_timeout_seconds = seconds;
+        }
+        if let Ok(path) = std::env::var("AUDIT_LOG_PATH") {
+            security.audit_log_path = path;
+        }
+        if let Some(max) = std::env::var("MAX_FAILED_LOGINS").ok().and_then(|v| v.parse().ok()) {
+            security.max_failed_logins = max;
+        }
+        if let Ok(value) = std::env::var("LOCKOUT_DURATION_SECONDS") {
+            match value.trim().parse::<i64>() {
+                Ok(seconds) if chrono::Duration::try_seconds(seconds).is_some() && seconds >= 0 => {
+                    security.lockout_duration_seconds = seconds;
+                }
+                _ => log::warn!("Ignoring invalid LOCKOUT_DURATION_SECONDS '{}'", value),
+            }
+        }
+
+        let mut rate_limits = RateLimitConfig::default();
+        if let Some(geo) = rate_limits.ip_limits.geographic_restrictions.as_mut() {
+            if let Ok(countries) = std::env::var("GEO_ALLOWED_COUNTRIES") {
+                geo.allowed_countries = countries
+                    .split(',')
+                    .map(|c| c.trim().to_ascii_uppercase())
+                    .filter(|c| !c.is_empty())
+                    .collect();
+            }
+            if let Ok(value) = std::env::var("GEO_BLOCK_VPN_PROXY") {
+                match value.trim().to_ascii_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => geo.block_vpn_proxy = true,
+                    "0" | "false" | "no" | "off" => geo.block_vpn_proxy = false,
+                    _ => log::warn!("Ignoring invalid GEO_BLOCK_VPN_PROXY '{}'", value),
+                }
+            }
+        }
+
+        if let Ok(value) = std::env::var("SESSION_QUOTA_ACTION") {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "reject" => rate_limits.session_quota_action = SessionQuotaAction::Reject,
+                "evict_oldest" => rate_limits.session_quota_action = SessionQuotaAction::EvictOldest,
+                _ => log::warn!("Ignoring invalid SESSION_QUOTA_ACTION '{}'", value),
+            }
+        }
+
+        Self {
+            security,
+            rate_limits,
+            compliance: ComplianceConfig::default(),
+            outbound: crate::security::outbound::outbound_guard().policy().clone(),
+        }
+    }
+
+    /// Configuration as JSON with secret values replaced
+    pub fn redacted(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or(Value::Null);
+        redact_secrets(&mut value);
+        value
+    }
+
+    /// HMAC-SHA256 over the full canonical configuration, so any change (secrets included)
+    /// alters it while the hash alone reveals nothing about the secrets
+    pub fn config_hash(&self, key: &hmac::Key) -> String {
+        // serde_json maps are sorted, which keeps the serialization canonical
+        let canonical = serde_json::to_vec(&serde_json::to_value(self).unwrap_or(Value::Null))
+            .unwrap_or_default();
+        hmac::sign(key, &canonical)
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+fn random_key() -> Vec<u8> {
+    use ring::rand::SecureRandom;
+    let mut key = vec![0u8; 32];
+    if ring::rand::SystemRandom::new().fill(&mut key).is_err() {
+        log::error!("Random source unavailable; security config hashes are unkeyed until storage is attached");
+    }
+    key
+}
+
+fn load_or_create_key(path: &Path) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) if key.len() == 32 => Ok(key),
+        Ok(_) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "security config hash key is corrupt")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            use ring::rand::SecureRandom;
+            let mut key = vec![0u8; 32];
+            ring::rand::SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "random source unavailable"))?;
+            std::fs::write(path, &key)?;
+            Ok(key)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                let is_secret = SECRET_KEY_FRAGMENTS.iter().any(|fragment| key.contains(fragment));
+                if is_secret && child.is_string() {
+                    *child = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(child);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Redacted configuration snapshot returned for review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityConfigSnapshot {
+    pub config: Value,
+    pub config_hash: String,
+    pub previous_hash: Option<String>,
+    pub changed_since_last_review: bool,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Review baseline saved between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ReviewBaseline {
+    recorded_hash: Option<String>,
+}
+
+struct ConfigRecord {
+    config: EffectiveSecurityConfig,
+    hash_key: hmac::Key,
+    /// Hash last written to the audit log
+    recorded_hash: Option<String>,
+    baseline_path: Option<PathBuf>,
+}
+
+/// Managed state holding the live configuration
+#[derive(Clone)]
+pub struct SecurityConfigState(Arc<RwLock<ConfigRecord>>);
+
+impl SecurityConfigState {
+    /// Until storage is attached the hash key is ephemeral and no baseline is kept
+    pub fn new(config: EffectiveSecurityConfig) -> Self {
+        Self(Arc::new(RwLock::new(ConfigRecord {
+            config,
+            hash_key: hmac::Key::new(hmac::HMAC_SHA256, &random_key()),
+            recorded_hash: None,
+            baseline_path: None,
+        })))
+    }
+
+    /// Load the install's hash key and last reviewed hash from `dir`, so drift between runs
+    /// (an edited environment, say) shows up at the next review
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        let key = load_or_create_key(&dir.join(HASH_KEY_FILE))?;
+        let baseline_path = dir.join(REVIEW_BASELINE_FILE);
+        let baseline = match std::fs::read(&baseline_path) {
+            Ok(bytes) => serde_json::from_slice::<ReviewBaseline>(&bytes)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ReviewBaseline::default(),
+            Err(e) => return Err(e),
+        };
+        let mut record = self.0.write().unwrap();
+        record.hash_key = hmac::Key::new(hmac::HMAC_SHA256, &key);
+        record.recorded_hash = baseline.recorded_hash;
+        record.baseline_path = Some(baseline_path);
+        Ok(())
+    }
+
+    /// Current configuration
+    pub fn current(&self) -> EffectiveSecurityConfig {
+        self.0.read().unwrap().config.clone()
+    }
+
+    /// Produce a review snapshot and remember its hash as the audited baseline
+    pub fn snapshot_for_review(&self) -> SecurityConfigSnapshot {
+        let mut record = self.0.write().unwrap();
+        let config_hash = record.config.config_hash(&record.hash_key);
+        let previous_hash = record.recorded_hash.replace(config_hash.clone());
+        if let Some(path) = &record.baseline_path {
+            let baseline = ReviewBaseline { recorded_hash: Some(config_hash.clone()) };
+            match serde_json::to_vec_pretty(&baseline) {
+                Ok(bytes) => {
+                    if let Err(e) = std::fs::write(path, bytes) {
+                        log::warn!("Failed to persist security config review baseline: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to serialize security config review baseline: {}", e),
+            }
+        }
+        SecurityConfigSnapshot {
+            config: record.config.redacted(),
+            changed_since_last_review: previous_hash.as_deref().map_or(false, |h| h != config_hash),
+            config_hash,
+            previous_hash,
+            generated_at: Utc::now(),
+        }
+    }
+}
+
+impl Default for SecurityConfigState {
+    fn default() -> Self {
+        Self::new(EffectiveSecurityConfig::from_env())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> EffectiveSecurityConfig {
+        EffectiveSecurityConfig {
+            security: SecurityConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            compliance: ComplianceConfig::default(),
+            outbound: OutboundPolicy::default(),
+        }
+    }
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let mut config = test_config();
+        config.security.jwt_secret = "very-secret-value".to_string();
+
+        let redacted = config.redacted();
+        assert_eq!(redacted["security"]["jwt_secret"], REDACTED);
+        assert!(!redacted.to_string().contains("very-secret-value"));
+        // Numeric settings that merely mention keys are kept
+        assert_eq!(redacted["security"]["encryption_key_rotation_days"], 90);
+    }
+
+    #[test]
+    fn test_hash_is_keyed_and_tracks_secret_changes() {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, &[7u8; 32]);
+        let mut config = test_config();
+        let before = config.config_hash(&key);
+        config.security.jwt_secret = "rotated-secret".to_string();
+        assert_ne!(config.config_hash(&key), before);
+        // Without the key the hash cannot be recomputed from a guessed configuration
+        assert_ne!(config.config_hash(&hmac::Key::new(hmac::HMAC_SHA256, &[8u8; 32])), config.config_hash(&key));
+    }
+
+    #[test]
+    fn test_drift_between_runs_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = SecurityConfigState::new(test_config());
+        state.attach_storage(dir.path()).unwrap();
+        let first = state.snapshot_for_review();
+        assert!(!first.changed_since_last_review);
+
+        // Same configuration on the next run: no drift
+        let restarted = SecurityConfigState::new(test_config());
+        restarted.attach_storage(dir.path()).unwrap();
+        let unchanged = restarted.snapshot_for_review();
+        assert!(!unchanged.changed_since_last_review);
+        assert_eq!(unchanged.config_hash, first.config_hash);
+
+        let mut edited = test_config();
+        edited.security.session_timeout_seconds = 4 * 3600;
+        let reconfigured = SecurityConfigState::new(edited);
+        reconfigured.attach_storage(dir.path()).unwrap();
+        let second = reconfigured.snapshot_for_review();
+        assert!(second.changed_since_last_review);
+        assert_eq!(second.previous_hash.as_deref(), Some(first.config_hash.as_str()));
+    }
+}
@@ -0,0 +1,185 @@
+// IP Geolocation for PsyPsy CMS
+// Sessions record where they were opened from so sign-ins can be compared with the user's recent
+// locations. Two sign-ins farther apart than anyone could travel in the time between them
+// ("impossible travel") are treated as anomalous. The lookup sits behind `GeoResolver` so the
+// provider can be swapped, and tests can supply fixed locations.
+//
+// An HTTP lookup sends the clinician's IP address to the configured provider, a third party.
+// It is opt-in (`GEOIP_LOOKUP_URL`); a deployment enabling it must list the provider among its
+// personal information disclosures (Quebec Law 25) or point it at a service it hosts itself.
+
+use crate::security::outbound::is_internal_address;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Faster than a commercial flight, allowing for airport time
+const MAX_TRAVEL_SPEED_KMH: f64 = 1_000.0;
+/// IP geolocation is imprecise; closer sign-ins are never compared
+const MIN_TRAVEL_DISTANCE_KM: f64 = 500.0;
+/// How far back sign-in locations are compared
+pub const TRAVEL_WINDOW_HOURS: i64 = 24;
+
+const EARTH_RADIUS_KM: f64 = 6_371.0;
+
+/// Sign-in waits on the lookup, so a slow provider is given up on
+const LOOKUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub city: Option<String>,
+    /// ISO country code
+    pub country: Option<String>,
+}
+
+impl GeoLocation {
+    /// "City, CC" as far as known, else coordinates
+    pub fn label(&self) -> String {
+        match (&self.city, &self.country) {
+            (Some(city), Some(country)) => format!("{}, {}", city, country),
+            (Some(place), None) | (None, Some(place)) => place.clone(),
+            (None, None) => format!("{:.2},{:.2}", self.latitude, self.longitude),
+        }
+    }
+
+    /// Great-circle distance in kilometres
+    pub fn distance_km(&self, other: &GeoLocation) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.longitude - self.longitude).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
+
+/// Looks up where an IP address is
+#[async_trait]
+pub trait GeoResolver: Send + Sync {
+    /// `Ok(None)` when the address cannot be placed, e.g. a private network address
+    async fn resolve(&self, ip: &str) -> Result<Option<GeoLocation>, String>;
+}
+
+/// Places nothing; sessions carry no location and travel is never checked
+pub struct NoopGeoResolver;
+
+#[async_trait]
+impl GeoResolver for NoopGeoResolver {
+    async fn resolve(&self, _ip: &str) -> Result<Option<GeoLocation>, String> {
+        Ok(None)
+    }
+}
+
+/// Queries a lookup service at a URL containing `{ip}`, answering with a `GeoLocation` as JSON
+pub struct HttpGeoResolver {
+    url_template: String,
+    client: reqwest::Client,
+}
+
+impl HttpGeoResolver {
+    pub fn new(url_template: String) -> Self {
+        Self { url_template, client: crate::security::outbound::guarded_client() }
+    }
+}
+
+#[async_trait]
+impl GeoResolver for HttpGeoResolver {
+    async fn resolve(&self, ip: &str) -> Result<Option<GeoLocation>, String> {
+        let parsed: IpAddr = ip.parse().map_err(|_| format!("Invalid IP address '{}'", ip))?;
+        if is_internal_address(parsed) {
+            return Ok(None);
+        }
+        let response = self
+            .client
+            .get(self.url_template.replace("{ip}", &parsed.to_string()))
+            .timeout(LOOKUP_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("Geolocation lookup failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Geolocation lookup returned {}", response.status()));
+        }
+        response.json::<GeoLocation>().await.map(Some).map_err(|e| format!("Invalid geolocation response: {}", e))
+    }
+}
+
+/// `HttpGeoResolver` when `GEOIP_LOOKUP_URL` is set, otherwise no lookups
+pub fn resolver_from_env() -> Arc<dyn GeoResolver> {
+    match std::env::var("GEOIP_LOOKUP_URL") {
+        Ok(url) if url.contains("{ip}") => Arc::new(HttpGeoResolver::new(url)),
+        Ok(url) => {
+            log::warn!("Ignoring GEOIP_LOOKUP_URL without an {{ip}} placeholder: {}", url);
+            Arc::new(NoopGeoResolver)
+        }
+        Err(_) => Arc::new(NoopGeoResolver),
+    }
+}
+
+/// A sign-in too far from a recent one to have been reached in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpossibleTravel {
+    pub previous: GeoLocation,
+    pub previous_at: DateTime<Utc>,
+    pub current: GeoLocation,
+    pub distance_km: f64,
+    pub elapsed_minutes: i64,
+    /// Speed the user would have needed
+    pub speed_kmh: f64,
+}
+
+/// The most implausible jump from `recent` sign-ins (within the travel window) to `current`
+pub fn detect_impossible_travel(
+    recent: &[(GeoLocation, DateTime<Utc>)],
+    current: &GeoLocation,
+    now: DateTime<Utc>,
+) -> Option<ImpossibleTravel> {
+    recent
+        .iter()
+        .filter(|(_, at)| *at <= now && now - *at <= Duration::hours(TRAVEL_WINDOW_HOURS))
+        .filter_map(|(previous, at)| {
+            let distance_km = previous.distance_km(current);
+            // At least a minute apart, so simultaneous sign-ins do not divide by zero
+            let hours = ((now - *at).num_seconds() as f64 / 3600.0).max(1.0 / 60.0);
+            let speed_kmh = distance_km / hours;
+            (distance_km >= MIN_TRAVEL_DISTANCE_KM && speed_kmh > MAX_TRAVEL_SPEED_KMH).then(|| ImpossibleTravel {
+                previous: previous.clone(),
+                previous_at: *at,
+                current: current.clone(),
+                distance_km,
+                elapsed_minutes: (now - *at).num_minutes(),
+                speed_kmh,
+            })
+        })
+        .max_by(|a, b| a.speed_kmh.total_cmp(&b.speed_kmh))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(city: &str, latitude: f64, longitude: f64) -> GeoLocation {
+        GeoLocation { latitude, longitude, city: Some(city.to_string()), country: None }
+    }
+
+    #[test]
+    fn test_travel_speed_decides_what_is_impossible() {
+        let montreal = place("Montreal", 45.50, -73.57);
+        let quebec = place("Quebec", 46.81, -71.21);
+        let paris = place("Paris", 48.86, 2.35);
+        let now = Utc::now();
+
+        assert!((montreal.distance_km(&paris) - 5_520.0).abs() < 50.0);
+        // Montreal to Paris in an hour is impossible; in a day it is a flight
+        let flagged = detect_impossible_travel(&[(montreal.clone(), now - Duration::hours(1))], &paris, now).unwrap();
+        assert_eq!(flagged.previous.label(), "Montreal");
+        assert_eq!(flagged.elapsed_minutes, 60);
+        assert!(detect_impossible_travel(&[(montreal.clone(), now - Duration::hours(12))], &paris, now).is_none());
+        // Nearby cities are within geolocation noise
+        assert!(detect_impossible_travel(&[(montreal, now - Duration::minutes(5))], &quebec, now).is_none());
+    }
+}
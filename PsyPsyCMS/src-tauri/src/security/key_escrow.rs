@@ -0,0 +1,328 @@
+// Master Key Escrow for PsyPsy CMS
+// Optional M-of-N Shamir secret sharing of the note storage master key, so it can be rebuilt
+// from a quorum of custodian shares after a disaster without any single person holding the key.
+
+use crate::security::SecurityError;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+use uuid::Uuid;
+use zeroize::Zeroize;
+
+/// Manifest file inside the app data directory; holds no share material
+const MANIFEST_FILE: &str = "key_escrow.json";
+
+/// Escrow configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyEscrowConfig {
+    /// Allow creating an escrow at all
+    pub enabled: bool,
+    /// Shares required to recover the key (M)
+    pub threshold: u8,
+    /// Shares handed out (N)
+    pub total_shares: u8,
+}
+
+impl Default for KeyEscrowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 3,
+            total_shares: 5,
+        }
+    }
+}
+
+impl KeyEscrowConfig {
+    /// Defaults with `KEY_ESCROW_*` environment overrides
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str, default: u8| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            enabled: std::env::var("KEY_ESCROW_ENABLED")
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(defaults.enabled),
+            threshold: number("KEY_ESCROW_THRESHOLD", defaults.threshold),
+            total_shares: number("KEY_ESCROW_TOTAL_SHARES", defaults.total_shares),
+        }
+    }
+}
+
+/// Public description of an escrow, kept locally so recovery knows the quorum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscrowManifest {
+    pub escrow_id: Uuid,
+    pub threshold: u8,
+    pub total_shares: u8,
+    /// Custodian holding each share, in share-index order
+    pub custodians: Vec<String>,
+    /// Fingerprint used to confirm a reconstructed key
+    pub key_fingerprint: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EscrowManifest {
+    pub fn load(dir: &Path) -> Result<Option<Self>, SecurityError> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(&path).map_err(|e| SecurityError::ConfigurationError {
+            reason: format!("Failed to read escrow manifest: {}", e),
+        })?;
+        serde_json::from_slice(&bytes).map(Some).map_err(|e| SecurityError::ConfigurationError {
+            reason: format!("Invalid escrow manifest: {}", e),
+        })
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), SecurityError> {
+        let bytes = serde_json::to_vec_pretty(self).map_err(|e| SecurityError::ConfigurationError {
+            reason: format!("Failed to serialize escrow manifest: {}", e),
+        })?;
+        std::fs::write(dir.join(MANIFEST_FILE), bytes).map_err(|e| SecurityError::ConfigurationError {
+            reason: format!("Failed to write escrow manifest: {}", e),
+        })
+    }
+}
+
+/// One custodian's share, handed over once at distribution time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub escrow_id: Uuid,
+    /// Share index (x coordinate), 1-based
+    pub index: u8,
+    pub custodian_id: String,
+    /// Base64 share bytes
+    pub share: String,
+}
+
+/// Fingerprint of a key that does not reveal it
+pub fn key_fingerprint(key: &[u8]) -> String {
+    let digest = Sha256::new()
+        .chain_update(b"psypsy-key-escrow-fingerprint")
+        .chain_update(key)
+        .finalize();
+    digest.iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(2^8) (a^254); `a` must be non-zero
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Split `secret` into `total` shares, any `threshold` of which rebuild it
+pub fn split_secret(secret: &[u8], threshold: u8, total: u8) -> Result<Vec<(u8, Vec<u8>)>, SecurityError> {
+    if threshold < 2 || total < threshold {
+        return Err(SecurityError::ConfigurationError {
+            reason: format!("Invalid escrow quorum {} of {}", threshold, total),
+        });
+    }
+
+    let mut shares: Vec<(u8, Vec<u8>)> = (1..=total).map(|x| (x, Vec::with_capacity(secret.len()))).collect();
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret {
+        coefficients[0] = byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+        for (x, share) in shares.iter_mut() {
+            // Horner evaluation of the random polynomial at x
+            let y = coefficients.iter().rev().fold(0u8, |acc, &c| gf_mul(acc, *x) ^ c);
+            share.push(y);
+        }
+    }
+    coefficients.zeroize();
+    Ok(shares)
+}
+
+/// Lagrange interpolation at zero over the given shares
+pub fn combine_shares(shares: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let length = shares.first().map(|(_, s)| s.len()).unwrap_or(0);
+    (0..length)
+        .map(|i| {
+            shares.iter().fold(0u8, |secret, (xj, yj)| {
+                let basis = shares
+                    .iter()
+                    .filter(|(xm, _)| xm != xj)
+                    .fold(1u8, |acc, (xm, _)| gf_mul(acc, gf_mul(*xm, gf_inv(xm ^ xj))));
+                secret ^ gf_mul(yj[i], basis)
+            })
+        })
+        .collect()
+}
+
+/// Create an escrow of `master_key`, one share per custodian
+pub fn create_escrow(
+    master_key: &[u8],
+    threshold: u8,
+    custodians: &[String],
+    created_by: &str,
+) -> Result<(EscrowManifest, Vec<KeyShare>), SecurityError> {
+    let unique: HashSet<&String> = custodians.iter().collect();
+    if unique.len() != custodians.len() || custodians.iter().any(|c| c.trim().is_empty()) {
+        return Err(SecurityError::ValidationFailed {
+            reason: "Each share must go to a distinct, named custodian".to_string(),
+        });
+    }
+    let total = u8::try_from(custodians.len()).map_err(|_| SecurityError::ValidationFailed {
+        reason: "At most 255 custodians are supported".to_string(),
+    })?;
+
+    let manifest = EscrowManifest {
+        escrow_id: Uuid::new_v4(),
+        threshold,
+        total_shares: total,
+        custodians: custodians.to_vec(),
+        key_fingerprint: key_fingerprint(master_key),
+        created_by: created_by.to_string(),
+        created_at: Utc::now(),
+    };
+    let shares = split_secret(master_key, threshold, total)?
+        .into_iter()
+        .zip(custodians)
+        .map(|((index, mut bytes), custodian)| {
+            let share = KeyShare {
+                escrow_id: manifest.escrow_id,
+                index,
+                custodian_id: custodian.clone(),
+                share: BASE64.encode(&bytes),
+            };
+            bytes.zeroize();
+            share
+        })
+        .collect();
+
+    Ok((manifest, shares))
+}
+
+/// Rebuild the master key; refuses anything short of a verified quorum
+pub fn recover_master_key(manifest: &EscrowManifest, shares: &[KeyShare]) -> Result<Vec<u8>, SecurityError> {
+    let mut seen = HashSet::new();
+    let mut points = Vec::new();
+    for share in shares {
+        if share.escrow_id != manifest.escrow_id {
+            return Err(SecurityError::ValidationFailed {
+                reason: format!("Share {} belongs to a different escrow", share.index),
+            });
+        }
+        let expected_custodian = manifest.custodians.get((share.index as usize).wrapping_sub(1));
+        if expected_custodian != Some(&share.custodian_id) {
+            return Err(SecurityError::ValidationFailed {
+                reason: format!("Share {} is not registered to custodian {}", share.index, share.custodian_id),
+            });
+        }
+        if !seen.insert(share.index) {
+            continue;
+        }
+        let bytes = BASE64.decode(&share.share).map_err(|_| SecurityError::ValidationFailed {
+            reason: format!("Share {} is not valid base64", share.index),
+        })?;
+        points.push((share.index, bytes));
+    }
+
+    if points.len() < manifest.threshold as usize {
+        return Err(SecurityError::AccessDenied {
+            reason: format!(
+                "Key recovery requires {} distinct custodian shares; {} presented",
+                manifest.threshold,
+                points.len()
+            ),
+        });
+    }
+    let length = points[0].1.len();
+    if points.iter().any(|(_, bytes)| bytes.len() != length) {
+        return Err(SecurityError::ValidationFailed {
+            reason: "Shares have inconsistent lengths".to_string(),
+        });
+    }
+
+    let key = combine_shares(&points);
+    for (_, bytes) in points.iter_mut() {
+        bytes.zeroize();
+    }
+    if key_fingerprint(&key) != manifest.key_fingerprint {
+        return Err(SecurityError::CryptographicError {
+            reason: "Recovered key does not match the escrowed key fingerprint".to_string(),
+        });
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custodians(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("custodian-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_any_quorum_recovers_key() {
+        let key = [42u8; 32];
+        let (manifest, shares) = create_escrow(&key, 3, &custodians(5), "admin").unwrap();
+
+        let subset = vec![shares[4].clone(), shares[1].clone(), shares[2].clone()];
+        assert_eq!(recover_master_key(&manifest, &subset).unwrap(), key.to_vec());
+        assert_eq!(recover_master_key(&manifest, &shares).unwrap(), key.to_vec());
+    }
+
+    #[test]
+    fn test_recovery_refused_below_threshold() {
+        let (manifest, shares) = create_escrow(&[7u8; 32], 3, &custodians(5), "admin").unwrap();
+
+        let err = recover_master_key(&manifest, &shares[..2]).unwrap_err();
+        assert!(matches!(err, SecurityError::AccessDenied { .. }));
+
+        // Presenting the same share twice does not count toward the quorum
+        let repeated = vec![shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert!(matches!(recover_master_key(&manifest, &repeated), Err(SecurityError::AccessDenied { .. })));
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_shares_rejected() {
+        let (manifest, mut shares) = create_escrow(&[9u8; 32], 2, &custodians(3), "admin").unwrap();
+        let (_, foreign) = create_escrow(&[9u8; 32], 2, &custodians(3), "admin").unwrap();
+
+        assert!(recover_master_key(&manifest, &[shares[0].clone(), foreign[1].clone()]).is_err());
+
+        let mut bytes = BASE64.decode(&shares[1].share).unwrap();
+        bytes[0] ^= 0xFF;
+        shares[1].share = BASE64.encode(&bytes);
+        assert!(matches!(
+            recover_master_key(&manifest, &shares[..2]),
+            Err(SecurityError::CryptographicError { .. })
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+// Account Lockout for PsyPsy CMS
+// Failed password checks are counted per account (the sign-in email), not per IP, so a brute
+// force spread across many addresses still trips the lock. After `max_failed_logins` failures the
+// account is refused for `lockout_duration_seconds`; a successful sign-in resets the count.
+// Failures older than the lockout duration are forgotten, so the map only holds recent accounts.
+
+use crate::security::{SecurityConfig, SecurityError};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Reason carried by the error returned for a locked account
+pub const ACCOUNT_LOCKED: &str = "account locked";
+
+/// Lock applied when the configured duration is negative or out of range
+const DEFAULT_LOCKOUT_SECONDS: i64 = 900;
+
+#[derive(Debug)]
+struct FailedLogins {
+    failures: u32,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl FailedLogins {
+    /// Whether the entry still matters at `now`: a lock in force, or failures inside the window
+    fn is_live(&self, now: DateTime<Utc>, window: Duration) -> bool {
+        match self.locked_until {
+            Some(until) => until > now,
+            None => self.last_failure + window > now,
+        }
+    }
+}
+
+/// Managed per-account failed login tracking
+#[derive(Debug)]
+pub struct LoginLockout {
+    /// Zero disables the lockout
+    max_failed_logins: u32,
+    lockout_duration: Duration,
+    accounts: Mutex<HashMap<String, FailedLogins>>,
+}
+
+fn account_key(account: &str) -> String {
+    account.trim().to_lowercase()
+}
+
+impl LoginLockout {
+    pub fn new(max_failed_logins: u32, lockout_duration: Duration) -> Self {
+        Self { max_failed_logins, lockout_duration, accounts: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn from_config(config: &SecurityConfig) -> Self {
+        let lockout_duration = Duration::try_seconds(config.lockout_duration_seconds)
+            .filter(|duration| *duration >= Duration::zero())
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "Ignoring invalid lockout duration {}s; using {}s",
+                    config.lockout_duration_seconds, DEFAULT_LOCKOUT_SECONDS
+                );
+                Duration::seconds(DEFAULT_LOCKOUT_SECONDS)
+            });
+        Self::new(config.max_failed_logins, lockout_duration)
+    }
+
+    /// When the account's lock lifts, if it is locked at `now`. An expired lock is cleared along
+    /// with its failure count.
+    pub fn locked_until(&self, account: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        let key = account_key(account);
+        let until = accounts.get(&key)?.locked_until?;
+        if until <= now {
+            accounts.remove(&key);
+            return None;
+        }
+        Some(until)
+    }
+
+    /// Refuse a locked account before its password is checked
+    pub fn check(&self, account: &str, now: DateTime<Utc>) -> Result<(), SecurityError> {
+        match self.locked_until(account, now) {
+            Some(_) => Err(SecurityError::AuthenticationFailed { reason: ACCOUNT_LOCKED.to_string() }),
+            None => Ok(()),
+        }
+    }
+
+    /// Count a failed password check; returns when the lock lifts if this failure locked the account
+    pub fn record_failure(&self, account: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.max_failed_logins == 0 {
+            return None;
+        }
+        let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        let window = self.lockout_duration;
+        accounts.retain(|_, entry| entry.is_live(now, window));
+        let entry = accounts
+            .entry(account_key(account))
+            .or_insert(FailedLogins { failures: 0, last_failure: now, locked_until: None });
+        if entry.locked_until.is_some_and(|until| until > now) {
+            return None;
+        }
+        entry.failures += 1;
+        entry.last_failure = now;
+        if entry.failures < self.max_failed_logins {
+            return None;
+        }
+        let until = now + self.lockout_duration;
+        entry.locked_until = Some(until);
+        Some(until)
+    }
+
+    /// A successful sign-in clears the account's failures
+    pub fn record_success(&self, account: &str) {
+        self.accounts.lock().unwrap_or_else(|e| e.into_inner()).remove(&account_key(account));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockout_trips_and_expires() {
+        let lockout = LoginLockout::new(3, Duration::minutes(15));
+        let now = Utc::now();
+
+        assert_eq!(lockout.record_failure("clinician@example.com", now), None);
+        assert_eq!(lockout.record_failure("clinician@example.com", now), None);
+        // Keyed on the account, whatever case or address the attempts came from
+        let until = lockout.record_failure(" Clinician@Example.com", now).expect("third failure locks");
+        assert_eq!(until, now + Duration::minutes(15));
+
+        let err = lockout.check("clinician@example.com", now + Duration::minutes(14)).unwrap_err();
+        assert!(matches!(err, SecurityError::AuthenticationFailed { ref reason } if reason == ACCOUNT_LOCKED));
+        assert!(lockout.check("other@example.com", now).is_ok());
+
+        // Once the lock lifts the count starts over
+        let later = now + Duration::minutes(15);
+        assert!(lockout.check("clinician@example.com", later).is_ok());
+        assert_eq!(lockout.record_failure("clinician@example.com", later), None);
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let lockout = LoginLockout::new(2, Duration::minutes(15));
+        let now = Utc::now();
+        lockout.record_failure("clinician@example.com", now);
+        lockout.record_success("clinician@example.com");
+        assert_eq!(lockout.record_failure("clinician@example.com", now), None);
+        assert!(lockout.check("clinician@example.com", now).is_ok());
+    }
+
+    #[test]
+    fn test_stale_accounts_are_pruned_and_bad_durations_fall_back() {
+        let lockout = LoginLockout::new(3, Duration::minutes(15));
+        let now = Utc::now();
+        for i in 0..50 {
+            lockout.record_failure(&format!("guess{}@example.com", i), now);
+        }
+        lockout.record_failure("clinician@example.com", now + Duration::minutes(16));
+        assert_eq!(lockout.accounts.lock().unwrap().len(), 1);
+
+        let config = SecurityConfig { lockout_duration_seconds: i64::MAX, ..SecurityConfig::default() };
+        assert_eq!(LoginLockout::from_config(&config).lockout_duration, Duration::seconds(DEFAULT_LOCKOUT_SECONDS));
+    }
+}
@@ -0,0 +1,250 @@
+// Minimum-Necessary Response Shaping for PsyPsy CMS
+// Projects command responses down to the fields the caller's role may see
+
+use crate::security::{HealthcareRole, SecurityError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Field path granting the complete record
+pub const ALL_FIELDS: &str = "*";
+
+/// Field sets allowed per role for each resource type.
+///
+/// Paths are dotted, camelCase JSON paths as serialized to the frontend,
+/// e.g. `medicalInfo.insuranceInfo`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimizationPolicy {
+    /// Resource type -> role -> allowed field paths
+    pub resources: HashMap<String, HashMap<HealthcareRole, HashSet<String>>>,
+    /// Fields returned for roles without an explicit rule
+    pub default_fields: HashSet<String>,
+}
+
+impl Default for MinimizationPolicy {
+    fn default() -> Self {
+        let mut resources = HashMap::new();
+        resources.insert("client".to_string(), default_client_rules());
+
+        Self {
+            resources,
+            default_fields: fields(&["objectId", "status"]),
+        }
+    }
+}
+
+fn fields(paths: &[&str]) -> HashSet<String> {
+    paths.iter().map(|p| p.to_string()).collect()
+}
+
+/// Default field sets for client records
+fn default_client_rules() -> HashMap<HealthcareRole, HashSet<String>> {
+    let identity = [
+        "objectId", "userId", "firstName", "lastName", "dateOfBirth", "status",
+        "createdAt", "updatedAt",
+    ];
+
+    let mut rules = HashMap::new();
+
+    // Clinical roles and the record owner see the full chart
+    for role in [
+        HealthcareRole::SuperAdmin,
+        HealthcareRole::HealthcareProvider,
+        HealthcareRole::Patient,
+        HealthcareRole::Guardian,
+    ] {
+        rules.insert(role, fields(&[ALL_FIELDS]));
+    }
+
+    // Billing needs identity, address and insurance, never clinical data
+    let mut billing = fields(&identity);
+    billing.extend(fields(&[
+        "addressObj",
+        "totalAppointments",
+        "completedAppointments",
+        "cancelledAppointments",
+        "ramqNumber",
+        "medicalInfo.insuranceInfo",
+    ]));
+    rules.insert(HealthcareRole::BillingStaff, billing);
+
+    // Front-desk staff handle scheduling and contact details
+    let mut administrative = fields(&identity);
+    administrative.extend(fields(&[
+        "gender",
+        "profilePicture",
+        "email",
+        "phone",
+        "addressObj",
+        "spokenLangArr",
+        "searchRadius",
+        "assignedProfessionals",
+        "totalAppointments",
+        "completedAppointments",
+        "cancelledAppointments",
+        "emergencyContacts",
+        "preferences",
+    ]));
+    for role in [
+        HealthcareRole::Administrator,
+        HealthcareRole::AdminStaff,
+        HealthcareRole::AdministrativeStaff,
+    ] {
+        rules.insert(role, administrative.clone());
+    }
+
+    rules.insert(HealthcareRole::EmergencyContact, fields(&["objectId", "firstName", "lastName"]));
+
+    rules
+}
+
+impl MinimizationPolicy {
+    /// Field paths a role may receive for a resource type
+    pub fn allowed_fields(&self, resource: &str, role: Option<&HealthcareRole>) -> HashSet<String> {
+        role.and_then(|role| self.resources.get(resource).and_then(|rules| rules.get(role)))
+            .cloned()
+            .unwrap_or_else(|| self.default_fields.clone())
+    }
+
+    /// Resolve the fields to return: the caller's request intersected with what the role permits
+    pub fn effective_fields(
+        &self,
+        resource: &str,
+        role: Option<&HealthcareRole>,
+        requested: Option<&[String]>,
+    ) -> HashSet<String> {
+        let allowed = self.allowed_fields(resource, role);
+        let requested = match requested {
+            Some(requested) if !requested.is_empty() => requested,
+            _ => return allowed,
+        };
+
+        let mut effective = HashSet::new();
+        for path in requested {
+            if is_permitted(&allowed, path) {
+                effective.insert(path.clone());
+            } else {
+                // A broader request narrows to the permitted sub-fields
+                let prefix = format!("{}.", path);
+                effective.extend(allowed.iter().filter(|a| a.starts_with(&prefix)).cloned());
+            }
+        }
+        effective
+    }
+
+    /// Shape a serializable response for the caller
+    pub fn shape<T: Serialize>(
+        &self,
+        resource: &str,
+        value: &T,
+        role: Option<&HealthcareRole>,
+        requested: Option<&[String]>,
+    ) -> Result<Value, SecurityError> {
+        let value = serde_json::to_value(value).map_err(|e| SecurityError::ValidationFailed {
+            reason: format!("Failed to serialize {} response: {}", resource, e),
+        })?;
+        let paths = self.effective_fields(resource, role, requested);
+        Ok(project(&value, &paths))
+    }
+}
+
+/// Whether a path is covered by the allowed set (directly or via an ancestor)
+fn is_permitted(allowed: &HashSet<String>, path: &str) -> bool {
+    if allowed.contains(ALL_FIELDS) || allowed.contains(path) {
+        return true;
+    }
+    let mut end = path.len();
+    while let Some(idx) = path[..end].rfind('.') {
+        if allowed.contains(&path[..idx]) {
+            return true;
+        }
+        end = idx;
+    }
+    false
+}
+
+/// Copy only the given paths from `value`; fields outside them are omitted entirely
+pub fn project(value: &Value, paths: &HashSet<String>) -> Value {
+    if paths.contains(ALL_FIELDS) {
+        return value.clone();
+    }
+
+    let mut target = Value::Object(Map::new());
+    for path in paths {
+        let segments: Vec<&str> = path.split('.').collect();
+        copy_path(value, &mut target, &segments);
+    }
+    target
+}
+
+fn copy_path(source: &Value, target: &mut Value, segments: &[&str]) {
+    let (head, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let (Some(source_map), Some(target_map)) = (source.as_object(), target.as_object_mut()) else {
+        return;
+    };
+    let Some(child) = source_map.get(*head) else {
+        return;
+    };
+
+    if rest.is_empty() || child.is_null() {
+        target_map.insert(head.to_string(), child.clone());
+        return;
+    }
+
+    let entry = target_map
+        .entry(head.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    copy_path(child, entry, rest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record() -> Value {
+        json!({
+            "objectId": "c1",
+            "firstName": "Jane",
+            "status": "active",
+            "medicalInfo": {
+                "conditions": ["F41.1"],
+                "insuranceInfo": {"provider": "RAMQ", "policyNumber": "123"}
+            }
+        })
+    }
+
+    #[test]
+    fn test_billing_receives_insurance_but_not_conditions() {
+        let policy = MinimizationPolicy::default();
+        let shaped = policy.shape("client", &record(), Some(&HealthcareRole::BillingStaff), None).unwrap();
+
+        assert_eq!(shaped["medicalInfo"]["insuranceInfo"]["provider"], "RAMQ");
+        assert!(shaped["medicalInfo"].get("conditions").is_none());
+    }
+
+    #[test]
+    fn test_requested_fields_cannot_exceed_role() {
+        let policy = MinimizationPolicy::default();
+        let requested = vec!["firstName".to_string(), "medicalInfo".to_string()];
+        let shaped = policy
+            .shape("client", &record(), Some(&HealthcareRole::BillingStaff), Some(&requested))
+            .unwrap();
+
+        assert_eq!(shaped["firstName"], "Jane");
+        assert!(shaped.get("objectId").is_none());
+        assert!(shaped["medicalInfo"].get("conditions").is_none());
+        assert!(shaped["medicalInfo"].get("insuranceInfo").is_some());
+    }
+
+    #[test]
+    fn test_unknown_role_gets_default_fields() {
+        let policy = MinimizationPolicy::default();
+        let shaped = policy.shape("client", &record(), None, None).unwrap();
+
+        assert_eq!(shaped, json!({"objectId": "c1", "status": "active"}));
+    }
+}
@@ -8,10 +8,29 @@
 pub mod auth;
 pub mod crypto;
 pub mod audit;
+pub mod audit_worm;
 pub mod rbac;
+pub mod command_policy;
+pub mod session_binding;
+pub mod secure_wipe;
+pub mod step_up;
 pub mod rate_limit;
 pub mod validation;
 pub mod compliance;
+pub mod minimization;
+pub mod outbound;
+pub mod transport;
+pub mod effective_config;
+pub mod readiness;
+pub mod key_escrow;
+pub mod access_justification;
+pub mod data_scope;
+pub mod audit_export;
+pub mod encryption_coverage;
+pub mod access_heatmap;
+pub mod login_lockout;
+pub mod totp;
+pub mod geolocation;
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -95,8 +114,8 @@ impl DataClassification {
     }
 }
 
-/// Encryption levels matching data classification requirements
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Encryption levels matching data classification requirements, ordered weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum EncryptionLevel {
     None,
     Standard,   // AES-128
@@ -165,18 +184,33 @@ pub struct SecuritySession {
 }
 
 impl SecuritySession {
-    /// Check if session is still valid
-    pub fn is_valid(&self) -> bool {
-        let now = Utc::now();
-        let session_timeout = chrono::Duration::hours(8); // 8-hour sessions for healthcare
-        now.signed_duration_since(self.last_activity) < session_timeout
+    /// Check if session is still valid: neither past its absolute expiry nor idle for longer
+    /// than `idle_timeout`
+    pub fn is_valid(&self, idle_timeout: chrono::Duration) -> bool {
+        self.is_valid_at(idle_timeout, Utc::now())
+    }
+
+    pub fn is_valid_at(&self, idle_timeout: chrono::Duration, now: DateTime<Utc>) -> bool {
+        now < self.expired_at(idle_timeout)
+    }
+
+    /// When the session ends if it sees no further activity
+    pub fn expired_at(&self, idle_timeout: chrono::Duration) -> DateTime<Utc> {
+        self.expires_at.min(self.last_activity + idle_timeout)
     }
 
     /// Check if MFA is required for a specific action
     pub fn requires_mfa(&self, action: &str) -> bool {
         // High-risk actions always require MFA
         let high_risk_actions = ["delete_patient", "export_phi", "modify_audit_log", "admin_override"];
-        high_risk_actions.contains(&action) || matches!(self.role, HealthcareRole::SuperAdmin)
+        high_risk_actions.contains(&action)
+            || matches!(self.role, HealthcareRole::SuperAdmin)
+            || self.mfa_pending()
+    }
+
+    /// Flagged at sign-in (e.g. impossible travel) and MFA not yet completed
+    pub fn mfa_pending(&self) -> bool {
+        self.security_metadata["mfa_required"] == true && !self.mfa_verified
     }
 
     /// Update last activity timestamp
@@ -195,21 +229,42 @@ impl SecuritySession {
 pub struct SecurityConfig {
     pub jwt_secret: String,
     pub jwt_expiry_seconds: i64,
-    pub session_timeout_hours: u64,
+    /// Idle time after which a session ends, however long its tokens remain valid
+    pub session_timeout_seconds: i64,
     pub mfa_required_for_admin: bool,
     pub audit_log_path: String,
     pub encryption_key_rotation_days: u32,
+    /// Require record-bound ciphertexts to be decrypted with their patient/record context
+    pub require_encryption_context: bool,
+    /// Failed sign-ins an account may have before it is locked; 0 disables the lockout
+    pub max_failed_logins: u32,
+    pub lockout_duration_seconds: i64,
+}
+
+impl SecurityConfig {
+    pub fn idle_timeout(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.session_timeout_seconds)
+    }
+
+    /// How long a refresh token stays redeemable: as long as a session may sit idle, and never
+    /// shorter than the access token it renews
+    pub fn refresh_token_lifetime(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.session_timeout_seconds.max(self.jwt_expiry_seconds))
+    }
 }
 
 impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
-            jwt_secret: "default-dev-secret-change-in-production".to_string(),
+            jwt_secret: readiness::DEV_JWT_SECRET.to_string(),
             jwt_expiry_seconds: 3600, // 1 hour
-            session_timeout_hours: 8,
+            session_timeout_seconds: 8 * 3600, // 8 hours
             mfa_required_for_admin: true,
             audit_log_path: "./logs/audit.log".to_string(),
             encryption_key_rotation_days: 90,
+            require_encryption_context: true,
+            max_failed_logins: 5,
+            lockout_duration_seconds: 900, // 15 minutes
         }
     }
 }
@@ -237,6 +292,10 @@ pub enum AuditEventType {
     PatientDataCreated,
     LoginFailed,
     UserLogin,
+    UserLogout,
+    MfaEnabled,
+    AnomalousActivity,
+    EncryptionKeyRotated,
 }
 
 /// Initialize security subsystem
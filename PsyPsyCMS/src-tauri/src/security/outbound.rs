@@ -0,0 +1,347 @@
+// Outbound Request Guard for PsyPsy CMS
+// SSRF protection and transport security for every outbound HTTP call made by the backend
+
+use crate::security::audit::{audit_service, log_security_violation, AuditService};
+use crate::security::transport::{tls_client_config, TransportSecurityPolicy};
+use crate::security::SecurityError;
+use once_cell::sync::OnceCell;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+/// Destinations the backend may contact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundPolicy {
+    /// Allowed hosts; `*.example.com` matches any subdomain
+    pub allowed_hosts: Vec<String>,
+    /// Hosts refused even if they match the allowlist
+    pub denied_hosts: Vec<String>,
+    /// Allowed URL schemes
+    pub allowed_schemes: Vec<String>,
+    /// Refuse private, link-local, loopback and metadata addresses
+    pub block_private_networks: bool,
+    /// Permit loopback destinations (local Firebase emulators)
+    pub allow_loopback: bool,
+    /// Maximum redirects followed, each re-checked against the policy
+    pub max_redirects: usize,
+    /// Minimum TLS version and certificate pins
+    #[serde(default)]
+    pub transport: TransportSecurityPolicy,
+}
+
+impl Default for OutboundPolicy {
+    fn default() -> Self {
+        let use_emulator = std::env::var("FIREBASE_USE_EMULATOR").unwrap_or_else(|_| "false".to_string()) == "true";
+
+        let mut allowed_hosts: Vec<String> = [
+            // Firebase, Google OAuth and Cloud KMS
+            "*.googleapis.com",
+            "*.cloudfunctions.net",
+            // Social media platforms
+            "api.linkedin.com",
+            "www.linkedin.com",
+            "graph.facebook.com",
+            "graph.instagram.com",
+            "api.twitter.com",
+            "api.x.com",
+        ].iter().map(|h| h.to_string()).collect();
+
+        let mut allowed_schemes = vec!["https".to_string()];
+        if use_emulator {
+            allowed_hosts.extend(["127.0.0.1".to_string(), "localhost".to_string()]);
+            allowed_schemes.push("http".to_string());
+        }
+
+        Self {
+            allowed_hosts,
+            denied_hosts: vec![
+                "metadata.google.internal".to_string(),
+                "metadata".to_string(),
+                "instance-data".to_string(),
+            ],
+            allowed_schemes,
+            block_private_networks: true,
+            allow_loopback: use_emulator,
+            max_redirects: 5,
+            transport: TransportSecurityPolicy::from_env(),
+        }
+    }
+}
+
+impl OutboundPolicy {
+    /// Whether a hostname passes the allow/deny lists
+    pub fn host_allowed(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if self.denied_hosts.iter().any(|pattern| host_matches(pattern, &host)) {
+            return false;
+        }
+        self.allowed_hosts.iter().any(|pattern| host_matches(pattern, &host))
+    }
+
+    /// Whether a resolved address may be contacted
+    pub fn ip_allowed(&self, ip: IpAddr) -> bool {
+        if !self.block_private_networks {
+            return true;
+        }
+        if ip.is_loopback() {
+            return self.allow_loopback;
+        }
+        !is_internal_address(ip)
+    }
+}
+
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host.len() > suffix.len() && host.ends_with(&format!(".{}", suffix)),
+        None => host == pattern,
+    }
+}
+
+/// RFC1918, link-local (including cloud metadata), CGNAT, unspecified and other internal ranges
+pub fn is_internal_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_internal_v4(v4);
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // unique local fc00::/7
+                || (first & 0xffc0) == 0xfe80 // link-local fe80::/10
+                || v6 == Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254) // AWS metadata (IPv6)
+        }
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local() // 169.254.0.0/16, includes 169.254.169.254 metadata
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || (octets[0] == 100 && (octets[1] & 0xc0) == 64) // CGNAT 100.64.0.0/10
+        || octets[0] == 0
+}
+
+/// Enforces the outbound policy and records blocked destinations
+pub struct OutboundGuard {
+    policy: OutboundPolicy,
+    audit: Option<Arc<AuditService>>,
+}
+
+impl OutboundGuard {
+    /// Create guard with the given policy
+    pub fn new(policy: OutboundPolicy) -> Self {
+        Self { policy, audit: None }
+    }
+
+    /// Record blocked requests in this audit service rather than the process-wide one
+    pub fn with_audit_service(mut self, audit: Arc<AuditService>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Active policy
+    pub fn policy(&self) -> &OutboundPolicy {
+        &self.policy
+    }
+
+    /// Validate a URL before a request is built from it.
+    ///
+    /// Hostnames are additionally re-checked after DNS resolution by the guarded
+    /// client, so this mainly catches disallowed schemes and IP-literal targets.
+    pub async fn check_url(&self, url: &str) -> Result<Url, SecurityError> {
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                return Err(SecurityError::ValidationFailed {
+                    reason: format!("Invalid outbound URL: {}", e),
+                })
+            }
+        };
+
+        if !self.policy.allowed_schemes.iter().any(|s| s == parsed.scheme()) {
+            return Err(self.block(&parsed, &format!("scheme '{}' not allowed", parsed.scheme())).await);
+        }
+
+        let host = match parsed.host_str() {
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']').to_string(),
+            None => return Err(self.block(&parsed, "missing host").await),
+        };
+
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            if !self.policy.ip_allowed(ip) {
+                return Err(self.block(&parsed, &format!("internal address {}", ip)).await);
+            }
+        }
+
+        if !self.policy.host_allowed(&host) {
+            return Err(self.block(&parsed, "host not on outbound allowlist").await);
+        }
+
+        Ok(parsed)
+    }
+
+    async fn block(&self, url: &Url, reason: &str) -> SecurityError {
+        // Never log query strings; they may carry API keys or tokens
+        let target = format!("{}://{}{}", url.scheme(), url.host_str().unwrap_or(""), url.path());
+        self.report_violation(&target, reason).await;
+        SecurityError::AccessDenied {
+            reason: format!("Outbound request to {} blocked: {}", target, reason),
+        }
+    }
+
+    /// The guard is installed before the audit service exists, so without an attached service
+    /// violations go to the process-wide one once it is installed
+    async fn report_violation(&self, target: &str, reason: &str) {
+        log::error!("SecurityViolationDetected: outbound request to {} blocked ({})", target, reason);
+        if let Some(audit) = self.audit.clone().or_else(audit_service) {
+            let description = format!("Outbound request to {} blocked: {}", target, reason);
+            if let Err(e) = log_security_violation(&audit, None, "outbound_request_blocked", &description, None).await {
+                log::warn!("Failed to audit blocked outbound request: {}", e);
+            }
+        }
+    }
+
+    /// Client builder whose DNS resolution and redirects are checked against the policy, and
+    /// whose TLS handshakes enforce the transport policy
+    pub fn client_builder(self: &Arc<Self>) -> reqwest::ClientBuilder {
+        let redirect_guard = Arc::clone(self);
+        let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= redirect_guard.policy.max_redirects {
+                return attempt.error("too many redirects");
+            }
+            let allowed = attempt.url().host_str()
+                .map(|host| {
+                    let host = host.trim_start_matches('[').trim_end_matches(']');
+                    let ip_ok = host.parse::<IpAddr>().map_or(true, |ip| redirect_guard.policy.ip_allowed(ip));
+                    ip_ok && redirect_guard.policy.host_allowed(host)
+                })
+                .unwrap_or(false)
+                && redirect_guard.policy.allowed_schemes.iter().any(|s| s == attempt.url().scheme());
+            if allowed {
+                attempt.follow()
+            } else {
+                log::error!(
+                    "SecurityViolationDetected: redirect to {:?} blocked",
+                    attempt.url().host_str()
+                );
+                attempt.error("redirect target blocked by outbound policy")
+            }
+        });
+
+        reqwest::Client::builder()
+            .dns_resolver(Arc::new(GuardedResolver { guard: Arc::clone(self) }))
+            .redirect(redirect_policy)
+            .https_only(!self.policy.allowed_schemes.iter().any(|s| s == "http"))
+            .use_preconfigured_tls(tls_client_config(&self.policy.transport))
+    }
+}
+
+/// DNS resolver that refuses disallowed hosts and internal addresses.
+///
+/// Checking the resolved addresses (rather than only the URL) prevents DNS
+/// rebinding from steering an allowed hostname at an internal service.
+struct GuardedResolver {
+    guard: Arc<OutboundGuard>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let guard = Arc::clone(&self.guard);
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            if !guard.policy.host_allowed(&host) {
+                guard.report_violation(&host, "host not on outbound allowlist").await;
+                return Err(format!("outbound request to {} blocked", host).into());
+            }
+
+            let resolved: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let permitted: Vec<SocketAddr> = resolved.iter()
+                .copied()
+                .filter(|addr| guard.policy.ip_allowed(addr.ip()))
+                .collect();
+
+            if permitted.is_empty() {
+                guard.report_violation(&host, "resolves only to internal addresses").await;
+                return Err(format!("outbound request to {} blocked", host).into());
+            }
+
+            let addrs: Addrs = Box::new(permitted.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+static OUTBOUND_GUARD: OnceCell<Arc<OutboundGuard>> = OnceCell::new();
+
+/// Install the process-wide outbound guard; must run before any client is built
+pub fn install_outbound_guard(guard: OutboundGuard) -> Result<(), SecurityError> {
+    OUTBOUND_GUARD.set(Arc::new(guard)).map_err(|_| SecurityError::ConfigurationError {
+        reason: "Outbound guard already installed".to_string(),
+    })
+}
+
+/// Process-wide outbound guard (default policy if none was installed)
+pub fn outbound_guard() -> Arc<OutboundGuard> {
+    Arc::clone(OUTBOUND_GUARD.get_or_init(|| Arc::new(OutboundGuard::new(OutboundPolicy::default()))))
+}
+
+/// Shared builder for every backend HTTP client
+pub fn guarded_client_builder() -> reqwest::ClientBuilder {
+    outbound_guard().client_builder()
+}
+
+/// Shared HTTP client with default settings
+pub fn guarded_client() -> reqwest::Client {
+    guarded_client_builder()
+        .build()
+        .expect("Failed to create guarded HTTP client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict_policy() -> OutboundPolicy {
+        OutboundPolicy {
+            allow_loopback: false,
+            allowed_schemes: vec!["https".to_string()],
+            ..OutboundPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_internal_addresses_blocked() {
+        let policy = strict_policy();
+        for ip in ["10.0.0.5", "172.16.3.4", "192.168.1.1", "169.254.169.254", "127.0.0.1", "100.64.0.1", "0.0.0.0", "::1", "fe80::1", "fd00::1", "::ffff:10.0.0.1"] {
+            assert!(!policy.ip_allowed(ip.parse().unwrap()), "{} should be blocked", ip);
+        }
+        assert!(policy.ip_allowed("142.250.80.10".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_host_allowlist() {
+        let policy = strict_policy();
+        assert!(policy.host_allowed("firestore.googleapis.com"));
+        assert!(policy.host_allowed("api.linkedin.com"));
+        assert!(!policy.host_allowed("googleapis.com.evil.example"));
+        assert!(!policy.host_allowed("metadata.google.internal"));
+        assert!(!policy.host_allowed("internal.corp"));
+    }
+
+    #[tokio::test]
+    async fn test_check_url_rejects_ssrf_targets() {
+        let guard = OutboundGuard::new(strict_policy());
+        assert!(guard.check_url("https://169.254.169.254/computeMetadata/v1/").await.is_err());
+        assert!(guard.check_url("http://firestore.googleapis.com/").await.is_err());
+        assert!(guard.check_url("https://localhost:9881/").await.is_err());
+        assert!(guard.check_url("https://oauth2.googleapis.com/token").await.is_ok());
+    }
+}
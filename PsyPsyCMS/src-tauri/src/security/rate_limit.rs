@@ -2,6 +2,8 @@
 // Implements comprehensive rate limiting to prevent abuse and ensure system stability
 
 use crate::security::{SecurityError, HealthcareRole};
+use crate::security::geolocation::{GeoResolver, NoopGeoResolver};
+use crate::security::outbound::is_internal_address;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -9,7 +11,7 @@ use std::time::{Duration, Instant};
 use std::num::NonZeroU32;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use governor::{Quota, RateLimiter, state::{InMemoryState, NotKeyed}, clock::{DefaultClock, Clock}};
+use governor::Quota;
 use std::net::IpAddr;
 
 /// Rate limiting configuration for different user roles and endpoints
@@ -35,6 +37,20 @@ pub struct RateLimitConfig {
     pub max_violations_before_ban: u32,
     /// Temporary ban duration in minutes
     pub temporary_ban_duration_minutes: u32,
+    /// What happens when a login would exceed the role's `max_concurrent_sessions`
+    #[serde(default)]
+    pub session_quota_action: SessionQuotaAction,
+}
+
+/// Response to a login beyond the role's concurrent-session limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionQuotaAction {
+    /// Refuse the new login
+    Reject,
+    /// End the least recently active session to make room
+    #[default]
+    EvictOldest,
 }
 
 /// Role-based rate limits
@@ -69,6 +85,25 @@ pub struct EndpointLimits {
     pub risk_level: u8,
     /// Requires MFA for rate limit exemption
     pub mfa_exemption: bool,
+    /// Requests per minute granted to MFA-verified sessions when `mfa_exemption` is set;
+    /// capped at `MAX_MFA_ELEVATION_FACTOR` times the base rate
+    #[serde(default)]
+    pub mfa_elevated_requests_per_minute: u32,
+}
+
+/// Upper bound on the MFA exemption, as a multiple of the endpoint's base rate
+pub const MAX_MFA_ELEVATION_FACTOR: u32 = 3;
+
+impl EndpointLimits {
+    /// Effective per-minute rate for an MFA-verified session; never unlimited
+    pub fn mfa_elevated_limit(&self) -> u32 {
+        if !self.mfa_exemption {
+            return self.requests_per_minute;
+        }
+        self.mfa_elevated_requests_per_minute
+            .min(self.requests_per_minute.saturating_mul(MAX_MFA_ELEVATION_FACTOR))
+            .max(self.requests_per_minute)
+    }
 }
 
 /// IP-based rate limiting
@@ -119,7 +154,7 @@ pub struct HipaaSensitiveLimits {
 /// Geographic access restrictions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeographicRestrictions {
-    /// Allowed countries (ISO country codes)
+    /// Allowed countries (ISO country codes); empty allows all. A Quebec clinic would usually list only "CA"
     pub allowed_countries: Vec<String>,
     /// Blocked countries (takes precedence)
     pub blocked_countries: Vec<String>,
@@ -127,6 +162,13 @@ pub struct GeographicRestrictions {
     pub block_vpn_proxy: bool,
     /// Suspicious location alerting
     pub alert_suspicious_locations: bool,
+    /// Sessions opened after impossible travel must pass MFA again
+    #[serde(default)]
+    pub require_mfa_on_suspicious_location: bool,
+    /// Let a request through when the country lookup fails. Off by default, so a non-empty
+    /// allow list is not bypassed while the lookup service is down.
+    #[serde(default)]
+    pub allow_on_lookup_failure: bool,
 }
 
 impl Default for RateLimitConfig {
@@ -183,6 +225,7 @@ impl Default for RateLimitConfig {
             accesses_phi: false,
             risk_level: 3,
             mfa_exemption: false,
+            mfa_elevated_requests_per_minute: 0,
         });
         
         // PHI access endpoints
@@ -193,6 +236,7 @@ impl Default for RateLimitConfig {
             accesses_phi: true,
             risk_level: 5,
             mfa_exemption: true,
+            mfa_elevated_requests_per_minute: 90, // Heavy clinical use, MFA-verified only
         });
         
         // Data export endpoints
@@ -203,6 +247,7 @@ impl Default for RateLimitConfig {
             accesses_phi: true,
             risk_level: 5,
             mfa_exemption: false,
+            mfa_elevated_requests_per_minute: 0,
         });
         
         Self {
@@ -220,6 +265,8 @@ impl Default for RateLimitConfig {
                     blocked_countries: vec![], // None blocked by default
                     block_vpn_proxy: false,    // Disabled by default
                     alert_suspicious_locations: true,
+                    require_mfa_on_suspicious_location: true,
+                    allow_on_lookup_failure: false,
                 }),
             },
             anonymous_limits: AnonymousLimits {
@@ -244,6 +291,7 @@ impl Default for RateLimitConfig {
             violation_window_minutes: 60,
             max_violations_before_ban: 5,
             temporary_ban_duration_minutes: 30,
+            session_quota_action: SessionQuotaAction::default(),
         }
     }
 }
@@ -292,6 +340,8 @@ pub enum LimitType {
     RoleBased,
     /// HIPAA-sensitive operation limit
     HippaSensitive,
+    /// Request from a country outside the geographic restrictions
+    GeographicRestriction,
 }
 
 /// Rate information for violations
@@ -322,33 +372,57 @@ pub enum ViolationSeverity {
     Severe,
 }
 
+/// A request allowed only because of the MFA exemption
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaExemptionUse {
+    pub timestamp: DateTime<Utc>,
+    pub user_id: Option<Uuid>,
+    pub session_id: Option<String>,
+    pub ip_address: IpAddr,
+    pub endpoint: String,
+    /// Base endpoint rate that was exceeded
+    pub base_limit: u32,
+    /// Elevated rate the request was counted against
+    pub elevated_limit: u32,
+}
+
 /// Rate limiting service
 pub struct RateLimitService {
     /// Configuration
     config: Arc<RwLock<RateLimitConfig>>,
     /// Per-user rate limiters
     user_limiters: Arc<RwLock<HashMap<Uuid, UserLimiter>>>,
+    /// Per-user HIPAA-sensitive operation limiters
+    hipaa_limiters: Arc<RwLock<HashMap<Uuid, HipaaOperationLimiter>>>,
     /// Per-IP rate limiters
     ip_limiters: Arc<RwLock<HashMap<IpAddr, IpLimiter>>>,
     /// Endpoint-specific limiters
-    endpoint_limiters: Arc<RwLock<HashMap<String, RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>>,
+    endpoint_limiters: Arc<RwLock<HashMap<String, Budget>>>,
+    /// Extra headroom above the endpoint limit for MFA-verified sessions, per user and endpoint
+    mfa_headroom_limiters: Arc<RwLock<HashMap<String, Budget>>>,
+    /// Requests served under the MFA exemption
+    mfa_exemption_uses: Arc<RwLock<Vec<MfaExemptionUse>>>,
     /// Violation tracking
     violations: Arc<RwLock<Vec<RateLimitViolation>>>,
     /// Banned IPs
     banned_ips: Arc<RwLock<HashMap<IpAddr, BanInfo>>>,
     /// Banned users
     banned_users: Arc<RwLock<HashMap<Uuid, BanInfo>>>,
+    /// Places request IPs for the geographic restrictions
+    geo_resolver: Arc<dyn GeoResolver>,
+    /// Resolved country per IP; `None` when the address could not be placed
+    ip_countries: Arc<RwLock<HashMap<IpAddr, Option<String>>>>,
 }
 
 /// Per-user rate limiter
 #[derive(Debug)]
 pub struct UserLimiter {
     /// General request limiter
-    pub request_limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+    pub request_limiter: Budget,
     /// PHI access limiter
-    pub phi_access_limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+    pub phi_access_limiter: Budget,
     /// Data export limiter
-    pub data_export_limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+    pub data_export_limiter: Budget,
     /// Last activity timestamp
     pub last_activity: Instant,
     /// User's role
@@ -359,13 +433,140 @@ pub struct UserLimiter {
     pub violation_count: u32,
 }
 
+/// Per-user limiters for HIPAA-sensitive operations, kept apart from the request limiters so
+/// routine calls never use up the PHI budget
+#[derive(Debug)]
+pub struct HipaaOperationLimiter {
+    pub phi_view_limiter: Budget,
+    pub phi_modification_limiter: Budget,
+    pub phi_export_limiter: Budget,
+    pub audit_access_limiter: Budget,
+    /// Last activity timestamp
+    pub last_activity: Instant,
+}
+
+impl HipaaOperationLimiter {
+    fn new(limits: &HipaaSensitiveLimits) -> Self {
+        Self {
+            phi_view_limiter: Budget::new(windowed_quota(limits.phi_views_per_hour, HOUR)),
+            phi_modification_limiter: Budget::new(windowed_quota(limits.phi_modifications_per_hour, HOUR)),
+            phi_export_limiter: Budget::new(windowed_quota(limits.phi_exports_per_day, DAY)),
+            audit_access_limiter: Budget::new(windowed_quota(limits.audit_access_per_hour, HOUR)),
+            last_activity: Instant::now(),
+        }
+    }
+
+    fn limiter(&mut self, operation: HipaaOperation) -> &mut Budget {
+        match operation {
+            HipaaOperation::PhiView => &mut self.phi_view_limiter,
+            HipaaOperation::PhiModification => &mut self.phi_modification_limiter,
+            HipaaOperation::PhiExport => &mut self.phi_export_limiter,
+            HipaaOperation::AuditAccess => &mut self.audit_access_limiter,
+        }
+    }
+}
+
+/// Operation counted against `HipaaSensitiveLimits`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HipaaOperation {
+    PhiView,
+    PhiModification,
+    PhiExport,
+    AuditAccess,
+}
+
+impl HipaaOperation {
+    /// Configured cap and the window it applies to
+    fn limit(self, limits: &HipaaSensitiveLimits) -> (u32, Duration) {
+        match self {
+            HipaaOperation::PhiView => (limits.phi_views_per_hour, HOUR),
+            HipaaOperation::PhiModification => (limits.phi_modifications_per_hour, HOUR),
+            HipaaOperation::PhiExport => (limits.phi_exports_per_day, DAY),
+            HipaaOperation::AuditAccess => (limits.audit_access_per_hour, HOUR),
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            HipaaOperation::PhiView => "PHI view",
+            HipaaOperation::PhiModification => "PHI modification",
+            HipaaOperation::PhiExport => "PHI export",
+            HipaaOperation::AuditAccess => "Audit log access",
+        }
+    }
+}
+
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(86_400);
+
+/// Up to `max` operations at once, replenished evenly across `window`; a zero cap allows one
+fn windowed_quota(max: u32, window: Duration) -> Quota {
+    let max = NonZeroU32::new(max).unwrap_or(NonZeroU32::MIN);
+    Quota::with_period(window / max.get())
+        .expect("window is non-zero")
+        .allow_burst(max)
+}
+
+/// Token bucket for one limit. Unlike a governor limiter it can be checked without spending,
+/// so a request refused by one limit does not use up the budgets it passed.
+#[derive(Debug)]
+pub struct Budget {
+    burst: f64,
+    replenish_interval: Duration,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Budget {
+    pub fn new(quota: Quota) -> Self {
+        let burst = quota.burst_size().get() as f64;
+        Self {
+            burst,
+            replenish_interval: quota.replenish_interval(),
+            tokens: burst,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let earned = now.saturating_duration_since(self.updated).as_secs_f64() / self.replenish_interval.as_secs_f64();
+        self.tokens = (self.tokens + earned).min(self.burst);
+        self.updated = now;
+    }
+
+    /// `None` when a request fits now, otherwise how long until one does
+    pub fn wait_time(&mut self) -> Option<Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            None
+        } else {
+            Some(self.replenish_interval.mul_f64(1.0 - self.tokens))
+        }
+    }
+
+    /// Use up one request. Spent only after every limit passed; a request racing in between can
+    /// leave the bucket briefly in debt, which delays the next one.
+    pub fn spend(&mut self) {
+        self.refill();
+        self.tokens -= 1.0;
+    }
+}
+
+/// MFA headroom budget for one user and endpoint, created on first use
+fn mfa_headroom(limiters: &mut HashMap<String, Budget>, key: String, per_minute: u32) -> &mut Budget {
+    limiters.entry(key).or_insert_with(|| {
+        Budget::new(Quota::per_minute(NonZeroU32::new(per_minute).unwrap()))
+    })
+}
+
 /// Per-IP rate limiter
 #[derive(Debug)]
 pub struct IpLimiter {
     /// Request limiter
-    pub request_limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+    pub request_limiter: Budget,
     /// Authentication attempt limiter
-    pub auth_limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock>,
+    pub auth_limiter: Budget,
     /// Last activity timestamp
     pub last_activity: Instant,
     /// Violation count
@@ -408,6 +609,25 @@ impl BanInfo {
     }
 }
 
+/// What a ban applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum BanTarget {
+    Ip(IpAddr),
+    User(Uuid),
+}
+
+/// A ban in force, for the admin UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveBan {
+    pub target: BanTarget,
+    pub ban: BanInfo,
+    pub remaining_seconds: i64,
+}
+
+/// How often expired bans and idle limiters are swept
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
+
 /// Rate limit check result
 #[derive(Debug, Clone)]
 pub struct RateLimitResult {
@@ -423,6 +643,18 @@ pub struct RateLimitResult {
     pub violation: Option<RateLimitViolation>,
 }
 
+impl RateLimitResult {
+    pub fn allowed() -> Self {
+        Self {
+            allowed: true,
+            denial_reason: None,
+            rate_info: None,
+            retry_after_seconds: None,
+            violation: None,
+        }
+    }
+}
+
 /// Rate limit check context
 #[derive(Debug, Clone)]
 pub struct RateLimitContext {
@@ -444,6 +676,8 @@ pub struct RateLimitContext {
     pub accesses_phi: bool,
     /// Whether request is a data export
     pub is_data_export: bool,
+    /// Whether request reads the audit log
+    pub accesses_audit_log: bool,
     /// Whether MFA is verified
     pub mfa_verified: bool,
     /// Request timestamp
@@ -456,15 +690,27 @@ impl RateLimitService {
         Self {
             config: Arc::new(RwLock::new(config)),
             user_limiters: Arc::new(RwLock::new(HashMap::new())),
+            hipaa_limiters: Arc::new(RwLock::new(HashMap::new())),
             ip_limiters: Arc::new(RwLock::new(HashMap::new())),
             endpoint_limiters: Arc::new(RwLock::new(HashMap::new())),
+            mfa_headroom_limiters: Arc::new(RwLock::new(HashMap::new())),
+            mfa_exemption_uses: Arc::new(RwLock::new(Vec::new())),
             violations: Arc::new(RwLock::new(Vec::new())),
             banned_ips: Arc::new(RwLock::new(HashMap::new())),
             banned_users: Arc::new(RwLock::new(HashMap::new())),
+            geo_resolver: Arc::new(NoopGeoResolver),
+            ip_countries: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     
-    /// Check if request should be rate limited
+    /// Resolve request countries with `geo_resolver` so the geographic restrictions can apply
+    pub fn with_geo_resolver(mut self, geo_resolver: Arc<dyn GeoResolver>) -> Self {
+        self.geo_resolver = geo_resolver;
+        self
+    }
+    
+    /// Check if request should be rate limited. Every limit is checked before any is spent, so
+    /// a refused request does not use up the budgets it passed.
     pub async fn check_rate_limit(&self, context: RateLimitContext) -> RateLimitResult {
         // Check if user is banned
         if let Some(user_id) = context.user_id {
@@ -496,49 +742,52 @@ impl RateLimitService {
             }
         }
         
-        // Check IP rate limits
-        let ip_result = self.check_ip_rate_limit(&context).await;
-        if !ip_result.allowed {
-            return ip_result;
-        }
-        
-        // Check user rate limits (if authenticated)
-        if context.user_id.is_some() {
-            let user_result = self.check_user_rate_limit(&context).await;
-            if !user_result.allowed {
-                return user_result;
+        // First pass checks every limit; the second spends them once all have passed
+        for spend in [false, true] {
+            // Check IP rate limits
+            let ip_result = self.check_ip_rate_limit(&context, spend).await;
+            if !ip_result.allowed {
+                return ip_result;
             }
-        }
-        
-        // Check endpoint-specific rate limits
-        let endpoint_result = self.check_endpoint_rate_limit(&context).await;
-        if !endpoint_result.allowed {
-            return endpoint_result;
-        }
-        
-        // Check HIPAA-sensitive operation limits
-        if context.accesses_phi || context.is_data_export {
-            let hipaa_result = self.check_hipaa_sensitive_limit(&context).await;
-            if !hipaa_result.allowed {
-                return hipaa_result;
+            
+            // Check user rate limits (if authenticated)
+            if context.user_id.is_some() {
+                let user_result = self.check_user_rate_limit(&context, spend).await;
+                if !user_result.allowed {
+                    return user_result;
+                }
+            }
+            
+            // Check endpoint-specific rate limits
+            let endpoint_result = self.check_endpoint_rate_limit(&context, spend).await;
+            if !endpoint_result.allowed {
+                return endpoint_result;
+            }
+            
+            // Check HIPAA-sensitive operation limits
+            if context.accesses_phi || context.is_data_export || context.accesses_audit_log {
+                let hipaa_result = self.check_hipaa_sensitive_limit(&context, spend).await;
+                if !hipaa_result.allowed {
+                    return hipaa_result;
+                }
             }
         }
         
-        RateLimitResult {
-            allowed: true,
-            denial_reason: None,
-            rate_info: None,
-            retry_after_seconds: None,
-            violation: None,
-        }
+        RateLimitResult::allowed()
     }
     
-    /// Check IP-based rate limits
-    async fn check_ip_rate_limit(&self, context: &RateLimitContext) -> RateLimitResult {
-        let config = self.config.read().unwrap();
+    /// Check IP-based rate limits; with `spend`, use up the request instead
+    async fn check_ip_rate_limit(&self, context: &RateLimitContext, spend: bool) -> RateLimitResult {
+        let (trusted, restrictions) = {
+            let config = self.config.read().unwrap();
+            (
+                config.ip_limits.trusted_ips.contains(&context.ip_address.to_string()),
+                config.ip_limits.geographic_restrictions.clone(),
+            )
+        };
         
         // Check if IP is trusted
-        if config.ip_limits.trusted_ips.contains(&context.ip_address.to_string()) {
+        if trusted {
             return RateLimitResult {
                 allowed: true,
                 denial_reason: None,
@@ -548,13 +797,20 @@ impl RateLimitService {
             };
         }
         
+        if let (Some(restrictions), false) = (restrictions, spend) {
+            if let Some(denied) = self.check_geographic_restrictions(context, &restrictions).await {
+                return denied;
+            }
+        }
+        
+        let config = self.config.read().unwrap();
         let mut ip_limiters = self.ip_limiters.write().unwrap();
         let ip_limiter = ip_limiters.entry(context.ip_address).or_insert_with(|| {
             IpLimiter {
-                request_limiter: RateLimiter::direct(
+                request_limiter: Budget::new(
                     Quota::per_minute(NonZeroU32::new(config.ip_limits.requests_per_minute_per_ip).unwrap())
                 ),
-                auth_limiter: RateLimiter::direct(
+                auth_limiter: Budget::new(
                     Quota::per_hour(NonZeroU32::new(config.ip_limits.max_failed_auth_per_hour).unwrap())
                 ),
                 last_activity: Instant::now(),
@@ -564,17 +820,15 @@ impl RateLimitService {
         });
         
         ip_limiter.last_activity = Instant::now();
+        if spend {
+            ip_limiter.request_limiter.spend();
+            return RateLimitResult::allowed();
+        }
         
         // Check general IP rate limit
-        match ip_limiter.request_limiter.check() {
-            Ok(_) => RateLimitResult {
-                allowed: true,
-                denial_reason: None,
-                rate_info: None,
-                retry_after_seconds: None,
-                violation: None,
-            },
-            Err(negative) => {
+        match ip_limiter.request_limiter.wait_time() {
+            None => RateLimitResult::allowed(),
+            Some(wait) => {
                 ip_limiter.violation_count += 1;
                 
                 let violation = self.record_violation(
@@ -601,17 +855,91 @@ impl RateLimitService {
                         allowed_rate: config.ip_limits.requests_per_minute_per_ip,
                         time_unit_seconds: 60,
                         current_usage: config.ip_limits.requests_per_minute_per_ip,
-                        reset_in_seconds: negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32,
+                        reset_in_seconds: wait.as_secs() as u32,
                     }),
-                    retry_after_seconds: Some(negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32),
+                    retry_after_seconds: Some(wait.as_secs() as u32),
                     violation: Some(violation),
                 }
             }
         }
     }
     
-    /// Check user-based rate limits
-    async fn check_user_rate_limit(&self, context: &RateLimitContext) -> RateLimitResult {
+    /// Deny requests from blocked countries, or from outside a non-empty allow list. Addresses
+    /// that cannot be placed (private, loopback, or unknown to the resolver) are not restricted;
+    /// a failed lookup is denied under an allow list unless `allow_on_lookup_failure` is set.
+    async fn check_geographic_restrictions(
+        &self,
+        context: &RateLimitContext,
+        restrictions: &GeographicRestrictions,
+    ) -> Option<RateLimitResult> {
+        if restrictions.allowed_countries.is_empty() && restrictions.blocked_countries.is_empty() {
+            return None;
+        }
+        let country = match self.resolve_country(context.ip_address).await {
+            Ok(country) => country?,
+            Err(_) if restrictions.allowed_countries.is_empty() || restrictions.allow_on_lookup_failure => return None,
+            Err(e) => {
+                let violation = self.record_violation(
+                    context,
+                    LimitType::GeographicRestriction,
+                    0,
+                    ViolationSeverity::Moderate,
+                );
+                return Some(RateLimitResult {
+                    allowed: false,
+                    denial_reason: Some(format!("Country of {} could not be verified: {}", context.ip_address, e)),
+                    rate_info: None,
+                    retry_after_seconds: None,
+                    violation: Some(violation),
+                });
+            }
+        };
+        let listed = |countries: &[String]| countries.iter().any(|c| c.eq_ignore_ascii_case(&country));
+        
+        let reason = if listed(&restrictions.blocked_countries) {
+            format!("Access from country {} is blocked", country)
+        } else if !restrictions.allowed_countries.is_empty() && !listed(&restrictions.allowed_countries) {
+            format!("Access from country {} is not permitted", country)
+        } else {
+            return None;
+        };
+        
+        let violation = self.record_violation(
+            context,
+            LimitType::GeographicRestriction,
+            0,
+            ViolationSeverity::Severe,
+        );
+        Some(RateLimitResult {
+            allowed: false,
+            denial_reason: Some(reason),
+            rate_info: None,
+            retry_after_seconds: None,
+            violation: Some(violation),
+        })
+    }
+    
+    /// Country code for an IP, cached per address; `Ok(None)` when the address cannot be placed.
+    /// Lookup failures are not cached and are retried on the next request.
+    async fn resolve_country(&self, ip: IpAddr) -> Result<Option<String>, String> {
+        if is_internal_address(ip) {
+            return Ok(None);
+        }
+        if let Some(country) = self.ip_countries.read().unwrap().get(&ip) {
+            return Ok(country.clone());
+        }
+        let country = self.geo_resolver.resolve(&ip.to_string()).await
+            .map_err(|e| {
+                log::warn!("Could not resolve country for {}: {}", ip, e);
+                e
+            })?
+            .and_then(|l| l.country);
+        self.ip_countries.write().unwrap().insert(ip, country.clone());
+        Ok(country)
+    }
+    
+    /// Check user-based rate limits; with `spend`, use up the request instead
+    async fn check_user_rate_limit(&self, context: &RateLimitContext, spend: bool) -> RateLimitResult {
         let user_id = context.user_id.unwrap();
         let user_role = context.user_role.as_ref().unwrap();
         let config = self.config.read().unwrap();
@@ -625,13 +953,13 @@ impl RateLimitService {
         let mut user_limiters = self.user_limiters.write().unwrap();
         let user_limiter = user_limiters.entry(user_id).or_insert_with(|| {
             UserLimiter {
-                request_limiter: RateLimiter::direct(
+                request_limiter: Budget::new(
                     Quota::per_minute(NonZeroU32::new(role_limits.requests_per_minute).unwrap())
                 ),
-                phi_access_limiter: RateLimiter::direct(
+                phi_access_limiter: Budget::new(
                     Quota::per_hour(NonZeroU32::new(role_limits.phi_access_per_hour).unwrap())
                 ),
-                data_export_limiter: RateLimiter::direct(
+                data_export_limiter: Budget::new(
                     // Using per_hour * 24 to approximate per_day since per_day doesn't exist
                     Quota::per_hour(NonZeroU32::new(role_limits.data_exports_per_day * 24).unwrap())
                 ),
@@ -643,15 +971,26 @@ impl RateLimitService {
         });
         
         user_limiter.last_activity = Instant::now();
+        if spend {
+            user_limiter.request_limiter.spend();
+            if context.accesses_phi {
+                user_limiter.phi_access_limiter.spend();
+            }
+            if context.is_data_export {
+                user_limiter.data_export_limiter.spend();
+            }
+            return RateLimitResult::allowed();
+        }
         
         // Check general request rate limit
-        match user_limiter.request_limiter.check() {
-            Ok(_) => {
+        match user_limiter.request_limiter.wait_time() {
+            None => {
                 // Check PHI access if applicable
                 if context.accesses_phi {
-                    match user_limiter.phi_access_limiter.check() {
-                        Ok(_) => (),
-                        Err(negative) => {
+                    match user_limiter.phi_access_limiter.wait_time() {
+                        None => (),
+                        Some(wait) => {
+                            self.count_user_violation(user_limiter, user_id, &config);
                             let violation = self.record_violation(
                                 context,
                                 LimitType::PhiAccess,
@@ -667,9 +1006,9 @@ impl RateLimitService {
                                     allowed_rate: role_limits.phi_access_per_hour,
                                     time_unit_seconds: 3600,
                                     current_usage: role_limits.phi_access_per_hour,
-                                    reset_in_seconds: negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32,
+                                    reset_in_seconds: wait.as_secs() as u32,
                                 }),
-                                retry_after_seconds: Some(negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32),
+                                retry_after_seconds: Some(wait.as_secs() as u32),
                                 violation: Some(violation),
                             };
                         }
@@ -678,9 +1017,10 @@ impl RateLimitService {
                 
                 // Check data export if applicable
                 if context.is_data_export {
-                    match user_limiter.data_export_limiter.check() {
-                        Ok(_) => (),
-                        Err(negative) => {
+                    match user_limiter.data_export_limiter.wait_time() {
+                        None => (),
+                        Some(wait) => {
+                            self.count_user_violation(user_limiter, user_id, &config);
                             let violation = self.record_violation(
                                 context,
                                 LimitType::DataExport,
@@ -696,9 +1036,9 @@ impl RateLimitService {
                                     allowed_rate: role_limits.data_exports_per_day,
                                     time_unit_seconds: 86400,
                                     current_usage: role_limits.data_exports_per_day,
-                                    reset_in_seconds: negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32,
+                                    reset_in_seconds: wait.as_secs() as u32,
                                 }),
-                                retry_after_seconds: Some(negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32),
+                                retry_after_seconds: Some(wait.as_secs() as u32),
                                 violation: Some(violation),
                             };
                         }
@@ -713,8 +1053,8 @@ impl RateLimitService {
                     violation: None,
                 }
             },
-            Err(negative) => {
-                user_limiter.violation_count += 1;
+            Some(wait) => {
+                self.count_user_violation(user_limiter, user_id, &config);
                 
                 let violation = self.record_violation(
                     context,
@@ -731,17 +1071,29 @@ impl RateLimitService {
                         allowed_rate: role_limits.requests_per_minute,
                         time_unit_seconds: 60,
                         current_usage: role_limits.requests_per_minute,
-                        reset_in_seconds: negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32,
+                        reset_in_seconds: wait.as_secs() as u32,
                     }),
-                    retry_after_seconds: Some(negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32),
+                    retry_after_seconds: Some(wait.as_secs() as u32),
                     violation: Some(violation),
                 }
             }
         }
     }
     
-    /// Check endpoint-specific rate limits
-    async fn check_endpoint_rate_limit(&self, context: &RateLimitContext) -> RateLimitResult {
+    /// Count a user's violation and ban them once `max_violations_before_ban` is reached
+    fn count_user_violation(&self, limiter: &mut UserLimiter, user_id: Uuid, config: &RateLimitConfig) {
+        limiter.violation_count += 1;
+        if limiter.violation_count >= config.max_violations_before_ban {
+            self.ban_user(
+                user_id,
+                "Excessive rate limit violations".to_string(),
+                Duration::from_secs(config.temporary_ban_duration_minutes as u64 * 60),
+            );
+        }
+    }
+    
+    /// Check endpoint-specific rate limits; with `spend`, use up the request instead
+    async fn check_endpoint_rate_limit(&self, context: &RateLimitContext, spend: bool) -> RateLimitResult {
         let config = self.config.read().unwrap();
         
         // Find matching endpoint configuration
@@ -755,22 +1107,50 @@ impl RateLimitService {
         if let Some(endpoint_config) = endpoint_config {
             let mut endpoint_limiters = self.endpoint_limiters.write().unwrap();
             let endpoint_limiter = endpoint_limiters.entry(context.endpoint.clone()).or_insert_with(|| {
-                RateLimiter::direct(Quota::per_minute(NonZeroU32::new(endpoint_config.requests_per_minute).unwrap()))
+                Budget::new(Quota::per_minute(NonZeroU32::new(endpoint_config.requests_per_minute).unwrap()))
             });
             
-            match endpoint_limiter.check() {
-                Ok(_) => RateLimitResult {
-                    allowed: true,
-                    denial_reason: None,
-                    rate_info: None,
-                    retry_after_seconds: None,
-                    violation: None,
-                },
-                Err(negative) => {
+            // MFA-verified sessions on exemptable endpoints may draw on a bounded headroom
+            // once the base limit is exhausted
+            let elevated_limit = endpoint_config.mfa_elevated_limit();
+            let exemption_applies = context.mfa_verified && elevated_limit > endpoint_config.requests_per_minute;
+            let allowed_rate = if exemption_applies { elevated_limit } else { endpoint_config.requests_per_minute };
+            
+            let headroom_key = format!(
+                "{}#{}",
+                context.endpoint,
+                context.user_id.map(|id| id.to_string())
+                    .or_else(|| context.session_id.clone())
+                    .unwrap_or_else(|| context.ip_address.to_string()),
+            );
+            let mut headroom_limiters = self.mfa_headroom_limiters.write().unwrap();
+            let headroom_per_minute = elevated_limit - endpoint_config.requests_per_minute;
+            
+            let base_wait = endpoint_limiter.wait_time();
+            if spend {
+                if base_wait.is_none() {
+                    endpoint_limiter.spend();
+                } else if exemption_applies {
+                    mfa_headroom(&mut headroom_limiters, headroom_key, headroom_per_minute).spend();
+                    self.record_mfa_exemption_use(context, endpoint_config.requests_per_minute, elevated_limit);
+                }
+                return RateLimitResult::allowed();
+            }
+            
+            let wait = match base_wait {
+                Some(wait) if exemption_applies => mfa_headroom(&mut headroom_limiters, headroom_key, headroom_per_minute)
+                    .wait_time()
+                    .map(|headroom_wait| headroom_wait.min(wait)),
+                wait => wait,
+            };
+            
+            match wait {
+                None => RateLimitResult::allowed(),
+                Some(wait) => {
                     let violation = self.record_violation(
                         context,
                         LimitType::EndpointSpecific,
-                        endpoint_config.requests_per_minute,
+                        allowed_rate,
                         if endpoint_config.risk_level >= 4 {
                             ViolationSeverity::Major
                         } else {
@@ -782,13 +1162,13 @@ impl RateLimitService {
                         allowed: false,
                         denial_reason: Some(format!("Endpoint rate limit exceeded: {}", context.endpoint)),
                         rate_info: Some(RateInfo {
-                            requested_rate: endpoint_config.requests_per_minute + 1,
-                            allowed_rate: endpoint_config.requests_per_minute,
+                            requested_rate: allowed_rate + 1,
+                            allowed_rate,
                             time_unit_seconds: 60,
-                            current_usage: endpoint_config.requests_per_minute,
-                            reset_in_seconds: negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32,
+                            current_usage: allowed_rate,
+                            reset_in_seconds: wait.as_secs() as u32,
                         }),
-                        retry_after_seconds: Some(negative.wait_time_from(DefaultClock::default().now()).as_secs() as u32),
+                        retry_after_seconds: Some(wait.as_secs() as u32),
                         violation: Some(violation),
                     }
                 }
@@ -804,10 +1184,73 @@ impl RateLimitService {
         }
     }
     
-    /// Check HIPAA-sensitive operation limits
-    async fn check_hipaa_sensitive_limit(&self, _context: &RateLimitContext) -> RateLimitResult {
-        // Implementation would check specific HIPAA-sensitive operation limits
-        // For now, return allowed
+    /// Check HIPAA-sensitive operation limits; with `spend`, use up the operations instead
+    async fn check_hipaa_sensitive_limit(&self, context: &RateLimitContext, spend: bool) -> RateLimitResult {
+        let Some(user_id) = context.user_id else {
+            // Anonymous requests never reach PHI; the IP limits cover them
+            return RateLimitResult {
+                allowed: true,
+                denial_reason: None,
+                rate_info: None,
+                retry_after_seconds: None,
+                violation: None,
+            };
+        };
+        let limits = self.config.read().unwrap().hipaa_sensitive_limits.clone();
+        
+        let mut operations = Vec::new();
+        if context.accesses_phi {
+            // Reads are views; anything else changes the record
+            if matches!(context.method.to_ascii_uppercase().as_str(), "GET" | "HEAD") {
+                operations.push(HipaaOperation::PhiView);
+            } else {
+                operations.push(HipaaOperation::PhiModification);
+            }
+        }
+        if context.is_data_export {
+            operations.push(HipaaOperation::PhiExport);
+        }
+        if context.accesses_audit_log {
+            operations.push(HipaaOperation::AuditAccess);
+        }
+        
+        let mut hipaa_limiters = self.hipaa_limiters.write().unwrap();
+        let limiter = hipaa_limiters.entry(user_id).or_insert_with(|| HipaaOperationLimiter::new(&limits));
+        limiter.last_activity = Instant::now();
+        if spend {
+            for operation in operations {
+                limiter.limiter(operation).spend();
+            }
+            return RateLimitResult::allowed();
+        }
+        
+        for operation in operations {
+            if let Some(wait) = limiter.limiter(operation).wait_time() {
+                let (allowed_rate, window) = operation.limit(&limits);
+                let wait_seconds = wait.as_secs() as u32;
+                let violation = self.record_violation(
+                    context,
+                    LimitType::HippaSensitive,
+                    allowed_rate,
+                    ViolationSeverity::Major,
+                );
+                
+                return RateLimitResult {
+                    allowed: false,
+                    denial_reason: Some(format!("{} limit exceeded", operation.description())),
+                    rate_info: Some(RateInfo {
+                        requested_rate: allowed_rate + 1,
+                        allowed_rate,
+                        time_unit_seconds: window.as_secs() as u32,
+                        current_usage: allowed_rate,
+                        reset_in_seconds: wait_seconds,
+                    }),
+                    retry_after_seconds: Some(wait_seconds),
+                    violation: Some(violation),
+                };
+            }
+        }
+        
         RateLimitResult {
             allowed: true,
             denial_reason: None,
@@ -852,6 +1295,27 @@ impl RateLimitService {
         violation
     }
     
+    /// Record a request that exceeded the base limit and was allowed by the MFA exemption
+    fn record_mfa_exemption_use(&self, context: &RateLimitContext, base_limit: u32, elevated_limit: u32) {
+        self.mfa_exemption_uses.write().unwrap().push(MfaExemptionUse {
+            timestamp: context.timestamp,
+            user_id: context.user_id,
+            session_id: context.session_id.clone(),
+            ip_address: context.ip_address,
+            endpoint: context.endpoint.clone(),
+            base_limit,
+            elevated_limit,
+        });
+        
+        log::info!("MFA rate limit exemption used by {:?} on endpoint {} ({} -> {} per minute)",
+            context.user_id, context.endpoint, base_limit, elevated_limit);
+    }
+    
+    /// Requests served under the MFA exemption, for compliance review
+    pub fn get_mfa_exemption_uses(&self) -> Vec<MfaExemptionUse> {
+        self.mfa_exemption_uses.read().unwrap().clone()
+    }
+    
     /// Ban an IP address
     fn ban_ip(&self, ip: IpAddr, reason: String, duration: Duration) {
         let ban_info = BanInfo {
@@ -880,6 +1344,75 @@ impl RateLimitService {
         log::error!("Banned user {} for {} minutes: {}", user_id, duration.as_secs() / 60, reason);
     }
     
+    /// The ban in force on `target`, if any
+    pub fn active_ban(&self, target: &BanTarget) -> Option<BanInfo> {
+        let ban = match target {
+            BanTarget::Ip(ip) => self.banned_ips.read().unwrap().get(ip).cloned(),
+            BanTarget::User(user_id) => self.banned_users.read().unwrap().get(user_id).cloned(),
+        };
+        ban.filter(|ban| ban.is_active())
+    }
+    
+    /// Lift an IP ban before it expires; the address's violation count starts over
+    pub fn unban_ip(&self, ip: IpAddr, by_user: &str) -> Result<BanInfo, SecurityError> {
+        let ban = self.banned_ips.write().unwrap()
+            .remove(&ip)
+            .filter(|ban| ban.is_active())
+            .ok_or_else(|| SecurityError::NotFound {
+                reason: format!("No active ban for IP {}", ip),
+            })?;
+        if let Some(limiter) = self.ip_limiters.write().unwrap().get_mut(&ip) {
+            limiter.violation_count = 0;
+        }
+        
+        log::warn!("Ban on IP {} lifted by {} ({})", ip, by_user, ban.reason);
+        Ok(ban)
+    }
+    
+    /// Lift a user ban before it expires; the user's violation count starts over
+    pub fn unban_user(&self, user_id: Uuid, by_user: &str) -> Result<BanInfo, SecurityError> {
+        let ban = self.banned_users.write().unwrap()
+            .remove(&user_id)
+            .filter(|ban| ban.is_active())
+            .ok_or_else(|| SecurityError::NotFound {
+                reason: format!("No active ban for user {}", user_id),
+            })?;
+        if let Some(limiter) = self.user_limiters.write().unwrap().get_mut(&user_id) {
+            limiter.violation_count = 0;
+        }
+        
+        log::warn!("Ban on user {} lifted by {} ({})", user_id, by_user, ban.reason);
+        Ok(ban)
+    }
+    
+    /// Bans currently in force, oldest first
+    pub fn list_active_bans(&self) -> Vec<ActiveBan> {
+        let active = |target: BanTarget, ban: &BanInfo| {
+            ban.time_remaining().map(|remaining| ActiveBan {
+                target,
+                ban: ban.clone(),
+                remaining_seconds: remaining.num_seconds(),
+            })
+        };
+        let mut bans: Vec<ActiveBan> = self.banned_ips.read().unwrap().iter()
+            .filter_map(|(ip, ban)| active(BanTarget::Ip(*ip), ban))
+            .chain(self.banned_users.read().unwrap().iter()
+                .filter_map(|(user_id, ban)| active(BanTarget::User(*user_id), ban)))
+            .collect();
+        bans.sort_by_key(|b| b.ban.banned_at);
+        bans
+    }
+    
+    /// Drop expired bans; returns how many were removed
+    pub fn sweep_expired_bans(&self) -> usize {
+        let mut banned_ips = self.banned_ips.write().unwrap();
+        let mut banned_users = self.banned_users.write().unwrap();
+        let before = banned_ips.len() + banned_users.len();
+        banned_ips.retain(|_, ban| ban.is_active());
+        banned_users.retain(|_, ban| ban.is_active());
+        before - banned_ips.len() - banned_users.len()
+    }
+    
     /// Get rate limit statistics
     pub fn get_statistics(&self) -> RateLimitStatistics {
         let violations = self.violations.read().unwrap();
@@ -897,6 +1430,7 @@ impl RateLimitService {
             active_user_bans: banned_users.values().filter(|b| b.is_active()).count(),
             active_user_limiters: self.user_limiters.read().unwrap().len(),
             active_ip_limiters: self.ip_limiters.read().unwrap().len(),
+            mfa_exemption_uses: self.mfa_exemption_uses.read().unwrap().len(),
         }
     }
     
@@ -910,19 +1444,50 @@ impl RateLimitService {
             now.duration_since(limiter.last_activity) < cleanup_threshold
         });
         
+        // HIPAA budgets include daily caps, so they are kept for a day of inactivity
+        self.hipaa_limiters.write().unwrap().retain(|_, limiter| {
+            now.duration_since(limiter.last_activity) < DAY
+        });
+        
         // Clean up inactive IP limiters
         self.ip_limiters.write().unwrap().retain(|_, limiter| {
             now.duration_since(limiter.last_activity) < cleanup_threshold
         });
         
+        // Country lookups are refreshed after each cleanup
+        self.ip_countries.write().unwrap().clear();
+        
+        // Keep MFA exemption records for the violation window
+        let window = chrono::Duration::minutes(self.config.read().unwrap().violation_window_minutes as i64);
+        self.mfa_exemption_uses.write().unwrap().retain(|u| Utc::now() - u.timestamp < window);
+        
         // Clean up expired bans
-        self.banned_ips.write().unwrap().retain(|_, ban| ban.is_active());
-        self.banned_users.write().unwrap().retain(|_, ban| ban.is_active());
+        let expired_bans = self.sweep_expired_bans();
         
-        log::debug!("Cleaned up expired rate limiters and bans");
+        log::debug!("Cleaned up expired rate limiters and {} expired bans", expired_bans);
     }
 }
 
+/// Shared rate limiting service state
+#[derive(Clone)]
+pub struct RateLimitServiceState(pub Arc<RateLimitService>);
+
+impl RateLimitServiceState {
+    pub fn new(service: RateLimitService) -> Self {
+        Self(Arc::new(service))
+    }
+}
+
+/// Periodically sweep expired bans and idle limiters so the maps do not grow without bound
+pub fn start_cleanup_sweep(service: Arc<RateLimitService>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            service.cleanup().await;
+        }
+    })
+}
+
 /// Rate limiting statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitStatistics {
@@ -932,6 +1497,7 @@ pub struct RateLimitStatistics {
     pub active_user_bans: usize,
     pub active_user_limiters: usize,
     pub active_ip_limiters: usize,
+    pub mfa_exemption_uses: usize,
 }
 
 /// Initialize rate limiting system
@@ -950,6 +1516,7 @@ pub async fn initialize_rate_limiter() -> Result<(), SecurityError> {
         session_id: Some(Uuid::new_v4().to_string()),
         accesses_phi: false,
         is_data_export: false,
+        accesses_audit_log: false,
         mfa_verified: true,
         timestamp: Utc::now(),
     };
@@ -997,6 +1564,7 @@ mod tests {
             session_id: None,
             accesses_phi: false,
             is_data_export: false,
+            accesses_audit_log: false,
             mfa_verified: false,
             timestamp: Utc::now(),
         };
@@ -1028,6 +1596,7 @@ mod tests {
             session_id: Some(Uuid::new_v4().to_string()),
             accesses_phi: false,
             is_data_export: false,
+            accesses_audit_log: false,
             mfa_verified: false,
             timestamp: Utc::now(),
         };
@@ -1041,6 +1610,283 @@ mod tests {
         assert!(!result2.allowed);
     }
     
+    fn phi_endpoint_context(mfa_verified: bool) -> RateLimitContext {
+        RateLimitContext {
+            user_id: Some(Uuid::new_v4()),
+            user_role: Some(HealthcareRole::HealthcareProvider),
+            ip_address: IpAddr::from_str("127.0.0.1").unwrap(),
+            endpoint: "/api/patients/123".to_string(),
+            method: "GET".to_string(),
+            user_agent: Some("Test".to_string()),
+            session_id: Some(Uuid::new_v4().to_string()),
+            accesses_phi: true,
+            is_data_export: false,
+            accesses_audit_log: false,
+            mfa_verified,
+            timestamp: Utc::now(),
+        }
+    }
+    
+    fn exemptable_endpoint_config() -> RateLimitConfig {
+        let mut config = RateLimitConfig::default();
+        let patients = config.endpoint_limits.get_mut("/api/patients/.*").unwrap();
+        patients.requests_per_minute = 2;
+        patients.mfa_elevated_requests_per_minute = 4;
+        config
+    }
+    
+    #[tokio::test]
+    async fn test_mfa_verified_session_gets_elevated_endpoint_limit() {
+        let service = RateLimitService::new(exemptable_endpoint_config());
+        let context = phi_endpoint_context(true);
+        
+        for _ in 0..4 {
+            assert!(service.check_rate_limit(context.clone()).await.allowed);
+        }
+        
+        // Elevated limit is still a limit
+        let denied = service.check_rate_limit(context).await;
+        assert!(!denied.allowed);
+        assert_eq!(denied.rate_info.unwrap().allowed_rate, 4);
+        
+        // Only the requests above the base limit count as exemption use
+        let uses = service.get_mfa_exemption_uses();
+        assert_eq!(uses.len(), 2);
+        assert!(uses.iter().all(|u| u.base_limit == 2 && u.elevated_limit == 4));
+        assert_eq!(service.get_statistics().mfa_exemption_uses, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_non_mfa_session_stays_at_base_endpoint_limit() {
+        let service = RateLimitService::new(exemptable_endpoint_config());
+        let context = phi_endpoint_context(false);
+        
+        for _ in 0..2 {
+            assert!(service.check_rate_limit(context.clone()).await.allowed);
+        }
+        
+        let denied = service.check_rate_limit(context).await;
+        assert!(!denied.allowed);
+        assert_eq!(denied.rate_info.unwrap().allowed_rate, 2);
+        assert!(service.get_mfa_exemption_uses().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_phi_view_budget_is_separate_from_request_limit() {
+        let mut config = RateLimitConfig::default();
+        config.hipaa_sensitive_limits.phi_views_per_hour = 3;
+        let service = RateLimitService::new(config);
+        let mut phi_view = phi_endpoint_context(false);
+        phi_view.endpoint = "/api/clients/123/notes".to_string();
+        
+        for _ in 0..3 {
+            assert!(service.check_rate_limit(phi_view.clone()).await.allowed);
+        }
+        
+        let denied = service.check_rate_limit(phi_view.clone()).await;
+        assert!(!denied.allowed);
+        assert_eq!(denied.violation.unwrap().limit_type, LimitType::HippaSensitive);
+        let rate_info = denied.rate_info.unwrap();
+        assert_eq!((rate_info.allowed_rate, rate_info.time_unit_seconds), (3, 3600));
+        
+        // The same provider's non-PHI calls are unaffected
+        let routine = RateLimitContext {
+            endpoint: "/api/appointments".to_string(),
+            accesses_phi: false,
+            ..phi_view.clone()
+        };
+        assert!(service.check_rate_limit(routine).await.allowed);
+        
+        // Modifications have their own budget
+        let update = RateLimitContext { method: "PUT".to_string(), ..phi_view };
+        assert!(service.check_rate_limit(update).await.allowed);
+    }
+    
+    #[tokio::test]
+    async fn test_refused_request_spends_no_other_limit() {
+        let mut config = RateLimitConfig::default();
+        config.ip_limits.requests_per_minute_per_ip = 2;
+        config.hipaa_sensitive_limits.phi_views_per_hour = 1;
+        let service = RateLimitService::new(config);
+        let phi_view = RateLimitContext {
+            ip_address: IpAddr::from_str("10.0.0.7").unwrap(),
+            endpoint: "/api/clients/123/notes".to_string(),
+            ..phi_endpoint_context(false)
+        };
+        
+        assert!(service.check_rate_limit(phi_view.clone()).await.allowed);
+        let denied = service.check_rate_limit(phi_view.clone()).await;
+        assert_eq!(denied.violation.unwrap().limit_type, LimitType::HippaSensitive);
+        
+        // The refused PHI view did not use the address's second request
+        let routine = RateLimitContext { accesses_phi: false, ..phi_view };
+        assert!(service.check_rate_limit(routine.clone()).await.allowed);
+        assert!(!service.check_rate_limit(routine).await.allowed);
+    }
+    
+    /// Places public test addresses by their first octet, including private ranges
+    struct CountryByOctet;
+    
+    #[async_trait::async_trait]
+    impl GeoResolver for CountryByOctet {
+        async fn resolve(&self, ip: &str) -> Result<Option<crate::security::geolocation::GeoLocation>, String> {
+            let country = match ip.split('.').next() {
+                Some("9") => return Err("lookup service unavailable".to_string()),
+                Some("24") => "CA",
+                Some("8") => "US",
+                Some("5") => "RU",
+                _ => "ZZ",
+            };
+            Ok(Some(crate::security::geolocation::GeoLocation {
+                latitude: 0.0,
+                longitude: 0.0,
+                city: None,
+                country: Some(country.to_string()),
+            }))
+        }
+    }
+    
+    fn geo_restricted_service() -> RateLimitService {
+        let mut config = RateLimitConfig::default();
+        let restrictions = config.ip_limits.geographic_restrictions.as_mut().unwrap();
+        restrictions.allowed_countries = vec!["CA".to_string()];
+        restrictions.blocked_countries = vec!["RU".to_string()];
+        config.ip_limits.trusted_ips = vec!["8.8.4.4".to_string()];
+        RateLimitService::new(config).with_geo_resolver(Arc::new(CountryByOctet))
+    }
+    
+    fn request_from(ip: &str) -> RateLimitContext {
+        RateLimitContext {
+            ip_address: IpAddr::from_str(ip).unwrap(),
+            user_id: None,
+            user_role: None,
+            endpoint: "/api/appointments".to_string(),
+            session_id: None,
+            accesses_phi: false,
+            ..phi_endpoint_context(false)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_geographic_restrictions_allow_and_block_countries() {
+        let service = geo_restricted_service();
+        
+        assert!(service.check_rate_limit(request_from("24.48.0.1")).await.allowed);
+        
+        let outside = service.check_rate_limit(request_from("8.8.8.8")).await;
+        assert!(!outside.allowed);
+        assert_eq!(outside.denial_reason.as_deref(), Some("Access from country US is not permitted"));
+        let violation = outside.violation.unwrap();
+        assert_eq!(violation.limit_type, LimitType::GeographicRestriction);
+        assert_eq!(violation.severity, ViolationSeverity::Severe);
+        
+        let blocked = service.check_rate_limit(request_from("5.3.0.1")).await;
+        assert_eq!(blocked.denial_reason.as_deref(), Some("Access from country RU is blocked"));
+    }
+    
+    #[tokio::test]
+    async fn test_failed_lookup_is_denied_under_an_allow_list() {
+        let service = geo_restricted_service();
+        let denied = service.check_rate_limit(request_from("9.9.9.9")).await;
+        assert!(!denied.allowed);
+        assert!(denied.denial_reason.unwrap().starts_with("Country of 9.9.9.9 could not be verified"));
+        
+        let mut config = service.config.read().unwrap().clone();
+        config.ip_limits.geographic_restrictions.as_mut().unwrap().allow_on_lookup_failure = true;
+        let service = RateLimitService::new(config).with_geo_resolver(Arc::new(CountryByOctet));
+        assert!(service.check_rate_limit(request_from("9.9.9.9")).await.allowed);
+    }
+    
+    #[tokio::test]
+    async fn test_unplaceable_and_trusted_ips_bypass_geographic_restrictions() {
+        let service = geo_restricted_service();
+        
+        // The resolver would call these "ZZ", but private and loopback addresses are never placed
+        for ip in ["127.0.0.1", "10.0.0.7", "192.168.1.20", "::1"] {
+            assert!(service.check_rate_limit(request_from(ip)).await.allowed, "{} was restricted", ip);
+        }
+        // Trusted addresses skip every check, geography included
+        assert!(service.check_rate_limit(request_from("8.8.4.4")).await.allowed);
+        assert_eq!(service.get_statistics().total_violations, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_unban_lifts_ban_and_resets_violations() {
+        let mut config = RateLimitConfig::default();
+        config.ip_limits.requests_per_minute_per_ip = 1;
+        config.max_violations_before_ban = 2;
+        let service = RateLimitService::new(config);
+        let context = RateLimitContext {
+            user_id: None,
+            user_role: None,
+            session_id: None,
+            ..phi_endpoint_context(false)
+        };
+        let ip = context.ip_address;
+        
+        for _ in 0..3 {
+            service.check_rate_limit(context.clone()).await;
+        }
+        let bans = service.list_active_bans();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].target, BanTarget::Ip(ip));
+        assert!(service.check_rate_limit(context.clone()).await.denial_reason.unwrap().starts_with("IP banned"));
+        
+        let lifted = service.unban_ip(ip, "admin-1").unwrap();
+        assert_eq!(lifted.reason, "Excessive rate limit violations");
+        assert!(service.list_active_bans().is_empty());
+        assert_eq!(service.ip_limiters.read().unwrap()[&ip].violation_count, 0);
+        // Nothing left to lift
+        assert!(matches!(service.unban_ip(ip, "admin-1"), Err(SecurityError::NotFound { .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_repeated_user_violations_lead_to_a_ban() {
+        let mut config = RateLimitConfig::default();
+        config.max_violations_before_ban = 2;
+        config.role_limits.get_mut(&HealthcareRole::HealthcareProvider).unwrap().requests_per_minute = 1;
+        let service = RateLimitService::new(config);
+        let context = RateLimitContext {
+            accesses_phi: false,
+            ..phi_endpoint_context(false)
+        };
+        let user_id = context.user_id.unwrap();
+        
+        for _ in 0..3 {
+            service.check_rate_limit(context.clone()).await;
+        }
+        let ban = service.active_ban(&BanTarget::User(user_id)).unwrap();
+        assert_eq!(ban.reason, "Excessive rate limit violations");
+        assert!(service.check_rate_limit(context).await.denial_reason.unwrap().starts_with("User banned"));
+    }
+    
+    #[test]
+    fn test_sweep_removes_only_expired_bans() {
+        let service = RateLimitService::new(RateLimitConfig::default());
+        let (current, expired) = (Uuid::new_v4(), Uuid::new_v4());
+        service.ban_user(current, "Abuse".to_string(), Duration::from_secs(3600));
+        service.ban_user(expired, "Abuse".to_string(), Duration::from_secs(3600));
+        service.banned_users.write().unwrap().get_mut(&expired).unwrap().banned_at = Utc::now() - chrono::Duration::hours(2);
+        
+        assert_eq!(service.sweep_expired_bans(), 1);
+        let bans = service.list_active_bans();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].target, BanTarget::User(current));
+        // An expired ban cannot be lifted, it is already gone
+        assert!(service.unban_user(expired, "admin-1").is_err());
+        assert!(service.unban_user(current, "admin-1").is_ok());
+    }
+    
+    #[test]
+    fn test_mfa_elevation_is_bounded() {
+        let mut limits = RateLimitConfig::default().endpoint_limits.remove("/api/patients/.*").unwrap();
+        limits.mfa_elevated_requests_per_minute = u32::MAX;
+        assert_eq!(limits.mfa_elevated_limit(), limits.requests_per_minute * MAX_MFA_ELEVATION_FACTOR);
+        
+        limits.mfa_exemption = false;
+        assert_eq!(limits.mfa_elevated_limit(), limits.requests_per_minute);
+    }
+    
     #[test]
     fn test_ban_info() {
         let ban = BanInfo {
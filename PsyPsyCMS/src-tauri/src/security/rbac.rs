@@ -1,7 +1,9 @@
 // Role-Based Access Control (RBAC) System for HIPAA Compliance
 // Implements healthcare-specific permissions and access controls
 
-use crate::security::{SecurityError, HealthcareRole};
+use crate::security::{SecurityError, HealthcareRole, AuditEventType};
+use crate::security::audit::{log_phi_access, AuditEvent, AuditOutcome, AuditService};
+use crate::security::auth::SessionTracker;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
@@ -353,6 +355,75 @@ pub struct RiskAssessment {
     pub requires_monitoring: bool,
 }
 
+/// Kind of elevated access that bypasses normal role permissions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ElevatedGrantKind {
+    /// Emergency break-glass access
+    BreakGlass,
+    /// Permissions delegated from one user to another
+    Delegation,
+}
+
+/// Break-glass session or delegation granting access outside normal RBAC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElevatedGrant {
+    /// Grant identifier
+    pub id: Uuid,
+    /// Break-glass or delegation
+    pub kind: ElevatedGrantKind,
+    /// User holding the elevated access
+    pub grantee_user_id: String,
+    /// User who delegated the access (delegations only)
+    pub grantor_user_id: Option<String>,
+    /// Patient the access applies to (if scoped to one patient)
+    pub patient_id: Option<String>,
+    /// Permissions made available by the grant
+    pub scope: HashSet<Permission>,
+    /// Justification recorded when the grant was opened
+    pub reason: String,
+    /// When the grant was opened
+    pub granted_at: DateTime<Utc>,
+    /// When the grant lapses
+    pub expires_at: DateTime<Utc>,
+    /// When the grant was revoked early (if it was)
+    pub terminated_at: Option<DateTime<Utc>>,
+    /// User who revoked the grant
+    pub terminated_by: Option<String>,
+    /// Reason given for revocation
+    pub termination_reason: Option<String>,
+}
+
+impl ElevatedGrant {
+    /// Whether the grant is in force at the given instant
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.terminated_at.is_none() && self.granted_at <= now && now < self.expires_at
+    }
+
+    /// Whether the grant is currently in force
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(Utc::now())
+    }
+}
+
+/// Break-glass access is an elevated grant of kind `BreakGlass`
+pub type BreakGlassGrant = ElevatedGrant;
+
+/// How long break-glass access lasts
+pub const BREAK_GLASS_DURATION_MINUTES: i64 = 60;
+/// Shortest justification accepted for break-glass access
+pub const MIN_BREAK_GLASS_JUSTIFICATION_CHARS: usize = 30;
+
+/// Stable UUID for an account or record id. Ids that are UUIDs map to themselves; other ids
+/// (Parse-style object ids) map to the first 16 bytes of their SHA-256.
+pub fn stable_uuid(id: &str) -> Uuid {
+    Uuid::parse_str(id).unwrap_or_else(|_| {
+        let digest = ring::digest::digest(&ring::digest::SHA256, id.as_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest.as_ref()[..16]);
+        Uuid::from_bytes(bytes)
+    })
+}
+
 /// RBAC service for healthcare permissions
 pub struct RbacService {
     /// Role definitions
@@ -361,6 +432,14 @@ pub struct RbacService {
     permission_cache: Arc<RwLock<HashMap<String, PermissionResult>>>,
     /// Active permission checks (for audit trail)
     active_checks: Arc<RwLock<HashMap<String, PermissionContext>>>,
+    /// Break-glass and delegation grants, including lapsed ones until purged
+    elevated_grants: Arc<RwLock<HashMap<Uuid, ElevatedGrant>>>,
+    /// Audit trail for break-glass access and assignment denials
+    audit: Option<Arc<AuditService>>,
+    /// Users assigned to each patient record, keyed by record id
+    care_assignments: Arc<RwLock<HashMap<String, HashSet<Uuid>>>>,
+    /// Active sessions, once the auth service is up; patient data access must come from one
+    sessions: Arc<RwLock<Option<SessionTracker>>>,
 }
 
 impl RbacService {
@@ -370,13 +449,33 @@ impl RbacService {
             roles: Arc::new(RwLock::new(HashMap::new())),
             permission_cache: Arc::new(RwLock::new(HashMap::new())),
             active_checks: Arc::new(RwLock::new(HashMap::new())),
+            elevated_grants: Arc::new(RwLock::new(HashMap::new())),
+            audit: None,
+            care_assignments: Arc::new(RwLock::new(HashMap::new())),
+            sessions: Arc::new(RwLock::new(None)),
         };
         
         // Initialize default healthcare roles
         service.initialize_default_roles();
         service
     }
+
+    /// Record break-glass access in the audit trail
+    pub fn with_audit_service(mut self, audit: Arc<AuditService>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
     
+    /// Explicit audit service, else the app-wide one installed at startup
+    fn audit_sink(&self) -> Option<Arc<AuditService>> {
+        self.audit.clone().or_else(crate::security::audit::audit_service)
+    }
+
+    /// Check patient data access against the auth service's sessions from now on
+    pub fn attach_sessions(&self, tracker: SessionTracker) {
+        *self.sessions.write().unwrap() = Some(tracker);
+    }
+
     /// Initialize default healthcare role definitions
     fn initialize_default_roles(&self) {
         let mut roles = self.roles.write().unwrap();
@@ -491,14 +590,46 @@ impl RbacService {
             }),
         });
         
-        // Add other roles (TechnicalSupport, Auditor, Guest)...
+        // Auditor
+        roles.insert(HealthcareRole::Auditor, RoleDefinition {
+            role: HealthcareRole::Auditor,
+            permissions: self.get_auditor_permissions(),
+            description: "Compliance auditor with read access to audit and security records".to_string(),
+            self_assignable: false,
+            max_session_duration: 240,
+            requires_mfa: true,
+            ip_restrictions: None,
+            time_restrictions: None,
+            data_restrictions: None,
+        });
+        
+        // Add other roles (TechnicalSupport, Guest)...
     }
     
     /// Check if user has permission for specific operation
     pub async fn check_permission(&self, context: PermissionContext) -> Result<PermissionResult, SecurityError> {
+        let _timer = crate::services::command_profiler::profiler().time("rbac.check_permission");
         // Store context for audit trail
         let check_id = Uuid::new_v4().to_string();
         self.active_checks.write().unwrap().insert(check_id.clone(), context.clone());
+
+        // A session flagged at sign-in needs MFA for everything; checked ahead of the cache,
+        // which may hold a result from before the flag
+        let tracker = self.sessions.read().unwrap().clone();
+        if !context.mfa_verified && tracker.map_or(false, |tracker| tracker.mfa_pending(&context.session_id)) {
+            return Ok(PermissionResult {
+                granted: false,
+                denial_reason: Some("Multi-factor authentication required after a flagged sign-in".to_string()),
+                mfa_required: true,
+                requirements: vec!["Complete MFA verification".to_string()],
+                risk_assessment: RiskAssessment {
+                    level: 3,
+                    factors: vec!["Sign-in flagged as suspicious".to_string()],
+                    recommendations: vec!["Complete MFA verification before proceeding".to_string()],
+                    requires_monitoring: true,
+                },
+            });
+        }
         
         // Check cache first
         let cache_key = format!("{}:{}:{:?}", context.user_id, context.session_id, context.permission);
@@ -738,6 +869,14 @@ impl RbacService {
         ].into_iter().collect()
     }
     
+    /// Get permissions for compliance auditors
+    fn get_auditor_permissions(&self) -> HashSet<Permission> {
+        vec![
+            Permission::ViewAuditLogs, Permission::ExportAuditLogs, Permission::GenerateComplianceReports,
+            Permission::ViewSecurityReports, Permission::ViewSystemLogs, Permission::ViewUserActivity,
+        ].into_iter().collect()
+    }
+    
     /// Add custom role
     pub async fn add_role(&self, role_def: RoleDefinition) -> Result<(), SecurityError> {
         self.roles.write().unwrap().insert(role_def.role.clone(), role_def);
@@ -764,6 +903,19 @@ impl RbacService {
         self.roles.read().unwrap().get(role).cloned()
     }
     
+    /// Whether a role's definition includes a permission. Alias roles resolve to the role
+    /// they share a definition with; roles without a definition hold no permissions.
+    pub fn role_grants(&self, role: &HealthcareRole, permission: &Permission) -> bool {
+        let role = match role {
+            HealthcareRole::Administrator => &HealthcareRole::SuperAdmin,
+            HealthcareRole::AdminStaff => &HealthcareRole::AdministrativeStaff,
+            other => other,
+        };
+        self.roles.read().unwrap()
+            .get(role)
+            .map_or(false, |def| def.permissions.contains(permission))
+    }
+    
     /// Clear permission cache
     pub fn clear_cache(&self) {
         self.permission_cache.write().unwrap().clear();
@@ -777,6 +929,285 @@ impl RbacService {
             self.active_checks.read().unwrap().len(),
         )
     }
+    
+    /// Record a break-glass session or delegation
+    pub fn register_elevated_grant(&self, grant: ElevatedGrant) -> Result<Uuid, SecurityError> {
+        if grant.expires_at <= grant.granted_at {
+            return Err(SecurityError::ValidationFailed {
+                reason: "Elevated grant must expire after it is granted".to_string(),
+            });
+        }
+        if grant.reason.trim().is_empty() {
+            return Err(SecurityError::ValidationFailed {
+                reason: "Elevated grant requires a justification".to_string(),
+            });
+        }
+        
+        let id = grant.id;
+        self.elevated_grants.write().unwrap().insert(id, grant);
+        // Cached decisions may not reflect the new grant
+        self.clear_cache();
+        log::warn!("Elevated grant {} registered", id);
+        Ok(id)
+    }
+    
+    /// List grants currently in force, soonest-expiring first
+    pub fn list_active_elevated_grants(&self) -> Vec<ElevatedGrant> {
+        let now = Utc::now();
+        let mut active: Vec<ElevatedGrant> = self.elevated_grants.read().unwrap()
+            .values()
+            .filter(|grant| grant.is_active_at(now))
+            .cloned()
+            .collect();
+        active.sort_by_key(|grant| grant.expires_at);
+        active
+    }
+    
+    /// Revoke an active grant before its expiry
+    pub fn terminate_grant(&self, grant_id: Uuid, terminated_by: &str, reason: &str) -> Result<ElevatedGrant, SecurityError> {
+        let mut grants = self.elevated_grants.write().unwrap();
+        let grant = grants.get_mut(&grant_id)
+            .ok_or_else(|| SecurityError::NotFound {
+                reason: format!("Elevated grant {} not found", grant_id),
+            })?;
+        
+        if !grant.is_active() {
+            return Err(SecurityError::ValidationFailed {
+                reason: format!("Elevated grant {} is no longer active", grant_id),
+            });
+        }
+        
+        grant.terminated_at = Some(Utc::now());
+        grant.terminated_by = Some(terminated_by.to_string());
+        grant.termination_reason = Some(reason.to_string());
+        let terminated = grant.clone();
+        drop(grants);
+        
+        self.clear_cache();
+        log::warn!("Elevated grant {} terminated by {}", grant_id, terminated_by);
+        Ok(terminated)
+    }
+    
+    /// Replace the users assigned to a patient record
+    pub fn set_assignments(&self, patient_id: &str, user_ids: impl IntoIterator<Item = Uuid>) {
+        let user_ids: HashSet<Uuid> = user_ids.into_iter().collect();
+        let mut assignments = self.care_assignments.write().unwrap();
+        if user_ids.is_empty() {
+            assignments.remove(patient_id);
+        } else {
+            assignments.insert(patient_id.to_string(), user_ids);
+        }
+    }
+
+    /// Whether the user is assigned to the patient record
+    pub fn is_assigned(&self, user_id: Uuid, patient_id: &str) -> bool {
+        self.care_assignments.read().unwrap()
+            .get(patient_id)
+            .map_or(false, |users| users.contains(&user_id))
+    }
+
+    /// Open time-boxed emergency `ViewPHI` access to one patient outside normal assignment.
+    /// Only providers and super admins may, with MFA verified and a written justification; every
+    /// grant is audited as a high-risk compliance event that must be reviewed.
+    pub async fn request_break_glass(&self, ctx: PermissionContext, justification: String) -> Result<BreakGlassGrant, SecurityError> {
+        if !matches!(ctx.role, HealthcareRole::HealthcareProvider | HealthcareRole::SuperAdmin) {
+            return Err(SecurityError::AuthorizationDenied {
+                reason: format!("Role {:?} may not use break-glass access", ctx.role),
+            });
+        }
+        if !ctx.mfa_verified {
+            return Err(SecurityError::MfaRequired {
+                reason: "Break-glass access requires multi-factor authentication".to_string(),
+            });
+        }
+        let justification = justification.trim().to_string();
+        if justification.chars().count() < MIN_BREAK_GLASS_JUSTIFICATION_CHARS {
+            return Err(SecurityError::ValidationFailed {
+                reason: format!("Break-glass justification must be at least {} characters", MIN_BREAK_GLASS_JUSTIFICATION_CHARS),
+            });
+        }
+        let patient_id = ctx.resource_id.clone()
+            .or_else(|| ctx.patient_id.map(|id| id.to_string()))
+            .ok_or_else(|| SecurityError::ValidationFailed {
+                reason: "Break-glass access must name the patient".to_string(),
+            })?;
+
+        let now = Utc::now();
+        let grant = ElevatedGrant {
+            id: Uuid::new_v4(),
+            kind: ElevatedGrantKind::BreakGlass,
+            grantee_user_id: ctx.user_id.to_string(),
+            grantor_user_id: None,
+            patient_id: Some(patient_id.clone()),
+            scope: [Permission::ViewPHI].into_iter().collect(),
+            reason: justification.clone(),
+            granted_at: now,
+            expires_at: now + chrono::Duration::minutes(BREAK_GLASS_DURATION_MINUTES),
+            terminated_at: None,
+            terminated_by: None,
+            termination_reason: None,
+        };
+        self.register_elevated_grant(grant.clone())?;
+
+        if let Some(audit) = self.audit_sink() {
+            let mut event = AuditEvent::new(
+                AuditEventType::ComplianceEvent,
+                Some(ctx.user_id),
+                "break_glass_access_granted".to_string(),
+                AuditOutcome::Success,
+            ).mark_high_risk("Break-glass access requires mandatory review")
+            .with_session(ctx.session_id.clone(), ctx.ip_address.clone(), None);
+            event.user_role = Some(ctx.role.clone());
+            event.patient_id = ctx.patient_id;
+            event.resource_type = Some("patient_record".to_string());
+            event.resource_id = Some(patient_id);
+            event.description = format!("Break-glass ViewPHI access until {}", grant.expires_at);
+            event.compliance_tags.push("MANDATORY_REVIEW".to_string());
+            event.metadata.insert("grant_id".to_string(), serde_json::json!(grant.id));
+            event.metadata.insert("justification".to_string(), serde_json::json!(justification));
+            event.metadata.insert("expires_at".to_string(), serde_json::json!(grant.expires_at));
+            audit.log_event(event).await?;
+        }
+        log::warn!("Break-glass grant {} opened by {} for patient record {:?}", grant.id, ctx.user_id, grant.patient_id);
+        Ok(grant)
+    }
+
+    /// Permission check for patient data. Besides the role permission, the caller must be
+    /// assigned to the patient unless they are a super admin. With a break-glass grant the grant
+    /// alone decides: it must belong to the caller, cover the patient and permission, and still
+    /// be in force.
+    /// Granted accesses are audited through `log_phi_access`, with the `action` and
+    /// `justification` given in the context metadata.
+    pub async fn access_patient_data(&self, ctx: PermissionContext, break_glass_grant_id: Option<Uuid>) -> Result<PermissionResult, SecurityError> {
+        let result = self.decide_patient_access(&ctx, break_glass_grant_id).await?;
+        // The client record ID when known, so the access can be traced back to the record
+        let patient = ctx.resource_id.clone().or_else(|| ctx.patient_id.map(|id| id.to_string()));
+        if let (true, Some(audit), Some(patient)) = (result.granted, self.audit_sink(), patient) {
+            log_phi_access(
+                &audit,
+                ctx.user_id,
+                Some(ctx.role.clone()),
+                &patient,
+                ctx.metadata.get("action").map_or("access_patient_data", String::as_str),
+                AuditOutcome::Success,
+                ctx.session_id.clone(),
+                ctx.metadata.get("justification").map(String::as_str),
+            ).await?;
+        }
+        Ok(result)
+    }
+
+    async fn decide_patient_access(&self, ctx: &PermissionContext, break_glass_grant_id: Option<Uuid>) -> Result<PermissionResult, SecurityError> {
+        let ctx = ctx.clone();
+        // An expired or idle session cannot reach patient data; a live one counts this as activity
+        let tracker = self.sessions.read().unwrap().clone();
+        if let Some(tracker) = tracker {
+            // Server time: the context's timestamp comes from the caller
+            tracker.validate(&ctx.session_id, Utc::now())?;
+        }
+        let Some(grant_id) = break_glass_grant_id else {
+            let patient_key = ctx.resource_id.clone().or_else(|| ctx.patient_id.map(|id| id.to_string()));
+            let result = self.check_permission(ctx.clone()).await?;
+            let needs_assignment = result.granted
+                && ctx.role != HealthcareRole::SuperAdmin
+                && ctx.permission.category() == PermissionCategory::PatientData;
+            return match patient_key {
+                Some(patient) if needs_assignment && !self.is_assigned(ctx.user_id, &patient) => {
+                    self.deny_unassigned(&ctx, &patient).await
+                }
+                _ => Ok(result),
+            };
+        };
+        let grant = self.elevated_grants.read().unwrap()
+            .get(&grant_id)
+            .cloned()
+            .ok_or_else(|| SecurityError::NotFound {
+                reason: format!("Break-glass grant {} not found", grant_id),
+            })?;
+        let patient_matches = match grant.patient_id.as_deref() {
+            None => true,
+            Some(patient) => ctx.resource_id.as_deref() == Some(patient)
+                || ctx.patient_id.map_or(false, |id| id.to_string() == patient),
+        };
+        if grant.kind != ElevatedGrantKind::BreakGlass
+            || grant.grantee_user_id != ctx.user_id.to_string()
+            || !patient_matches
+            || !grant.scope.contains(&ctx.permission)
+        {
+            return Err(SecurityError::AccessDenied {
+                reason: format!("Break-glass grant {} does not cover this access", grant_id),
+            });
+        }
+        if let Some(terminated_at) = grant.terminated_at {
+            return Err(SecurityError::SessionExpired {
+                expired_at: terminated_at,
+                reason: format!("Break-glass grant {} was terminated", grant_id),
+            });
+        }
+        // Server time: a caller-supplied timestamp must not revive an expired grant
+        if !grant.is_active_at(Utc::now()) {
+            return Err(SecurityError::SessionExpired {
+                expired_at: grant.expires_at,
+                reason: format!("Break-glass grant {} has expired", grant_id),
+            });
+        }
+
+        log::warn!("Break-glass grant {} used by {} for {:?}", grant_id, ctx.user_id, ctx.permission);
+        Ok(PermissionResult {
+            granted: true,
+            denial_reason: None,
+            mfa_required: true,
+            requirements: vec!["Break-glass access is subject to mandatory review".to_string()],
+            risk_assessment: RiskAssessment {
+                level: 5,
+                factors: vec!["Emergency access outside normal assignment".to_string()],
+                recommendations: vec!["HIPAA audit trail required".to_string()],
+                requires_monitoring: true,
+            },
+        })
+    }
+
+    /// Denial for a caller not assigned to the patient, recorded as a denied PHI view
+    async fn deny_unassigned(&self, ctx: &PermissionContext, patient_id: &str) -> Result<PermissionResult, SecurityError> {
+        if let Some(audit) = self.audit_sink() {
+            let mut event = AuditEvent::new(
+                AuditEventType::PatientDataViewed,
+                Some(ctx.user_id),
+                "access_patient_data".to_string(),
+                AuditOutcome::Denied,
+            ).with_session(ctx.session_id.clone(), ctx.ip_address.clone(), None);
+            event.user_role = Some(ctx.role.clone());
+            event.patient_id = ctx.patient_id;
+            event.resource_type = Some("patient_record".to_string());
+            event.resource_id = Some(patient_id.to_string());
+            event.description = "Caller is not assigned to this patient".to_string();
+            event.metadata.insert("permission".to_string(), serde_json::json!(ctx.permission));
+            audit.log_event(event).await?;
+        }
+        log::warn!("Denied {:?} on patient record {} to unassigned user {}", ctx.permission, patient_id, ctx.user_id);
+
+        Ok(PermissionResult {
+            granted: false,
+            denial_reason: Some("Not assigned to this patient".to_string()),
+            mfa_required: false,
+            requirements: vec!["Assignment to the patient or break-glass access".to_string()],
+            risk_assessment: RiskAssessment {
+                level: 4,
+                factors: vec!["Access to a patient outside the caller's caseload".to_string()],
+                recommendations: vec!["HIPAA audit trail required".to_string()],
+                requires_monitoring: true,
+            },
+        })
+    }
+
+    /// Drop lapsed and terminated grants from memory
+    pub fn purge_inactive_grants(&self) -> usize {
+        let now = Utc::now();
+        let mut grants = self.elevated_grants.write().unwrap();
+        let before = grants.len();
+        grants.retain(|_, grant| grant.is_active_at(now));
+        before - grants.len()
+    }
 }
 
 /// Initialize RBAC system
@@ -875,4 +1306,141 @@ mod tests {
         let denied_result = rbac_service.check_permission(denied_context).await.unwrap();
         assert!(!denied_result.granted);
     }
+    
+    fn test_grant(kind: ElevatedGrantKind, expires_in: chrono::Duration) -> ElevatedGrant {
+        let now = Utc::now();
+        ElevatedGrant {
+            id: Uuid::new_v4(),
+            kind,
+            grantee_user_id: "provider-1".to_string(),
+            grantor_user_id: None,
+            patient_id: Some("patient-1".to_string()),
+            scope: [Permission::ViewPHI].into_iter().collect(),
+            reason: "Emergency consult".to_string(),
+            granted_at: now - chrono::Duration::minutes(5),
+            expires_at: now + expires_in,
+            terminated_at: None,
+            terminated_by: None,
+            termination_reason: None,
+        }
+    }
+    
+    #[test]
+    fn test_expired_grants_never_listed_as_active() {
+        let rbac_service = RbacService::new();
+        
+        let active = test_grant(ElevatedGrantKind::BreakGlass, chrono::Duration::minutes(30));
+        let mut expired = test_grant(ElevatedGrantKind::Delegation, chrono::Duration::minutes(30));
+        expired.expires_at = Utc::now() - chrono::Duration::seconds(1);
+        
+        let active_id = rbac_service.register_elevated_grant(active).unwrap();
+        // Expired grants are inserted directly since registration rejects them
+        rbac_service.elevated_grants.write().unwrap().insert(expired.id, expired);
+        
+        let listed = rbac_service.list_active_elevated_grants();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, active_id);
+        assert_eq!(rbac_service.purge_inactive_grants(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_break_glass_is_time_boxed() {
+        let rbac_service = RbacService::new();
+        let ctx = PermissionContext {
+            user_id: Uuid::new_v4(),
+            role: HealthcareRole::HealthcareProvider,
+            permission: Permission::ViewPHI,
+            resource_id: Some("client-42".to_string()),
+            patient_id: None,
+            ip_address: None,
+            timestamp: Utc::now(),
+            session_id: Uuid::new_v4().to_string(),
+            mfa_verified: true,
+            metadata: HashMap::new(),
+        };
+        let justification = "Patient in crisis at the ER, treating clinician unreachable".to_string();
+
+        let staff = PermissionContext { role: HealthcareRole::AdministrativeStaff, ..ctx.clone() };
+        assert!(rbac_service.request_break_glass(staff, justification.clone()).await.is_err());
+        let no_mfa = PermissionContext { mfa_verified: false, ..ctx.clone() };
+        assert!(rbac_service.request_break_glass(no_mfa, justification.clone()).await.is_err());
+        assert!(rbac_service.request_break_glass(ctx.clone(), "Emergency".to_string()).await.is_err());
+
+        let grant = rbac_service.request_break_glass(ctx.clone(), justification).await.unwrap();
+        assert_eq!(grant.expires_at - grant.granted_at, chrono::Duration::minutes(BREAK_GLASS_DURATION_MINUTES));
+        assert!(rbac_service.access_patient_data(ctx.clone(), Some(grant.id)).await.unwrap().granted);
+        let other_patient = PermissionContext { resource_id: Some("client-7".to_string()), ..ctx.clone() };
+        assert!(rbac_service.access_patient_data(other_patient, Some(grant.id)).await.is_err());
+
+        // Once the hour is up the same grant is refused as expired, whatever time the caller claims
+        rbac_service.elevated_grants.write().unwrap().get_mut(&grant.id).unwrap().expires_at = Utc::now();
+        let backdated = PermissionContext { timestamp: grant.granted_at, ..ctx };
+        assert!(matches!(
+            rbac_service.access_patient_data(backdated, Some(grant.id)).await,
+            Err(SecurityError::SessionExpired { .. })
+        ));
+    }
+
+    fn patient_context(role: HealthcareRole) -> PermissionContext {
+        PermissionContext {
+            user_id: Uuid::new_v4(),
+            role,
+            permission: Permission::ViewPHI,
+            resource_id: Some("client-42".to_string()),
+            patient_id: None,
+            ip_address: None,
+            timestamp: Utc::now(),
+            session_id: Uuid::new_v4().to_string(),
+            mfa_verified: true,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assigned_provider_may_view_patient() {
+        let rbac_service = RbacService::new();
+        let ctx = patient_context(HealthcareRole::HealthcareProvider);
+        rbac_service.set_assignments("client-42", [ctx.user_id]);
+        assert!(rbac_service.access_patient_data(ctx, None).await.unwrap().granted);
+    }
+
+    #[tokio::test]
+    async fn test_unassigned_provider_is_denied() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log_path = temp_dir.path().join("rbac_audit.log");
+        let mut config = crate::security::audit::AuditConfig::default();
+        config.log_file_path = Some(log_path.clone());
+        config.enable_real_time_alerts = false;
+        let audit = Arc::new(AuditService::new(config).unwrap());
+        let rbac_service = RbacService::new().with_audit_service(audit.clone());
+        rbac_service.set_assignments("client-42", [Uuid::new_v4()]);
+
+        let result = rbac_service.access_patient_data(patient_context(HealthcareRole::HealthcareProvider), None).await.unwrap();
+        assert!(!result.granted);
+        // Denials are written at once
+        let events = crate::security::audit::read_audit_entries(&log_path).unwrap();
+        assert!(events.iter().any(|e| e.event_type == AuditEventType::PatientDataViewed && e.outcome == AuditOutcome::Denied));
+    }
+
+    #[tokio::test]
+    async fn test_super_admin_needs_no_assignment() {
+        let rbac_service = RbacService::new();
+        let result = rbac_service.access_patient_data(patient_context(HealthcareRole::SuperAdmin), None).await.unwrap();
+        assert!(result.granted);
+    }
+
+    #[test]
+    fn test_terminate_grant() {
+        let rbac_service = RbacService::new();
+        let grant_id = rbac_service
+            .register_elevated_grant(test_grant(ElevatedGrantKind::BreakGlass, chrono::Duration::hours(1)))
+            .unwrap();
+        
+        let terminated = rbac_service.terminate_grant(grant_id, "security-officer", "Not an emergency").unwrap();
+        assert!(terminated.terminated_at.is_some());
+        assert!(rbac_service.list_active_elevated_grants().is_empty());
+        
+        // Terminating twice is rejected
+        assert!(rbac_service.terminate_grant(grant_id, "security-officer", "again").is_err());
+    }
 }
\ No newline at end of file
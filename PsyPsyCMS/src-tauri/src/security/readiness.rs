@@ -0,0 +1,193 @@
+// Production Readiness Check for PsyPsy CMS
+// Several settings ship with development defaults (a placeholder JWT secret, demo Firebase
+// credentials, no geographic or VPN restrictions). With HIPAA/Quebec compliance mode on, startup
+// refuses to continue while any of them is still in effect and names each setting to change.
+// Local work can bypass the check with PSYPSY_ALLOW_INSECURE_DEFAULTS, which debug builds only
+// honour: in release builds the override is not compiled in at all.
+
+use crate::security::effective_config::EffectiveSecurityConfig;
+use serde::{Deserialize, Serialize};
+
+/// Placeholder JWT secret used when `JWT_SECRET` is not set
+pub const DEV_JWT_SECRET: &str = "default-dev-secret-change-in-production";
+/// Placeholder Firebase API key used when `FIREBASE_API_KEY` is not set
+pub const DEMO_FIREBASE_API_KEY: &str = "demo-api-key";
+/// Development project used when `FIREBASE_PROJECT_ID` is not set
+pub const DEV_FIREBASE_PROJECT: &str = "psypsy-cms-dev";
+
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// A setting still at an insecure default
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InsecureSetting {
+    /// Environment variable or configuration path to change
+    pub setting: String,
+    pub reason: String,
+}
+
+/// Deployment values the check looks at besides the security configuration
+#[derive(Debug, Clone, Default)]
+pub struct DeploymentSettings {
+    pub firebase_api_key: Option<String>,
+    pub firebase_project_id: Option<String>,
+}
+
+impl DeploymentSettings {
+    pub fn from_env() -> Self {
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        Self {
+            firebase_api_key: non_empty("FIREBASE_API_KEY"),
+            firebase_project_id: non_empty("FIREBASE_PROJECT_ID"),
+        }
+    }
+}
+
+/// Compliance mode is on unless `HIPAA_COMPLIANCE_ENABLED` or `QUEBEC_COMPLIANCE_MODE` turns it off
+pub fn compliance_mode_enabled() -> bool {
+    ["HIPAA_COMPLIANCE_ENABLED", "QUEBEC_COMPLIANCE_MODE"].iter().all(|name| {
+        std::env::var(name)
+            .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no" | "off"))
+            .unwrap_or(true)
+    })
+}
+
+/// Every known-insecure default still in effect
+pub fn insecure_defaults(config: &EffectiveSecurityConfig, deployment: &DeploymentSettings) -> Vec<InsecureSetting> {
+    let mut findings = Vec::new();
+    let mut flag = |setting: &str, reason: &str| {
+        findings.push(InsecureSetting { setting: setting.to_string(), reason: reason.to_string() });
+    };
+
+    let jwt_secret = config.security.jwt_secret.as_str();
+    if jwt_secret == DEV_JWT_SECRET {
+        flag("JWT_SECRET", "still the development placeholder secret");
+    } else if jwt_secret.len() < MIN_JWT_SECRET_LEN {
+        flag("JWT_SECRET", &format!("shorter than {} characters", MIN_JWT_SECRET_LEN));
+    }
+
+    match deployment.firebase_api_key.as_deref() {
+        None => flag("FIREBASE_API_KEY", "not set; the demo API key would be used"),
+        Some(DEMO_FIREBASE_API_KEY) => flag("FIREBASE_API_KEY", "still the demo API key"),
+        Some(_) => {}
+    }
+    match deployment.firebase_project_id.as_deref() {
+        None => flag("FIREBASE_PROJECT_ID", "not set; the development project would be used"),
+        Some(DEV_FIREBASE_PROJECT) => flag("FIREBASE_PROJECT_ID", "still the development project"),
+        Some(_) => {}
+    }
+
+    match &config.rate_limits.ip_limits.geographic_restrictions {
+        None => flag("GEO_ALLOWED_COUNTRIES", "no geographic restrictions configured"),
+        Some(geo) => {
+            if geo.allowed_countries.is_empty() {
+                flag("GEO_ALLOWED_COUNTRIES", "empty, so access is allowed from every country");
+            }
+            if !geo.block_vpn_proxy {
+                flag("GEO_BLOCK_VPN_PROXY", "VPN and proxy connections are not blocked");
+            }
+        }
+    }
+
+    findings
+}
+
+/// Whether the developer override is in force; always false in release builds
+fn dev_override_requested() -> bool {
+    #[cfg(debug_assertions)]
+    {
+        std::env::var("PSYPSY_ALLOW_INSECURE_DEFAULTS")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+    }
+    #[cfg(not(debug_assertions))]
+    {
+        false
+    }
+}
+
+/// Decide whether startup may continue given the findings
+fn evaluate(findings: &[InsecureSetting], compliance_mode: bool, dev_override: bool) -> Result<(), String> {
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let listing: Vec<String> = findings.iter().map(|f| format!("  - {}: {}", f.setting, f.reason)).collect();
+    if !compliance_mode {
+        log::warn!("Compliance mode is off; insecure defaults in effect:\n{}", listing.join("\n"));
+        return Ok(());
+    }
+    if dev_override {
+        log::warn!(
+            "PSYPSY_ALLOW_INSECURE_DEFAULTS is set; starting with insecure defaults (never use this for a clinic):\n{}",
+            listing.join("\n")
+        );
+        return Ok(());
+    }
+    Err(format!(
+        "Refusing to start: compliance mode is on but insecure defaults are still in effect. Change these settings:\n{}",
+        listing.join("\n")
+    ))
+}
+
+/// Startup gate: an error lists every setting that must change before the app may start
+pub fn enforce_production_readiness(config: &EffectiveSecurityConfig, deployment: &DeploymentSettings) -> Result<(), String> {
+    evaluate(&insecure_defaults(config, deployment), compliance_mode_enabled(), dev_override_requested())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::compliance::ComplianceConfig;
+    use crate::security::outbound::OutboundPolicy;
+    use crate::security::rate_limit::RateLimitConfig;
+    use crate::security::SecurityConfig;
+
+    fn default_config() -> EffectiveSecurityConfig {
+        EffectiveSecurityConfig {
+            security: SecurityConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            compliance: ComplianceConfig::default(),
+            outbound: OutboundPolicy::default(),
+        }
+    }
+
+    fn hardened() -> (EffectiveSecurityConfig, DeploymentSettings) {
+        let mut config = default_config();
+        config.security.jwt_secret = "k".repeat(48);
+        let geo = config.rate_limits.ip_limits.geographic_restrictions.as_mut().unwrap();
+        geo.allowed_countries = vec!["CA".to_string()];
+        geo.block_vpn_proxy = true;
+        let deployment = DeploymentSettings {
+            firebase_api_key: Some("AIza-production-key".to_string()),
+            firebase_project_id: Some("psypsy-clinic-prod".to_string()),
+        };
+        (config, deployment)
+    }
+
+    #[test]
+    fn test_shipped_defaults_are_all_reported() {
+        let findings = insecure_defaults(&default_config(), &DeploymentSettings::default());
+        let settings: Vec<&str> = findings.iter().map(|f| f.setting.as_str()).collect();
+        assert_eq!(
+            settings,
+            vec!["JWT_SECRET", "FIREBASE_API_KEY", "FIREBASE_PROJECT_ID", "GEO_ALLOWED_COUNTRIES", "GEO_BLOCK_VPN_PROXY"]
+        );
+
+        let (config, deployment) = hardened();
+        assert!(insecure_defaults(&config, &deployment).is_empty());
+    }
+
+    #[test]
+    fn test_compliance_mode_refuses_to_start_unless_overridden() {
+        let (mut config, deployment) = hardened();
+        config.security.jwt_secret = DEV_JWT_SECRET.to_string();
+        let findings = insecure_defaults(&config, &deployment);
+
+        let err = evaluate(&findings, true, false).unwrap_err();
+        assert!(err.contains("JWT_SECRET"));
+        assert!(!err.contains("FIREBASE_API_KEY"));
+        assert!(evaluate(&findings, true, true).is_ok());
+        assert!(evaluate(&findings, false, false).is_ok());
+        assert!(evaluate(&[], true, false).is_ok());
+    }
+}
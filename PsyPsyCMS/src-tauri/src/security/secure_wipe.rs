@@ -0,0 +1,200 @@
+// Secure Wipe for PsyPsy CMS
+// Temporary audio, image and transcript files may hold PHI, and an ordinary delete leaves their
+// bytes on disk. Files are overwritten before they are unlinked; because SSD wear levelling can
+// keep the old blocks anyway, temp files are preferably written encrypted under an ephemeral key
+// that only ever lives in memory, so discarding the key is enough to make the bytes unreadable.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use zeroize::{Zeroize, Zeroizing};
+
+const NONCE_LEN: usize = 12;
+
+/// How temporary files are protected while they exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TempFileProtection {
+    /// Plaintext on disk, overwritten before unlinking
+    Overwrite,
+    /// Encrypted under a per-file key held only in memory; also overwritten on removal
+    EphemeralKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureWipeConfig {
+    pub temp_file_protection: TempFileProtection,
+    /// Random overwrite passes before a file is unlinked
+    pub overwrite_passes: u32,
+}
+
+impl Default for SecureWipeConfig {
+    fn default() -> Self {
+        Self {
+            temp_file_protection: TempFileProtection::EphemeralKey,
+            overwrite_passes: 1,
+        }
+    }
+}
+
+impl SecureWipeConfig {
+    /// Defaults with `SECURE_WIPE_TEMP_FILES` (`overwrite` or `ephemeral_key`) and
+    /// `SECURE_WIPE_PASSES` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("SECURE_WIPE_TEMP_FILES") {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "overwrite" => config.temp_file_protection = TempFileProtection::Overwrite,
+                "ephemeral_key" => config.temp_file_protection = TempFileProtection::EphemeralKey,
+                _ => log::warn!("Ignoring invalid SECURE_WIPE_TEMP_FILES '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("SECURE_WIPE_PASSES") {
+            match value.trim().parse::<u32>() {
+                Ok(passes) if (1..=7).contains(&passes) => config.overwrite_passes = passes,
+                _ => log::warn!("Ignoring invalid SECURE_WIPE_PASSES '{}'", value),
+            }
+        }
+        config
+    }
+}
+
+/// Overwrite a file with random bytes `passes` times, then unlink it. A file that is already
+/// gone counts as wiped.
+pub fn wipe_file(path: &Path, passes: u32) -> std::io::Result<()> {
+    let len = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let mut chunk = vec![0u8; 64 * 1024];
+    for _ in 0..passes.max(1) {
+        file.seek(SeekFrom::Start(0))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = remaining.min(chunk.len() as u64) as usize;
+            rand::thread_rng().fill_bytes(&mut chunk[..n]);
+            file.write_all(&chunk[..n])?;
+            remaining -= n as u64;
+        }
+        file.sync_all()?;
+    }
+    drop(file);
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// A temporary file that is wiped when dropped. Under `EphemeralKey` the contents are
+/// AES-256-GCM encrypted with a key that is zeroized with the handle.
+pub struct SecureTempFile {
+    path: PathBuf,
+    key: Option<Zeroizing<[u8; 32]>>,
+    passes: u32,
+}
+
+impl SecureTempFile {
+    /// Write `contents` to a new file in `dir`
+    pub fn create(dir: &Path, extension: &str, contents: &[u8], config: &SecureWipeConfig) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.{}", Uuid::new_v4(), extension));
+        let key = match config.temp_file_protection {
+            TempFileProtection::Overwrite => None,
+            TempFileProtection::EphemeralKey => {
+                let mut key = Zeroizing::new([0u8; 32]);
+                OsRng.fill_bytes(key.as_mut());
+                Some(key)
+            }
+        };
+        let temp = Self { path, key, passes: config.overwrite_passes };
+        let bytes = match &temp.key {
+            None => contents.to_vec(),
+            Some(key) => {
+                let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+                let mut nonce = [0u8; NONCE_LEN];
+                OsRng.fill_bytes(&mut nonce);
+                let mut sealed = nonce.to_vec();
+                sealed.extend(cipher.encrypt(Nonce::from_slice(&nonce), contents).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::Other, "Failed to encrypt temporary file")
+                })?);
+                sealed
+            }
+        };
+        std::fs::write(&temp.path, bytes)?;
+        Ok(temp)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Contents as written; the plaintext is zeroized when the returned buffer is dropped
+    pub fn read(&self) -> std::io::Result<Zeroizing<Vec<u8>>> {
+        let bytes = Zeroizing::new(std::fs::read(&self.path)?);
+        match &self.key {
+            None => Ok(bytes),
+            Some(key) => {
+                if bytes.len() < NONCE_LEN {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Temporary file truncated"));
+                }
+                let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+                cipher
+                    .decrypt(Nonce::from_slice(&bytes[..NONCE_LEN]), &bytes[NONCE_LEN..])
+                    .map(Zeroizing::new)
+                    .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "Temporary file failed authentication"))
+            }
+        }
+    }
+}
+
+impl Drop for SecureTempFile {
+    fn drop(&mut self) {
+        if let Err(e) = wipe_file(&self.path, self.passes) {
+            log::error!("Failed to wipe temporary file {}: {}", self.path.display(), e);
+        }
+        // `Zeroizing` clears the key as it drops
+    }
+}
+
+/// Zero a sample or byte buffer in place, including its spare capacity, and leave it empty
+pub fn wipe_buffer<T: Zeroize>(buffer: &mut Vec<T>) {
+    buffer.zeroize();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wipe_file_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.wav");
+        std::fs::write(&path, b"captured audio").unwrap();
+
+        wipe_file(&path, 2).unwrap();
+        assert!(!path.exists());
+        wipe_file(&path, 2).unwrap();
+    }
+
+    #[test]
+    fn test_ephemeral_temp_file_is_encrypted_and_removed_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let temp = SecureTempFile::create(dir.path(), "png", b"chart for patient 42", &SecureWipeConfig::default()).unwrap();
+        let on_disk = std::fs::read(temp.path()).unwrap();
+        assert!(!on_disk.windows(7).any(|w| w == b"patient"));
+        assert_eq!(temp.read().unwrap().as_slice(), b"chart for patient 42");
+
+        let path = temp.path().to_path_buf();
+        drop(temp);
+        assert!(!path.exists());
+    }
+}
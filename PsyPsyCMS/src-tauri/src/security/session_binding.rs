@@ -0,0 +1,376 @@
+// Session Binding for PsyPsy CMS
+// Ties a session to the device and network it was created on, so a copied token or stored
+// session replayed elsewhere is flagged or refused. The device identity is keyed by a secret
+// kept in the app data directory; the user agent plays no part, so spoofing it gains nothing.
+
+use crate::security::step_up::StepUpState;
+use crate::security::SecurityError;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::{IpAddr, UdpSocket};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// What happens when a session is used from a different context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingMode {
+    Off,
+    /// Record an intrusion attempt but let the request through
+    Monitor,
+    /// Record an intrusion attempt and refuse the request
+    Enforce,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBindingConfig {
+    pub mode: BindingMode,
+    /// Prefix compared for IPv4 addresses; /24 tolerates DHCP churn on one network
+    pub ipv4_prefix_len: u8,
+    pub ipv6_prefix_len: u8,
+    /// Additional networks a session may move to before a change counts as suspicious
+    /// (laptops moving between clinic Wi-Fi and tethering)
+    pub roaming_networks: usize,
+}
+
+impl Default for SessionBindingConfig {
+    fn default() -> Self {
+        Self {
+            mode: BindingMode::Monitor,
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 56,
+            roaming_networks: 2,
+        }
+    }
+}
+
+impl SessionBindingConfig {
+    /// Defaults with `SESSION_BINDING_MODE` (off, monitor, enforce), `SESSION_BINDING_IPV4_PREFIX`,
+    /// `SESSION_BINDING_IPV6_PREFIX` and `SESSION_BINDING_ROAMING_NETWORKS` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(mode) = std::env::var("SESSION_BINDING_MODE") {
+            match mode.trim().to_ascii_lowercase().as_str() {
+                "off" => config.mode = BindingMode::Off,
+                "monitor" => config.mode = BindingMode::Monitor,
+                "enforce" => config.mode = BindingMode::Enforce,
+                other => log::warn!("Ignoring unknown SESSION_BINDING_MODE '{}'", other),
+            }
+        }
+        let parse = |name: &str, max: u8, target: &mut u8| {
+            if let Ok(value) = std::env::var(name) {
+                match value.trim().parse::<u8>() {
+                    Ok(v) if v <= max => *target = v,
+                    _ => log::warn!("Ignoring invalid {} '{}'", name, value),
+                }
+            }
+        };
+        parse("SESSION_BINDING_IPV4_PREFIX", 32, &mut config.ipv4_prefix_len);
+        parse("SESSION_BINDING_IPV6_PREFIX", 128, &mut config.ipv6_prefix_len);
+        if let Ok(value) = std::env::var("SESSION_BINDING_ROAMING_NETWORKS") {
+            match value.trim().parse() {
+                Ok(n) => config.roaming_networks = n,
+                Err(_) => log::warn!("Ignoring invalid SESSION_BINDING_ROAMING_NETWORKS '{}'", value),
+            }
+        }
+        config
+    }
+
+    /// Network an address belongs to, as `address/prefix`
+    pub fn network_of(&self, ip: IpAddr) -> String {
+        match ip {
+            IpAddr::V4(v4) => {
+                let prefix = self.ipv4_prefix_len.min(32);
+                let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - u32::from(prefix)) };
+                format!("{}/{}", std::net::Ipv4Addr::from(u32::from(v4) & mask), prefix)
+            }
+            IpAddr::V6(v6) => {
+                let prefix = self.ipv6_prefix_len.min(128);
+                let mask = if prefix == 0 { 0 } else { u128::MAX << (128 - u32::from(prefix)) };
+                format!("{}/{}", std::net::Ipv6Addr::from(u128::from(v6) & mask), prefix)
+            }
+        }
+    }
+}
+
+/// Where a request is coming from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingContext {
+    /// Keyed hash of the installation and machine identity
+    pub device_hash: String,
+    /// Address the host would use for outbound traffic; `None` when offline
+    pub ip: Option<IpAddr>,
+}
+
+/// Context recorded when the session was created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBinding {
+    pub device_hash: String,
+    /// Networks seen so far, the originating one first
+    pub networks: Vec<String>,
+}
+
+/// Outcome of comparing a request with the binding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingVerdict {
+    Match,
+    /// Moved to a new network within the roaming tolerance
+    Roamed { network: String },
+    Violation { reasons: Vec<String> },
+}
+
+impl SessionBinding {
+    pub fn capture(config: &SessionBindingConfig, context: &BindingContext) -> Self {
+        Self {
+            device_hash: context.device_hash.clone(),
+            networks: context.ip.map(|ip| config.network_of(ip)).into_iter().collect(),
+        }
+    }
+
+    /// Compare a request's context with the binding, remembering networks the session
+    /// roams to within tolerance
+    pub fn evaluate(&mut self, config: &SessionBindingConfig, context: &BindingContext) -> BindingVerdict {
+        let mut reasons = Vec::new();
+        if !constant_time_eq(self.device_hash.as_bytes(), context.device_hash.as_bytes()) {
+            reasons.push("session used from a different device".to_string());
+        }
+        let mut roamed = None;
+        if let Some(ip) = context.ip {
+            let network = config.network_of(ip);
+            if self.networks.is_empty() {
+                // Bound while offline; the first network seen becomes the origin
+                self.networks.push(network);
+            } else if !self.networks.contains(&network) {
+                if self.networks.len() <= config.roaming_networks {
+                    roamed = Some(network);
+                } else {
+                    reasons.push(format!("session used from network {} after {} networks", network, self.networks.len()));
+                }
+            }
+        }
+        match (reasons.is_empty(), roamed) {
+            (false, _) => BindingVerdict::Violation { reasons },
+            (true, Some(network)) => {
+                self.networks.push(network.clone());
+                BindingVerdict::Roamed { network }
+            }
+            (true, None) => BindingVerdict::Match,
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+const DEVICE_KEY_FILE: &str = "device_binding.key";
+
+/// Secret generated on first use and kept with the app data; it never leaves the machine,
+/// so the device hash cannot be recomputed elsewhere from public machine attributes
+fn install_secret(app_data_dir: &Path) -> std::io::Result<Vec<u8>> {
+    let path = app_data_dir.join(DEVICE_KEY_FILE);
+    match std::fs::read(&path) {
+        Ok(secret) if secret.len() == 32 => Ok(secret),
+        _ => {
+            use ring::rand::SecureRandom;
+            let mut secret = vec![0u8; 32];
+            ring::rand::SystemRandom::new()
+                .fill(&mut secret)
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "random source unavailable"))?;
+            std::fs::create_dir_all(app_data_dir)?;
+            std::fs::write(&path, &secret)?;
+            Ok(secret)
+        }
+    }
+}
+
+/// Stable identifier of the host: the OS machine ID where one exists, else the host name
+fn machine_identifier() -> String {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()))
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_default()
+}
+
+/// Keyed hash identifying this installation on this machine
+pub fn device_hash(app_data_dir: &Path) -> std::io::Result<String> {
+    let secret = install_secret(app_data_dir)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(&secret).expect("HMAC accepts any key length");
+    for part in [machine_identifier().as_str(), std::env::consts::OS, std::env::consts::ARCH] {
+        mac.update(part.as_bytes());
+        mac.update(&[0]);
+    }
+    Ok(mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Local address used for outbound traffic. Connecting a UDP socket sends nothing; it only
+/// asks the OS which interface would carry the traffic.
+pub fn outbound_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:9").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip()).filter(|ip| !ip.is_unspecified())
+}
+
+/// Binding of the signed-in session
+#[derive(Default)]
+pub struct SessionBindingState {
+    binding: Mutex<Option<SessionBinding>>,
+    device_hash: OnceLock<String>,
+}
+
+impl SessionBindingState {
+    /// Context of the current request
+    pub fn current_context(&self, app_data_dir: &Path) -> Option<BindingContext> {
+        let device_hash = match self.device_hash.get() {
+            Some(hash) => hash.clone(),
+            None => match device_hash(app_data_dir) {
+                Ok(hash) => self.device_hash.get_or_init(|| hash).clone(),
+                Err(e) => {
+                    log::error!("Failed to derive device identity for session binding: {}", e);
+                    return None;
+                }
+            },
+        };
+        Some(BindingContext { device_hash, ip: outbound_ip() })
+    }
+
+    pub fn bind(&self, binding: SessionBinding) {
+        *self.binding.lock().unwrap() = Some(binding);
+    }
+
+    pub fn clear(&self) {
+        *self.binding.lock().unwrap() = None;
+    }
+
+    pub fn current(&self) -> Option<SessionBinding> {
+        self.binding.lock().unwrap().clone()
+    }
+
+    /// Evaluate the bound session against a request; `None` when nothing is bound
+    pub fn evaluate(&self, config: &SessionBindingConfig, context: &BindingContext) -> Option<BindingVerdict> {
+        self.binding.lock().unwrap().as_mut().map(|binding| binding.evaluate(config, context))
+    }
+}
+
+/// Check the signed-in session against its binding before PHI is touched. Violations are
+/// logged as intrusion attempts and audited; they are refused only in enforce mode.
+pub fn check_phi_access<R: Runtime>(app: &AppHandle<R>, user_id: &str, command: &str) -> Result<(), SecurityError> {
+    let (Some(config), Some(state)) = (app.try_state::<SessionBindingConfig>(), app.try_state::<SessionBindingState>()) else {
+        return Ok(());
+    };
+    if config.mode == BindingMode::Off {
+        return Ok(());
+    }
+    let Ok(app_data_dir) = app.path().app_data_dir() else {
+        return Ok(());
+    };
+    let Some(context) = state.current_context(&app_data_dir) else {
+        return Ok(());
+    };
+
+    match state.evaluate(&config, &context) {
+        None | Some(BindingVerdict::Match) => Ok(()),
+        Some(BindingVerdict::Roamed { network }) => {
+            log::info!("Session for user {} roamed to network {}", user_id, network);
+            raise_step_up(app, user_id, &format!("session moved to network {}", network));
+            Ok(())
+        }
+        Some(BindingVerdict::Violation { reasons }) => {
+            let enforced = config.mode == BindingMode::Enforce;
+            log::error!(
+                "IntrusionAttempt: session binding violated for user {} on '{}': {}",
+                user_id, command, reasons.join("; ")
+            );
+            record_violation(app, user_id, command, &reasons, enforced);
+            if enforced {
+                Err(SecurityError::AuthenticationFailed {
+                    reason: "Session is bound to another device or network; sign in again".to_string(),
+                })
+            } else {
+                raise_step_up(app, user_id, "session used outside its bound context");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A context change the session is allowed to survive still costs a fresh MFA challenge
+fn raise_step_up<R: Runtime>(app: &AppHandle<R>, user_id: &str, reason: &str) {
+    if let Some(step_up) = app.try_state::<StepUpState>() {
+        if step_up.rules.new_network {
+            step_up.raise(user_id, reason);
+        }
+    }
+}
+
+/// Audit a binding violation without blocking the caller
+pub fn record_violation<R: Runtime>(app: &AppHandle<R>, user_id: &str, context: &str, reasons: &[String], enforced: bool) {
+    let app = app.clone();
+    let (user_id, context, reasons) = (user_id.to_string(), context.to_string(), reasons.to_vec());
+    tauri::async_runtime::spawn(async move {
+        let firebase = app.state::<FirebaseServiceState>();
+        let firebase_guard = firebase.0.lock().await;
+        if let Some(firebase) = firebase_guard.as_ref() {
+            let _ = firebase.audit_log(
+                "SESSION_BINDING_VIOLATION",
+                "session",
+                &user_id,
+                false,
+                Some(serde_json::json!({
+                    "event_type": "IntrusionAttempt",
+                    "context": context,
+                    "reasons": reasons,
+                    "enforced": enforced,
+                }))
+            ).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(device: &str, ip: &str) -> BindingContext {
+        BindingContext { device_hash: device.to_string(), ip: Some(ip.parse().unwrap()) }
+    }
+
+    #[test]
+    fn test_roaming_tolerance_then_violation() {
+        let config = SessionBindingConfig { roaming_networks: 1, ..SessionBindingConfig::default() };
+        let mut binding = SessionBinding::capture(&config, &context("device-a", "10.1.2.3"));
+
+        // DHCP renewal inside the same /24 is the same network
+        assert_eq!(binding.evaluate(&config, &context("device-a", "10.1.2.200")), BindingVerdict::Match);
+        assert_eq!(
+            binding.evaluate(&config, &context("device-a", "172.20.5.9")),
+            BindingVerdict::Roamed { network: "172.20.5.0/24".to_string() }
+        );
+        // Returning to a known network is fine; a third one exceeds the tolerance
+        assert_eq!(binding.evaluate(&config, &context("device-a", "10.1.2.7")), BindingVerdict::Match);
+        assert!(matches!(binding.evaluate(&config, &context("device-a", "203.0.113.4")), BindingVerdict::Violation { .. }));
+    }
+
+    #[test]
+    fn test_other_device_is_a_violation_on_the_same_network() {
+        let config = SessionBindingConfig::default();
+        let mut binding = SessionBinding::capture(&config, &context("device-a", "10.1.2.3"));
+        match binding.evaluate(&config, &context("device-b", "10.1.2.3")) {
+            BindingVerdict::Violation { reasons } => assert_eq!(reasons.len(), 1),
+            other => panic!("expected violation, got {:?}", other),
+        }
+
+        // The device hash depends on a per-install secret, not only on public machine facts
+        let (first, second) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        assert_eq!(device_hash(first.path()).unwrap(), device_hash(first.path()).unwrap());
+        assert_ne!(device_hash(first.path()).unwrap(), device_hash(second.path()).unwrap());
+    }
+}
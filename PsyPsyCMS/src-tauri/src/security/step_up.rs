@@ -0,0 +1,204 @@
+// Adaptive MFA Step-up for PsyPsy CMS
+// Routine access needs no repeated MFA; configured risk signals (sensitive commands, flagged
+// patients, a session that moved to a new network) demand a fresh MFA challenge mid-session.
+// Until `complete_mfa_stepup` succeeds the protected action stays refused, so an abandoned or
+// failed challenge never falls through to access.
+
+use crate::security::auth::AuthState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// Prefix of the error a command returns when it needs step-up; the frontend starts an MFA
+/// challenge when it sees it and retries after `complete_mfa_stepup`
+pub const STEP_UP_SIGNAL: &str = "require_mfa_stepup";
+
+/// Which actions and records trigger step-up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepUpRules {
+    /// How long a completed step-up covers further risky actions
+    pub window_minutes: i64,
+    /// Commands that always need a recent step-up
+    pub commands: HashSet<String>,
+    /// Patients flagged in the access-justification policy need step-up as well as a reason
+    pub flagged_patients: bool,
+    /// A session seen on a new network must step up before its next PHI access
+    pub new_network: bool,
+}
+
+impl Default for StepUpRules {
+    fn default() -> Self {
+        Self {
+            window_minutes: 10,
+            commands: ["request_data_export", "run_approved_export"].iter().map(|c| c.to_string()).collect(),
+            flagged_patients: true,
+            new_network: true,
+        }
+    }
+}
+
+impl StepUpRules {
+    /// Defaults with `STEP_UP_WINDOW_MINUTES`, `STEP_UP_COMMANDS` (comma-separated, replaces the
+    /// defaults), `STEP_UP_FLAGGED_PATIENTS` and `STEP_UP_NEW_NETWORK` overrides
+    pub fn from_env() -> Self {
+        let mut rules = Self::default();
+        if let Ok(value) = std::env::var("STEP_UP_WINDOW_MINUTES") {
+            match value.trim().parse::<i64>() {
+                Ok(minutes) if minutes > 0 => rules.window_minutes = minutes,
+                _ => log::warn!("Ignoring invalid STEP_UP_WINDOW_MINUTES '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("STEP_UP_COMMANDS") {
+            rules.commands = value.split(',').map(str::trim).filter(|c| !c.is_empty()).map(str::to_string).collect();
+        }
+        let flag = |name: &str, target: &mut bool| {
+            if let Ok(value) = std::env::var(name) {
+                match value.trim().to_ascii_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => *target = true,
+                    "0" | "false" | "no" | "off" => *target = false,
+                    _ => log::warn!("Ignoring invalid {} '{}'", name, value),
+                }
+            }
+        };
+        flag("STEP_UP_FLAGGED_PATIENTS", &mut rules.flagged_patients);
+        flag("STEP_UP_NEW_NETWORK", &mut rules.new_network);
+        rules
+    }
+}
+
+/// A risk signal raised for the signed-in user that has not been answered yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaisedRisk {
+    pub user_id: String,
+    pub reason: String,
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Step-up rules plus the session's outstanding risk
+#[derive(Debug, Default)]
+pub struct StepUpState {
+    pub rules: StepUpRules,
+    raised: Mutex<Option<RaisedRisk>>,
+}
+
+impl StepUpState {
+    pub fn new(rules: StepUpRules) -> Self {
+        Self { rules, raised: Mutex::new(None) }
+    }
+
+    /// Demand step-up before the user's next PHI access
+    pub fn raise(&self, user_id: &str, reason: &str) {
+        let mut raised = self.raised.lock().unwrap();
+        if raised.as_ref().map_or(true, |r| r.user_id != user_id) {
+            log::warn!("MFA step-up required for user {}: {}", user_id, reason);
+            *raised = Some(RaisedRisk { user_id: user_id.to_string(), reason: reason.to_string(), raised_at: Utc::now() });
+        }
+    }
+
+    pub fn outstanding(&self) -> Option<RaisedRisk> {
+        self.raised.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        *self.raised.lock().unwrap() = None;
+    }
+
+    /// Whether the session completed MFA recently enough, and after any outstanding risk
+    fn satisfied(&self, auth: &AuthState, now: DateTime<Utc>) -> bool {
+        let raised_at = self
+            .raised
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|r| auth.user_id.as_deref() == Some(r.user_id.as_str()))
+            .map(|r| r.raised_at);
+        auth.mfa_verified_within(self.rules.window_minutes, now)
+            && match (raised_at, auth.mfa_verified_at) {
+                (Some(raised), Some(verified)) => verified >= raised,
+                _ => true,
+            }
+    }
+
+    /// Raise the step-up signal unless the session is already stepped up
+    pub fn require_mfa_stepup(&self, auth: &AuthState, reason: &str, now: DateTime<Utc>) -> Result<(), String> {
+        if self.satisfied(auth, now) {
+            Ok(())
+        } else {
+            Err(format!("{}: {}", STEP_UP_SIGNAL, reason))
+        }
+    }
+
+    /// Step-up required by the command itself or by an outstanding risk on PHI access
+    pub fn check_command(&self, command: &str, touches_phi: bool, auth: &AuthState, now: DateTime<Utc>) -> Result<(), String> {
+        if self.rules.commands.contains(command) {
+            return self.require_mfa_stepup(auth, &format!("'{}' requires recent MFA", command), now);
+        }
+        let outstanding = self
+            .outstanding()
+            .filter(|r| touches_phi && auth.user_id.as_deref() == Some(r.user_id.as_str()));
+        match outstanding {
+            Some(risk) => self.require_mfa_stepup(auth, &risk.reason, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Step-up for a flagged patient's record
+    pub fn check_patient(&self, auth: &AuthState, patient_id: &str, flagged: &HashSet<String>, now: DateTime<Utc>) -> Result<(), String> {
+        if self.rules.flagged_patients && flagged.contains(patient_id) {
+            self.require_mfa_stepup(auth, "record is flagged for additional verification", now)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::HealthcareRole;
+
+    fn session() -> AuthState {
+        let mut auth = AuthState::new();
+        auth.set_authenticated(
+            "user-1".to_string(),
+            "access".to_string(),
+            "refresh".to_string(),
+            HealthcareRole::HealthcareProvider,
+            Vec::new(),
+            Utc::now() + chrono::Duration::hours(1),
+        );
+        auth
+    }
+
+    #[test]
+    fn test_new_network_blocks_phi_until_fresh_mfa() {
+        let state = StepUpState::new(StepUpRules::default());
+        let mut auth = session();
+        auth.mfa_verified_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert!(state.check_command("get_client", true, &auth, Utc::now()).is_ok());
+
+        state.raise("user-1", "session moved to a new network");
+        let err = state.check_command("get_client", true, &auth, Utc::now()).unwrap_err();
+        assert!(err.starts_with(STEP_UP_SIGNAL));
+        // Low-risk commands keep working while the challenge is outstanding
+        assert!(state.check_command("get_reminder_template", false, &auth, Utc::now()).is_ok());
+
+        auth.mark_mfa_verified();
+        assert!(state.check_command("get_client", true, &auth, Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn test_stepped_up_session_expires_after_window() {
+        let state = StepUpState::new(StepUpRules::default());
+        let mut auth = session();
+        assert!(state.check_command("run_approved_export", false, &auth, Utc::now()).is_err());
+
+        auth.mark_mfa_verified();
+        assert!(state.check_command("run_approved_export", false, &auth, Utc::now()).is_ok());
+        let later = Utc::now() + chrono::Duration::minutes(state.rules.window_minutes + 1);
+        assert!(state.check_command("run_approved_export", false, &auth, later).is_err());
+    }
+}
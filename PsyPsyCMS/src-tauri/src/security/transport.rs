@@ -0,0 +1,189 @@
+// Transport Security Policy for PsyPsy CMS
+// Minimum TLS version and certificate public-key pinning for every client built by the
+// outbound guard. A pinned host whose chain carries none of its pins is refused.
+
+use base64::{Engine as _, engine::general_purpose};
+use rustls_021::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls_021::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Lowest TLS version outbound connections may negotiate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+/// Accepted SPKI pins for a host; `*.example.com` matches any subdomain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificatePin {
+    pub host: String,
+    /// Base64 SHA-256 of a certificate's SubjectPublicKeyInfo (the HPKP `pin-sha256` format).
+    /// Any certificate in the chain may match, so pinning an intermediate survives leaf rotation.
+    pub spki_sha256: Vec<String>,
+}
+
+/// TLS requirements for outbound calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransportSecurityPolicy {
+    pub min_tls_version: TlsVersion,
+    /// Hosts without an entry are checked against the public roots only
+    pub pins: Vec<CertificatePin>,
+}
+
+impl Default for TransportSecurityPolicy {
+    fn default() -> Self {
+        Self {
+            min_tls_version: TlsVersion::Tls12,
+            pins: Vec::new(),
+        }
+    }
+}
+
+impl TransportSecurityPolicy {
+    /// Defaults with `OUTBOUND_MIN_TLS_VERSION` ("1.2" or "1.3") and `OUTBOUND_CERTIFICATE_PINS`
+    /// (JSON list of pins) overrides
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        match std::env::var("OUTBOUND_MIN_TLS_VERSION").as_deref().map(str::trim) {
+            Ok("1.3") => policy.min_tls_version = TlsVersion::Tls13,
+            Ok("1.2") | Err(_) => {}
+            Ok(other) => log::warn!("Ignoring unsupported OUTBOUND_MIN_TLS_VERSION '{}'; using 1.2", other),
+        }
+        if let Ok(pins) = std::env::var("OUTBOUND_CERTIFICATE_PINS") {
+            match serde_json::from_str(&pins) {
+                Ok(pins) => policy.pins = pins,
+                Err(e) => log::warn!("Ignoring invalid OUTBOUND_CERTIFICATE_PINS: {}", e),
+            }
+        }
+        policy
+    }
+
+    /// Pins that apply to `host`, merged across matching entries
+    pub fn pins_for(&self, host: &str) -> Vec<&str> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.pins
+            .iter()
+            .filter(|pin| crate::security::outbound::host_matches(&pin.host, &host))
+            .flat_map(|pin| pin.spki_sha256.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+/// `pin-sha256` value of a DER certificate
+pub fn spki_pin(certificate_der: &[u8]) -> Option<String> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(certificate_der).ok()?;
+    let digest = Sha256::digest(certificate.tbs_certificate.subject_pki.raw);
+    Some(general_purpose::STANDARD.encode(digest))
+}
+
+/// Whether any certificate of a presented chain carries one of `pins`
+pub fn chain_matches_pins<'a>(chain: impl IntoIterator<Item = &'a [u8]>, pins: &[&str]) -> bool {
+    chain.into_iter().filter_map(spki_pin).any(|pin| pins.contains(&pin.as_str()))
+}
+
+/// Normal WebPKI validation followed by the pin check for pinned hosts
+struct PinningVerifier {
+    roots: WebPkiVerifier,
+    policy: TransportSecurityPolicy,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls_021::Error> {
+        let verified = self
+            .roots
+            .verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+
+        let host = match server_name {
+            ServerName::DnsName(name) => name.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            _ => String::new(),
+        };
+        let pins = self.policy.pins_for(&host);
+        if pins.is_empty() {
+            return Ok(verified);
+        }
+        let chain = std::iter::once(end_entity).chain(intermediates).map(|c| c.0.as_slice());
+        if chain_matches_pins(chain, &pins) {
+            Ok(verified)
+        } else {
+            log::error!("SecurityViolationDetected: certificate pin mismatch for {}; connection refused", host);
+            Err(rustls_021::Error::General(format!("certificate pin mismatch for {}", host)))
+        }
+    }
+}
+
+/// TLS configuration enforcing the policy, for `reqwest::ClientBuilder::use_preconfigured_tls`
+pub fn tls_client_config(policy: &TransportSecurityPolicy) -> ClientConfig {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+
+    let versions: &[&'static rustls_021::SupportedProtocolVersion] = match policy.min_tls_version {
+        TlsVersion::Tls12 => &[&rustls_021::version::TLS13, &rustls_021::version::TLS12],
+        TlsVersion::Tls13 => &[&rustls_021::version::TLS13],
+    };
+    let mut config = ClientConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(versions)
+        .expect("safe default cipher suites cover TLS 1.2 and 1.3")
+        .with_custom_certificate_verifier(Arc::new(PinningVerifier {
+            roots: WebPkiVerifier::new(roots, None),
+            policy: policy.clone(),
+        }))
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pins_match_host_patterns() {
+        let policy = TransportSecurityPolicy {
+            pins: vec![
+                CertificatePin { host: "*.googleapis.com".to_string(), spki_sha256: vec!["gts-root".to_string()] },
+                CertificatePin { host: "cloudkms.googleapis.com".to_string(), spki_sha256: vec!["kms-backup".to_string()] },
+            ],
+            ..TransportSecurityPolicy::default()
+        };
+        assert_eq!(policy.pins_for("CloudKMS.googleapis.com."), vec!["gts-root", "kms-backup"]);
+        assert_eq!(policy.pins_for("firestore.googleapis.com"), vec!["gts-root"]);
+        assert!(policy.pins_for("api.linkedin.com").is_empty());
+    }
+
+    #[test]
+    fn test_unparseable_chain_fails_closed() {
+        assert!(spki_pin(b"not a certificate").is_none());
+        assert!(!chain_matches_pins([b"not a certificate".as_slice()], &["anything"]));
+    }
+
+    #[test]
+    fn test_tls13_minimum_drops_tls12() {
+        let config = tls_client_config(&TransportSecurityPolicy {
+            min_tls_version: TlsVersion::Tls13,
+            pins: Vec::new(),
+        });
+        assert!(config.supports_version(rustls_021::ProtocolVersion::TLSv1_3));
+        assert!(!config.supports_version(rustls_021::ProtocolVersion::TLSv1_2));
+    }
+}
@@ -6,9 +6,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use validator::{Validate, ValidationError};
 use regex::Regex;
+use once_cell::sync::Lazy;
 // use sanitize_html::{sanitize_str, rules::predefined::DEFAULT};  // Commented out due to threading issues
 use std::str::FromStr;
+use std::sync::Arc;
 use chrono::{DateTime, Utc, NaiveDate};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
 /// Validation context for healthcare-specific validation rules
@@ -151,7 +155,8 @@ impl ValidationRules {
                 })?,
             
             // Security patterns
-            password_strength: Regex::new(r"^(?=.*[a-z])(?=.*[A-Z])(?=.*\d)(?=.*[@$!%*?&])[A-Za-z\d@$!%*?&]{12,}$")
+            // The regex crate has no look-ahead, so only length and charset are expressed here
+            password_strength: Regex::new(r"^[A-Za-z\d@$!%*?&]{12,}$")
                 .map_err(|e| SecurityError::ValidationFailed { 
                     reason: format!("Regex error: {}", e) 
                 })?,
@@ -535,8 +540,148 @@ impl SanitizationService {
         
         Ok(sanitized)
     }
+
+    /// Clean one form field for display and storage. Applying it to its own output changes nothing,
+    /// so clean input comes back untouched with `was_modified` false.
+    pub fn sanitize_field(&self, field_type: SanitizeFieldType, value: &str) -> SanitizeResult {
+        let mut findings = Vec::new();
+        let keeps_line_breaks = field_type == SanitizeFieldType::FreeText;
+
+        let mut sanitized: String = value
+            .chars()
+            .filter(|c| !c.is_control() || (keeps_line_breaks && matches!(c, '\n' | '\t')))
+            .collect();
+        if sanitized.len() != value.len() {
+            findings.push(SanitizeFinding::ControlCharacters);
+        }
+
+        // Removing one tag can join the pieces of another, so repeat until nothing matches
+        let mut removed_script = false;
+        let mut removed_markup = false;
+        loop {
+            let without_scripts = SCRIPT_BLOCK.replace_all(&sanitized, "");
+            removed_script |= without_scripts.len() != sanitized.len();
+            let without_urls = SCRIPT_URL.replace_all(&without_scripts, "");
+            removed_script |= without_urls.len() != without_scripts.len();
+            let without_tags = MARKUP.replace_all(&without_urls, "");
+            removed_markup |= without_tags.len() != without_urls.len();
+            if without_tags == sanitized {
+                break;
+            }
+            sanitized = without_tags.into_owned();
+        }
+        if removed_script {
+            findings.push(SanitizeFinding::ScriptInjection);
+        }
+        if removed_markup {
+            findings.push(SanitizeFinding::HtmlMarkup);
+        }
+
+        match field_type {
+            SanitizeFieldType::Name => {
+                // Compose decomposed accents first; marks with no precomposed form are kept as they are
+                let composed: String = sanitized.nfc().collect();
+                let allowed: String = composed
+                    .chars()
+                    .filter(|&c| {
+                        c.is_alphabetic() || is_combining_mark(c) || c.is_whitespace() || matches!(c, '-' | '\'' | '’' | '.')
+                    })
+                    .collect();
+                if allowed.len() != composed.len() {
+                    findings.push(SanitizeFinding::DisallowedCharacters);
+                }
+                sanitized = allowed.split_whitespace().collect::<Vec<_>>().join(" ");
+            }
+            SanitizeFieldType::Email => {
+                sanitized = sanitized.trim().to_string();
+                if !self.validation_rules.email_address.is_match(&sanitized) {
+                    findings.push(SanitizeFinding::InvalidFormat);
+                }
+            }
+            SanitizeFieldType::Phone => {
+                let allowed: String = sanitized
+                    .trim()
+                    .chars()
+                    .filter(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | '(' | ')' | ' '))
+                    .collect();
+                if allowed.len() != sanitized.trim().len() {
+                    findings.push(SanitizeFinding::DisallowedCharacters);
+                }
+                sanitized = allowed.trim().to_string();
+                if !self.validation_rules.phone_number.is_match(&sanitized) {
+                    findings.push(SanitizeFinding::InvalidFormat);
+                }
+            }
+            // Comparisons such as "< 50 mg" are not markup and are kept as written
+            SanitizeFieldType::FreeText => {}
+        }
+
+        SanitizeResult { was_modified: sanitized != value, sanitized, findings }
+    }
+}
+
+/// Shared sanitization service state, so the patterns are compiled once
+#[derive(Clone)]
+pub struct SanitizationServiceState(pub Arc<SanitizationService>);
+
+impl SanitizationServiceState {
+    pub fn new(service: SanitizationService) -> Self {
+        Self(Arc::new(service))
+    }
+}
+
+/// Kind of form field `sanitize_field` cleans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SanitizeFieldType {
+    /// Person names; letters in any script, spaces, hyphens, apostrophes and periods
+    Name,
+    Email,
+    Phone,
+    /// Notes and other prose; line breaks and clinical notation are kept
+    FreeText,
 }
 
+/// Why a field was changed, or would be rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SanitizeFinding {
+    ControlCharacters,
+    HtmlMarkup,
+    /// Script blocks or `javascript:` URLs were removed
+    ScriptInjection,
+    DisallowedCharacters,
+    /// Left as entered but not a valid value for the field
+    InvalidFormat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SanitizeResult {
+    pub sanitized: String,
+    pub was_modified: bool,
+    pub findings: Vec<SanitizeFinding>,
+}
+
+/// Script and style elements, removed with their content
+static SCRIPT_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<(script|style)\b[^>]*>.*?</(script|style)\s*>").expect("valid script block pattern")
+});
+
+/// Script-bearing URL schemes
+static SCRIPT_URL: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)(javascript|vbscript)\s*:").expect("valid script URL pattern"));
+
+/// HTML comments, well-formed tags, and tag-like text carrying an event handler. Prose such as
+/// "intake <a litre/day, output >2L" does not parse as a tag and is left alone.
+static MARKUP: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?s)<!--.*?-->",
+        r#"|</?[A-Za-z][A-Za-z0-9-]*(?:\s+[^\s"'<>/=]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s"'=<>`]+))?)*\s*/?>"#,
+        r"|<[A-Za-z][^<>]*\bon[a-z]+\s*=[^<>]*>",
+    ))
+    .expect("valid markup pattern")
+});
+
 /// PHI detection result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhiDetection {
@@ -557,9 +702,73 @@ pub enum PhiType {
     InsurancePolicyNumber,
     PhoneNumber,
     EmailAddress,
+    /// Quebec health insurance (RAMQ) number
+    HealthInsuranceNumber,
+    PostalCode,
+    /// Specialty, diagnosis or treatment wording that discloses care
+    ClinicalInformation,
     Other,
 }
 
+/// Specialty, diagnosis and treatment wording (English and French) that reveals the nature of care
+static CLINICAL_TERMS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?i)\b(",
+        r"psychiatr\w*|psycholog\w*|psychoth[ée]rap\w*|th[ée]rap\w*|counsell?ing|",
+        r"mental health|sant[ée] mentale|addiction\w*|d[ée]pendance\w*|rehab\w*|",
+        r"oncolog\w*|chemotherap\w*|chimioth[ée]rapie|HIV|VIH|STI|ITSS|fertility|fertilit[ée]|abortion|avortement|",
+        r"depress\w*|d[ée]pression|anxi\w*|bipolar\w*|bipolaire|schizophren\w*|PTSD|TSPT|ADHD|TDAH|OCD|TOC|",
+        r"eating disorder|trouble alimentaire|autism\w*|autisme|suicid\w*|diagnos\w*|",
+        r"[A-TV-Z]\d{2}(\.\d{1,4})?",
+        r")\b",
+    ))
+    .expect("valid clinical term pattern")
+});
+
+/// Find wording that would disclose a specialty, diagnosis or treatment
+pub fn detect_clinical_terms(text: &str) -> Vec<PhiDetection> {
+    CLINICAL_TERMS
+        .find_iter(text)
+        .map(|mat| PhiDetection {
+            pattern_type: PhiType::ClinicalInformation,
+            matched_text: mat.as_str().to_string(),
+            start_position: mat.start(),
+            end_position: mat.end(),
+            confidence: 0.8,
+        })
+        .collect()
+}
+
+/// Free-text identifier patterns, checked in order
+static IDENTIFIER_PATTERNS: Lazy<Vec<(PhiType, Regex)>> = Lazy::new(|| {
+    [
+        // RAMQ: four letters then eight digits, often grouped in fours
+        (PhiType::HealthInsuranceNumber, r"(?i)\b[A-Z]{4}[\s-]?\d{4}[\s-]?\d{4}\b"),
+        (PhiType::EmailAddress, r"(?i)\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b"),
+        (PhiType::PhoneNumber, r"(?:\+?1[-.\s]?)?\(?\b\d{3}\)?[-.\s]?\d{3}[-.\s]?\d{4}\b"),
+        (PhiType::PostalCode, r"(?i)\b[ABCEGHJ-NPRSTVXY]\d[ABCEGHJ-NPRSTV-Z][\s-]?\d[ABCEGHJ-NPRSTV-Z]\d\b"),
+    ]
+    .into_iter()
+    .map(|(kind, pattern)| (kind, Regex::new(pattern).expect("valid identifier pattern")))
+    .collect()
+});
+
+/// Find identifiers in free text: RAMQ numbers, emails, phone numbers and postal codes
+pub fn detect_identifiers(text: &str) -> Vec<PhiDetection> {
+    IDENTIFIER_PATTERNS
+        .iter()
+        .flat_map(|(kind, pattern)| {
+            pattern.find_iter(text).map(move |mat| PhiDetection {
+                pattern_type: kind.clone(),
+                matched_text: mat.as_str().to_string(),
+                start_position: mat.start(),
+                end_position: mat.end(),
+                confidence: 0.85,
+            })
+        })
+        .collect()
+}
+
 // Custom validation functions
 fn validate_date_of_birth(dob: &str) -> Result<(), ValidationError> {
     if let Ok(date) = NaiveDate::from_str(dob) {
@@ -737,6 +946,13 @@ pub fn validate_client_data(request: &crate::models::CreateClientRequest) -> Res
         }
     }
 
+    // RAMQ numbers are four letters and eight digits, spacing aside
+    if let Some(ref ramq) = request.ramq_number {
+        if crate::services::client_dedup::normalize_ramq(ramq).is_none() {
+            errors.push("Invalid RAMQ number format".to_string());
+        }
+    }
+
     // Validate email format
     if !request.email.is_empty() {
         let email_regex = regex::Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap();
@@ -788,6 +1004,59 @@ mod tests {
         assert!(sanitized.contains("Hello World"));
     }
     
+    #[test]
+    fn test_sanitize_field_neutralizes_xss() {
+        let service = SanitizationService::new().unwrap();
+        let payloads = [
+            (SanitizeFieldType::FreeText, "Session notes<script>alert('XSS')</script>", "Session notes"),
+            (SanitizeFieldType::FreeText, "<img src=x onerror=alert(1)>Follow up", "Follow up"),
+            (SanitizeFieldType::FreeText, "<<script></script>script>alert(1)<</script>/script>", "alert(1)"),
+            (SanitizeFieldType::FreeText, "see jaVaScRiPt:alert(1)", "see alert(1)"),
+            (SanitizeFieldType::Name, "Marie<svg onload=alert(1)>", "Marie"),
+            (SanitizeFieldType::FreeText, "<img src=x/onerror=alert(1)>Follow up", "Follow up"),
+        ];
+        for (field_type, payload, expected) in payloads {
+            let result = service.sanitize_field(field_type, payload);
+            assert_eq!(result.sanitized, expected, "payload {:?}", payload);
+            assert!(result.was_modified);
+            // Sanitizing the output again is a no-op
+            let again = service.sanitize_field(field_type, &result.sanitized);
+            assert_eq!(again.sanitized, result.sanitized);
+            assert!(!again.was_modified);
+        }
+        let result = service.sanitize_field(SanitizeFieldType::FreeText, "<script>x</script>ok");
+        assert_eq!(result.findings, vec![SanitizeFinding::ScriptInjection]);
+    }
+
+    #[test]
+    fn test_sanitize_field_keeps_clean_input() {
+        let service = SanitizationService::new().unwrap();
+        let clean = [
+            (SanitizeFieldType::Name, "Hélène Côté-Bélanger"),
+            (SanitizeFieldType::Name, "Zoë O’Brien"),
+            (SanitizeFieldType::Name, "François D'Amours"),
+            (SanitizeFieldType::Email, "helene.cote@example.ca"),
+            (SanitizeFieldType::Phone, "+1 (514) 555-0199"),
+            (SanitizeFieldType::FreeText, "Sertraline 50 mg die, titrate if < 100 mg/day tolerated.\nPHQ-9 >= 10 & GAD-7 = 8"),
+            (SanitizeFieldType::FreeText, "Fluid intake <a litre/day, output >2L"),
+        ];
+        for (field_type, value) in clean {
+            let result = service.sanitize_field(field_type, value);
+            assert_eq!(result.sanitized, value);
+            assert!(!result.was_modified);
+            assert!(result.findings.is_empty(), "{:?} flagged {:?}", value, result.findings);
+        }
+
+        let result = service.sanitize_field(SanitizeFieldType::Name, "  Hélène\u{0007}  Côté ");
+        assert_eq!(result.sanitized, "Hélène Côté");
+        assert_eq!(result.findings, vec![SanitizeFinding::ControlCharacters]);
+
+        // Decomposed accents, as some keyboards and pasted text produce, are composed rather than dropped
+        let result = service.sanitize_field(SanitizeFieldType::Name, "He\u{0301}le\u{0300}ne");
+        assert_eq!(result.sanitized, "Hélène");
+        assert!(result.findings.is_empty());
+    }
+
     #[test]
     fn test_phi_detection() {
         let service = SanitizationService::new().unwrap();
@@ -799,6 +1068,15 @@ mod tests {
         assert!(detections.iter().any(|d| d.pattern_type == PhiType::CreditCardNumber));
     }
     
+    #[test]
+    fn test_clinical_term_detection() {
+        let detections = detect_clinical_terms("Rappel: votre rendez-vous en psychiatrie (F32.1) demain");
+        let matched: Vec<_> = detections.iter().map(|d| d.matched_text.as_str()).collect();
+        assert_eq!(matched, vec!["psychiatrie", "F32.1"]);
+
+        assert!(detect_clinical_terms("Reminder: you have an appointment on Tuesday at 10:00").is_empty());
+    }
+
     #[test]
     fn test_patient_info_validation() {
         let service = SanitizationService::new().unwrap();
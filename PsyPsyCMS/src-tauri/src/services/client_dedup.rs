@@ -0,0 +1,540 @@
+// Duplicate Client Detection and Merge for PsyPsy CMS
+// Scores client pairs on normalized name, date of birth and RAMQ number, and plans merges that
+// surface every conflicting field for a human decision instead of picking a value silently.
+
+use crate::models::{Client, ClientStatus};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+/// Firestore collection holding merge records
+pub const MERGE_COLLECTION: &str = "client_merges";
+
+/// Relative weight of each identity signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateWeights {
+    pub name: f64,
+    pub date_of_birth: f64,
+    pub ramq: f64,
+}
+
+/// Detection and merge settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateDetectionConfig {
+    pub weights: DuplicateWeights,
+    /// Minimum score (0-1) for a pair to be reported
+    pub threshold: f64,
+    /// Hours during which a merge can be undone
+    pub undo_window_hours: i64,
+}
+
+impl Default for DuplicateDetectionConfig {
+    fn default() -> Self {
+        Self {
+            weights: DuplicateWeights { name: 0.35, date_of_birth: 0.25, ramq: 0.4 },
+            threshold: 0.75,
+            undo_window_hours: 72,
+        }
+    }
+}
+
+impl DuplicateDetectionConfig {
+    /// Defaults with `CLIENT_DUPLICATE_THRESHOLD`, `CLIENT_DUPLICATE_WEIGHTS` (JSON) and
+    /// `CLIENT_MERGE_UNDO_HOURS` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(threshold) = std::env::var("CLIENT_DUPLICATE_THRESHOLD").ok().and_then(|v| v.parse::<f64>().ok()) {
+            if (0.0..=1.0).contains(&threshold) {
+                config.threshold = threshold;
+            } else {
+                log::warn!("Ignoring CLIENT_DUPLICATE_THRESHOLD outside 0-1: {}", threshold);
+            }
+        }
+        if let Ok(weights) = std::env::var("CLIENT_DUPLICATE_WEIGHTS") {
+            match serde_json::from_str(&weights) {
+                Ok(weights) => config.weights = weights,
+                Err(e) => log::warn!("Ignoring invalid CLIENT_DUPLICATE_WEIGHTS: {}", e),
+            }
+        }
+        if let Some(hours) = std::env::var("CLIENT_MERGE_UNDO_HOURS").ok().and_then(|v| v.parse().ok()).filter(|h| *h > 0) {
+            config.undo_window_hours = hours;
+        }
+        config
+    }
+}
+
+/// Lowercase, accent-free name tokens ("Hélène Côté-Roy" -> ["cote", "helene", "roy"])
+pub fn normalize_name(first: &str, last: &str) -> BTreeSet<String> {
+    format!("{} {}", first, last)
+        .chars()
+        .map(|c| match c.to_lowercase().next().unwrap_or(c) {
+            'à' | 'á' | 'â' | 'ä' | 'ã' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' | 'í' | 'ì' => 'i',
+            'ô' | 'ö' | 'ó' | 'ò' | 'õ' => 'o',
+            'ù' | 'û' | 'ü' | 'ú' => 'u',
+            'ç' => 'c',
+            'ÿ' => 'y',
+            'ñ' => 'n',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// RAMQ number as four uppercase letters and eight digits, or `None` if malformed
+pub fn normalize_ramq(value: &str) -> Option<String> {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace() && *c != '-').collect::<String>().to_ascii_uppercase();
+    let valid = compact.len() == 12
+        && compact.is_ascii()
+        && compact[..4].chars().all(|c| c.is_ascii_alphabetic())
+        && compact[4..].chars().all(|c| c.is_ascii_digit());
+    valid.then_some(compact)
+}
+
+fn parse_dob(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    let date_part = value.split('T').next().unwrap_or(value);
+    ["%Y-%m-%d", "%Y/%m/%d", "%d/%m/%Y", "%Y%m%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date_part, format).ok())
+}
+
+/// A client pair that likely describes the same person
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCandidate {
+    pub client_id: String,
+    pub duplicate_id: String,
+    pub score: f64,
+    /// Signals that agreed: `name`, `date_of_birth`, `ramq`
+    pub matched_on: Vec<String>,
+    /// Fields that would need a decision if the pair were merged
+    pub conflicting_fields: Vec<String>,
+}
+
+/// Weighted similarity of two clients over the signals both records carry.
+/// A RAMQ mismatch caps the score (different insured persons), and a name-only match is
+/// discounted because common names collide.
+pub fn score_pair(a: &Client, b: &Client, weights: &DuplicateWeights) -> (f64, Vec<String>) {
+    let mut total = 0.0;
+    let mut weight_sum = 0.0;
+    let mut matched = Vec::new();
+
+    let (name_a, name_b) = (
+        normalize_name(&a.profile.first_name, &a.profile.last_name),
+        normalize_name(&b.profile.first_name, &b.profile.last_name),
+    );
+    if !name_a.is_empty() && !name_b.is_empty() {
+        let similarity = name_a.intersection(&name_b).count() as f64 / name_a.union(&name_b).count() as f64;
+        total += weights.name * similarity;
+        weight_sum += weights.name;
+        if similarity >= 0.99 {
+            matched.push("name".to_string());
+        }
+    }
+
+    let dob = |c: &Client| c.profile.date_of_birth.as_deref().and_then(parse_dob);
+    if let (Some(dob_a), Some(dob_b)) = (dob(a), dob(b)) {
+        // Day and month swapped is a common entry error
+        let similarity = if dob_a == dob_b {
+            1.0
+        } else if NaiveDate::from_ymd_opt(
+            chrono::Datelike::year(&dob_a),
+            chrono::Datelike::day(&dob_a),
+            chrono::Datelike::month(&dob_a),
+        ) == Some(dob_b) {
+            0.5
+        } else {
+            0.0
+        };
+        total += weights.date_of_birth * similarity;
+        weight_sum += weights.date_of_birth;
+        if similarity >= 0.99 {
+            matched.push("date_of_birth".to_string());
+        }
+    }
+
+    let ramq = |c: &Client| c.ramq_number.as_deref().and_then(normalize_ramq);
+    let mut ramq_conflict = false;
+    if let (Some(ramq_a), Some(ramq_b)) = (ramq(a), ramq(b)) {
+        weight_sum += weights.ramq;
+        if ramq_a == ramq_b {
+            total += weights.ramq;
+            matched.push("ramq".to_string());
+        } else {
+            ramq_conflict = true;
+        }
+    }
+
+    if weight_sum <= 0.0 {
+        return (0.0, matched);
+    }
+    let mut score = total / weight_sum;
+    if ramq_conflict {
+        score = score.min(0.5);
+    }
+    if weight_sum <= weights.name {
+        score *= 0.6;
+    }
+    (score, matched)
+}
+
+/// Likely duplicates among `clients`, best first. With `focus`, only pairs involving that
+/// client are reported. Records already merged away are ignored.
+pub fn find_duplicates(clients: &[Client], focus: Option<&str>, config: &DuplicateDetectionConfig) -> Vec<DuplicateCandidate> {
+    let live: Vec<&Client> = clients.iter().filter(|c| c.merged_into.is_none()).collect();
+    let mut candidates = Vec::new();
+    for (i, a) in live.iter().enumerate() {
+        for b in &live[i + 1..] {
+            if let Some(focus) = focus {
+                if a.object_id != focus && b.object_id != focus {
+                    continue;
+                }
+            }
+            let (score, matched_on) = score_pair(a, b, &config.weights);
+            if score >= config.threshold {
+                let (client, duplicate) = if focus == Some(b.object_id.as_str()) { (b, a) } else { (a, b) };
+                candidates.push(DuplicateCandidate {
+                    client_id: client.object_id.clone(),
+                    duplicate_id: duplicate.object_id.clone(),
+                    score,
+                    matched_on,
+                    conflicting_fields: merge_conflicts(client, duplicate).into_iter().map(|c| c.field).collect(),
+                });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// Fields carried over in a merge; the rest (IDs, counters, assignments, timestamps) are
+/// combined mechanically
+const MERGE_FIELDS: &[&str] = &[
+    "firstName",
+    "lastName",
+    "dateOfBirth",
+    "gender",
+    "ramqNumber",
+    "addressObj",
+    "spokenLangArr",
+    "medicalInfo",
+    "emergencyContacts",
+    "preferences",
+];
+
+/// Survivor fields a merge can change, and so the ones an undo restores
+const TRACKED_FIELDS: &[&str] = &[
+    "firstName",
+    "lastName",
+    "dateOfBirth",
+    "gender",
+    "ramqNumber",
+    "addressObj",
+    "spokenLangArr",
+    "medicalInfo",
+    "emergencyContacts",
+    "preferences",
+    "assignedProfessionals",
+    "totalAppointments",
+    "completedAppointments",
+    "cancelledAppointments",
+];
+
+/// Which record's value to keep for a conflicting field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSide {
+    Survivor,
+    Merged,
+}
+
+/// A field both records set to different values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldConflict {
+    pub field: String,
+    pub survivor_value: Value,
+    pub merged_value: Value,
+}
+
+/// JSON value of a tracked field, by its serialized name
+fn field_value(client: &Client, field: &str) -> Value {
+    let value = match field {
+        "firstName" => serde_json::to_value(&client.profile.first_name),
+        "lastName" => serde_json::to_value(&client.profile.last_name),
+        "dateOfBirth" => serde_json::to_value(&client.profile.date_of_birth),
+        "gender" => serde_json::to_value(client.profile.gender),
+        "ramqNumber" => serde_json::to_value(&client.ramq_number),
+        "addressObj" => serde_json::to_value(&client.address_obj),
+        "spokenLangArr" => serde_json::to_value(&client.spoken_lang_arr),
+        "medicalInfo" => serde_json::to_value(&client.medical_info),
+        "emergencyContacts" => serde_json::to_value(&client.emergency_contacts),
+        "preferences" => serde_json::to_value(&client.preferences),
+        "assignedProfessionals" => serde_json::to_value(&client.assigned_professionals),
+        "totalAppointments" => serde_json::to_value(client.total_appointments),
+        "completedAppointments" => serde_json::to_value(client.completed_appointments),
+        "cancelledAppointments" => serde_json::to_value(client.cancelled_appointments),
+        _ => Ok(Value::Null),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+/// Set a tracked field from its JSON value
+fn set_field(client: &mut Client, field: &str, value: Value) -> Result<(), serde_json::Error> {
+    use serde_json::from_value;
+    match field {
+        "firstName" => client.profile.first_name = from_value(value)?,
+        "lastName" => client.profile.last_name = from_value(value)?,
+        "dateOfBirth" => client.profile.date_of_birth = from_value(value)?,
+        "gender" => client.profile.gender = from_value(value)?,
+        "ramqNumber" => client.ramq_number = from_value(value)?,
+        "addressObj" => client.address_obj = from_value(value)?,
+        "spokenLangArr" => client.spoken_lang_arr = from_value(value)?,
+        "medicalInfo" => client.medical_info = from_value(value)?,
+        "emergencyContacts" => client.emergency_contacts = from_value(value)?,
+        "preferences" => client.preferences = from_value(value)?,
+        "assignedProfessionals" => client.assigned_professionals = from_value(value)?,
+        "totalAppointments" => client.total_appointments = from_value(value)?,
+        "completedAppointments" => client.completed_appointments = from_value(value)?,
+        "cancelledAppointments" => client.cancelled_appointments = from_value(value)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Array(a) => a.is_empty(),
+        _ => false,
+    }
+}
+
+/// Fields where both records hold a different, non-empty value
+pub fn merge_conflicts(survivor: &Client, merged: &Client) -> Vec<FieldConflict> {
+    MERGE_FIELDS
+        .iter()
+        .map(|field| (field, field_value(survivor, field), field_value(merged, field)))
+        .filter(|(_, survivor_value, merged_value)| {
+            !is_empty(survivor_value) && !is_empty(merged_value) && survivor_value != merged_value
+        })
+        .map(|(field, survivor_value, merged_value)| FieldConflict {
+            field: field.to_string(),
+            survivor_value,
+            merged_value,
+        })
+        .collect()
+}
+
+/// Conflicts the caller has not resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedConflicts {
+    pub conflicts: Vec<FieldConflict>,
+}
+
+impl std::fmt::Display for UnresolvedConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<&str> = self.conflicts.iter().map(|c| c.field.as_str()).collect();
+        write!(f, "Conflicting fields need a resolution before merging: {}", fields.join(", "))
+    }
+}
+
+/// The surviving record after a merge. Every conflicting field needs an entry in
+/// `resolutions`; empty survivor fields are filled from the merged record; assignments and
+/// appointment counters are combined.
+pub fn merged_survivor(
+    survivor: &Client,
+    merged: &Client,
+    resolutions: &HashMap<String, MergeSide>,
+) -> Result<Client, UnresolvedConflicts> {
+    let conflicts = merge_conflicts(survivor, merged);
+    let unresolved: Vec<FieldConflict> = conflicts.iter().filter(|c| !resolutions.contains_key(&c.field)).cloned().collect();
+    if !unresolved.is_empty() {
+        return Err(UnresolvedConflicts { conflicts: unresolved });
+    }
+
+    let mut result = survivor.clone();
+    for field in MERGE_FIELDS {
+        let take_merged = if is_empty(&field_value(survivor, field)) {
+            !is_empty(&field_value(merged, field))
+        } else {
+            resolutions.get(*field) == Some(&MergeSide::Merged)
+        };
+        if take_merged {
+            // Same field type on both sides, so this cannot fail
+            let _ = set_field(&mut result, field, field_value(merged, field));
+        }
+    }
+
+    for professional_id in &merged.assigned_professionals {
+        result.assign_professional(professional_id.clone());
+    }
+    result.total_appointments += merged.total_appointments;
+    result.completed_appointments += merged.completed_appointments;
+    result.cancelled_appointments += merged.cancelled_appointments;
+    result.updated_at = crate::models::common::firestore_now();
+    Ok(result)
+}
+
+/// The merged record as kept after the merge: inactive, pointing at the survivor
+pub fn tombstone(merged: &Client, survivor_id: &str) -> Client {
+    let mut tombstone = merged.clone();
+    tombstone.merged_into = Some(survivor_id.to_string());
+    tombstone.status = ClientStatus::Inactive;
+    tombstone.profile.is_active = false;
+    tombstone.updated_at = crate::models::common::firestore_now();
+    tombstone
+}
+
+/// One survivor field changed by a merge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Tracked survivor fields that differ between two versions
+pub fn field_changes(before: &Client, after: &Client) -> Vec<FieldChange> {
+    TRACKED_FIELDS
+        .iter()
+        .map(|field| (field, field_value(before, field), field_value(after, field)))
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange { field: field.to_string(), before, after })
+        .collect()
+}
+
+/// Everything needed to audit and undo a merge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientMergeRecord {
+    pub merge_id: Uuid,
+    pub survivor_id: String,
+    pub merged_id: String,
+    pub resolutions: HashMap<String, MergeSide>,
+    pub survivor_changes: Vec<FieldChange>,
+    /// Status of the merged record before it became a tombstone
+    pub merged_status_before: ClientStatus,
+    pub merged_was_active: bool,
+    pub moved_appointment_ids: Vec<String>,
+    pub moved_note_ids: Vec<String>,
+    pub merged_by: String,
+    pub merged_at: DateTime<Utc>,
+    pub undo_until: DateTime<Utc>,
+    pub reverted_by: Option<String>,
+    pub reverted_at: Option<DateTime<Utc>>,
+}
+
+impl ClientMergeRecord {
+    pub fn undo_deadline(merged_at: DateTime<Utc>, config: &DuplicateDetectionConfig) -> DateTime<Utc> {
+        merged_at + Duration::hours(config.undo_window_hours)
+    }
+
+    /// Why the merge cannot be undone now, if it cannot
+    pub fn undo_blocker(&self, now: DateTime<Utc>) -> Option<String> {
+        if self.reverted_at.is_some() {
+            Some("Merge has already been reverted".to_string())
+        } else if now > self.undo_until {
+            Some(format!("Undo window closed at {}", self.undo_until.to_rfc3339()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Restore the survivor's pre-merge values for fields nobody has edited since the merge.
+/// Returns the fields left alone because they changed after the merge.
+pub fn revert_survivor(current: &Client, changes: &[FieldChange]) -> (Client, Vec<String>) {
+    let mut reverted = current.clone();
+    let mut skipped = Vec::new();
+    for change in changes {
+        let unchanged_since_merge = field_value(current, &change.field) == change.after;
+        if !unchanged_since_merge || set_field(&mut reverted, &change.field, change.before.clone()).is_err() {
+            skipped.push(change.field.clone());
+        }
+    }
+    reverted.updated_at = crate::models::common::firestore_now();
+    (reverted, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AddressObject, CreateClientRequest};
+
+    fn client(id: &str, first: &str, last: &str, dob: Option<&str>, ramq: Option<&str>) -> Client {
+        Client::from_request(
+            CreateClientRequest {
+                user_id: format!("user-{}", id),
+                first_name: first.to_string(),
+                last_name: last.to_string(),
+                email: format!("{}@example.com", id),
+                phone: "5145550000".to_string(),
+                date_of_birth: dob.map(str::to_string),
+                ramq_number: ramq.map(str::to_string),
+                address: AddressObject {
+                    street: "123 Rue Principale".to_string(),
+                    city: "Montreal".to_string(),
+                    state: "QC".to_string(),
+                    zip_code: "H2X 1Y4".to_string(),
+                    country: "Canada".to_string(),
+                },
+                spoken_languages: vec![1],
+                search_radius: None,
+                preferences: None,
+                emergency_contacts: None,
+            },
+            id.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_scores_normalized_identity() {
+        let config = DuplicateDetectionConfig::default();
+        let a = client("a", "Hélène", "Côté-Roy", Some("1985-03-07"), Some("COTH 8503 0712"));
+        let b = client("b", "helene", "Cote Roy", Some("1985-03-07"), Some("coth85030712"));
+        let (score, matched) = score_pair(&a, &b, &config.weights);
+        assert!(score > 0.99);
+        assert_eq!(matched, ["name", "date_of_birth", "ramq"]);
+
+        // Same name and birth date but different insured person
+        let c = client("c", "Hélène", "Côté-Roy", Some("1985-03-07"), Some("COTH85030799"));
+        assert!(score_pair(&a, &c, &config.weights).0 <= 0.5);
+        assert_eq!(find_duplicates(&[a, b, c], None, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_conflicts_require_resolution() {
+        let survivor = client("a", "Marc", "Gagnon", Some("1990-01-01"), None);
+        let mut merged = client("b", "Marc", "Gagnon", Some("1990-01-10"), Some("GAGM90011012"));
+        merged.assign_professional("prof-1".to_string());
+
+        let err = merged_survivor(&survivor, &merged, &HashMap::new()).unwrap_err();
+        assert_eq!(err.conflicts.len(), 1);
+        assert_eq!(err.conflicts[0].field, "dateOfBirth");
+
+        let resolutions = HashMap::from([("dateOfBirth".to_string(), MergeSide::Merged)]);
+        let result = merged_survivor(&survivor, &merged, &resolutions).unwrap();
+        assert_eq!(result.object_id, "a");
+        assert_eq!(result.profile.date_of_birth.as_deref(), Some("1990-01-10"));
+        // Empty survivor field filled without a decision
+        assert_eq!(result.ramq_number.as_deref(), Some("GAGM90011012"));
+        assert_eq!(result.assigned_professionals, ["prof-1"]);
+
+        let (reverted, skipped) = revert_survivor(&result, &field_changes(&survivor, &result));
+        assert!(skipped.is_empty());
+        assert_eq!(reverted.profile.date_of_birth.as_deref(), Some("1990-01-01"));
+        assert!(reverted.ramq_number.is_none());
+        assert!(reverted.assigned_professionals.is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+// Client Search for PsyPsy CMS
+// Matches a query against client name, email and phone with accents and case folded away on
+// both sides, so "Leger" finds "Léger". Folding is only used for matching; results are the
+// records as stored.
+
+use crate::models::common::compare_profile_names;
+use crate::models::Client;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// How well a client matched, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchRank {
+    /// A whole field equals the query
+    Exact,
+    /// A field, or a word in it, starts with the query
+    Prefix,
+    Substring,
+}
+
+/// Lowercase with diacritics removed (NFD, then combining marks dropped) and whitespace collapsed
+pub fn fold_diacritics(value: &str) -> String {
+    let folded: String = value
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn digits(value: &str) -> String {
+    value.chars().filter(char::is_ascii_digit).collect()
+}
+
+fn rank_field(field: &str, query: &str) -> Option<MatchRank> {
+    if field.is_empty() || query.is_empty() {
+        return None;
+    }
+    if field == query {
+        Some(MatchRank::Exact)
+    } else if field.starts_with(query)
+        || field.split([' ', '-', '@', '.']).any(|word| word.starts_with(query))
+    {
+        Some(MatchRank::Prefix)
+    } else if field.contains(query) {
+        Some(MatchRank::Substring)
+    } else {
+        None
+    }
+}
+
+/// Best rank of `client` for an already folded query, or `None` if nothing matches. Phone
+/// numbers are compared on their digits only.
+pub fn match_rank(client: &Client, folded_query: &str) -> Option<MatchRank> {
+    let profile = &client.profile;
+    let text_fields = [
+        format!("{} {}", profile.first_name, profile.last_name),
+        profile.first_name.clone(),
+        profile.last_name.clone(),
+        client.email.clone().unwrap_or_default(),
+    ];
+    let text_rank = text_fields.iter().filter_map(|field| rank_field(&fold_diacritics(field), folded_query)).min();
+
+    let query_digits = digits(folded_query);
+    let phone_rank = client
+        .phone
+        .as_deref()
+        .filter(|_| query_digits.len() >= 3)
+        .and_then(|phone| rank_field(&digits(phone), &query_digits));
+
+    text_rank.into_iter().chain(phone_rank).min()
+}
+
+/// Clients matching `query`, exact matches first, then prefix, then substring; by name within a rank
+pub fn search_clients(clients: Vec<Client>, query: &str, limit: usize) -> Vec<Client> {
+    let folded_query = fold_diacritics(query);
+    let mut ranked: Vec<(MatchRank, Client)> = clients
+        .into_iter()
+        .filter_map(|client| match_rank(&client, &folded_query).map(|rank| (rank, client)))
+        .collect();
+    ranked.sort_by(|(rank_a, a), (rank_b, b)| {
+        rank_a
+            .cmp(rank_b)
+            .then_with(|| compare_profile_names(&a.profile, &b.profile))
+            .then_with(|| a.object_id.cmp(&b.object_id))
+    });
+    ranked.into_iter().take(limit).map(|(_, client)| client).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AddressObject, CreateClientRequest};
+
+    fn client(id: &str, first: &str, last: &str, email: &str, phone: &str) -> Client {
+        Client::from_request(
+            CreateClientRequest {
+                user_id: format!("user-{}", id),
+                first_name: first.to_string(),
+                last_name: last.to_string(),
+                email: email.to_string(),
+                phone: phone.to_string(),
+                date_of_birth: None,
+                ramq_number: None,
+                address: AddressObject {
+                    street: "123 Rue Principale".to_string(),
+                    city: "Montreal".to_string(),
+                    state: "QC".to_string(),
+                    zip_code: "H2X 1Y4".to_string(),
+                    country: "Canada".to_string(),
+                },
+                spoken_languages: vec![1],
+                search_radius: None,
+                preferences: None,
+                emergency_contacts: None,
+            },
+            id.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_fold_diacritics() {
+        assert_eq!(fold_diacritics("Léger"), "leger");
+        assert_eq!(fold_diacritics("élève"), "eleve");
+        assert_eq!(fold_diacritics("  Hélène   CÔTÉ-Roy "), "helene cote-roy");
+    }
+
+    #[test]
+    fn test_search_ignores_accents_and_ranks_matches() {
+        let clients = vec![
+            client("c1", "Sophie", "Desléger", "sophie.d@example.com", "514-555-0101"),
+            client("c2", "Marc", "Léger", "marc@example.com", "(438) 555-0199"),
+            client("c3", "Léa", "Légerette", "lea@example.com", "450 555 0123"),
+            client("c4", "Jean", "Tremblay", "jt@example.com", "514 555 0142"),
+            client("c5", "Élève", "Stagiaire", "stage@example.com", "514 555 0177"),
+        ];
+
+        let found = search_clients(clients.clone(), "Leger", 10);
+        let ids: Vec<&str> = found.iter().map(|c| c.object_id.as_str()).collect();
+        // Exact last name, then a name starting with it, then one containing it
+        assert_eq!(ids, ["c2", "c3", "c1"]);
+        // Results keep their accents
+        assert_eq!(found[0].profile.last_name, "Léger");
+
+        let found = search_clients(clients.clone(), "eleve", 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].profile.first_name, "Élève");
+
+        let found = search_clients(clients.clone(), "438555", 10);
+        assert_eq!(found[0].object_id, "c2");
+        assert_eq!(search_clients(clients, "jt@example.com", 1)[0].object_id, "c4");
+    }
+}
@@ -0,0 +1,283 @@
+// Command Latency Profiler for PsyPsy CMS
+// Opt-in profiling mode: the webview times every command end to end and backend stages (audit
+// write, note encryption, RBAC) are timed in place. Only names and durations are kept, never
+// arguments or results, so samples cannot carry PHI. When off, a timer costs one atomic load.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Samples kept per command or stage; older ones are dropped
+const SAMPLES_PER_LABEL: usize = 1_000;
+/// Upper bound on distinct labels, so a misbehaving caller cannot grow the table without limit
+const MAX_LABELS: usize = 500;
+
+static PROFILER: Lazy<CommandProfiler> = Lazy::new(CommandProfiler::new);
+
+/// Process-wide profiler
+pub fn profiler() -> &'static CommandProfiler {
+    &PROFILER
+}
+
+/// Where a sample was measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleKind {
+    /// Whole command, measured in the webview from invoke to response
+    Command,
+    /// Backend step inside a command
+    Stage,
+}
+
+#[derive(Debug, Default)]
+struct LabelSamples {
+    durations: VecDeque<Duration>,
+    errors: u64,
+    total: u64,
+}
+
+#[derive(Debug)]
+struct ProfilerData {
+    started_at: DateTime<Utc>,
+    labels: HashMap<(SampleKind, String), LabelSamples>,
+}
+
+pub struct CommandProfiler {
+    enabled: AtomicBool,
+    data: Mutex<ProfilerData>,
+}
+
+/// Percentile latencies for one command or stage, in milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub name: String,
+    pub kind: SampleKind,
+    /// Samples recorded since the last reset, including ones no longer retained
+    pub count: u64,
+    pub errors: u64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceReport {
+    pub enabled: bool,
+    pub since: DateTime<Utc>,
+    /// Slowest p95 first
+    pub commands: Vec<LatencyStats>,
+    pub stages: Vec<LatencyStats>,
+}
+
+/// Nearest-rank percentile of sorted durations
+fn percentile_ms(sorted: &[Duration], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1].as_secs_f64() * 1000.0
+}
+
+/// Command and stage names are identifiers like `get_client` or `plugin:shell|open`; anything
+/// else is refused so free text (and with it PHI) never becomes a label
+pub fn is_valid_label(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 80
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '|' | '-'))
+}
+
+impl CommandProfiler {
+    fn new() -> Self {
+        let enabled = std::env::var("PROFILING_ENABLED")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Self {
+            enabled: AtomicBool::new(enabled),
+            data: Mutex::new(ProfilerData {
+                started_at: Utc::now(),
+                labels: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Start timing a backend stage; `None` (and no work) while profiling is off
+    pub fn time(&self, stage: &'static str) -> Option<PerformanceTimer> {
+        self.is_enabled().then(|| PerformanceTimer {
+            start: Instant::now(),
+            operation: stage,
+        })
+    }
+
+    /// Record one sample; ignored while profiling is off or if the label is not an identifier
+    pub fn record(&self, kind: SampleKind, name: &str, duration: Duration, ok: bool) {
+        if !self.is_enabled() || !is_valid_label(name) {
+            return;
+        }
+        let mut data = self.data.lock().unwrap();
+        let key = (kind, name.to_string());
+        if !data.labels.contains_key(&key) && data.labels.len() >= MAX_LABELS {
+            return;
+        }
+        let samples = data.labels.entry(key).or_default();
+        if samples.durations.len() == SAMPLES_PER_LABEL {
+            samples.durations.pop_front();
+        }
+        samples.durations.push_back(duration);
+        samples.total += 1;
+        if !ok {
+            samples.errors += 1;
+        }
+    }
+
+    pub fn reset(&self) {
+        let mut data = self.data.lock().unwrap();
+        data.labels.clear();
+        data.started_at = Utc::now();
+    }
+
+    pub fn report(&self) -> PerformanceReport {
+        let data = self.data.lock().unwrap();
+        let mut commands = Vec::new();
+        let mut stages = Vec::new();
+        for ((kind, name), samples) in &data.labels {
+            let mut sorted: Vec<Duration> = samples.durations.iter().copied().collect();
+            sorted.sort();
+            let stats = LatencyStats {
+                name: name.clone(),
+                kind: *kind,
+                count: samples.total,
+                errors: samples.errors,
+                p50_ms: percentile_ms(&sorted, 50.0),
+                p90_ms: percentile_ms(&sorted, 90.0),
+                p95_ms: percentile_ms(&sorted, 95.0),
+                p99_ms: percentile_ms(&sorted, 99.0),
+                max_ms: sorted.last().map_or(0.0, |d| d.as_secs_f64() * 1000.0),
+            };
+            match kind {
+                SampleKind::Command => commands.push(stats),
+                SampleKind::Stage => stages.push(stats),
+            }
+        }
+        for list in [&mut commands, &mut stages] {
+            list.sort_by(|a, b| b.p95_ms.total_cmp(&a.p95_ms));
+        }
+        PerformanceReport {
+            enabled: self.is_enabled(),
+            since: data.started_at,
+            commands,
+            stages,
+        }
+    }
+}
+
+/// Times a backend stage and records it when dropped, following the test suite's
+/// `performance_utils::PerformanceTimer`
+pub struct PerformanceTimer {
+    start: Instant,
+    operation: &'static str,
+}
+
+impl Drop for PerformanceTimer {
+    fn drop(&mut self) {
+        profiler().record(SampleKind::Stage, self.operation, self.start.elapsed(), true);
+    }
+}
+
+/// Script that wraps the webview's invoke with timing. Samples are batched and sent to
+/// `record_command_latencies`; only the command name, duration and outcome leave the page.
+pub fn profiler_injection_script(enabled: bool) -> String {
+    format!(
+        r#"
+    (function() {{
+        const internals = window.__TAURI_INTERNALS__;
+        if (!internals) return;
+        if (window.__PSYPSY_PROFILER__) {{
+            window.__PSYPSY_PROFILER__.enabled = {enabled};
+            return;
+        }}
+        const profiler = {{ enabled: {enabled}, pending: [], timer: null }};
+        window.__PSYPSY_PROFILER__ = profiler;
+        const originalInvoke = internals.invoke;
+
+        function flush() {{
+            profiler.timer = null;
+            if (!profiler.pending.length) return;
+            const samples = profiler.pending.splice(0, profiler.pending.length);
+            originalInvoke.call(internals, 'record_command_latencies', {{ samples }}).catch(function() {{}});
+        }}
+
+        internals.invoke = function(cmd, args, options) {{
+            if (!profiler.enabled || cmd === 'record_command_latencies') {{
+                return originalInvoke.call(internals, cmd, args, options);
+            }}
+            const start = performance.now();
+            const done = function(ok) {{
+                profiler.pending.push({{ command: cmd, durationMs: performance.now() - start, ok: ok }});
+                if (!profiler.timer) profiler.timer = setTimeout(flush, 2000);
+            }};
+            return originalInvoke.call(internals, cmd, args, options).then(
+                function(result) {{ done(true); return result; }},
+                function(error) {{ done(false); throw error; }}
+            );
+        }};
+    }})();
+    "#,
+        enabled = enabled
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_disabled_recording() {
+        let profiler = CommandProfiler::new();
+        profiler.set_enabled(false);
+        profiler.record(SampleKind::Command, "get_client", Duration::from_millis(5), true);
+        assert!(profiler.report().commands.is_empty());
+
+        profiler.set_enabled(true);
+        for ms in 1..=100 {
+            profiler.record(SampleKind::Command, "get_client", Duration::from_millis(ms), ms % 10 != 0);
+        }
+        profiler.record(SampleKind::Command, "Jean Tremblay, RAMQ TREM 8501", Duration::from_millis(1), true);
+
+        let report = profiler.report();
+        assert_eq!(report.commands.len(), 1);
+        let stats = &report.commands[0];
+        assert_eq!((stats.count, stats.errors), (100, 10));
+        assert_eq!(stats.p50_ms, 50.0);
+        assert_eq!(stats.p99_ms, 99.0);
+        assert_eq!(stats.max_ms, 100.0);
+    }
+
+    #[test]
+    fn test_samples_are_bounded() {
+        let profiler = CommandProfiler::new();
+        profiler.set_enabled(true);
+        for _ in 0..(SAMPLES_PER_LABEL + 50) {
+            profiler.record(SampleKind::Stage, "audit.write", Duration::from_millis(1), true);
+        }
+        let data = profiler.data.lock().unwrap();
+        let samples = &data.labels[&(SampleKind::Stage, "audit.write".to_string())];
+        assert_eq!(samples.durations.len(), SAMPLES_PER_LABEL);
+        assert_eq!(samples.total, (SAMPLES_PER_LABEL + 50) as u64);
+    }
+}
@@ -0,0 +1,171 @@
+// Dashboard Aggregate Cache for PsyPsy CMS
+// Dashboard figures change slowly but are recomputed from whole collections, so each one is kept
+// for a short TTL. Entries are keyed by the caller's data scope as well as the figure, since a
+// provider's caseload numbers must never be served to a practice-wide role or the reverse.
+// Writes to clients, professionals or appointments drop the figures built from them.
+
+use crate::security::data_scope::DataScope;
+use chrono::{DateTime, Duration, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Default time a cached figure is served for
+const DEFAULT_TTL_SECONDS: i64 = 60;
+
+/// Dashboard figure kinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardStat {
+    Overview,
+    Clients,
+    Professionals,
+    Appointments,
+}
+
+/// Collections dashboard figures are computed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardEntity {
+    Client,
+    Professional,
+    Appointment,
+}
+
+impl DashboardStat {
+    fn depends_on(&self, entity: DashboardEntity) -> bool {
+        match self {
+            DashboardStat::Overview => true,
+            DashboardStat::Clients => entity == DashboardEntity::Client,
+            // Caseload scopes are resolved through professional records
+            DashboardStat::Professionals => entity == DashboardEntity::Professional,
+            DashboardStat::Appointments => entity == DashboardEntity::Appointment,
+        }
+    }
+}
+
+/// Cache key part for a scope; caseloads are keyed by their exact professional IDs
+fn scope_key(scope: &DataScope) -> String {
+    match scope {
+        DataScope::Caseload(ids) => {
+            let mut ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+            ids.sort_unstable();
+            format!("caseload:{}", ids.join(","))
+        }
+        other => other.label().to_string(),
+    }
+}
+
+struct CachedStat {
+    computed_at: DateTime<Utc>,
+    value: serde_json::Value,
+}
+
+/// Managed cache of computed dashboard figures
+pub struct DashboardCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<(DashboardStat, String), CachedStat>>,
+}
+
+impl Default for DashboardCache {
+    fn default() -> Self {
+        Self::new(Duration::seconds(DEFAULT_TTL_SECONDS))
+    }
+}
+
+impl DashboardCache {
+    /// A TTL of zero disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Default TTL, overridden by `DASHBOARD_CACHE_TTL_SECONDS`
+    pub fn from_env() -> Self {
+        match std::env::var("DASHBOARD_CACHE_TTL_SECONDS") {
+            Ok(value) => match value.parse::<i64>() {
+                Ok(seconds) if seconds >= 0 => Self::new(Duration::seconds(seconds)),
+                _ => {
+                    log::warn!("Ignoring invalid DASHBOARD_CACHE_TTL_SECONDS: {}", value);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// The figure cached for this scope, if still fresh at `now`
+    pub fn get<T: DeserializeOwned>(&self, stat: DashboardStat, scope: &DataScope, now: DateTime<Utc>) -> Option<T> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let cached = entries.get(&(stat, scope_key(scope)))?;
+        if now - cached.computed_at >= self.ttl {
+            return None;
+        }
+        serde_json::from_value(cached.value.clone()).ok()
+    }
+
+    pub fn insert<T: Serialize>(&self, stat: DashboardStat, scope: &DataScope, value: &T, now: DateTime<Utc>) {
+        if self.ttl <= Duration::zero() {
+            return;
+        }
+        let Ok(value) = serde_json::to_value(value) else {
+            return;
+        };
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, cached| now - cached.computed_at < self.ttl);
+        entries.insert((stat, scope_key(scope)), CachedStat { computed_at: now, value });
+    }
+
+    /// Drop every figure computed from `entity`, or everything when `None`; returns how many
+    /// entries were dropped
+    pub fn invalidate(&self, entity: Option<DashboardEntity>) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        entries.retain(|(stat, _), _| entity.is_some_and(|entity| !stat.depends_on(entity)));
+        before - entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn caseload(ids: &[&str]) -> DataScope {
+        DataScope::Caseload(ids.iter().map(|id| id.to_string()).collect::<HashSet<_>>())
+    }
+
+    #[test]
+    fn test_entries_are_per_scope_and_expire() {
+        let cache = DashboardCache::new(Duration::seconds(60));
+        let now = Utc::now();
+        cache.insert(DashboardStat::Clients, &DataScope::Practice, &120u32, now);
+        cache.insert(DashboardStat::Clients, &caseload(&["prof-2", "prof-1"]), &7u32, now);
+
+        assert_eq!(cache.get::<u32>(DashboardStat::Clients, &DataScope::Practice, now), Some(120));
+        assert_eq!(cache.get::<u32>(DashboardStat::Clients, &caseload(&["prof-1", "prof-2"]), now), Some(7));
+        // Another provider's caseload and another scope never see these figures
+        assert_eq!(cache.get::<u32>(DashboardStat::Clients, &caseload(&["prof-3"]), now), None);
+        assert_eq!(cache.get::<u32>(DashboardStat::Clients, &DataScope::Billing, now), None);
+
+        let later = now + Duration::seconds(60);
+        assert_eq!(cache.get::<u32>(DashboardStat::Clients, &DataScope::Practice, later), None);
+    }
+
+    #[test]
+    fn test_mutation_invalidates_dependent_stats_only() {
+        let cache = DashboardCache::default();
+        let now = Utc::now();
+        for stat in [DashboardStat::Overview, DashboardStat::Clients, DashboardStat::Appointments] {
+            cache.insert(stat, &DataScope::Practice, &1u32, now);
+        }
+
+        // A client was created or updated
+        assert_eq!(cache.invalidate(Some(DashboardEntity::Client)), 2);
+        assert_eq!(cache.get::<u32>(DashboardStat::Clients, &DataScope::Practice, now), None);
+        assert_eq!(cache.get::<u32>(DashboardStat::Overview, &DataScope::Practice, now), None);
+        assert_eq!(cache.get::<u32>(DashboardStat::Appointments, &DataScope::Practice, now), Some(1));
+
+        assert_eq!(cache.invalidate(None), 1);
+    }
+}
@@ -0,0 +1,209 @@
+// Emergency Data Lock for PsyPsy CMS
+// Last-resort control for device theft or duress: drops the note store's master key, revokes every
+// session and refuses PHI access until the owner re-authenticates with password and MFA.
+// Encrypted data on disk is left untouched; unlike logout, a normal login cannot lift the lock.
+
+use crate::commands::medical_notes_commands::StorageState;
+use crate::security::auth::AuthState;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::RwLock;
+
+/// Event emitted to every window when the data lock engages
+pub const DATA_LOCKED_EVENT: &str = "data-locked";
+
+/// Data lock settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataLockConfig {
+    /// Allow the panic lock at all
+    pub enabled: bool,
+    /// Deactivate "Remember Me" sessions so a restart cannot sign back in
+    pub clear_remembered_sessions: bool,
+}
+
+impl Default for DataLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            clear_remembered_sessions: true,
+        }
+    }
+}
+
+impl DataLockConfig {
+    /// Defaults with `PANIC_LOCK_*` environment overrides
+    pub fn from_env() -> Self {
+        let flag = |name: &str, default: bool| {
+            std::env::var(name)
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(default)
+        };
+        let defaults = Self::default();
+        Self {
+            enabled: flag("PANIC_LOCK_ENABLED", defaults.enabled),
+            clear_remembered_sessions: flag("PANIC_LOCK_CLEAR_REMEMBERED_SESSIONS", defaults.clear_remembered_sessions),
+        }
+    }
+}
+
+/// Current lock, as shown to the UI and written to the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataLockStatus {
+    pub locked: bool,
+    pub locked_at: Option<DateTime<Utc>>,
+    /// User whose session was active when the lock engaged; only they can lift it
+    pub owner: Option<String>,
+    /// What engaged the lock (command, shortcut, ...)
+    pub trigger: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct DataLock {
+    locked_at: DateTime<Utc>,
+    owner: Option<String>,
+    trigger: String,
+}
+
+/// Whether the data lock is engaged
+#[derive(Debug, Clone, Default)]
+pub struct DataLockState(Arc<std::sync::RwLock<Option<DataLock>>>);
+
+impl DataLockState {
+    /// Engage the lock; returns false if it was already engaged
+    pub fn engage(&self, owner: Option<String>, trigger: &str) -> bool {
+        let mut lock = self.0.write().unwrap();
+        if lock.is_some() {
+            return false;
+        }
+        *lock = Some(DataLock {
+            locked_at: Utc::now(),
+            owner,
+            trigger: trigger.to_string(),
+        });
+        true
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.0.read().unwrap().is_some()
+    }
+
+    pub fn owner(&self) -> Option<String> {
+        self.0.read().unwrap().as_ref().and_then(|l| l.owner.clone())
+    }
+
+    pub fn release(&self) {
+        self.0.write().unwrap().take();
+    }
+
+    pub fn status(&self) -> DataLockStatus {
+        let lock = self.0.read().unwrap();
+        DataLockStatus {
+            locked: lock.is_some(),
+            locked_at: lock.as_ref().map(|l| l.locked_at),
+            owner: lock.as_ref().and_then(|l| l.owner.clone()),
+            trigger: lock.as_ref().map(|l| l.trigger.clone()),
+        }
+    }
+
+    /// Refuse an operation while the lock is engaged
+    pub fn ensure_unlocked(&self) -> Result<(), String> {
+        if self.is_locked() {
+            return Err("All data is locked; unlock with password and MFA to continue".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Deactivate every remembered session on this device
+fn deactivate_remembered_sessions(app: &AppHandle) -> Result<usize, String> {
+    let db_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("psypsy_sessions.db");
+    if !db_path.exists() {
+        return Ok(0);
+    }
+    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
+    conn.execute("UPDATE user_sessions SET is_active = FALSE WHERE is_active = TRUE", [])
+        .map_err(|e| e.to_string())
+}
+
+/// Engage the data lock. The lock flag, key purge and session revocation happen before any
+/// disk or network I/O, so PHI access stops even if auditing is slow or offline.
+pub async fn engage_data_lock(app: &AppHandle, trigger: &str) -> DataLockStatus {
+    let lock_state = app.state::<DataLockState>();
+    let auth_state = app.state::<Arc<RwLock<AuthState>>>();
+
+    let owner = auth_state.read().await.user_id.clone();
+    if !lock_state.engage(owner.clone(), trigger) {
+        return lock_state.status();
+    }
+
+    // Dropping the storage zeroizes its master key; notes stay encrypted on disk
+    let key_purged = app.state::<StorageState>().lock().await.take().is_some();
+    auth_state.write().await.revoke();
+
+    let status = lock_state.status();
+    log::warn!("Data lock engaged ({})", trigger);
+    if let Err(e) = app.emit(DATA_LOCKED_EVENT, &status) {
+        log::warn!("Failed to notify UI of data lock: {}", e);
+    }
+
+    let sessions_cleared = if app.state::<DataLockConfig>().clear_remembered_sessions {
+        deactivate_remembered_sessions(app).unwrap_or_else(|e| {
+            log::error!("Failed to deactivate remembered sessions: {}", e);
+            0
+        })
+    } else {
+        0
+    };
+
+    let firebase_state = app.state::<FirebaseServiceState>();
+    let firebase_guard = firebase_state.0.lock().await;
+    match firebase_guard.as_ref() {
+        Some(firebase) => {
+            if let Err(e) = firebase.audit_log(
+                "DATA_LOCK_ENGAGED",
+                "authentication",
+                owner.as_deref().unwrap_or("anonymous"),
+                false,
+                Some(serde_json::json!({
+                    "trigger": trigger,
+                    "locked_at": status.locked_at,
+                    "master_key_purged": key_purged,
+                    "remembered_sessions_cleared": sessions_cleared,
+                }))
+            ).await {
+                log::error!("Failed to audit data lock: {}", e);
+            }
+        }
+        None => log::error!("AUDIT: Data lock engaged ({}) with Firebase unavailable", trigger),
+    }
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_refuses_until_released() {
+        let state = DataLockState::default();
+        assert!(state.ensure_unlocked().is_ok());
+
+        assert!(state.engage(Some("clinician-1".to_string()), "command"));
+        assert!(!state.engage(Some("someone-else".to_string()), "shortcut"));
+        assert!(state.ensure_unlocked().is_err());
+        assert_eq!(state.owner().as_deref(), Some("clinician-1"));
+
+        state.release();
+        assert!(state.ensure_unlocked().is_ok());
+        assert!(!state.status().locked);
+    }
+}
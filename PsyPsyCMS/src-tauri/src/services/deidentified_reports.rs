@@ -0,0 +1,399 @@
+// Scheduled De-identified Practice Reports for PsyPsy CMS
+// Aggregates appointment volumes, no-show rates and utilization into counts only, suppresses
+// small cells so no group can be traced back to a patient, and delivers the result through the
+// notifier on a schedule. Every run, including skipped ones, is recorded and audited.
+
+use crate::models::{Appointment, AppointmentStatus, MeetingPreference};
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use crate::services::notifier::{Notification, NotifierState};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Upper bound on appointments pulled per run
+const MAX_REPORT_APPOINTMENTS: u32 = 10_000;
+
+/// Runs kept in memory for review
+const MAX_RECORDED_RUNS: usize = 100;
+
+/// Actor recorded in the audit trail for scheduled runs
+const REPORT_ACTOR: &str = "system:deidentified-reports";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeidentifiedReportConfig {
+    pub enabled: bool,
+    pub interval_hours: u64,
+    /// Days of appointments covered by each report, ending at the run
+    pub period_days: i64,
+    /// Groups with fewer appointments than this are suppressed
+    pub min_cell_size: u64,
+    /// Below this many appointments in the period the run is skipped entirely
+    pub min_total_appointments: usize,
+    pub recipients: Vec<String>,
+}
+
+impl Default for DeidentifiedReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24 * 7,
+            period_days: 30,
+            min_cell_size: 11,
+            min_total_appointments: 30,
+            recipients: Vec::new(),
+        }
+    }
+}
+
+impl DeidentifiedReportConfig {
+    /// Defaults with `DEID_REPORT_ENABLED`, `DEID_REPORT_INTERVAL_HOURS`, `DEID_REPORT_PERIOD_DAYS`,
+    /// `DEID_REPORT_MIN_CELL_SIZE`, `DEID_REPORT_MIN_TOTAL` and `DEID_REPORT_RECIPIENTS`
+    /// (comma-separated) overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("DEID_REPORT_ENABLED") {
+            config.enabled = !matches!(value.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no" | "off");
+        }
+        fn parse<T: std::str::FromStr>(name: &str, target: &mut T) {
+            if let Ok(value) = std::env::var(name) {
+                match value.trim().parse() {
+                    Ok(v) => *target = v,
+                    Err(_) => log::warn!("Ignoring invalid {} '{}'", name, value),
+                }
+            }
+        }
+        parse("DEID_REPORT_INTERVAL_HOURS", &mut config.interval_hours);
+        parse("DEID_REPORT_PERIOD_DAYS", &mut config.period_days);
+        parse("DEID_REPORT_MIN_CELL_SIZE", &mut config.min_cell_size);
+        parse("DEID_REPORT_MIN_TOTAL", &mut config.min_total_appointments);
+        if config.min_cell_size < 2 {
+            log::warn!("DEID_REPORT_MIN_CELL_SIZE below 2 offers no protection; using 2");
+            config.min_cell_size = 2;
+        }
+        if let Ok(recipients) = std::env::var("DEID_REPORT_RECIPIENTS") {
+            config.recipients = recipients
+                .split(',')
+                .map(str::trim)
+                .filter(|r| !r.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        config
+    }
+}
+
+/// Aggregate figures for one period; counts of `None` were suppressed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeidentifiedReport {
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub total_appointments: u64,
+    pub by_status: BTreeMap<String, Option<u64>>,
+    pub by_service_type: BTreeMap<String, Option<u64>>,
+    pub by_meeting_mode: BTreeMap<String, Option<u64>>,
+    /// Percentage of attended-or-missed sessions that were missed
+    pub no_show_rate: Option<f64>,
+    /// Minutes actually delivered as a percentage of minutes booked
+    pub utilization_rate: Option<f64>,
+    pub suppressed_cells: usize,
+    pub min_cell_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "outcome")]
+pub enum ReportRunOutcome {
+    Delivered { recipients: usize },
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRun {
+    pub id: Uuid,
+    pub ran_at: DateTime<Utc>,
+    pub outcome: ReportRunOutcome,
+    #[serde(default)]
+    pub suppressed_cells: usize,
+}
+
+/// History of report runs
+#[derive(Default)]
+pub struct DeidentifiedReportState(Mutex<VecDeque<ReportRun>>);
+
+impl DeidentifiedReportState {
+    pub fn record(&self, run: ReportRun) {
+        let mut runs = self.0.lock().unwrap();
+        if runs.len() == MAX_RECORDED_RUNS {
+            runs.pop_front();
+        }
+        runs.push_back(run);
+    }
+
+    /// Most recent first
+    pub fn runs(&self) -> Vec<ReportRun> {
+        self.0.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
+
+/// Suppress every non-zero cell below `min`. When that hides exactly one cell, the next
+/// smallest is hidden too so the suppressed value cannot be recovered from the total.
+pub fn suppress_small_cells(counts: BTreeMap<String, u64>, min: u64) -> (BTreeMap<String, Option<u64>>, usize) {
+    let mut hidden: Vec<String> = counts
+        .iter()
+        .filter(|(_, &count)| count > 0 && count < min)
+        .map(|(key, _)| key.clone())
+        .collect();
+    if hidden.len() == 1 {
+        if let Some((key, _)) = counts
+            .iter()
+            .filter(|(key, &count)| count > 0 && !hidden.contains(key))
+            .min_by_key(|(_, &count)| count)
+        {
+            hidden.push(key.clone());
+        }
+    }
+    let suppressed = hidden.len();
+    let cells = counts
+        .into_iter()
+        .map(|(key, count)| {
+            let value = if hidden.contains(&key) { None } else { Some(count) };
+            (key, value)
+        })
+        .collect();
+    (cells, suppressed)
+}
+
+fn status_label(status: &AppointmentStatus) -> &'static str {
+    match status {
+        AppointmentStatus::Pending => "pending",
+        AppointmentStatus::Confirmed => "confirmed",
+        AppointmentStatus::InProgress => "in_progress",
+        AppointmentStatus::Completed => "completed",
+        AppointmentStatus::Cancelled => "cancelled",
+        AppointmentStatus::NoShow => "no_show",
+    }
+}
+
+fn meeting_label(preference: &MeetingPreference) -> &'static str {
+    match preference {
+        MeetingPreference::InPerson => "in_person",
+        MeetingPreference::Online => "online",
+        MeetingPreference::Both => "either",
+    }
+}
+
+fn rate(part: u64, whole: u64, min: u64) -> Option<f64> {
+    // A rate over a small denominator says too much about the few people in it
+    (whole >= min).then(|| part as f64 * 100.0 / whole as f64)
+}
+
+/// Build the report for appointments starting in `[now - period_days, now)`.
+/// Only enum labels and counts leave this function; no identifiers, notes or exact dates.
+pub fn build_report(
+    appointments: &[Appointment],
+    config: &DeidentifiedReportConfig,
+    now: DateTime<Utc>,
+) -> Result<DeidentifiedReport, String> {
+    let start = now - Duration::days(config.period_days.max(1));
+    let in_period: Vec<&Appointment> = appointments
+        .iter()
+        .filter(|a| a.scheduled_start().is_some_and(|t| t >= start && t < now))
+        .collect();
+    if in_period.len() < config.min_total_appointments {
+        return Err(format!(
+            "insufficient data: {} appointments in period, {} required",
+            in_period.len(),
+            config.min_total_appointments
+        ));
+    }
+
+    let mut by_status = BTreeMap::new();
+    let mut by_service_type = BTreeMap::new();
+    let mut by_meeting_mode = BTreeMap::new();
+    let (mut booked_minutes, mut delivered_minutes) = (0i64, 0i64);
+    for appointment in &in_period {
+        *by_status.entry(status_label(&appointment.status).to_string()).or_insert(0u64) += 1;
+        *by_service_type.entry(format!("service_{}", appointment.service_type)).or_insert(0u64) += 1;
+        *by_meeting_mode.entry(meeting_label(&appointment.meet_pref).to_string()).or_insert(0u64) += 1;
+        if appointment.status != AppointmentStatus::Cancelled {
+            booked_minutes += appointment.scheduled_duration().num_minutes();
+        }
+        if appointment.status == AppointmentStatus::Completed {
+            delivered_minutes += appointment
+                .actual_duration
+                .map(i64::from)
+                .unwrap_or_else(|| appointment.scheduled_duration().num_minutes());
+        }
+    }
+
+    let min = config.min_cell_size;
+    let completed = by_status.get("completed").copied().unwrap_or(0);
+    let no_shows = by_status.get("no_show").copied().unwrap_or(0);
+    let billable = in_period.len() as u64 - by_status.get("cancelled").copied().unwrap_or(0);
+    let (by_status, status_hidden) = suppress_small_cells(by_status, min);
+    let (by_service_type, service_hidden) = suppress_small_cells(by_service_type, min);
+    let (by_meeting_mode, mode_hidden) = suppress_small_cells(by_meeting_mode, min);
+
+    Ok(DeidentifiedReport {
+        period_start: start.date_naive(),
+        period_end: now.date_naive(),
+        total_appointments: in_period.len() as u64,
+        by_status,
+        by_service_type,
+        by_meeting_mode,
+        no_show_rate: rate(no_shows, completed + no_shows, min),
+        utilization_rate: (billable >= min && booked_minutes > 0)
+            .then(|| delivered_minutes as f64 * 100.0 / booked_minutes as f64),
+        suppressed_cells: status_hidden + service_hidden + mode_hidden,
+        min_cell_size: min,
+    })
+}
+
+/// Compute, deliver and record one report
+pub async fn run_report(app: &AppHandle, config: &DeidentifiedReportConfig) -> ReportRun {
+    let mut run = ReportRun {
+        id: Uuid::new_v4(),
+        ran_at: Utc::now(),
+        outcome: ReportRunOutcome::Skipped { reason: "no recipients configured".to_string() },
+        suppressed_cells: 0,
+    };
+
+    let firebase_state = app.state::<FirebaseServiceState>();
+    let firebase_guard = firebase_state.0.lock().await;
+    let Some(firebase) = firebase_guard.as_ref() else {
+        run.outcome = ReportRunOutcome::Skipped { reason: "Firebase service not initialized".to_string() };
+        log::warn!("De-identified report {} skipped: Firebase service not initialized", run.id);
+        app.state::<DeidentifiedReportState>().record(run.clone());
+        return run;
+    };
+
+    if !config.recipients.is_empty() {
+        run.outcome = match firebase.query_documents::<Appointment>("appointments", 1, MAX_REPORT_APPOINTMENTS).await {
+            Err(e) => ReportRunOutcome::Failed { error: e.to_string() },
+            Ok(appointments) => match build_report(&appointments, config, run.ran_at) {
+                Err(reason) => {
+                    log::info!("De-identified report {} skipped: {}", run.id, reason);
+                    ReportRunOutcome::Skipped { reason }
+                }
+                Ok(report) => {
+                    run.suppressed_cells = report.suppressed_cells;
+                    deliver(app, config, &report).await
+                }
+            },
+        };
+    }
+
+    if let Err(e) = firebase.audit_log(
+        "DEIDENTIFIED_REPORT_RUN",
+        "deidentified_report",
+        REPORT_ACTOR,
+        false,
+        Some(serde_json::json!({
+            "run_id": run.id,
+            "outcome": run.outcome,
+            "suppressed_cells": run.suppressed_cells,
+            "min_cell_size": config.min_cell_size,
+        }))
+    ).await {
+        log::error!("Failed to audit de-identified report run {}: {}", run.id, e);
+    }
+    app.state::<DeidentifiedReportState>().record(run.clone());
+    run
+}
+
+async fn deliver(app: &AppHandle, config: &DeidentifiedReportConfig, report: &DeidentifiedReport) -> ReportRunOutcome {
+    let body = match serde_json::to_string_pretty(report) {
+        Ok(body) => body,
+        Err(e) => return ReportRunOutcome::Failed { error: e.to_string() },
+    };
+    let notifier = app.state::<NotifierState>().0.clone();
+    let subject = format!("Practice report {} to {}", report.period_start, report.period_end);
+    let mut failures = Vec::new();
+    for recipient in &config.recipients {
+        let notification = Notification {
+            recipient: recipient.clone(),
+            category: "deidentified_report".to_string(),
+            subject: subject.clone(),
+            body: body.clone(),
+        };
+        if let Err(e) = notifier.deliver(&notification).await {
+            failures.push(format!("{}: {}", recipient, e));
+        }
+    }
+    if failures.is_empty() {
+        ReportRunOutcome::Delivered { recipients: config.recipients.len() }
+    } else {
+        ReportRunOutcome::Failed { error: failures.join("; ") }
+    }
+}
+
+/// Run the report on the configured schedule
+pub fn start_deidentified_report_job(app: AppHandle, config: DeidentifiedReportConfig) {
+    if !config.enabled {
+        log::info!("Scheduled de-identified reports disabled by configuration");
+        return;
+    }
+    if config.recipients.is_empty() {
+        log::info!("No recipients configured; scheduled de-identified reports not started");
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let interval = std::time::Duration::from_secs(config.interval_hours.max(1) * 3600);
+        loop {
+            tokio::time::sleep(interval).await;
+            run_report(&app, &config).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreateAppointmentRequest, GenderPreference};
+
+    #[test]
+    fn test_single_small_cell_forces_complementary_suppression() {
+        let counts: BTreeMap<String, u64> =
+            [("completed", 40), ("cancelled", 15), ("no_show", 3), ("pending", 0)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+        let (cells, suppressed) = suppress_small_cells(counts, 11);
+
+        assert_eq!(suppressed, 2);
+        assert_eq!(cells["no_show"], None);
+        assert_eq!(cells["cancelled"], None);
+        assert_eq!(cells["completed"], Some(40));
+        assert_eq!(cells["pending"], Some(0));
+    }
+
+    #[test]
+    fn test_report_skips_when_data_is_insufficient() {
+        let config = DeidentifiedReportConfig { min_total_appointments: 5, ..DeidentifiedReportConfig::default() };
+        let request = CreateAppointmentRequest {
+            client_id: "client-1".to_string(),
+            prof_types: vec![1],
+            service_type: 2,
+            subcategories: vec![],
+            gender_preference: GenderPreference::None,
+            language_preference: 0,
+            meeting_preference: MeetingPreference::Online,
+            availability: vec![],
+            preferred_date_time: Some((Utc::now() - Duration::days(1)).to_rfc3339()),
+            session_duration: Some(50),
+        };
+        let appointments: Vec<Appointment> = (0..3)
+            .map(|i| Appointment::from_request(request.clone(), format!("appt-{}", i)))
+            .collect();
+
+        let err = build_report(&appointments, &config, Utc::now()).unwrap_err();
+        assert!(err.contains("insufficient data"));
+    }
+}
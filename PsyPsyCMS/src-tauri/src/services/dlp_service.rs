@@ -302,7 +302,7 @@ pub struct DLPService {
 
 impl DLPService {
     pub fn new(config: DLPConfig, db_pool: Pool<Sqlite>) -> Self {
-        let gcp_client = reqwest::Client::builder()
+        let gcp_client = crate::security::outbound::guarded_client_builder()
             .timeout(std::time::Duration::from_secs(config.timeout_seconds))
             .build()
             .ok();
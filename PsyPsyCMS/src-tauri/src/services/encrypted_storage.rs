@@ -1,15 +1,22 @@
 use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce, Key
 };
+use chacha20poly1305::ChaCha20Poly1305;
 use base64::{Engine as _, engine::general_purpose};
 use ring::digest::{Context, SHA256};
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::security::{DataClassification, EncryptionLevel};
+use crate::security::key_escrow::{self, EscrowManifest, KeyShare};
+use crate::security::encryption_coverage::{ArtifactEnvelope, ArtifactKind};
+use crate::services::media_moderation::MediaScanResult;
+use crate::services::phi_detector::phi_detector;
 
 
 #[derive(Debug, thiserror::Error)]
@@ -24,6 +31,8 @@ pub enum EncryptionError {
     KeyDerivation(String),
     #[error("Law 25 compliance violation: {0}")]
     ComplianceViolation(String),
+    #[error("Schema migration failed: {0}")]
+    Migration(#[from] crate::storage::migrations::MigrationError),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,6 +48,22 @@ pub struct MedicalNote {
     pub deidentified: bool,
     pub sync_status: SyncStatus,
     pub quebec_compliance: QuebecComplianceMetadata,
+    /// Queryable fields stored in the clear beside the encrypted body; see `NOTE_FIELD_CLASSIFICATIONS`
+    #[serde(default)]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+    /// Version this copy of the note is; 0 for notes not amended since versioning was added
+    #[serde(default)]
+    pub version: u32,
+}
+
+/// One immutable version in a note's history, without its content
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteVersionSummary {
+    pub version: u32,
+    pub author_id: String,
+    pub created_at: DateTime<Utc>,
+    pub template_type: String,
+    pub content_checksum: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -47,6 +72,8 @@ pub enum SyncStatus {
     Synced,
     Conflict,
     Local,
+    /// Rejected by the server for a reason retrying will not fix
+    SyncFailed,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -65,6 +92,9 @@ pub struct AuditEntry {
     pub user_id: String,
     pub phi_accessed: bool,
     pub ip_address: Option<String>,
+    /// Note version read or written, when the action concerned a single version
+    #[serde(default)]
+    pub note_version: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,6 +102,216 @@ struct EncryptedData {
     nonce: Vec<u8>,
     ciphertext: Vec<u8>,
     checksum: String,
+    /// Envelopes written before levels were recorded are plain AES-256-GCM
+    #[serde(default = "legacy_level")]
+    level: EncryptionLevel,
+    /// Nonce of the inner ChaCha20-Poly1305 layer (Maximum only)
+    #[serde(default)]
+    inner_nonce: Option<Vec<u8>>,
+}
+
+fn legacy_level() -> EncryptionLevel {
+    EncryptionLevel::Strong
+}
+
+/// Weakest level the note store writes, whatever the classification
+const MINIMUM_NOTE_LEVEL: EncryptionLevel = EncryptionLevel::Strong;
+
+/// Envelope format that records its encryption level
+const LEVELED_ENCRYPTION_VERSION: i32 = 2;
+
+/// Clinical notes are PHI unless reclassified
+const DEFAULT_NOTE_CLASSIFICATION: DataClassification = DataClassification::Phi;
+
+/// Encryption level a classification requires in the note store
+fn required_note_level(classification: DataClassification) -> EncryptionLevel {
+    classification.encryption_requirements().max(MINIMUM_NOTE_LEVEL)
+}
+
+fn level_from_str(value: &str) -> Result<EncryptionLevel, EncryptionError> {
+    match value {
+        "None" => Ok(EncryptionLevel::None),
+        "Standard" => Ok(EncryptionLevel::Standard),
+        "Strong" => Ok(EncryptionLevel::Strong),
+        "Medical" => Ok(EncryptionLevel::Medical),
+        "Maximum" => Ok(EncryptionLevel::Maximum),
+        other => Err(EncryptionError::DecryptionFailed(format!("Unknown encryption level: {}", other))),
+    }
+}
+
+fn classification_from_str(value: &str) -> Result<DataClassification, EncryptionError> {
+    match value {
+        "Public" => Ok(DataClassification::Public),
+        "Internal" => Ok(DataClassification::Internal),
+        "Confidential" => Ok(DataClassification::Confidential),
+        "Phi" => Ok(DataClassification::Phi),
+        "MedicalSensitive" => Ok(DataClassification::MedicalSensitive),
+        other => Err(EncryptionError::DecryptionFailed(format!("Unknown classification: {}", other))),
+    }
+}
+
+/// Shape a queryable note field must have, checked on write so filters compare like with like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NoteFieldKind {
+    /// `YYYY-MM-DD`, so string order is date order
+    Date,
+    Text,
+    Number,
+}
+
+/// Note fields that may be stored outside the encrypted body. Any field not listed is PHI and
+/// belongs in `content`.
+const NOTE_FIELD_CLASSIFICATIONS: &[(&str, DataClassification, NoteFieldKind)] = &[
+    ("appointment_date", DataClassification::Internal, NoteFieldKind::Date),
+    ("session_type", DataClassification::Internal, NoteFieldKind::Text),
+    ("duration_minutes", DataClassification::Internal, NoteFieldKind::Number),
+    ("billing_code", DataClassification::Internal, NoteFieldKind::Text),
+];
+
+/// Metadata field holding the appointment date range filters apply to
+pub const APPOINTMENT_DATE_FIELD: &str = "appointment_date";
+
+/// Classification of a note field; unknown fields are PHI
+pub fn note_field_classification(field: &str) -> DataClassification {
+    NOTE_FIELD_CLASSIFICATIONS
+        .iter()
+        .find(|(name, _, _)| *name == field)
+        .map(|(_, classification, _)| *classification)
+        .unwrap_or(DataClassification::Phi)
+}
+
+fn note_field_kind(field: &str) -> Option<NoteFieldKind> {
+    NOTE_FIELD_CLASSIFICATIONS.iter().find(|(name, _, _)| *name == field).map(|(_, _, kind)| *kind)
+}
+
+/// Check every metadata field may be stored in the clear and return the column values to write.
+/// Only Public and Internal fields qualify; a PHI field, or a text value the detector flags,
+/// is refused rather than written unencrypted.
+fn queryable_metadata(metadata: &BTreeMap<String, serde_json::Value>) -> Result<Vec<(String, String)>, EncryptionError> {
+    let mut columns = Vec::with_capacity(metadata.len());
+    for (field, value) in metadata {
+        let classification = note_field_classification(field);
+        let kind = match (classification, note_field_kind(field)) {
+            (DataClassification::Public | DataClassification::Internal, Some(kind)) => kind,
+            _ => {
+                return Err(EncryptionError::ComplianceViolation(format!(
+                    "Note field '{}' is classified {:?} and must stay in the encrypted body", field, classification
+                )))
+            }
+        };
+        let stored = match (kind, value) {
+            (NoteFieldKind::Date, serde_json::Value::String(date))
+                if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok() => date.clone(),
+            (NoteFieldKind::Number, serde_json::Value::Number(number)) => number.to_string(),
+            (NoteFieldKind::Text, serde_json::Value::String(text)) => {
+                if phi_detector().contains_phi(text) {
+                    return Err(EncryptionError::ComplianceViolation(format!(
+                        "Note field '{}' contains PHI and cannot be stored unencrypted", field
+                    )));
+                }
+                text.clone()
+            }
+            _ => {
+                return Err(EncryptionError::ComplianceViolation(format!(
+                    "Note field '{}' must be a {:?} value", field, kind
+                )))
+            }
+        };
+        columns.push((field.clone(), stored));
+    }
+    Ok(columns)
+}
+
+/// Load the queryable fields of a note back into JSON values
+fn load_metadata(conn: &Connection, note_id: &str) -> Result<BTreeMap<String, serde_json::Value>, EncryptionError> {
+    let mut stmt = conn.prepare("SELECT field, value FROM note_metadata WHERE note_id = ?1")?;
+    let rows = stmt.query_map(params![note_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+    let mut metadata = BTreeMap::new();
+    for row in rows {
+        let (field, value) = row?;
+        let value = match note_field_kind(&field) {
+            Some(NoteFieldKind::Number) => serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value)),
+            _ => serde_json::Value::String(value),
+        };
+        metadata.insert(field, value);
+    }
+    Ok(metadata)
+}
+
+/// Filters `list_notes_for_patient` applies in SQL, before any body is decrypted
+#[derive(Debug, Clone, Default)]
+pub struct NoteListFilter {
+    /// Inclusive bounds on the `appointment_date` field; notes without one are excluded when set
+    pub appointment_from: Option<NaiveDate>,
+    pub appointment_to: Option<NaiveDate>,
+}
+
+/// Associated data binding a leveled envelope to its note
+fn note_aad(note_id: &str, patient_id: &str) -> Vec<u8> {
+    format!("psypsy-note|{}|{}", note_id, patient_id).into_bytes()
+}
+
+/// Associated data binding a history envelope to its note and version. Unlike `note_aad` it
+/// leaves out the patient, so history survives a reassignment untouched.
+fn note_version_aad(note_id: &str, version: u32) -> Vec<u8> {
+    format!("psypsy-note-version|{}|{}", note_id, version).into_bytes()
+}
+
+/// Associated data binding a sync record envelope to its note and kind
+fn sync_record_aad(note_id: &str, kind: &str) -> Vec<u8> {
+    format!("psypsy-sync-record|{}|{}", note_id, kind).into_bytes()
+}
+
+/// Version recorded in an audit entry's details, if any
+fn audited_version(details: Option<String>) -> Option<u32> {
+    let details: serde_json::Value = serde_json::from_str(details.as_deref()?).ok()?;
+    details.get("version")?.as_u64().map(|v| v as u32)
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, EncryptionError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| EncryptionError::DecryptionFailed(format!("Date parsing failed: {}", e)))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ReencryptionJobStatus {
+    Running,
+    Completed,
+    CompletedWithFailures,
+}
+
+/// A note that could not be upgraded and needs manual follow-up
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReencryptionFailure {
+    pub note_id: String,
+    pub error: String,
+}
+
+/// Progress of a classification re-encryption run; persisted so it can resume
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReencryptionJob {
+    pub id: String,
+    pub status: ReencryptionJobStatus,
+    pub started_by: String,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Last note ID processed; a resumed run continues after it
+    pub cursor: Option<String>,
+    pub examined: u64,
+    pub upgraded: u64,
+    pub failures: Vec<ReencryptionFailure>,
+}
+
+/// Media attached to a note, with the moderation scan run before it was attached
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteAttachment {
+    pub id: String,
+    pub note_id: String,
+    pub media_ref: String,
+    pub scan: MediaScanResult,
+    pub attached_by: String,
+    pub attached_at: DateTime<Utc>,
 }
 
 pub struct EncryptedNoteStorage {
@@ -79,6 +319,12 @@ pub struct EncryptedNoteStorage {
     master_key: [u8; 32],
 }
 
+impl Drop for EncryptedNoteStorage {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.master_key);
+    }
+}
+
 impl EncryptedNoteStorage {
     /// Initialize encrypted storage with Quebec Law 25 compliance
     pub fn new(app_handle: &AppHandle, passphrase: &str) -> Result<Self, EncryptionError> {
@@ -90,8 +336,11 @@ impl EncryptedNoteStorage {
         std::fs::create_dir_all(&app_data_dir)
             .map_err(|e| EncryptionError::KeyDerivation(format!("Failed to create app data directory: {}", e)))?;
 
-        let db_path = app_data_dir.join("psypsy_notes.db");
+        Self::open(app_data_dir.join("psypsy_notes.db"), passphrase)
+    }
 
+    /// Open storage at an explicit database path
+    pub fn open(db_path: PathBuf, passphrase: &str) -> Result<Self, EncryptionError> {
         // Derive master key from passphrase using PBKDF2-like approach
         let master_key = Self::derive_key(passphrase)?;
 
@@ -102,6 +351,34 @@ impl EncryptedNoteStorage {
         Ok(storage)
     }
 
+    /// Open storage with a master key rebuilt from escrow shares
+    pub fn open_with_key(db_path: PathBuf, master_key: [u8; 32]) -> Result<Self, EncryptionError> {
+        let storage = Self { db_path, master_key };
+        storage.initialize_database()?;
+
+        tracing::info!("Encrypted note storage opened with recovered master key");
+        Ok(storage)
+    }
+
+    /// Default database location under the app data directory
+    pub fn default_db_path(app_handle: &AppHandle) -> Result<PathBuf, EncryptionError> {
+        app_handle
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join("psypsy_notes.db"))
+            .map_err(|e| EncryptionError::KeyDerivation(format!("Unable to resolve app data directory: {}", e)))
+    }
+
+    /// Split the master key into custodian shares (see `security::key_escrow`)
+    pub fn escrow_master_key(
+        &self,
+        threshold: u8,
+        custodians: &[String],
+        created_by: &str,
+    ) -> Result<(EscrowManifest, Vec<KeyShare>), crate::security::SecurityError> {
+        key_escrow::create_escrow(&self.master_key, threshold, custodians, created_by)
+    }
+
     /// Derive encryption key from passphrase
     fn derive_key(passphrase: &str) -> Result<[u8; 32], EncryptionError> {
         let mut context = Context::new(&SHA256);
@@ -117,65 +394,94 @@ impl EncryptedNoteStorage {
 
     /// Initialize SQLite database with Quebec compliance schema
     fn initialize_database(&self) -> Result<(), EncryptionError> {
-        let conn = Connection::open(&self.db_path)?;
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS medical_notes (
-                id TEXT PRIMARY KEY,
-                patient_id TEXT NOT NULL,
-                encrypted_content BLOB NOT NULL,
-                template_type TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                modified_at TEXT NOT NULL,
-                consent_obtained BOOLEAN NOT NULL,
-                encrypted BOOLEAN DEFAULT TRUE,
-                deidentified BOOLEAN DEFAULT TRUE,
-                sync_status TEXT NOT NULL DEFAULT 'Local',
-                quebec_compliance TEXT NOT NULL,
-                content_checksum TEXT NOT NULL,
-                encryption_version INTEGER NOT NULL DEFAULT 1
-            )",
-            [],
-        )?;
-
-        // Create audit log table for Law 25 compliance
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS audit_log (
-                id TEXT PRIMARY KEY,
-                timestamp TEXT NOT NULL,
-                note_id TEXT,
-                action TEXT NOT NULL,
-                user_id TEXT NOT NULL,
-                phi_accessed BOOLEAN NOT NULL,
-                ip_address TEXT,
-                details TEXT,
-                FOREIGN KEY(note_id) REFERENCES medical_notes(id)
-            )",
-            [],
-        )?;
-
-        // Create index for efficient queries
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_patient_id ON medical_notes(patient_id)",
-            [],
+        let mut conn = crate::storage::migrations::open_and_migrate(
+            &self.db_path,
+            crate::storage::migrations::NOTES_MIGRATIONS,
         )?;
+        self.reseal_backfilled_versions(&mut conn)
+    }
 
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_created_at ON medical_notes(created_at)",
-            [],
-        )?;
+    /// Version 0 rows backfilled by the migration still carry the note envelope; reseal them
+    /// under the history AAD so `get_note_version` can read them.
+    fn reseal_backfilled_versions(&self, conn: &mut Connection) -> Result<(), EncryptionError> {
+        let pending = {
+            let mut stmt = conn.prepare(
+                "SELECT v.note_id, n.patient_id, v.encrypted_content
+                 FROM note_versions v JOIN medical_notes n ON n.id = v.note_id
+                 WHERE v.version = 0 AND v.encrypted_content = n.encrypted_content"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+            )))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        if pending.is_empty() {
+            return Ok(());
+        }
 
+        let tx = conn.transaction()?;
+        for (note_id, patient_id, blob) in pending {
+            let envelope: EncryptedData = serde_json::from_slice(&blob)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Envelope parsing failed: {}", e)))?;
+            let content = match self.decrypt_content(&envelope, &note_aad(&note_id, &patient_id)) {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::warn!("Leaving version 0 of note {} sealed to the note: {}", note_id, e);
+                    continue;
+                }
+            };
+            let resealed = self.encrypt_content(&content, envelope.level, &note_version_aad(&note_id, 0))?;
+            let resealed_blob = serde_json::to_vec(&resealed)
+                .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+            tx.execute(
+                "UPDATE note_versions SET encrypted_content = ?1, content_checksum = ?2, encryption_level = ?3
+                 WHERE note_id = ?4 AND version = 0",
+                params![resealed_blob, resealed.checksum, format!("{:?}", resealed.level), note_id],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    /// Encrypt medical note content with AES-256-GCM
-    fn encrypt_content(&self, content: &str) -> Result<EncryptedData, EncryptionError> {
-        let key = Key::<Aes256Gcm>::from_slice(&self.master_key);
-        let cipher = Aes256Gcm::new(key);
+    /// Sub-key for a leveled envelope layer
+    fn layer_key(&self, label: &[u8]) -> [u8; 32] {
+        let mut context = Context::new(&SHA256);
+        context.update(&self.master_key);
+        context.update(label);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(context.finish().as_ref());
+        key
+    }
+
+    /// Encrypt medical note content at the given level.
+    /// Strong keeps the original AES-256-GCM envelope; Medical adds a dedicated key and
+    /// record-bound AAD; Maximum wraps a ChaCha20-Poly1305 layer inside the Medical one.
+    fn encrypt_content(&self, content: &str, level: EncryptionLevel, aad: &[u8]) -> Result<EncryptedData, EncryptionError> {
+        let _timer = crate::services::command_profiler::profiler().time("crypto.encrypt_note");
+        let level = level.max(MINIMUM_NOTE_LEVEL);
+
+        let (plaintext, inner_nonce) = if level == EncryptionLevel::Maximum {
+            let inner = ChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(&self.layer_key(b"maximum-inner")));
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+            let sealed = inner
+                .encrypt(&nonce, Payload { msg: content.as_bytes(), aad })
+                .map_err(|e| EncryptionError::EncryptionFailed(format!("ChaCha20 encryption failed: {}", e)))?;
+            (sealed, Some(nonce.to_vec()))
+        } else {
+            (content.as_bytes().to_vec(), None)
+        };
+
+        let (key, aad) = match level {
+            EncryptionLevel::Medical | EncryptionLevel::Maximum => (self.layer_key(b"medical"), aad),
+            _ => (self.master_key, &[][..]),
+        };
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
 
         let ciphertext = cipher
-            .encrypt(&nonce, content.as_bytes())
+            .encrypt(&nonce, Payload { msg: &plaintext, aad })
             .map_err(|e| EncryptionError::EncryptionFailed(format!("AES encryption failed: {}", e)))?;
 
         // Generate checksum for integrity verification
@@ -187,11 +493,14 @@ impl EncryptedNoteStorage {
             nonce: nonce.to_vec(),
             ciphertext,
             checksum,
+            level,
+            inner_nonce,
         })
     }
 
     /// Decrypt medical note content
-    fn decrypt_content(&self, encrypted_data: &EncryptedData) -> Result<String, EncryptionError> {
+    fn decrypt_content(&self, encrypted_data: &EncryptedData, aad: &[u8]) -> Result<String, EncryptionError> {
+        let _timer = crate::services::command_profiler::profiler().time("crypto.decrypt_note");
         // Verify checksum first
         let mut context = Context::new(&SHA256);
         context.update(&encrypted_data.ciphertext);
@@ -201,22 +510,64 @@ impl EncryptedNoteStorage {
             return Err(EncryptionError::DecryptionFailed("Checksum verification failed".to_string()));
         }
 
-        let key = Key::<Aes256Gcm>::from_slice(&self.master_key);
-        let cipher = Aes256Gcm::new(key);
+        let (key, outer_aad) = match encrypted_data.level {
+            EncryptionLevel::Medical | EncryptionLevel::Maximum => (self.layer_key(b"medical"), aad),
+            _ => (self.master_key, &[][..]),
+        };
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
         let nonce = Nonce::from_slice(&encrypted_data.nonce);
 
-        let plaintext = cipher
-            .decrypt(nonce, encrypted_data.ciphertext.as_ref())
+        let mut plaintext = cipher
+            .decrypt(nonce, Payload { msg: encrypted_data.ciphertext.as_ref(), aad: outer_aad })
             .map_err(|e| EncryptionError::DecryptionFailed(format!("AES decryption failed: {}", e)))?;
 
+        if encrypted_data.level == EncryptionLevel::Maximum {
+            let inner_nonce = encrypted_data.inner_nonce.as_ref()
+                .ok_or_else(|| EncryptionError::DecryptionFailed("Missing inner nonce".to_string()))?;
+            let inner = ChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(&self.layer_key(b"maximum-inner")));
+            plaintext = inner
+                .decrypt(chacha20poly1305::Nonce::from_slice(inner_nonce), Payload { msg: &plaintext, aad })
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("ChaCha20 decryption failed: {}", e)))?;
+        }
+
         String::from_utf8(plaintext)
             .map_err(|e| EncryptionError::DecryptionFailed(format!("UTF-8 conversion failed: {}", e)))
     }
 
-    /// Save encrypted medical note with Law 25 compliance
+    /// Stored classification and encryption level of a note, if it exists
+    fn stored_protection(&self, conn: &Connection, note_id: &str) -> Result<Option<(DataClassification, EncryptionLevel)>, EncryptionError> {
+        let row = conn.query_row(
+            "SELECT classification, encryption_level FROM medical_notes WHERE id = ?1",
+            params![note_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        );
+        match row {
+            Ok((classification, level)) => Ok(Some((classification_from_str(&classification)?, level_from_str(&level)?))),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(EncryptionError::Database(e)),
+        }
+    }
+
+    /// Current version of a stored note and whether it has been deleted
+    fn stored_revision(&self, conn: &Connection, note_id: &str) -> Result<Option<(u32, bool)>, EncryptionError> {
+        let row = conn.query_row(
+            "SELECT current_version, deleted_at IS NOT NULL FROM medical_notes WHERE id = ?1",
+            params![note_id],
+            |row| Ok((row.get::<_, u32>(0)?, row.get::<_, bool>(1)?)),
+        );
+        match row {
+            Ok(revision) => Ok(Some(revision)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(EncryptionError::Database(e)),
+        }
+    }
+
+    /// Save a medical note with Law 25 compliance. Every save is a new immutable version; the
+    /// earlier ones stay readable through `get_note_version`.
     pub async fn save_note(&self, mut note: MedicalNote, user_id: &str) -> Result<String, EncryptionError> {
         // Validate Law 25 compliance before saving
         self.validate_law25_compliance(&note)?;
+        let metadata_columns = queryable_metadata(&note.metadata)?;
 
         let note_id = if note.id.is_empty() {
             Uuid::new_v4().to_string()
@@ -229,10 +580,33 @@ impl EncryptedNoteStorage {
         note.encrypted = true;
         note.deidentified = true;
 
-        // Encrypt the content
-        let encrypted_data = self.encrypt_content(&note.content)?;
+        // Encrypt at the level the classification requires, never below what is already stored
+        let mut conn = Connection::open(&self.db_path)?;
+        let (classification, level) = match self.stored_protection(&conn, &note.id)? {
+            Some((classification, stored_level)) => (classification, required_note_level(classification).max(stored_level)),
+            None => (DEFAULT_NOTE_CLASSIFICATION, required_note_level(DEFAULT_NOTE_CLASSIFICATION)),
+        };
+        let version = match self.stored_revision(&conn, &note.id)? {
+            Some((_, true)) => {
+                return Err(EncryptionError::ComplianceViolation(format!(
+                    "Note {} has been deleted and cannot be amended", note.id
+                )))
+            }
+            Some((current, false)) => current + 1,
+            None => 1,
+        };
+        note.version = version;
+
+        let encrypted_data = self.encrypt_content(&note.content, level, &note_aad(&note.id, &note.patient_id))?;
         let encrypted_blob = serde_json::to_vec(&encrypted_data)
             .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+        let version_data = self.encrypt_content(&note.content, level, &note_version_aad(&note.id, version))?;
+        let version_blob = serde_json::to_vec(&version_data)
+            .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+        let sync_status = serde_json::to_string(&note.sync_status)
+            .map_err(|e| EncryptionError::EncryptionFailed(format!("Sync status serialization failed: {}", e)))?;
+        let metadata_json = serde_json::to_string(&note.metadata)
+            .map_err(|e| EncryptionError::EncryptionFailed(format!("Metadata serialization failed: {}", e)))?;
 
         // Add audit entry
         let audit_entry = AuditEntry {
@@ -241,17 +615,20 @@ impl EncryptedNoteStorage {
             user_id: user_id.to_string(),
             phi_accessed: true,
             ip_address: None,
+            note_version: Some(version),
         };
 
         note.quebec_compliance.audit_trail.push(audit_entry);
+        let quebec_compliance = serde_json::to_string(&note.quebec_compliance)
+            .map_err(|e| EncryptionError::EncryptionFailed(format!("Quebec compliance serialization failed: {}", e)))?;
 
-        let conn = Connection::open(&self.db_path)?;
-        conn.execute(
+        let tx = conn.transaction()?;
+        tx.execute(
             "INSERT OR REPLACE INTO medical_notes
              (id, patient_id, encrypted_content, template_type, created_at, modified_at,
               consent_obtained, encrypted, deidentified, sync_status, quebec_compliance,
-              content_checksum, encryption_version)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+              content_checksum, encryption_version, classification, encryption_level, current_version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
             params![
                 note.id,
                 note.patient_id,
@@ -262,28 +639,139 @@ impl EncryptedNoteStorage {
                 note.consent_obtained,
                 note.encrypted,
                 note.deidentified,
-                serde_json::to_string(&note.sync_status).unwrap(),
-                serde_json::to_string(&note.quebec_compliance).unwrap(),
+                sync_status,
+                quebec_compliance,
                 encrypted_data.checksum,
-                1
+                LEVELED_ENCRYPTION_VERSION,
+                format!("{:?}", classification),
+                format!("{:?}", encrypted_data.level),
+                version,
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO note_versions
+             (note_id, version, encrypted_content, content_checksum, encryption_level, template_type,
+              metadata, author_id, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                note.id,
+                version,
+                version_blob,
+                version_data.checksum,
+                format!("{:?}", version_data.level),
+                note.template_type,
+                metadata_json,
+                user_id,
+                note.modified_at.to_rfc3339(),
             ],
         )?;
+        tx.execute("DELETE FROM note_metadata WHERE note_id = ?1", params![note.id])?;
+        for (field, value) in &metadata_columns {
+            tx.execute(
+                "INSERT INTO note_metadata (note_id, field, value) VALUES (?1, ?2, ?3)",
+                params![note.id, field, value],
+            )?;
+        }
+        tx.commit()?;
 
         // Log audit entry
-        self.log_audit_entry_sync(&note_id, "note_save", user_id, true)?;
+        self.log_version_access(&note_id, "note_save", user_id, version)?;
 
         tracing::info!("Medical note saved with encryption: {}", note_id);
         Ok(note_id)
     }
 
-    /// Retrieve and decrypt medical note
-    pub async fn get_note(&self, note_id: &str, _user_id: &str) -> Result<Option<MedicalNote>, EncryptionError> {
+    /// Patient a note belongs to, read without decrypting the note or logging an access;
+    /// deleted notes included so their history can still be authorized
+    pub fn note_patient_id(&self, note_id: &str) -> Result<Option<String>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        match conn.query_row("SELECT patient_id FROM medical_notes WHERE id = ?1", params![note_id], |row| row.get(0)) {
+            Ok(patient_id) => Ok(Some(patient_id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(EncryptionError::Database(e)),
+        }
+    }
+
+    /// Store an encrypted sync record for a note, replacing any earlier one of the same kind
+    pub fn save_sync_record(&self, note_id: &str, kind: &str, payload: &str) -> Result<(), EncryptionError> {
+        let encrypted = self.encrypt_content(
+            payload,
+            required_note_level(DEFAULT_NOTE_CLASSIFICATION),
+            &sync_record_aad(note_id, kind),
+        )?;
+        let blob = serde_json::to_vec(&encrypted)
+            .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+        let conn = Connection::open(&self.db_path)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO note_sync_records (note_id, kind, encrypted_payload, updated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![note_id, kind, blob, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Every sync record of one kind, decrypted, by note ID
+    pub fn load_sync_records(&self, kind: &str) -> Result<Vec<(String, String)>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let mut stmt = conn.prepare("SELECT note_id, encrypted_payload FROM note_sync_records WHERE kind = ?1")?;
+        let rows = stmt.query_map(params![kind], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)))?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (note_id, blob) = row?;
+            let encrypted: EncryptedData = serde_json::from_slice(&blob)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Sync record parsing failed: {}", e)))?;
+            let payload = self.decrypt_content(&encrypted, &sync_record_aad(&note_id, kind))?;
+            records.push((note_id, payload));
+        }
+        Ok(records)
+    }
+
+    pub fn delete_sync_record(&self, note_id: &str, kind: &str) -> Result<(), EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        conn.execute("DELETE FROM note_sync_records WHERE note_id = ?1 AND kind = ?2", params![note_id, kind])?;
+        Ok(())
+    }
+
+    /// Live notes waiting to upload with their last edit time, read without decrypting or logging
+    /// an access
+    pub fn pending_sync_notes(&self) -> Result<Vec<(String, DateTime<Utc>)>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let pending = serde_json::to_string(&SyncStatus::Pending)
+            .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, modified_at FROM medical_notes WHERE sync_status = ?1 AND deleted_at IS NULL"
+        )?;
+        let rows = stmt.query_map(params![pending], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut notes = Vec::new();
+        for row in rows {
+            let (id, modified_at) = row?;
+            let modified_at = DateTime::parse_from_rfc3339(&modified_at)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Date parsing failed: {}", e)))?
+                .with_timezone(&Utc);
+            notes.push((id, modified_at));
+        }
+        Ok(notes)
+    }
+
+    /// Retrieve and decrypt the latest version of a medical note for a user; deleted notes are
+    /// not returned. The read is recorded in the audit trail.
+    pub async fn get_note(&self, note_id: &str, user_id: &str) -> Result<Option<MedicalNote>, EncryptionError> {
+        let note = self.read_note(note_id).await?;
+        if let Some(note) = &note {
+            self.log_version_access(&note.id, "note_read", user_id, note.version)?;
+        }
+        Ok(note)
+    }
+
+    /// Decrypt the latest version of a note without auditing it, for internal reads such as
+    /// sync. Anything shown to a user goes through `get_note`.
+    pub(crate) async fn read_note(&self, note_id: &str) -> Result<Option<MedicalNote>, EncryptionError> {
         let conn = Connection::open(&self.db_path)?;
 
         let mut stmt = conn.prepare(
             "SELECT id, patient_id, encrypted_content, template_type, created_at, modified_at,
-                    consent_obtained, encrypted, deidentified, sync_status, quebec_compliance
-             FROM medical_notes WHERE id = ?1"
+                    consent_obtained, encrypted, deidentified, sync_status, quebec_compliance, current_version
+             FROM medical_notes WHERE id = ?1 AND deleted_at IS NULL"
         )?;
 
         let result = stmt.query_row(params![note_id], |row| {
@@ -303,15 +791,17 @@ impl EncryptedNoteStorage {
                 row.get::<_, bool>(8)?,    // deidentified
                 row.get::<_, String>(9)?,  // sync_status
                 row.get::<_, String>(10)?, // quebec_compliance
+                row.get::<_, u32>(11)?,    // current_version
             ))
         });
 
         match result {
             Ok((id, patient_id, encrypted_data, template_type, created_at, modified_at,
-                consent_obtained, encrypted, deidentified, sync_status, quebec_compliance)) => {
+                consent_obtained, encrypted, deidentified, sync_status, quebec_compliance, version)) => {
 
                 // Decrypt content
-                let content = self.decrypt_content(&encrypted_data)?;
+                let content = self.decrypt_content(&encrypted_data, &note_aad(&id, &patient_id))?;
+                let metadata = load_metadata(&conn, &id)?;
 
                 let note = MedicalNote {
                     id,
@@ -331,6 +821,8 @@ impl EncryptedNoteStorage {
                         .map_err(|e| EncryptionError::DecryptionFailed(format!("Sync status parsing failed: {}", e)))?,
                     quebec_compliance: serde_json::from_str(&quebec_compliance)
                         .map_err(|e| EncryptionError::DecryptionFailed(format!("Quebec compliance parsing failed: {}", e)))?,
+                    metadata,
+                    version,
                 };
 
                 Ok(Some(note))
@@ -340,20 +832,34 @@ impl EncryptedNoteStorage {
         }
     }
 
-    /// List medical notes for a patient with pagination
-    pub async fn list_notes_for_patient(&self, patient_id: &str, user_id: &str, limit: u32, offset: u32) -> Result<Vec<MedicalNote>, EncryptionError> {
+    /// List medical notes for a patient with pagination. The filter runs on the queryable
+    /// metadata, so only matching bodies are decrypted.
+    pub async fn list_notes_for_patient(
+        &self,
+        patient_id: &str,
+        user_id: &str,
+        limit: u32,
+        offset: u32,
+        filter: &NoteListFilter,
+    ) -> Result<Vec<MedicalNote>, EncryptionError> {
         let conn = Connection::open(&self.db_path)?;
 
         let mut stmt = conn.prepare(
             "SELECT id, patient_id, encrypted_content, template_type, created_at, modified_at,
-                    consent_obtained, encrypted, deidentified, sync_status, quebec_compliance
+                    consent_obtained, encrypted, deidentified, sync_status, quebec_compliance, current_version
              FROM medical_notes
-             WHERE patient_id = ?1
+             WHERE patient_id = ?1 AND deleted_at IS NULL
+               AND (?4 IS NULL OR EXISTS (SELECT 1 FROM note_metadata m
+                    WHERE m.note_id = medical_notes.id AND m.field = ?6 AND m.value >= ?4))
+               AND (?5 IS NULL OR EXISTS (SELECT 1 FROM note_metadata m
+                    WHERE m.note_id = medical_notes.id AND m.field = ?6 AND m.value <= ?5))
              ORDER BY created_at DESC
              LIMIT ?2 OFFSET ?3"
         )?;
 
-        let rows = stmt.query_map(params![patient_id, limit, offset], |row| {
+        let from = filter.appointment_from.map(|date| date.format("%Y-%m-%d").to_string());
+        let to = filter.appointment_to.map(|date| date.format("%Y-%m-%d").to_string());
+        let rows = stmt.query_map(params![patient_id, limit, offset, from, to, APPOINTMENT_DATE_FIELD], |row| {
             let encrypted_blob: Vec<u8> = row.get(2)?;
             let encrypted_data: EncryptedData = serde_json::from_slice(&encrypted_blob)
                 .map_err(|_| rusqlite::Error::InvalidColumnType(2, "encrypted_data".to_string(), rusqlite::types::Type::Blob))?;
@@ -370,16 +876,18 @@ impl EncryptedNoteStorage {
                 row.get::<_, bool>(8)?,    // deidentified
                 row.get::<_, String>(9)?,  // sync_status
                 row.get::<_, String>(10)?, // quebec_compliance
+                row.get::<_, u32>(11)?,    // current_version
             ))
         })?;
 
         let mut notes = Vec::new();
         for row_result in rows {
             let (id, patient_id, encrypted_data, template_type, created_at, modified_at,
-                 consent_obtained, encrypted, deidentified, sync_status, quebec_compliance) = row_result?;
+                 consent_obtained, encrypted, deidentified, sync_status, quebec_compliance, version) = row_result?;
 
             // Decrypt content
-            let content = self.decrypt_content(&encrypted_data)?;
+            let content = self.decrypt_content(&encrypted_data, &note_aad(&id, &patient_id))?;
+            let metadata = load_metadata(&conn, &id)?;
 
             let note = MedicalNote {
                 id,
@@ -399,6 +907,8 @@ impl EncryptedNoteStorage {
                     .map_err(|e| EncryptionError::DecryptionFailed(format!("Sync status parsing failed: {}", e)))?,
                 quebec_compliance: serde_json::from_str(&quebec_compliance)
                     .map_err(|e| EncryptionError::DecryptionFailed(format!("Quebec compliance parsing failed: {}", e)))?,
+                metadata,
+                version,
             };
 
             notes.push(note);
@@ -410,19 +920,661 @@ impl EncryptedNoteStorage {
         Ok(notes)
     }
 
-    /// Delete medical note with audit trail
+    /// Soft-delete a medical note. It disappears from reads and lists, but the note and its
+    /// history are kept until `purge_expired_notes` removes them after the retention period.
     pub async fn delete_note(&self, note_id: &str, user_id: &str) -> Result<(), EncryptionError> {
         let conn = Connection::open(&self.db_path)?;
 
-        // Log deletion before actually deleting
+        let updated = conn.execute(
+            "UPDATE medical_notes SET deleted_at = ?1, deleted_by = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            params![Utc::now().to_rfc3339(), user_id, note_id],
+        )?;
+        if updated == 0 {
+            return Err(EncryptionError::Database(rusqlite::Error::QueryReturnedNoRows));
+        }
         self.log_audit_entry_sync(note_id, "note_delete", user_id, true)?;
 
-        conn.execute("DELETE FROM medical_notes WHERE id = ?1", params![note_id])?;
-
         tracing::info!("Medical note deleted: {}", note_id);
         Ok(())
     }
 
+    /// Permanently remove deleted notes, with their history, attachments and metadata, once
+    /// their retention period has passed since deletion. Returns the IDs purged.
+    pub async fn purge_expired_notes(&self, now: DateTime<Utc>, user_id: &str) -> Result<Vec<String>, EncryptionError> {
+        let mut conn = Connection::open(&self.db_path)?;
+        let deleted: Vec<(String, String, String)> = {
+            let mut stmt = conn.prepare(
+                "SELECT id, deleted_at, quebec_compliance FROM medical_notes WHERE deleted_at IS NOT NULL ORDER BY id"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let mut expired = Vec::new();
+        for (note_id, deleted_at, compliance) in deleted {
+            let compliance: QuebecComplianceMetadata = serde_json::from_str(&compliance)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Quebec compliance parsing failed: {}", e)))?;
+            let retained_until = parse_timestamp(&deleted_at)? + chrono::Duration::days(compliance.retention_period_days as i64);
+            if retained_until <= now {
+                expired.push(note_id);
+            }
+        }
+
+        let tx = conn.transaction()?;
+        for note_id in &expired {
+            tx.execute("DELETE FROM note_attachments WHERE note_id = ?1", params![note_id])?;
+            tx.execute("DELETE FROM note_metadata WHERE note_id = ?1", params![note_id])?;
+            tx.execute("DELETE FROM note_versions WHERE note_id = ?1", params![note_id])?;
+            tx.execute("DELETE FROM medical_notes WHERE id = ?1", params![note_id])?;
+        }
+        tx.commit()?;
+        for note_id in &expired {
+            self.log_audit_entry_sync(note_id, "note_purge", user_id, false)?;
+        }
+
+        if !expired.is_empty() {
+            tracing::info!("Purged {} deleted notes past their retention period", expired.len());
+        }
+        Ok(expired)
+    }
+
+    /// Versions of a note, oldest first; still available after the note is deleted
+    pub async fn get_note_history(&self, note_id: &str, user_id: &str) -> Result<Vec<NoteVersionSummary>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let mut stmt = conn.prepare(
+            "SELECT version, author_id, created_at, template_type, content_checksum
+             FROM note_versions WHERE note_id = ?1 ORDER BY version"
+        )?;
+        let rows = stmt.query_map(params![note_id], |row| {
+            Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?))
+        })?;
+
+        let mut history = Vec::new();
+        for row in rows {
+            let (version, author_id, created_at, template_type, content_checksum) = row?;
+            history.push(NoteVersionSummary {
+                version,
+                author_id,
+                created_at: parse_timestamp(&created_at)?,
+                template_type,
+                content_checksum,
+            });
+        }
+
+        self.log_audit_entry_sync(note_id, "note_history", user_id, false)?;
+        Ok(history)
+    }
+
+    /// Decrypt one version of a note. Fields that are not versioned (consent, sync status,
+    /// compliance) come from the note as it is now.
+    pub async fn get_note_version(&self, note_id: &str, version: u32, user_id: &str) -> Result<Option<MedicalNote>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let row = conn.query_row(
+            "SELECT n.patient_id, v.encrypted_content, v.template_type, n.created_at, v.created_at,
+                    n.consent_obtained, n.encrypted, n.deidentified, n.sync_status, n.quebec_compliance, v.metadata
+             FROM note_versions v JOIN medical_notes n ON n.id = v.note_id
+             WHERE v.note_id = ?1 AND v.version = ?2",
+            params![note_id, version],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, bool>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, bool>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, String>(9)?,
+                row.get::<_, String>(10)?,
+            )),
+        );
+        let (patient_id, blob, template_type, created_at, modified_at, consent_obtained, encrypted,
+             deidentified, sync_status, quebec_compliance, metadata) = match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(EncryptionError::Database(e)),
+        };
+
+        let encrypted_data: EncryptedData = serde_json::from_slice(&blob)
+            .map_err(|e| EncryptionError::DecryptionFailed(format!("Envelope parsing failed: {}", e)))?;
+        let content = self.decrypt_content(&encrypted_data, &note_version_aad(note_id, version))?;
+
+        let note = MedicalNote {
+            id: note_id.to_string(),
+            patient_id,
+            content,
+            template_type,
+            created_at: parse_timestamp(&created_at)?,
+            modified_at: parse_timestamp(&modified_at)?,
+            consent_obtained,
+            encrypted,
+            deidentified,
+            sync_status: serde_json::from_str(&sync_status)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Sync status parsing failed: {}", e)))?,
+            quebec_compliance: serde_json::from_str(&quebec_compliance)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Quebec compliance parsing failed: {}", e)))?,
+            metadata: serde_json::from_str(&metadata).unwrap_or_default(),
+            version,
+        };
+
+        self.log_version_access(note_id, "note_version_read", user_id, version)?;
+        Ok(Some(note))
+    }
+
+    /// Attach scanned media to a note; blocked media is refused
+    pub async fn attach_media(&self, note_id: &str, scan: MediaScanResult, user_id: &str) -> Result<NoteAttachment, EncryptionError> {
+        if scan.is_blocked() {
+            return Err(EncryptionError::ComplianceViolation(
+                "Media failed the content scan and cannot be attached".to_string()
+            ));
+        }
+
+        let conn = Connection::open(&self.db_path)?;
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM medical_notes WHERE id = ?1 AND deleted_at IS NULL)",
+            params![note_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(EncryptionError::Database(rusqlite::Error::QueryReturnedNoRows));
+        }
+
+        let attachment = NoteAttachment {
+            id: Uuid::new_v4().to_string(),
+            note_id: note_id.to_string(),
+            media_ref: scan.media_ref.clone(),
+            scan,
+            attached_by: user_id.to_string(),
+            attached_at: Utc::now(),
+        };
+        conn.execute(
+            "INSERT INTO note_attachments (id, note_id, media_ref, scan_status, scan_result, attached_by, attached_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                attachment.id,
+                attachment.note_id,
+                attachment.media_ref,
+                format!("{:?}", attachment.scan.status),
+                serde_json::to_string(&attachment.scan).unwrap(),
+                attachment.attached_by,
+                attachment.attached_at.to_rfc3339(),
+            ],
+        )?;
+
+        self.log_audit_entry_sync(note_id, "note_attach_media", user_id, attachment.scan.contains_phi())?;
+        tracing::info!("Media attached to note {} (scan {:?})", note_id, attachment.scan.status);
+        Ok(attachment)
+    }
+
+    /// Attachments of a note with their scan results
+    pub async fn list_attachments(&self, note_id: &str) -> Result<Vec<NoteAttachment>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, note_id, media_ref, scan_result, attached_by, attached_at
+             FROM note_attachments WHERE note_id = ?1 ORDER BY attached_at"
+        )?;
+        let rows = stmt.query_map(params![note_id], |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        )))?;
+
+        let mut attachments = Vec::new();
+        for row in rows {
+            let (id, note_id, media_ref, scan_result, attached_by, attached_at) = row?;
+            attachments.push(NoteAttachment {
+                id,
+                note_id,
+                media_ref,
+                scan: serde_json::from_str(&scan_result)
+                    .map_err(|e| EncryptionError::DecryptionFailed(format!("Scan result parsing failed: {}", e)))?,
+                attached_by,
+                attached_at: DateTime::parse_from_rfc3339(&attached_at)
+                    .map_err(|e| EncryptionError::DecryptionFailed(format!("Date parsing failed: {}", e)))?
+                    .with_timezone(&Utc),
+            });
+        }
+        Ok(attachments)
+    }
+
+    /// Envelope metadata of a patient's notes, pending sync entries and attachments.
+    /// Envelopes are parsed for their recorded level only; nothing is decrypted.
+    pub fn encryption_envelopes(&self, patient_id: &str) -> Result<Vec<ArtifactEnvelope>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, encrypted_content, classification, encryption_level, encrypted, sync_status
+             FROM medical_notes WHERE patient_id = ?1 ORDER BY id"
+        )?;
+        let rows: Vec<(String, Vec<u8>, String, String, bool, String)> = stmt
+            .query_map(params![patient_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)))?
+            .collect::<Result<_, _>>()?;
+
+        let mut envelopes = Vec::new();
+        for (note_id, blob, classification, recorded_level, encrypted_flag, sync_status) in rows {
+            let classification = classification_from_str(&classification)?;
+            let (envelope_level, plaintext, detail) = match serde_json::from_slice::<EncryptedData>(&blob) {
+                Ok(envelope) if envelope.ciphertext.is_empty() => (None, false, "Envelope has no ciphertext".to_string()),
+                Ok(envelope) if level_from_str(&recorded_level).ok() != Some(envelope.level) => (
+                    Some(envelope.level),
+                    false,
+                    format!("Envelope level {:?} differs from recorded level {}", envelope.level, recorded_level),
+                ),
+                Ok(envelope) => (Some(envelope.level), false, format!("Envelope level {:?}", envelope.level)),
+                Err(_) => (None, true, "Stored content is not an encryption envelope".to_string()),
+            };
+            let detail = if encrypted_flag { detail } else { format!("{}; row flagged unencrypted", detail) };
+
+            envelopes.push(ArtifactEnvelope {
+                kind: ArtifactKind::Note,
+                artifact_id: note_id.clone(),
+                classification,
+                envelope_level,
+                plaintext,
+                detail: Some(detail.clone()),
+            });
+            // Queued uploads are read from the same row, so they share its envelope
+            let sync_status: Option<SyncStatus> = serde_json::from_str(&sync_status).ok();
+            if matches!(sync_status, Some(SyncStatus::Pending) | Some(SyncStatus::Conflict)) {
+                envelopes.push(ArtifactEnvelope {
+                    kind: ArtifactKind::SyncQueueEntry,
+                    artifact_id: note_id.clone(),
+                    classification,
+                    envelope_level,
+                    plaintext,
+                    detail: Some(format!("Queued for sync ({:?}); {}", sync_status.unwrap(), detail)),
+                });
+            }
+
+            for attachment in self.attachment_rows(&conn, &note_id)? {
+                let (id, media_ref, scan) = attachment;
+                // Attached media lives outside the note store, so it has no envelope here
+                let contains_phi = scan.contains_phi();
+                envelopes.push(ArtifactEnvelope {
+                    kind: ArtifactKind::Attachment,
+                    artifact_id: id,
+                    classification,
+                    envelope_level: None,
+                    plaintext: contains_phi,
+                    detail: Some(if contains_phi {
+                        format!("Media {} contains PHI and is stored outside the encrypted note store", media_ref)
+                    } else {
+                        format!("Media {} is stored outside the encrypted note store", media_ref)
+                    }),
+                });
+            }
+        }
+        Ok(envelopes)
+    }
+
+    fn attachment_rows(&self, conn: &Connection, note_id: &str) -> Result<Vec<(String, String, MediaScanResult)>, EncryptionError> {
+        let mut stmt = conn.prepare("SELECT id, media_ref, scan_result FROM note_attachments WHERE note_id = ?1 ORDER BY attached_at")?;
+        let rows: Vec<(String, String, String)> = stmt
+            .query_map(params![note_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        rows.into_iter()
+            .map(|(id, media_ref, scan)| {
+                let scan = serde_json::from_str(&scan)
+                    .map_err(|e| EncryptionError::DecryptionFailed(format!("Scan result parsing failed: {}", e)))?;
+                Ok((id, media_ref, scan))
+            })
+            .collect()
+    }
+
+    /// Record a new classification; ciphertext is upgraded by the re-encryption job
+    pub async fn set_note_classification(&self, note_id: &str, classification: DataClassification, user_id: &str) -> Result<(), EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        let updated = conn.execute(
+            "UPDATE medical_notes SET classification = ?1 WHERE id = ?2",
+            params![format!("{:?}", classification), note_id],
+        )?;
+        if updated == 0 {
+            return Err(EncryptionError::Database(rusqlite::Error::QueryReturnedNoRows));
+        }
+
+        self.log_audit_entry_sync(note_id, "note_reclassify", user_id, false)?;
+        tracing::info!("Medical note {} reclassified as {:?}", note_id, classification);
+        Ok(())
+    }
+
+    /// Upgrade every note whose ciphertext is weaker than its classification requires.
+    /// Progress is committed per note, so an interrupted run resumes from its cursor when
+    /// called again with the same job ID. Encryption is never downgraded.
+    pub async fn reencrypt_for_classification<F>(
+        &self,
+        resume_job_id: Option<&str>,
+        user_id: &str,
+        batch_size: u32,
+        mut on_progress: F,
+    ) -> Result<ReencryptionJob, EncryptionError>
+    where
+        F: FnMut(&ReencryptionJob),
+    {
+        let mut conn = Connection::open(&self.db_path)?;
+
+        let mut job = match resume_job_id {
+            Some(job_id) => Self::load_job(&conn, job_id)?.ok_or_else(|| {
+                EncryptionError::Database(rusqlite::Error::QueryReturnedNoRows)
+            })?,
+            None => {
+                let job = ReencryptionJob {
+                    id: Uuid::new_v4().to_string(),
+                    status: ReencryptionJobStatus::Running,
+                    started_by: user_id.to_string(),
+                    started_at: Utc::now(),
+                    updated_at: Utc::now(),
+                    cursor: None,
+                    examined: 0,
+                    upgraded: 0,
+                    failures: Vec::new(),
+                };
+                Self::save_job(&conn, &job)?;
+                job
+            }
+        };
+        if job.status != ReencryptionJobStatus::Running {
+            return Ok(job);
+        }
+
+        loop {
+            let batch: Vec<(String, String, Vec<u8>, String, String)> = {
+                let mut stmt = conn.prepare(
+                    "SELECT id, patient_id, encrypted_content, classification, encryption_level
+                     FROM medical_notes
+                     WHERE id > ?1
+                     ORDER BY id
+                     LIMIT ?2"
+                )?;
+                let rows = stmt.query_map(
+                    params![job.cursor.clone().unwrap_or_default(), batch_size.max(1)],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+                )?;
+                rows.collect::<Result<_, _>>()?
+            };
+            if batch.is_empty() {
+                break;
+            }
+
+            for (note_id, patient_id, blob, classification, stored_level) in batch {
+                job.examined += 1;
+                job.cursor = Some(note_id.clone());
+                job.updated_at = Utc::now();
+
+                let upgrade = self.upgrade_envelope(&note_id, &patient_id, &blob, &classification, &stored_level)
+                    .and_then(|current| Ok((current, self.upgrade_history(&conn, &note_id, &classification)?)));
+                let tx = conn.transaction()?;
+                match upgrade {
+                    Ok((current, history)) => {
+                        for (version, envelope) in history {
+                            let envelope_blob = serde_json::to_vec(&envelope)
+                                .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+                            tx.execute(
+                                "UPDATE note_versions SET encrypted_content = ?1, content_checksum = ?2, encryption_level = ?3
+                                 WHERE note_id = ?4 AND version = ?5",
+                                params![envelope_blob, envelope.checksum, format!("{:?}", envelope.level), note_id, version],
+                            )?;
+                        }
+                        if let Some((from, encrypted)) = current {
+                            let encrypted_blob = serde_json::to_vec(&encrypted)
+                                .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+                            // Guard against a concurrent save having changed the row meanwhile
+                            tx.execute(
+                                "UPDATE medical_notes
+                                 SET encrypted_content = ?1, content_checksum = ?2, encryption_level = ?3, encryption_version = ?4
+                                 WHERE id = ?5 AND encryption_level = ?6",
+                                params![
+                                    encrypted_blob,
+                                    encrypted.checksum,
+                                    format!("{:?}", encrypted.level),
+                                    LEVELED_ENCRYPTION_VERSION,
+                                    note_id,
+                                    stored_level,
+                                ],
+                            )?;
+                            tx.execute(
+                                "INSERT INTO audit_log (id, timestamp, note_id, action, user_id, phi_accessed, details)
+                                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                                params![
+                                    Uuid::new_v4().to_string(),
+                                    Utc::now().to_rfc3339(),
+                                    note_id,
+                                    "encryption_upgrade",
+                                    user_id,
+                                    false,
+                                    serde_json::json!({
+                                        "job_id": job.id,
+                                        "from_level": format!("{:?}", from),
+                                        "to_level": format!("{:?}", encrypted.level),
+                                    }).to_string(),
+                                ],
+                            )?;
+                            job.upgraded += 1;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to upgrade encryption for note {}: {}", note_id, e);
+                        job.failures.push(ReencryptionFailure { note_id: note_id.clone(), error: e.to_string() });
+                    }
+                }
+                Self::save_job(&tx, &job)?;
+                tx.commit()?;
+            }
+
+            on_progress(&job);
+        }
+
+        job.status = if job.failures.is_empty() {
+            ReencryptionJobStatus::Completed
+        } else {
+            ReencryptionJobStatus::CompletedWithFailures
+        };
+        job.updated_at = Utc::now();
+        Self::save_job(&conn, &job)?;
+        on_progress(&job);
+
+        tracing::info!(
+            "Re-encryption job {} finished: {} examined, {} upgraded, {} failed",
+            job.id, job.examined, job.upgraded, job.failures.len()
+        );
+        Ok(job)
+    }
+
+    /// Re-encrypted envelope for a note that needs it, with the level it had before
+    fn upgrade_envelope(
+        &self,
+        note_id: &str,
+        patient_id: &str,
+        blob: &[u8],
+        classification: &str,
+        stored_level: &str,
+    ) -> Result<Option<(EncryptionLevel, EncryptedData)>, EncryptionError> {
+        let current = level_from_str(stored_level)?;
+        let target = required_note_level(classification_from_str(classification)?);
+        if target <= current {
+            return Ok(None);
+        }
+
+        let aad = note_aad(note_id, patient_id);
+        let existing: EncryptedData = serde_json::from_slice(blob)
+            .map_err(|e| EncryptionError::DecryptionFailed(format!("Envelope parsing failed: {}", e)))?;
+        let content = self.decrypt_content(&existing, &aad)?;
+        let upgraded = self.encrypt_content(&content, target, &aad)?;
+
+        // Verify before the old ciphertext is replaced
+        if self.decrypt_content(&upgraded, &aad)? != content {
+            return Err(EncryptionError::EncryptionFailed("Round-trip verification failed".to_string()));
+        }
+        Ok(Some((current, upgraded)))
+    }
+
+    /// Re-encrypted history envelopes of a note that are below the level its classification requires
+    fn upgrade_history(&self, conn: &Connection, note_id: &str, classification: &str) -> Result<Vec<(u32, EncryptedData)>, EncryptionError> {
+        let target = required_note_level(classification_from_str(classification)?);
+        let versions: Vec<(u32, Vec<u8>, String)> = {
+            let mut stmt = conn.prepare(
+                "SELECT version, encrypted_content, encryption_level FROM note_versions WHERE note_id = ?1 ORDER BY version"
+            )?;
+            let rows = stmt.query_map(params![note_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let mut upgraded = Vec::new();
+        for (version, blob, level) in versions {
+            if target <= level_from_str(&level)? {
+                continue;
+            }
+            let aad = note_version_aad(note_id, version);
+            let existing: EncryptedData = serde_json::from_slice(&blob)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Envelope parsing failed: {}", e)))?;
+            let content = self.decrypt_content(&existing, &aad)?;
+            let envelope = self.encrypt_content(&content, target, &aad)?;
+            if self.decrypt_content(&envelope, &aad)? != content {
+                return Err(EncryptionError::EncryptionFailed("Round-trip verification failed".to_string()));
+            }
+            upgraded.push((version, envelope));
+        }
+        Ok(upgraded)
+    }
+
+    /// Move notes from one patient to another, e.g. when duplicate client records are merged.
+    /// Ciphertext is bound to the patient ID, so each note is re-encrypted at its current level
+    /// under the new binding. `only` limits the move to specific notes (used to undo a merge).
+    /// All notes move in one transaction; returns the IDs moved.
+    pub async fn reassign_patient_notes(
+        &self,
+        from_patient_id: &str,
+        to_patient_id: &str,
+        only: Option<&[String]>,
+        user_id: &str,
+    ) -> Result<Vec<String>, EncryptionError> {
+        let mut conn = Connection::open(&self.db_path)?;
+        let notes: Vec<(String, Vec<u8>)> = {
+            let mut stmt = conn.prepare(
+                "SELECT id, encrypted_content FROM medical_notes WHERE patient_id = ?1 ORDER BY id"
+            )?;
+            let rows = stmt.query_map(params![from_patient_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .filter(|(id, _)| only.map_or(true, |ids| ids.contains(id)))
+                .collect()
+        };
+
+        let tx = conn.transaction()?;
+        let mut moved = Vec::with_capacity(notes.len());
+        for (note_id, blob) in notes {
+            let existing: EncryptedData = serde_json::from_slice(&blob)
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Envelope parsing failed: {}", e)))?;
+            let content = self.decrypt_content(&existing, &note_aad(&note_id, from_patient_id))?;
+            let new_aad = note_aad(&note_id, to_patient_id);
+            let rebound = self.encrypt_content(&content, existing.level, &new_aad)?;
+            if self.decrypt_content(&rebound, &new_aad)? != content {
+                return Err(EncryptionError::EncryptionFailed("Round-trip verification failed".to_string()));
+            }
+            let rebound_blob = serde_json::to_vec(&rebound)
+                .map_err(|e| EncryptionError::EncryptionFailed(format!("Serialization failed: {}", e)))?;
+
+            tx.execute(
+                "UPDATE medical_notes SET patient_id = ?1, encrypted_content = ?2, content_checksum = ?3 WHERE id = ?4",
+                params![to_patient_id, rebound_blob, rebound.checksum, note_id],
+            )?;
+            tx.execute(
+                "INSERT INTO audit_log (id, timestamp, note_id, action, user_id, phi_accessed, details)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    Utc::now().to_rfc3339(),
+                    note_id,
+                    "note_reassign",
+                    user_id,
+                    false,
+                    serde_json::json!({
+                        "from_patient_id": from_patient_id,
+                        "to_patient_id": to_patient_id,
+                    }).to_string(),
+                ],
+            )?;
+            moved.push(note_id);
+        }
+        tx.commit()?;
+
+        tracing::info!("Reassigned {} notes from patient {} to {}", moved.len(), from_patient_id, to_patient_id);
+        Ok(moved)
+    }
+
+    /// Look up a re-encryption job
+    pub async fn get_reencryption_job(&self, job_id: &str) -> Result<Option<ReencryptionJob>, EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        Self::load_job(&conn, job_id)
+    }
+
+    fn save_job(conn: &Connection, job: &ReencryptionJob) -> Result<(), EncryptionError> {
+        conn.execute(
+            "INSERT OR REPLACE INTO reencryption_jobs
+             (id, status, started_by, started_at, updated_at, cursor, examined, upgraded, failures)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                job.id,
+                format!("{:?}", job.status),
+                job.started_by,
+                job.started_at.to_rfc3339(),
+                job.updated_at.to_rfc3339(),
+                job.cursor,
+                job.examined as i64,
+                job.upgraded as i64,
+                serde_json::to_string(&job.failures).unwrap(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn load_job(conn: &Connection, job_id: &str) -> Result<Option<ReencryptionJob>, EncryptionError> {
+        let row = conn.query_row(
+            "SELECT id, status, started_by, started_at, updated_at, cursor, examined, upgraded, failures
+             FROM reencryption_jobs WHERE id = ?1",
+            params![job_id],
+            |row| Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, i64>(6)?,
+                row.get::<_, i64>(7)?,
+                row.get::<_, String>(8)?,
+            )),
+        );
+        let (id, status, started_by, started_at, updated_at, cursor, examined, upgraded, failures) = match row {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(EncryptionError::Database(e)),
+        };
+
+        let parse_time = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| EncryptionError::DecryptionFailed(format!("Date parsing failed: {}", e)))
+        };
+        Ok(Some(ReencryptionJob {
+            id,
+            status: match status.as_str() {
+                "Completed" => ReencryptionJobStatus::Completed,
+                "CompletedWithFailures" => ReencryptionJobStatus::CompletedWithFailures,
+                _ => ReencryptionJobStatus::Running,
+            },
+            started_by,
+            started_at: parse_time(&started_at)?,
+            updated_at: parse_time(&updated_at)?,
+            cursor,
+            examined: examined as u64,
+            upgraded: upgraded as u64,
+            failures: serde_json::from_str(&failures).unwrap_or_default(),
+        }))
+    }
+
     /// Validate Quebec Law 25 compliance
     fn validate_law25_compliance(&self, note: &MedicalNote) -> Result<(), EncryptionError> {
         if !note.consent_obtained {
@@ -474,6 +1626,25 @@ impl EncryptedNoteStorage {
         Ok(())
     }
 
+    /// Audit an action on one version of a note, recording the version in the entry's details
+    fn log_version_access(&self, note_id: &str, action: &str, user_id: &str, version: u32) -> Result<(), EncryptionError> {
+        let conn = Connection::open(&self.db_path)?;
+        conn.execute(
+            "INSERT INTO audit_log (id, timestamp, note_id, action, user_id, phi_accessed, details)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                Uuid::new_v4().to_string(),
+                Utc::now().to_rfc3339(),
+                note_id,
+                action,
+                user_id,
+                true,
+                serde_json::json!({ "version": version }).to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
     /// Get audit trail for compliance reporting
     pub async fn get_audit_trail(&self, note_id: Option<&str>, user_id: &str) -> Result<Vec<AuditEntry>, EncryptionError> {
         let conn = Connection::open(&self.db_path)?;
@@ -482,7 +1653,7 @@ impl EncryptedNoteStorage {
 
         if let Some(note_id) = note_id {
             let mut stmt = conn.prepare(
-                "SELECT timestamp, action, user_id, phi_accessed, ip_address
+                "SELECT timestamp, action, user_id, phi_accessed, ip_address, details
                  FROM audit_log
                  WHERE note_id = ?1
                  ORDER BY timestamp DESC"
@@ -496,6 +1667,7 @@ impl EncryptedNoteStorage {
                     user_id: row.get(2)?,
                     phi_accessed: row.get(3)?,
                     ip_address: row.get(4)?,
+                    note_version: audited_version(row.get(5)?),
                 })
             })?;
 
@@ -504,7 +1676,7 @@ impl EncryptedNoteStorage {
             }
         } else {
             let mut stmt = conn.prepare(
-                "SELECT timestamp, action, user_id, phi_accessed, ip_address
+                "SELECT timestamp, action, user_id, phi_accessed, ip_address, details
                  FROM audit_log
                  ORDER BY timestamp DESC
                  LIMIT 1000"
@@ -518,6 +1690,7 @@ impl EncryptedNoteStorage {
                     user_id: row.get(2)?,
                     phi_accessed: row.get(3)?,
                     ip_address: row.get(4)?,
+                    note_version: audited_version(row.get(5)?),
                 })
             })?;
 
@@ -531,4 +1704,342 @@ impl EncryptedNoteStorage {
 
         Ok(audit_entries)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_storage() -> (EncryptedNoteStorage, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("psypsy-notes-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = EncryptedNoteStorage::open(dir.join("psypsy_notes.db"), "test-passphrase").unwrap();
+        (storage, dir)
+    }
+
+    fn test_note(id: &str) -> MedicalNote {
+        MedicalNote {
+            id: id.to_string(),
+            patient_id: "patient-1".to_string(),
+            content: format!("Session notes for {}", id),
+            template_type: "progress".to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            consent_obtained: true,
+            encrypted: false,
+            deidentified: false,
+            sync_status: SyncStatus::Local,
+            quebec_compliance: QuebecComplianceMetadata {
+                law_25_consent: true,
+                data_minimization: true,
+                retention_period_days: 3650,
+                professional_order: None,
+                audit_trail: Vec::new(),
+            },
+            metadata: BTreeMap::new(),
+            version: 0,
+        }
+    }
+
+    /// Write a note the way storage did before levels existed
+    fn insert_legacy_note(storage: &EncryptedNoteStorage, note: &MedicalNote) {
+        let legacy = storage.encrypt_content(&note.content, EncryptionLevel::Strong, &[]).unwrap();
+        let conn = Connection::open(&storage.db_path).unwrap();
+        conn.execute(
+            "INSERT INTO medical_notes
+             (id, patient_id, encrypted_content, template_type, created_at, modified_at,
+              consent_obtained, sync_status, quebec_compliance, content_checksum)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                note.id,
+                note.patient_id,
+                serde_json::to_vec(&legacy).unwrap(),
+                note.template_type,
+                note.created_at.to_rfc3339(),
+                note.modified_at.to_rfc3339(),
+                true,
+                serde_json::to_string(&note.sync_status).unwrap(),
+                serde_json::to_string(&note.quebec_compliance).unwrap(),
+                legacy.checksum,
+            ],
+        ).unwrap();
+    }
+
+    fn stored_level(storage: &EncryptedNoteStorage, note_id: &str) -> EncryptionLevel {
+        let conn = Connection::open(&storage.db_path).unwrap();
+        storage.stored_protection(&conn, note_id).unwrap().unwrap().1
+    }
+
+    #[tokio::test]
+    async fn test_reclassified_note_is_upgraded_and_readable() {
+        let (storage, dir) = test_storage();
+        let note = test_note("note-a");
+        insert_legacy_note(&storage, &note);
+        let modified_at = storage.get_note("note-a", "u").await.unwrap().unwrap().modified_at;
+
+        storage.set_note_classification("note-a", DataClassification::MedicalSensitive, "admin").await.unwrap();
+        let job = storage.reencrypt_for_classification(None, "admin", 10, |_| {}).await.unwrap();
+
+        assert_eq!(job.status, ReencryptionJobStatus::Completed);
+        assert_eq!((job.examined, job.upgraded), (1, 1));
+        assert_eq!(stored_level(&storage, "note-a"), EncryptionLevel::Maximum);
+
+        let reread = storage.get_note("note-a", "u").await.unwrap().unwrap();
+        assert_eq!(reread.content, note.content);
+        assert_eq!(reread.modified_at, modified_at);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_never_downgrades() {
+        let (storage, dir) = test_storage();
+        storage.save_note(test_note("note-b"), "u").await.unwrap();
+        assert_eq!(stored_level(&storage, "note-b"), EncryptionLevel::Medical);
+
+        storage.set_note_classification("note-b", DataClassification::Internal, "admin").await.unwrap();
+        let job = storage.reencrypt_for_classification(None, "admin", 10, |_| {}).await.unwrap();
+        assert_eq!(job.upgraded, 0);
+        assert_eq!(stored_level(&storage, "note-b"), EncryptionLevel::Medical);
+
+        // A later save keeps the stronger level too
+        storage.save_note(test_note("note-b"), "u").await.unwrap();
+        assert_eq!(stored_level(&storage, "note-b"), EncryptionLevel::Medical);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_job_resumes_and_reports_failures() {
+        let (storage, dir) = test_storage();
+        for id in ["note-1", "note-2", "note-3"] {
+            insert_legacy_note(&storage, &test_note(id));
+        }
+        let conn = Connection::open(&storage.db_path).unwrap();
+        conn.execute("UPDATE medical_notes SET classification = 'Phi'", []).unwrap();
+        conn.execute("UPDATE medical_notes SET content_checksum = 'x', encrypted_content = X'7B7D' WHERE id = 'note-3'", []).unwrap();
+
+        // A run interrupted after committing its cursor at note-1
+        let interrupted = ReencryptionJob {
+            id: Uuid::new_v4().to_string(),
+            status: ReencryptionJobStatus::Running,
+            started_by: "admin".to_string(),
+            started_at: Utc::now(),
+            updated_at: Utc::now(),
+            cursor: Some("note-1".to_string()),
+            examined: 1,
+            upgraded: 0,
+            failures: Vec::new(),
+        };
+        EncryptedNoteStorage::save_job(&conn, &interrupted).unwrap();
+
+        let resumed = storage.reencrypt_for_classification(Some(&interrupted.id), "admin", 1, |_| {}).await.unwrap();
+        assert_eq!(resumed.examined, 3);
+        assert_eq!(resumed.upgraded, 1);
+        assert_eq!(stored_level(&storage, "note-1"), EncryptionLevel::Strong);
+        assert_eq!(stored_level(&storage, "note-2"), EncryptionLevel::Medical);
+        assert_eq!(resumed.status, ReencryptionJobStatus::CompletedWithFailures);
+        assert_eq!(resumed.failures.len(), 1);
+        assert_eq!(resumed.failures[0].note_id, "note-3");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_attachments_keep_scan_result_and_refuse_blocked_media() {
+        use crate::services::media_moderation::{MediaModerationConfig, MediaScanStatus, MediaScanner, ModerationAction, TextExtractor};
+
+        struct ChartOcr;
+        impl TextExtractor for ChartOcr {
+            fn extract_text(&self, _image: &[u8]) -> Result<String, String> {
+                Ok("Diagnosis: bipolar disorder".to_string())
+            }
+        }
+
+        let (storage, dir) = test_storage();
+        insert_legacy_note(&storage, &test_note("note-m"));
+        let scanner = MediaScanner::with_extractor(
+            MediaModerationConfig { enabled: true, ..MediaModerationConfig::default() },
+            std::sync::Arc::new(ChartOcr),
+        );
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+        let blocked = scanner.scan_bytes("chart.png", &png, ModerationAction::Block);
+        assert!(storage.attach_media("note-m", blocked, "dr-1").await.is_err());
+
+        let flagged = scanner.scan_bytes("chart.png", &png, ModerationAction::Flag);
+        storage.attach_media("note-m", flagged, "dr-1").await.unwrap();
+        let attachments = storage.list_attachments("note-m").await.unwrap();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].scan.status, MediaScanStatus::Flagged);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_encryption_envelopes_flag_under_encrypted_notes() {
+        use crate::security::encryption_coverage::{build_coverage_report, CoverageStatus};
+
+        let (storage, dir) = test_storage();
+        let mut pending = test_note("note-a");
+        pending.sync_status = SyncStatus::Pending;
+        storage.save_note(pending, "u").await.unwrap();
+        insert_legacy_note(&storage, &test_note("note-b"));
+        storage.set_note_classification("note-b", DataClassification::MedicalSensitive, "u").await.unwrap();
+
+        let envelopes = storage.encryption_envelopes("patient-1").unwrap();
+        let report = build_coverage_report("patient-1", "auditor", &envelopes, Vec::new());
+        let statuses: Vec<(ArtifactKind, CoverageStatus)> = report.artifacts.iter().map(|a| (a.kind, a.status)).collect();
+        assert_eq!(statuses, vec![
+            (ArtifactKind::Note, CoverageStatus::Protected),
+            (ArtifactKind::SyncQueueEntry, CoverageStatus::Protected),
+            (ArtifactKind::Note, CoverageStatus::UnderEncrypted),
+        ]);
+        assert_eq!(report.violations.len(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn dated_note(id: &str, date: &str, narrative: &str) -> MedicalNote {
+        let mut note = test_note(id);
+        note.content = narrative.to_string();
+        note.metadata.insert("appointment_date".to_string(), serde_json::json!(date));
+        note.metadata.insert("duration_minutes".to_string(), serde_json::json!(50));
+        note
+    }
+
+    #[tokio::test]
+    async fn test_metadata_is_queryable_while_body_stays_encrypted() {
+        let (storage, dir) = test_storage();
+        let narrative = "Client described recurring panic attacks after the divorce hearing";
+        storage.save_note(dated_note("note-march", "2026-03-02", narrative), "u").await.unwrap();
+        storage.save_note(dated_note("note-june", "2026-06-15", "Follow-up on sleep hygiene"), "u").await.unwrap();
+
+        let conn = Connection::open(&storage.db_path).unwrap();
+        let blobs: Vec<Vec<u8>> = conn
+            .prepare("SELECT encrypted_content FROM medical_notes").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .map(Result::unwrap)
+            .collect();
+        for blob in &blobs {
+            let envelope: EncryptedData = serde_json::from_slice(blob).unwrap();
+            assert!(!blob.windows(narrative.len()).any(|w| w == narrative.as_bytes()));
+            assert!(!envelope.ciphertext.windows(narrative.len()).any(|w| w == narrative.as_bytes()));
+        }
+        let stored_date: String = conn.query_row(
+            "SELECT value FROM note_metadata WHERE note_id = 'note-march' AND field = 'appointment_date'",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(stored_date, "2026-03-02");
+
+        let filter = NoteListFilter {
+            appointment_from: NaiveDate::from_ymd_opt(2026, 3, 1),
+            appointment_to: NaiveDate::from_ymd_opt(2026, 3, 31),
+        };
+        let notes = storage.list_notes_for_patient("patient-1", "u", 50, 0, &filter).await.unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].content, narrative);
+        assert_eq!(notes[0].metadata["duration_minutes"], serde_json::json!(50));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_phi_fields_cannot_be_stored_in_the_clear() {
+        let (storage, dir) = test_storage();
+
+        let mut diagnosis = test_note("note-dx");
+        diagnosis.metadata.insert("diagnosis".to_string(), serde_json::json!("Generalized anxiety disorder"));
+        assert!(matches!(storage.save_note(diagnosis, "u").await, Err(EncryptionError::ComplianceViolation(_))));
+
+        let mut leaky = test_note("note-leak");
+        leaky.metadata.insert("session_type".to_string(), serde_json::json!("Intake, RAMQ ABCD 1234 5678"));
+        assert!(matches!(storage.save_note(leaky, "u").await, Err(EncryptionError::ComplianceViolation(_))));
+
+        let mut bad_date = test_note("note-date");
+        bad_date.metadata.insert("appointment_date".to_string(), serde_json::json!("March 2nd"));
+        assert!(matches!(storage.save_note(bad_date, "u").await, Err(EncryptionError::ComplianceViolation(_))));
+
+        // Nothing was written for the refused notes
+        assert!(storage.get_note("note-dx", "u").await.unwrap().is_none());
+        assert!(storage.get_note("note-leak", "u").await.unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_pre_versioning_notes_get_a_readable_version_zero() {
+        let dir = std::env::temp_dir().join(format!("psypsy-notes-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("psypsy_notes.db");
+
+        // A database last migrated before note history existed
+        let mut conn = Connection::open(&db_path).unwrap();
+        crate::storage::migrations::run_migrations(&mut conn, &crate::storage::migrations::NOTES_MIGRATIONS[..4]).unwrap();
+        let legacy = EncryptedNoteStorage { db_path: db_path.clone(), master_key: EncryptedNoteStorage::derive_key("test-passphrase").unwrap() };
+        let note = test_note("note-old");
+        let level = required_note_level(DEFAULT_NOTE_CLASSIFICATION);
+        let envelope = legacy.encrypt_content(&note.content, level, &note_aad(&note.id, &note.patient_id)).unwrap();
+        conn.execute(
+            "INSERT INTO medical_notes
+             (id, patient_id, encrypted_content, template_type, created_at, modified_at, consent_obtained,
+              sync_status, quebec_compliance, content_checksum, encryption_level)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, 1, ?6, ?7, ?8, ?9)",
+            params![
+                note.id, note.patient_id, serde_json::to_vec(&envelope).unwrap(), note.template_type,
+                note.modified_at.to_rfc3339(), serde_json::to_string(&note.sync_status).unwrap(),
+                serde_json::to_string(&note.quebec_compliance).unwrap(), envelope.checksum, format!("{:?}", level),
+            ],
+        ).unwrap();
+        drop(conn);
+
+        let storage = EncryptedNoteStorage::open(db_path, "test-passphrase").unwrap();
+        let original = storage.get_note_version("note-old", 0, "auditor").await.unwrap().unwrap();
+        assert_eq!(original.content, note.content);
+        assert_eq!(storage.get_note_history("note-old", "auditor").await.unwrap().len(), 1);
+
+        // Sync reads are not user access and stay out of the trail
+        assert!(storage.read_note("note-old").await.unwrap().is_some());
+        let trail = storage.get_audit_trail(Some("note-old"), "auditor").await.unwrap();
+        assert!(!trail.iter().any(|e| e.action == "note_read"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_amendments_keep_history_and_deletes_are_soft() {
+        let (storage, dir) = test_storage();
+        let mut note = test_note("note-v");
+        note.content = "Initial assessment".to_string();
+        storage.save_note(note.clone(), "dr-a").await.unwrap();
+        note.content = "Initial assessment, amended with collateral history".to_string();
+        storage.save_note(note, "dr-b").await.unwrap();
+
+        let latest = storage.get_note("note-v", "dr-a").await.unwrap().unwrap();
+        assert_eq!(latest.version, 2);
+        let history = storage.get_note_history("note-v", "dr-a").await.unwrap();
+        let authors: Vec<(u32, &str)> = history.iter().map(|v| (v.version, v.author_id.as_str())).collect();
+        assert_eq!(authors, vec![(1, "dr-a"), (2, "dr-b")]);
+        let first = storage.get_note_version("note-v", 1, "auditor").await.unwrap().unwrap();
+        assert_eq!(first.content, "Initial assessment");
+
+        let trail = storage.get_audit_trail(Some("note-v"), "auditor").await.unwrap();
+        assert!(trail.iter().any(|e| e.action == "note_version_read" && e.user_id == "auditor" && e.note_version == Some(1)));
+        assert!(trail.iter().any(|e| e.action == "note_read" && e.note_version == Some(2)));
+
+        storage.delete_note("note-v", "dr-a").await.unwrap();
+        assert!(storage.get_note("note-v", "dr-a").await.unwrap().is_none());
+        assert!(storage.list_notes_for_patient("patient-1", "dr-a", 50, 0, &NoteListFilter::default()).await.unwrap().is_empty());
+        assert_eq!(storage.get_note_history("note-v", "auditor").await.unwrap().len(), 2);
+        assert!(matches!(storage.save_note(test_note("note-v"), "dr-a").await, Err(EncryptionError::ComplianceViolation(_))));
+
+        // Retention is 3650 days from deletion
+        assert!(storage.purge_expired_notes(Utc::now(), "system").await.unwrap().is_empty());
+        let purged = storage.purge_expired_notes(Utc::now() + chrono::Duration::days(3651), "system").await.unwrap();
+        assert_eq!(purged, vec!["note-v".to_string()]);
+        assert!(storage.get_note_history("note-v", "auditor").await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
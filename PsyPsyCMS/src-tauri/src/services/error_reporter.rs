@@ -0,0 +1,350 @@
+// Anonymized Error Reporting for PsyPsy CMS
+// Opt-in capture of panics and command failures, scrubbed of PHI before storage or forwarding.
+// Disabled by default: nothing is captured unless a deployment explicitly enables it.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// File holding locally stored reports inside the app data directory
+const REPORTS_FILE: &str = "error_reports.json";
+
+/// Placeholder written in place of scrubbed content
+const SCRUBBED: &str = "[SCRUBBED]";
+
+/// Patterns removed from every message, in order; quoted values go first since
+/// errors routinely echo user input back inside quotes
+static SCRUB_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        r#""[^"]*"|'[^']*'|`[^`]*`"#,
+        r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+        r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b",
+        // RAMQ health insurance numbers
+        r"\b[A-Z]{4}\s?\d{8}\b",
+        // Phone, SIN, card and record numbers
+        r"\d(?:[\d\s().-]{4,}\d)",
+        // Firestore-style document paths end in a document identifier
+        r"\b(?:clients|patients|professionals|appointments|medical_notes|users)/[A-Za-z0-9_-]+",
+    ]
+    .iter()
+    .map(|p| Regex::new(p).expect("valid scrub pattern"))
+    .collect()
+});
+
+/// Error reporting configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorReporterConfig {
+    /// Capture reports at all; off unless the deployment opts in
+    pub enabled: bool,
+    /// Optional endpoint receiving scrubbed reports (subject to the outbound guard)
+    pub forward_endpoint: Option<String>,
+    /// Reports kept locally before the oldest are dropped
+    pub max_stored_reports: usize,
+}
+
+impl Default for ErrorReporterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            forward_endpoint: None,
+            max_stored_reports: 200,
+        }
+    }
+}
+
+impl ErrorReporterConfig {
+    /// Defaults with `ERROR_REPORTING_ENABLED` / `ERROR_REPORT_ENDPOINT` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.enabled = std::env::var("ERROR_REPORTING_ENABLED")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        config.forward_endpoint = std::env::var("ERROR_REPORT_ENDPOINT")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        config
+    }
+}
+
+/// What produced the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorReportKind {
+    Panic,
+    CommandError,
+}
+
+/// Non-identifying runtime information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportEnvironment {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub debug_build: bool,
+}
+
+impl ReportEnvironment {
+    pub fn current() -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            debug_build: cfg!(debug_assertions),
+        }
+    }
+}
+
+/// A scrubbed error report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub id: Uuid,
+    /// Reference shown to the user so support can find the report
+    pub correlation_id: Uuid,
+    pub kind: ErrorReportKind,
+    /// Command that failed, for command errors
+    pub command: Option<String>,
+    /// Message with PHI scrubbed
+    pub message: String,
+    /// Source location, for panics
+    pub location: Option<String>,
+    pub environment: ReportEnvironment,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Remove anything that could identify a patient from a message
+pub fn scrub_message(message: &str) -> String {
+    SCRUB_PATTERNS
+        .iter()
+        .fold(message.to_string(), |text, pattern| pattern.replace_all(&text, SCRUBBED).into_owned())
+}
+
+/// Locally stored, optionally forwarded error reports
+#[derive(Debug)]
+pub struct ErrorReporter {
+    config: ErrorReporterConfig,
+    storage_path: RwLock<Option<PathBuf>>,
+    reports: RwLock<Vec<ErrorReport>>,
+}
+
+impl ErrorReporter {
+    pub fn new(config: ErrorReporterConfig) -> Self {
+        Self {
+            config,
+            storage_path: RwLock::new(None),
+            reports: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Persist reports under `dir`, loading any kept from previous runs
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        let path = dir.join(REPORTS_FILE);
+        if path.exists() {
+            let existing: Vec<ErrorReport> = serde_json::from_slice(&std::fs::read(&path)?)
+                .unwrap_or_default();
+            let mut reports = self.reports.write().unwrap();
+            let mut merged = existing;
+            merged.append(&mut reports);
+            *reports = merged;
+        }
+        *self.storage_path.write().unwrap() = Some(path);
+        self.persist();
+        Ok(())
+    }
+
+    /// Record a report; returns its correlation ID, or `None` when reporting is disabled
+    pub fn capture(
+        &self,
+        kind: ErrorReportKind,
+        command: Option<&str>,
+        message: &str,
+        location: Option<String>,
+    ) -> Option<Uuid> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let report = ErrorReport {
+            id: Uuid::new_v4(),
+            correlation_id: Uuid::new_v4(),
+            kind,
+            command: command.map(str::to_string),
+            message: scrub_message(message),
+            location,
+            environment: ReportEnvironment::current(),
+            occurred_at: Utc::now(),
+        };
+        let correlation_id = report.correlation_id;
+
+        {
+            let mut reports = self.reports.write().unwrap();
+            reports.push(report.clone());
+            let overflow = reports.len().saturating_sub(self.config.max_stored_reports);
+            reports.drain(..overflow);
+        }
+        self.persist();
+        self.forward(report);
+
+        Some(correlation_id)
+    }
+
+    /// Stored reports, newest first
+    pub fn list(&self) -> Vec<ErrorReport> {
+        let mut reports = self.reports.read().unwrap().clone();
+        reports.reverse();
+        reports
+    }
+
+    /// Remove every stored report; returns how many were removed
+    pub fn clear(&self) -> usize {
+        let removed = {
+            let mut reports = self.reports.write().unwrap();
+            let removed = reports.len();
+            reports.clear();
+            removed
+        };
+        self.persist();
+        removed
+    }
+
+    fn persist(&self) {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            return;
+        };
+        let reports = self.reports.read().unwrap();
+        match serde_json::to_vec_pretty(&*reports) {
+            Ok(bytes) => {
+                if let Err(e) = std::fs::write(&path, bytes) {
+                    log::warn!("Failed to persist error reports: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize error reports: {}", e),
+        }
+    }
+
+    /// Send an already-scrubbed report to the configured endpoint
+    fn forward(&self, report: ErrorReport) {
+        let Some(endpoint) = self.config.forward_endpoint.clone() else {
+            return;
+        };
+        tauri::async_runtime::spawn(async move {
+            let guard = crate::security::outbound::outbound_guard();
+            if let Err(e) = guard.check_url(&endpoint).await {
+                log::warn!("Error report endpoint rejected: {}", e);
+                return;
+            }
+            let client = crate::security::outbound::guarded_client();
+            if let Err(e) = client.post(&endpoint).json(&report).send().await {
+                log::warn!("Failed to forward error report {}: {}", report.correlation_id, e);
+            }
+        });
+    }
+}
+
+static ERROR_REPORTER: OnceCell<Arc<ErrorReporter>> = OnceCell::new();
+
+/// Install the process-wide reporter and its panic hook
+pub fn install_error_reporter(reporter: ErrorReporter) -> Arc<ErrorReporter> {
+    let reporter = Arc::clone(ERROR_REPORTER.get_or_init(|| Arc::new(reporter)));
+
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(reporter) = ERROR_REPORTER.get() {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic with non-string payload".to_string());
+            let location = info.location().map(|l| format!("{}:{}", l.file(), l.line()));
+            reporter.capture(ErrorReportKind::Panic, None, &message, location);
+        }
+        previous_hook(info);
+    }));
+
+    reporter
+}
+
+/// Process-wide reporter, if installed
+pub fn error_reporter() -> Option<Arc<ErrorReporter>> {
+    ERROR_REPORTER.get().cloned()
+}
+
+/// Record a command failure and append the correlation ID to the message returned to the UI
+pub fn report_command_error(command: &str, error: String) -> String {
+    match error_reporter().and_then(|r| r.capture(ErrorReportKind::CommandError, Some(command), &error, None)) {
+        Some(correlation_id) => format!("{} (ref: {})", error, correlation_id),
+        None => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_reporter() -> ErrorReporter {
+        ErrorReporter::new(ErrorReporterConfig {
+            enabled: true,
+            ..ErrorReporterConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let reporter = ErrorReporter::new(ErrorReporterConfig::default());
+        assert!(reporter.capture(ErrorReportKind::CommandError, Some("get_client"), "boom", None).is_none());
+        assert!(reporter.list().is_empty());
+    }
+
+    #[test]
+    fn test_phi_is_scrubbed() {
+        let scrubbed = scrub_message(
+            "Failed to save note for 'Marie Tremblay' (marie@example.com, RAMQ TREM12345678, 514-555-1234) at clients/abc123",
+        );
+        for leaked in ["Marie", "marie@example.com", "TREM12345678", "555", "abc123"] {
+            assert!(!scrubbed.contains(leaked), "{} leaked into {}", leaked, scrubbed);
+        }
+        assert!(scrubbed.starts_with("Failed to save note for"));
+    }
+
+    #[test]
+    fn test_capture_bounded_and_clearable() {
+        let reporter = ErrorReporter::new(ErrorReporterConfig {
+            enabled: true,
+            max_stored_reports: 2,
+            ..ErrorReporterConfig::default()
+        });
+        for i in 0..3 {
+            reporter.capture(ErrorReportKind::CommandError, Some("save_medical_note"), &format!("failure {}", i), None);
+        }
+
+        let reports = reporter.list();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].message, "failure 2");
+        assert_eq!(reporter.clear(), 2);
+        assert!(reporter.list().is_empty());
+    }
+
+    #[test]
+    fn test_reports_persist_across_restarts() {
+        let dir = std::env::temp_dir().join(format!("psypsy-reports-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let reporter = enabled_reporter();
+        reporter.attach_storage(&dir).unwrap();
+        let id = reporter.capture(ErrorReportKind::Panic, None, "index out of bounds", Some("src/lib.rs:1".into()));
+
+        let restarted = enabled_reporter();
+        restarted.attach_storage(&dir).unwrap();
+        assert_eq!(restarted.list()[0].correlation_id, id.unwrap());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
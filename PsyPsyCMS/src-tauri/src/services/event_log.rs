@@ -0,0 +1,201 @@
+// Replayable Domain Event Log
+// Bounded, sequence-numbered log of domain changes so the webview can catch up after a reload.
+// Events carry only identifiers and change types - never PHI payloads.
+
+use crate::security::HealthcareRole;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Default number of events retained per session
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// Kind of domain change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainEventKind {
+    AppointmentCreated,
+    AppointmentUpdated,
+    AppointmentCancelled,
+    AppointmentCompleted,
+    AppointmentDeleted,
+    NoteSaved,
+    NoteDeleted,
+    SyncCompleted,
+}
+
+impl DomainEventKind {
+    /// Roles allowed to see this kind of change
+    pub fn visible_to(&self, role: &HealthcareRole) -> bool {
+        use HealthcareRole::*;
+        match self {
+            // Even the existence of a clinical note is clinical information
+            DomainEventKind::NoteSaved | DomainEventKind::NoteDeleted => {
+                matches!(role, SuperAdmin | HealthcareProvider)
+            }
+            DomainEventKind::AppointmentCreated
+            | DomainEventKind::AppointmentUpdated
+            | DomainEventKind::AppointmentCancelled
+            | DomainEventKind::AppointmentCompleted
+            | DomainEventKind::AppointmentDeleted => matches!(
+                role,
+                SuperAdmin | Administrator | HealthcareProvider | AdminStaff | AdministrativeStaff | BillingStaff
+            ),
+            DomainEventKind::SyncCompleted => !matches!(role, Guest),
+        }
+    }
+}
+
+/// A single change notification; identifiers only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainEvent {
+    /// Monotonically increasing within a session epoch
+    pub seq: u64,
+    pub kind: DomainEventKind,
+    /// Entity type, e.g. `appointment` or `medical_note`
+    pub entity_type: String,
+    /// Entity identifier
+    pub entity_id: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Events returned to the frontend for replay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventReplay {
+    /// Changes when the log is reset; the frontend must refetch if it differs from what it saw
+    pub epoch: Uuid,
+    pub events: Vec<DomainEvent>,
+    /// Highest sequence number issued so far
+    pub latest_seq: u64,
+    /// Requested events were evicted from the bounded log; a full refetch is required
+    pub truncated: bool,
+}
+
+/// Bounded per-session event log
+#[derive(Debug)]
+pub struct EventLog {
+    epoch: Uuid,
+    events: VecDeque<DomainEvent>,
+    next_seq: u64,
+    capacity: usize,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            epoch: Uuid::new_v4(),
+            events: VecDeque::with_capacity(capacity),
+            next_seq: 1,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append an event, evicting the oldest when full
+    pub fn record(&mut self, kind: DomainEventKind, entity_type: &str, entity_id: &str) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(DomainEvent {
+            seq,
+            kind,
+            entity_type: entity_type.to_string(),
+            entity_id: entity_id.to_string(),
+            occurred_at: Utc::now(),
+        });
+        seq
+    }
+
+    /// Events after `since_seq` that the role may see
+    pub fn events_since(&self, since_seq: u64, role: &HealthcareRole) -> EventReplay {
+        let oldest_retained = self.events.front().map(|e| e.seq).unwrap_or(self.next_seq);
+        EventReplay {
+            epoch: self.epoch,
+            events: self.events.iter()
+                .filter(|e| e.seq > since_seq && e.kind.visible_to(role))
+                .cloned()
+                .collect(),
+            latest_seq: self.next_seq - 1,
+            truncated: since_seq + 1 < oldest_retained,
+        }
+    }
+
+    /// Start a new epoch (login/logout) so events never leak across sessions
+    pub fn reset(&mut self) {
+        self.epoch = Uuid::new_v4();
+        self.events.clear();
+        self.next_seq = 1;
+    }
+}
+
+/// Managed event log state
+#[derive(Debug, Clone)]
+pub struct EventLogState(pub Arc<Mutex<EventLog>>);
+
+impl EventLogState {
+    /// Record a change; failures to lock are logged rather than failing the command
+    pub fn record(&self, kind: DomainEventKind, entity_type: &str, entity_id: &str) {
+        match self.0.lock() {
+            Ok(mut log) => {
+                log.record(kind, entity_type, entity_id);
+            }
+            Err(e) => tracing::warn!("Event log unavailable: {}", e),
+        }
+    }
+}
+
+impl Default for EventLogState {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(EventLog::new(DEFAULT_CAPACITY))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_since_sequence() {
+        let mut log = EventLog::new(10);
+        log.record(DomainEventKind::AppointmentCreated, "appointment", "a1");
+        let seq = log.record(DomainEventKind::AppointmentUpdated, "appointment", "a1");
+        log.record(DomainEventKind::AppointmentCancelled, "appointment", "a2");
+
+        let replay = log.events_since(seq, &HealthcareRole::AdministrativeStaff);
+        assert_eq!(replay.events.len(), 1);
+        assert_eq!(replay.events[0].entity_id, "a2");
+        assert_eq!(replay.latest_seq, 3);
+        assert!(!replay.truncated);
+    }
+
+    #[test]
+    fn test_events_filtered_by_role() {
+        let mut log = EventLog::new(10);
+        log.record(DomainEventKind::NoteSaved, "medical_note", "n1");
+        log.record(DomainEventKind::AppointmentCreated, "appointment", "a1");
+
+        let billing = log.events_since(0, &HealthcareRole::BillingStaff);
+        assert_eq!(billing.events.len(), 1);
+        assert_eq!(billing.events[0].kind, DomainEventKind::AppointmentCreated);
+
+        let provider = log.events_since(0, &HealthcareRole::HealthcareProvider);
+        assert_eq!(provider.events.len(), 2);
+    }
+
+    #[test]
+    fn test_bounded_log_reports_truncation() {
+        let mut log = EventLog::new(2);
+        for i in 0..5 {
+            log.record(DomainEventKind::AppointmentUpdated, "appointment", &format!("a{}", i));
+        }
+
+        let replay = log.events_since(1, &HealthcareRole::SuperAdmin);
+        assert!(replay.truncated);
+        assert_eq!(replay.events.len(), 2);
+        assert!(!log.events_since(3, &HealthcareRole::SuperAdmin).truncated);
+    }
+}
@@ -0,0 +1,300 @@
+// Data Export Approval Workflow for PsyPsy CMS
+// Bulk and DSAR exports of PHI wait for a privacy officer's approval before any data is
+// generated. Small single-patient exports can be configured to skip the queue.
+
+use crate::security::SecurityError;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// Approval settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportApprovalConfig {
+    /// Let exports covering a single patient run without approval
+    pub auto_approve_single_patient: bool,
+    /// Hours a pending request waits for a decision before it expires
+    pub pending_ttl_hours: i64,
+    /// Hours an approved request may still be executed
+    pub approval_validity_hours: i64,
+}
+
+impl Default for ExportApprovalConfig {
+    fn default() -> Self {
+        Self {
+            auto_approve_single_patient: true,
+            pending_ttl_hours: 72,
+            approval_validity_hours: 24,
+        }
+    }
+}
+
+impl ExportApprovalConfig {
+    /// Defaults with `EXPORT_APPROVAL_*` environment overrides
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let hours = |name: &str, default: i64| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).filter(|h| *h > 0).unwrap_or(default)
+        };
+        Self {
+            auto_approve_single_patient: std::env::var("EXPORT_APPROVAL_SKIP_SINGLE_PATIENT")
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(defaults.auto_approve_single_patient),
+            pending_ttl_hours: hours("EXPORT_APPROVAL_PENDING_TTL_HOURS", defaults.pending_ttl_hours),
+            approval_validity_hours: hours("EXPORT_APPROVAL_VALIDITY_HOURS", defaults.approval_validity_hours),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// Export of selected or all patient records
+    Bulk,
+    /// Data subject access request on behalf of a patient
+    Dsar,
+}
+
+/// What an export covers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportScope {
+    pub kind: ExportKind,
+    /// Clients included; empty with `all_clients` set means every client
+    #[serde(default)]
+    pub client_ids: Vec<String>,
+    #[serde(default)]
+    pub all_clients: bool,
+}
+
+impl ExportScope {
+    /// Covers exactly one patient
+    pub fn is_single_patient(&self) -> bool {
+        !self.all_clients && self.client_ids.len() == 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportRequest {
+    pub id: Uuid,
+    pub scope: ExportScope,
+    pub requested_by: String,
+    pub justification: String,
+    pub status: ExportRequestStatus,
+    pub created_at: DateTime<Utc>,
+    /// Deadline for the current step: a decision while pending, execution once approved
+    pub expires_at: DateTime<Utc>,
+    /// Approver or rejecter; `None` for auto-approved requests
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_note: Option<String>,
+    /// Approved without review under the single-patient rule
+    pub auto_approved: bool,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Export requests awaiting or past a decision
+#[derive(Debug, Clone, Default)]
+pub struct ExportApprovalState(pub Arc<RwLock<HashMap<Uuid, ExportRequest>>>);
+
+impl ExportApprovalState {
+    /// File a new request; single-patient requests are approved immediately when configured
+    pub fn submit(
+        &self,
+        scope: ExportScope,
+        requested_by: &str,
+        justification: &str,
+        config: &ExportApprovalConfig,
+    ) -> Result<ExportRequest, SecurityError> {
+        let justification = justification.trim();
+        if justification.is_empty() {
+            return Err(SecurityError::ValidationFailed {
+                reason: "An export request must state its justification".to_string(),
+            });
+        }
+        if !scope.all_clients && scope.client_ids.is_empty() {
+            return Err(SecurityError::ValidationFailed {
+                reason: "An export request must name the clients it covers".to_string(),
+            });
+        }
+
+        let now = Utc::now();
+        let auto_approved = config.auto_approve_single_patient && scope.is_single_patient();
+        let request = ExportRequest {
+            id: Uuid::new_v4(),
+            scope,
+            requested_by: requested_by.to_string(),
+            justification: justification.to_string(),
+            status: if auto_approved { ExportRequestStatus::Approved } else { ExportRequestStatus::Pending },
+            created_at: now,
+            expires_at: now + Duration::hours(if auto_approved {
+                config.approval_validity_hours
+            } else {
+                config.pending_ttl_hours
+            }),
+            decided_by: None,
+            decided_at: auto_approved.then_some(now),
+            decision_note: None,
+            auto_approved,
+            completed_at: None,
+        };
+        self.0.write().unwrap().insert(request.id, request.clone());
+        Ok(request)
+    }
+
+    /// Approve or reject a pending request; the approver must not be the requester
+    pub fn decide(
+        &self,
+        id: Uuid,
+        approver: &str,
+        approve: bool,
+        note: Option<String>,
+        config: &ExportApprovalConfig,
+    ) -> Result<ExportRequest, SecurityError> {
+        let now = Utc::now();
+        let mut requests = self.0.write().unwrap();
+        let request = requests.get_mut(&id).ok_or_else(|| SecurityError::ValidationFailed {
+            reason: format!("Export request {} not found", id),
+        })?;
+        if request.status != ExportRequestStatus::Pending || request.expires_at <= now {
+            return Err(SecurityError::ValidationFailed {
+                reason: format!("Export request {} is no longer awaiting a decision", id),
+            });
+        }
+        if request.requested_by == approver {
+            return Err(SecurityError::AccessDenied {
+                reason: "An export request cannot be decided by its requester".to_string(),
+            });
+        }
+
+        request.status = if approve { ExportRequestStatus::Approved } else { ExportRequestStatus::Rejected };
+        request.decided_by = Some(approver.to_string());
+        request.decided_at = Some(now);
+        request.decision_note = note;
+        if approve {
+            request.expires_at = now + Duration::hours(config.approval_validity_hours);
+        }
+        Ok(request.clone())
+    }
+
+    /// Claim an approved request for execution by its requester; each approval runs once
+    pub fn begin_export(&self, id: Uuid, user_id: &str) -> Result<ExportRequest, SecurityError> {
+        let now = Utc::now();
+        let mut requests = self.0.write().unwrap();
+        let request = requests.get_mut(&id).ok_or_else(|| SecurityError::ValidationFailed {
+            reason: format!("Export request {} not found", id),
+        })?;
+        if request.requested_by != user_id {
+            return Err(SecurityError::AccessDenied {
+                reason: "Only the requester can run an approved export".to_string(),
+            });
+        }
+        if request.status != ExportRequestStatus::Approved || request.expires_at <= now {
+            return Err(SecurityError::AccessDenied {
+                reason: format!("Export request {} is not approved for execution", id),
+            });
+        }
+        request.status = ExportRequestStatus::Completed;
+        request.completed_at = Some(now);
+        Ok(request.clone())
+    }
+
+    /// Remove requests whose decision or execution window has lapsed; returns what was removed
+    pub fn purge_expired(&self, now: DateTime<Utc>) -> Vec<ExportRequest> {
+        let mut requests = self.0.write().unwrap();
+        let expired: Vec<Uuid> = requests
+            .values()
+            .filter(|r| matches!(r.status, ExportRequestStatus::Pending | ExportRequestStatus::Approved))
+            .filter(|r| r.expires_at <= now)
+            .map(|r| r.id)
+            .collect();
+        expired.iter().filter_map(|id| requests.remove(id)).collect()
+    }
+
+    pub fn list(&self, status: Option<ExportRequestStatus>) -> Vec<ExportRequest> {
+        let mut requests: Vec<ExportRequest> = self
+            .0
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| status.map_or(true, |s| r.status == s))
+            .cloned()
+            .collect();
+        requests.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulk() -> ExportScope {
+        ExportScope {
+            kind: ExportKind::Bulk,
+            client_ids: vec![],
+            all_clients: true,
+        }
+    }
+
+    #[test]
+    fn test_bulk_export_needs_distinct_approver() {
+        let state = ExportApprovalState::default();
+        let config = ExportApprovalConfig::default();
+        let request = state.submit(bulk(), "analyst", "Annual program evaluation", &config).unwrap();
+        assert_eq!(request.status, ExportRequestStatus::Pending);
+
+        assert!(state.begin_export(request.id, "analyst").is_err());
+        assert!(matches!(
+            state.decide(request.id, "analyst", true, None, &config),
+            Err(SecurityError::AccessDenied { .. })
+        ));
+
+        let approved = state.decide(request.id, "privacy-officer", true, None, &config).unwrap();
+        assert_eq!(approved.decided_by.as_deref(), Some("privacy-officer"));
+        assert!(state.begin_export(request.id, "analyst").is_ok());
+        // An approval covers one run
+        assert!(state.begin_export(request.id, "analyst").is_err());
+    }
+
+    #[test]
+    fn test_single_patient_skips_approval_when_configured() {
+        let state = ExportApprovalState::default();
+        let scope = ExportScope {
+            kind: ExportKind::Dsar,
+            client_ids: vec!["client-1".to_string()],
+            all_clients: false,
+        };
+
+        let request = state.submit(scope.clone(), "clerk", "Patient access request", &ExportApprovalConfig::default()).unwrap();
+        assert!(request.auto_approved);
+        assert_eq!(request.status, ExportRequestStatus::Approved);
+
+        let strict = ExportApprovalConfig { auto_approve_single_patient: false, ..ExportApprovalConfig::default() };
+        let request = state.submit(scope, "clerk", "Patient access request", &strict).unwrap();
+        assert_eq!(request.status, ExportRequestStatus::Pending);
+    }
+
+    #[test]
+    fn test_expired_requests_are_purged() {
+        let state = ExportApprovalState::default();
+        let config = ExportApprovalConfig::default();
+        let pending = state.submit(bulk(), "analyst", "Quarterly report", &config).unwrap();
+
+        assert!(state.purge_expired(Utc::now()).is_empty());
+        let purged = state.purge_expired(Utc::now() + Duration::hours(config.pending_ttl_hours + 1));
+        assert_eq!(purged.len(), 1);
+        assert!(state.decide(pending.id, "privacy-officer", true, None, &config).is_err());
+    }
+}
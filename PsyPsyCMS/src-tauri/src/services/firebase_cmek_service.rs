@@ -15,12 +15,23 @@
  * - Backup and recovery procedures for encryption keys
  */
 
+use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
-use sqlx::{Pool, Sqlite};
+use sqlx::{Pool, Row, Sqlite};
 use thiserror::Error;
+use crate::services::firebase_service_simple::{load_service_account, mint_scoped_access_token, AccessToken};
+
+/// OAuth scope for Cloud KMS administration
+const CLOUD_KMS_SCOPE: &str = "https://www.googleapis.com/auth/cloudkms";
+
+/// Keys due for rotation within this many days are reported as pending
+const ROTATION_PENDING_WINDOW_DAYS: i64 = 7;
+
+/// How often the automatic rotation task looks for overdue keys
+const ROTATION_CHECK_INTERVAL_SECS: u64 = 3600;
 
 #[derive(Error, Debug)]
 pub enum CMEKError {
@@ -72,6 +83,10 @@ pub struct CMEKConfig {
     pub backup_retention_days: u32,
     pub access_control_enabled: bool,
     pub service_account_email: String,
+    /// Service-account key used to mint Cloud KMS tokens
+    pub service_account_path: String,
+    /// Cloud KMS REST API base URL
+    pub kms_endpoint: String,
 }
 
 impl Default for CMEKConfig {
@@ -94,6 +109,9 @@ impl Default for CMEKConfig {
             access_control_enabled: true,
             service_account_email: std::env::var("FIREBASE_SERVICE_ACCOUNT_EMAIL")
                 .unwrap_or_else(|_| "firebase-cmek@psypsy-cms-quebec.iam.gserviceaccount.com".to_string()),
+            service_account_path: std::env::var("FIREBASE_SERVICE_ACCOUNT_PATH")
+                .unwrap_or_else(|_| "firebase-service-account.json".to_string()),
+            kms_endpoint: "https://cloudkms.googleapis.com/v1".to_string(),
         }
     }
 }
@@ -210,16 +228,143 @@ pub struct CMEKAccessGrant {
     pub last_used: Option<DateTime<Utc>>,
 }
 
+/// Error message from a Cloud KMS error body (`{"error": {"message": ..., "status": ...}}`)
+fn kms_error_message(status: reqwest::StatusCode, body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("HTTP {}: {}", status, body))
+}
+
+/// Send a Cloud KMS request; `Ok(None)` means the resource already exists
+async fn send_kms_request(request: reqwest::RequestBuilder) -> Result<Option<serde_json::Value>, CMEKError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| CMEKError::Network(format!("Cloud KMS request failed: {}", e)))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::CONFLICT {
+        return Ok(None);
+    }
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(CMEKError::CloudKMS(kms_error_message(status, &body)));
+    }
+    serde_json::from_str(&body)
+        .map(Some)
+        .map_err(|e| CMEKError::CloudKMS(format!("Unexpected Cloud KMS response: {}", e)))
+}
+
+/// Create a key ring under `parent` (`projects/{p}/locations/{l}`). A key ring that already
+/// exists counts as created, so initialization can be re-run.
+async fn create_kms_key_ring(
+    client: &reqwest::Client,
+    endpoint: &str,
+    token: &str,
+    parent: &str,
+    key_ring_id: &str,
+) -> Result<String, CMEKError> {
+    let request = client
+        .post(format!("{}/{}/keyRings", endpoint.trim_end_matches('/'), parent))
+        .query(&[("keyRingId", key_ring_id)])
+        .bearer_auth(token)
+        .json(&serde_json::json!({}));
+
+    match send_kms_request(request).await? {
+        Some(body) => Ok(body["name"].as_str().map(str::to_string).unwrap_or_else(|| format!("{}/keyRings/{}", parent, key_ring_id))),
+        None => {
+            tracing::info!("📋 KMS key ring {} already exists", key_ring_id);
+            Ok(format!("{}/keyRings/{}", parent, key_ring_id))
+        }
+    }
+}
+
+/// Create a crypto key in `key_ring`, or fetch it when it already exists
+async fn create_kms_crypto_key(
+    client: &reqwest::Client,
+    endpoint: &str,
+    token: &str,
+    key_ring: &str,
+    key_id: &str,
+    body: &serde_json::Value,
+) -> Result<serde_json::Value, CMEKError> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let request = client
+        .post(format!("{}/{}/cryptoKeys", endpoint, key_ring))
+        .query(&[("cryptoKeyId", key_id)])
+        .bearer_auth(token)
+        .json(body);
+
+    if let Some(created) = send_kms_request(request).await? {
+        return Ok(created);
+    }
+    let existing = client
+        .get(format!("{}/{}/cryptoKeys/{}", endpoint, key_ring, key_id))
+        .bearer_auth(token);
+    send_kms_request(existing)
+        .await?
+        .ok_or_else(|| CMEKError::CloudKMS(format!("Crypto key {} conflicts but could not be read", key_id)))
+}
+
+/// Map a Cloud KMS `CryptoKey` resource onto the stored key record
+/// Disabled or destroyed keys cannot be rotated and are never reported as due
+fn rotatable(key: &CMEKKeyInfo) -> bool {
+    !matches!(key.state.as_str(), "DISABLED" | "DESTROYED")
+}
+
+fn rotation_overdue(key: &CMEKKeyInfo, now: DateTime<Utc>) -> bool {
+    rotatable(key) && key.next_rotation_time.map_or(false, |t| t <= now)
+}
+
+fn rotation_pending(key: &CMEKKeyInfo, now: DateTime<Utc>) -> bool {
+    rotatable(key)
+        && key.next_rotation_time.map_or(false, |t| t > now && t <= now + chrono::Duration::days(ROTATION_PENDING_WINDOW_DAYS))
+}
+
+fn parse_crypto_key(service: &str, key_id: &str, location: &str, key: &serde_json::Value) -> Result<CMEKKeyInfo, CMEKError> {
+    let time = |value: &serde_json::Value| {
+        value.as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let text = |value: &serde_json::Value, default: &str| value.as_str().unwrap_or(default).to_string();
+
+    let key_name = key["name"].as_str()
+        .ok_or_else(|| CMEKError::CloudKMS("Crypto key response has no name".to_string()))?
+        .to_string();
+    // The resource name is authoritative for where the key actually lives
+    let in_region = key_name.contains(&format!("/locations/{}/", location));
+    let primary = &key["primary"];
+
+    Ok(CMEKKeyInfo {
+        key_id: key_id.to_string(),
+        key_name,
+        service: service.to_string(),
+        purpose: text(&key["purpose"], "ENCRYPT_DECRYPT"),
+        algorithm: text(&key["versionTemplate"]["algorithm"], "GOOGLE_SYMMETRIC_ENCRYPTION"),
+        protection_level: text(&key["versionTemplate"]["protectionLevel"], "SOFTWARE"),
+        state: text(&primary["state"], "PENDING_GENERATION"),
+        create_time: time(&key["createTime"]).unwrap_or_else(Utc::now),
+        primary_version: primary["name"].as_str().and_then(|n| n.rsplit('/').next()).map(str::to_string),
+        next_rotation_time: time(&key["nextRotationTime"]),
+        rotation_period: key["rotationPeriod"].as_str().map(str::to_string),
+        labels: serde_json::from_value(key["labels"].clone()).unwrap_or_default(),
+        quebec_compliant: in_region,
+        data_residency_confirmed: in_region,
+    })
+}
+
 pub struct FirebaseCMEKService {
     config: CMEKConfig,
     db_pool: Pool<Sqlite>,
     kms_client: Option<reqwest::Client>,
-    auth_token: Option<String>,
+    auth_token: tokio::sync::Mutex<Option<AccessToken>>,
 }
 
 impl FirebaseCMEKService {
     pub fn new(config: CMEKConfig, db_pool: Pool<Sqlite>) -> Self {
-        let kms_client = reqwest::Client::builder()
+        let kms_client = crate::security::outbound::guarded_client_builder()
             .timeout(std::time::Duration::from_secs(60))
             .build()
             .ok();
@@ -228,8 +373,31 @@ impl FirebaseCMEKService {
             config,
             db_pool,
             kms_client,
-            auth_token: None,
+            auth_token: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    fn kms_client(&self) -> Result<&reqwest::Client, CMEKError> {
+        self.kms_client.as_ref()
+            .ok_or_else(|| CMEKError::Configuration("Cloud KMS HTTP client is unavailable".to_string()))
+    }
+
+    /// Cloud KMS bearer token from the service account, minted again shortly before expiry
+    async fn kms_token(&self) -> Result<String, CMEKError> {
+        let mut cached = self.auth_token.lock().await;
+        if let Some(token) = cached.as_ref().filter(|t| !t.needs_refresh()) {
+            return Ok(token.bearer().to_string());
         }
+
+        let credential = load_service_account(&self.config.service_account_path)
+            .await
+            .map_err(|e| CMEKError::Authentication(e.to_string()))?;
+        let token = mint_scoped_access_token(&credential, CLOUD_KMS_SCOPE)
+            .await
+            .map_err(|e| CMEKError::Authentication(e.to_string()))?;
+        let bearer = token.bearer().to_string();
+        *cached = Some(token);
+        Ok(bearer)
     }
 
     /// Validate Quebec Law 25 compliance for CMEK configuration
@@ -330,51 +498,45 @@ impl FirebaseCMEKService {
 
     /// Create Cloud KMS key ring for Quebec region
     async fn create_key_ring(&self) -> Result<String, CMEKError> {
-        let key_ring_name = format!(
-            "projects/{}/locations/{}/keyRings/{}",
-            self.config.project_id, self.config.location, self.config.key_ring_id
-        );
+        // Never reach KMS with a configuration that could place keys outside Montreal
+        self.validate_quebec_compliance()?;
 
-        // This is a mock implementation for development
-        // In production, this would use the Google Cloud KMS API
-        tracing::info!("📋 Creating KMS key ring: {}", key_ring_name);
+        let parent = format!("projects/{}/locations/{}", self.config.project_id, self.config.location);
+        tracing::info!("📋 Creating KMS key ring: {}/keyRings/{}", parent, self.config.key_ring_id);
 
-        // Simulate key ring creation with Quebec compliance
-        Ok(key_ring_name)
+        let token = self.kms_token().await?;
+        create_kms_key_ring(self.kms_client()?, &self.config.kms_endpoint, &token, &parent, &self.config.key_ring_id).await
     }
 
     /// Create encryption key for a specific Firebase service
     async fn create_encryption_key(&self, service: &str, key_id: &str) -> Result<CMEKKeyInfo, CMEKError> {
-        let key_name = format!(
-            "projects/{}/locations/{}/keyRings/{}/cryptoKeys/{}",
-            self.config.project_id, self.config.location, self.config.key_ring_id, key_id
+        self.validate_quebec_compliance()?;
+
+        let key_ring = format!(
+            "projects/{}/locations/{}/keyRings/{}",
+            self.config.project_id, self.config.location, self.config.key_ring_id
         );
+        tracing::info!("🔑 Creating encryption key for {}: {}/cryptoKeys/{}", service, key_ring, key_id);
 
-        // This is a mock implementation for development
-        // In production, this would use the Google Cloud KMS API
-        tracing::info!("🔑 Creating encryption key for {}: {}", service, key_name);
+        let body = serde_json::json!({
+            "purpose": "ENCRYPT_DECRYPT",
+            "versionTemplate": {
+                "algorithm": "GOOGLE_SYMMETRIC_ENCRYPTION",
+                "protectionLevel": "HSM", // Use HSM for healthcare data
+            },
+            "rotationPeriod": format!("{}s", self.config.rotation_period_days as u64 * 24 * 3600),
+            "nextRotationTime": (Utc::now() + chrono::Duration::days(self.config.rotation_period_days as i64)).to_rfc3339(),
+            "labels": {
+                "service": service,
+                "compliance": "quebec-law-25",
+                "environment": "production",
+                "data-classification": "healthcare",
+            },
+        });
 
-        let key_info = CMEKKeyInfo {
-            key_id: key_id.to_string(),
-            key_name: key_name.clone(),
-            service: service.to_string(),
-            purpose: "ENCRYPT_DECRYPT".to_string(),
-            algorithm: "GOOGLE_SYMMETRIC_ENCRYPTION".to_string(),
-            protection_level: "HSM".to_string(), // Use HSM for healthcare data
-            state: "ENABLED".to_string(),
-            create_time: Utc::now(),
-            primary_version: Some("1".to_string()),
-            next_rotation_time: Some(Utc::now() + chrono::Duration::days(self.config.rotation_period_days as i64)),
-            rotation_period: Some(format!("{}s", self.config.rotation_period_days * 24 * 3600)),
-            labels: HashMap::from([
-                ("service".to_string(), service.to_string()),
-                ("compliance".to_string(), "quebec-law-25".to_string()),
-                ("environment".to_string(), "production".to_string()),
-                ("data-classification".to_string(), "healthcare".to_string()),
-            ]),
-            quebec_compliant: true,
-            data_residency_confirmed: true,
-        };
+        let token = self.kms_token().await?;
+        let key = create_kms_crypto_key(self.kms_client()?, &self.config.kms_endpoint, &token, &key_ring, key_id, &body).await?;
+        let key_info = parse_crypto_key(service, key_id, &self.config.location, &key)?;
 
         // Store key info in database
         self.store_key_info(&key_info).await?;
@@ -500,8 +662,71 @@ impl FirebaseCMEKService {
         Ok(())
     }
 
-    /// Manually rotate a specific key
+    /// Manually rotate a specific key; `initiated_by` needs a live grant for "rotate" on it
     pub async fn rotate_key(&self, key_id: &str, initiated_by: &str) -> Result<String, CMEKError> {
+        self.authorize_operation(initiated_by, key_id, "rotate").await?;
+        self.rotate_key_version(key_id, initiated_by).await
+    }
+
+    /// Encrypt with a key on behalf of `user_id`, who needs a live grant for "encrypt" on it.
+    /// Returns the base64 ciphertext from Cloud KMS.
+    pub async fn encrypt(&self, user_id: &str, key_id: &str, plaintext: &[u8]) -> Result<String, CMEKError> {
+        self.authorize_operation(user_id, key_id, "encrypt").await?;
+        let body = serde_json::json!({ "plaintext": general_purpose::STANDARD.encode(plaintext) });
+        let response = self.use_key(user_id, key_id, "ENCRYPT", &body).await?;
+        response["ciphertext"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| CMEKError::CloudKMS("Encrypt response has no ciphertext".to_string()))
+    }
+
+    /// Decrypt base64 ciphertext on behalf of `user_id`, who needs a live grant for "decrypt"
+    /// on the key
+    pub async fn decrypt(&self, user_id: &str, key_id: &str, ciphertext: &str) -> Result<Vec<u8>, CMEKError> {
+        self.authorize_operation(user_id, key_id, "decrypt").await?;
+        let body = serde_json::json!({ "ciphertext": ciphertext });
+        let response = self.use_key(user_id, key_id, "DECRYPT", &body).await?;
+        let plaintext = response["plaintext"].as_str()
+            .ok_or_else(|| CMEKError::CloudKMS("Decrypt response has no plaintext".to_string()))?;
+        general_purpose::STANDARD.decode(plaintext)
+            .map_err(|e| CMEKError::CloudKMS(format!("Decrypt response is not base64: {}", e)))
+    }
+
+    /// Call `:encrypt` or `:decrypt` on a key and log the use; callers authorize first
+    async fn use_key(&self, user_id: &str, key_id: &str, operation_type: &str, body: &serde_json::Value) -> Result<serde_json::Value, CMEKError> {
+        let key_name = format!(
+            "projects/{}/locations/{}/keyRings/{}/cryptoKeys/{}",
+            self.config.project_id, self.config.location, self.config.key_ring_id, key_id
+        );
+        let token = self.kms_token().await?;
+        let request = self.kms_client()?
+            .post(format!("{}/{}:{}", self.config.kms_endpoint.trim_end_matches('/'), key_name, operation_type.to_ascii_lowercase()))
+            .bearer_auth(token)
+            .json(body);
+        let result = send_kms_request(request)
+            .await
+            .and_then(|response| response.ok_or_else(|| CMEKError::CloudKMS(format!("{} on {} returned a conflict", operation_type, key_id))));
+
+        self.log_cmek_operation(&CMEKOperation {
+            operation_id: Uuid::new_v4().to_string(),
+            operation_type: operation_type.to_string(),
+            key_id: key_id.to_string(),
+            service: "kms".to_string(),
+            initiated_by: user_id.to_string(),
+            initiated_at: Utc::now(),
+            completed_at: Some(Utc::now()),
+            status: if result.is_ok() { "COMPLETED" } else { "FAILED" }.to_string(),
+            error_message: result.as_ref().err().map(|e| e.to_string()),
+            metadata: HashMap::new(),
+            quebec_compliance_verified: true,
+            audit_logged: true,
+        }).await?;
+
+        result
+    }
+
+    /// Create a new primary version of the key. Scheduled rotation calls this directly; user
+    /// requests go through `rotate_key`.
+    async fn rotate_key_version(&self, key_id: &str, initiated_by: &str) -> Result<String, CMEKError> {
         tracing::info!("🔄 Rotating key: {}", key_id);
 
         let operation_id = Uuid::new_v4().to_string();
@@ -547,6 +772,14 @@ impl FirebaseCMEKService {
 
         self.store_key_version(&key_version).await?;
 
+        // The new version starts a fresh rotation period
+        sqlx::query("UPDATE cmek_keys SET primary_version = ?, next_rotation_time = ? WHERE key_id = ?")
+            .bind(&new_version_id)
+            .bind(Utc::now() + chrono::Duration::days(self.config.rotation_period_days as i64))
+            .bind(key_id)
+            .execute(&self.db_pool)
+            .await?;
+
         // Update operation as completed
         let completed_operation = CMEKOperation {
             completed_at: Some(Utc::now()),
@@ -560,6 +793,67 @@ impl FirebaseCMEKService {
         Ok(new_version_id)
     }
 
+    /// All keys on record
+    async fn list_keys(&self) -> Result<Vec<CMEKKeyInfo>, CMEKError> {
+        let rows = sqlx::query("SELECT * FROM cmek_keys")
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| CMEKKeyInfo {
+            key_id: row.get("key_id"),
+            key_name: row.get("key_name"),
+            service: row.get("service"),
+            purpose: row.get("purpose"),
+            algorithm: row.get("algorithm"),
+            protection_level: row.get("protection_level"),
+            state: row.get("state"),
+            create_time: row.get("create_time"),
+            primary_version: row.get("primary_version"),
+            next_rotation_time: row.get("next_rotation_time"),
+            rotation_period: row.get("rotation_period"),
+            labels: row.get::<Option<String>, _>("labels_json")
+                .and_then(|labels| serde_json::from_str(&labels).ok())
+                .unwrap_or_default(),
+            quebec_compliant: row.get("quebec_compliant"),
+            data_residency_confirmed: row.get("data_residency_confirmed"),
+        }).collect())
+    }
+
+    /// Keys whose rotation time has passed; disabled and destroyed keys are left out
+    pub async fn list_keys_needing_rotation(&self) -> Result<Vec<CMEKKeyInfo>, CMEKError> {
+        let now = Utc::now();
+        Ok(self.list_keys().await?.into_iter().filter(|key| rotation_overdue(key, now)).collect())
+    }
+
+    /// Rotate every overdue key, returning the new version IDs. One failed key does not stop the
+    /// others from being rotated.
+    pub async fn rotate_overdue_keys(&self) -> Result<Vec<String>, CMEKError> {
+        let mut rotated = Vec::new();
+        for key in self.list_keys_needing_rotation().await? {
+            match self.rotate_key_version(&key.key_id, "system_rotation").await {
+                Ok(version) => rotated.push(version),
+                Err(e) => tracing::error!("❌ Automatic rotation of {} failed: {}", key.key_id, e),
+            }
+        }
+        Ok(rotated)
+    }
+
+    /// Automatic rotation task; runs until dropped
+    pub async fn run_automatic_rotation(&self) {
+        if !self.config.enable_automatic_rotation {
+            return;
+        }
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(ROTATION_CHECK_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            match self.rotate_overdue_keys().await {
+                Ok(rotated) if !rotated.is_empty() => tracing::info!("🔄 Rotated {} overdue CMEK key(s)", rotated.len()),
+                Ok(_) => {}
+                Err(e) => tracing::error!("❌ CMEK rotation check failed: {}", e),
+            }
+        }
+    }
+
     /// Request access to a CMEK key for healthcare operations
     pub async fn request_key_access(&self, request: CMEKAccessRequest) -> Result<String, CMEKError> {
         tracing::info!("🔐 Processing key access request: {} for key: {}", request.request_id, request.key_id);
@@ -631,6 +925,96 @@ impl FirebaseCMEKService {
         Ok(grant_id)
     }
 
+    /// Check that the user holds a live grant for the operation on the key before it runs, and
+    /// count the use. Revoked or expired grants never authorize anything.
+    pub async fn authorize_operation(&self, user_id: &str, key_id: &str, operation: &str) -> Result<(), CMEKError> {
+        let query = r#"
+            SELECT g.grant_id, g.operations_allowed_json, g.expires_at,
+                   COALESCE(r.emergency_access, FALSE) as emergency_access
+            FROM cmek_access_grants g
+            LEFT JOIN cmek_access_requests r ON r.request_id = g.request_id
+            WHERE g.user_id = ? AND g.key_id = ? AND g.revoked = FALSE
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(user_id)
+            .bind(key_id)
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        let now = Utc::now();
+        let grant = rows.iter().find(|row| {
+            let operations: Vec<String> = serde_json::from_str(&row.get::<String, _>("operations_allowed_json")).unwrap_or_default();
+            row.get::<DateTime<Utc>, _>("expires_at") > now && operations.iter().any(|op| op == operation)
+        });
+        let Some(grant) = grant else {
+            tracing::warn!("🚫 CMEK {} on key {} denied for {}: no active grant", operation, key_id, user_id);
+            return Err(CMEKError::AccessDenied(format!(
+                "No active grant allows '{}' on key {}", operation, key_id
+            )));
+        };
+        let grant_id: String = grant.get("grant_id");
+
+        // Conditional on the grant still being live, so a revocation that lands first wins
+        let updated = sqlx::query(
+            "UPDATE cmek_access_grants SET usage_count = usage_count + 1, last_used = ? WHERE grant_id = ? AND revoked = FALSE",
+        )
+            .bind(now)
+            .bind(&grant_id)
+            .execute(&self.db_pool)
+            .await?;
+        if updated.rows_affected() == 0 {
+            return Err(CMEKError::AccessDenied(format!("Grant {} was revoked", grant_id)));
+        }
+
+        if grant.get::<bool, _>("emergency_access") {
+            tracing::warn!("🚨 Emergency CMEK grant {} used: {} on key {} by {}", grant_id, operation, key_id, user_id);
+        }
+        Ok(())
+    }
+
+    /// Revoke a grant; the holder loses access on their next operation
+    pub async fn revoke_grant(&self, grant_id: &str, revoked_by: &str, reason: &str) -> Result<(), CMEKError> {
+        let query = r#"
+            UPDATE cmek_access_grants
+            SET revoked = TRUE, revoked_at = ?, revoked_by = ?, revocation_reason = ?
+            WHERE grant_id = ? AND revoked = FALSE
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(Utc::now())
+            .bind(revoked_by)
+            .bind(reason)
+            .bind(grant_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(CMEKError::KeyManagement(format!("Grant {} not found or already revoked", grant_id)));
+        }
+
+        self.log_cmek_operation(&CMEKOperation {
+            operation_id: Uuid::new_v4().to_string(),
+            operation_type: "REVOKE_GRANT".to_string(),
+            key_id: grant_id.to_string(),
+            service: "kms".to_string(),
+            initiated_by: revoked_by.to_string(),
+            initiated_at: Utc::now(),
+            completed_at: Some(Utc::now()),
+            status: "COMPLETED".to_string(),
+            error_message: None,
+            metadata: HashMap::from([
+                ("grant_id".to_string(), grant_id.to_string()),
+                ("reason".to_string(), reason.to_string()),
+            ]),
+            quebec_compliance_verified: true,
+            audit_logged: true,
+        }).await?;
+
+        tracing::info!("🔒 CMEK grant {} revoked by {}", grant_id, revoked_by);
+        Ok(())
+    }
+
     /// Get CMEK metrics for monitoring and compliance
     pub async fn get_metrics(&self) -> Result<CMEKMetrics, CMEKError> {
         let keys_query = r#"
@@ -678,12 +1062,27 @@ impl FirebaseCMEKService {
         let total_ops: i64 = compliance_row.get("total_ops");
         let compliant_ops: i64 = compliance_row.get("compliant_ops");
 
+        let now = Utc::now();
+        let keys = self.list_keys().await?;
+        let pending_rotation_keys = keys.iter().filter(|k| rotation_pending(k, now)).count() as i32;
+        let overdue_rotation_keys = keys.iter().filter(|k| rotation_overdue(k, now)).count() as i32;
+        let next_scheduled_rotation = keys.iter()
+            .filter(|k| rotatable(k))
+            .filter_map(|k| k.next_rotation_time)
+            .min();
+
+        let last_rotation_row = sqlx::query(
+            "SELECT MAX(completed_at) as last_rotation FROM cmek_operations WHERE operation_type = 'ROTATE_KEY' AND status = 'COMPLETED'",
+        )
+            .fetch_one(&self.db_pool)
+            .await?;
+
         Ok(CMEKMetrics {
             total_keys: keys_row.get("total_keys"),
             active_keys: keys_row.get("active_keys"),
             disabled_keys: keys_row.get("disabled_keys"),
-            pending_rotation_keys: 0, // Would calculate from rotation schedules
-            overdue_rotation_keys: 0,  // Would calculate from rotation schedules
+            pending_rotation_keys,
+            overdue_rotation_keys,
             total_operations_today: operations_row.get("total_operations"),
             successful_operations_today: operations_row.get("successful_operations"),
             failed_operations_today: operations_row.get("failed_operations"),
@@ -693,8 +1092,8 @@ impl FirebaseCMEKService {
             } else {
                 100.0
             },
-            last_successful_rotation: None, // Would query from operations
-            next_scheduled_rotation: None,  // Would calculate from rotation schedules
+            last_successful_rotation: last_rotation_row.get("last_rotation"),
+            next_scheduled_rotation,
         })
     }
 
@@ -948,67 +1347,12 @@ mod tests {
 
         let pool = sqlx::SqlitePool::connect(&database_url).await.unwrap();
 
-        // Create test tables (simplified versions)
-        sqlx::query(r#"
-            CREATE TABLE cmek_keys (
-                id TEXT PRIMARY KEY,
-                key_id TEXT NOT NULL,
-                key_name TEXT NOT NULL,
-                service TEXT NOT NULL,
-                purpose TEXT NOT NULL,
-                algorithm TEXT NOT NULL,
-                protection_level TEXT NOT NULL,
-                state TEXT NOT NULL,
-                create_time DATETIME NOT NULL,
-                primary_version TEXT,
-                next_rotation_time DATETIME,
-                rotation_period TEXT,
-                labels_json TEXT,
-                quebec_compliant BOOLEAN NOT NULL DEFAULT TRUE,
-                data_residency_confirmed BOOLEAN NOT NULL DEFAULT TRUE
-            )
-        "#).execute(&pool).await.unwrap();
-
-        sqlx::query(r#"
-            CREATE TABLE cmek_operations (
-                id TEXT PRIMARY KEY,
-                operation_id TEXT NOT NULL,
-                operation_type TEXT NOT NULL,
-                key_id TEXT NOT NULL,
-                service TEXT NOT NULL,
-                initiated_by TEXT NOT NULL,
-                initiated_at DATETIME NOT NULL,
-                completed_at DATETIME,
-                status TEXT NOT NULL,
-                error_message TEXT,
-                metadata_json TEXT,
-                quebec_compliance_verified BOOLEAN NOT NULL DEFAULT TRUE,
-                audit_logged BOOLEAN NOT NULL DEFAULT TRUE
-            )
-        "#).execute(&pool).await.unwrap();
-
-        sqlx::query(r#"
-            CREATE TABLE cmek_access_requests (
-                id TEXT PRIMARY KEY,
-                request_id TEXT NOT NULL,
-                user_id TEXT NOT NULL,
-                professional_id TEXT,
-                key_id TEXT NOT NULL,
-                operation TEXT NOT NULL,
-                purpose TEXT NOT NULL,
-                patient_id TEXT,
-                session_id TEXT,
-                justification TEXT NOT NULL,
-                emergency_access BOOLEAN NOT NULL DEFAULT FALSE,
-                requested_at DATETIME NOT NULL,
-                expires_at DATETIME NOT NULL,
-                approved BOOLEAN NOT NULL DEFAULT FALSE,
-                approved_by TEXT,
-                approved_at DATETIME,
-                notes TEXT,
-                audit_trail_id TEXT NOT NULL
-            )
-        "#).execute(&pool).await.unwrap();
+        // Same schema the migration runner creates in production
+        for migration in crate::storage::migrations::INTEGRATIONS_MIGRATIONS {
+            for statement in migration.statements {
+                sqlx::query(statement).execute(&pool).await.unwrap();
+            }
+        }
 
         pool
     }
@@ -1034,16 +1378,170 @@ mod tests {
 
     #[tokio::test]
     async fn test_key_creation() {
-        let pool = create_test_db().await;
-        let config = CMEKConfig::default();
-        let service = FirebaseCMEKService::new(config, pool);
-
-        let key_info = service.create_encryption_key("firestore", "test-key").await.unwrap();
-
+        use wiremock::matchers::{header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let parent = "projects/psypsy/locations/northamerica-northeast1";
+        let key_ring = format!("{}/keyRings/firebase-cmek-keyring", parent);
+        Mock::given(method("POST"))
+            .and(path(format!("/{}/keyRings", parent)))
+            .and(header("authorization", "Bearer kms-token"))
+            .respond_with(ResponseTemplate::new(409).set_body_json(serde_json::json!({
+                "error": { "code": 409, "message": "KeyRing already exists", "status": "ALREADY_EXISTS" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/{}/cryptoKeys", key_ring)))
+            .and(query_param("cryptoKeyId", "test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": format!("{}/cryptoKeys/test-key", key_ring),
+                "primary": { "name": format!("{}/cryptoKeys/test-key/cryptoKeyVersions/1", key_ring), "state": "ENABLED" },
+                "purpose": "ENCRYPT_DECRYPT",
+                "createTime": "2026-03-04T10:00:00Z",
+                "nextRotationTime": "2026-06-02T10:00:00Z",
+                "rotationPeriod": "7776000s",
+                "versionTemplate": { "algorithm": "GOOGLE_SYMMETRIC_ENCRYPTION", "protectionLevel": "HSM" },
+                "labels": { "service": "firestore" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/{}/cryptoKeys", key_ring)))
+            .and(query_param("cryptoKeyId", "denied-key"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({
+                "error": { "code": 403, "message": "Permission 'cloudkms.cryptoKeys.create' denied", "status": "PERMISSION_DENIED" }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let ring = create_kms_key_ring(&client, &server.uri(), "kms-token", parent, "firebase-cmek-keyring").await.unwrap();
+        assert_eq!(ring, key_ring);
+
+        let key = create_kms_crypto_key(&client, &server.uri(), "kms-token", &key_ring, "test-key", &serde_json::json!({})).await.unwrap();
+        let key_info = parse_crypto_key("firestore", "test-key", "northamerica-northeast1", &key).unwrap();
         assert_eq!(key_info.service, "firestore");
         assert_eq!(key_info.key_id, "test-key");
+        assert_eq!(key_info.primary_version.as_deref(), Some("1"));
+        assert_eq!(key_info.rotation_period.as_deref(), Some("7776000s"));
         assert!(key_info.quebec_compliant);
         assert!(key_info.data_residency_confirmed);
+
+        let denied = create_kms_crypto_key(&client, &server.uri(), "kms-token", &key_ring, "denied-key", &serde_json::json!({})).await;
+        assert!(matches!(denied, Err(CMEKError::CloudKMS(message)) if message.contains("cloudkms.cryptoKeys.create")));
+    }
+
+    #[tokio::test]
+    async fn test_keys_outside_montreal_are_refused_before_any_request() {
+        let pool = create_test_db().await;
+        let config = CMEKConfig {
+            location: "us-east1".to_string(),
+            kms_endpoint: "http://unreachable.invalid/v1".to_string(),
+            ..CMEKConfig::default()
+        };
+        let service = FirebaseCMEKService::new(config, pool);
+
+        assert!(matches!(service.create_key_ring().await, Err(CMEKError::Compliance(_))));
+        assert!(matches!(service.create_encryption_key("firestore", "test-key").await, Err(CMEKError::Compliance(_))));
+    }
+
+    fn access_request(operation: &str, emergency_access: bool) -> CMEKAccessRequest {
+        CMEKAccessRequest {
+            request_id: Uuid::new_v4().to_string(),
+            user_id: "user-1".to_string(),
+            professional_id: None,
+            key_id: "firestore-encryption-key".to_string(),
+            operation: operation.to_string(),
+            purpose: "medical_note_access".to_string(),
+            patient_id: None,
+            session_id: None,
+            justification: "Patient in crisis at the emergency department, chart needed now".to_string(),
+            emergency_access,
+            requested_at: Utc::now(),
+            expires_at: Utc::now() + chrono::Duration::hours(1),
+            approved: false,
+            approved_by: None,
+            approved_at: None,
+            audit_trail_id: Uuid::new_v4().to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grants_gate_operations_until_revoked() {
+        let pool = create_test_db().await;
+        let service = FirebaseCMEKService::new(CMEKConfig::default(), pool.clone());
+        let key = "firestore-encryption-key";
+
+        assert!(matches!(service.authorize_operation("user-1", key, "decrypt").await, Err(CMEKError::AccessDenied(_))));
+
+        let request = access_request("decrypt", false);
+        service.request_key_access(request.clone()).await.unwrap();
+        let grant_id = service.approve_access_request(&request.request_id, "admin-1", "Approved").await.unwrap();
+
+        service.authorize_operation("user-1", key, "decrypt").await.unwrap();
+        service.authorize_operation("user-1", key, "decrypt").await.unwrap();
+        assert!(matches!(service.authorize_operation("user-1", key, "rotate").await, Err(CMEKError::AccessDenied(_))));
+        assert!(matches!(service.authorize_operation("user-2", key, "decrypt").await, Err(CMEKError::AccessDenied(_))));
+        let usage: i64 = sqlx::query("SELECT usage_count FROM cmek_access_grants WHERE grant_id = ?")
+            .bind(&grant_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get("usage_count");
+        assert_eq!(usage, 2);
+
+        service.revoke_grant(&grant_id, "admin-1", "Treatment ended").await.unwrap();
+        assert!(matches!(service.authorize_operation("user-1", key, "decrypt").await, Err(CMEKError::AccessDenied(_))));
+
+        // Emergency grants are approved on request and still usable
+        let emergency = access_request("decrypt", true);
+        service.request_key_access(emergency).await.unwrap();
+        service.authorize_operation("user-1", key, "decrypt").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rotation_due_counts_skip_disabled_keys() {
+        let pool = create_test_db().await;
+        let service = FirebaseCMEKService::new(CMEKConfig::default(), pool);
+        let now = Utc::now();
+
+        let seeds = [
+            ("overdue", "ENABLED", Some(now - chrono::Duration::days(2))),
+            ("pending", "ENABLED", Some(now + chrono::Duration::days(3))),
+            ("later", "ENABLED", Some(now + chrono::Duration::days(60))),
+            ("disabled", "DISABLED", Some(now - chrono::Duration::days(10))),
+            ("destroyed", "DESTROYED", Some(now + chrono::Duration::days(1))),
+        ];
+        for (key_id, state, next_rotation_time) in seeds {
+            service.store_key_info(&CMEKKeyInfo {
+                key_id: key_id.to_string(),
+                key_name: format!("projects/p/locations/northamerica-northeast1/keyRings/r/cryptoKeys/{}", key_id),
+                service: "firestore".to_string(),
+                purpose: "ENCRYPT_DECRYPT".to_string(),
+                algorithm: "GOOGLE_SYMMETRIC_ENCRYPTION".to_string(),
+                protection_level: "HSM".to_string(),
+                state: state.to_string(),
+                create_time: now - chrono::Duration::days(90),
+                primary_version: Some("1".to_string()),
+                next_rotation_time,
+                rotation_period: Some("7776000s".to_string()),
+                labels: HashMap::new(),
+                quebec_compliant: true,
+                data_residency_confirmed: true,
+            }).await.unwrap();
+        }
+
+        let metrics = service.get_metrics().await.unwrap();
+        assert_eq!(metrics.overdue_rotation_keys, 1);
+        assert_eq!(metrics.pending_rotation_keys, 1);
+
+        let due: Vec<String> = service.list_keys_needing_rotation().await.unwrap().into_iter().map(|k| k.key_id).collect();
+        assert_eq!(due, vec!["overdue".to_string()]);
+
+        assert_eq!(service.rotate_overdue_keys().await.unwrap().len(), 1);
+        assert!(service.list_keys_needing_rotation().await.unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -1051,12 +1549,17 @@ mod tests {
         let pool = create_test_db().await;
         let config = CMEKConfig::default();
         let service = FirebaseCMEKService::new(config, pool);
+        let key = "firestore-encryption-key";
 
-        // First create a key
-        let key_info = service.create_encryption_key("firestore", "test-key").await.unwrap();
+        assert!(matches!(service.rotate_key(key, "user-1").await, Err(CMEKError::AccessDenied(_))));
+        // Denied before any Cloud KMS call is attempted
+        assert!(matches!(service.decrypt("user-1", key, "Y2lwaGVy").await, Err(CMEKError::AccessDenied(_))));
+        assert!(matches!(service.encrypt("user-1", key, b"note").await, Err(CMEKError::AccessDenied(_))));
 
-        // Then rotate it
-        let new_version = service.rotate_key(&key_info.key_id, "test-user").await.unwrap();
+        let request = access_request("rotate", false);
+        service.request_key_access(request.clone()).await.unwrap();
+        service.approve_access_request(&request.request_id, "admin-1", "Approved").await.unwrap();
+        let new_version = service.rotate_key(key, "user-1").await.unwrap();
 
         assert!(!new_version.is_empty());
     }
@@ -83,7 +83,7 @@ impl FirebaseFunctionsApi {
     pub fn new() -> Self {
         let config = EnvironmentConfig::new();
 
-        let client = Client::builder()
+        let client = crate::security::outbound::guarded_client_builder()
             .timeout(Duration::from_secs(config.timeout_seconds))
             .build()
             .expect("Failed to create HTTP client");
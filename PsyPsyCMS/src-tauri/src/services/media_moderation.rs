@@ -0,0 +1,415 @@
+// Media Moderation for PsyPsy CMS
+// OCRs images attached to notes or social posts and runs the extracted text through the PHI
+// detectors, so a photo of a chart or an intake form is caught before it is attached or published.
+// OCR is compute-heavy, so scanning is off unless a deployment enables it.
+
+use crate::security::validation::{detect_clinical_terms, PhiType, SanitizationService};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+/// What to do with media whose text matches a PHI pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    /// Keep the media but mark it for review
+    Flag,
+    /// Refuse to attach or publish the media
+    Block,
+}
+
+/// Media moderation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaModerationConfig {
+    /// Run OCR scans at all
+    pub enabled: bool,
+    /// OCR executable; must accept `<input> stdout -l <languages>`
+    pub ocr_command: String,
+    /// Tesseract language codes
+    pub ocr_languages: String,
+    /// Larger media is not scanned
+    pub max_media_bytes: u64,
+    /// Action for media attached to clinical notes
+    pub note_action: ModerationAction,
+    /// Action for media in social posts
+    pub social_action: ModerationAction,
+    /// Treat media that could not be scanned as a match
+    pub fail_closed: bool,
+    /// Extra terms (case-insensitive) treated as inappropriate content
+    pub blocked_terms: Vec<String>,
+}
+
+impl Default for MediaModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ocr_command: "tesseract".to_string(),
+            ocr_languages: "eng+fra".to_string(),
+            max_media_bytes: 20 * 1024 * 1024,
+            note_action: ModerationAction::Flag,
+            social_action: ModerationAction::Block,
+            fail_closed: true,
+            blocked_terms: Vec::new(),
+        }
+    }
+}
+
+impl MediaModerationConfig {
+    /// Defaults with `MEDIA_MODERATION_*` environment overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.enabled = std::env::var("MEDIA_MODERATION_ENABLED")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        if let Ok(command) = std::env::var("MEDIA_MODERATION_OCR_COMMAND") {
+            if !command.trim().is_empty() {
+                config.ocr_command = command;
+            }
+        }
+        if let Ok(terms) = std::env::var("MEDIA_MODERATION_BLOCKED_TERMS") {
+            config.blocked_terms = terms
+                .split(',')
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect();
+        }
+        config
+    }
+}
+
+/// Outcome of scanning one piece of media
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaScanStatus {
+    /// No PHI or blocked terms found
+    Clean,
+    /// Matches found; media kept for review
+    Flagged,
+    /// Matches found (or scan failed closed); media refused
+    Blocked,
+    /// Scanning disabled, or not an image
+    Skipped,
+    /// Scan could not complete
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaFindingKind {
+    ClinicalInformation,
+    Identifier,
+    InappropriateText,
+}
+
+/// A match in the extracted text. The matched text itself is not kept, since it is likely PHI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaFinding {
+    pub kind: MediaFindingKind,
+    pub phi_type: Option<PhiType>,
+}
+
+/// Scan result stored with the attachment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaScanResult {
+    pub media_ref: String,
+    pub status: MediaScanStatus,
+    pub findings: Vec<MediaFinding>,
+    /// Characters of text recovered by OCR
+    pub extracted_chars: usize,
+    pub error: Option<String>,
+    pub scanned_at: DateTime<Utc>,
+}
+
+impl MediaScanResult {
+    fn new(media_ref: &str, status: MediaScanStatus) -> Self {
+        Self {
+            media_ref: media_ref.to_string(),
+            status,
+            findings: Vec::new(),
+            extracted_chars: 0,
+            error: None,
+            scanned_at: Utc::now(),
+        }
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.status == MediaScanStatus::Blocked
+    }
+
+    pub fn contains_phi(&self) -> bool {
+        self.findings.iter().any(|f| f.kind != MediaFindingKind::InappropriateText)
+    }
+}
+
+/// Extracts text from image bytes
+pub trait TextExtractor: Send + Sync {
+    fn extract_text(&self, image: &[u8]) -> Result<String, String>;
+}
+
+/// OCR through the Tesseract CLI; the image is piped over stdin so it never touches disk
+pub struct TesseractOcr {
+    command: String,
+    languages: String,
+}
+
+impl TesseractOcr {
+    pub fn new(command: &str, languages: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            languages: languages.to_string(),
+        }
+    }
+}
+
+impl TextExtractor for TesseractOcr {
+    fn extract_text(&self, image: &[u8]) -> Result<String, String> {
+        let mut child = Command::new(&self.command)
+            .args(["stdin", "stdout", "-l", &self.languages])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start OCR: {}", e))?;
+
+        child
+            .stdin
+            .take()
+            .ok_or("OCR stdin unavailable")?
+            .write_all(image)
+            .map_err(|e| format!("Failed to send image to OCR: {}", e))?;
+
+        let output = child.wait_with_output().map_err(|e| format!("OCR failed: {}", e))?;
+        let stdout = Zeroizing::new(output.stdout);
+        if !output.status.success() {
+            return Err(format!("OCR exited with {}", output.status));
+        }
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+}
+
+/// Recognise the image formats OCR can read from their magic bytes
+fn is_image(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x89, b'P', b'N', b'G'])
+        || bytes.starts_with(&[0xFF, 0xD8, 0xFF])
+        || bytes.starts_with(b"GIF8")
+        || bytes.starts_with(b"BM")
+        || bytes.starts_with(b"II*\0")
+        || bytes.starts_with(b"MM\0*")
+        || (bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP")
+}
+
+/// Run extracted text through the shared PHI detectors and the configured blocked terms
+pub fn scan_text(text: &str, blocked_terms: &[String]) -> Vec<MediaFinding> {
+    let mut findings: Vec<MediaFinding> = detect_clinical_terms(text)
+        .into_iter()
+        .map(|d| MediaFinding {
+            kind: MediaFindingKind::ClinicalInformation,
+            phi_type: Some(d.pattern_type),
+        })
+        .collect();
+
+    match SanitizationService::new() {
+        Ok(sanitizer) => findings.extend(sanitizer.detect_phi(text).into_iter().map(|d| MediaFinding {
+            kind: MediaFindingKind::Identifier,
+            phi_type: Some(d.pattern_type),
+        })),
+        Err(e) => {
+            // Fail closed: text that cannot be checked for identifiers is treated as a match
+            log::error!("Identifier detector unavailable: {}", e);
+            findings.push(MediaFinding {
+                kind: MediaFindingKind::Identifier,
+                phi_type: None,
+            });
+        }
+    }
+
+    let lowered = text.to_lowercase();
+    findings.extend(
+        blocked_terms
+            .iter()
+            .filter(|term| lowered.contains(term.as_str()))
+            .map(|_| MediaFinding {
+                kind: MediaFindingKind::InappropriateText,
+                phi_type: None,
+            }),
+    );
+
+    findings
+}
+
+/// Scans media before it is attached or published
+pub struct MediaScanner {
+    config: MediaModerationConfig,
+    extractor: Arc<dyn TextExtractor>,
+}
+
+impl MediaScanner {
+    pub fn new(config: MediaModerationConfig) -> Self {
+        let extractor = Arc::new(TesseractOcr::new(&config.ocr_command, &config.ocr_languages));
+        Self::with_extractor(config, extractor)
+    }
+
+    pub fn with_extractor(config: MediaModerationConfig, extractor: Arc<dyn TextExtractor>) -> Self {
+        Self { config, extractor }
+    }
+
+    pub fn config(&self) -> &MediaModerationConfig {
+        &self.config
+    }
+
+    /// Scan media already in memory
+    pub fn scan_bytes(&self, media_ref: &str, bytes: &[u8], action: ModerationAction) -> MediaScanResult {
+        if !self.config.enabled {
+            return MediaScanResult::new(media_ref, MediaScanStatus::Skipped);
+        }
+        if bytes.len() as u64 > self.config.max_media_bytes {
+            return self.failed(media_ref, action, "Media exceeds the scan size limit".to_string());
+        }
+        if !is_image(bytes) {
+            return MediaScanResult::new(media_ref, MediaScanStatus::Skipped);
+        }
+
+        // Recognised text is as sensitive as the image; it is zeroed once scanned
+        let text = match self.extractor.extract_text(bytes) {
+            Ok(text) => Zeroizing::new(text),
+            Err(e) => return self.failed(media_ref, action, e),
+        };
+
+        let mut result = MediaScanResult::new(media_ref, MediaScanStatus::Clean);
+        result.extracted_chars = text.chars().count();
+        result.findings = scan_text(&text, &self.config.blocked_terms);
+        if !result.findings.is_empty() {
+            result.status = match action {
+                ModerationAction::Flag => MediaScanStatus::Flagged,
+                ModerationAction::Block => MediaScanStatus::Blocked,
+            };
+            log::warn!("Media scan matched {} pattern(s); status {:?}", result.findings.len(), result.status);
+        }
+        result
+    }
+
+    /// Load media from a local path, `file://` or `http(s)://` reference and scan it
+    pub async fn scan_media(self: &Arc<Self>, media_ref: &str, action: ModerationAction) -> MediaScanResult {
+        if !self.config.enabled {
+            return MediaScanResult::new(media_ref, MediaScanStatus::Skipped);
+        }
+
+        let bytes = match load_media(media_ref, self.config.max_media_bytes).await {
+            Ok(bytes) => Zeroizing::new(bytes),
+            Err(e) => return self.failed(media_ref, action, e),
+        };
+
+        let scanner = Arc::clone(self);
+        let media_ref_owned = media_ref.to_string();
+        match tokio::task::spawn_blocking(move || scanner.scan_bytes(&media_ref_owned, &bytes, action)).await {
+            Ok(result) => result,
+            Err(e) => self.failed(media_ref, action, format!("Scan task failed: {}", e)),
+        }
+    }
+
+    fn failed(&self, media_ref: &str, action: ModerationAction, error: String) -> MediaScanResult {
+        log::warn!("Media scan failed: {}", error);
+        let status = if self.config.fail_closed && action == ModerationAction::Block {
+            MediaScanStatus::Blocked
+        } else {
+            MediaScanStatus::Failed
+        };
+        let mut result = MediaScanResult::new(media_ref, status);
+        result.error = Some(error);
+        result
+    }
+}
+
+async fn load_media(media_ref: &str, max_bytes: u64) -> Result<Vec<u8>, String> {
+    if media_ref.starts_with("http://") || media_ref.starts_with("https://") {
+        crate::security::outbound::outbound_guard()
+            .check_url(media_ref)
+            .await
+            .map_err(|e| format!("Media host rejected: {}", e))?;
+        let response = crate::security::outbound::guarded_client()
+            .get(media_ref)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch media: {}", e))?;
+        if response.content_length().is_some_and(|len| len > max_bytes) {
+            return Err("Media exceeds the scan size limit".to_string());
+        }
+        let bytes = response.bytes().await.map_err(|e| format!("Failed to read media: {}", e))?;
+        return Ok(bytes.to_vec());
+    }
+
+    let path = media_ref.strip_prefix("file://").unwrap_or(media_ref);
+    let metadata = tokio::fs::metadata(path).await.map_err(|e| format!("Media not readable: {}", e))?;
+    if metadata.len() > max_bytes {
+        return Err("Media exceeds the scan size limit".to_string());
+    }
+    tokio::fs::read(path).await.map_err(|e| format!("Media not readable: {}", e))
+}
+
+/// Shared scanner managed by Tauri
+#[derive(Clone)]
+pub struct MediaScannerState(pub Arc<MediaScanner>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    struct FixedText(&'static str);
+
+    impl TextExtractor for FixedText {
+        fn extract_text(&self, _image: &[u8]) -> Result<String, String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    struct BrokenOcr;
+
+    impl TextExtractor for BrokenOcr {
+        fn extract_text(&self, _image: &[u8]) -> Result<String, String> {
+            Err("tesseract not installed".to_string())
+        }
+    }
+
+    fn scanner(text: &'static str) -> MediaScanner {
+        let config = MediaModerationConfig { enabled: true, ..MediaModerationConfig::default() };
+        MediaScanner::with_extractor(config, Arc::new(FixedText(text)))
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let scanner = MediaScanner::with_extractor(MediaModerationConfig::default(), Arc::new(FixedText("depression")));
+        assert_eq!(scanner.scan_bytes("chart.png", PNG, ModerationAction::Block).status, MediaScanStatus::Skipped);
+    }
+
+    #[test]
+    fn test_chart_photo_blocked_for_social_and_flagged_for_notes() {
+        let scanner = scanner("Dx: major depressive disorder, F32.1");
+
+        let social = scanner.scan_bytes("chart.png", PNG, ModerationAction::Block);
+        assert!(social.is_blocked());
+        assert!(social.contains_phi());
+
+        let note = scanner.scan_bytes("chart.png", PNG, ModerationAction::Flag);
+        assert_eq!(note.status, MediaScanStatus::Flagged);
+    }
+
+    #[test]
+    fn test_clean_image_and_non_image() {
+        let scanner = scanner("Sleep hygiene tips / Conseils pour mieux dormir");
+        assert_eq!(scanner.scan_bytes("tips.png", PNG, ModerationAction::Block).status, MediaScanStatus::Clean);
+        assert_eq!(scanner.scan_bytes("clip.mp4", b"\0\0\0\x18ftypmp42", ModerationAction::Block).status, MediaScanStatus::Skipped);
+    }
+
+    #[test]
+    fn test_ocr_failure_fails_closed_when_blocking() {
+        let config = MediaModerationConfig { enabled: true, ..MediaModerationConfig::default() };
+        let scanner = MediaScanner::with_extractor(config, Arc::new(BrokenOcr));
+        assert!(scanner.scan_bytes("chart.png", PNG, ModerationAction::Block).is_blocked());
+        assert_eq!(scanner.scan_bytes("chart.png", PNG, ModerationAction::Flag).status, MediaScanStatus::Failed);
+    }
+}
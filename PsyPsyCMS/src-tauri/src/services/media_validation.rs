@@ -0,0 +1,277 @@
+// Media Validation for PsyPsy CMS
+// Checks every media item on a social post before it is queued or published: the type must be
+// one the platforms accept and the size must fit the smallest limit among the target platforms.
+// Local files are identified from their leading bytes, never their extension; remote media must
+// answer a HEAD request through the outbound guard with an acceptable type and length.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Size limits for one platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformMediaLimits {
+    pub max_image_bytes: u64,
+    pub max_video_bytes: u64,
+}
+
+impl PlatformMediaLimits {
+    fn for_mime(&self, mime: &str) -> u64 {
+        if mime.starts_with("video/") {
+            self.max_video_bytes
+        } else {
+            self.max_image_bytes
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaValidationConfig {
+    /// MIME types accepted on any platform
+    pub allowed_mime_types: Vec<String>,
+    /// Limits per platform name
+    pub platform_limits: HashMap<String, PlatformMediaLimits>,
+    /// Limits for platforms without their own entry
+    pub default_limits: PlatformMediaLimits,
+    /// Timeout for the reachability check on remote media
+    pub request_timeout_secs: u64,
+}
+
+impl Default for MediaValidationConfig {
+    fn default() -> Self {
+        let limits = |image_mib: u64, video_mib: u64| PlatformMediaLimits {
+            max_image_bytes: image_mib * MIB,
+            max_video_bytes: video_mib * MIB,
+        };
+        Self {
+            allowed_mime_types: ["image/jpeg", "image/png", "image/gif", "image/webp", "video/mp4"]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            platform_limits: [
+                ("linkedin", limits(8, 200)),
+                ("facebook", limits(10, 1024)),
+                ("instagram", limits(8, 100)),
+                ("twitter", limits(5, 512)),
+            ]
+            .into_iter()
+            .map(|(platform, limits)| (platform.to_string(), limits))
+            .collect(),
+            default_limits: limits(5, 100),
+            request_timeout_secs: 10,
+        }
+    }
+}
+
+impl MediaValidationConfig {
+    /// Defaults with `MEDIA_VALIDATION_ALLOWED_TYPES` (comma-separated, replaces the defaults),
+    /// `MEDIA_VALIDATION_LIMITS` (`platform=image_mib:video_mib,...`, merged over the defaults)
+    /// and `MEDIA_VALIDATION_TIMEOUT_SECS` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("MEDIA_VALIDATION_ALLOWED_TYPES") {
+            let types: Vec<String> = value
+                .split(',')
+                .map(|t| t.trim().to_ascii_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect();
+            if types.is_empty() {
+                log::warn!("Ignoring invalid MEDIA_VALIDATION_ALLOWED_TYPES '{}'", value);
+            } else {
+                config.allowed_mime_types = types;
+            }
+        }
+        if let Ok(value) = std::env::var("MEDIA_VALIDATION_LIMITS") {
+            for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let parsed = entry.split_once('=').and_then(|(platform, sizes)| {
+                    let (image, video) = sizes.split_once(':')?;
+                    Some((
+                        platform.trim().to_ascii_lowercase(),
+                        PlatformMediaLimits {
+                            max_image_bytes: image.trim().parse::<u64>().ok()? * MIB,
+                            max_video_bytes: video.trim().parse::<u64>().ok()? * MIB,
+                        },
+                    ))
+                });
+                match parsed {
+                    Some((platform, limits)) => {
+                        config.platform_limits.insert(platform, limits);
+                    }
+                    None => log::warn!("Ignoring invalid MEDIA_VALIDATION_LIMITS entry '{}'", entry),
+                }
+            }
+        }
+        if let Ok(value) = std::env::var("MEDIA_VALIDATION_TIMEOUT_SECS") {
+            match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => config.request_timeout_secs = secs,
+                _ => log::warn!("Ignoring invalid MEDIA_VALIDATION_TIMEOUT_SECS '{}'", value),
+            }
+        }
+        config
+    }
+
+    /// Largest size every target platform accepts for this type
+    fn max_bytes(&self, mime: &str, platforms: &[String]) -> u64 {
+        platforms
+            .iter()
+            .map(|p| self.platform_limits.get(&p.to_ascii_lowercase()).unwrap_or(&self.default_limits).for_mime(mime))
+            .min()
+            .unwrap_or_else(|| self.default_limits.for_mime(mime))
+    }
+
+    /// Check a media item's verified type and size against the target platforms
+    pub fn check(&self, mime: &str, size: u64, platforms: &[String]) -> Result<(), String> {
+        if !self.allowed_mime_types.iter().any(|m| m == mime) {
+            return Err(format!("unsupported media type {}", mime));
+        }
+        let max = self.max_bytes(mime, platforms);
+        if size > max {
+            return Err(format!("{} exceeds the {} limit for {}", format_size(size), format_size(max), platforms.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+/// Type and size confirmed for one media item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatedMedia {
+    pub mime_type: String,
+    pub size: u64,
+}
+
+/// Identify media from its leading bytes
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        match &bytes[8..12] {
+            b"qt  " => Some("video/quicktime"),
+            _ => Some("video/mp4"),
+        }
+    } else {
+        None
+    }
+}
+
+/// Validate one media reference (local path, `file://` or `http(s)://`) for the target platforms
+pub async fn validate_media(
+    media_ref: &str,
+    declared_mime: Option<&str>,
+    platforms: &[String],
+    config: &MediaValidationConfig,
+) -> Result<ValidatedMedia, String> {
+    let media = if media_ref.starts_with("http://") || media_ref.starts_with("https://") {
+        probe_remote(media_ref, config).await?
+    } else {
+        let path = media_ref.strip_prefix("file://").unwrap_or(media_ref).to_string();
+        tokio::task::spawn_blocking(move || probe_local(&path))
+            .await
+            .map_err(|e| format!("validation task failed: {}", e))??
+    };
+
+    if let Some(declared) = declared_mime.map(|m| m.trim().to_ascii_lowercase()).filter(|m| !m.is_empty()) {
+        if declared != media.mime_type {
+            return Err(format!("declared as {} but the media is {}", declared, media.mime_type));
+        }
+    }
+    config.check(&media.mime_type, media.size, platforms)?;
+    Ok(media)
+}
+
+fn probe_local(path: &str) -> Result<ValidatedMedia, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("file not readable: {}", e))?;
+    if !metadata.is_file() {
+        return Err("not a regular file".to_string());
+    }
+    let mut header = [0u8; 16];
+    let mut file = std::fs::File::open(path).map_err(|e| format!("file not readable: {}", e))?;
+    let read = file.read(&mut header).map_err(|e| format!("file not readable: {}", e))?;
+    let mime = sniff_mime(&header[..read]).ok_or("file contents are not a recognised image or video")?;
+    Ok(ValidatedMedia { mime_type: mime.to_string(), size: metadata.len() })
+}
+
+async fn probe_remote(url: &str, config: &MediaValidationConfig) -> Result<ValidatedMedia, String> {
+    crate::security::outbound::outbound_guard()
+        .check_url(url)
+        .await
+        .map_err(|e| format!("media host rejected: {}", e))?;
+    let client = crate::security::outbound::guarded_client_builder()
+        .timeout(std::time::Duration::from_secs(config.request_timeout_secs))
+        .build()
+        .map_err(|e| format!("failed to create HTTP client: {}", e))?;
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|e| format!("media URL is unreachable: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("media URL is unreachable (HTTP {})", response.status().as_u16()));
+    }
+
+    let mime = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .ok_or("media server did not report a content type")?;
+    let size = response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .ok_or("media server did not report a content length")?;
+    Ok(ValidatedMedia { mime_type: mime, size })
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= MIB {
+        format!("{:.1} MB", bytes as f64 / MIB as f64)
+    } else {
+        format!("{} KB", bytes.div_ceil(1024))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_media_is_identified_by_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let platforms = vec!["linkedin".to_string()];
+        let config = MediaValidationConfig::default();
+
+        let disguised = dir.path().join("photo.png");
+        std::fs::write(&disguised, b"#!/bin/sh\necho not an image").unwrap();
+        let err = validate_media(disguised.to_str().unwrap(), None, &platforms, &config).await.unwrap_err();
+        assert!(err.contains("not a recognised image"));
+
+        let real = dir.path().join("photo.bin");
+        std::fs::write(&real, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0]).unwrap();
+        let media = validate_media(real.to_str().unwrap(), Some("image/png"), &platforms, &config).await.unwrap();
+        assert_eq!(media.mime_type, "image/png");
+        assert!(validate_media(real.to_str().unwrap(), Some("image/jpeg"), &platforms, &config).await.is_err());
+    }
+
+    #[test]
+    fn test_size_limit_is_the_strictest_target_platform() {
+        let config = MediaValidationConfig::default();
+        let both = vec!["facebook".to_string(), "linkedin".to_string()];
+        assert!(config.check("video/mp4", 500 * MIB, &["facebook".to_string()]).is_ok());
+        assert!(config.check("video/mp4", 500 * MIB, &both).is_err());
+        assert!(config.check("video/mp4", 2048 * MIB, &["facebook".to_string()]).is_err());
+        assert!(config.check("application/pdf", 1024, &both).unwrap_err().contains("unsupported"));
+    }
+}
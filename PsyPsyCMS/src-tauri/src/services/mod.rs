@@ -4,6 +4,28 @@ pub mod firebase_service_simple;
 // pub mod offline_service;  // Uses sqlx - temporarily disabled
 pub mod encrypted_storage;
 pub mod offline_sync;
+pub mod event_log;
+pub mod error_reporter;
+pub mod workstation_lock;
+pub mod reminder_templates;
+pub mod media_moderation;
+pub mod media_validation;
+pub mod scheduling;
+pub mod export_approval;
+pub mod data_lock;
+pub mod specialty_taxonomy;
+pub mod command_profiler;
+pub mod client_dedup;
+pub mod client_search;
+pub mod notifier;
+pub mod deidentified_reports;
+pub mod dashboard_cache;
+pub mod patient_timeline;
+pub mod patient_access_notifications;
+pub mod sync_schedule;
+pub mod phi_detector;
+pub mod note_compliance_rules;
+pub mod system_health;
 // pub mod quebec_audit_service;  // Uses sqlx - temporarily disabled
 // pub mod notification_service;  // Uses sqlx - temporarily disabled
 // pub mod quebec_compliance_service;  // Uses sqlx - temporarily disabled
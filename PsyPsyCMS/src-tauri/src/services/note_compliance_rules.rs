@@ -0,0 +1,272 @@
+// Note Compliance Rules for PsyPsy CMS
+// What makes a clinical note "compliant" before it is saved. Law 25 consent and retention checks
+// always apply; required fields, content length, consent references and prohibited content come
+// from a rule set clinics can replace with a JSON file.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::encrypted_storage::MedicalNote;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    /// Worth a look, does not block saving
+    Warning,
+    /// The note must not be saved as is
+    Error,
+}
+
+/// One failed rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceFinding {
+    pub rule_id: String,
+    pub severity: FindingSeverity,
+    /// Note field the finding is about, e.g. `content` or `metadata.appointment_date`
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// Content the note must mention, e.g. that consent to treatment was discussed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsentReferenceRule {
+    pub id: String,
+    pub description: String,
+    /// Case-insensitive regexes; any one matching satisfies the rule
+    pub patterns: Vec<String>,
+    #[serde(default = "default_error")]
+    pub severity: FindingSeverity,
+}
+
+/// Content the note must not contain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProhibitedContentRule {
+    pub id: String,
+    pub description: String,
+    /// Case-insensitive regex
+    pub pattern: String,
+    #[serde(default = "default_error")]
+    pub severity: FindingSeverity,
+}
+
+fn default_error() -> FindingSeverity {
+    FindingSeverity::Error
+}
+
+/// Tunable part of note validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ComplianceRuleSet {
+    /// `patient_id`, `template_type`, `content`, `professional_order`, or `metadata.<field>`
+    pub required_fields: Vec<String>,
+    /// Minimum trimmed length of the note body, in characters
+    pub min_content_length: usize,
+    pub consent_references: Vec<ConsentReferenceRule>,
+    pub prohibited_content: Vec<ProhibitedContentRule>,
+}
+
+impl Default for ComplianceRuleSet {
+    fn default() -> Self {
+        Self {
+            required_fields: vec!["patient_id".to_string(), "template_type".to_string(), "content".to_string()],
+            min_content_length: 20,
+            consent_references: vec![ConsentReferenceRule {
+                id: "consent.treatment_reference".to_string(),
+                description: "The note must record that consent to treatment was obtained or reviewed".to_string(),
+                patterns: vec![r"\bconsent(s|ed|ement)?\b".to_string()],
+                severity: FindingSeverity::Error,
+            }],
+            prohibited_content: vec![ProhibitedContentRule {
+                id: "content.social_insurance_number".to_string(),
+                description: "Social insurance numbers do not belong in clinical notes".to_string(),
+                pattern: r"\b\d{3}[ -]?\d{3}[ -]?\d{3}\b".to_string(),
+                severity: FindingSeverity::Error,
+            }],
+        }
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))
+}
+
+impl ComplianceRuleSet {
+    /// Built-in rules, replaced by the JSON file at `NOTE_COMPLIANCE_RULES_PATH` when set
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("NOTE_COMPLIANCE_RULES_PATH") else {
+            return Self::default();
+        };
+        let loaded = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| serde_json::from_slice::<Self>(&raw).map_err(|e| e.to_string()))
+            .and_then(|rules| rules.check().map(|_| rules));
+        match loaded {
+            Ok(rules) => rules,
+            Err(e) => {
+                log::warn!("Ignoring note compliance rules at {}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Every pattern compiles
+    pub fn check(&self) -> Result<(), String> {
+        for rule in &self.consent_references {
+            for pattern in &rule.patterns {
+                compile(pattern)?;
+            }
+        }
+        for rule in &self.prohibited_content {
+            compile(&rule.pattern)?;
+        }
+        Ok(())
+    }
+
+    /// Findings for a note, Law 25 checks first; empty when the note is compliant
+    pub fn evaluate(&self, note: &MedicalNote) -> Vec<ComplianceFinding> {
+        let mut findings = Vec::new();
+        let mut fail = |rule_id: &str, severity: FindingSeverity, field: Option<&str>, message: String| {
+            findings.push(ComplianceFinding {
+                rule_id: rule_id.to_string(),
+                severity,
+                field: field.map(str::to_string),
+                message,
+            })
+        };
+
+        if !note.consent_obtained {
+            fail("law25.consent_obtained", FindingSeverity::Error, Some("consent_obtained"),
+                "Law 25: Patient consent is required for processing personal health information".to_string());
+        }
+        if !note.quebec_compliance.law_25_consent {
+            fail("law25.explicit_consent", FindingSeverity::Error, Some("quebec_compliance.law_25_consent"),
+                "Law 25: Explicit consent flag must be set".to_string());
+        }
+        if !note.quebec_compliance.data_minimization {
+            fail("law25.data_minimization", FindingSeverity::Error, Some("quebec_compliance.data_minimization"),
+                "Law 25: Data minimization principle must be enforced".to_string());
+        }
+        if note.quebec_compliance.retention_period_days == 0 {
+            fail("law25.retention_period", FindingSeverity::Error, Some("quebec_compliance.retention_period_days"),
+                "Law 25: Retention period must be specified".to_string());
+        }
+
+        for field in &self.required_fields {
+            let present = match field.as_str() {
+                "patient_id" => !note.patient_id.trim().is_empty(),
+                "template_type" => !note.template_type.trim().is_empty(),
+                "content" => !note.content.trim().is_empty(),
+                "professional_order" => note.quebec_compliance.professional_order.as_deref().is_some_and(|o| !o.trim().is_empty()),
+                other => match other.strip_prefix("metadata.") {
+                    Some(key) => note.metadata.get(key).is_some_and(|v| !v.is_null() && v.as_str() != Some("")),
+                    None => {
+                        log::warn!("Unknown required note field '{}'", other);
+                        true
+                    }
+                },
+            };
+            if !present {
+                fail("required_field", FindingSeverity::Error, Some(field.as_str()), format!("{} is required", field));
+            }
+        }
+
+        let content = note.content.trim();
+        if !content.is_empty() && content.chars().count() < self.min_content_length {
+            fail("content.min_length", FindingSeverity::Error, Some("content"),
+                format!("Note content must be at least {} characters", self.min_content_length));
+        }
+
+        for rule in &self.consent_references {
+            let referenced = rule.patterns.iter().filter_map(|p| compile(p).ok()).any(|re| re.is_match(&note.content));
+            if !referenced {
+                fail(&rule.id, rule.severity, Some("content"), rule.description.clone());
+            }
+        }
+
+        for rule in &self.prohibited_content {
+            if compile(&rule.pattern).is_ok_and(|re| re.is_match(&note.content)) {
+                fail(&rule.id, rule.severity, Some("content"), rule.description.clone());
+            }
+        }
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::encrypted_storage::{QuebecComplianceMetadata, SyncStatus};
+    use chrono::Utc;
+
+    fn compliant_note() -> MedicalNote {
+        MedicalNote {
+            id: "note-1".to_string(),
+            patient_id: "patient-1".to_string(),
+            content: "Reviewed consent to treatment. Client reports improved sleep this week.".to_string(),
+            template_type: "progress".to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            consent_obtained: true,
+            encrypted: false,
+            deidentified: false,
+            sync_status: SyncStatus::Local,
+            quebec_compliance: QuebecComplianceMetadata {
+                law_25_consent: true,
+                data_minimization: true,
+                retention_period_days: 2555,
+                professional_order: Some("OPQ".to_string()),
+                audit_trail: Vec::new(),
+            },
+            metadata: Default::default(),
+            version: 0,
+        }
+    }
+
+    fn rule_ids(rules: &ComplianceRuleSet, note: &MedicalNote) -> Vec<String> {
+        rules.evaluate(note).into_iter().map(|f| f.rule_id).collect()
+    }
+
+    #[test]
+    fn test_compliant_note_has_no_findings() {
+        assert!(ComplianceRuleSet::default().evaluate(&compliant_note()).is_empty());
+    }
+
+    #[test]
+    fn test_each_missing_element_has_its_own_finding() {
+        let mut rules = ComplianceRuleSet::default();
+        rules.required_fields.push("metadata.appointment_date".to_string());
+        let mut dated = compliant_note();
+        dated.metadata.insert("appointment_date".to_string(), serde_json::json!("2026-03-02"));
+        assert!(rules.evaluate(&dated).is_empty());
+
+        let cases: Vec<(&str, Box<dyn Fn(&mut MedicalNote)>)> = vec![
+            ("law25.consent_obtained", Box::new(|n| n.consent_obtained = false)),
+            ("law25.explicit_consent", Box::new(|n| n.quebec_compliance.law_25_consent = false)),
+            ("law25.data_minimization", Box::new(|n| n.quebec_compliance.data_minimization = false)),
+            ("law25.retention_period", Box::new(|n| n.quebec_compliance.retention_period_days = 0)),
+            ("required_field", Box::new(|n| n.template_type.clear())),
+            ("required_field", Box::new(|n| { n.metadata.remove("appointment_date"); })),
+            ("content.min_length", Box::new(|n| n.content = "Consent ok.".to_string())),
+            ("consent.treatment_reference", Box::new(|n| n.content = "Client reports improved sleep this week.".to_string())),
+            ("content.social_insurance_number", Box::new(|n| n.content.push_str(" SIN 123 456 782."))),
+        ];
+        for (expected, break_note) in cases {
+            let mut note = dated.clone();
+            break_note(&mut note);
+            assert_eq!(rule_ids(&rules, &note), vec![expected.to_string()], "expected only {}", expected);
+        }
+
+        let mut missing_date = dated.clone();
+        missing_date.metadata.clear();
+        let finding = &rules.evaluate(&missing_date)[0];
+        assert_eq!(finding.field.as_deref(), Some("metadata.appointment_date"));
+        assert_eq!(finding.severity, FindingSeverity::Error);
+    }
+}
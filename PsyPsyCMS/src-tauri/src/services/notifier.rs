@@ -0,0 +1,106 @@
+// Notification Delivery for PsyPsy CMS
+// One delivery interface for backend-generated messages (scheduled reports, patient-facing
+// notices). The default implementation only logs; a webhook implementation posts through the
+// outbound guard so delivery targets are subject to the same allowlist as every other request.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Message handed to a notifier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    /// Address or identifier the delivery channel understands
+    pub recipient: String,
+    /// Short machine-readable kind, e.g. `deidentified_report`
+    pub category: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NotifierError {
+    #[error("Delivery failed: {0}")]
+    DeliveryFailed(String),
+    #[error("Invalid recipient: {0}")]
+    InvalidRecipient(String),
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn deliver(&self, notification: &Notification) -> Result<(), NotifierError>;
+}
+
+/// Records that a notification would have been sent; the body is never logged
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn deliver(&self, notification: &Notification) -> Result<(), NotifierError> {
+        log::info!(
+            "Notification '{}' ({}) queued for {} ({} bytes)",
+            notification.subject,
+            notification.category,
+            notification.recipient,
+            notification.body.len()
+        );
+        Ok(())
+    }
+}
+
+/// Posts each notification as JSON to a delivery service
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        Self { url, client: crate::security::outbound::guarded_client() }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn deliver(&self, notification: &Notification) -> Result<(), NotifierError> {
+        if notification.recipient.trim().is_empty() {
+            return Err(NotifierError::InvalidRecipient("empty recipient".to_string()));
+        }
+        let response = self
+            .client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await
+            .map_err(|e| NotifierError::DeliveryFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(NotifierError::DeliveryFailed(format!("webhook returned {}", response.status())));
+        }
+        Ok(())
+    }
+}
+
+/// Notifier shared by background jobs and commands
+#[derive(Clone)]
+pub struct NotifierState(pub Arc<dyn Notifier>);
+
+impl Default for NotifierState {
+    fn default() -> Self {
+        Self(Arc::new(LogNotifier))
+    }
+}
+
+impl NotifierState {
+    /// Webhook delivery when `NOTIFIER_WEBHOOK_URL` is set, otherwise log-only
+    pub fn from_env() -> Self {
+        match std::env::var("NOTIFIER_WEBHOOK_URL") {
+            Ok(url) if url.starts_with("https://") => Self(Arc::new(WebhookNotifier::new(url))),
+            Ok(url) => {
+                log::warn!("Ignoring NOTIFIER_WEBHOOK_URL without https: {}", url);
+                Self::default()
+            }
+            Err(_) => Self::default(),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::services::encrypted_storage::{EncryptedNoteStorage, MedicalNote, SyncStatus};
-use crate::services::firebase_service_simple::FirebaseService;
+use crate::services::firebase_service_simple::{FirebaseError, FirebaseService};
 use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use tokio::time::{interval, Duration};
 use uuid::Uuid;
 
@@ -17,20 +20,237 @@ pub enum SyncError {
     Network(String),
     #[error("Sync disabled due to compliance violation: {0}")]
     ComplianceViolation(String),
+    #[error("Invalid note: {0}")]
+    Validation(String),
+    #[error("Not authorized to sync: {0}")]
+    Unauthorized(String),
+    #[error("Firebase rejected the note: {0}")]
+    Rejected(String),
+    #[error("Sync backend unavailable: {0}")]
+    Setup(String),
+}
+
+impl SyncError {
+    /// Only outages are worth retrying; validation, compliance and auth failures fail the same way again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SyncError::Firebase(_) | SyncError::Network(_))
+    }
+
+    /// Failures of the note itself, which no later sync can fix. Local storage, auth and setup
+    /// problems leave the note pending for when they are resolved.
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, SyncError::Validation(_) | SyncError::ComplianceViolation(_) | SyncError::Rejected(_))
+    }
+
+    /// Outages and throttling are retried; anything else Firestore refused is the note's fault
+    fn from_firebase(error: FirebaseError) -> Self {
+        const TRANSIENT: [&str; 12] = [
+            "unavailable", "deadline_exceeded", "resource_exhausted", "aborted", "internal", "timed out",
+            "timeout", "connection", "429", "502", "503", "504",
+        ];
+        match error {
+            FirebaseError::Auth(e) => SyncError::Unauthorized(e),
+            FirebaseError::Firestore(e) if e.to_lowercase().contains("permission_denied") => SyncError::Unauthorized(e),
+            FirebaseError::Firestore(e) if TRANSIENT.iter().any(|marker| e.to_lowercase().contains(marker)) => {
+                SyncError::Firebase(e)
+            }
+            FirebaseError::Firestore(e) => SyncError::Rejected(e),
+            e => SyncError::Setup(e.to_string()),
+        }
+    }
+}
+
+/// Retries of a failed note push
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRetryPolicy {
+    /// Pushes per note per sync, the first included
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for SyncRetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 5, initial_delay_ms: 1_000, max_delay_ms: 60_000 }
+    }
+}
+
+impl SyncRetryPolicy {
+    /// Defaults with `SYNC_RETRY_*` environment overrides
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(value) = std::env::var("SYNC_RETRY_MAX_ATTEMPTS") {
+            match value.trim().parse::<u32>() {
+                Ok(n) if (1..=20).contains(&n) => policy.max_attempts = n,
+                _ => log::warn!("Ignoring invalid SYNC_RETRY_MAX_ATTEMPTS '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("SYNC_RETRY_INITIAL_DELAY_MS") {
+            match value.trim().parse::<u64>() {
+                Ok(ms) if (10..=60_000).contains(&ms) => policy.initial_delay_ms = ms,
+                _ => log::warn!("Ignoring invalid SYNC_RETRY_INITIAL_DELAY_MS '{}'", value),
+            }
+        }
+        policy.max_delay_ms = policy.max_delay_ms.max(policy.initial_delay_ms);
+        policy
+    }
+
+    /// Wait before retry `retry` (1-based): doubling from the initial delay up to the cap, then
+    /// scaled by `jitter` in [0.5, 1] so clients that failed together do not retry together
+    pub fn delay(&self, retry: u32, jitter: f64) -> Duration {
+        let exponential = self.initial_delay_ms.saturating_mul(1u64 << (retry.saturating_sub(1)).min(20));
+        let capped = exponential.min(self.max_delay_ms) as f64;
+        Duration::from_millis((capped * jitter.clamp(0.5, 1.0)) as u64)
+    }
+}
+
+fn jitter() -> f64 {
+    use rand::Rng;
+    rand::thread_rng().gen_range(0.5..=1.0)
+}
+
+/// Push once, then retry transient failures with backoff until the policy's attempts run out.
+/// Returns the attempts made and the last outcome.
+pub async fn push_with_retry<F, Fut>(policy: &SyncRetryPolicy, mut push: F) -> (u32, Result<(), SyncError>)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(), SyncError>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match push().await {
+            Err(e) if e.is_retryable() && attempts < policy.max_attempts => {
+                let delay = policy.delay(attempts, jitter());
+                tracing::warn!("Sync push failed (attempt {}), retrying in {}ms: {}", attempts, delay.as_millis(), e);
+                tokio::time::sleep(delay).await;
+            }
+            outcome => return (attempts, outcome),
+        }
+    }
+}
+
+/// Run a sync pass under `state`'s lock, retrying with backoff while uploads fail transiently.
+/// The lock is released during the wait, so saves and status queries are not held up by it.
+pub async fn sync_with_retry<F>(state: &tokio::sync::Mutex<Option<OfflineSyncService>>, pass: F) -> Result<(), SyncError>
+where
+    F: for<'a> Fn(&'a mut OfflineSyncService) -> BoxFuture<'a, Result<(), SyncError>>,
+{
+    let not_initialized = || SyncError::Setup("Sync service not initialized".to_string());
+    let policy = state.lock().await.as_ref().map(|service| service.retry_policy.clone()).ok_or_else(not_initialized)?;
+    let pass = &pass;
+    let (_, result) = push_with_retry(&policy, move || async move {
+        let mut guard = state.lock().await;
+        let service = guard.as_mut().ok_or_else(not_initialized)?;
+        pass(service).await?;
+        match service.transient_failures {
+            0 => Ok(()),
+            n => Err(SyncError::Network(format!("{} note(s) could not be uploaded", n))),
+        }
+    })
+    .await;
+    result
+}
+
+/// Push history of a note that has not synced cleanly
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NoteSyncAttempts {
+    /// Pushes made across syncs since the note last synced
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// Set for permanent errors; the note is `SyncFailed` and is not retried
+    pub failed: bool,
+}
+
+/// Notes waiting to upload, split by whether another sync will try them again
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PendingSyncCount {
+    pub retryable: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone)]
+struct QueuedNote {
+    modified_at: DateTime<Utc>,
+    /// Order of the `force_sync_note` boost; later boosts go first
+    boost: Option<u64>,
+}
+
+/// Notes waiting to upload. Forced notes go first, most recently forced at the front; the rest
+/// go newest edit first, so the note a clinician just wrote is not stuck behind old ones.
+#[derive(Debug, Default)]
+pub struct SyncQueue {
+    notes: HashMap<String, QueuedNote>,
+    boosts: u64,
+}
+
+impl SyncQueue {
+    /// Queue a note, or update its recency if already queued
+    pub fn push(&mut self, note_id: &str, modified_at: DateTime<Utc>) {
+        self.notes
+            .entry(note_id.to_string())
+            .and_modify(|queued| queued.modified_at = modified_at)
+            .or_insert(QueuedNote { modified_at, boost: None });
+    }
+
+    /// Move a note to the front of the queue
+    pub fn boost(&mut self, note_id: &str, modified_at: DateTime<Utc>) {
+        self.boosts += 1;
+        self.notes.insert(note_id.to_string(), QueuedNote { modified_at, boost: Some(self.boosts) });
+    }
+
+    pub fn remove(&mut self, note_id: &str) -> bool {
+        self.notes.remove(note_id).is_some()
+    }
+
+    /// Note IDs in upload order
+    pub fn ordered(&self) -> Vec<String> {
+        let mut notes: Vec<(&String, &QueuedNote)> = self.notes.iter().collect();
+        notes.sort_by(|(a_id, a), (b_id, b)| {
+            b.boost
+                .cmp(&a.boost)
+                .then(b.modified_at.cmp(&a.modified_at))
+                .then(a_id.cmp(b_id))
+        });
+        notes.into_iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    /// Place in the queue, 1 being the next upload
+    pub fn position(&self, note_id: &str) -> Option<usize> {
+        self.ordered().iter().position(|id| id == note_id).map(|i| i + 1)
+    }
+
+    /// The next note to upload that is not in `skip`
+    pub fn next_excluding(&self, skip: &HashSet<String>) -> Option<String> {
+        self.ordered().into_iter().find(|id| !skip.contains(id))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SyncMetadata {
     pub last_sync: Option<DateTime<Utc>>,
+    /// Queued note IDs in upload order
     pub pending_uploads: Vec<String>,
     pub conflict_notes: Vec<String>,
     pub sync_enabled: bool,
     pub firebase_collection: String,
+    /// Notes whose last push failed, by ID
+    #[serde(default)]
+    pub note_attempts: HashMap<String, NoteSyncAttempts>,
+}
+
+impl SyncMetadata {
+    pub fn pending_count(&self) -> PendingSyncCount {
+        let failed = self.note_attempts.values().filter(|a| a.failed).count();
+        PendingSyncCount { retryable: self.pending_uploads.len(), failed }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConflictResolution {
     pub note_id: String,
+    /// Last version both sides agreed on, when this session synced the note before
+    pub base_version: Option<MedicalNote>,
     pub local_version: MedicalNote,
     pub remote_version: MedicalNote,
     pub resolution_strategy: ResolutionStrategy,
@@ -45,11 +265,169 @@ pub enum ResolutionStrategy {
     ManualReview,
 }
 
+/// How `resolve_conflict_manually` settles a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    #[serde(alias = "use_local")]
+    KeepLocal,
+    #[serde(alias = "use_remote")]
+    KeepRemote,
+    /// Take each side's changes to different fields; a field both sides changed differently
+    /// leaves the note in conflict
+    #[serde(alias = "merge")]
+    ThreeWayMerge,
+    /// Save the note as the clinician edited it
+    Manual,
+}
+
+/// What happened to one field of a conflicted note since the base version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldChange {
+    Unchanged,
+    Local,
+    Remote,
+    /// Both sides made the same change
+    Both,
+    Conflict,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub field: String,
+    /// `None` where the field is absent
+    pub base: Option<Value>,
+    pub local: Option<Value>,
+    pub remote: Option<Value>,
+    pub change: FieldChange,
+}
+
+/// A conflicted note with a per-field diff for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteConflict {
+    pub note_id: String,
+    pub local_version: MedicalNote,
+    pub remote_version: MedicalNote,
+    /// Whether the content is a JSON object diffed field by field; otherwise the whole content
+    /// is the single field `content`
+    pub structured: bool,
+    pub fields: Vec<FieldDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictResolutionOutcome {
+    pub note_id: String,
+    pub resolved: bool,
+    /// Fields both sides changed differently; the note stays in conflict while any remain
+    pub unresolved_fields: Vec<String>,
+    pub fields: Vec<FieldDiff>,
+}
+
+/// Field name used when note content is not structured
+const WHOLE_CONTENT_FIELD: &str = "content";
+
+fn structured_fields(content: &str) -> Option<Map<String, Value>> {
+    match serde_json::from_str(content) {
+        Ok(Value::Object(fields)) => Some(fields),
+        _ => None,
+    }
+}
+
+/// Per-field diff of the three versions' content. Without a base every difference is a
+/// conflict, since there is no telling which side changed.
+pub fn diff_note_content(base: Option<&str>, local: &str, remote: &str) -> (bool, Vec<FieldDiff>) {
+    let (structured, base, local, remote) = match (structured_fields(local), structured_fields(remote)) {
+        (Some(local), Some(remote)) => (true, base.map(|b| structured_fields(b).unwrap_or_default()), local, remote),
+        _ => {
+            let whole = |content: &str| Map::from_iter([(WHOLE_CONTENT_FIELD.to_string(), Value::String(content.to_string()))]);
+            (false, base.map(whole), whole(local), whole(remote))
+        }
+    };
+
+    let names: BTreeSet<&String> = local.keys().chain(remote.keys()).chain(base.iter().flat_map(|b| b.keys())).collect();
+    let fields = names
+        .into_iter()
+        .map(|name| {
+            let (l, r) = (local.get(name), remote.get(name));
+            let b = base.as_ref().and_then(|b| b.get(name));
+            let change = match &base {
+                _ if l == r && l == b => FieldChange::Unchanged,
+                _ if l == r => FieldChange::Both,
+                Some(_) if l == b => FieldChange::Remote,
+                Some(_) if r == b => FieldChange::Local,
+                _ => FieldChange::Conflict,
+            };
+            FieldDiff { field: name.clone(), base: b.cloned(), local: l.cloned(), remote: r.cloned(), change }
+        })
+        .collect();
+    (structured, fields)
+}
+
+/// Merged content, or the fields that could not be merged
+pub fn merge_note_content(structured: bool, fields: &[FieldDiff]) -> Result<String, Vec<String>> {
+    let unresolved: Vec<String> = fields
+        .iter()
+        .filter(|f| f.change == FieldChange::Conflict)
+        .map(|f| f.field.clone())
+        .collect();
+    if !unresolved.is_empty() {
+        return Err(unresolved);
+    }
+
+    let mut merged = Map::new();
+    for field in fields {
+        let value = if field.change == FieldChange::Remote { &field.remote } else { &field.local };
+        if let Some(value) = value {
+            merged.insert(field.field.clone(), value.clone());
+        }
+    }
+    if structured {
+        return Ok(Value::Object(merged).to_string());
+    }
+    Ok(merged
+        .get(WHOLE_CONTENT_FIELD)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Sync record kinds kept in note storage so merge bases and open conflicts survive a restart
+const SYNCED_VERSION_RECORD: &str = "synced_version";
+const CONFLICT_RECORD: &str = "conflict";
+
+/// Decode the stored sync records of one kind; a record that does not decode is skipped
+fn load_records<T: serde::de::DeserializeOwned>(storage: &EncryptedNoteStorage, kind: &str) -> HashMap<String, T> {
+    match storage.load_sync_records(kind) {
+        Ok(records) => records
+            .into_iter()
+            .filter_map(|(note_id, payload)| match serde_json::from_str(&payload) {
+                Ok(record) => Some((note_id, record)),
+                Err(e) => {
+                    tracing::error!("Skipping unreadable {} record for note {}: {}", kind, note_id, e);
+                    None
+                }
+            })
+            .collect(),
+        Err(e) => {
+            tracing::error!("Failed to load {} records: {}", kind, e);
+            HashMap::new()
+        }
+    }
+}
+
 pub struct OfflineSyncService {
     local_storage: EncryptedNoteStorage,
     firebase_service: Option<FirebaseService>,
     sync_metadata: SyncMetadata,
     user_id: String,
+    /// Last synced version of each note, the base of a three-way merge
+    synced_versions: HashMap<String, MedicalNote>,
+    conflicts: HashMap<String, ConflictResolution>,
+    retry_policy: SyncRetryPolicy,
+    queue: SyncQueue,
+    /// Notes whose upload failed transiently in the last pass
+    transient_failures: usize,
 }
 
 impl OfflineSyncService {
@@ -59,22 +437,79 @@ impl OfflineSyncService {
         firebase_service: Option<FirebaseService>,
         user_id: String,
     ) -> Self {
+        let synced_versions = load_records(&local_storage, SYNCED_VERSION_RECORD);
+        let conflicts: HashMap<String, ConflictResolution> = load_records(&local_storage, CONFLICT_RECORD);
+        let mut conflict_notes: Vec<String> = conflicts.keys().cloned().collect();
+        conflict_notes.sort();
+
         let sync_metadata = SyncMetadata {
             last_sync: None,
             pending_uploads: Vec::new(),
-            conflict_notes: Vec::new(),
+            conflict_notes,
             sync_enabled: firebase_service.is_some(),
             firebase_collection: "encrypted_medical_notes".to_string(),
+            note_attempts: HashMap::new(),
         };
 
-        Self {
+        let mut service = Self {
             local_storage,
             firebase_service,
             sync_metadata,
             user_id,
+            synced_versions,
+            conflicts,
+            retry_policy: SyncRetryPolicy::from_env(),
+            queue: SyncQueue::default(),
+            transient_failures: 0,
+        };
+        // Notes saved while no sync service was running are still pending in storage
+        match service.local_storage.pending_sync_notes() {
+            Ok(pending) => {
+                for (note_id, modified_at) in pending {
+                    service.queue.push(&note_id, modified_at);
+                }
+                service.sync_metadata.pending_uploads = service.queue.ordered();
+            }
+            Err(e) => tracing::error!("Failed to load pending notes into the sync queue: {}", e),
+        }
+        service
+    }
+
+    /// Queue a saved note for upload
+    pub fn enqueue_note(&mut self, note: &MedicalNote) {
+        self.queue.push(&note.id, note.modified_at);
+        self.sync_metadata.pending_uploads = self.queue.ordered();
+    }
+
+    fn dequeue_note(&mut self, note_id: &str) {
+        if self.queue.remove(note_id) {
+            self.sync_metadata.pending_uploads = self.queue.ordered();
         }
     }
 
+    /// Place of a note in the upload queue, 1 being the next upload
+    pub fn queue_position(&self, note_id: &str) -> Option<usize> {
+        self.queue.position(note_id)
+    }
+
+    fn remember_synced(&mut self, note: &MedicalNote) {
+        if note.id.is_empty() {
+            return;
+        }
+        // Only the merge base is lost if this fails; the note itself is saved separately
+        let stored = serde_json::to_string(note)
+            .map_err(|e| e.to_string())
+            .and_then(|payload| {
+                self.local_storage
+                    .save_sync_record(&note.id, SYNCED_VERSION_RECORD, &payload)
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = stored {
+            tracing::error!("Failed to store the synced version of note {}: {}", note.id, e);
+        }
+        self.synced_versions.insert(note.id.clone(), note.clone());
+    }
+
     /// Start background sync process
     pub async fn start_background_sync(&mut self) -> Result<(), SyncError> {
         if !self.sync_metadata.sync_enabled {
@@ -101,6 +536,11 @@ impl OfflineSyncService {
 
     /// Perform full sync operation
     pub async fn perform_sync(&mut self) -> Result<(), SyncError> {
+        self.perform_sync_limited(None).await
+    }
+
+    /// Sync, uploading at most `max_uploads` queued notes; the rest wait for the next run
+    pub async fn perform_sync_limited(&mut self, max_uploads: Option<usize>) -> Result<(), SyncError> {
         if !self.sync_metadata.sync_enabled {
             return Err(SyncError::Network("Sync is disabled".to_string()));
         }
@@ -108,7 +548,7 @@ impl OfflineSyncService {
         tracing::info!("Starting sync operation for user: {}", self.user_id);
 
         // Step 1: Upload pending local changes
-        self.upload_pending_notes().await?;
+        self.upload_pending_notes(max_uploads).await?;
 
         // Step 2: Download remote changes
         self.download_remote_changes().await?;
@@ -124,47 +564,90 @@ impl OfflineSyncService {
     }
 
     /// Upload pending local notes to Firebase
-    async fn upload_pending_notes(&mut self) -> Result<(), SyncError> {
+    async fn upload_pending_notes(&mut self, max_uploads: Option<usize>) -> Result<(), SyncError> {
         let _firebase = self.firebase_service.as_ref()
-            .ok_or_else(|| SyncError::Firebase("Firebase service not available".to_string()))?;
-
-        let pending_notes = self.get_pending_notes().await?;
-
-        for note in pending_notes {
-            match self.upload_note_to_firebase(&note).await {
+            .ok_or_else(|| SyncError::Setup("Firebase service not available".to_string()))?;
+
+        // The queue is re-read after every upload so a note boosted meanwhile goes next; a note
+        // that failed stays queued for the next sync but is not tried again in this one
+        self.transient_failures = 0;
+        let mut tried = HashSet::new();
+        let mut uploads = 0;
+        while let Some(note_id) = self.queue.next_excluding(&tried) {
+            if max_uploads.is_some_and(|max| uploads >= max) {
+                tracing::info!("Upload batch limit reached; {} note(s) left for the next sync", self.queue.ordered().len());
+                break;
+            }
+            tried.insert(note_id.clone());
+            let note = match self.local_storage.read_note(&note_id).await {
+                Ok(Some(note)) if note.sync_status == SyncStatus::Pending => note,
                 Ok(_) => {
-                    // Update local note status to synced
-                    let mut updated_note = note.clone();
-                    updated_note.sync_status = SyncStatus::Synced;
-
-                    self.local_storage
-                        .save_note(updated_note, &self.user_id)
-                        .await
-                        .map_err(|e| SyncError::Storage(format!("Failed to update note status: {}", e)))?;
-
-                    tracing::info!("Successfully uploaded note: {}", if note.id.is_empty() { "unknown" } else { &note.id });
+                    // Deleted, or already synced or resolved elsewhere
+                    self.dequeue_note(&note_id);
+                    continue;
                 }
                 Err(e) => {
-                    tracing::error!("Failed to upload note {}: {}", if note.id.is_empty() { "unknown" } else { &note.id }, e);
-                    // Mark as conflict for manual review
-                    let mut updated_note = note.clone();
-                    updated_note.sync_status = SyncStatus::Conflict;
-
-                    self.local_storage
-                        .save_note(updated_note, &self.user_id)
-                        .await
-                        .map_err(|e| SyncError::Storage(format!("Failed to update note status: {}", e)))?;
+                    tracing::error!("Failed to load queued note {}: {}", note_id, e);
+                    continue;
                 }
+            };
+            uploads += 1;
+            match self.push_note(note).await {
+                Ok(()) => tracing::info!("Successfully uploaded note: {}", note_id),
+                Err(e) => tracing::error!("Failed to upload note {}: {}", note_id, e),
             }
         }
 
         Ok(())
     }
 
+    /// Upload one note once; retries are left to `sync_with_retry`, which does not hold the sync
+    /// lock while it waits. A note that fails stays pending for the next attempt; one rejected
+    /// for good is marked `SyncFailed`.
+    async fn push_note(&mut self, note: MedicalNote) -> Result<(), SyncError> {
+        let result = self.upload_note_to_firebase(&note).await;
+
+        let mut updated_note = note;
+        let note_id = updated_note.id.clone();
+        match &result {
+            Ok(()) => {
+                updated_note.sync_status = SyncStatus::Synced;
+                self.remember_synced(&updated_note);
+                self.sync_metadata.note_attempts.remove(&note_id);
+                self.dequeue_note(&note_id);
+            }
+            Err(e) => {
+                let record = self.sync_metadata.note_attempts.entry(note_id.clone()).or_default();
+                record.attempts += 1;
+                record.last_error = Some(e.to_string());
+                record.failed = e.is_permanent();
+                if e.is_retryable() {
+                    self.transient_failures += 1;
+                }
+                if record.failed {
+                    updated_note.sync_status = SyncStatus::SyncFailed;
+                    self.dequeue_note(&note_id);
+                } else {
+                    // Keeps its place, boost included
+                    updated_note.sync_status = SyncStatus::Pending;
+                    if self.queue.position(&note_id).is_none() {
+                        self.enqueue_note(&updated_note);
+                    }
+                }
+            }
+        }
+
+        self.local_storage
+            .save_note(updated_note, &self.user_id)
+            .await
+            .map_err(|e| SyncError::Storage(format!("Failed to update note status: {}", e)))?;
+        result
+    }
+
     /// Download remote changes from Firebase
     async fn download_remote_changes(&mut self) -> Result<(), SyncError> {
         let _firebase = self.firebase_service.as_ref()
-            .ok_or_else(|| SyncError::Firebase("Firebase service not available".to_string()))?;
+            .ok_or_else(|| SyncError::Setup("Firebase service not available".to_string()))?;
 
         // Query Firebase for notes modified since last sync
         let last_sync = self.sync_metadata.last_sync.unwrap_or_else(|| {
@@ -198,7 +681,7 @@ impl OfflineSyncService {
         }
 
         // Check if we have a local version
-        match self.local_storage.get_note(note_id, &self.user_id).await {
+        match self.local_storage.read_note(note_id).await {
             Ok(Some(local_note)) => {
                 // We have both local and remote versions - check for conflicts
                 if local_note.modified_at > remote_note.modified_at {
@@ -214,6 +697,7 @@ impl OfflineSyncService {
                     // Remote is newer or same - update local
                     let mut updated_note = remote_note;
                     updated_note.sync_status = SyncStatus::Synced;
+                    self.remember_synced(&updated_note);
 
                     self.local_storage
                         .save_note(updated_note, &self.user_id)
@@ -225,6 +709,7 @@ impl OfflineSyncService {
                 // No local version - download remote
                 let mut new_note = remote_note;
                 new_note.sync_status = SyncStatus::Synced;
+                self.remember_synced(&new_note);
 
                 self.local_storage
                     .save_note(new_note, &self.user_id)
@@ -243,14 +728,19 @@ impl OfflineSyncService {
     async fn create_conflict_record(&mut self, local_note: MedicalNote, remote_note: MedicalNote) -> Result<(), SyncError> {
         let conflict = ConflictResolution {
             note_id: if local_note.id.is_empty() { Uuid::new_v4().to_string() } else { local_note.id.clone() },
+            base_version: self.synced_versions.get(&local_note.id).cloned(),
             local_version: local_note.clone(),
             remote_version: remote_note,
             resolution_strategy: ResolutionStrategy::ManualReview,
             resolved_version: None,
         };
 
-        // Save conflict record (in a real implementation, this would be stored separately)
         tracing::warn!("Conflict detected for note: {}", conflict.note_id);
+        let payload = serde_json::to_string(&conflict)
+            .map_err(|e| SyncError::Storage(format!("Failed to serialize conflict: {}", e)))?;
+        self.local_storage
+            .save_sync_record(&conflict.note_id, CONFLICT_RECORD, &payload)
+            .map_err(|e| SyncError::Storage(format!("Failed to store conflict: {}", e)))?;
 
         // Mark local note as conflict
         let mut conflicted_note = local_note;
@@ -261,7 +751,10 @@ impl OfflineSyncService {
             .await
             .map_err(|e| SyncError::Storage(format!("Failed to mark note as conflict: {}", e)))?;
 
-        self.sync_metadata.conflict_notes.push(conflict.note_id);
+        if !self.sync_metadata.conflict_notes.contains(&conflict.note_id) {
+            self.sync_metadata.conflict_notes.push(conflict.note_id.clone());
+        }
+        self.conflicts.insert(conflict.note_id.clone(), conflict);
 
         Ok(())
     }
@@ -296,17 +789,10 @@ impl OfflineSyncService {
         Ok(false) // Not automatically resolved
     }
 
-    /// Get notes that need to be uploaded
-    async fn get_pending_notes(&self) -> Result<Vec<MedicalNote>, SyncError> {
-        // In a real implementation, this would query the local storage for pending notes
-        // For now, return empty list
-        Ok(Vec::new())
-    }
-
     /// Upload a single note to Firebase
     async fn upload_note_to_firebase(&self, note: &MedicalNote) -> Result<(), SyncError> {
         let firebase = self.firebase_service.as_ref()
-            .ok_or_else(|| SyncError::Firebase("Firebase service not available".to_string()))?;
+            .ok_or_else(|| SyncError::Setup("Firebase service not available".to_string()))?;
 
         // Validate Quebec Law 25 compliance before upload
         if !note.consent_obtained || !note.quebec_compliance.law_25_consent {
@@ -317,18 +803,18 @@ impl OfflineSyncService {
 
         // Create Firebase document
         let document_data = serde_json::to_value(note)
-            .map_err(|e| SyncError::Firebase(format!("Failed to serialize note: {}", e)))?;
+            .map_err(|e| SyncError::Validation(format!("Failed to serialize note: {}", e)))?;
 
         let collection = &self.sync_metadata.firebase_collection;
         let document_id = &note.id;
         if document_id.is_empty() {
-            return Err(SyncError::Storage("Note missing ID".to_string()));
+            return Err(SyncError::Validation("Note missing ID".to_string()));
         }
 
         firebase
             .create_document(collection, document_id, &document_data)
             .await
-            .map_err(|e| SyncError::Firebase(format!("Failed to upload to Firebase: {:?}", e)))?;
+            .map_err(SyncError::from_firebase)?;
 
         tracing::info!("Note uploaded to Firebase: {}", document_id);
         Ok(())
@@ -337,7 +823,7 @@ impl OfflineSyncService {
     /// Get remote notes modified since timestamp
     async fn get_remote_notes_since(&self, _since: DateTime<Utc>) -> Result<Vec<MedicalNote>, SyncError> {
         let _firebase = self.firebase_service.as_ref()
-            .ok_or_else(|| SyncError::Firebase("Firebase service not available".to_string()))?;
+            .ok_or_else(|| SyncError::Setup("Firebase service not available".to_string()))?;
 
         // In a real implementation, this would query Firebase with timestamp filter
         // For now, return empty list
@@ -351,21 +837,16 @@ impl OfflineSyncService {
         }
 
         let note = self.local_storage
-            .get_note(note_id, &self.user_id)
+            .read_note(note_id)
             .await
             .map_err(|e| SyncError::Storage(format!("Failed to get note: {}", e)))?
             .ok_or_else(|| SyncError::Storage("Note not found".to_string()))?;
 
-        self.upload_note_to_firebase(&note).await?;
-
-        // Update note status
-        let mut updated_note = note;
-        updated_note.sync_status = SyncStatus::Synced;
-
-        self.local_storage
-            .save_note(updated_note, &self.user_id)
-            .await
-            .map_err(|e| SyncError::Storage(format!("Failed to update note status: {}", e)))?;
+        // Jump the queue; if the upload fails the note stays at the front for the next sync
+        self.transient_failures = 0;
+        self.queue.boost(note_id, note.modified_at);
+        self.sync_metadata.pending_uploads = self.queue.ordered();
+        self.push_note(note).await?;
 
         tracing::info!("Force sync completed for note: {}", note_id);
         Ok(())
@@ -376,42 +857,319 @@ impl OfflineSyncService {
         &self.sync_metadata
     }
 
+    /// Whether the remote store answers; `Ok(false)` when sync is off and nothing is probed
+    pub async fn check_connectivity(&self) -> Result<bool, SyncError> {
+        match &self.firebase_service {
+            Some(firebase) if self.sync_metadata.sync_enabled => {
+                let status = firebase
+                    .check_firestore_reachability()
+                    .await
+                    .map_err(|e| SyncError::Network(e.to_string()))?;
+                if status.is_success() {
+                    Ok(true)
+                } else {
+                    Err(SyncError::Network(format!("Firestore answered with {}", status)))
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
     /// Enable/disable sync
     pub fn set_sync_enabled(&mut self, enabled: bool) {
         self.sync_metadata.sync_enabled = enabled && self.firebase_service.is_some();
         tracing::info!("Sync enabled: {}", self.sync_metadata.sync_enabled);
     }
 
-    /// Get conflict notes for manual resolution
-    pub async fn get_conflict_notes(&self) -> Result<Vec<MedicalNote>, SyncError> {
-        let mut conflicts = Vec::new();
-
-        for note_id in &self.sync_metadata.conflict_notes {
-            if let Ok(Some(note)) = self.local_storage.get_note(note_id, &self.user_id).await {
-                if note.sync_status == SyncStatus::Conflict {
-                    conflicts.push(note);
+    /// Conflicted notes with a per-field diff of local and remote changes
+    pub fn get_conflict_notes(&self) -> Vec<NoteConflict> {
+        self.sync_metadata
+            .conflict_notes
+            .iter()
+            .filter_map(|note_id| self.conflicts.get(note_id))
+            .map(|conflict| {
+                let (structured, fields) = diff_note_content(
+                    conflict.base_version.as_ref().map(|b| b.content.as_str()),
+                    &conflict.local_version.content,
+                    &conflict.remote_version.content,
+                );
+                NoteConflict {
+                    note_id: conflict.note_id.clone(),
+                    local_version: conflict.local_version.clone(),
+                    remote_version: conflict.remote_version.clone(),
+                    structured,
+                    fields,
                 }
-            }
-        }
-
-        Ok(conflicts)
+            })
+            .collect()
     }
 
-    /// Manually resolve a conflict
-    pub async fn resolve_conflict_manually(&mut self, note_id: &str, resolution: ResolutionStrategy, resolved_note: MedicalNote) -> Result<(), SyncError> {
-        // Save the resolved note
-        let mut final_note = resolved_note;
-        final_note.sync_status = SyncStatus::Pending; // Will be synced on next upload
+    /// Resolve a conflict. A three-way merge that finds fields both sides changed differently
+    /// saves nothing and leaves the note in conflict.
+    pub async fn resolve_conflict_manually(
+        &mut self,
+        note_id: &str,
+        strategy: MergeStrategy,
+        resolved_note: Option<MedicalNote>,
+    ) -> Result<ConflictResolutionOutcome, SyncError> {
+        let conflict = self.conflicts.get(note_id).cloned();
+        let (structured, fields) = conflict.as_ref().map_or((false, Vec::new()), |c| {
+            diff_note_content(
+                c.base_version.as_ref().map(|b| b.content.as_str()),
+                &c.local_version.content,
+                &c.remote_version.content,
+            )
+        });
+        let recorded = || {
+            conflict
+                .clone()
+                .ok_or_else(|| SyncError::ConflictResolution(format!("No conflict recorded for note {}", note_id)))
+        };
 
-        self.local_storage
+        let final_note = match strategy {
+            MergeStrategy::KeepLocal => recorded()?.local_version,
+            MergeStrategy::KeepRemote => recorded()?.remote_version,
+            MergeStrategy::ThreeWayMerge => match merge_note_content(structured, &fields) {
+                Ok(content) => {
+                    let mut merged = recorded()?.local_version;
+                    merged.content = content;
+                    merged.modified_at = Utc::now();
+                    merged
+                }
+                Err(unresolved_fields) => {
+                    tracing::warn!("Three-way merge of note {} left {} conflicting field(s)", note_id, unresolved_fields.len());
+                    return Ok(ConflictResolutionOutcome {
+                        note_id: note_id.to_string(),
+                        resolved: false,
+                        unresolved_fields,
+                        fields,
+                    });
+                }
+            },
+            MergeStrategy::Manual => resolved_note.ok_or_else(|| {
+                SyncError::ConflictResolution("Manual resolution needs the resolved note".to_string())
+            })?,
+        };
+
+        // The remote version is already on the server; anything else is uploaded on the next sync
+        let mut final_note = final_note;
+        final_note.sync_status = if strategy == MergeStrategy::KeepRemote { SyncStatus::Synced } else { SyncStatus::Pending };
+        if strategy == MergeStrategy::KeepRemote {
+            self.remember_synced(&final_note);
+        }
+
+        let pending = final_note.sync_status == SyncStatus::Pending;
+        let mut saved_note = final_note.clone();
+        saved_note.id = self.local_storage
             .save_note(final_note, &self.user_id)
             .await
             .map_err(|e| SyncError::Storage(format!("Failed to save resolved note: {}", e)))?;
+        if pending {
+            saved_note.modified_at = Utc::now();
+            self.enqueue_note(&saved_note);
+        }
 
         // Remove from conflict list
         self.sync_metadata.conflict_notes.retain(|id| id != note_id);
+        self.conflicts.remove(note_id);
+        if let Err(e) = self.local_storage.delete_sync_record(note_id, CONFLICT_RECORD) {
+            tracing::error!("Failed to remove the stored conflict for note {}: {}", note_id, e);
+        }
 
-        tracing::info!("Conflict manually resolved for note: {} using strategy: {:?}", note_id, resolution);
-        Ok(())
+        tracing::info!("Conflict manually resolved for note: {} using strategy: {:?}", note_id, strategy);
+        Ok(ConflictResolutionOutcome {
+            note_id: note_id.to_string(),
+            resolved: true,
+            unresolved_fields: Vec::new(),
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_push_retries_transient_failures_until_success() {
+        let policy = SyncRetryPolicy { max_attempts: 5, initial_delay_ms: 1, max_delay_ms: 4 };
+        let mut calls = 0;
+        let (attempts, result) = push_with_retry(&policy, || {
+            calls += 1;
+            let outcome = if calls <= 3 { Err(SyncError::Network("unavailable".into())) } else { Ok(()) };
+            async move { outcome }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts, 4);
+
+        // Transient failures stop at the attempt limit; permanent ones are not retried
+        let (attempts, result) = push_with_retry(&policy, || async { Err(SyncError::Firebase("503".into())) }).await;
+        assert_eq!(attempts, 5);
+        assert!(result.unwrap_err().is_retryable());
+        let (attempts, result) = push_with_retry(&policy, || async { Err(SyncError::Unauthorized("expired".into())) }).await;
+        assert_eq!(attempts, 1);
+        assert!(!result.unwrap_err().is_retryable());
+    }
+
+    #[test]
+    fn test_only_faults_of_the_note_fail_it_for_good() {
+        let unavailable = SyncError::from_firebase(FirebaseError::Firestore("503 UNAVAILABLE".into()));
+        assert!(unavailable.is_retryable() && !unavailable.is_permanent());
+        let invalid = SyncError::from_firebase(FirebaseError::Firestore("400 INVALID_ARGUMENT: field too large".into()));
+        assert!(!invalid.is_retryable() && invalid.is_permanent());
+        let denied = SyncError::from_firebase(FirebaseError::Firestore("403 PERMISSION_DENIED".into()));
+        assert!(matches!(denied, SyncError::Unauthorized(_)) && !denied.is_permanent());
+        let misconfigured = SyncError::from_firebase(FirebaseError::Init("no credentials".into()));
+        assert!(!misconfigured.is_retryable() && !misconfigured.is_permanent());
+
+        // A full disk is not the note's fault
+        assert!(!SyncError::Storage("database is locked".into()).is_permanent());
+    }
+
+    #[test]
+    fn test_forced_note_jumps_ahead_of_queued_notes() {
+        let now = Utc::now();
+        let mut queue = SyncQueue::default();
+        queue.push("a", now - chrono::Duration::hours(2));
+        queue.push("b", now - chrono::Duration::hours(1));
+        assert_eq!(queue.ordered(), vec!["b", "a"]);
+
+        queue.boost("c", now - chrono::Duration::days(3));
+        assert_eq!(queue.ordered(), vec!["c", "b", "a"]);
+        assert_eq!(queue.position("c"), Some(1));
+        assert_eq!(queue.position("a"), Some(3));
+
+        // A later boost goes ahead of an earlier one, and skipped notes are passed over
+        queue.boost("a", now - chrono::Duration::hours(2));
+        assert_eq!(queue.ordered(), vec!["a", "c", "b"]);
+        assert_eq!(queue.next_excluding(&HashSet::from(["a".to_string()])), Some("c".to_string()));
+        assert!(queue.remove("c"));
+        assert_eq!(queue.position("c"), None);
+    }
+
+    fn pending_note(id: &str) -> MedicalNote {
+        MedicalNote {
+            id: id.to_string(),
+            patient_id: "patient-1".to_string(),
+            content: format!("Session notes for {}", id),
+            template_type: "progress".to_string(),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+            consent_obtained: true,
+            encrypted: false,
+            deidentified: false,
+            sync_status: SyncStatus::Pending,
+            quebec_compliance: crate::services::encrypted_storage::QuebecComplianceMetadata {
+                law_25_consent: true,
+                data_minimization: true,
+                retention_period_days: 3650,
+                professional_order: None,
+                audit_trail: Vec::new(),
+            },
+            metadata: Default::default(),
+            version: 0,
+        }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_notes_saved_before_the_service_started_are_queued() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = EncryptedNoteStorage::open(dir.path().join("notes.db"), "test-passphrase").unwrap();
+        storage.save_note(pending_note("pending"), "u").await.unwrap();
+        let mut synced = pending_note("synced");
+        synced.sync_status = SyncStatus::Synced;
+        storage.save_note(synced, "u").await.unwrap();
+
+        let service = OfflineSyncService::new(storage, None, "u".to_string());
+        assert_eq!(service.get_sync_status().pending_uploads, vec!["pending"]);
+        assert_eq!(service.queue_position("pending"), Some(1));
+        assert_eq!(service.queue_position("synced"), None);
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_and_merge_bases_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("notes.db");
+        let storage = EncryptedNoteStorage::open(db_path.clone(), "test-passphrase").unwrap();
+        let mut service = OfflineSyncService::new(storage, None, "u".to_string());
+
+        let mut base = pending_note("note-1");
+        base.content = r#"{"plan":"Weekly CBT"}"#.to_string();
+        service.remember_synced(&base);
+        let mut local = base.clone();
+        local.content = r#"{"plan":"Weekly CBT","risk":"low"}"#.to_string();
+        let mut remote = base.clone();
+        remote.content = r#"{"plan":"Biweekly CBT"}"#.to_string();
+        service.create_conflict_record(local, remote).await.unwrap();
+        drop(service);
+
+        let storage = EncryptedNoteStorage::open(db_path, "test-passphrase").unwrap();
+        let mut service = OfflineSyncService::new(storage, None, "u".to_string());
+        assert_eq!(service.get_sync_status().conflict_notes, vec!["note-1"]);
+        let conflicts = service.get_conflict_notes();
+        assert_eq!(conflicts.len(), 1);
+        let change = |name: &str| conflicts[0].fields.iter().find(|f| f.field == name).unwrap().change;
+        assert_eq!(change("plan"), FieldChange::Remote);
+        assert_eq!(change("risk"), FieldChange::Local);
+
+        let outcome = service
+            .resolve_conflict_manually("note-1", MergeStrategy::ThreeWayMerge, None)
+            .await
+            .unwrap();
+        assert!(outcome.resolved);
+        assert_eq!(service.queue_position("note-1"), Some(1));
+        assert!(service.local_storage.load_sync_records(CONFLICT_RECORD).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_backoff_doubles_to_the_cap_and_jitter_shortens_it() {
+        let policy = SyncRetryPolicy { max_attempts: 5, initial_delay_ms: 1_000, max_delay_ms: 5_000 };
+        assert_eq!(policy.delay(1, 1.0), Duration::from_millis(1_000));
+        assert_eq!(policy.delay(3, 1.0), Duration::from_millis(4_000));
+        assert_eq!(policy.delay(10, 1.0), Duration::from_millis(5_000));
+        assert_eq!(policy.delay(2, 0.5), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn test_three_way_merge_takes_non_overlapping_field_changes() {
+        let base = r#"{"assessment":"Low mood","plan":"Weekly CBT","risk":"none"}"#;
+        let local = r#"{"assessment":"Low mood, improving","plan":"Weekly CBT","risk":"none"}"#;
+        let remote = r#"{"assessment":"Low mood","plan":"Biweekly CBT","risk":"none","homework":"Journal"}"#;
+
+        let (structured, fields) = diff_note_content(Some(base), local, remote);
+        assert!(structured);
+        let change = |name: &str| fields.iter().find(|f| f.field == name).unwrap().change;
+        assert_eq!(change("assessment"), FieldChange::Local);
+        assert_eq!(change("plan"), FieldChange::Remote);
+        assert_eq!(change("homework"), FieldChange::Remote);
+        assert_eq!(change("risk"), FieldChange::Unchanged);
+
+        let merged: Value = serde_json::from_str(&merge_note_content(structured, &fields).unwrap()).unwrap();
+        assert_eq!(
+            merged,
+            serde_json::json!({
+                "assessment": "Low mood, improving",
+                "plan": "Biweekly CBT",
+                "risk": "none",
+                "homework": "Journal",
+            })
+        );
+    }
+
+    #[test]
+    fn test_same_field_edited_differently_stays_unresolved() {
+        let base = r#"{"plan":"Weekly CBT","risk":"none"}"#;
+        let local = r#"{"plan":"Weekly CBT","risk":"moderate"}"#;
+        let remote = r#"{"plan":"Weekly CBT","risk":"low"}"#;
+        let (structured, fields) = diff_note_content(Some(base), local, remote);
+        assert_eq!(merge_note_content(structured, &fields), Err(vec!["risk".to_string()]));
+
+        // Free-text notes merge only when one side left the text alone
+        let (structured, fields) = diff_note_content(Some("Session 1"), "Session 1 notes", "Session 1");
+        assert!(!structured);
+        assert_eq!(merge_note_content(structured, &fields), Ok("Session 1 notes".to_string()));
+        let (structured, fields) = diff_note_content(None, "Session 1 notes", "Session 1");
+        assert_eq!(merge_note_content(structured, &fields), Err(vec!["content".to_string()]));
+    }
+}
@@ -0,0 +1,480 @@
+// Patient Access Notifications for PsyPsy CMS
+// Law 25 transparency: a patient who opts in is told when their record is accessed (by which
+// role, when and for what kind of purpose) and can review the access log for their own record.
+// Notices are built from the single access they describe, so they never carry another patient's
+// data. Routine accesses are throttled to one notice per interval with a count of the rest;
+// break-glass accesses are always sent, to the patient's contact address even without opt-in.
+// Accesses are taken from the PHI access audit trail, so every read that is audited through
+// `log_phi_access` reaches the patient's log. Opt-ins and logs are encrypted at rest.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use crate::security::audit::{AuditEvent, AuditOutcome};
+use crate::security::rbac::{stable_uuid, ElevatedGrant, ElevatedGrantKind};
+use crate::security::HealthcareRole;
+use crate::services::notifier::{Notification, NotifierState};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const STORE_FILE: &str = "patient_access_notifications.json";
+const KEY_FILE: &str = "patient_access_notifications.key";
+const NONCE_LEN: usize = 12;
+
+/// Broad reason for an access, shown to the patient instead of internal action names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessPurpose {
+    Care,
+    Administration,
+    Billing,
+    Compliance,
+    Emergency,
+}
+
+impl AccessPurpose {
+    pub fn from_action(action: &str, break_glass: bool) -> Self {
+        let action = action.to_ascii_uppercase();
+        if break_glass {
+            AccessPurpose::Emergency
+        } else if ["AUDIT", "EXPORT", "COMPLIANCE"].iter().any(|k| action.contains(k)) {
+            AccessPurpose::Compliance
+        } else if ["BILL", "INVOICE", "PAYMENT"].iter().any(|k| action.contains(k)) {
+            AccessPurpose::Billing
+        } else if ["CLIENT", "TIMELINE", "NOTE", "TRANSCRIPT", "APPOINTMENT", "PATIENT"].iter().any(|k| action.contains(k)) {
+            AccessPurpose::Care
+        } else {
+            AccessPurpose::Administration
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            AccessPurpose::Care => "your care",
+            AccessPurpose::Administration => "administration",
+            AccessPurpose::Billing => "billing",
+            AccessPurpose::Compliance => "a compliance review",
+            AccessPurpose::Emergency => "emergency access",
+        }
+    }
+}
+
+/// One access to a patient's record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientAccess {
+    pub patient_id: String,
+    pub accessor_user_id: String,
+    pub accessor_role: Option<HealthcareRole>,
+    pub purpose: AccessPurpose,
+    pub break_glass: bool,
+    pub accessed_at: DateTime<Utc>,
+}
+
+impl PatientAccess {
+    pub fn new(
+        patient_id: &str,
+        accessor_user_id: &str,
+        accessor_role: Option<HealthcareRole>,
+        action: &str,
+        break_glass: bool,
+    ) -> Self {
+        Self {
+            patient_id: patient_id.to_string(),
+            accessor_user_id: accessor_user_id.to_string(),
+            accessor_role,
+            purpose: AccessPurpose::from_action(action, break_glass),
+            break_glass,
+            accessed_at: Utc::now(),
+        }
+    }
+
+    /// Access described by a granted `log_phi_access` audit event. The event's patient ID is a
+    /// stable UUID, so the client record is taken from its resource ID.
+    pub fn from_audit_event(event: &AuditEvent, break_glass: bool) -> Option<Self> {
+        if event.outcome != AuditOutcome::Success || event.resource_type.as_deref() != Some("patient_record") {
+            return None;
+        }
+        Some(Self {
+            patient_id: event.resource_id.clone()?,
+            accessor_user_id: event.user_id?.to_string(),
+            accessor_role: event.user_role.clone(),
+            purpose: AccessPurpose::from_action(&event.action, break_glass),
+            break_glass,
+            accessed_at: event.timestamp,
+        })
+    }
+}
+
+/// Whether the user holds an active break-glass grant covering the patient
+pub fn holds_break_glass(grants: &[ElevatedGrant], user_id: &str, patient_id: &str, now: DateTime<Utc>) -> bool {
+    // Grants opened through RBAC name the grantee by their stable UUID
+    let subject = stable_uuid(user_id).to_string();
+    grants.iter().any(|g| {
+        g.kind == ElevatedGrantKind::BreakGlass
+            && (g.grantee_user_id == user_id || g.grantee_user_id == subject)
+            && g.patient_id.as_deref().map_or(true, |p| p == patient_id)
+            && g.is_active_at(now)
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientAccessNotificationConfig {
+    /// At most one routine notice per patient per interval
+    pub min_interval_minutes: i64,
+    /// Entries kept per patient for the access log
+    pub log_limit: usize,
+}
+
+impl Default for PatientAccessNotificationConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_minutes: 60,
+            log_limit: 500,
+        }
+    }
+}
+
+impl PatientAccessNotificationConfig {
+    /// Defaults with `PATIENT_ACCESS_NOTIFY_INTERVAL_MINUTES` and `PATIENT_ACCESS_LOG_LIMIT` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("PATIENT_ACCESS_NOTIFY_INTERVAL_MINUTES") {
+            match value.trim().parse::<i64>() {
+                Ok(minutes) if minutes >= 0 => config.min_interval_minutes = minutes,
+                _ => log::warn!("Ignoring invalid PATIENT_ACCESS_NOTIFY_INTERVAL_MINUTES '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("PATIENT_ACCESS_LOG_LIMIT") {
+            match value.trim().parse::<usize>() {
+                Ok(limit) if limit > 0 => config.log_limit = limit,
+                _ => log::warn!("Ignoring invalid PATIENT_ACCESS_LOG_LIMIT '{}'", value),
+            }
+        }
+        config
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    /// Patient ID to the address notices go to
+    subscriptions: HashMap<String, String>,
+    /// Newest last
+    log: HashMap<String, VecDeque<PatientAccess>>,
+    #[serde(skip)]
+    throttle: HashMap<String, Throttle>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Throttle {
+    last_sent: DateTime<Utc>,
+    /// Routine accesses since `last_sent` that did not get their own notice
+    suppressed: u32,
+}
+
+/// Encrypted file the store is saved to
+#[derive(Clone)]
+struct StoreFile {
+    path: PathBuf,
+    key: Vec<u8>,
+}
+
+impl StoreFile {
+    fn read(&self) -> std::io::Result<Option<Store>> {
+        let sealed = match std::fs::read(&self.path) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = |reason: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, reason.to_string());
+        if sealed.len() < NONCE_LEN {
+            return Err(invalid("patient access notification store is truncated"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid("patient access notification store does not decrypt"))?;
+        serde_json::from_slice(&plaintext)
+            .map(Some)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Replace the file through a temporary one, so a crash never leaves half a store
+    fn write(&self, plaintext: &[u8]) -> std::io::Result<()> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key))
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "failed to encrypt the store"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, sealed)?;
+        std::fs::rename(&temp, &self.path)
+    }
+}
+
+fn load_or_create_key(path: &Path) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) if key.len() == 32 => Ok(key),
+        Ok(_) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "patient access notification key is corrupt")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            use ring::rand::SecureRandom;
+            let mut key = vec![0u8; 32];
+            ring::rand::SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "random source unavailable"))?;
+            std::fs::write(path, &key)?;
+            Ok(key)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Opt-ins, per-patient access logs and notice throttling; persisted once storage is attached
+pub struct PatientAccessNotifications {
+    config: PatientAccessNotificationConfig,
+    store: Mutex<Store>,
+    storage: Mutex<Option<StoreFile>>,
+    /// Held while writing, so an older snapshot never lands after a newer one
+    writing: tokio::sync::Mutex<()>,
+}
+
+impl PatientAccessNotifications {
+    pub fn new(config: PatientAccessNotificationConfig) -> Self {
+        Self {
+            config,
+            store: Mutex::new(Store::default()),
+            storage: Mutex::new(None),
+            writing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Load saved opt-ins and logs from `dir` and persist future changes there. A store that
+    /// cannot be read is an error and is left as it is rather than replaced by an empty one.
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        let file = StoreFile {
+            path: dir.join(STORE_FILE),
+            key: load_or_create_key(&dir.join(KEY_FILE))?,
+        };
+        if let Some(saved) = file.read()? {
+            *self.store.lock().unwrap() = saved;
+        }
+        *self.storage.lock().unwrap() = Some(file);
+        Ok(())
+    }
+
+    /// Opt a patient in with the address to notify, or out with `None`
+    pub async fn set_subscription(&self, patient_id: &str, recipient: Option<String>) {
+        {
+            let mut store = self.store.lock().unwrap();
+            match recipient {
+                Some(recipient) => store.subscriptions.insert(patient_id.to_string(), recipient),
+                None => store.subscriptions.remove(patient_id),
+            };
+        }
+        self.persist().await;
+    }
+
+    pub fn subscription(&self, patient_id: &str) -> Option<String> {
+        self.store.lock().unwrap().subscriptions.get(patient_id).cloned()
+    }
+
+    /// The patient's own access log, newest first
+    pub fn access_log(&self, patient_id: &str, limit: usize) -> Vec<PatientAccess> {
+        self.store
+            .lock()
+            .unwrap()
+            .log
+            .get(patient_id)
+            .map(|entries| entries.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Record the access and return the notice to send, if any. `contact` is only used for
+    /// break-glass accesses to patients who did not opt in. The change is saved by `persist`.
+    pub fn record(&self, access: PatientAccess, contact: Option<&str>, now: DateTime<Utc>) -> Option<Notification> {
+        let mut store = self.store.lock().unwrap();
+        let entries = store.log.entry(access.patient_id.clone()).or_default();
+        entries.push_back(access.clone());
+        while entries.len() > self.config.log_limit {
+            entries.pop_front();
+        }
+
+        let recipient = match store.subscriptions.get(&access.patient_id) {
+            Some(recipient) => Some(recipient.clone()),
+            None if access.break_glass => contact.filter(|c| !c.trim().is_empty()).map(str::to_string),
+            None => None,
+        };
+        let Some(recipient) = recipient else {
+            return None;
+        };
+
+        let interval = Duration::minutes(self.config.min_interval_minutes);
+        let throttle = store.throttle.get(&access.patient_id).copied();
+        let earlier = match throttle {
+            Some(t) if !access.break_glass && now - t.last_sent < interval => {
+                store.throttle.insert(access.patient_id.clone(), Throttle { suppressed: t.suppressed + 1, ..t });
+                return None;
+            }
+            Some(t) => t.suppressed,
+            None => 0,
+        };
+        store.throttle.insert(access.patient_id.clone(), Throttle { last_sent: now, suppressed: 0 });
+        drop(store);
+
+        Some(notice(&access, recipient, earlier))
+    }
+
+    /// Save the store; the file is written off the async runtime and outside the store lock
+    pub async fn persist(&self) {
+        let Some(file) = self.storage.lock().unwrap().clone() else {
+            return;
+        };
+        let _writing = self.writing.lock().await;
+        // Taken after the write lock, so the last writer saves the latest state
+        let snapshot = match serde_json::to_vec(&*self.store.lock().unwrap()) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                log::warn!("Failed to serialize patient access notifications: {}", e);
+                return;
+            }
+        };
+        match tokio::task::spawn_blocking(move || file.write(&snapshot)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log::warn!("Failed to persist patient access notifications: {}", e),
+            Err(e) => log::warn!("Patient access notification write task failed: {}", e),
+        }
+    }
+}
+
+impl Default for PatientAccessNotifications {
+    fn default() -> Self {
+        Self::new(PatientAccessNotificationConfig::default())
+    }
+}
+
+/// Patient-facing text; only this access and a count of earlier ones, nothing from the record
+fn notice(access: &PatientAccess, recipient: String, earlier: u32) -> Notification {
+    let role = access.accessor_role.as_ref().map_or("staff member".to_string(), |r| r.to_string());
+    let mut body = format!(
+        "Your record was accessed on {} UTC by a {} (user {}) for {}.",
+        access.accessed_at.format("%Y-%m-%d %H:%M"),
+        role,
+        access.accessor_user_id,
+        access.purpose.describe()
+    );
+    if access.break_glass {
+        body.push_str(" This was an emergency access outside the user's normal permissions and will be reviewed.");
+    }
+    if earlier > 0 {
+        body.push_str(&format!(" There were {} other access(es) since our last notice; see your access log.", earlier));
+    }
+    Notification {
+        recipient,
+        category: "patient_record_access".to_string(),
+        subject: if access.break_glass { "Emergency access to your record" } else { "Your record was accessed" }.to_string(),
+        body,
+    }
+}
+
+/// Record an access and deliver any resulting notice; delivery problems never fail the access
+pub async fn notify_record_access(
+    notifications: &PatientAccessNotifications,
+    notifier: &NotifierState,
+    access: PatientAccess,
+    contact: Option<&str>,
+) {
+    let notification = notifications.record(access, contact, Utc::now());
+    notifications.persist().await;
+    if let Some(notification) = notification {
+        if let Err(e) = notifier.0.deliver(&notification).await {
+            log::warn!("Failed to deliver patient access notification: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(patient: &str, break_glass: bool) -> PatientAccess {
+        PatientAccess::new(patient, "user-7", Some(HealthcareRole::HealthcareProvider), "VIEW_CLIENT", break_glass)
+    }
+
+    #[tokio::test]
+    async fn test_routine_accesses_are_throttled_and_counted() {
+        let notifications = PatientAccessNotifications::default();
+        let now = Utc::now();
+        assert!(notifications.record(access("p-1", false), None, now).is_none());
+
+        notifications.set_subscription("p-1", Some("patient@example.com".to_string())).await;
+        let first = notifications.record(access("p-1", false), None, now).unwrap();
+        assert_eq!(first.recipient, "patient@example.com");
+        assert!(notifications.record(access("p-1", false), None, now + Duration::minutes(5)).is_none());
+        assert!(notifications.record(access("p-1", false), None, now + Duration::minutes(10)).is_none());
+
+        let later = notifications.record(access("p-1", false), None, now + Duration::minutes(61)).unwrap();
+        assert!(later.body.contains("2 other access"));
+        assert_eq!(notifications.access_log("p-1", 100).len(), 5);
+        assert!(notifications.access_log("p-2", 100).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_break_glass_always_notifies() {
+        let notifications = PatientAccessNotifications::default();
+        let now = Utc::now();
+        let notice = notifications.record(access("p-1", true), Some("contact@example.com"), now).unwrap();
+        assert_eq!(notice.recipient, "contact@example.com");
+        assert!(notice.body.contains("emergency access"));
+        assert!(!notice.body.contains("p-1"));
+
+        notifications.set_subscription("p-1", Some("patient@example.com".to_string())).await;
+        assert!(notifications.record(access("p-1", false), None, now).is_none());
+        // Inside the throttle window, but break-glass is never held back
+        let notice = notifications.record(access("p-1", true), None, now + Duration::minutes(1)).unwrap();
+        assert_eq!(notice.recipient, "patient@example.com");
+        assert!(notice.body.contains("1 other access"));
+    }
+
+    #[tokio::test]
+    async fn test_store_is_encrypted_and_a_corrupt_one_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let notifications = PatientAccessNotifications::default();
+        notifications.attach_storage(dir.path()).unwrap();
+        notifications.set_subscription("client-9", Some("patient@example.com".to_string())).await;
+
+        let raw = std::fs::read(dir.path().join(STORE_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("patient@example.com"));
+        let reloaded = PatientAccessNotifications::default();
+        reloaded.attach_storage(dir.path()).unwrap();
+        assert_eq!(reloaded.subscription("client-9").as_deref(), Some("patient@example.com"));
+
+        std::fs::write(dir.path().join(STORE_FILE), b"{not a store").unwrap();
+        assert!(PatientAccessNotifications::default().attach_storage(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_accesses_come_from_granted_phi_access_events() {
+        let user = uuid::Uuid::new_v4();
+        let mut event = AuditEvent::new(
+            crate::security::AuditEventType::PatientDataViewed,
+            Some(user),
+            "VIEW_CLINICAL_NOTES".to_string(),
+            AuditOutcome::Success,
+        ).with_phi_access(stable_uuid("client-9"), "patient_record");
+        event.resource_id = Some("client-9".to_string());
+
+        let access = PatientAccess::from_audit_event(&event, false).unwrap();
+        assert_eq!(access.patient_id, "client-9");
+        assert_eq!(access.accessor_user_id, user.to_string());
+        assert_eq!(access.purpose, AccessPurpose::Care);
+
+        event.outcome = AuditOutcome::Denied;
+        assert!(PatientAccess::from_audit_event(&event, false).is_none());
+    }
+}
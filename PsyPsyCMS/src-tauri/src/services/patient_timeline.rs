@@ -0,0 +1,244 @@
+// Patient Timeline for PsyPsy CMS
+// Merges a patient's appointments, clinical notes, session transcripts and audit events into one
+// time-ordered stream. Sources are collected by the command only when the caller's role may see
+// them; anything else is left out of the stream entirely, never shown as a placeholder.
+
+use crate::models::{Appointment, PaginatedResponse};
+use crate::security::audit::AuditEvent;
+use crate::services::encrypted_storage::MedicalNote;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEventType {
+    Appointment,
+    Note,
+    Transcript,
+    Audit,
+}
+
+/// One entry in a patient's timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEvent {
+    pub event_type: TimelineEventType,
+    pub occurred_at: DateTime<Utc>,
+    /// ID of the appointment, note or audit event, or the transcript file name
+    pub source_id: String,
+    pub summary: String,
+    pub detail: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientTimelineConfig {
+    pub default_page_size: u32,
+    pub max_page_size: u32,
+    /// Notes decrypted per request; older notes beyond this are not shown
+    pub max_notes: u32,
+    /// Audit events about the patient appear for callers allowed to view audit logs
+    pub include_audit_events: bool,
+}
+
+impl Default for PatientTimelineConfig {
+    fn default() -> Self {
+        Self {
+            default_page_size: 50,
+            max_page_size: 200,
+            max_notes: 500,
+            include_audit_events: true,
+        }
+    }
+}
+
+impl PatientTimelineConfig {
+    /// Defaults with `PATIENT_TIMELINE_PAGE_SIZE`, `PATIENT_TIMELINE_MAX_PAGE_SIZE`,
+    /// `PATIENT_TIMELINE_MAX_NOTES` and `PATIENT_TIMELINE_INCLUDE_AUDIT` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        fn parse<T: std::str::FromStr>(name: &str, target: &mut T) {
+            if let Ok(value) = std::env::var(name) {
+                match value.trim().parse() {
+                    Ok(v) => *target = v,
+                    Err(_) => log::warn!("Ignoring invalid {} '{}'", name, value),
+                }
+            }
+        }
+        parse("PATIENT_TIMELINE_PAGE_SIZE", &mut config.default_page_size);
+        parse("PATIENT_TIMELINE_MAX_PAGE_SIZE", &mut config.max_page_size);
+        parse("PATIENT_TIMELINE_MAX_NOTES", &mut config.max_notes);
+        if let Ok(value) = std::env::var("PATIENT_TIMELINE_INCLUDE_AUDIT") {
+            config.include_audit_events = !matches!(value.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no" | "off");
+        }
+        config.max_page_size = config.max_page_size.max(1);
+        config.default_page_size = config.default_page_size.clamp(1, config.max_page_size);
+        config
+    }
+}
+
+/// Which events a request asks for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineFilter {
+    /// All types when absent
+    pub event_types: Option<HashSet<TimelineEventType>>,
+    pub start: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimelineFilter {
+    pub fn wants(&self, event_type: TimelineEventType) -> bool {
+        self.event_types.as_ref().map_or(true, |types| types.contains(&event_type))
+    }
+
+    fn matches(&self, event: &TimelineEvent) -> bool {
+        self.wants(event.event_type)
+            && self.start.map_or(true, |s| event.occurred_at >= s)
+            && self.end.map_or(true, |e| event.occurred_at < e)
+    }
+}
+
+/// Appointments are placed at their scheduled time, or when they were requested if unscheduled
+pub fn appointment_event(appointment: &Appointment) -> TimelineEvent {
+    TimelineEvent {
+        event_type: TimelineEventType::Appointment,
+        occurred_at: appointment.scheduled_start().unwrap_or(appointment.created_at.0),
+        source_id: appointment.object_id.clone(),
+        summary: format!("Appointment ({:?})", appointment.status),
+        detail: serde_json::json!({
+            "status": appointment.status,
+            "serviceType": appointment.service_type,
+            "meetPref": appointment.meet_pref,
+            "assignedProfessional": appointment.assigned_professional,
+            "durationMinutes": appointment.actual_duration.or(appointment.session_duration),
+        }),
+    }
+}
+
+pub fn note_event(note: &MedicalNote) -> TimelineEvent {
+    TimelineEvent {
+        event_type: TimelineEventType::Note,
+        occurred_at: note.created_at,
+        source_id: note.id.clone(),
+        summary: format!("Clinical note ({})", note.template_type),
+        detail: serde_json::json!({
+            "templateType": note.template_type,
+            "content": note.content,
+            "modifiedAt": note.modified_at,
+        }),
+    }
+}
+
+pub fn transcript_event(file_name: String, recorded_at: DateTime<Utc>, content: String) -> TimelineEvent {
+    TimelineEvent {
+        event_type: TimelineEventType::Transcript,
+        occurred_at: recorded_at,
+        source_id: file_name,
+        summary: "Session transcript".to_string(),
+        detail: serde_json::json!({ "content": content }),
+    }
+}
+
+/// Audit events are reduced to who did what and how it ended; recorded states stay in the log
+pub fn audit_event(event: &AuditEvent) -> TimelineEvent {
+    TimelineEvent {
+        event_type: TimelineEventType::Audit,
+        occurred_at: event.timestamp,
+        source_id: event.event_id.to_string(),
+        summary: event.action.clone(),
+        detail: serde_json::json!({
+            "action": event.action,
+            "outcome": event.outcome,
+            "userRole": event.user_role,
+            "resourceType": event.resource_type,
+        }),
+    }
+}
+
+/// Whether an audit event concerns the patient
+pub fn audit_concerns_patient(event: &AuditEvent, patient_id: &str) -> bool {
+    event.patient_id.is_some_and(|id| id.to_string() == patient_id) || event.resource_id.as_deref() == Some(patient_id)
+}
+
+/// Filter, order oldest first and cut out one page (pages start at 1)
+pub fn assemble(
+    events: Vec<TimelineEvent>,
+    filter: &TimelineFilter,
+    page: u32,
+    limit: u32,
+) -> PaginatedResponse<TimelineEvent> {
+    let mut events: Vec<TimelineEvent> = events.into_iter().filter(|e| filter.matches(e)).collect();
+    events.sort_by(|a, b| a.occurred_at.cmp(&b.occurred_at).then_with(|| a.source_id.cmp(&b.source_id)));
+
+    let page = page.max(1);
+    let limit = limit.max(1);
+    let total = events.len() as u32;
+    let start = ((page - 1) as usize).saturating_mul(limit as usize);
+    let data = events.into_iter().skip(start).take(limit as usize).collect();
+    PaginatedResponse {
+        data,
+        page,
+        limit,
+        total,
+        has_next_page: total > page.saturating_mul(limit),
+        has_previous_page: page > 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn event(event_type: TimelineEventType, minutes: i64, id: &str) -> TimelineEvent {
+        TimelineEvent {
+            event_type,
+            occurred_at: DateTime::parse_from_rfc3339("2026-03-01T09:00:00Z").unwrap().with_timezone(&Utc)
+                + Duration::minutes(minutes),
+            source_id: id.to_string(),
+            summary: String::new(),
+            detail: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_timeline_is_chronological_and_paginated() {
+        let events = vec![
+            event(TimelineEventType::Note, 30, "note-1"),
+            event(TimelineEventType::Appointment, 0, "appt-1"),
+            event(TimelineEventType::Transcript, 60, "t-1.json"),
+            event(TimelineEventType::Audit, 10, "audit-1"),
+        ];
+        let first = assemble(events.clone(), &TimelineFilter::default(), 1, 3);
+        let ids: Vec<&str> = first.data.iter().map(|e| e.source_id.as_str()).collect();
+        assert_eq!(ids, vec!["appt-1", "audit-1", "note-1"]);
+        assert_eq!(first.total, 4);
+        assert!(first.has_next_page);
+
+        let second = assemble(events, &TimelineFilter::default(), 2, 3);
+        assert_eq!(second.data.len(), 1);
+        assert_eq!(second.data[0].source_id, "t-1.json");
+        assert!(!second.has_next_page);
+    }
+
+    #[test]
+    fn test_filter_by_type_and_date_range() {
+        let events = vec![
+            event(TimelineEventType::Note, 0, "note-1"),
+            event(TimelineEventType::Note, 120, "note-2"),
+            event(TimelineEventType::Appointment, 60, "appt-1"),
+        ];
+        let start = events[0].occurred_at + Duration::minutes(30);
+        let filter = TimelineFilter {
+            event_types: Some([TimelineEventType::Note].into_iter().collect()),
+            start: Some(start),
+            end: None,
+        };
+        let page = assemble(events, &filter, 1, 50);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.data[0].source_id, "note-2");
+    }
+}
@@ -0,0 +1,179 @@
+// PHI Detector for PsyPsy CMS
+// Finds identifiers in free text before it leaves the practice (social posts, shared drafts):
+// Canadian SINs, Quebec RAMQ health-card numbers, phone numbers, email addresses, dates of birth
+// and street addresses. Each match carries its byte span so the UI can highlight it. Words like
+// "patient" or "diagnosis" are not identifiers and are never matched on their own.
+
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Kind of identifier found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PhiType {
+    /// Canadian Social Insurance Number
+    Sin,
+    /// Quebec health insurance (RAMQ) card number
+    RamqNumber,
+    Phone,
+    Email,
+    DateOfBirth,
+    StreetAddress,
+}
+
+/// One identifier found in the text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhiMatch {
+    pub phi_type: PhiType,
+    /// Byte offsets into the scanned text, end exclusive
+    pub start: usize,
+    pub end: usize,
+    pub confidence: f64,
+}
+
+impl PhiMatch {
+    /// The matched text
+    pub fn text<'a>(&self, content: &'a str) -> &'a str {
+        &content[self.start..self.end]
+    }
+}
+
+struct Pattern {
+    phi_type: PhiType,
+    regex: Regex,
+    confidence: f64,
+}
+
+/// Compiled identifier patterns
+pub struct PhiDetector {
+    patterns: Vec<Pattern>,
+}
+
+static DETECTOR: Lazy<PhiDetector> = Lazy::new(PhiDetector::new);
+
+/// Shared detector; the patterns are compiled once
+pub fn phi_detector() -> &'static PhiDetector {
+    &DETECTOR
+}
+
+fn compile(pattern: &str) -> Regex {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .expect("valid PHI pattern")
+}
+
+/// Luhn checksum used by SINs
+fn luhn_valid(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { let x = d * 2; if x > 9 { x - 9 } else { x } } else { d })
+        .sum();
+    sum % 10 == 0
+}
+
+impl Default for PhiDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhiDetector {
+    pub fn new() -> Self {
+        let pattern = |phi_type, regex: Regex, confidence| Pattern { phi_type, regex, confidence };
+        Self {
+            patterns: vec![
+                pattern(PhiType::Sin, compile(r"\b\d{3}[ -]?\d{3}[ -]?\d{3}\b"), 0.6),
+                // Four letters (surname and given name) then birth date and sequence digits; the
+                // letters are always upper case on the card
+                pattern(PhiType::RamqNumber, Regex::new(r"\b[A-Z]{4}[ -]?\d{4}[ -]?\d{4}\b").expect("valid PHI pattern"), 0.95),
+                pattern(PhiType::Phone, compile(r"(?:\+?1[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]?\d{3}[ .-]?\d{4}\b"), 0.8),
+                pattern(PhiType::Email, compile(r"\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b"), 0.95),
+                // Dates only count when introduced as a birth date
+                pattern(
+                    PhiType::DateOfBirth,
+                    compile(
+                        r"\b(?:DOB|D\.O\.B\.?|born(?:\s+on)?|birth\s*date|date\s+of\s+birth|n[ée]e?\s+le|date\s+de\s+naissance)\s*[:\-]?\s*(?:\d{4}[-/.]\d{1,2}[-/.]\d{1,2}|\d{1,2}[-/.]\d{1,2}[-/.]\d{2,4}|\d{1,2}\s+[A-Zéû]+\.?\s+\d{4}|[A-Z]+\.?\s+\d{1,2},?\s+\d{4})",
+                    ),
+                    0.9,
+                ),
+                pattern(
+                    PhiType::StreetAddress,
+                    compile(
+                        r"\b\d{1,5},?\s+(?:(?:rue|avenue|av\.?|boulevard|boul\.?|chemin|ch\.?|route|rang|place|montée)\s+[A-ZÀ-ÿ][\wÀ-ÿ'.-]*(?:\s+[A-ZÀ-ÿ][\wÀ-ÿ'.-]*){0,3}|(?:[A-ZÀ-ÿ][\wÀ-ÿ'.-]*\s+){1,4}(?:street|st|avenue|ave|road|rd|boulevard|blvd|drive|dr|lane|ln|court|ct|way|crescent|cres)\b\.?)",
+                    ),
+                    0.75,
+                ),
+            ],
+        }
+    }
+
+    /// Every identifier in the text, in order of position. Where matches overlap, the more
+    /// confident one is kept.
+    pub fn detect(&self, text: &str) -> Vec<PhiMatch> {
+        let mut found: Vec<PhiMatch> = Vec::new();
+        for pattern in &self.patterns {
+            for m in pattern.regex.find_iter(text) {
+                let mut confidence = pattern.confidence;
+                if pattern.phi_type == PhiType::Sin {
+                    let digits: Vec<u32> = m.as_str().chars().filter_map(|c| c.to_digit(10)).collect();
+                    // A valid checksum makes a nine-digit number far more likely to be a SIN
+                    if luhn_valid(&digits) {
+                        confidence = 0.9;
+                    }
+                }
+                found.push(PhiMatch { phi_type: pattern.phi_type, start: m.start(), end: m.end(), confidence });
+            }
+        }
+
+        found.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then(a.start.cmp(&b.start)));
+        let mut kept: Vec<PhiMatch> = Vec::new();
+        for candidate in found {
+            if kept.iter().all(|k| candidate.end <= k.start || candidate.start >= k.end) {
+                kept.push(candidate);
+            }
+        }
+        kept.sort_by_key(|m| m.start);
+        kept
+    }
+
+    pub fn contains_phi(&self, text: &str) -> bool {
+        !self.detect(text).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramq_number_is_flagged_but_awareness_post_is_not() {
+        let detector = PhiDetector::new();
+        let text = "Carte RAMQ ABCD 1234 5678 au dossier";
+        let matches = detector.detect(text);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].phi_type, PhiType::RamqNumber);
+        assert_eq!(matches[0].text(text), "ABCD 1234 5678");
+
+        assert!(detector.detect("Sharing insights about mental health awareness and patient care.").is_empty());
+        assert!(detector.detect("Our diagnosis workshop runs 2026-05-12 at 19:00.").is_empty());
+    }
+
+    #[test]
+    fn test_identifiers_are_typed_with_spans() {
+        let detector = PhiDetector::new();
+        let text = "SIN 046 454 286, call 514-555-0199 or write jane@example.com; DOB: 1984-03-07, lives at 1234 rue Saint-Denis";
+        let types: Vec<PhiType> = detector.detect(text).iter().map(|m| m.phi_type).collect();
+        assert_eq!(
+            types,
+            vec![PhiType::Sin, PhiType::Phone, PhiType::Email, PhiType::DateOfBirth, PhiType::StreetAddress]
+        );
+        let sin = &detector.detect(text)[0];
+        assert_eq!(sin.text(text), "046 454 286");
+        assert_eq!(sin.confidence, 0.9);
+    }
+}
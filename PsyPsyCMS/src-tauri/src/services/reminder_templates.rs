@@ -0,0 +1,226 @@
+// Appointment Reminder Templates for PsyPsy CMS
+// Per-clinic reminder wording with PHI-safe defaults. Templates are scanned before they are
+// saved so SMS/email reminders never disclose a specialty, diagnosis or identifier.
+
+use crate::models::NotificationMethod;
+use crate::security::validation::{detect_clinical_terms, SanitizationService};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Placeholders a template may use; none of them reveal the nature of care
+pub const ALLOWED_PLACEHOLDERS: &[&str] = &["date", "time", "clinic_name", "clinic_phone"];
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\s*([A-Za-z0-9_]+)\s*\}").expect("valid placeholder pattern"));
+
+/// Reminder wording for one clinic and channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderTemplate {
+    pub clinic_id: String,
+    pub channel: NotificationMethod,
+    /// Email subject; unused for other channels
+    pub subject: Option<String>,
+    pub body: String,
+    pub updated_by: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ReminderTemplate {
+    /// Generic wording with no specialty, professional or reason for the visit
+    pub fn phi_safe_default(clinic_id: &str, channel: NotificationMethod) -> Self {
+        let (subject, body) = match channel {
+            NotificationMethod::Email => (
+                Some("Appointment reminder / Rappel de rendez-vous".to_string()),
+                "Hello,\n\nThis is a reminder of your appointment on {date} at {time} with {clinic_name}. \
+                 To reschedule, please call {clinic_phone}.\n\n\
+                 Bonjour,\n\nCeci est un rappel de votre rendez-vous le {date} à {time} avec {clinic_name}. \
+                 Pour le déplacer, veuillez appeler le {clinic_phone}."
+                    .to_string(),
+            ),
+            _ => (
+                None,
+                "Appointment reminder: {date} {time}. {clinic_phone} / Rappel de rendez-vous : {date} {time}. {clinic_phone}"
+                    .to_string(),
+            ),
+        };
+        Self {
+            clinic_id: clinic_id.to_string(),
+            channel,
+            subject,
+            body,
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// Why template content was flagged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TemplateFindingKind {
+    /// Specialty, diagnosis or treatment wording
+    ClinicalInformation,
+    /// Identifier such as a card or insurance number
+    Identifier,
+    /// Placeholder outside [`ALLOWED_PLACEHOLDERS`]
+    DisallowedPlaceholder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateFinding {
+    pub kind: TemplateFindingKind,
+    /// `subject` or `body`
+    pub field: String,
+    pub matched_text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateComplianceReport {
+    pub compliant: bool,
+    pub findings: Vec<TemplateFinding>,
+}
+
+/// Scan template content with the shared PHI detectors
+pub fn check_template(template: &ReminderTemplate) -> TemplateComplianceReport {
+    let mut findings = Vec::new();
+    let sanitizer = match SanitizationService::new() {
+        Ok(sanitizer) => Some(sanitizer),
+        Err(e) => {
+            // Fail closed: a template that cannot be scanned is not saved
+            log::error!("Identifier detector unavailable: {}", e);
+            findings.push(TemplateFinding {
+                kind: TemplateFindingKind::Identifier,
+                field: "body".to_string(),
+                matched_text: "[identifier detector unavailable]".to_string(),
+            });
+            None
+        }
+    };
+
+    let fields = [("subject", template.subject.as_deref()), ("body", Some(template.body.as_str()))];
+    for (field, text) in fields {
+        let Some(text) = text else { continue };
+
+        for detection in detect_clinical_terms(text) {
+            findings.push(TemplateFinding {
+                kind: TemplateFindingKind::ClinicalInformation,
+                field: field.to_string(),
+                matched_text: detection.matched_text,
+            });
+        }
+        if let Some(sanitizer) = &sanitizer {
+            for detection in sanitizer.detect_phi(text) {
+                findings.push(TemplateFinding {
+                    kind: TemplateFindingKind::Identifier,
+                    field: field.to_string(),
+                    matched_text: detection.matched_text,
+                });
+            }
+        }
+        for capture in PLACEHOLDER.captures_iter(text) {
+            if !ALLOWED_PLACEHOLDERS.contains(&&capture[1]) {
+                findings.push(TemplateFinding {
+                    kind: TemplateFindingKind::DisallowedPlaceholder,
+                    field: field.to_string(),
+                    matched_text: capture[0].to_string(),
+                });
+            }
+        }
+    }
+
+    TemplateComplianceReport {
+        compliant: findings.is_empty(),
+        findings,
+    }
+}
+
+/// Fill allowed placeholders; anything else is left out of the rendered text
+pub fn render_template(text: &str, values: &HashMap<String, String>) -> String {
+    PLACEHOLDER
+        .replace_all(text, |caps: &regex::Captures| {
+            let name = &caps[1];
+            if ALLOWED_PLACEHOLDERS.contains(&name) {
+                values.get(name).cloned().unwrap_or_default()
+            } else {
+                String::new()
+            }
+        })
+        .into_owned()
+}
+
+/// Configured templates keyed by clinic and channel
+#[derive(Debug, Clone, Default)]
+pub struct ReminderTemplateState(pub Arc<RwLock<HashMap<(String, NotificationMethod), ReminderTemplate>>>);
+
+impl ReminderTemplateState {
+    /// Configured template, or the PHI-safe default
+    pub fn get(&self, clinic_id: &str, channel: NotificationMethod) -> ReminderTemplate {
+        self.0
+            .read()
+            .unwrap()
+            .get(&(clinic_id.to_string(), channel.clone()))
+            .cloned()
+            .unwrap_or_else(|| ReminderTemplate::phi_safe_default(clinic_id, channel))
+    }
+
+    /// Store a template only if it passes the compliance check
+    pub fn save(&self, template: ReminderTemplate) -> Result<ReminderTemplate, TemplateComplianceReport> {
+        let report = check_template(&template);
+        if !report.compliant {
+            return Err(report);
+        }
+        self.0
+            .write()
+            .unwrap()
+            .insert((template.clinic_id.clone(), template.channel.clone()), template.clone());
+        Ok(template)
+    }
+
+    /// Drop a clinic's template so the default applies again
+    pub fn reset(&self, clinic_id: &str, channel: NotificationMethod) {
+        self.0.write().unwrap().remove(&(clinic_id.to_string(), channel));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_phi_safe() {
+        for channel in [NotificationMethod::Email, NotificationMethod::Sms, NotificationMethod::PushNotification] {
+            let template = ReminderTemplate::phi_safe_default("clinic-1", channel);
+            assert!(check_template(&template).compliant, "{:?}", template.channel);
+        }
+    }
+
+    #[test]
+    fn test_specialty_and_diagnosis_are_flagged() {
+        let state = ReminderTemplateState::default();
+        let mut template = ReminderTemplate::phi_safe_default("clinic-1", NotificationMethod::Sms);
+        template.body = "Reminder: your psychiatry appointment for depression on {date} ({service_type})".to_string();
+
+        let report = state.save(template).unwrap_err();
+        let kinds: Vec<_> = report.findings.iter().map(|f| f.kind.clone()).collect();
+        assert_eq!(kinds.iter().filter(|k| **k == TemplateFindingKind::ClinicalInformation).count(), 2);
+        assert!(kinds.contains(&TemplateFindingKind::DisallowedPlaceholder));
+
+        // Rejected templates never replace the default
+        assert!(check_template(&state.get("clinic-1", NotificationMethod::Sms)).compliant);
+    }
+
+    #[test]
+    fn test_render_only_fills_allowed_placeholders() {
+        let values = HashMap::from([
+            ("date".to_string(), "2025-03-04".to_string()),
+            ("service_type".to_string(), "Psychotherapy".to_string()),
+        ]);
+        assert_eq!(render_template("On {date} {service_type}", &values), "On 2025-03-04 ");
+    }
+}
@@ -0,0 +1,938 @@
+// Schedule Conflict Detection for PsyPsy CMS
+// Scans a professional's appointments (recurring series expanded) for overlaps, double-bookings,
+// sessions outside working hours and back-to-back sessions without the configured buffer or
+// travel time, and finds open slots under the same rules. Also expands recurrence rules into the
+// individual sessions of a new series.
+
+use crate::models::appointment::{resolve_local, series_local_start};
+use crate::models::{Appointment, AppointmentStatus, GeoPoint, RecurrenceRule, WorkingHours};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Travel time between in-person sessions at different locations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TravelRule {
+    /// Average door-to-door speed used to estimate travel time
+    pub average_speed_kmh: f64,
+    /// Locations closer than this count as the same place
+    pub same_location_radius_km: f64,
+    /// Least travel time allowed between different locations
+    pub minimum_minutes: u32,
+    /// Cap on estimated travel time
+    pub maximum_minutes: u32,
+}
+
+impl Default for TravelRule {
+    fn default() -> Self {
+        Self {
+            average_speed_kmh: 40.0,
+            same_location_radius_km: 0.5,
+            minimum_minutes: 10,
+            maximum_minutes: 120,
+        }
+    }
+}
+
+impl TravelRule {
+    /// Estimated travel time; zero for the same location
+    pub fn travel_time(&self, from: &GeoPoint, to: &GeoPoint) -> Duration {
+        let distance = haversine_km(from, to);
+        if distance <= self.same_location_radius_km || self.average_speed_kmh <= 0.0 {
+            return Duration::zero();
+        }
+        let minutes = (distance / self.average_speed_kmh * 60.0).ceil() as u32;
+        Duration::minutes(minutes.clamp(self.minimum_minutes, self.cap_minutes()) as i64)
+    }
+
+    fn cap_minutes(&self) -> u32 {
+        self.maximum_minutes.max(self.minimum_minutes)
+    }
+}
+
+/// Great-circle distance between two points
+pub fn haversine_km(a: &GeoPoint, b: &GeoPoint) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+    let (lat1, lat2) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.longitude - a.longitude).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// Gap rules for one professional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfessionalScheduleRules {
+    pub buffer_minutes: u32,
+    /// Travel time on top of the buffer between different locations; `None` for office-only practice
+    pub travel: Option<TravelRule>,
+}
+
+impl ProfessionalScheduleRules {
+    fn buffer(&self) -> Duration {
+        Duration::minutes(self.buffer_minutes as i64)
+    }
+
+    /// Travel needed between two sessions; missing locations fall back to no travel
+    pub fn travel_between(&self, from: Option<&GeoPoint>, to: Option<&GeoPoint>) -> Duration {
+        match (&self.travel, from, to) {
+            (Some(rule), Some(from), Some(to)) => rule.travel_time(from, to),
+            _ => Duration::zero(),
+        }
+    }
+
+    /// Longest gap any pair of sessions can require
+    fn max_gap(&self) -> Duration {
+        self.buffer() + self.travel.as_ref().map_or(Duration::zero(), |t| Duration::minutes(t.cap_minutes() as i64))
+    }
+}
+
+/// Scheduling rules shared by conflict checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulingConfig {
+    /// Clinic hours, used for professionals without their own
+    pub working_hours: WorkingHours,
+    /// Minimum gap between consecutive sessions; 0 disables the check
+    pub buffer_minutes: u32,
+    /// Shortest session a free slot can be offered for
+    #[serde(default = "default_minimum_session_minutes")]
+    pub minimum_session_minutes: u32,
+    /// Spacing between the start times free slots are offered at
+    #[serde(default = "default_slot_step_minutes")]
+    pub slot_step_minutes: u32,
+    /// Per-professional overrides of the buffer, with optional travel rules
+    #[serde(default)]
+    pub professional_rules: HashMap<String, ProfessionalScheduleRules>,
+    /// IANA zone of the clinic; recurring series step on its wall clock
+    #[serde(default = "default_clinic_timezone")]
+    pub timezone: String,
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            working_hours: WorkingHours::default(),
+            buffer_minutes: 0,
+            minimum_session_minutes: default_minimum_session_minutes(),
+            slot_step_minutes: default_slot_step_minutes(),
+            professional_rules: HashMap::new(),
+            timezone: default_clinic_timezone(),
+        }
+    }
+}
+
+fn default_minimum_session_minutes() -> u32 {
+    30
+}
+
+fn default_slot_step_minutes() -> u32 {
+    15
+}
+
+fn default_clinic_timezone() -> String {
+    DEFAULT_CLINIC_TIMEZONE.to_string()
+}
+
+impl SchedulingConfig {
+    /// Defaults with `SCHEDULE_BUFFER_MINUTES`, `SCHEDULE_MIN_SESSION_MINUTES`,
+    /// `SCHEDULE_SLOT_STEP_MINUTES`, `SCHEDULE_PROFESSIONAL_RULES` (JSON map of professional ID
+    /// to rules) and `CLINIC_TIMEZONE` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(buffer) = std::env::var("SCHEDULE_BUFFER_MINUTES").ok().and_then(|v| v.parse().ok()) {
+            config.buffer_minutes = buffer;
+        }
+        if let Some(minutes) = std::env::var("SCHEDULE_MIN_SESSION_MINUTES").ok().and_then(|v| v.parse().ok()).filter(|m| *m > 0) {
+            config.minimum_session_minutes = minutes;
+        }
+        if let Some(minutes) = std::env::var("SCHEDULE_SLOT_STEP_MINUTES").ok().and_then(|v| v.parse().ok()).filter(|m| *m > 0) {
+            config.slot_step_minutes = minutes;
+        }
+        if let Ok(rules) = std::env::var("SCHEDULE_PROFESSIONAL_RULES") {
+            match serde_json::from_str(&rules) {
+                Ok(rules) => config.professional_rules = rules,
+                Err(e) => log::warn!("Ignoring invalid SCHEDULE_PROFESSIONAL_RULES: {}", e),
+            }
+        }
+        if let Ok(name) = std::env::var("CLINIC_TIMEZONE") {
+            match clinic_timezone(Some(&name)) {
+                Ok(tz) => config.timezone = tz.name().to_string(),
+                Err(e) => log::warn!("Ignoring invalid CLINIC_TIMEZONE: {}", e),
+            }
+        }
+        config
+    }
+
+    /// The clinic's zone, or the default if the configured name is unknown
+    pub fn clinic_tz(&self) -> chrono_tz::Tz {
+        clinic_timezone(Some(&self.timezone)).unwrap_or(chrono_tz::America::Montreal)
+    }
+
+    /// Rules for a professional, falling back to the practice-wide buffer
+    pub fn rules_for(&self, professional_id: &str) -> ProfessionalScheduleRules {
+        self.professional_rules.get(professional_id).cloned().unwrap_or(ProfessionalScheduleRules {
+            buffer_minutes: self.buffer_minutes,
+            travel: None,
+        })
+    }
+}
+
+/// Whether two half-open intervals overlap
+pub fn intervals_overlap(a_start: DateTime<Utc>, a_end: DateTime<Utc>, b_start: DateTime<Utc>, b_end: DateTime<Utc>) -> bool {
+    a_start < b_end && b_start < a_end
+}
+
+/// One occurrence of an appointment on the calendar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledOccurrence {
+    pub appointment_id: String,
+    pub client_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Part of a recurring series
+    pub recurring: bool,
+    /// Visit location; kept server-side since it may be a client's home
+    #[serde(skip)]
+    pub location: Option<GeoPoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Two sessions booked into the same slot
+    DoubleBooking,
+    /// Two sessions partially overlap
+    Overlap,
+    /// Session falls outside working hours
+    OutsideWorkingHours,
+    /// Back-to-back sessions closer than the configured buffer
+    NoBuffer,
+    /// Not enough time to travel between two locations (plus buffer)
+    InsufficientTravelTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleConflict {
+    pub kind: ConflictKind,
+    pub first: ScheduledOccurrence,
+    /// Other occurrence involved, for pairwise conflicts
+    pub second: Option<ScheduledOccurrence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleConflictReport {
+    pub professional_id: String,
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+    pub occurrences_scanned: usize,
+    pub buffer_minutes: u32,
+    /// Whether travel time between locations was checked
+    pub travel_rule_applied: bool,
+    pub conflicts: Vec<ScheduleConflict>,
+}
+
+/// A professional's active occurrences in `[range_start, range_end)`, sorted by start
+fn professional_occurrences<Tz: TimeZone>(
+    appointments: &[Appointment],
+    professional_id: &str,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+    tz: &Tz,
+) -> Vec<ScheduledOccurrence> {
+    let mut occurrences: Vec<ScheduledOccurrence> = appointments
+        .iter()
+        .filter(|a| a.assigned_professional.as_deref() == Some(professional_id))
+        .filter(|a| !matches!(a.status, AppointmentStatus::Cancelled | AppointmentStatus::NoShow))
+        .flat_map(|a| {
+            a.occurrences_between(range_start, range_end, tz)
+                .into_iter()
+                .map(move |(start, end)| ScheduledOccurrence {
+                    appointment_id: a.object_id.clone(),
+                    client_id: a.client_ptr.clone(),
+                    start,
+                    end,
+                    recurring: a.recurrence.is_some(),
+                    location: a.visit_location().cloned(),
+                })
+        })
+        .collect();
+    occurrences.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.appointment_id.cmp(&b.appointment_id)));
+    occurrences
+}
+
+/// Every conflict among a professional's active appointments in `[range_start, range_end)`.
+/// Sessions are checked against `hours` (the professional's own, or the clinic's) in `tz`.
+#[allow(clippy::too_many_arguments)]
+pub fn find_schedule_conflicts<Tz: TimeZone>(
+    appointments: &[Appointment],
+    professional_id: &str,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+    hours: &WorkingHours,
+    config: &SchedulingConfig,
+    tz: &Tz,
+) -> ScheduleConflictReport {
+    let occurrences = professional_occurrences(appointments, professional_id, range_start, range_end, tz);
+    let rules = config.rules_for(professional_id);
+    let max_gap = rules.max_gap();
+    let mut conflicts = Vec::new();
+
+    for (i, first) in occurrences.iter().enumerate() {
+        if !hours.contains(first.start, first.end, tz) {
+            conflicts.push(ScheduleConflict {
+                kind: ConflictKind::OutsideWorkingHours,
+                first: first.clone(),
+                second: None,
+            });
+        }
+
+        // Sorted by start, so only later occurrences starting before this one ends (plus the widest gap) matter
+        for second in occurrences[i + 1..].iter().take_while(|o| o.start < first.end + max_gap) {
+            let travel = rules.travel_between(first.location.as_ref(), second.location.as_ref());
+            let kind = if first.start == second.start {
+                ConflictKind::DoubleBooking
+            } else if intervals_overlap(first.start, first.end, second.start, second.end) {
+                ConflictKind::Overlap
+            } else if second.start >= first.end + rules.buffer() + travel {
+                continue;
+            } else if travel > Duration::zero() && second.start >= first.end + rules.buffer() {
+                ConflictKind::InsufficientTravelTime
+            } else {
+                ConflictKind::NoBuffer
+            };
+            conflicts.push(ScheduleConflict {
+                kind,
+                first: first.clone(),
+                second: Some(second.clone()),
+            });
+        }
+    }
+
+    ScheduleConflictReport {
+        professional_id: professional_id.to_string(),
+        range_start,
+        range_end,
+        occurrences_scanned: occurrences.len(),
+        buffer_minutes: rules.buffer_minutes,
+        travel_rule_applied: rules.travel.is_some(),
+        conflicts,
+    }
+}
+
+/// Span the professional's other bookings must be looked up in to check sessions running from
+/// `first_start` to `last_end`, widened by their buffer and longest travel time
+pub fn booking_window(
+    config: &SchedulingConfig,
+    professional_id: &str,
+    first_start: DateTime<Utc>,
+    last_end: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let max_gap = config.rules_for(professional_id).max_gap();
+    (first_start - max_gap, last_end + max_gap)
+}
+
+/// A booking refused because it collides with the professional's other sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookingConflict {
+    pub professional_id: String,
+    pub appointment_ids: Vec<String>,
+}
+
+impl std::fmt::Display for BookingConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Conflict: professional {} is already booked at this time (appointments {})",
+            self.professional_id,
+            self.appointment_ids.join(", ")
+        )
+    }
+}
+
+/// Check that `candidate` (every occurrence, for a series) fits around its professional's other
+/// active sessions with their buffer and travel time. Back-to-back sessions are fine when no
+/// buffer is configured. Unassigned or cancelled appointments always pass. Series are expanded
+/// in the clinic's zone.
+pub fn check_booking(appointments: &[Appointment], candidate: &Appointment, config: &SchedulingConfig) -> Result<(), BookingConflict> {
+    let Some(professional_id) = candidate.assigned_professional.as_deref() else {
+        return Ok(());
+    };
+    if matches!(candidate.status, AppointmentStatus::Cancelled | AppointmentStatus::NoShow) {
+        return Ok(());
+    }
+    let tz = config.clinic_tz();
+    let wanted = candidate.occurrences_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC, &tz);
+    let (Some(first), Some(last)) = (wanted.first(), wanted.last()) else {
+        return Ok(());
+    };
+
+    let rules = config.rules_for(professional_id);
+    let location = candidate.visit_location();
+    let (range_start, range_end) = booking_window(config, professional_id, first.0, last.1);
+    let existing: Vec<ScheduledOccurrence> =
+        professional_occurrences(appointments, professional_id, range_start, range_end, &tz)
+            .into_iter()
+            .filter(|o| o.appointment_id != candidate.object_id)
+            .collect();
+
+    let mut conflicting: Vec<String> = Vec::new();
+    for (start, end) in wanted {
+        for o in &existing {
+            let clear = if o.end <= start {
+                start >= o.end + rules.buffer() + rules.travel_between(o.location.as_ref(), location)
+            } else if o.start >= end {
+                o.start >= end + rules.buffer() + rules.travel_between(location, o.location.as_ref())
+            } else {
+                false
+            };
+            if !clear && !conflicting.contains(&o.appointment_id) {
+                conflicting.push(o.appointment_id.clone());
+            }
+        }
+    }
+
+    if conflicting.is_empty() {
+        Ok(())
+    } else {
+        conflicting.sort();
+        Err(BookingConflict { professional_id: professional_id.to_string(), appointment_ids: conflicting })
+    }
+}
+
+/// Upper bound on appointments generated from one recurrence rule
+const MAX_GENERATED_SERIES: u32 = 260;
+
+/// Easter Sunday (anonymous Gregorian algorithm)
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let (a, b, c) = (year % 19, year / 100, year % 100);
+    let (d, e) = (b / 4, b % 4);
+    let g = (8 * b + 13) / 25;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let (i, k) = (c / 4, c % 4);
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 19 * l) / 433;
+    let month = (h + l - 7 * m + 90) / 25;
+    let day = (h + l - 7 * m + 33 * month + 19) % 32;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// Statutory holidays in Quebec for `year`; Good Friday stands in for the Easter holiday
+pub fn quebec_statutory_holidays(year: i32) -> Vec<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day);
+    let nth_monday = |month, n| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n);
+    // National Patriots' Day is the Monday before May 25
+    let patriots = date(5, 24).map(|d| d - Duration::days(d.weekday().num_days_from_monday() as i64));
+    let mut holidays: Vec<NaiveDate> = [
+        date(1, 1),
+        easter_sunday(year).map(|d| d - Duration::days(2)),
+        patriots,
+        date(6, 24),
+        date(7, 1),
+        nth_monday(9, 1),
+        nth_monday(10, 2),
+        date(12, 25),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    holidays.sort();
+    holidays
+}
+
+/// Why an occurrence of a new series was not booked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum SkipReason {
+    ExcludedDate,
+    StatutoryHoliday,
+    /// Clashes with the professional's other sessions
+    #[serde(rename_all = "camelCase")]
+    Conflict { appointment_ids: Vec<String> },
+}
+
+/// An occurrence of a series left out, with the local date it would have fallen on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedOccurrence {
+    pub local_date: NaiveDate,
+    pub start: Option<DateTime<Utc>>,
+    pub reason: SkipReason,
+}
+
+/// Occurrence starts of a series before conflict checks
+#[derive(Debug, Clone, Default)]
+pub struct SeriesPlan {
+    pub starts: Vec<DateTime<Utc>>,
+    pub skipped: Vec<SkippedOccurrence>,
+}
+
+/// Expand a recurrence rule from `first` into occurrence starts. Steps are taken on the clinic's
+/// wall clock, so a weekly 10:00 session stays at 10:00 local across DST changes, and excluded
+/// dates and holidays are matched against the local date. `count` includes skipped occurrences.
+pub fn expand_series<Tz: TimeZone>(rule: &RecurrenceRule, first: DateTime<Utc>, tz: &Tz) -> SeriesPlan {
+    let first_local = first.with_timezone(tz).naive_local();
+    let limit = rule.count.unwrap_or(MAX_GENERATED_SERIES).min(MAX_GENERATED_SERIES);
+    let mut plan = SeriesPlan::default();
+
+    for n in 0..limit {
+        let Some(local) = series_local_start(rule, first_local, n) else { break };
+        let Some(start) = resolve_local(tz, local) else { continue };
+        if rule.until.is_some_and(|until| start > until) {
+            break;
+        }
+
+        let date = local.date();
+        let reason = if rule.excluded_dates.contains(&date) {
+            Some(SkipReason::ExcludedDate)
+        } else if rule.skip_statutory_holidays && quebec_statutory_holidays(date.year()).contains(&date) {
+            Some(SkipReason::StatutoryHoliday)
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => plan.skipped.push(SkippedOccurrence { local_date: date, start: Some(start), reason }),
+            None => plan.starts.push(start),
+        }
+    }
+    plan
+}
+
+/// Zone clinic days are counted in when a command is not given one
+pub const DEFAULT_CLINIC_TIMEZONE: &str = "America/Montreal";
+
+/// IANA timezone named by `name`, or the clinic default
+pub fn clinic_timezone(name: Option<&str>) -> Result<chrono_tz::Tz, String> {
+    let name = name.map(str::trim).filter(|n| !n.is_empty()).unwrap_or(DEFAULT_CLINIC_TIMEZONE);
+    name.parse().map_err(|_| format!("Unknown timezone: {}", name))
+}
+
+/// Instants `[start, end)` covering the local days `from..=to` in `tz`; a day may be 23 or 25
+/// hours long around DST changes
+pub fn local_day_bounds<Tz: TimeZone>(tz: &Tz, from: NaiveDate, to: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let start = resolve_local(tz, from.and_time(NaiveTime::MIN))?;
+    let end = resolve_local(tz, to.succ_opt()?.and_time(NaiveTime::MIN))?;
+    Some((start, end))
+}
+
+/// Appointments with an occurrence starting on one of the local days `from..=to` in `tz`. Stored
+/// UTC times are converted to the zone for the comparison, sorted by first start in range.
+pub fn appointments_on_local_days<Tz: TimeZone>(
+    appointments: &[Appointment],
+    tz: &Tz,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<Appointment> {
+    let Some((range_start, range_end)) = local_day_bounds(tz, from, to) else {
+        return Vec::new();
+    };
+    let mut matching: Vec<(DateTime<Utc>, &Appointment)> = appointments
+        .iter()
+        .filter_map(|a| {
+            a.occurrences_between(range_start, range_end, tz)
+                .into_iter()
+                .map(|(start, _)| start)
+                .find(|start| (from..=to).contains(&start.with_timezone(tz).date_naive()))
+                .map(|start| (start, a))
+        })
+        .collect();
+    matching.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.object_id.cmp(&b.1.object_id)));
+    matching.into_iter().map(|(_, a)| a.clone()).collect()
+}
+
+/// Bookable slot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableSlot {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Open slots of `duration` on `date` (clinic-local), stepping by `step`, inside `hours` and
+/// respecting the professional's buffer and travel rules around existing sessions. A day
+/// without hours has no slots.
+#[allow(clippy::too_many_arguments)]
+pub fn find_available_slots<Tz: TimeZone>(
+    appointments: &[Appointment],
+    professional_id: &str,
+    date: NaiveDate,
+    duration: Duration,
+    step: Duration,
+    location: Option<&GeoPoint>,
+    hours: &WorkingHours,
+    config: &SchedulingConfig,
+    tz: &Tz,
+) -> Vec<AvailableSlot> {
+    let ranges = hours.ranges_on(date);
+    if duration <= Duration::zero() || step <= Duration::zero() {
+        return Vec::new();
+    }
+    let rules = config.rules_for(professional_id);
+    let to_utc = |time: NaiveTime| tz.from_local_datetime(&date.and_time(time)).earliest().map(|t| t.with_timezone(&Utc));
+
+    let mut slots = Vec::new();
+    for range in ranges {
+        let (Some(day_start), Some(day_end)) = (to_utc(range.start), to_utc(range.end)) else {
+            continue;
+        };
+        let nearby = professional_occurrences(appointments, professional_id, day_start - rules.max_gap(), day_end + rules.max_gap(), tz);
+
+        let mut start = day_start;
+        while start + duration <= day_end {
+            let end = start + duration;
+            let clear = nearby.iter().all(|o| {
+                if o.end <= start {
+                    start >= o.end + rules.buffer() + rules.travel_between(o.location.as_ref(), location)
+                } else if o.start >= end {
+                    o.start >= end + rules.buffer() + rules.travel_between(location, o.location.as_ref())
+                } else {
+                    false
+                }
+            });
+            if clear {
+                slots.push(AvailableSlot { start, end });
+            }
+            start += step;
+        }
+    }
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        CreateAppointmentRequest, GenderPreference, MeetingPreference, RecurrenceFrequency, RecurrenceRule, TimeRange, WorkingHoursException,
+    };
+
+    fn appointment(id: &str, start: &str, minutes: i32) -> Appointment {
+        let mut appointment = Appointment::from_request(
+            CreateAppointmentRequest {
+                client_id: format!("client-{}", id),
+                prof_types: vec![],
+                service_type: 0,
+                subcategories: vec![],
+                gender_preference: GenderPreference::None,
+                language_preference: 0,
+                meeting_preference: MeetingPreference::InPerson,
+                availability: vec![],
+                preferred_date_time: Some(start.to_string()),
+                session_duration: Some(minutes),
+            },
+            id.to_string(),
+        );
+        appointment.assigned_professional = Some("prof-1".to_string());
+        appointment.status = AppointmentStatus::Confirmed;
+        appointment
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    fn kinds(report: &ScheduleConflictReport) -> Vec<ConflictKind> {
+        report.conflicts.iter().map(|c| c.kind).collect()
+    }
+
+    #[test]
+    fn test_overlap_double_booking_and_hours() {
+        // 2024-06-10 is a Monday
+        let appointments = vec![
+            appointment("a", "2024-06-10T10:00:00Z", 60),
+            appointment("b", "2024-06-10T10:30:00Z", 60),
+            appointment("c", "2024-06-10T14:00:00Z", 50),
+            appointment("d", "2024-06-10T14:00:00Z", 50),
+            appointment("e", "2024-06-10T22:00:00Z", 50),
+        ];
+        let report = find_schedule_conflicts(
+            &appointments,
+            "prof-1",
+            utc("2024-06-10T00:00:00Z"),
+            utc("2024-06-11T00:00:00Z"),
+            &WorkingHours::default(),
+            &SchedulingConfig::default(),
+            &Utc,
+        );
+        assert_eq!(report.occurrences_scanned, 5);
+        assert_eq!(kinds(&report), vec![ConflictKind::Overlap, ConflictKind::DoubleBooking, ConflictKind::OutsideWorkingHours]);
+    }
+
+    #[test]
+    fn test_buffer_flags_back_to_back_sessions() {
+        let appointments = vec![
+            appointment("a", "2024-06-10T10:00:00Z", 50),
+            appointment("b", "2024-06-10T10:50:00Z", 50),
+        ];
+        let range = (utc("2024-06-10T00:00:00Z"), utc("2024-06-11T00:00:00Z"));
+
+        let without_buffer = find_schedule_conflicts(&appointments, "prof-1", range.0, range.1, &WorkingHours::default(), &SchedulingConfig::default(), &Utc);
+        assert!(without_buffer.conflicts.is_empty());
+
+        let config = SchedulingConfig { buffer_minutes: 10, ..SchedulingConfig::default() };
+        let with_buffer = find_schedule_conflicts(&appointments, "prof-1", range.0, range.1, &WorkingHours::default(), &config, &Utc);
+        assert_eq!(kinds(&with_buffer), vec![ConflictKind::NoBuffer]);
+    }
+
+    #[test]
+    fn test_recurring_series_occurrences_conflict_individually() {
+        let mut weekly = appointment("series", "2024-06-03T10:00:00Z", 60);
+        weekly.recurrence = Some(RecurrenceRule {
+            frequency: RecurrenceFrequency::Weekly,
+            interval: 1,
+            count: Some(4),
+            until: None,
+            excluded_dates: vec![chrono::NaiveDate::from_ymd_opt(2024, 6, 17).unwrap()],
+            skip_statutory_holidays: false,
+        });
+        let one_off_clash = appointment("clash", "2024-06-10T10:15:00Z", 30);
+        let excluded_week = appointment("free", "2024-06-17T10:00:00Z", 60);
+
+        let report = find_schedule_conflicts(
+            &[weekly, one_off_clash, excluded_week],
+            "prof-1",
+            utc("2024-06-01T00:00:00Z"),
+            utc("2024-07-01T00:00:00Z"),
+            &WorkingHours::default(),
+            &SchedulingConfig::default(),
+            &Utc,
+        );
+
+        // Occurrences on 06-03, 06-10, 06-24 plus the two one-offs
+        assert_eq!(report.occurrences_scanned, 5);
+        assert_eq!(report.conflicts.len(), 1);
+        let conflict = &report.conflicts[0];
+        assert_eq!(conflict.kind, ConflictKind::Overlap);
+        assert!(conflict.first.recurring);
+        assert_eq!(conflict.first.start, utc("2024-06-10T10:00:00Z"));
+    }
+
+    fn at(id: &str, start: &str, latitude: f64, longitude: f64) -> Appointment {
+        let mut appointment = appointment(id, start, 50);
+        appointment.location = Some(GeoPoint { latitude, longitude });
+        appointment
+    }
+
+    fn travelling_config() -> SchedulingConfig {
+        let rules = ProfessionalScheduleRules { buffer_minutes: 10, travel: Some(TravelRule::default()) };
+        SchedulingConfig {
+            professional_rules: [("prof-1".to_string(), rules)].into_iter().collect(),
+            ..SchedulingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_same_location_needs_buffer_only() {
+        // Both at the same clinic, 10 minutes apart
+        let appointments = vec![
+            at("a", "2024-06-10T10:00:00Z", 45.5017, -73.5673),
+            at("b", "2024-06-10T11:00:00Z", 45.5018, -73.5672),
+        ];
+        let range = (utc("2024-06-10T00:00:00Z"), utc("2024-06-11T00:00:00Z"));
+        let report = find_schedule_conflicts(&appointments, "prof-1", range.0, range.1, &WorkingHours::default(), &travelling_config(), &Utc);
+        assert!(report.conflicts.is_empty());
+        assert!(report.travel_rule_applied);
+
+        // Missing locations fall back to the buffer alone
+        let mut unknown = appointments.clone();
+        unknown[1].location = None;
+        let report = find_schedule_conflicts(&unknown, "prof-1", range.0, range.1, &WorkingHours::default(), &travelling_config(), &Utc);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_different_locations_need_buffer_and_travel() {
+        // Montreal to Laval (~15 km): buffer 10 + ~25 minutes travel
+        let appointments = vec![
+            at("a", "2024-06-10T10:00:00Z", 45.5017, -73.5673),
+            at("b", "2024-06-10T11:00:00Z", 45.6066, -73.7124),
+        ];
+        let range = (utc("2024-06-10T00:00:00Z"), utc("2024-06-11T00:00:00Z"));
+        let report = find_schedule_conflicts(&appointments, "prof-1", range.0, range.1, &WorkingHours::default(), &travelling_config(), &Utc);
+        assert_eq!(kinds(&report), vec![ConflictKind::InsufficientTravelTime]);
+
+        // Slots after the morning session leave room for the trip to the other location
+        let date = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let laval = GeoPoint { latitude: 45.6066, longitude: -73.7124 };
+        let slots = find_available_slots(
+            &appointments[..1],
+            "prof-1",
+            date,
+            Duration::minutes(50),
+            Duration::minutes(15),
+            Some(&laval),
+            &WorkingHours::default(),
+            &travelling_config(),
+            &Utc,
+        );
+        let after_first = slots.iter().find(|s| s.start > utc("2024-06-10T10:00:00Z")).unwrap();
+        assert_eq!(after_first.start, utc("2024-06-10T11:30:00Z"));
+
+        let same_place = GeoPoint { latitude: 45.5017, longitude: -73.5673 };
+        let slots = find_available_slots(
+            &appointments[..1],
+            "prof-1",
+            date,
+            Duration::minutes(50),
+            Duration::minutes(15),
+            Some(&same_place),
+            &WorkingHours::default(),
+            &travelling_config(),
+            &Utc,
+        );
+        let after_first = slots.iter().find(|s| s.start > utc("2024-06-10T10:00:00Z")).unwrap();
+        assert_eq!(after_first.start, utc("2024-06-10T11:00:00Z"));
+    }
+
+    #[test]
+    fn test_professional_hours_on_a_partially_booked_day() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let hours = WorkingHours {
+            weekly: HashMap::from([(Weekday::Mon, vec![TimeRange { start: time(9, 0), end: time(12, 30) }])]),
+            exceptions: vec![WorkingHoursException {
+                date: NaiveDate::from_ymd_opt(2024, 6, 17).unwrap(),
+                ranges: Vec::new(),
+                reason: Some("Training".to_string()),
+            }],
+        };
+        let config = SchedulingConfig { buffer_minutes: 10, ..Default::default() };
+        let appointments = vec![
+            appointment("a", "2024-06-10T09:30:00Z", 50),
+            appointment("b", "2024-06-10T11:00:00Z", 30),
+        ];
+        let slots = |date| {
+            find_available_slots(&appointments, "prof-1", date, Duration::minutes(30), Duration::minutes(15), None, &hours, &config, &Utc)
+        };
+
+        // 9:00 leaves no buffer before 9:30, and 10:20-11:00 is only 20 minutes once buffered
+        let starts: Vec<DateTime<Utc>> = slots(monday).iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![utc("2024-06-10T11:45:00Z"), utc("2024-06-10T12:00:00Z")]);
+
+        // No weekly hours on Tuesday, and the following Monday is an exception day off
+        assert!(slots(NaiveDate::from_ymd_opt(2024, 6, 11).unwrap()).is_empty());
+        assert!(slots(NaiveDate::from_ymd_opt(2024, 6, 17).unwrap()).is_empty());
+
+        // 13:00 is inside clinic hours but after this professional stops for the day
+        let afternoon = [appointment("c", "2024-06-10T13:00:00Z", 30)];
+        let range = (utc("2024-06-10T00:00:00Z"), utc("2024-06-11T00:00:00Z"));
+        let report = find_schedule_conflicts(&afternoon, "prof-1", range.0, range.1, &hours, &config, &Utc);
+        assert_eq!(kinds(&report), vec![ConflictKind::OutsideWorkingHours]);
+        assert!(find_schedule_conflicts(&afternoon, "prof-1", range.0, range.1, &WorkingHours::default(), &config, &Utc).conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_booking_allows_back_to_back_but_not_overlaps() {
+        let existing = vec![appointment("a", "2024-06-10T10:00:00Z", 60)];
+        let config = SchedulingConfig::default();
+
+        // Exact boundary on either side
+        assert!(check_booking(&existing, &appointment("new", "2024-06-10T11:00:00Z", 50), &config).is_ok());
+        assert!(check_booking(&existing, &appointment("new", "2024-06-10T09:10:00Z", 50), &config).is_ok());
+
+        let partial = check_booking(&existing, &appointment("new", "2024-06-10T10:30:00Z", 60), &config).unwrap_err();
+        assert_eq!(partial.appointment_ids, vec!["a".to_string()]);
+        let contained = check_booking(&existing, &appointment("new", "2024-06-10T10:15:00Z", 30), &config).unwrap_err();
+        assert_eq!(contained.appointment_ids, vec!["a".to_string()]);
+        let containing = check_booking(&existing, &appointment("new", "2024-06-10T09:00:00Z", 180), &config).unwrap_err();
+        assert_eq!(containing.appointment_ids, vec!["a".to_string()]);
+
+        // Rescheduling an appointment onto its own old slot is not a conflict
+        assert!(check_booking(&existing, &appointment("a", "2024-06-10T10:30:00Z", 60), &config).is_ok());
+    }
+
+    #[test]
+    fn test_booking_respects_buffer() {
+        let existing = vec![
+            appointment("a", "2024-06-10T10:00:00Z", 60),
+            appointment("b", "2024-06-10T12:00:00Z", 60),
+        ];
+        let config = SchedulingConfig { buffer_minutes: 10, ..SchedulingConfig::default() };
+
+        let squeezed = check_booking(&existing, &appointment("new", "2024-06-10T11:00:00Z", 60), &config).unwrap_err();
+        assert_eq!(squeezed.appointment_ids, vec!["a".to_string(), "b".to_string()]);
+        assert!(check_booking(&existing, &appointment("new", "2024-06-10T11:10:00Z", 40), &config).is_ok());
+    }
+
+    #[test]
+    fn test_series_skips_holidays_and_keeps_wall_clock_time() {
+        // Mondays at 10:00 Eastern standard time, spanning Patriots' Day and Saint-Jean-Baptiste
+        let eastern = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+        let rule = RecurrenceRule {
+            frequency: RecurrenceFrequency::Weekly,
+            interval: 1,
+            count: Some(7),
+            until: None,
+            excluded_dates: vec![NaiveDate::from_ymd_opt(2024, 6, 3).unwrap()],
+            skip_statutory_holidays: true,
+        };
+        let plan = expand_series(&rule, utc("2024-05-13T15:00:00Z"), &eastern);
+
+        let skipped: Vec<(NaiveDate, SkipReason)> = plan.skipped.iter().map(|s| (s.local_date, s.reason.clone())).collect();
+        assert_eq!(skipped, vec![
+            (NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(), SkipReason::StatutoryHoliday),
+            (NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(), SkipReason::ExcludedDate),
+            (NaiveDate::from_ymd_opt(2024, 6, 24).unwrap(), SkipReason::StatutoryHoliday),
+        ]);
+        assert_eq!(plan.starts.len(), 4);
+        assert!(plan.starts.iter().all(|s| s.with_timezone(&eastern).time() == NaiveTime::from_hms_opt(10, 0, 0).unwrap()));
+
+        assert_eq!(easter_sunday(2025), NaiveDate::from_ymd_opt(2025, 4, 20));
+        assert!(quebec_statutory_holidays(2025).contains(&NaiveDate::from_ymd_opt(2025, 4, 18).unwrap()));
+    }
+
+    #[test]
+    fn test_series_steps_on_montreal_wall_clock_across_dst() {
+        // Mondays at 10:00 from 2024-03-04 (EST); clocks spring forward on Sunday 2024-03-10
+        let montreal = clinic_timezone(None).unwrap();
+        let rule = RecurrenceRule {
+            frequency: RecurrenceFrequency::Weekly,
+            interval: 1,
+            count: Some(3),
+            until: None,
+            excluded_dates: vec![NaiveDate::from_ymd_opt(2024, 3, 18).unwrap()],
+            skip_statutory_holidays: false,
+        };
+        let mut weekly = appointment("series", "2024-03-04T15:00:00Z", 60);
+        weekly.recurrence = Some(rule.clone());
+
+        let plan = expand_series(&rule, utc("2024-03-04T15:00:00Z"), &montreal);
+        assert_eq!(plan.starts, vec![utc("2024-03-04T15:00:00Z"), utc("2024-03-11T14:00:00Z")]);
+        let starts: Vec<DateTime<Utc>> = weekly
+            .occurrences_between(utc("2024-03-01T00:00:00Z"), utc("2024-04-01T00:00:00Z"), &montreal)
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(starts, plan.starts);
+
+        // 10:15 EDT on the 11th clashes with the series; stepping in UTC would have missed it
+        let clash = appointment("clash", "2024-03-11T14:15:00Z", 30);
+        assert!(check_booking(&[weekly], &clash, &SchedulingConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_local_days_bucket_around_midnight_and_dst() {
+        let montreal = clinic_timezone(None).unwrap();
+        let day = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let ids = |found: Vec<Appointment>| found.into_iter().map(|a| a.object_id).collect::<Vec<_>>();
+        let appointments = vec![
+            // 23:30 and 00:30 Montreal time (EDT, UTC-4) either side of midnight
+            appointment("late", "2024-06-11T03:30:00Z", 50),
+            appointment("early", "2024-06-11T04:30:00Z", 50),
+            // 00:30 EDT on the first day of daylight time; UTC-5 would put it on the 10th
+            appointment("after-dst", "2024-03-11T04:30:00Z", 50),
+        ];
+
+        assert_eq!(ids(appointments_on_local_days(&appointments, &montreal, day(6, 10), day(6, 10))), vec!["late"]);
+        assert_eq!(ids(appointments_on_local_days(&appointments, &montreal, day(6, 11), day(6, 11))), vec!["early"]);
+        assert!(appointments_on_local_days(&appointments, &montreal, day(3, 10), day(3, 10)).is_empty());
+        assert_eq!(ids(appointments_on_local_days(&appointments, &montreal, day(3, 11), day(3, 11))), vec!["after-dst"]);
+
+        // The spring-forward day is 23 hours long
+        let (start, end) = local_day_bounds(&montreal, day(3, 10), day(3, 10)).unwrap();
+        assert_eq!(end - start, Duration::hours(23));
+        assert!(clinic_timezone(Some("Mars/Olympus")).is_err());
+    }
+}
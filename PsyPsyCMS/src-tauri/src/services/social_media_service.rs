@@ -16,12 +16,15 @@
  * - Patient privacy protection safeguards
  */
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 use uuid::Uuid;
-use sqlx::{Pool, Sqlite};
+use sqlx::{Pool, Row, Sqlite};
 use thiserror::Error;
+use crate::services::phi_detector::{phi_detector, PhiType};
 
 #[derive(Error, Debug)]
 pub enum SocialMediaError {
@@ -70,6 +73,10 @@ pub struct SocialMediaConfig {
     pub rate_limit_window_minutes: u32,
     pub content_review_required: bool,
     pub supervisor_approval_required: bool,
+    /// Fix auto-fixable compliance issues (such as a missing disclaimer) when a post is created
+    pub auto_fix_compliance: bool,
+    /// Appended to posts that read as medical advice
+    pub medical_disclaimer: String,
 }
 
 impl Default for SocialMediaConfig {
@@ -87,10 +94,123 @@ impl Default for SocialMediaConfig {
             rate_limit_window_minutes: 60,
             content_review_required: true,
             supervisor_approval_required: false,
+            auto_fix_compliance: false,
+            medical_disclaimer: "This content is for informational purposes only and does not constitute medical advice. / Ce contenu est fourni à titre informatif seulement et ne constitue pas un avis médical.".to_string(),
         }
     }
 }
 
+/// LinkedIn OAuth2 application settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedInOAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+    pub token_url: String,
+    /// Return a fixed development token instead of calling LinkedIn
+    pub use_mock_tokens: bool,
+}
+
+impl Default for LinkedInOAuthConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: String::new(),
+            redirect_uri: String::new(),
+            token_url: "https://www.linkedin.com/oauth/v2/accessToken".to_string(),
+            use_mock_tokens: false,
+        }
+    }
+}
+
+impl LinkedInOAuthConfig {
+    /// Settings from `LINKEDIN_CLIENT_ID`, `LINKEDIN_CLIENT_SECRET`, `LINKEDIN_REDIRECT_URI`,
+    /// `LINKEDIN_TOKEN_URL` and `LINKEDIN_USE_MOCK_TOKENS`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        if let Some(client_id) = var("LINKEDIN_CLIENT_ID") {
+            config.client_id = client_id;
+        }
+        if let Some(client_secret) = var("LINKEDIN_CLIENT_SECRET") {
+            config.client_secret = client_secret;
+        }
+        if let Some(redirect_uri) = var("LINKEDIN_REDIRECT_URI") {
+            config.redirect_uri = redirect_uri;
+        }
+        if let Some(token_url) = var("LINKEDIN_TOKEN_URL") {
+            config.token_url = token_url;
+        }
+        if let Some(value) = var("LINKEDIN_USE_MOCK_TOKENS") {
+            config.use_mock_tokens = matches!(value.to_ascii_lowercase().as_str(), "1" | "true" | "yes");
+        }
+        config
+    }
+}
+
+/// Token endpoint response; LinkedIn only issues refresh tokens to approved applications
+#[derive(Debug, Deserialize)]
+struct LinkedInTokenResponse {
+    access_token: String,
+    expires_in: i64,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    token_type: Option<String>,
+}
+
+/// Exchange an authorization code at the LinkedIn token endpoint
+async fn exchange_linkedin_code(
+    client: &reqwest::Client,
+    config: &LinkedInOAuthConfig,
+    auth_code: &str,
+) -> Result<LinkedInCredentials, SocialMediaError> {
+    if config.client_id.is_empty() || config.client_secret.is_empty() || config.redirect_uri.is_empty() {
+        return Err(SocialMediaError::Authentication(
+            "LinkedIn OAuth is not configured (LINKEDIN_CLIENT_ID, LINKEDIN_CLIENT_SECRET, LINKEDIN_REDIRECT_URI)".to_string(),
+        ));
+    }
+    let response = client
+        .post(&config.token_url)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", auth_code),
+            ("redirect_uri", config.redirect_uri.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|e| SocialMediaError::Authentication(format!("LinkedIn token request failed: {}", e)))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(SocialMediaError::Authentication(format!(
+            "LinkedIn token exchange failed (HTTP {}): {}",
+            status.as_u16(),
+            body
+        )));
+    }
+    let token: LinkedInTokenResponse = response
+        .json()
+        .await
+        .map_err(|e| SocialMediaError::Authentication(format!("Unexpected LinkedIn token response: {}", e)))?;
+
+    Ok(LinkedInCredentials {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at: Utc::now() + chrono::Duration::seconds(token.expires_in),
+        // Filled in from the profile once the account is connected
+        profile_id: String::new(),
+        company_id: None,
+        scope: token.scope.unwrap_or_default(),
+        token_type: token.token_type.unwrap_or_else(|| "Bearer".to_string()),
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinkedInCredentials {
     pub access_token: String,
@@ -150,6 +270,9 @@ pub struct SocialMediaPost {
     pub scheduled_for: Option<DateTime<Utc>>,
     pub posted_at: Option<DateTime<Utc>>,
     pub status: String, // draft, scheduled, posted, failed, deleted
+    /// Why publishing failed, for posts in the `failed` state
+    #[serde(default)]
+    pub error_message: Option<String>,
     pub engagement_stats: Option<PostEngagementStats>,
     pub compliance_checked: bool,
     pub compliance_status: String, // approved, pending, rejected
@@ -191,6 +314,17 @@ pub struct ContentTemplate {
     pub last_used: Option<DateTime<Utc>>,
 }
 
+impl ContentTemplate {
+    /// Replace targeted specialties with their taxonomy codes, refusing unknown ones
+    pub fn normalize_specialties(
+        &mut self,
+        taxonomy: &crate::services::specialty_taxonomy::SpecialtyTaxonomy,
+    ) -> Result<(), crate::services::specialty_taxonomy::InvalidSpecialties> {
+        self.professional_specialties = taxonomy.validate(&self.professional_specialties)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComplianceCheck {
     pub check_id: String,
@@ -257,15 +391,101 @@ pub struct SchedulingRule {
     pub created_at: DateTime<Utc>,
 }
 
+/// A scheduled post with the earliest time its scheduling rule lets it go out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledPost {
+    pub post: SocialMediaPost,
+    pub next_eligible_at: DateTime<Utc>,
+}
+
+/// Outcome of one scheduler pass, by post ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchedulerRunReport {
+    pub published: Vec<String>,
+    pub deferred: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+/// How often `run_scheduler` looks for due posts
+const SCHEDULER_POLL_INTERVAL_SECS: u64 = 60;
+
+fn frequency_window(period: &str) -> Duration {
+    match period {
+        "weekly" => Duration::days(7),
+        "monthly" => Duration::days(30),
+        _ => Duration::days(1),
+    }
+}
+
+/// Rules store their timezone as "UTC" or a fixed offset such as "-05:00"; anything else is
+/// treated as UTC
+fn rule_offset(timezone: &str) -> FixedOffset {
+    timezone.trim().parse::<FixedOffset>().unwrap_or_else(|_| FixedOffset::east_opt(0).expect("zero offset"))
+}
+
+/// How far back published posts matter to a rule
+fn rule_lookback(rule: &SchedulingRule) -> Duration {
+    frequency_window(&rule.frequency_period).max(Duration::hours(rule.min_interval_hours.max(0) as i64))
+}
+
+/// Earliest time at or after `now` that the rule lets another post go out, given when the
+/// professional's earlier posts on that platform were published
+pub fn next_eligible_time(rule: &SchedulingRule, published: &[DateTime<Utc>], now: DateTime<Utc>) -> DateTime<Utc> {
+    let mut candidate = now;
+
+    if rule.min_interval_hours > 0 {
+        if let Some(last) = published.iter().max() {
+            candidate = candidate.max(*last + Duration::hours(rule.min_interval_hours as i64));
+        }
+    }
+
+    if rule.frequency_limit > 0 {
+        let window = frequency_window(&rule.frequency_period);
+        let limit = rule.frequency_limit as usize;
+        let mut in_window: Vec<DateTime<Utc>> = published.iter().copied().filter(|t| *t > candidate - window).collect();
+        in_window.sort();
+        if in_window.len() >= limit {
+            // A slot frees up once enough of the oldest posts have aged out of the window
+            candidate = in_window[in_window.len() - limit] + window;
+        }
+    }
+
+    if rule.exclude_weekends {
+        let offset = rule_offset(&rule.timezone);
+        let local = candidate.with_timezone(&offset);
+        let days_to_monday = match local.weekday() {
+            Weekday::Sat => Some(2),
+            Weekday::Sun => Some(1),
+            _ => None,
+        };
+        if let Some(days) = days_to_monday {
+            candidate = (local.date_naive() + Duration::days(days))
+                .and_hms_opt(0, 0, 0)
+                .and_then(|monday| monday.and_local_timezone(offset).single())
+                .map(|monday| monday.with_timezone(&Utc))
+                .unwrap_or(candidate);
+        }
+    }
+
+    candidate
+}
+
+/// Whether a professional currently consents to posting on a platform, given their ID and the
+/// platform
+pub type PostingConsentCheck = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
+
 pub struct SocialMediaService {
     config: SocialMediaConfig,
     db_pool: Pool<Sqlite>,
     http_client: reqwest::Client,
+    media_validation: crate::services::media_validation::MediaValidationConfig,
+    linkedin_oauth: LinkedInOAuthConfig,
+    posting_consent: Option<PostingConsentCheck>,
 }
 
 impl SocialMediaService {
     pub fn new(config: SocialMediaConfig, db_pool: Pool<Sqlite>) -> Self {
-        let http_client = reqwest::Client::builder()
+        let http_client = crate::security::outbound::guarded_client_builder()
             .timeout(std::time::Duration::from_secs(30))
             .user_agent("PsyPsy-CMS/1.0 Healthcare-Professional-Platform")
             .build()
@@ -275,9 +495,45 @@ impl SocialMediaService {
             config,
             db_pool,
             http_client,
+            media_validation: crate::services::media_validation::MediaValidationConfig::from_env(),
+            linkedin_oauth: LinkedInOAuthConfig::from_env(),
+            posting_consent: None,
         }
     }
 
+    /// Open the integrations database at `db_path`, bringing its schema up to date first
+    pub async fn open(config: SocialMediaConfig, db_path: &Path) -> Result<Self, SocialMediaError> {
+        let path = db_path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            crate::storage::migrations::open_and_migrate(&path, crate::storage::migrations::INTEGRATIONS_MIGRATIONS)
+        })
+        .await
+        .map_err(|e| SocialMediaError::Configuration(format!("Migration task failed: {}", e)))?
+        .map_err(|e| SocialMediaError::Configuration(format!("Failed to migrate integrations database: {}", e)))?;
+
+        let database_url = format!("sqlite:{}", db_path.display());
+        let pool = sqlx::SqlitePool::connect(&database_url).await?;
+        Ok(Self::new(config, pool))
+    }
+
+    /// Ask `check` for consent before every publish; without one nothing is published
+    pub fn with_posting_consent(mut self, check: PostingConsentCheck) -> Self {
+        self.posting_consent = Some(check);
+        self
+    }
+
+    /// Run the scheduler in the background for as long as the service lives
+    pub fn spawn_scheduler(self: &Arc<Self>) -> tauri::async_runtime::JoinHandle<()> {
+        let service = Arc::clone(self);
+        tauri::async_runtime::spawn(async move { service.run_scheduler().await })
+    }
+
+    /// Use these LinkedIn OAuth settings instead of the environment
+    pub fn with_linkedin_oauth(mut self, linkedin_oauth: LinkedInOAuthConfig) -> Self {
+        self.linkedin_oauth = linkedin_oauth;
+        self
+    }
+
     /// Connect a professional's LinkedIn account
     pub async fn connect_linkedin_account(&self, professional_id: &str, auth_code: &str) -> Result<String, SocialMediaError> {
         tracing::info!("🔗 Connecting LinkedIn account for professional: {}", professional_id);
@@ -370,7 +626,16 @@ impl SocialMediaService {
         tracing::info!("📝 Creating social media post: {} for professional: {}", post_id, professional_id);
 
         // Validate content compliance
-        let compliance_check = self.check_content_compliance(&post_data.content, &post_data.hashtags).await?;
+        let mut content = post_data.content.clone();
+        let mut compliance_check = self.check_content_compliance(&content, &post_data.hashtags).await?;
+
+        if self.config.auto_fix_compliance && compliance_check.issues.iter().any(|i| i.auto_fixable) {
+            let (fixed, remaining) = self.apply_auto_fixes(&content, &compliance_check);
+            tracing::info!("🔧 Applied compliance auto-fixes to post {}; {} issue(s) need manual changes", post_id, remaining.len());
+            compliance_check = self.check_content_compliance(&fixed, &post_data.hashtags).await?;
+            compliance_check.auto_fix_applied = true;
+            content = fixed;
+        }
 
         if compliance_check.status == "failed" {
             return Err(SocialMediaError::ComplianceViolation(
@@ -386,7 +651,7 @@ impl SocialMediaService {
             professional_id: professional_id.to_string(),
             content_type: post_data.content_type.clone(),
             title: post_data.title.clone(),
-            content: post_data.content.clone(),
+            content,
             hashtags: post_data.hashtags.clone(),
             mentions: post_data.mentions.clone(),
             media_urls: post_data.media_urls.clone(),
@@ -396,6 +661,7 @@ impl SocialMediaService {
             scheduled_for: post_data.scheduled_for,
             posted_at: None,
             status: if post_data.scheduled_for.is_some() { "scheduled".to_string() } else { "draft".to_string() },
+            error_message: None,
             engagement_stats: None,
             compliance_checked: true,
             compliance_status: compliance_check.status.clone(),
@@ -428,6 +694,29 @@ impl SocialMediaService {
 
         let post = self.get_post(post_id).await?;
 
+        // Consent and review can change between scheduling and publishing, and the content may
+        // have been edited since it was checked
+        let consented = self.posting_consent
+            .as_ref()
+            .map_or(false, |check| check(&post.professional_id, &post.platform));
+        if !consented {
+            return Err(SocialMediaError::ComplianceViolation(
+                format!("No current posting consent for {} on {}", post.professional_id, post.platform)
+            ));
+        }
+        if post.review_required && post.reviewed_at.is_none() {
+            return Err(SocialMediaError::ComplianceViolation("Post is awaiting compliance review".to_string()));
+        }
+        let compliance_check = self.check_content_compliance(&post.content, &post.hashtags).await?;
+        if post.compliance_status == "failed" || compliance_check.status == "failed" {
+            return Err(SocialMediaError::ComplianceViolation(
+                format!("Content compliance check failed: {:?}", compliance_check.issues)
+            ));
+        }
+
+        // Media may have changed or disappeared since the post was created
+        self.validate_media_urls(&post).await?;
+
         // Check rate limits
         self.check_rate_limits(&post.professional_id, &post.platform).await?;
 
@@ -445,14 +734,249 @@ impl SocialMediaService {
         Ok(())
     }
 
+    /// Save a scheduling rule, replacing any earlier version with the same ID
+    pub async fn save_scheduling_rule(&self, rule: &SchedulingRule) -> Result<(), SocialMediaError> {
+        let query = r#"
+            INSERT INTO social_media_scheduling_rules (
+                id, rule_id, professional_id, platform, rule_name,
+                content_types_json, optimal_times_json, frequency_limit,
+                frequency_period, timezone, exclude_weekends, exclude_holidays,
+                min_interval_hours, active, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(rule_id) DO UPDATE SET
+                platform = excluded.platform,
+                rule_name = excluded.rule_name,
+                content_types_json = excluded.content_types_json,
+                optimal_times_json = excluded.optimal_times_json,
+                frequency_limit = excluded.frequency_limit,
+                frequency_period = excluded.frequency_period,
+                timezone = excluded.timezone,
+                exclude_weekends = excluded.exclude_weekends,
+                exclude_holidays = excluded.exclude_holidays,
+                min_interval_hours = excluded.min_interval_hours,
+                active = excluded.active
+        "#;
+
+        sqlx::query(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(&rule.rule_id)
+            .bind(&rule.professional_id)
+            .bind(&rule.platform)
+            .bind(&rule.rule_name)
+            .bind(serde_json::to_string(&rule.content_types)?)
+            .bind(serde_json::to_string(&rule.optimal_times)?)
+            .bind(rule.frequency_limit)
+            .bind(&rule.frequency_period)
+            .bind(&rule.timezone)
+            .bind(rule.exclude_weekends)
+            .bind(rule.exclude_holidays)
+            .bind(rule.min_interval_hours)
+            .bind(rule.active)
+            .bind(rule.created_at)
+            .execute(&self.db_pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The professional's newest active rule for a platform
+    pub async fn active_scheduling_rule(&self, professional_id: &str, platform: &str) -> Result<Option<SchedulingRule>, SocialMediaError> {
+        let query = r#"
+            SELECT * FROM social_media_scheduling_rules
+            WHERE professional_id = ? AND platform = ? AND active = TRUE
+            ORDER BY created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(professional_id)
+            .bind(platform)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        Ok(row.map(|row| SchedulingRule {
+            rule_id: row.get("rule_id"),
+            professional_id: row.get("professional_id"),
+            platform: row.get("platform"),
+            rule_name: row.get("rule_name"),
+            content_types: serde_json::from_str(&row.get::<String, _>("content_types_json")).unwrap_or_default(),
+            optimal_times: serde_json::from_str(&row.get::<String, _>("optimal_times_json")).unwrap_or_default(),
+            frequency_limit: row.get("frequency_limit"),
+            frequency_period: row.get("frequency_period"),
+            timezone: row.get("timezone"),
+            exclude_weekends: row.get("exclude_weekends"),
+            exclude_holidays: row.get("exclude_holidays"),
+            min_interval_hours: row.get("min_interval_hours"),
+            active: row.get("active"),
+            created_at: row.get("created_at"),
+        }))
+    }
+
+    /// Earliest time the post may go out: its scheduled time, pushed back by the professional's
+    /// active rule for the platform
+    async fn next_eligible_at(&self, post: &SocialMediaPost, now: DateTime<Utc>) -> Result<DateTime<Utc>, SocialMediaError> {
+        let scheduled_for = post.scheduled_for.unwrap_or(now);
+        let Some(rule) = self.active_scheduling_rule(&post.professional_id, &post.platform).await? else {
+            return Ok(scheduled_for);
+        };
+
+        let query = r#"
+            SELECT posted_at FROM social_media_posts
+            WHERE professional_id = ? AND platform = ?
+            AND status = 'posted' AND posted_at > ?
+        "#;
+        let published: Vec<DateTime<Utc>> = sqlx::query(query)
+            .bind(&post.professional_id)
+            .bind(&post.platform)
+            .bind(now - rule_lookback(&rule))
+            .fetch_all(&self.db_pool)
+            .await?
+            .iter()
+            .map(|row| row.get("posted_at"))
+            .collect();
+
+        Ok(next_eligible_time(&rule, &published, scheduled_for.max(now)))
+    }
+
+    /// A professional's scheduled posts, soonest first, with when each can actually go out
+    pub async fn get_scheduled_posts(&self, professional_id: &str) -> Result<Vec<ScheduledPost>, SocialMediaError> {
+        let query = r#"
+            SELECT post_id FROM social_media_posts
+            WHERE professional_id = ? AND status = 'scheduled'
+            ORDER BY scheduled_for
+        "#;
+
+        let post_ids: Vec<String> = sqlx::query(query)
+            .bind(professional_id)
+            .fetch_all(&self.db_pool)
+            .await?
+            .iter()
+            .map(|row| row.get("post_id"))
+            .collect();
+
+        let now = Utc::now();
+        let mut scheduled = Vec::with_capacity(post_ids.len());
+        for post_id in post_ids {
+            let post = self.get_post(&post_id).await?;
+            let next_eligible_at = self.next_eligible_at(&post, now).await?;
+            scheduled.push(ScheduledPost { post, next_eligible_at });
+        }
+        Ok(scheduled)
+    }
+
+    /// Publish every due scheduled post its rule allows now and defer the rest. A post that
+    /// fails to publish is marked `failed` with the error rather than retried forever.
+    pub async fn process_due_posts(&self) -> Result<SchedulerRunReport, SocialMediaError> {
+        let now = Utc::now();
+        let query = r#"
+            SELECT post_id FROM social_media_posts
+            WHERE status = 'scheduled' AND scheduled_for <= ?
+            AND (next_eligible_at IS NULL OR next_eligible_at <= ?)
+            AND (review_required = FALSE OR reviewed_at IS NOT NULL)
+            ORDER BY scheduled_for
+        "#;
+
+        let post_ids: Vec<String> = sqlx::query(query)
+            .bind(now)
+            .bind(now)
+            .fetch_all(&self.db_pool)
+            .await?
+            .iter()
+            .map(|row| row.get("post_id"))
+            .collect();
+
+        let mut report = SchedulerRunReport::default();
+        for post_id in post_ids {
+            // Posts published earlier in this pass count against the rule
+            let post = self.get_post(&post_id).await?;
+            let eligible_at = self.next_eligible_at(&post, now).await?;
+            if eligible_at > now {
+                self.defer_post(&post_id, eligible_at).await?;
+                report.deferred.push(post_id);
+                continue;
+            }
+
+            match self.publish_post_now(&post_id).await {
+                Ok(()) => report.published.push(post_id),
+                Err(SocialMediaError::RateLimit(reason)) => {
+                    // The daily cap is not a publishing failure; try again tomorrow
+                    tracing::info!("⏳ Deferring post {}: {}", post_id, reason);
+                    let tomorrow = (now.date_naive() + Duration::days(1))
+                        .and_hms_opt(0, 0, 0)
+                        .map(|midnight| midnight.and_utc())
+                        .unwrap_or(now + Duration::days(1));
+                    self.defer_post(&post_id, tomorrow).await?;
+                    report.deferred.push(post_id);
+                }
+                Err(error) => {
+                    tracing::error!("❌ Scheduled post {} failed to publish: {}", post_id, error);
+                    self.mark_post_failed(&post_id, &error.to_string()).await?;
+                    report.failed.push(post_id);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Publish scheduled posts as they come due; runs until the task is dropped
+    pub async fn run_scheduler(&self) {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_POLL_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            match self.process_due_posts().await {
+                Ok(report) => {
+                    if !(report.published.is_empty() && report.deferred.is_empty() && report.failed.is_empty()) {
+                        tracing::info!(
+                            "🗓️ Scheduler pass: {} published, {} deferred, {} failed",
+                            report.published.len(),
+                            report.deferred.len(),
+                            report.failed.len()
+                        );
+                    }
+                }
+                Err(e) => tracing::error!("❌ Social media scheduler pass failed: {}", e),
+            }
+        }
+    }
+
+    /// Check each media URL's type, size and reachability against the platform's limits
+    async fn validate_media_urls(&self, post: &SocialMediaPost) -> Result<(), SocialMediaError> {
+        let platforms = vec![post.platform.clone()];
+        let mut errors = Vec::new();
+        for url in &post.media_urls {
+            if let Err(error) = crate::services::media_validation::validate_media(url, None, &platforms, &self.media_validation).await {
+                errors.push(format!("{}: {}", url, error));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SocialMediaError::ContentValidation(errors.join("; ")))
+        }
+    }
+
     /// Check content for compliance violations
     async fn check_content_compliance(&self, content: &str, hashtags: &[String]) -> Result<ComplianceCheck, SocialMediaError> {
         let check_id = Uuid::new_v4().to_string();
         let mut issues = Vec::new();
         let mut score = 1.0;
 
-        // Check for potential privacy violations
-        if self.detect_patient_information(content) {
+        // Identifiers are reported one by one so the editor can highlight each span
+        let phi_matches = phi_detector().detect(content);
+        for phi in &phi_matches {
+            issues.push(ComplianceIssue {
+                issue_type: "privacy_violation".to_string(),
+                severity: "critical".to_string(),
+                description: format!("{} detected in content", phi_label(phi.phi_type)),
+                location: Some(format!("{}..{}", phi.start, phi.end)),
+                suggestion: Some("Remove the identifier before posting".to_string()),
+                auto_fixable: false,
+            });
+        }
+        if !phi_matches.is_empty() {
+            score -= 0.5;
+        } else if self.detect_patient_information(content) {
             issues.push(ComplianceIssue {
                 issue_type: "privacy_violation".to_string(),
                 severity: "critical".to_string(),
@@ -464,7 +988,8 @@ impl SocialMediaService {
             score -= 0.5;
         }
 
-        // Check for medical advice
+        // Check for medical advice; advice without a disclaimer breaches professional-order
+        // rules, so it fails on its own until the disclaimer is added
         if self.detect_medical_advice(content) {
             issues.push(ComplianceIssue {
                 issue_type: "medical_advice".to_string(),
@@ -474,7 +999,7 @@ impl SocialMediaService {
                 suggestion: Some("Add disclaimer that content is for informational purposes only".to_string()),
                 auto_fixable: true,
             });
-            score -= 0.2;
+            score -= 0.45;
         }
 
         // Check professional tone
@@ -530,22 +1055,48 @@ impl SocialMediaService {
         })
     }
 
-    /// Detect potential patient information in content
+    /// Detect first-person accounts of a specific patient's case. Identifiers are found by the
+    /// PHI detector; general words like "patient" or "diagnosis" are fine in educational posts.
     fn detect_patient_information(&self, content: &str) -> bool {
         let content_lower = content.to_lowercase();
 
-        // Look for patterns that might indicate patient information
-        let privacy_indicators = [
-            "patient", "client", "my patient", "this patient", "john doe", "jane doe",
-            "diagnosis", "prescribed", "treatment plan", "medical history",
-            "case study", "session with", "therapy session"
+        let case_indicators = [
+            "my patient", "this patient", "a patient of mine", "my client", "this client",
+            "session with my", "session with a client", "treatment plan for",
         ];
 
-        privacy_indicators.iter().any(|&indicator| content_lower.contains(indicator))
+        case_indicators.iter().any(|&indicator| content_lower.contains(indicator))
+    }
+
+    /// Fix what can be fixed without the author: medical-advice findings get the configured
+    /// disclaimer appended. Returns the fixed content and the issues still needing a human.
+    pub fn apply_auto_fixes(&self, content: &str, check: &ComplianceCheck) -> (String, Vec<ComplianceIssue>) {
+        let mut fixed = content.to_string();
+        let mut remaining = Vec::new();
+
+        for issue in &check.issues {
+            match issue.issue_type.as_str() {
+                "medical_advice" if issue.auto_fixable => {
+                    let disclaimer = self.config.medical_disclaimer.trim();
+                    if !disclaimer.is_empty() && !fixed.contains(disclaimer) {
+                        fixed = format!("{}\n\n{}", fixed.trim_end(), disclaimer);
+                    }
+                }
+                _ => remaining.push(issue.clone()),
+            }
+        }
+
+        (fixed, remaining)
     }
 
-    /// Detect potential medical advice in content
+    /// Detect potential medical advice in content. Content already carrying the disclaimer is
+    /// presented as general information and passes.
     fn detect_medical_advice(&self, content: &str) -> bool {
+        let disclaimer = self.config.medical_disclaimer.trim();
+        if !disclaimer.is_empty() && content.contains(disclaimer) {
+            return false;
+        }
+
         let content_lower = content.to_lowercase();
 
         let advice_indicators = [
@@ -608,10 +1159,12 @@ impl SocialMediaService {
     }
 
     /// Exchange LinkedIn authorization code for access token
-    async fn exchange_linkedin_auth_code(&self, _auth_code: &str) -> Result<LinkedInCredentials, SocialMediaError> {
-        // Mock implementation for development
-        // In production, this would call LinkedIn's OAuth2 API
+    async fn exchange_linkedin_auth_code(&self, auth_code: &str) -> Result<LinkedInCredentials, SocialMediaError> {
+        if !self.linkedin_oauth.use_mock_tokens {
+            return exchange_linkedin_code(&self.http_client, &self.linkedin_oauth, auth_code).await;
+        }
 
+        // Development token; never reaches LinkedIn
         Ok(LinkedInCredentials {
             access_token: "mock_linkedin_access_token".to_string(),
             refresh_token: Some("mock_refresh_token".to_string()),
@@ -945,6 +1498,7 @@ impl SocialMediaService {
             scheduled_for: row.get("scheduled_for"),
             posted_at: row.get("posted_at"),
             status: row.get("status"),
+            error_message: row.get("error_message"),
             engagement_stats: None, // Would deserialize from JSON if present
             compliance_checked: row.get("compliance_checked"),
             compliance_status: row.get("compliance_status"),
@@ -977,6 +1531,42 @@ impl SocialMediaService {
         Ok(())
     }
 
+    /// Hold a scheduled post back until its rule allows it
+    async fn defer_post(&self, post_id: &str, next_eligible_at: DateTime<Utc>) -> Result<(), SocialMediaError> {
+        let query = r#"
+            UPDATE social_media_posts
+            SET next_eligible_at = ?, updated_at = ?
+            WHERE post_id = ?
+        "#;
+
+        sqlx::query(query)
+            .bind(next_eligible_at)
+            .bind(Utc::now())
+            .bind(post_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Move a post to `failed`, keeping the reason
+    async fn mark_post_failed(&self, post_id: &str, error: &str) -> Result<(), SocialMediaError> {
+        let query = r#"
+            UPDATE social_media_posts
+            SET status = 'failed', error_message = ?, updated_at = ?
+            WHERE post_id = ?
+        "#;
+
+        sqlx::query(query)
+            .bind(error)
+            .bind(Utc::now())
+            .bind(post_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        Ok(())
+    }
+
     /// Health check for social media service
     pub async fn health_check(&self) -> Result<HashMap<String, String>, SocialMediaError> {
         let mut status = HashMap::new();
@@ -1015,6 +1605,17 @@ pub struct CreatePostRequest {
     pub scheduled_for: Option<DateTime<Utc>>,
 }
 
+fn phi_label(phi_type: PhiType) -> &'static str {
+    match phi_type {
+        PhiType::Sin => "Social Insurance Number",
+        PhiType::RamqNumber => "RAMQ health card number",
+        PhiType::Phone => "Phone number",
+        PhiType::Email => "Email address",
+        PhiType::DateOfBirth => "Date of birth",
+        PhiType::StreetAddress => "Street address",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1027,58 +1628,12 @@ mod tests {
 
         let pool = sqlx::SqlitePool::connect(&database_url).await.unwrap();
 
-        // Create simplified test tables
-        sqlx::query(r#"
-            CREATE TABLE social_media_accounts (
-                id TEXT PRIMARY KEY,
-                account_id TEXT NOT NULL,
-                platform TEXT NOT NULL,
-                account_type TEXT NOT NULL,
-                professional_id TEXT NOT NULL,
-                display_name TEXT NOT NULL,
-                username TEXT,
-                profile_url TEXT,
-                follower_count INTEGER,
-                connection_count INTEGER,
-                verified BOOLEAN DEFAULT FALSE,
-                active BOOLEAN DEFAULT TRUE,
-                last_synced DATETIME,
-                sync_enabled BOOLEAN DEFAULT TRUE,
-                posting_enabled BOOLEAN DEFAULT TRUE,
-                analytics_enabled BOOLEAN DEFAULT TRUE
-            )
-        "#).execute(&pool).await.unwrap();
-
-        sqlx::query(r#"
-            CREATE TABLE social_media_posts (
-                id TEXT PRIMARY KEY,
-                post_id TEXT NOT NULL UNIQUE,
-                platform TEXT NOT NULL,
-                account_id TEXT NOT NULL,
-                professional_id TEXT NOT NULL,
-                content_type TEXT NOT NULL,
-                title TEXT,
-                content TEXT NOT NULL,
-                hashtags_json TEXT,
-                mentions_json TEXT,
-                media_urls_json TEXT,
-                link_url TEXT,
-                link_title TEXT,
-                link_description TEXT,
-                scheduled_for DATETIME,
-                posted_at DATETIME,
-                platform_post_id TEXT,
-                status TEXT NOT NULL DEFAULT 'draft',
-                compliance_checked BOOLEAN DEFAULT FALSE,
-                compliance_status TEXT,
-                compliance_notes TEXT,
-                review_required BOOLEAN DEFAULT FALSE,
-                reviewed_by TEXT,
-                reviewed_at DATETIME,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-        "#).execute(&pool).await.unwrap();
+        // Same schema the migration runner creates in production
+        for migration in crate::storage::migrations::INTEGRATIONS_MIGRATIONS {
+            for statement in migration.statements {
+                sqlx::query(statement).execute(&pool).await.unwrap();
+            }
+        }
 
         pool
     }
@@ -1094,6 +1649,229 @@ mod tests {
         assert!(service.config.content_compliance_enabled);
     }
 
+    fn oauth_config(token_url: String) -> LinkedInOAuthConfig {
+        LinkedInOAuthConfig {
+            client_id: "client-id".to_string(),
+            client_secret: "client-secret".to_string(),
+            redirect_uri: "https://cms.example.com/oauth/linkedin".to_string(),
+            token_url,
+            use_mock_tokens: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_linkedin_code_exchange() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/v2/accessToken"))
+            .and(body_string_contains("code=good-code"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "AQX-real-token",
+                "expires_in": 5_184_000,
+                "scope": "w_member_social"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/v2/accessToken"))
+            .and(body_string_contains("code=stale-code"))
+            .respond_with(ResponseTemplate::new(401).set_body_string(r#"{"error":"invalid_grant"}"#))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let config = oauth_config(format!("{}/oauth/v2/accessToken", server.uri()));
+
+        let credentials = exchange_linkedin_code(&client, &config, "good-code").await.unwrap();
+        assert_eq!(credentials.access_token, "AQX-real-token");
+        assert!(credentials.refresh_token.is_none());
+        assert_eq!(credentials.token_type, "Bearer");
+        let lifetime = credentials.expires_at - Utc::now();
+        assert!(lifetime > chrono::Duration::days(59) && lifetime <= chrono::Duration::days(60));
+
+        match exchange_linkedin_code(&client, &config, "stale-code").await {
+            Err(SocialMediaError::Authentication(message)) => assert!(message.contains("invalid_grant")),
+            other => panic!("expected an authentication error, got {:?}", other.map(|c| c.access_token)),
+        }
+    }
+
+    fn rule(frequency_limit: i32, min_interval_hours: i32, exclude_weekends: bool) -> SchedulingRule {
+        SchedulingRule {
+            rule_id: "rule-1".to_string(),
+            professional_id: "pro-1".to_string(),
+            platform: "linkedin".to_string(),
+            rule_name: "Weekday cadence".to_string(),
+            content_types: vec!["text".to_string()],
+            optimal_times: vec![],
+            frequency_limit,
+            frequency_period: "daily".to_string(),
+            timezone: "UTC".to_string(),
+            exclude_weekends,
+            exclude_holidays: false,
+            min_interval_hours,
+            active: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_next_eligible_time_applies_rule() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        // Wednesday
+        let now = at("2026-03-04T12:00:00Z");
+
+        assert_eq!(next_eligible_time(&rule(0, 0, false), &[], now), now);
+        assert_eq!(next_eligible_time(&rule(0, 4, false), &[at("2026-03-04T10:00:00Z")], now), at("2026-03-04T14:00:00Z"));
+
+        // Two a day: the slot reopens when the older of the two ages out
+        let published = [at("2026-03-04T08:00:00Z"), at("2026-03-04T11:00:00Z")];
+        assert_eq!(next_eligible_time(&rule(2, 0, false), &published, now), at("2026-03-05T08:00:00Z"));
+
+        // Saturday moves to Monday morning in the rule's timezone
+        let saturday = at("2026-03-07T12:00:00Z");
+        assert_eq!(next_eligible_time(&rule(0, 0, true), &[], saturday), at("2026-03-09T00:00:00Z"));
+        let mut eastern = rule(0, 0, true);
+        eastern.timezone = "-05:00".to_string();
+        assert_eq!(next_eligible_time(&eastern, &[], saturday), at("2026-03-09T05:00:00Z"));
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_marks_failed_posts_instead_of_leaving_them_scheduled() {
+        let pool = create_test_db().await;
+        let config = SocialMediaConfig { content_review_required: false, ..SocialMediaConfig::default() };
+        let service = SocialMediaService::new(config, pool).with_posting_consent(Arc::new(|_, _| true));
+
+        let due = Utc::now() - Duration::minutes(5);
+        for (post_id, platform) in [("post-ok", "linkedin"), ("post-bad", "myspace")] {
+            let post = SocialMediaPost {
+                post_id: post_id.to_string(),
+                platform: platform.to_string(),
+                account_id: "acct".to_string(),
+                professional_id: "pro-1".to_string(),
+                content_type: "text".to_string(),
+                title: None,
+                content: "Tips for better sleep".to_string(),
+                hashtags: vec![],
+                mentions: vec![],
+                media_urls: vec![],
+                link_url: None,
+                link_title: None,
+                link_description: None,
+                scheduled_for: Some(due),
+                posted_at: None,
+                status: "scheduled".to_string(),
+                error_message: None,
+                engagement_stats: None,
+                compliance_checked: true,
+                compliance_status: "approved".to_string(),
+                compliance_notes: None,
+                review_required: false,
+                reviewed_by: None,
+                reviewed_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            service.store_post(&post).await.unwrap();
+        }
+
+        let report = service.process_due_posts().await.unwrap();
+        assert_eq!(report.published, vec!["post-ok".to_string()]);
+        assert_eq!(report.failed, vec!["post-bad".to_string()]);
+
+        let failed = service.get_post("post-bad").await.unwrap();
+        assert_eq!(failed.status, "failed");
+        assert!(failed.error_message.unwrap().contains("Unsupported platform"));
+        assert!(service.get_scheduled_posts("pro-1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_publishing_rechecks_consent_and_content() {
+        let pool = create_test_db().await;
+        let config = SocialMediaConfig { content_review_required: false, ..SocialMediaConfig::default() };
+        let unconsented = SocialMediaService::new(config.clone(), pool.clone());
+        let service = SocialMediaService::new(config, pool)
+            .with_posting_consent(Arc::new(|professional_id, platform| professional_id == "pro-1" && platform == "linkedin"));
+
+        let mut post = SocialMediaPost {
+            post_id: "post-1".to_string(),
+            platform: "linkedin".to_string(),
+            account_id: "acct".to_string(),
+            professional_id: "pro-1".to_string(),
+            content_type: "text".to_string(),
+            title: None,
+            content: "Tips for better sleep".to_string(),
+            hashtags: vec![],
+            mentions: vec![],
+            media_urls: vec![],
+            link_url: None,
+            link_title: None,
+            link_description: None,
+            scheduled_for: None,
+            posted_at: None,
+            status: "scheduled".to_string(),
+            error_message: None,
+            engagement_stats: None,
+            compliance_checked: true,
+            compliance_status: "approved".to_string(),
+            compliance_notes: None,
+            review_required: false,
+            reviewed_by: None,
+            reviewed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        service.store_post(&post).await.unwrap();
+        assert!(matches!(unconsented.publish_post_now("post-1").await, Err(SocialMediaError::ComplianceViolation(_))));
+
+        // Edited after it was approved
+        post.post_id = "post-2".to_string();
+        post.content = "Had a great session with my patient John today who was diagnosed with depression.".to_string();
+        service.store_post(&post).await.unwrap();
+        assert!(matches!(service.publish_post_now("post-2").await, Err(SocialMediaError::ComplianceViolation(_))));
+        assert_eq!(service.get_post("post-2").await.unwrap().status, "scheduled");
+    }
+
+    #[tokio::test]
+    async fn test_auto_fix_turns_failing_advice_post_into_approved() {
+        let pool = create_test_db().await;
+        let config = SocialMediaConfig { auto_fix_compliance: true, ..SocialMediaConfig::default() };
+        let service = SocialMediaService::new(config, pool.clone());
+        let content = "You should try box breathing before a stressful meeting.";
+
+        let check = service.check_content_compliance(content, &[]).await.unwrap();
+        assert_eq!(check.status, "failed");
+        let (fixed, remaining) = service.apply_auto_fixes(content, &check);
+        assert!(remaining.is_empty());
+        assert!(fixed.ends_with(&service.config.medical_disclaimer));
+
+        let request = CreatePostRequest {
+            platform: "linkedin".to_string(),
+            account_id: "acct".to_string(),
+            content_type: "text".to_string(),
+            title: None,
+            content: content.to_string(),
+            hashtags: vec![],
+            mentions: vec![],
+            media_urls: vec![],
+            link_url: None,
+            link_title: None,
+            link_description: None,
+            scheduled_for: Some(Utc::now() + Duration::days(1)),
+        };
+        let post_id = service.create_post("pro-1", request).await.unwrap();
+        assert_eq!(service.get_post(&post_id).await.unwrap().content, fixed);
+
+        let stored = sqlx::query("SELECT status, auto_fix_applied FROM social_media_compliance_checks")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored.get::<String, _>("status"), "approved");
+        assert!(stored.get::<bool, _>("auto_fix_applied"));
+    }
+
     #[tokio::test]
     async fn test_content_compliance_check() {
         let pool = create_test_db().await;
@@ -0,0 +1,335 @@
+// Professional Specialty Taxonomy for PsyPsy CMS
+// Controlled vocabulary for professional specialties and template targeting. The built-in list
+// can be replaced or extended from a JSON file, and legacy free-text values are mapped onto it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+const TAXONOMY_FILE: &str = "specialty_taxonomy.json";
+
+/// One entry of the vocabulary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Specialty {
+    /// Stable identifier stored on records, e.g. `neuropsychology`
+    pub code: String,
+    pub label_en: String,
+    pub label_fr: String,
+    /// Other spellings accepted and mapped to this code
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Retired specialties stay resolvable for old records but are refused on new ones
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl Specialty {
+    fn new(code: &str, label_en: &str, label_fr: &str, aliases: &[&str]) -> Self {
+        Self {
+            code: code.to_string(),
+            label_en: label_en.to_string(),
+            label_fr: label_fr.to_string(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            active: true,
+        }
+    }
+
+    fn names(&self) -> impl Iterator<Item = &str> {
+        [self.code.as_str(), self.label_en.as_str(), self.label_fr.as_str()]
+            .into_iter()
+            .chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+/// How a free-text value was matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingMatch {
+    /// Code, label or alias
+    Exact,
+    /// Best word overlap; needs confirmation before it is applied
+    Suggested,
+    Unmatched,
+}
+
+/// Proposed mapping of one legacy value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecialtyMapping {
+    pub input: String,
+    pub code: Option<String>,
+    pub matched: MappingMatch,
+}
+
+/// Values refused by validation, each with its closest specialty if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidSpecialties {
+    pub mappings: Vec<SpecialtyMapping>,
+}
+
+impl std::fmt::Display for InvalidSpecialties {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values: Vec<String> = self
+            .mappings
+            .iter()
+            .map(|m| match &m.code {
+                Some(code) => format!("'{}' (did you mean '{}'?)", m.input, code),
+                None => format!("'{}'", m.input),
+            })
+            .collect();
+        write!(f, "Unknown specialties: {}", values.join(", "))
+    }
+}
+
+/// Lowercase, accent-insensitive words of a value
+fn words(value: &str) -> Vec<String> {
+    let folded: String = value
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'â' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    folded.split_whitespace().map(str::to_string).collect()
+}
+
+fn normalize(value: &str) -> String {
+    words(value).join(" ")
+}
+
+/// The specialty vocabulary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecialtyTaxonomy {
+    pub specialties: Vec<Specialty>,
+}
+
+impl Default for SpecialtyTaxonomy {
+    fn default() -> Self {
+        Self {
+            specialties: vec![
+                Specialty::new("clinical_psychology", "Clinical psychology", "Psychologie clinique", &["clinical psychologist", "psychologist"]),
+                Specialty::new("neuropsychology", "Neuropsychology", "Neuropsychologie", &["neuropsych", "neuropsychologist"]),
+                Specialty::new("psychotherapy", "Psychotherapy", "Psychothérapie", &["psychotherapist", "therapy"]),
+                Specialty::new("counselling", "Counselling", "Orientation", &["counseling", "guidance counsellor", "conseiller d'orientation"]),
+                Specialty::new("child_adolescent", "Child and adolescent", "Enfance et adolescence", &["child psychology", "pediatric", "youth"]),
+                Specialty::new("couple_family", "Couple and family therapy", "Thérapie conjugale et familiale", &["marriage and family", "family therapy", "couples therapy"]),
+                Specialty::new("addiction", "Addiction", "Dépendances", &["substance use", "addictions"]),
+                Specialty::new("trauma", "Trauma and PTSD", "Trauma et TSPT", &["ptsd", "trauma therapy"]),
+                Specialty::new("cbt", "Cognitive behavioural therapy", "Thérapie cognitivo-comportementale", &["cognitive behavioral therapy", "tcc"]),
+                Specialty::new("psychiatry", "Psychiatry", "Psychiatrie", &["psychiatrist"]),
+                Specialty::new("social_work", "Social work", "Travail social", &["social worker", "travailleur social"]),
+                Specialty::new("sexology", "Sexology", "Sexologie", &["sex therapy", "sexologist"]),
+            ],
+        }
+    }
+}
+
+impl SpecialtyTaxonomy {
+    /// Built-in vocabulary, replaced by the JSON file at `SPECIALTY_TAXONOMY_PATH` when set
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("SPECIALTY_TAXONOMY_PATH") else {
+            return Self::default();
+        };
+        match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|raw| serde_json::from_slice(&raw).map_err(|e| e.to_string())) {
+            Ok(taxonomy) => taxonomy,
+            Err(e) => {
+                log::warn!("Ignoring specialty taxonomy at {}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Specialty named by a code, label or alias
+    pub fn resolve(&self, value: &str) -> Option<&Specialty> {
+        let value = normalize(value);
+        if value.is_empty() {
+            return None;
+        }
+        self.specialties.iter().find(|s| s.names().any(|name| normalize(name) == value))
+    }
+
+    /// Map a legacy free-text value onto the vocabulary
+    pub fn map_value(&self, value: &str) -> SpecialtyMapping {
+        let mapping = |code: Option<&str>, matched| SpecialtyMapping {
+            input: value.to_string(),
+            code: code.map(str::to_string),
+            matched,
+        };
+        if let Some(specialty) = self.resolve(value) {
+            return mapping(Some(&specialty.code), MappingMatch::Exact);
+        }
+
+        // Otherwise the active specialty sharing the largest share of words
+        let input: HashSet<String> = words(value).into_iter().collect();
+        let best = self
+            .specialties
+            .iter()
+            .filter(|s| s.active)
+            .flat_map(|s| s.names().map(move |name| (s, name)))
+            .map(|(s, name)| {
+                let name: HashSet<String> = words(name).into_iter().collect();
+                let shared = input.intersection(&name).count() as f64;
+                (s, shared / input.union(&name).count().max(1) as f64)
+            })
+            .filter(|(_, score)| *score >= 0.5)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((specialty, _)) => mapping(Some(&specialty.code), MappingMatch::Suggested),
+            None => mapping(None, MappingMatch::Unmatched),
+        }
+    }
+
+    /// Canonical codes for `values`, deduplicated in order. Anything that is not an exact match
+    /// for an active specialty is refused along with a suggested mapping.
+    pub fn validate(&self, values: &[String]) -> Result<Vec<String>, InvalidSpecialties> {
+        let mut codes: Vec<String> = Vec::new();
+        let mut invalid = Vec::new();
+        for value in values {
+            match self.resolve(value) {
+                Some(specialty) if specialty.active => {
+                    if !codes.contains(&specialty.code) {
+                        codes.push(specialty.code.clone());
+                    }
+                }
+                _ => invalid.push(self.map_value(value)),
+            }
+        }
+        if invalid.is_empty() {
+            Ok(codes)
+        } else {
+            Err(InvalidSpecialties { mappings: invalid })
+        }
+    }
+
+    /// Add or replace a specialty by code
+    pub fn upsert(&mut self, specialty: Specialty) -> Result<(), String> {
+        if normalize(&specialty.code).is_empty() || specialty.label_en.trim().is_empty() {
+            return Err("A specialty needs a code and an English label".to_string());
+        }
+        // A name may only point at one specialty
+        if let Some(clash) = specialty
+            .names()
+            .filter_map(|name| self.resolve(name))
+            .find(|existing| existing.code != specialty.code)
+        {
+            return Err(format!("Specialty names overlap with '{}'", clash.code));
+        }
+        match self.specialties.iter_mut().find(|s| s.code == specialty.code) {
+            Some(existing) => *existing = specialty,
+            None => self.specialties.push(specialty),
+        }
+        Ok(())
+    }
+}
+
+/// Taxonomy in use, persisted in the app data directory once storage is attached
+#[derive(Debug, Clone, Default)]
+pub struct SpecialtyTaxonomyState {
+    taxonomy: Arc<RwLock<SpecialtyTaxonomy>>,
+    storage_path: Arc<RwLock<Option<PathBuf>>>,
+}
+
+impl SpecialtyTaxonomyState {
+    pub fn new(taxonomy: SpecialtyTaxonomy) -> Self {
+        Self {
+            taxonomy: Arc::new(RwLock::new(taxonomy)),
+            storage_path: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Load a saved taxonomy from `dir` (replacing the initial one) and persist future changes there
+    pub fn attach_storage(&self, dir: &Path) -> std::io::Result<()> {
+        let path = dir.join(TAXONOMY_FILE);
+        if path.exists() {
+            if let Ok(saved) = serde_json::from_slice::<SpecialtyTaxonomy>(&std::fs::read(&path)?) {
+                *self.taxonomy.write().unwrap() = saved;
+            }
+        }
+        *self.storage_path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    pub fn get(&self) -> SpecialtyTaxonomy {
+        self.taxonomy.read().unwrap().clone()
+    }
+
+    pub fn validate(&self, values: &[String]) -> Result<Vec<String>, InvalidSpecialties> {
+        self.taxonomy.read().unwrap().validate(values)
+    }
+
+    pub fn map_values(&self, values: &[String]) -> Vec<SpecialtyMapping> {
+        let taxonomy = self.taxonomy.read().unwrap();
+        values.iter().map(|v| taxonomy.map_value(v)).collect()
+    }
+
+    pub fn upsert(&self, specialty: Specialty) -> Result<(), String> {
+        self.taxonomy.write().unwrap().upsert(specialty)?;
+        self.persist();
+        Ok(())
+    }
+
+    fn persist(&self) {
+        let Some(path) = self.storage_path.read().unwrap().clone() else {
+            return;
+        };
+        match serde_json::to_vec_pretty(&*self.taxonomy.read().unwrap()) {
+            Ok(bytes) => {
+                if let Err(e) = std::fs::write(&path, bytes) {
+                    log::warn!("Failed to persist specialty taxonomy: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize specialty taxonomy: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_canonicalizes_and_flags_unknown() {
+        let taxonomy = SpecialtyTaxonomy::default();
+        let codes = taxonomy
+            .validate(&["Neuropsychologie".to_string(), "  CBT ".to_string(), "neuropsych".to_string()])
+            .unwrap();
+        assert_eq!(codes, vec!["neuropsychology", "cbt"]);
+
+        let err = taxonomy.validate(&["Family therapy for couples".to_string(), "Astrology".to_string()]).unwrap_err();
+        assert_eq!(err.mappings[0].matched, MappingMatch::Suggested);
+        assert_eq!(err.mappings[0].code.as_deref(), Some("couple_family"));
+        assert_eq!(err.mappings[1].matched, MappingMatch::Unmatched);
+        assert!(err.to_string().contains("'Astrology'"));
+    }
+
+    #[test]
+    fn test_upsert_extends_and_rejects_overlap() {
+        let mut taxonomy = SpecialtyTaxonomy::default();
+        taxonomy
+            .upsert(Specialty::new("art_therapy", "Art therapy", "Art-thérapie", &["art therapist"]))
+            .unwrap();
+        assert_eq!(taxonomy.validate(&["Art-therapie".to_string()]).unwrap(), vec!["art_therapy"]);
+
+        assert!(taxonomy.upsert(Specialty::new("ptsd_care", "PTSD care", "Soins TSPT", &["PTSD"])).is_err());
+
+        let mut retired = taxonomy.resolve("sexology").unwrap().clone();
+        retired.active = false;
+        taxonomy.upsert(retired).unwrap();
+        assert!(taxonomy.validate(&["sexology".to_string()]).is_err());
+        assert!(taxonomy.resolve("sexologist").is_some());
+    }
+}
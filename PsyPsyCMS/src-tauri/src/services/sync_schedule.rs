@@ -0,0 +1,615 @@
+// Background Sync Scheduling for PsyPsy CMS
+// Runs note sync on a configurable interval, suppressed during quiet-hours windows (sessions,
+// overnight backups). Quiet hours are in the workstation's local time. "Sync now" bypasses both
+// the interval and quiet hours; regaining connectivity outside quiet hours syncs straight away.
+// Schedule changes wake the scheduler, so they apply without a restart.
+// Each pass times a HEAD request to the sync endpoint: a slow or metered connection uploads in
+// small batches and a very slow one defers sync. A change in network quality only takes effect
+// once it has held for a debounce period, so a flapping connection does not thrash the scheduler.
+
+use crate::commands::offline_sync_commands::SyncServiceState;
+use crate::security::auth::AuthState;
+use crate::services::data_lock::DataLockState;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+/// How often connectivity is re-checked while offline or paused
+const CONNECTIVITY_PROBE_SECS: i64 = 60;
+
+/// Overlapping quiet windows followed when pushing a run past them
+const MAX_WINDOW_HOPS: usize = 8;
+
+/// Latency above which uploads go in small batches
+const SLOW_LATENCY_MS: u64 = 400;
+
+/// Latency above which background sync waits for a better connection
+const UNUSABLE_LATENCY_MS: u64 = 2_000;
+
+/// Notes uploaded per run on a slow or metered connection
+const REDUCED_BATCH_SIZE: usize = 5;
+
+/// How long a change in network quality must hold before sync behavior follows it
+const NETWORK_DEBOUNCE_SECS: i64 = 30;
+
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Connection as seen by the last probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub online: bool,
+    /// Round trip of a HEAD request to the sync endpoint; `None` when it failed
+    pub estimated_latency_ms: Option<u64>,
+    pub metered: bool,
+}
+
+/// How much background sync does on the current connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum SyncThrottle {
+    #[default]
+    Full,
+    /// Upload at most `batch_size` notes per run
+    #[serde(rename_all = "camelCase")]
+    Reduced { batch_size: usize },
+    /// Wait for a better connection; "sync now" still runs
+    Deferred,
+}
+
+impl SyncThrottle {
+    pub fn for_network(status: &NetworkStatus) -> Self {
+        let latency = status.estimated_latency_ms.unwrap_or(0);
+        if !status.online || latency > UNUSABLE_LATENCY_MS {
+            SyncThrottle::Deferred
+        } else if status.metered || latency > SLOW_LATENCY_MS {
+            SyncThrottle::Reduced { batch_size: REDUCED_BATCH_SIZE }
+        } else {
+            SyncThrottle::Full
+        }
+    }
+}
+
+/// Local time range during which background sync does not run; `end` before `start` spans midnight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// Days the window starts on; every day when empty
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+}
+
+impl QuietWindow {
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.weekdays.is_empty() || self.weekdays.contains(&day)
+    }
+
+    /// End of the window if `at` falls inside it
+    fn ends_after(&self, at: NaiveDateTime) -> Option<NaiveDateTime> {
+        let (date, time) = (at.date(), at.time());
+        if self.start < self.end {
+            (self.starts_on(date.weekday()) && time >= self.start && time < self.end).then(|| date.and_time(self.end))
+        } else if time >= self.start && self.starts_on(date.weekday()) {
+            Some(date.succ_opt()?.and_time(self.end))
+        } else if time < self.end && self.starts_on(date.pred_opt()?.weekday()) {
+            Some(date.and_time(self.end))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSchedule {
+    pub interval_minutes: u32,
+    pub quiet_hours: Vec<QuietWindow>,
+}
+
+impl Default for SyncSchedule {
+    fn default() -> Self {
+        Self {
+            interval_minutes: 5,
+            quiet_hours: Vec::new(),
+        }
+    }
+}
+
+impl SyncSchedule {
+    /// Defaults with `SYNC_INTERVAL_MINUTES` and `SYNC_QUIET_HOURS` (e.g. `22:00-06:00,12:00-13:00`)
+    /// overrides; an invalid schedule falls back to the defaults
+    pub fn from_env() -> Self {
+        let mut schedule = Self::default();
+        if let Ok(value) = std::env::var("SYNC_INTERVAL_MINUTES") {
+            match value.trim().parse() {
+                Ok(minutes) => schedule.interval_minutes = minutes,
+                Err(_) => log::warn!("Ignoring invalid SYNC_INTERVAL_MINUTES '{}'", value),
+            }
+        }
+        if let Ok(value) = std::env::var("SYNC_QUIET_HOURS") {
+            for range in value.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+                let parsed = range.split_once('-').and_then(|(start, end)| {
+                    Some(QuietWindow {
+                        start: NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?,
+                        end: NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?,
+                        weekdays: Vec::new(),
+                    })
+                });
+                match parsed {
+                    Some(window) => schedule.quiet_hours.push(window),
+                    None => log::warn!("Ignoring invalid quiet-hours range '{}'", range),
+                }
+            }
+        }
+        if let Err(e) = schedule.validate() {
+            log::warn!("Invalid sync schedule from environment ({}); using defaults", e);
+            return Self::default();
+        }
+        schedule
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=24 * 60).contains(&self.interval_minutes) {
+            return Err("Sync interval must be between 1 minute and 24 hours".to_string());
+        }
+        if self.quiet_hours.iter().any(|w| w.start == w.end) {
+            return Err("Quiet-hours windows must not start and end at the same time".to_string());
+        }
+        Ok(())
+    }
+
+    /// Latest end among the quiet windows `at` falls in
+    pub fn quiet_until(&self, at: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.quiet_hours.iter().filter_map(|w| w.ends_after(at)).max()
+    }
+
+    /// First time at or after `earliest` that is outside every quiet window
+    pub fn first_allowed(&self, earliest: NaiveDateTime) -> NaiveDateTime {
+        let mut at = earliest;
+        for _ in 0..MAX_WINDOW_HOPS {
+            match self.quiet_until(at) {
+                Some(end) => at = end,
+                None => break,
+            }
+        }
+        at
+    }
+
+    /// Next run after a run at `last`
+    pub fn next_run_after(&self, last: NaiveDateTime) -> NaiveDateTime {
+        self.first_allowed(last + Duration::minutes(self.interval_minutes as i64))
+    }
+}
+
+fn local_naive(at: DateTime<Utc>) -> NaiveDateTime {
+    at.with_timezone(&Local).naive_local()
+}
+
+fn from_local_naive(at: NaiveDateTime) -> DateTime<Utc> {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&at))
+}
+
+/// Schedule state reported by `get_sync_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncScheduleStatus {
+    pub interval_minutes: u32,
+    pub next_run: Option<DateTime<Utc>>,
+    pub in_quiet_window: bool,
+    pub quiet_until: Option<DateTime<Utc>>,
+    pub online: bool,
+    pub network: Option<NetworkStatus>,
+    pub throttle: SyncThrottle,
+    pub paused: bool,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct SchedulerRuntime {
+    next_run: Option<DateTime<Utc>>,
+    last_run: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    online: bool,
+    paused: bool,
+    run_requested: bool,
+    network: Option<NetworkStatus>,
+    throttle: SyncThrottle,
+    /// A differing reading and when it was first seen, waiting out the debounce
+    pending_network: Option<(NetworkStatus, DateTime<Utc>)>,
+}
+
+/// What the scheduler loop does next
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncDecision {
+    Run,
+    Wait(Duration),
+}
+
+/// Shared scheduler state; the loop started by `start_sync_scheduler` acts on it
+#[derive(Debug, Default)]
+pub struct SyncScheduler {
+    schedule: RwLock<SyncSchedule>,
+    runtime: Mutex<SchedulerRuntime>,
+    wake: Notify,
+}
+
+impl SyncScheduler {
+    pub fn new(schedule: SyncSchedule) -> Self {
+        Self {
+            schedule: RwLock::new(schedule),
+            ..Self::default()
+        }
+    }
+
+    pub fn schedule(&self) -> SyncSchedule {
+        self.schedule.read().unwrap().clone()
+    }
+
+    /// Replace the schedule; the next run is recomputed from the last one
+    pub fn set_schedule(&self, schedule: SyncSchedule, now: DateTime<Utc>) -> Result<(), String> {
+        schedule.validate()?;
+        // Same lock order as `decide`: schedule, then runtime
+        let mut current = self.schedule.write().unwrap();
+        let mut runtime = self.runtime.lock().unwrap();
+        let from = runtime.last_run.unwrap_or(now);
+        runtime.next_run = Some(from_local_naive(schedule.next_run_after(local_naive(from))).max(now));
+        *current = schedule;
+        drop(runtime);
+        drop(current);
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Run once as soon as possible, regardless of interval or quiet hours
+    pub fn request_run(&self) {
+        self.runtime.lock().unwrap().run_requested = true;
+        self.wake.notify_one();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.runtime.lock().unwrap().paused = paused;
+        self.wake.notify_one();
+    }
+
+    /// Record connectivity; coming back online makes an overdue run due immediately
+    pub fn set_online(&self, online: bool, now: DateTime<Utc>) {
+        let mut runtime = self.runtime.lock().unwrap();
+        if runtime.online == online {
+            return;
+        }
+        if online {
+            runtime.next_run = Some(runtime.next_run.map_or(now, |next| next.min(now)));
+            log::info!("Connectivity restored; background sync is due");
+        }
+        runtime.online = online;
+        drop(runtime);
+        self.wake.notify_one();
+    }
+
+    /// Record a network probe. The first reading applies at once; after that, a reading that
+    /// would change connectivity or throttling applies only once it has held for the debounce
+    /// period.
+    pub fn set_network(&self, status: NetworkStatus, now: DateTime<Utc>) {
+        let throttle = SyncThrottle::for_network(&status);
+        let mut runtime = self.runtime.lock().unwrap();
+        let same_behavior = |other: &NetworkStatus| other.online == status.online && SyncThrottle::for_network(other) == throttle;
+
+        let unchanged = runtime.network.as_ref().map(same_behavior);
+        let pending_since = runtime
+            .pending_network
+            .as_ref()
+            .filter(|(pending, _)| same_behavior(pending))
+            .map(|(_, since)| *since);
+        match (unchanged, pending_since) {
+            (None, _) => {}
+            (Some(true), _) => {
+                // Nothing changes; a pending change that did not hold is dropped
+                runtime.network = Some(status);
+                runtime.pending_network = None;
+                return;
+            }
+            (Some(false), Some(since)) if now - since >= Duration::seconds(NETWORK_DEBOUNCE_SECS) => {}
+            (Some(false), Some(_)) => return,
+            (Some(false), None) => {
+                runtime.pending_network = Some((status, now));
+                return;
+            }
+        }
+
+        if runtime.throttle != throttle {
+            log::info!("Background sync throttle now {:?}", throttle);
+        }
+        runtime.throttle = throttle;
+        runtime.pending_network = None;
+        let online = status.online;
+        runtime.network = Some(status);
+        drop(runtime);
+        self.set_online(online, now);
+        self.wake.notify_one();
+    }
+
+    /// Notes a run may upload; `None` for no limit. "Sync now" is never limited.
+    pub fn batch_limit(&self) -> Option<usize> {
+        let runtime = self.runtime.lock().unwrap();
+        match runtime.throttle {
+            SyncThrottle::Reduced { batch_size } if !runtime.run_requested => Some(batch_size),
+            _ => None,
+        }
+    }
+
+    pub fn decide(&self, now: DateTime<Utc>) -> SyncDecision {
+        let schedule = self.schedule.read().unwrap();
+        let mut runtime = self.runtime.lock().unwrap();
+        if runtime.run_requested {
+            return SyncDecision::Run;
+        }
+        let mut probe = Duration::seconds(CONNECTIVITY_PROBE_SECS);
+        // Probe again when a pending network change is due to settle
+        let settle = runtime
+            .pending_network
+            .as_ref()
+            .map(|(_, since)| (*since + Duration::seconds(NETWORK_DEBOUNCE_SECS) - now).max(Duration::seconds(1)));
+        if let Some(settle) = settle {
+            probe = probe.min(settle);
+        }
+        if runtime.paused || !runtime.online || runtime.throttle == SyncThrottle::Deferred {
+            return SyncDecision::Wait(probe);
+        }
+        if let Some(end) = schedule.quiet_until(local_naive(now)) {
+            return SyncDecision::Wait(from_local_naive(end) - now);
+        }
+        let next = *runtime.next_run.get_or_insert(now);
+        if next <= now {
+            SyncDecision::Run
+        } else {
+            SyncDecision::Wait((next - now).min(settle.unwrap_or(next - now)))
+        }
+    }
+
+    /// Record a finished run and schedule the next one
+    pub fn finish_run(&self, now: DateTime<Utc>, result: Result<(), String>) {
+        let schedule = self.schedule.read().unwrap();
+        let mut runtime = self.runtime.lock().unwrap();
+        runtime.run_requested = false;
+        runtime.last_run = Some(now);
+        runtime.next_run = Some(from_local_naive(schedule.next_run_after(local_naive(now))));
+        runtime.last_error = result.err();
+    }
+
+    pub fn status(&self, now: DateTime<Utc>) -> SyncScheduleStatus {
+        let schedule = self.schedule.read().unwrap();
+        let runtime = self.runtime.lock().unwrap();
+        let quiet_until = schedule.quiet_until(local_naive(now)).map(from_local_naive);
+        // A pending run that lands in quiet hours actually happens when the window ends
+        let next_run = runtime
+            .next_run
+            .map(|next| from_local_naive(schedule.first_allowed(local_naive(next.max(now)))));
+        SyncScheduleStatus {
+            interval_minutes: schedule.interval_minutes,
+            next_run: if runtime.paused { None } else { next_run },
+            in_quiet_window: quiet_until.is_some(),
+            quiet_until,
+            online: runtime.online,
+            network: runtime.network.clone(),
+            throttle: runtime.throttle,
+            paused: runtime.paused,
+            last_run: runtime.last_run,
+            last_error: runtime.last_error.clone(),
+        }
+    }
+}
+
+/// Endpoint timed by the probe: the Firestore emulator when enabled, otherwise Firestore
+fn sync_endpoint() -> &'static str {
+    if std::env::var("FIREBASE_USE_EMULATOR").map_or(false, |v| v == "true") {
+        "http://127.0.0.1:9881/"
+    } else {
+        "https://firestore.googleapis.com/"
+    }
+}
+
+/// Whether the connection is metered: `SYNC_ASSUME_METERED` when set, otherwise NetworkManager's
+/// view on Linux; other platforms are assumed unmetered
+fn detect_metered() -> bool {
+    if let Ok(value) = std::env::var("SYNC_ASSUME_METERED") {
+        return matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes");
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("nmcli")
+            .args(["-t", "-f", "GENERAL.METERED", "dev", "show"])
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .any(|line| line.trim_start_matches("GENERAL.METERED:").starts_with("yes"))
+            })
+            .unwrap_or(false)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
+/// Time a HEAD request to the sync endpoint; any HTTP response means the backend is reachable
+pub async fn probe_network() -> NetworkStatus {
+    let metered = tokio::task::spawn_blocking(detect_metered).await.unwrap_or(false);
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Failed to build network probe client: {}", e);
+            return NetworkStatus { online: false, estimated_latency_ms: None, metered };
+        }
+    };
+    let started = Instant::now();
+    match client.head(sync_endpoint()).send().await {
+        Ok(_) => NetworkStatus {
+            online: true,
+            estimated_latency_ms: Some(started.elapsed().as_millis() as u64),
+            metered,
+        },
+        Err(_) => NetworkStatus { online: false, estimated_latency_ms: None, metered },
+    }
+}
+
+/// Sync once, uploading at most `batch_limit` notes; nothing to do until a user has initialized
+/// the sync service. Checked before every run, since the lock or sign-out may have happened
+/// since the last one: no notes leave the device under a data lock or without a live session.
+async fn run_sync(app: &AppHandle, batch_limit: Option<usize>) -> Result<(), String> {
+    app.state::<DataLockState>().ensure_unlocked()?;
+    {
+        let auth = app.state::<Arc<tokio::sync::RwLock<AuthState>>>();
+        let auth = auth.read().await;
+        if !auth.is_authenticated || auth.is_session_expired() {
+            return Err("Sync skipped: no signed-in session".to_string());
+        }
+    }
+
+    let sync_state = app.state::<SyncServiceState>();
+    let mut guard = sync_state.lock().await;
+    match guard.as_mut() {
+        Some(service) => {
+            service.perform_sync_limited(batch_limit).await.map_err(|e| e.to_string())?;
+            log::info!("Scheduled background sync completed");
+        }
+        None => log::debug!("Scheduled background sync skipped: sync service not initialized"),
+    }
+    Ok(())
+}
+
+/// Start the scheduler loop; it sleeps until the next run, a schedule change or a "sync now"
+pub fn start_sync_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let network = probe_network().await;
+            let scheduler = app.state::<SyncScheduler>();
+            scheduler.set_network(network, Utc::now());
+
+            match scheduler.decide(Utc::now()) {
+                SyncDecision::Run => {
+                    let result = run_sync(&app, scheduler.batch_limit()).await;
+                    if let Err(e) = &result {
+                        log::warn!("Scheduled background sync failed: {}", e);
+                    }
+                    scheduler.finish_run(Utc::now(), result);
+                }
+                SyncDecision::Wait(wait) => {
+                    let wait = wait.to_std().unwrap_or_default();
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = scheduler.wake.notified() => {}
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2026-03-02 is a Monday
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn overnight() -> SyncSchedule {
+        SyncSchedule {
+            interval_minutes: 30,
+            quiet_hours: vec![QuietWindow {
+                start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+                weekdays: Vec::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_runs_are_pushed_past_quiet_hours() {
+        let schedule = overnight();
+        assert_eq!(schedule.quiet_until(at(2, 23, 0)), Some(at(3, 6, 0)));
+        assert_eq!(schedule.quiet_until(at(3, 5, 59)), Some(at(3, 6, 0)));
+        assert_eq!(schedule.quiet_until(at(3, 6, 0)), None);
+
+        assert_eq!(schedule.next_run_after(at(2, 12, 0)), at(2, 12, 30));
+        assert_eq!(schedule.next_run_after(at(2, 21, 45)), at(3, 6, 0));
+    }
+
+    #[test]
+    fn test_weekday_windows_only_apply_on_their_days() {
+        let schedule = SyncSchedule {
+            interval_minutes: 15,
+            quiet_hours: vec![QuietWindow {
+                start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+                weekdays: vec![Weekday::Sat],
+            }],
+        };
+        // Saturday night into Sunday morning is quiet; Sunday night is not
+        assert_eq!(schedule.quiet_until(at(8, 1, 0)), Some(at(8, 2, 0)));
+        assert_eq!(schedule.quiet_until(at(7, 23, 30)), Some(at(8, 2, 0)));
+        assert_eq!(schedule.quiet_until(at(8, 23, 30)), None);
+    }
+
+    #[test]
+    fn test_sync_now_overrides_and_reconnect_makes_run_due() {
+        let scheduler = SyncScheduler::new(SyncSchedule::default());
+        let now = Utc::now();
+        assert!(matches!(scheduler.decide(now), SyncDecision::Wait(_)));
+
+        scheduler.set_online(true, now);
+        assert_eq!(scheduler.decide(now), SyncDecision::Run);
+        scheduler.finish_run(now, Ok(()));
+        assert!(matches!(scheduler.decide(now), SyncDecision::Wait(_)));
+
+        scheduler.set_online(false, now);
+        scheduler.request_run();
+        assert_eq!(scheduler.decide(now), SyncDecision::Run);
+    }
+
+    #[test]
+    fn test_network_quality_sets_throttle() {
+        let status = |latency, metered| NetworkStatus { online: true, estimated_latency_ms: Some(latency), metered };
+        assert_eq!(SyncThrottle::for_network(&status(40, false)), SyncThrottle::Full);
+        assert_eq!(SyncThrottle::for_network(&status(40, true)), SyncThrottle::Reduced { batch_size: REDUCED_BATCH_SIZE });
+        assert_eq!(SyncThrottle::for_network(&status(900, false)), SyncThrottle::Reduced { batch_size: REDUCED_BATCH_SIZE });
+        assert_eq!(SyncThrottle::for_network(&status(5_000, false)), SyncThrottle::Deferred);
+    }
+
+    #[test]
+    fn test_flapping_connection_is_debounced() {
+        let wifi = NetworkStatus { online: true, estimated_latency_ms: Some(30), metered: false };
+        let cellular = NetworkStatus { online: true, estimated_latency_ms: Some(120), metered: true };
+        let scheduler = SyncScheduler::new(SyncSchedule::default());
+        let start = Utc::now();
+        scheduler.set_network(wifi.clone(), start);
+        assert_eq!(scheduler.batch_limit(), None);
+
+        // Flips back and forth faster than the debounce: behavior stays put
+        for i in 1..=6 {
+            let reading = if i % 2 == 1 { cellular.clone() } else { wifi.clone() };
+            scheduler.set_network(reading, start + Duration::seconds(i * 10));
+        }
+        assert_eq!(scheduler.status(start).throttle, SyncThrottle::Full);
+
+        // A change that holds past the debounce applies
+        scheduler.set_network(cellular.clone(), start + Duration::seconds(100));
+        scheduler.set_network(cellular.clone(), start + Duration::seconds(100 + NETWORK_DEBOUNCE_SECS));
+        assert_eq!(scheduler.batch_limit(), Some(REDUCED_BATCH_SIZE));
+        scheduler.request_run();
+        assert_eq!(scheduler.batch_limit(), None);
+    }
+}
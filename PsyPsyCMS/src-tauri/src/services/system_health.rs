@@ -0,0 +1,161 @@
+// System Health Probes for PsyPsy CMS
+// Each subsystem is probed for real, under its own timeout so one hung dependency cannot stall
+// the whole check. Services that are not compiled into this build report `disabled` and are left
+// out of the overall rollup rather than counting as failures.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Default time each probe gets before it counts as unhealthy
+const DEFAULT_PROBE_TIMEOUT_MS: u64 = 3_000;
+
+/// Services whose modules are commented out until the sqlx dependency conflict is resolved
+pub const DISABLED_SERVICES: &[&str] = &[
+    "offline_service",
+    "quebec_audit_service",
+    "notification_service",
+    "quebec_compliance_service",
+    "vertex_ai_service",
+    "gcp_security_service",
+    "dlp_service",
+    "firebase_cmek_service",
+    "social_media_service",
+    "compliance_validation_service",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Healthy,
+    /// Working, but not as configured or expected
+    Degraded,
+    Unhealthy,
+    /// Intentionally not running in this build
+    Disabled,
+}
+
+/// Result of probing one subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    pub latency_ms: u64,
+    pub detail: String,
+}
+
+impl ComponentHealth {
+    pub fn disabled(detail: impl Into<String>) -> Self {
+        Self { status: ComponentStatus::Disabled, latency_ms: 0, detail: detail.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemHealthReport {
+    pub overall: ComponentStatus,
+    pub components: BTreeMap<String, ComponentHealth>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl SystemHealthReport {
+    pub fn new(components: BTreeMap<String, ComponentHealth>) -> Self {
+        Self { overall: rollup(components.values()), components, checked_at: Utc::now() }
+    }
+}
+
+/// Worst status among enabled components; healthy when every component is disabled
+pub fn rollup<'a>(components: impl IntoIterator<Item = &'a ComponentHealth>) -> ComponentStatus {
+    components
+        .into_iter()
+        .map(|component| component.status)
+        .filter(|status| *status != ComponentStatus::Disabled)
+        .max()
+        .unwrap_or(ComponentStatus::Healthy)
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemHealthConfig {
+    pub probe_timeout: Duration,
+}
+
+impl Default for SystemHealthConfig {
+    fn default() -> Self {
+        Self { probe_timeout: Duration::from_millis(DEFAULT_PROBE_TIMEOUT_MS) }
+    }
+}
+
+impl SystemHealthConfig {
+    /// Defaults, with the per-probe timeout overridden by `SYSTEM_HEALTH_PROBE_TIMEOUT_MS`
+    pub fn from_env() -> Self {
+        match std::env::var("SYSTEM_HEALTH_PROBE_TIMEOUT_MS") {
+            Ok(value) => match value.parse::<u64>() {
+                Ok(ms) if ms > 0 => Self { probe_timeout: Duration::from_millis(ms) },
+                _ => {
+                    log::warn!("Ignoring invalid SYSTEM_HEALTH_PROBE_TIMEOUT_MS: {}", value);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+/// Run one probe, timing it; a probe still running after `timeout` is reported unhealthy
+pub async fn probe<F>(timeout: Duration, check: F) -> ComponentHealth
+where
+    F: Future<Output = (ComponentStatus, String)>,
+{
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(timeout, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match outcome {
+        Ok((status, detail)) => ComponentHealth { status, latency_ms, detail },
+        Err(_) => ComponentHealth {
+            status: ComponentStatus::Unhealthy,
+            latency_ms,
+            detail: format!("No response within {} ms", timeout.as_millis()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(status: ComponentStatus) -> ComponentHealth {
+        ComponentHealth { status, latency_ms: 1, detail: String::new() }
+    }
+
+    #[test]
+    fn test_rollup_ignores_disabled_components() {
+        let mut components = BTreeMap::new();
+        components.insert("firebase".to_string(), component(ComponentStatus::Healthy));
+        for service in DISABLED_SERVICES {
+            components.insert(service.to_string(), ComponentHealth::disabled("not compiled"));
+        }
+        assert_eq!(SystemHealthReport::new(components.clone()).overall, ComponentStatus::Healthy);
+
+        components.insert("devtools".to_string(), component(ComponentStatus::Degraded));
+        assert_eq!(rollup(components.values()), ComponentStatus::Degraded);
+        components.insert("crypto".to_string(), component(ComponentStatus::Unhealthy));
+        assert_eq!(rollup(components.values()), ComponentStatus::Unhealthy);
+    }
+
+    #[tokio::test]
+    async fn test_hung_probe_times_out_as_unhealthy() {
+        let hung = probe(Duration::from_millis(20), async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            (ComponentStatus::Healthy, "never".to_string())
+        })
+        .await;
+        assert_eq!(hung.status, ComponentStatus::Unhealthy);
+        assert!(hung.latency_ms < 60_000);
+
+        let quick = probe(Duration::from_millis(20), async { (ComponentStatus::Healthy, "ok".to_string()) }).await;
+        assert_eq!(quick.status, ComponentStatus::Healthy);
+        assert_eq!(quick.detail, "ok");
+    }
+}
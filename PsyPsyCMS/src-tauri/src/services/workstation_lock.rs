@@ -0,0 +1,250 @@
+// Workstation Lock Monitor for PsyPsy CMS
+// Locks the active session when the OS screen locks or the system sleeps (HIPAA §164.312(a)(2)(iii)).
+// Locking suspends the session without discarding drafts; resuming requires re-authentication.
+
+use crate::security::auth::AuthState;
+use crate::services::firebase_service_simple::FirebaseServiceState;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::RwLock;
+
+/// Event emitted to the webview when the session is locked
+pub const SESSION_LOCKED_EVENT: &str = "session-locked";
+
+/// Automatic logoff configuration, set per deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkstationLockConfig {
+    /// Monitor OS lock/sleep at all
+    pub enabled: bool,
+    /// Lock when the OS screen locks
+    pub lock_on_screen_lock: bool,
+    /// Lock when the system sleeps or hibernates
+    pub lock_on_sleep: bool,
+    /// Roles with PHI access must pass MFA again to resume
+    pub require_mfa_for_phi: bool,
+    /// How often the OS lock state is sampled
+    pub poll_interval_secs: u64,
+    /// Wall-clock gap beyond the poll interval treated as a sleep
+    pub sleep_gap_secs: i64,
+}
+
+impl Default for WorkstationLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lock_on_screen_lock: true,
+            lock_on_sleep: true,
+            require_mfa_for_phi: true,
+            poll_interval_secs: 5,
+            sleep_gap_secs: 30,
+        }
+    }
+}
+
+impl WorkstationLockConfig {
+    /// Defaults with `WORKSTATION_LOCK_*` environment overrides
+    pub fn from_env() -> Self {
+        let flag = |name: &str, default: bool| {
+            std::env::var(name)
+                .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(default)
+        };
+        let defaults = Self::default();
+        Self {
+            enabled: flag("WORKSTATION_LOCK_ENABLED", defaults.enabled),
+            lock_on_screen_lock: flag("WORKSTATION_LOCK_ON_SCREEN_LOCK", defaults.lock_on_screen_lock),
+            lock_on_sleep: flag("WORKSTATION_LOCK_ON_SLEEP", defaults.lock_on_sleep),
+            require_mfa_for_phi: flag("WORKSTATION_UNLOCK_REQUIRE_MFA", defaults.require_mfa_for_phi),
+            ..defaults
+        }
+    }
+}
+
+/// Why the session was locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockReason {
+    ScreenLock,
+    SystemSleep,
+}
+
+/// Payload of [`SESSION_LOCKED_EVENT`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLockedEvent {
+    pub reason: LockReason,
+    pub locked_at: DateTime<Utc>,
+}
+
+/// Detects suspend by comparing wall-clock progress against the expected tick
+#[derive(Debug)]
+pub struct SleepDetector {
+    last_tick: DateTime<Utc>,
+    expected: Duration,
+    tolerance: Duration,
+}
+
+impl SleepDetector {
+    pub fn new(now: DateTime<Utc>, expected: Duration, tolerance: Duration) -> Self {
+        Self { last_tick: now, expected, tolerance }
+    }
+
+    /// Record a tick; returns true if the system was asleep since the previous one
+    pub fn observe(&mut self, now: DateTime<Utc>) -> bool {
+        let elapsed = now - self.last_tick;
+        self.last_tick = now;
+        elapsed > self.expected + self.tolerance
+    }
+}
+
+/// Current OS screen-lock state, if the platform exposes it
+#[cfg(target_os = "linux")]
+fn screen_locked() -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").ok()?;
+    let output = std::process::Command::new("loginctl")
+        .args(["show-session", &session, "-p", "LockedHint", "--value"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn screen_locked() -> Option<bool> {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::string::CFString;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
+    }
+
+    let raw = unsafe { CGSessionCopyCurrentDictionary() };
+    if raw.is_null() {
+        return None;
+    }
+    let session: CFDictionary<CFString, CFType> = unsafe { CFDictionary::wrap_under_create_rule(raw) };
+    let locked = session
+        .find(CFString::from_static_string("CGSSessionScreenIsLocked"))
+        .and_then(|value| value.downcast::<CFBoolean>())
+        .map(bool::from)
+        .unwrap_or(false);
+    Some(locked)
+}
+
+#[cfg(target_os = "windows")]
+fn screen_locked() -> Option<bool> {
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP,
+    };
+
+    // The input desktop cannot be opened while the secure (lock) desktop is active
+    match unsafe { OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_SWITCHDESKTOP) } {
+        Ok(desktop) => {
+            let _ = unsafe { CloseDesktop(desktop) };
+            Some(false)
+        }
+        Err(_) => Some(true),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn screen_locked() -> Option<bool> {
+    None
+}
+
+/// Lock the active session, notify the webview and audit the event
+pub async fn lock_session(app: &AppHandle, reason: LockReason) {
+    let auth_state = app.state::<Arc<RwLock<AuthState>>>();
+    let Some(user_id) = auth_state.write().await.lock() else {
+        return;
+    };
+
+    let event = SessionLockedEvent { reason, locked_at: Utc::now() };
+    log::info!("Session locked ({:?})", reason);
+    if let Err(e) = app.emit(SESSION_LOCKED_EVENT, &event) {
+        log::warn!("Failed to notify UI of session lock: {}", e);
+    }
+
+    let firebase_state = app.state::<FirebaseServiceState>();
+    let firebase_guard = firebase_state.0.lock().await;
+    if let Some(firebase) = firebase_guard.as_ref() {
+        if let Err(e) = firebase.audit_log(
+            "SESSION_LOCKED",
+            "authentication",
+            &user_id,
+            false,
+            Some(serde_json::json!({
+                "reason": reason,
+                "locked_at": event.locked_at,
+            }))
+        ).await {
+            log::warn!("Failed to audit session lock: {}", e);
+        }
+    }
+}
+
+/// Watch for OS lock and sleep for the lifetime of the app
+pub fn start_workstation_lock_monitor(app: AppHandle, config: WorkstationLockConfig) {
+    if !config.enabled {
+        log::warn!("Workstation lock monitoring disabled by configuration");
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let poll = std::time::Duration::from_secs(config.poll_interval_secs.max(1));
+        let mut sleep_detector = SleepDetector::new(
+            Utc::now(),
+            Duration::seconds(poll.as_secs() as i64),
+            Duration::seconds(config.sleep_gap_secs),
+        );
+        let mut was_locked = false;
+
+        loop {
+            tokio::time::sleep(poll).await;
+
+            if sleep_detector.observe(Utc::now()) && config.lock_on_sleep {
+                lock_session(&app, LockReason::SystemSleep).await;
+            }
+
+            if config.lock_on_screen_lock {
+                let locked = tokio::task::spawn_blocking(screen_locked).await.ok().flatten().unwrap_or(false);
+                if locked && !was_locked {
+                    lock_session(&app, LockReason::ScreenLock).await;
+                }
+                was_locked = locked;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_detected_from_wall_clock_gap() {
+        let start = Utc::now();
+        let mut detector = SleepDetector::new(start, Duration::seconds(5), Duration::seconds(30));
+
+        assert!(!detector.observe(start + Duration::seconds(6)));
+        assert!(detector.observe(start + Duration::minutes(20)));
+        assert!(!detector.observe(start + Duration::minutes(20) + Duration::seconds(5)));
+    }
+
+    #[test]
+    fn test_monitoring_enabled_by_default() {
+        let config = WorkstationLockConfig::default();
+        assert!(config.enabled && config.lock_on_screen_lock && config.lock_on_sleep);
+        assert!(config.require_mfa_for_phi);
+    }
+}
@@ -4,7 +4,9 @@ use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use base64::{Engine as _, engine::general_purpose};
-use crate::security::crypto::{CryptoService, DataClassification};
+use std::sync::Arc;
+use crate::security::DataClassification;
+use crate::security::crypto::{CryptoService, EncryptedData, EncryptionContext};
 use crate::compliance::quebec_law25::QuebecComplianceTracker;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -74,22 +76,19 @@ pub enum MedicalNotesError {
 
 pub struct MedicalNotesStore {
     pool: SqlitePool,
-    crypto: CryptoService,
+    crypto: Arc<CryptoService>,
     compliance: QuebecComplianceTracker,
 }
 
 impl MedicalNotesStore {
-    pub async fn new(database_url: &str) -> Result<Self, MedicalNotesError> {
+    /// Open the store. `crypto` is the application's managed service, so notes share its
+    /// persisted keyring and are covered by key rotation.
+    pub async fn new(database_url: &str, crypto: Arc<CryptoService>) -> Result<Self, MedicalNotesError> {
         let pool = SqlitePool::connect(database_url).await?;
 
         // Run migrations
         sqlx::migrate!("./migrations/medical_notes").run(&pool).await?;
 
-        let crypto = CryptoService::new();
-        // Initialize crypto with Quebec Law 25 compliant master key
-        crypto.initialize_master_key("quebec_law25_master_key", None).await
-            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
-
         let compliance = QuebecComplianceTracker::new().await
             .map_err(|e| MedicalNotesError::Compliance(e.to_string()))?;
 
@@ -100,6 +99,30 @@ impl MedicalNotesStore {
         })
     }
 
+    /// Encrypt note content bound to its client and note ids. The whole envelope is stored,
+    /// so the nonce and key version travel with the ciphertext.
+    async fn seal_content(&self, client_id: &str, note_id: &str, content_json: &str) -> Result<(EncryptedData, Vec<u8>), MedicalNotesError> {
+        let encrypted_data = self.crypto.encrypt_with_context(
+            content_json.as_bytes(),
+            DataClassification::Phi,
+            None,
+            &EncryptionContext::for_record(client_id, note_id),
+        ).await
+            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
+        let bytes = serde_json::to_vec(&encrypted_data)?;
+        Ok((encrypted_data, bytes))
+    }
+
+    /// Reverse `seal_content`; fails when the row was moved to another client or note id
+    async fn open_content(&self, client_id: &str, note_id: &str, encrypted_content: &[u8]) -> Result<String, MedicalNotesError> {
+        let encrypted_data: EncryptedData = serde_json::from_slice(encrypted_content)
+            .map_err(|e| MedicalNotesError::Encryption(format!("Invalid encrypted note: {}", e)))?;
+        let decrypted = self.crypto.decrypt_with_context(&encrypted_data, &EncryptionContext::for_record(client_id, note_id)).await
+            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
+        String::from_utf8(decrypted)
+            .map_err(|e| MedicalNotesError::Encryption(format!("UTF-8 decode error: {}", e)))
+    }
+
     /// Create a new encrypted medical note
     pub async fn create_note(
         &self,
@@ -121,8 +144,7 @@ impl MedicalNotesStore {
 
         // Serialize and encrypt content using Quebec Law 25 PHI classification
         let content_json = serde_json::to_string(content)?;
-        let encrypted_data = self.crypto.encrypt(content_json.as_bytes(), DataClassification::PHI, None).await
-            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
+        let (encrypted_data, encrypted_content_bytes) = self.seal_content(client_id, &note_id, &content_json).await?;
 
         // Generate content hash for integrity verification (using SHA-256)
         let content_hash = {
@@ -146,10 +168,6 @@ impl MedicalNotesStore {
             "deidentification_required": false
         });
 
-        // Store encrypted data as base64 and convert key_id to string
-        let encrypted_content_bytes = general_purpose::STANDARD.decode(&encrypted_data.data)
-            .map_err(|e| MedicalNotesError::Encryption(format!("Base64 decode error: {}", e)))?;
-
         sqlx::query!(
             r#"
             INSERT INTO medical_notes (
@@ -198,27 +216,8 @@ impl MedicalNotesStore {
 
         let note = row.ok_or_else(|| MedicalNotesError::NotFound(note_id.to_string()))?;
 
-        // Reconstruct EncryptedData structure for decryption
-        let encrypted_data = crate::security::crypto::EncryptedData {
-            id: Uuid::new_v4(), // Not used for decryption
-            algorithm: "AES-256-GCM-PHI".to_string(), // Quebec Law 25 PHI encryption
-            data: general_purpose::STANDARD.encode(&note.encrypted_content),
-            iv: "".to_string(), // The IV is embedded in the data by the CryptoService
-            tag: None,
-            classification: DataClassification::PHI,
-            encrypted_at: note.created_at,
-            key_id: Uuid::parse_str(&note.encryption_key_id)
-                .map_err(|e| MedicalNotesError::Encryption(format!("Invalid key UUID: {}", e)))?,
-            aad: None,
-            hmac: None,
-        };
-
         // Decrypt content using Quebec Law 25 compliant decryption
-        let decrypted_content = self.crypto.decrypt(&encrypted_data).await
-            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
-
-        let content_str = String::from_utf8(decrypted_content)
-            .map_err(|e| MedicalNotesError::Encryption(format!("UTF-8 decode error: {}", e)))?;
+        let content_str = self.open_content(&note.client_id, note_id, &note.encrypted_content).await?;
 
         // Verify content integrity using SHA-256
         let computed_hash = {
@@ -253,21 +252,19 @@ impl MedicalNotesStore {
 
         // Verify note exists and practitioner has access
         let existing = sqlx::query!(
-            "SELECT sync_version FROM medical_notes WHERE id = ? AND practitioner_id = ?",
+            "SELECT sync_version, client_id FROM medical_notes WHERE id = ? AND practitioner_id = ?",
             note_id,
             practitioner_id
         )
         .fetch_optional(&self.pool)
         .await?;
 
-        let current_version = existing
-            .ok_or_else(|| MedicalNotesError::NotFound(note_id.to_string()))?
-            .sync_version;
+        let existing = existing.ok_or_else(|| MedicalNotesError::NotFound(note_id.to_string()))?;
+        let current_version = existing.sync_version;
 
         // Encrypt new content using Quebec Law 25 PHI classification
         let content_json = serde_json::to_string(content)?;
-        let encrypted_data = self.crypto.encrypt(content_json.as_bytes(), DataClassification::PHI, None).await
-            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
+        let (encrypted_data, encrypted_content_bytes) = self.seal_content(&existing.client_id, note_id, &content_json).await?;
 
         let content_hash = {
             use ring::digest::{Context, SHA256};
@@ -277,9 +274,6 @@ impl MedicalNotesStore {
             general_purpose::STANDARD.encode(digest.as_ref())
         };
 
-        let encrypted_content_bytes = general_purpose::STANDARD.decode(&encrypted_data.data)
-            .map_err(|e| MedicalNotesError::Encryption(format!("Base64 decode error: {}", e)))?;
-
         let quebec_org = content.quebec_specific_data.as_ref()
             .and_then(|data| data.organization_reference.clone());
 
@@ -387,7 +381,7 @@ impl MedicalNotesStore {
     ) -> Result<SyncConflict, MedicalNotesError> {
         // Get local note
         let local_row = sqlx::query!(
-            "SELECT practitioner_id, encrypted_content, encryption_key_id, sync_version FROM medical_notes WHERE id = ?",
+            "SELECT practitioner_id, client_id, encrypted_content, encryption_key_id, sync_version FROM medical_notes WHERE id = ?",
             note_id
         )
         .fetch_optional(&self.pool)
@@ -395,27 +389,8 @@ impl MedicalNotesStore {
 
         let local_note = local_row.ok_or_else(|| MedicalNotesError::NotFound(note_id.to_string()))?;
 
-        // Reconstruct EncryptedData structure for local note decryption
-        let local_encrypted_data = crate::security::crypto::EncryptedData {
-            id: Uuid::new_v4(),
-            algorithm: "AES-256-GCM-PHI".to_string(),
-            data: general_purpose::STANDARD.encode(&local_note.encrypted_content),
-            iv: "".to_string(),
-            tag: None,
-            classification: DataClassification::PHI,
-            encrypted_at: chrono::Utc::now(), // Approximate since we don't store this
-            key_id: Uuid::parse_str(&local_note.encryption_key_id)
-                .map_err(|e| MedicalNotesError::Encryption(format!("Invalid key UUID: {}", e)))?,
-            aad: None,
-            hmac: None,
-        };
-
         // Decrypt local content
-        let decrypted_local = self.crypto.decrypt(&local_encrypted_data).await
-            .map_err(|e| MedicalNotesError::Encryption(e.to_string()))?;
-
-        let local_content_str = String::from_utf8(decrypted_local)
-            .map_err(|e| MedicalNotesError::Encryption(format!("UTF-8 decode error: {}", e)))?;
+        let local_content_str = self.open_content(&local_note.client_id, note_id, &local_note.encrypted_content).await?;
 
         let local_content: MedicalNoteContent = serde_json::from_str(&local_content_str)?;
 
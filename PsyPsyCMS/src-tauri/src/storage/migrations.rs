@@ -0,0 +1,568 @@
+//! Versioned SQLite schema migrations
+//!
+//! Each local database owns an ordered list of migrations. Applied versions are
+//! recorded in `schema_migrations`; every migration runs in its own transaction
+//! together with its bookkeeping row, so a failure leaves the schema at the last
+//! fully-applied version. A database written by a newer release is refused rather
+//! than silently downgraded.
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Errors raised while migrating a database
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("Database schema version {found} is newer than supported version {supported}")]
+    NewerSchema { found: u32, supported: u32 },
+    #[error("Migration {version} was applied as '{applied}' but this release defines '{expected}'")]
+    Mismatch { version: u32, applied: String, expected: String },
+    #[error("Migration {version} ('{name}') failed: {source}")]
+    Failed {
+        version: u32,
+        name: String,
+        #[source]
+        source: rusqlite::Error,
+    },
+}
+
+/// A single schema change
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Monotonically increasing version, starting at 1
+    pub version: u32,
+    /// Short, stable description recorded alongside the version
+    pub name: &'static str,
+    /// Statements executed in order inside one transaction
+    pub statements: &'static [&'static str],
+}
+
+/// Summary of a migration run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<u32>,
+}
+
+/// Encrypted medical notes database (`psypsy_notes.db`)
+pub const NOTES_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "medical_notes_and_audit_log",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS medical_notes (
+                id TEXT PRIMARY KEY,
+                patient_id TEXT NOT NULL,
+                encrypted_content BLOB NOT NULL,
+                template_type TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                modified_at TEXT NOT NULL,
+                consent_obtained BOOLEAN NOT NULL,
+                encrypted BOOLEAN DEFAULT TRUE,
+                deidentified BOOLEAN DEFAULT TRUE,
+                sync_status TEXT NOT NULL DEFAULT 'Local',
+                quebec_compliance TEXT NOT NULL,
+                content_checksum TEXT NOT NULL,
+                encryption_version INTEGER NOT NULL DEFAULT 1
+            )",
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                note_id TEXT,
+                action TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                phi_accessed BOOLEAN NOT NULL,
+                ip_address TEXT,
+                details TEXT,
+                FOREIGN KEY(note_id) REFERENCES medical_notes(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_patient_id ON medical_notes(patient_id)",
+            "CREATE INDEX IF NOT EXISTS idx_created_at ON medical_notes(created_at)",
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "note_classification_and_reencryption_jobs",
+        statements: &[
+            // Existing rows were written with plain AES-256-GCM under the master key
+            "ALTER TABLE medical_notes ADD COLUMN classification TEXT NOT NULL DEFAULT 'Confidential'",
+            "ALTER TABLE medical_notes ADD COLUMN encryption_level TEXT NOT NULL DEFAULT 'Strong'",
+            "CREATE TABLE IF NOT EXISTS reencryption_jobs (
+                id TEXT PRIMARY KEY,
+                status TEXT NOT NULL,
+                started_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                cursor TEXT,
+                examined INTEGER NOT NULL DEFAULT 0,
+                upgraded INTEGER NOT NULL DEFAULT 0,
+                failures TEXT NOT NULL DEFAULT '[]'
+            )",
+        ],
+    },
+    Migration {
+        version: 3,
+        name: "note_attachments",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS note_attachments (
+                id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL,
+                media_ref TEXT NOT NULL,
+                scan_status TEXT NOT NULL,
+                scan_result TEXT NOT NULL,
+                attached_by TEXT NOT NULL,
+                attached_at TEXT NOT NULL,
+                FOREIGN KEY(note_id) REFERENCES medical_notes(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_note_attachments_note ON note_attachments(note_id)",
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "note_metadata",
+        statements: &[
+            // Low-sensitivity fields kept in the clear so lists can filter without decrypting bodies
+            "CREATE TABLE IF NOT EXISTS note_metadata (
+                note_id TEXT NOT NULL,
+                field TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (note_id, field),
+                FOREIGN KEY(note_id) REFERENCES medical_notes(id)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_note_metadata_field_value ON note_metadata(field, value)",
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "note_versions_and_soft_delete",
+        statements: &[
+            // Notes saved before versioning stay at version 0 until they are next amended
+            "ALTER TABLE medical_notes ADD COLUMN current_version INTEGER NOT NULL DEFAULT 0",
+            "ALTER TABLE medical_notes ADD COLUMN deleted_at TEXT",
+            "ALTER TABLE medical_notes ADD COLUMN deleted_by TEXT",
+            "CREATE TABLE IF NOT EXISTS note_versions (
+                note_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                encrypted_content BLOB NOT NULL,
+                content_checksum TEXT NOT NULL,
+                encryption_level TEXT NOT NULL,
+                template_type TEXT NOT NULL,
+                metadata TEXT NOT NULL DEFAULT '{}',
+                author_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (note_id, version),
+                FOREIGN KEY(note_id) REFERENCES medical_notes(id)
+            )",
+            // Existing notes get their current body as version 0 so their history starts from what
+            // was on disk. The copy is still sealed to the note; opening the storage reseals it.
+            "INSERT OR IGNORE INTO note_versions
+                (note_id, version, encrypted_content, content_checksum, encryption_level, template_type,
+                 metadata, author_id, created_at)
+             SELECT id, 0, encrypted_content, content_checksum, encryption_level, template_type,
+                    COALESCE((SELECT json_group_object(field, value) FROM note_metadata m WHERE m.note_id = medical_notes.id), '{}'),
+                    '', modified_at
+             FROM medical_notes",
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "note_sync_records",
+        statements: &[
+            // Encrypted sync bookkeeping that must outlive a restart: merge bases and open conflicts
+            "CREATE TABLE IF NOT EXISTS note_sync_records (
+                note_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                encrypted_payload BLOB NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (note_id, kind)
+            )",
+        ],
+    },
+];
+
+/// Remember-me session database (`psypsy_sessions.db`)
+pub const SESSIONS_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "user_sessions",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS user_sessions (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                user_data TEXT NOT NULL,
+                session_token TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL,
+                device_info TEXT,
+                is_active BOOLEAN DEFAULT TRUE
+            )",
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "session_binding",
+        statements: &[
+            "ALTER TABLE user_sessions ADD COLUMN binding TEXT",
+        ],
+    },
+];
+
+/// Integration database used by the social media and CMEK services
+pub const INTEGRATIONS_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "social_media",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS social_media_accounts (
+                id TEXT PRIMARY KEY,
+                account_id TEXT NOT NULL,
+                platform TEXT NOT NULL,
+                account_type TEXT NOT NULL,
+                professional_id TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                username TEXT,
+                profile_url TEXT,
+                follower_count INTEGER,
+                connection_count INTEGER,
+                verified BOOLEAN DEFAULT FALSE,
+                active BOOLEAN DEFAULT TRUE,
+                last_synced DATETIME,
+                sync_enabled BOOLEAN DEFAULT TRUE,
+                posting_enabled BOOLEAN DEFAULT TRUE,
+                analytics_enabled BOOLEAN DEFAULT TRUE
+            )",
+            "CREATE TABLE IF NOT EXISTS social_media_posts (
+                id TEXT PRIMARY KEY,
+                post_id TEXT NOT NULL UNIQUE,
+                platform TEXT NOT NULL,
+                account_id TEXT NOT NULL,
+                professional_id TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                title TEXT,
+                content TEXT NOT NULL,
+                hashtags_json TEXT,
+                mentions_json TEXT,
+                media_urls_json TEXT,
+                link_url TEXT,
+                link_title TEXT,
+                link_description TEXT,
+                scheduled_for DATETIME,
+                posted_at DATETIME,
+                platform_post_id TEXT,
+                status TEXT NOT NULL DEFAULT 'draft',
+                compliance_checked BOOLEAN DEFAULT FALSE,
+                compliance_status TEXT,
+                compliance_notes TEXT,
+                review_required BOOLEAN DEFAULT FALSE,
+                reviewed_by TEXT,
+                reviewed_at DATETIME,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "cmek",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS cmek_keys (
+                id TEXT PRIMARY KEY,
+                key_id TEXT NOT NULL,
+                key_name TEXT NOT NULL,
+                service TEXT NOT NULL,
+                purpose TEXT NOT NULL,
+                algorithm TEXT NOT NULL,
+                protection_level TEXT NOT NULL,
+                state TEXT NOT NULL,
+                create_time DATETIME NOT NULL,
+                primary_version TEXT,
+                next_rotation_time DATETIME,
+                rotation_period TEXT,
+                labels_json TEXT,
+                quebec_compliant BOOLEAN NOT NULL DEFAULT TRUE,
+                data_residency_confirmed BOOLEAN NOT NULL DEFAULT TRUE
+            )",
+            "CREATE TABLE IF NOT EXISTS cmek_operations (
+                id TEXT PRIMARY KEY,
+                operation_id TEXT NOT NULL,
+                operation_type TEXT NOT NULL,
+                key_id TEXT NOT NULL,
+                service TEXT NOT NULL,
+                initiated_by TEXT NOT NULL,
+                initiated_at DATETIME NOT NULL,
+                completed_at DATETIME,
+                status TEXT NOT NULL,
+                error_message TEXT,
+                metadata_json TEXT,
+                quebec_compliance_verified BOOLEAN NOT NULL DEFAULT TRUE,
+                audit_logged BOOLEAN NOT NULL DEFAULT TRUE
+            )",
+            "CREATE TABLE IF NOT EXISTS cmek_access_requests (
+                id TEXT PRIMARY KEY,
+                request_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                professional_id TEXT,
+                key_id TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                purpose TEXT NOT NULL,
+                patient_id TEXT,
+                session_id TEXT,
+                justification TEXT NOT NULL,
+                emergency_access BOOLEAN NOT NULL DEFAULT FALSE,
+                requested_at DATETIME NOT NULL,
+                expires_at DATETIME NOT NULL,
+                approved BOOLEAN NOT NULL DEFAULT FALSE,
+                approved_by TEXT,
+                approved_at DATETIME,
+                notes TEXT,
+                audit_trail_id TEXT NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 3,
+        name: "social_media_scheduling",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS social_media_scheduling_rules (
+                id TEXT PRIMARY KEY,
+                rule_id TEXT NOT NULL UNIQUE,
+                professional_id TEXT NOT NULL,
+                platform TEXT NOT NULL,
+                rule_name TEXT NOT NULL,
+                content_types_json TEXT,
+                optimal_times_json TEXT,
+                frequency_limit INTEGER NOT NULL,
+                frequency_period TEXT NOT NULL,
+                timezone TEXT NOT NULL,
+                exclude_weekends BOOLEAN NOT NULL DEFAULT FALSE,
+                exclude_holidays BOOLEAN NOT NULL DEFAULT FALSE,
+                min_interval_hours INTEGER NOT NULL DEFAULT 0,
+                active BOOLEAN NOT NULL DEFAULT TRUE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            "ALTER TABLE social_media_posts ADD COLUMN error_message TEXT",
+            "ALTER TABLE social_media_posts ADD COLUMN next_eligible_at DATETIME",
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "cmek_grants_and_key_versions",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS cmek_access_grants (
+                id TEXT PRIMARY KEY,
+                grant_id TEXT NOT NULL UNIQUE,
+                request_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                key_id TEXT NOT NULL,
+                operations_allowed_json TEXT NOT NULL,
+                granted_at DATETIME NOT NULL,
+                expires_at DATETIME NOT NULL,
+                revoked BOOLEAN NOT NULL DEFAULT FALSE,
+                revoked_at DATETIME,
+                revoked_by TEXT,
+                revocation_reason TEXT,
+                usage_count INTEGER NOT NULL DEFAULT 0,
+                last_used DATETIME
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_cmek_access_grants_user_key ON cmek_access_grants (user_id, key_id)",
+            "CREATE TABLE IF NOT EXISTS cmek_key_versions (
+                id TEXT PRIMARY KEY,
+                version_id TEXT NOT NULL,
+                key_id TEXT NOT NULL,
+                state TEXT NOT NULL,
+                create_time DATETIME NOT NULL,
+                destroy_time DATETIME,
+                algorithm TEXT NOT NULL,
+                protection_level TEXT NOT NULL,
+                attestation TEXT,
+                reimport_eligible BOOLEAN NOT NULL DEFAULT FALSE
+            )",
+            "CREATE TABLE IF NOT EXISTS cmek_service_configs (
+                id TEXT PRIMARY KEY,
+                service_name TEXT NOT NULL,
+                cmek_enabled BOOLEAN NOT NULL,
+                key_name TEXT NOT NULL,
+                encryption_config_json TEXT,
+                last_configured DATETIME NOT NULL,
+                configuration_status TEXT NOT NULL,
+                compliance_verified BOOLEAN NOT NULL DEFAULT FALSE
+            )",
+        ],
+    },
+];
+
+/// Latest version defined by a migration set
+pub fn latest_version(migrations: &[Migration]) -> u32 {
+    migrations.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+/// Current schema version recorded in the database (0 if never migrated)
+pub fn current_version(conn: &Connection) -> Result<u32, MigrationError> {
+    ensure_migrations_table(conn)?;
+    let version: Option<u32> = conn
+        .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))
+        .optional()?
+        .flatten();
+    Ok(version.unwrap_or(0))
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<(), MigrationError> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Bring a database up to the latest version of the given migration set
+pub fn run_migrations(conn: &mut Connection, migrations: &[Migration]) -> Result<MigrationReport, MigrationError> {
+    let from_version = current_version(conn)?;
+    let supported = latest_version(migrations);
+
+    if from_version > supported {
+        return Err(MigrationError::NewerSchema { found: from_version, supported });
+    }
+
+    // Versions already applied must still mean the same change
+    for migration in migrations.iter().filter(|m| m.version <= from_version) {
+        let applied: Option<String> = conn
+            .query_row(
+                "SELECT name FROM schema_migrations WHERE version = ?1",
+                params![migration.version],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(applied) = applied {
+            if applied != migration.name {
+                return Err(MigrationError::Mismatch {
+                    version: migration.version,
+                    applied,
+                    expected: migration.name.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut pending: Vec<&Migration> = migrations.iter().filter(|m| m.version > from_version).collect();
+    pending.sort_by_key(|m| m.version);
+
+    let mut applied = Vec::new();
+    for migration in pending {
+        let tx = conn.transaction()?;
+        let result = migration
+            .statements
+            .iter()
+            .try_for_each(|sql| tx.execute(sql, []).map(|_| ()))
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+                    params![migration.version, migration.name, Utc::now().to_rfc3339()],
+                )
+                .map(|_| ())
+            });
+
+        match result {
+            // Dropping the transaction on error rolls the migration back
+            Ok(()) => tx.commit()?,
+            Err(source) => {
+                return Err(MigrationError::Failed {
+                    version: migration.version,
+                    name: migration.name.to_string(),
+                    source,
+                })
+            }
+        }
+
+        tracing::info!("Applied schema migration {} ({})", migration.version, migration.name);
+        applied.push(migration.version);
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: current_version(conn)?,
+        applied,
+    })
+}
+
+/// Open a database file and migrate it
+pub fn open_and_migrate(path: &Path, migrations: &[Migration]) -> Result<Connection, MigrationError> {
+    let mut conn = Connection::open(path)?;
+    run_migrations(&mut conn, migrations)?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_exists(conn: &Connection, table: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn test_migrations_apply_and_are_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let report = run_migrations(&mut conn, INTEGRATIONS_MIGRATIONS).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, 4);
+        assert_eq!(report.applied, vec![1, 2, 3, 4]);
+        assert!(table_exists(&conn, "social_media_posts"));
+        assert!(table_exists(&conn, "cmek_access_requests"));
+        assert!(table_exists(&conn, "social_media_scheduling_rules"));
+        assert!(table_exists(&conn, "cmek_access_grants"));
+
+        let rerun = run_migrations(&mut conn, INTEGRATIONS_MIGRATIONS).unwrap();
+        assert!(rerun.applied.is_empty());
+        assert_eq!(rerun.to_version, 4);
+    }
+
+    #[test]
+    fn test_refuses_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, INTEGRATIONS_MIGRATIONS).unwrap();
+
+        let result = run_migrations(&mut conn, &INTEGRATIONS_MIGRATIONS[..1]);
+        assert!(matches!(result, Err(MigrationError::NewerSchema { found: 4, supported: 1 })));
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        const BROKEN: &[Migration] = &[
+            Migration {
+                version: 1,
+                name: "ok",
+                statements: &["CREATE TABLE IF NOT EXISTS first (id TEXT PRIMARY KEY)"],
+            },
+            Migration {
+                version: 2,
+                name: "broken",
+                statements: &[
+                    "CREATE TABLE IF NOT EXISTS second (id TEXT PRIMARY KEY)",
+                    "NOT VALID SQL",
+                ],
+            },
+        ];
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let result = run_migrations(&mut conn, BROKEN);
+        assert!(matches!(result, Err(MigrationError::Failed { version: 2, .. })));
+
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        assert!(table_exists(&conn, "first"));
+        assert!(!table_exists(&conn, "second"));
+    }
+}
@@ -4,6 +4,9 @@
 //! All data is encrypted at rest using medical-grade encryption and stored with comprehensive
 //! audit logging for regulatory compliance.
 
+pub mod migrations;
+pub mod search_index;
+
 // Temporarily disabled due to sqlx dependency
 // pub mod medical_notes_store;
 
@@ -0,0 +1,465 @@
+//! Keyed blind index for global search
+//!
+//! Identity fields of clients, professionals and appointments are reduced to HMAC tokens
+//! (whole-value tokens, plus word-prefix tokens for names) stored in their own database,
+//! apart from the encrypted records. A search recomputes the tokens for the query and matches
+//! them, so nothing is decrypted to answer it and the index reveals no more than which records
+//! share a value or a name prefix. The key lives in its own file and never enters the index.
+
+use crate::models::{Appointment, Client, Professional};
+use crate::services::client_dedup::{normalize_name, normalize_ramq};
+use crate::storage::migrations::{open_and_migrate, Migration};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Index database, separate from every payload database
+pub const SEARCH_INDEX_FILE: &str = "psypsy_search.db";
+
+/// HMAC key for index tokens
+const SEARCH_INDEX_KEY_FILE: &str = "search_index.key";
+
+/// Search index database (`psypsy_search.db`)
+pub const SEARCH_INDEX_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "blind_index_tokens",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS search_tokens (
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                token TEXT NOT NULL,
+                PRIMARY KEY (entity_type, entity_id, token)
+            )",
+            "CREATE INDEX IF NOT EXISTS idx_search_tokens_token ON search_tokens(token)",
+        ],
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexedEntity {
+    Client,
+    Professional,
+    Appointment,
+}
+
+impl IndexedEntity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            IndexedEntity::Client => "client",
+            IndexedEntity::Professional => "professional",
+            IndexedEntity::Appointment => "appointment",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "client" => Some(IndexedEntity::Client),
+            "professional" => Some(IndexedEntity::Professional),
+            "appointment" => Some(IndexedEntity::Appointment),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchIndexConfig {
+    /// Shortest name prefix that produces a token; shorter prefixes match too many records
+    pub min_prefix_len: usize,
+    /// Longest prefix indexed; longer query words are truncated to it
+    pub max_prefix_len: usize,
+    /// Upper bound on records returned per entity type
+    pub max_results: usize,
+}
+
+impl Default for SearchIndexConfig {
+    fn default() -> Self {
+        Self { min_prefix_len: 3, max_prefix_len: 12, max_results: 50 }
+    }
+}
+
+impl SearchIndexConfig {
+    /// Defaults with `SEARCH_INDEX_MIN_PREFIX`, `SEARCH_INDEX_MAX_PREFIX` and
+    /// `SEARCH_INDEX_MAX_RESULTS` overrides
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let parse = |name: &str, target: &mut usize| {
+            if let Ok(value) = std::env::var(name) {
+                match value.trim().parse::<usize>() {
+                    Ok(v) if v > 0 => *target = v,
+                    _ => log::warn!("Ignoring invalid {} '{}'", name, value),
+                }
+            }
+        };
+        parse("SEARCH_INDEX_MIN_PREFIX", &mut config.min_prefix_len);
+        parse("SEARCH_INDEX_MAX_PREFIX", &mut config.max_prefix_len);
+        parse("SEARCH_INDEX_MAX_RESULTS", &mut config.max_results);
+        if config.max_prefix_len < config.min_prefix_len {
+            log::warn!("SEARCH_INDEX_MAX_PREFIX below SEARCH_INDEX_MIN_PREFIX; using the minimum for both");
+            config.max_prefix_len = config.min_prefix_len;
+        }
+        config
+    }
+}
+
+/// A value to index for one record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexedValue {
+    /// Name words, matchable by prefix
+    Words(&'static str, Vec<String>),
+    /// Matchable only by the exact normalized value
+    Exact(&'static str, String),
+}
+
+fn exact(field: &'static str, value: Option<String>) -> Option<IndexedValue> {
+    value.filter(|v| !v.is_empty()).map(|v| IndexedValue::Exact(field, v))
+}
+
+fn digits(value: &str) -> String {
+    value.chars().filter(char::is_ascii_digit).collect()
+}
+
+pub fn client_values(client: &Client) -> Vec<IndexedValue> {
+    let mut values = vec![IndexedValue::Words(
+        "name",
+        normalize_name(&client.profile.first_name, &client.profile.last_name).into_iter().collect(),
+    )];
+    values.extend(exact("ramq", client.ramq_number.as_deref().and_then(normalize_ramq)));
+    values
+}
+
+pub fn professional_values(professional: &Professional) -> Vec<IndexedValue> {
+    let mut values = vec![
+        IndexedValue::Words(
+            "name",
+            normalize_name(&professional.profile.first_name, &professional.profile.last_name).into_iter().collect(),
+        ),
+        IndexedValue::Words("business", normalize_name(&professional.business_name, "").into_iter().collect()),
+    ];
+    values.extend(exact("email", Some(professional.buss_email.trim().to_lowercase())));
+    values.extend(exact("phone", Some(digits(&professional.phone_nb.number))));
+    values
+}
+
+/// Appointments are found through their client, so only the client reference is indexed
+pub fn appointment_values(appointment: &Appointment) -> Vec<IndexedValue> {
+    exact("client_ref", Some(appointment.client_ptr.clone())).into_iter().collect()
+}
+
+/// Record IDs matched by a query
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatches {
+    pub client_ids: Vec<String>,
+    pub professional_ids: Vec<String>,
+    pub appointment_ids: Vec<String>,
+}
+
+pub struct SearchIndex {
+    conn: Connection,
+    key: Vec<u8>,
+    config: SearchIndexConfig,
+}
+
+impl SearchIndex {
+    /// Open (creating if needed) the index and its key in `dir`
+    pub fn open(dir: &Path, config: SearchIndexConfig) -> Result<Self, String> {
+        let conn = open_and_migrate(&dir.join(SEARCH_INDEX_FILE), SEARCH_INDEX_MIGRATIONS)
+            .map_err(|e| format!("Failed to open search index: {}", e))?;
+        let key = load_or_create_key(&dir.join(SEARCH_INDEX_KEY_FILE))
+            .map_err(|e| format!("Failed to load search index key: {}", e))?;
+        Ok(Self { conn, key, config })
+    }
+
+    pub fn with_connection(mut conn: Connection, key: Vec<u8>, config: SearchIndexConfig) -> Result<Self, String> {
+        crate::storage::migrations::run_migrations(&mut conn, SEARCH_INDEX_MIGRATIONS).map_err(|e| e.to_string())?;
+        Ok(Self { conn, key, config })
+    }
+
+    fn token(&self, kind: &str, field: &str, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        for part in [kind, field, value] {
+            mac.update(part.as_bytes());
+            mac.update(&[0]);
+        }
+        mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn prefix_tokens(&self, field: &str, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let longest = chars.len().min(self.config.max_prefix_len);
+        (self.config.min_prefix_len..=longest)
+            .map(|len| self.token("prefix", field, &chars[..len].iter().collect::<String>()))
+            .collect()
+    }
+
+    fn tokens_for(&self, values: &[IndexedValue]) -> HashSet<String> {
+        let mut tokens = HashSet::new();
+        for value in values {
+            match value {
+                IndexedValue::Words(field, words) => {
+                    for word in words {
+                        tokens.insert(self.token("exact", field, word));
+                        tokens.extend(self.prefix_tokens(field, word));
+                    }
+                }
+                IndexedValue::Exact(field, value) => {
+                    tokens.insert(self.token("exact", field, value));
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Replace every token of a record in one transaction, so a search sees either the old
+    /// values or the new ones and never a mix
+    pub fn replace(&mut self, entity: IndexedEntity, id: &str, values: &[IndexedValue]) -> Result<(), String> {
+        let tokens = self.tokens_for(values);
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM search_tokens WHERE entity_type = ?1 AND entity_id = ?2",
+            params![entity.as_str(), id],
+        )
+        .map_err(|e| e.to_string())?;
+        {
+            let mut insert = tx
+                .prepare("INSERT OR IGNORE INTO search_tokens (entity_type, entity_id, token) VALUES (?1, ?2, ?3)")
+                .map_err(|e| e.to_string())?;
+            for token in &tokens {
+                insert.execute(params![entity.as_str(), id, token]).map_err(|e| e.to_string())?;
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    pub fn remove(&mut self, entity: IndexedEntity, id: &str) -> Result<(), String> {
+        self.conn
+            .execute(
+                "DELETE FROM search_tokens WHERE entity_type = ?1 AND entity_id = ?2",
+                params![entity.as_str(), id],
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn ids_with_any(&self, tokens: &[String]) -> Result<HashSet<(IndexedEntity, String)>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entity_type, entity_id FROM search_tokens WHERE token = ?1")
+            .map_err(|e| e.to_string())?;
+        let mut ids = HashSet::new();
+        for token in tokens {
+            let rows = stmt
+                .query_map(params![token], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| e.to_string())?;
+            for row in rows {
+                let (entity, id) = row.map_err(|e| e.to_string())?;
+                if let Some(entity) = IndexedEntity::parse(&entity) {
+                    ids.insert((entity, id));
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Records matching every word of the query by name prefix, or the whole query exactly
+    /// (RAMQ, email, phone). Appointments of matched clients are included.
+    pub fn search(&self, query: &str) -> Result<SearchMatches, String> {
+        let mut matched: HashSet<(IndexedEntity, String)> = HashSet::new();
+
+        let words: Vec<String> = normalize_name(query, "").into_iter().collect();
+        let mut per_word = Vec::new();
+        for word in &words {
+            // Short words are only indexed whole; longer ones match on their (capped) prefix
+            let (kind, word) = if word.chars().count() < self.config.min_prefix_len {
+                ("exact", word.clone())
+            } else {
+                ("prefix", word.chars().take(self.config.max_prefix_len).collect())
+            };
+            let tokens: Vec<String> = ["name", "business"].iter().map(|field| self.token(kind, field, &word)).collect();
+            per_word.push(self.ids_with_any(&tokens)?);
+        }
+        if let Some(first) = per_word.first() {
+            matched.extend(first.iter().filter(|id| per_word[1..].iter().all(|set| set.contains(*id))).cloned());
+        }
+
+        let trimmed = query.trim();
+        let mut exact_tokens = vec![self.token("exact", "email", &trimmed.to_lowercase())];
+        if let Some(ramq) = normalize_ramq(trimmed) {
+            exact_tokens.push(self.token("exact", "ramq", &ramq));
+        }
+        let phone = digits(trimmed);
+        if phone.len() >= 7 {
+            exact_tokens.push(self.token("exact", "phone", &phone));
+        }
+        matched.extend(self.ids_with_any(&exact_tokens)?);
+
+        let mut result = SearchMatches::default();
+        for (entity, id) in &matched {
+            match entity {
+                IndexedEntity::Client => result.client_ids.push(id.clone()),
+                IndexedEntity::Professional => result.professional_ids.push(id.clone()),
+                IndexedEntity::Appointment => {}
+            }
+        }
+        let client_refs: Vec<String> =
+            result.client_ids.iter().map(|id| self.token("exact", "client_ref", id)).collect();
+        result.appointment_ids = self
+            .ids_with_any(&client_refs)?
+            .into_iter()
+            .filter(|(entity, _)| *entity == IndexedEntity::Appointment)
+            .map(|(_, id)| id)
+            .collect();
+
+        let max = self.config.max_results;
+        for ids in [&mut result.client_ids, &mut result.professional_ids, &mut result.appointment_ids] {
+            ids.sort();
+            ids.truncate(max);
+        }
+        Ok(result)
+    }
+}
+
+fn load_or_create_key(path: &Path) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(key) if key.len() == 32 => Ok(key),
+        Ok(_) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "search index key is corrupt")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            use ring::rand::SecureRandom;
+            let mut key = vec![0u8; 32];
+            ring::rand::SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "random source unavailable"))?;
+            std::fs::write(path, &key)?;
+            Ok(key)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Index shared by the record commands and `global_search`
+#[derive(Default)]
+pub struct SearchIndexState {
+    index: Mutex<Option<SearchIndex>>,
+}
+
+impl SearchIndexState {
+    pub fn attach_storage(&self, dir: &Path, config: SearchIndexConfig) -> Result<(), String> {
+        *self.index.lock().unwrap() = Some(SearchIndex::open(dir, config)?);
+        Ok(())
+    }
+
+    fn with_index<T>(&self, f: impl FnOnce(&mut SearchIndex) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.index.lock().unwrap();
+        let index = guard.as_mut().ok_or("Search index not initialized")?;
+        f(index)
+    }
+
+    pub fn index_client(&self, client: &Client) -> Result<(), String> {
+        if client.merged_into.is_some() {
+            // A merged-away record is a tombstone and should not come up in searches
+            return self.remove(IndexedEntity::Client, &client.object_id);
+        }
+        self.with_index(|index| index.replace(IndexedEntity::Client, &client.object_id, &client_values(client)))
+    }
+
+    pub fn index_professional(&self, professional: &Professional) -> Result<(), String> {
+        self.with_index(|index| {
+            index.replace(IndexedEntity::Professional, &professional.object_id, &professional_values(professional))
+        })
+    }
+
+    pub fn index_appointment(&self, appointment: &Appointment) -> Result<(), String> {
+        self.with_index(|index| {
+            index.replace(IndexedEntity::Appointment, &appointment.object_id, &appointment_values(appointment))
+        })
+    }
+
+    pub fn remove(&self, entity: IndexedEntity, id: &str) -> Result<(), String> {
+        self.with_index(|index| index.remove(entity, id))
+    }
+
+    pub fn search(&self, query: &str) -> Result<SearchMatches, String> {
+        self.with_index(|index| index.search(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AddressObject, CreateClientRequest, UpdateClientRequest};
+
+    fn index() -> SearchIndex {
+        SearchIndex::with_connection(Connection::open_in_memory().unwrap(), vec![7u8; 32], SearchIndexConfig::default())
+            .unwrap()
+    }
+
+    fn client(first: &str, last: &str) -> Client {
+        Client::from_request(
+            CreateClientRequest {
+                user_id: "user-1".to_string(),
+                first_name: first.to_string(),
+                last_name: last.to_string(),
+                email: "client@example.com".to_string(),
+                phone: "5145550000".to_string(),
+                date_of_birth: None,
+                ramq_number: Some("TREH 8001 0112".to_string()),
+                address: AddressObject {
+                    street: "123 Rue Principale".to_string(),
+                    city: "Montreal".to_string(),
+                    state: "QC".to_string(),
+                    zip_code: "H2X 1Y4".to_string(),
+                    country: "Canada".to_string(),
+                },
+                spoken_languages: vec![1],
+                search_radius: None,
+                preferences: None,
+                emergency_contacts: None,
+            },
+            "client-1".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_rename_replaces_searchability_in_one_step() {
+        let mut index = index();
+        let mut record = client("Hélène", "Tremblay");
+        index.replace(IndexedEntity::Client, &record.object_id, &client_values(&record)).unwrap();
+        assert_eq!(index.search("trem").unwrap().client_ids, vec!["client-1"]);
+        assert_eq!(index.search("helene tremblay").unwrap().client_ids, vec!["client-1"]);
+        assert_eq!(index.search("TREH80010112").unwrap().client_ids, vec!["client-1"]);
+
+        record.update_from_request(UpdateClientRequest {
+            first_name: None,
+            last_name: Some("Gagnon".to_string()),
+            ramq_number: None,
+            address: None,
+            search_radius: None,
+            spoken_languages: None,
+            preferences: None,
+            emergency_contacts: None,
+            medical_info: None,
+        });
+        index.replace(IndexedEntity::Client, &record.object_id, &client_values(&record)).unwrap();
+
+        assert!(index.search("tremblay").unwrap().client_ids.is_empty());
+        assert_eq!(index.search("gagn").unwrap().client_ids, vec!["client-1"]);
+    }
+
+    #[test]
+    fn test_tokens_reveal_nothing_without_the_key() {
+        let index = index();
+        let tokens = index.tokens_for(&[IndexedValue::Words("name", vec!["tremblay".to_string()])]);
+        assert!(tokens.iter().all(|t| !t.contains("trem") && t.len() == 64));
+
+        let other = SearchIndex::with_connection(Connection::open_in_memory().unwrap(), vec![9u8; 32], SearchIndexConfig::default())
+            .unwrap();
+        assert!(tokens.is_disjoint(&other.tokens_for(&[IndexedValue::Words("name", vec!["tremblay".to_string()])])));
+    }
+}
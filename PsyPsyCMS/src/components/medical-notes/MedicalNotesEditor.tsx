@@ -92,8 +92,11 @@ export function MedicalNotesEditor({
         throw new Error(validation.error || 'Validation failed');
       }
 
-      if (validation.data.length > 0) {
-        console.warn('Compliance violations:', validation.data);
+      const findings: Array<{ ruleId: string; severity: 'warning' | 'error'; field?: string; message: string }> = validation.data;
+      if (findings.length > 0) {
+        console.warn('Compliance findings:', findings);
+      }
+      if (findings.some(finding => finding.severity === 'error')) {
         // TODO: Show compliance warnings to user
         return;
       }
@@ -95,35 +95,17 @@ export const RecordingControls: React.FC<RecordingControlsProps> = ({
       const savePath = `${dataDir}/recording-${timestamp}.wav`;
 
       console.log('Saving recording to:', savePath);
-      const result = await invoke('stop_recording', {
+      // The backend saves the audio encrypted under the app data directory and returns where;
+      // the file is ciphertext, so it is not loaded for playback
+      const savedPath = await invoke<string | null>('stop_recording', {
         args: {
           save_path: savePath
         }
       });
 
-      setRecordingPath(savePath);
+      setRecordingPath(savedPath);
       setIsProcessing(false);
 
-      // Initialize audio for playback
-      if (savePath) {
-        const audio = new Audio();
-        audio.src = `file://${savePath}`;
-        audio.addEventListener('loadedmetadata', () => {
-          setDuration(audio.duration);
-        });
-        audio.addEventListener('timeupdate', () => {
-          setCurrentTime(audio.currentTime);
-          setProgress((audio.currentTime / audio.duration) * 100);
-        });
-        audio.addEventListener('ended', () => {
-          setIsPlaying(false);
-          setCurrentTime(0);
-          setProgress(0);
-        });
-        setAudioElement(audio);
-        setShowPlayback(true);
-      }
-
       onRecordingStop(true);
     } catch (error) {
       console.error('Failed to stop recording:', error);
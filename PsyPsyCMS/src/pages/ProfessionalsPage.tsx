@@ -89,7 +89,7 @@ const ProfessionalsPage: React.FC = () => {
     refetch
   } = useQuery({
     queryKey: ['professionals'],
-    queryFn: () => healthcareAPI.professional.getAllProfessionals(),
+    queryFn: () => healthcareAPI.professional.getAllProfessionals({ limit: 100 }),
     staleTime: 5 * 60 * 1000, // 5 minutes
     gcTime: 10 * 60 * 1000, // 10 minutes
   })
@@ -232,11 +232,11 @@ const ProfessionalsPage: React.FC = () => {
 
   // Transform backend professionals to frontend format
   const professionals = React.useMemo(() => {
-    if (!professionalsResponse?.success || !professionalsResponse?.data?.data) {
+    if (!professionalsResponse?.success || !professionalsResponse?.data?.items) {
       return mockProfessionals
     }
 
-    const backendProfessionals = professionalsResponse.data.data
+    const backendProfessionals = professionalsResponse.data.items
     return backendProfessionals.map((backendProf: any) => ({
       objectId: backendProf.object_id || backendProf.id,
       userId: backendProf.user_id,
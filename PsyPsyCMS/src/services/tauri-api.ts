@@ -199,6 +199,24 @@ export interface PaginatedResponse<T> {
   has_previous_page: boolean
 }
 
+export interface ListParams {
+  limit?: number
+  offset?: number
+  // Must be one of the list's sortable fields, e.g. name or createdAt
+  sortBy?: string
+  sortDir?: 'ASC' | 'DESC'
+  filter?: {
+    status?: string
+    assignedProfessional?: string
+    searchQuery?: string
+  }
+}
+
+export interface ListPage<T> {
+  items: T[]
+  totalCount: number
+}
+
 export const professionalAPI = {
   // Connect to unused Professional model methods
   async createProfessional(request: CreateProfessionalRequest): Promise<ProfessionalResponse> {
@@ -209,8 +227,8 @@ export const professionalAPI = {
     return invoke('get_professional', { id })
   },
 
-  async getAllProfessionals(page?: number, limit?: number): Promise<ApiResponse<PaginatedResponse<Professional>>> {
-    return invoke('get_professionals', { page, limit })
+  async getAllProfessionals(params?: ListParams): Promise<ApiResponse<ListPage<Professional>>> {
+    return invoke('get_professionals', { params })
   },
 
   async updateProfessional(id: string, request: any): Promise<ProfessionalResponse> {
@@ -338,6 +356,45 @@ export interface SyncStatus {
   firebaseCollection: string
 }
 
+export interface NetworkStatus {
+  online: boolean
+  // Round trip of a HEAD request to the sync endpoint; null when it failed
+  estimatedLatencyMs: number | null
+  metered: boolean
+}
+
+export interface PendingSyncCount {
+  // Notes the next sync will push again
+  retryable: number
+  // Notes rejected for good (validation, auth); they need attention before they can sync
+  failed: number
+}
+
+export type MergeStrategy = 'keep_local' | 'keep_remote' | 'three_way_merge' | 'manual'
+
+export interface FieldDiff {
+  field: string
+  base?: unknown
+  local?: unknown
+  remote?: unknown
+  change: 'unchanged' | 'local' | 'remote' | 'both' | 'conflict'
+}
+
+export interface NoteConflict {
+  note_id: string
+  local_version: any
+  remote_version: any
+  structured: boolean
+  fields: FieldDiff[]
+}
+
+export interface ConflictResolutionOutcome {
+  note_id: string
+  resolved: boolean
+  unresolved_fields: string[]
+  fields: FieldDiff[]
+}
+
 export const offlineSyncAPI = {
   // Connect to unused OfflineSyncService methods
   async initializeSyncService(userId: string, enableFirebaseSync: boolean): Promise<{ success: boolean; message: string }> {
@@ -348,8 +405,9 @@ export const offlineSyncAPI = {
     return invoke('perform_manual_sync')
   },
 
-  async getSyncStatus(): Promise<{ success: boolean; data: SyncStatus }> {
-    return invoke('get_sync_status')
+  // With a note ID, `queue_position` says where that note is in the upload queue (1 = next)
+  async getSyncStatus(noteId?: string): Promise<{ success: boolean; data: SyncStatus & { queue_position?: number } }> {
+    return invoke('get_sync_status', { noteId: noteId ?? null })
   },
 
   async setSyncEnabled(enabled: boolean): Promise<{ success: boolean; message: string }> {
@@ -360,19 +418,23 @@ export const offlineSyncAPI = {
     return invoke('force_sync_note', { noteId })
   },
 
-  async getConflictNotes(): Promise<{ success: boolean; data: any[] }> {
+  async getConflictNotes(): Promise<{ success: boolean; data: NoteConflict[] }> {
     return invoke('get_conflict_notes')
   },
 
-  async resolveConflictManually(noteId: string, resolutionStrategy: string, resolvedNote: any): Promise<{ success: boolean; message: string }> {
-    return invoke('resolve_conflict_manually', { noteId, resolutionStrategy, resolvedNote })
+  async resolveConflictManually(
+    noteId: string,
+    resolutionStrategy: MergeStrategy,
+    resolvedNote?: any
+  ): Promise<{ success: boolean; data?: ConflictResolutionOutcome; error?: string }> {
+    return invoke('resolve_conflict_manually', { noteId, resolutionStrategy, resolvedNote: resolvedNote ?? null })
   },
 
-  async checkNetworkConnectivity(): Promise<{ success: boolean; data: boolean }> {
+  async checkNetworkConnectivity(): Promise<{ success: boolean; data: NetworkStatus }> {
     return invoke('check_network_connectivity')
   },
 
-  async getPendingSyncCount(): Promise<{ success: boolean; data: number }> {
+  async getPendingSyncCount(): Promise<{ success: boolean; data: PendingSyncCount }> {
     return invoke('get_pending_sync_count')
   },
 
@@ -20,6 +20,8 @@ export interface Professional {
 
   // Professional Details
   profType: number
+  /** Codes from the specialty taxonomy */
+  specialties: string[]
   eduInstitute: number
   motherTongue: number
   offeredLangArr: number[]